@@ -11,7 +11,9 @@ use dada_ir_sym::{
     ir::{
         binder::{Binder, BoundTerm},
         classes::{SymAggregate, SymClassMember, SymField},
-        functions::{SignatureSymbols, SymFunction, SymFunctionSignature, SymInputOutput},
+        functions::{
+            SignatureSymbols, SymFunction, SymFunctionSignature, SymFunctionSource, SymInputOutput,
+        },
         generics::{SymWhereClause, SymWhereClauseKind},
         module::{SymItem, SymModule},
         types::{SymGenericKind, SymGenericTerm, SymPerm, SymPlace, SymTy},
@@ -48,6 +50,7 @@ impl<'db> Check<'db> for SourceFile {
 
 impl<'db> Check<'db> for SymModule<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
+        self.checked_attributes(db);
         self.items(db).for_each(|item| item.check(db));
         self.check_use_items(db);
     }
@@ -65,7 +68,10 @@ impl<'db> Check<'db> for SymItem<'db> {
 
 impl<'db> Check<'db> for SymAggregate<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
+        self.checked_attributes(db);
         self.members(db).iter().for_each(|member| member.check(db));
+        let _ = self.checked_layout(db);
+        let _ = self.checked_destructor(db);
     }
 }
 
@@ -86,8 +92,15 @@ impl<'db> Check<'db> for SymField<'db> {
 
 impl<'db> Check<'db> for SymFunction<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
+        self.checked_attributes(db);
         let _ = self.checked_signature(db);
+        if matches!(self.source(db), SymFunctionSource::Extern(_)) || self.is_host_export(db) {
+            let _ = self.checked_ffi_signature(db);
+        }
         self.checked_body(db);
+        self.nested_items(db)
+            .values()
+            .for_each(|item| item.check(db));
     }
 }
 
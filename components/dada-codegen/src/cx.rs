@@ -1,55 +1,153 @@
+use std::collections::VecDeque;
+
 use dada_ir_sym::{ir::functions::SymFunction, ir::types::SymGenericTerm};
-use dada_util::{FromImpls, Map};
+use dada_util::{FromImpls, Map, Set};
+use host_exports::HostExport;
+use imports::FnImport;
 use salsa::Update;
-use wasm_encoder::{CodeSection, FunctionSection, TypeSection};
+use trap::TrapTable;
+use wasm_encoder::{Instruction, ValType};
+use wasm_fn_type::{FnType, FnTypeIndex};
+
+use crate::CodegenOptions;
 
+mod const_prop;
+mod debug_info;
 mod generate_expr;
 mod generate_fn;
+pub(crate) mod host_exports;
+pub(crate) mod imports;
+pub(crate) mod instantiations;
+mod peephole;
+mod reachability;
+mod tail_calls;
+pub(crate) mod trap;
+mod validate;
 mod wasm_fn_type;
 mod wasm_repr;
 
 /// Core codegen context.
+///
+/// 💡 The generated module must be a deterministic function of the input
+/// (see [`CodegenOptions::check_determinism`]). The `Map` fields below are
+/// hash maps whose iteration order is unspecified, so they are only ever used for lookups:
+/// everything that ends up in the module is produced by walking a `Vec`
+/// in declaration order, or is sorted first (e.g., imports and the instantiation report).
 pub(crate) struct Cx<'db> {
     db: &'db dyn crate::Db,
-    function_section: FunctionSection,
-    type_section: TypeSection,
-    code_section: CodeSection,
+    options: CodegenOptions,
+    /// Function types, indexed by [`FnTypeIndex`]. Each distinct type is declared once.
+    fn_types: Vec<FnType>,
+    fn_type_indices: Map<FnType, FnTypeIndex>,
+    /// The type of each declared function, indexed by [`FnIndex`].
+    fn_decls: Vec<FnTypeIndex>,
+    /// The body of each generated function, indexed by [`FnIndex`].
+    /// Since bodies are generated in declaration order, this has an entry for every
+    /// declared function once the codegen queue is empty.
+    fn_bodies: Vec<FnBody>,
     functions: Map<FnKey<'db>, FnIndex>,
-    codegen_queue: Vec<CodegenQueueItem<'db>>,
+    /// Functions imported from the host, keyed by their index. Imports have an empty entry in `fn_bodies`.
+    fn_imports: Map<FnIndex, FnImport>,
+    /// The import for each `extern fn` that has been declared.
+    extern_imports: Map<SymFunction<'db>, FnIndex>,
+    /// The functions imported to `print` values that have been declared, keyed by name
+    /// (see [`imports::PRINT_HOOK_MODULE`]).
+    print_hooks: Map<String, FnIndex>,
+    /// Functions awaiting codegen. This is processed in FIFO order so that
+    /// code is generated in the same order that functions are declared.
+    codegen_queue: VecDeque<CodegenQueueItem<'db>>,
+    trap_table: TrapTable,
+    /// Functions exported to the host, in the order they are exported.
+    host_exports: Vec<HostExport>,
+    /// Instantiations whose generic arguments nest deeper than the instantiation limit
+    /// (see [`CodegenOptions::instantiation_limit`]).
+    over_limit: Set<FnKey<'db>>,
 }
 
 impl<'db> Cx<'db> {
-    pub fn new(db: &'db dyn crate::Db) -> Self {
+    pub fn new(db: &'db dyn crate::Db, options: CodegenOptions) -> Self {
         Self {
             db,
-            function_section: Default::default(),
-            type_section: Default::default(),
-            code_section: Default::default(),
+            options,
+            fn_types: Default::default(),
+            fn_type_indices: Default::default(),
+            fn_decls: Default::default(),
+            fn_bodies: Default::default(),
             functions: Default::default(),
+            fn_imports: Default::default(),
+            extern_imports: Default::default(),
+            print_hooks: Default::default(),
             codegen_queue: Default::default(),
+            trap_table: Default::default(),
+            host_exports: Default::default(),
+            over_limit: Default::default(),
         }
     }
 
-    /// Generates all code reachable from the given fn instantiated with the given arguments.
+    /// Generates all code reachable from the given fn instantiated with the given arguments,
+    /// along with wrappers that export each of `host_exports` to the host
+    /// (see [`host_exports::host_exports`]).
     pub fn generate_from_fn(
         mut self,
         function: SymFunction<'db>,
         generics: Vec<SymGenericTerm<'db>>,
+        host_exports: &[SymFunction<'db>],
     ) -> wasm_encoder::Module {
-        self.declare_fn(function, generics);
-        while let Some(item) = self.codegen_queue.pop() {
-            match item {
-                CodegenQueueItem::Function(fn_key) => self.codegen_fn(fn_key),
-            }
+        let main_index = self.declare_fn(function, generics);
+        self.drain_codegen_queue();
+
+        let mut entry_points = vec![main_index];
+        for &host_export in host_exports {
+            entry_points.push(self.declare_host_export(host_export));
         }
 
+        let reachable = self.reachable_from(&entry_points);
+
         let mut module = wasm_encoder::Module::new();
-        module.section(&self.type_section);
-        module.section(&self.function_section);
-        module.section(&self.code_section);
+        self.encode_types_and_functions(&reachable, &mut module);
+        self.encode_exports(&reachable, &mut module);
+        self.encode_code(&reachable, &mut module);
+        self.encode_names(&reachable, &mut module);
+        self.encode_trap_table(&mut module);
+        self.encode_instantiations(&mut module);
+
+        if cfg!(debug_assertions) {
+            self.validate(function, &reachable, module.as_slice());
+        }
 
         module
     }
+
+    /// Generates code for everything on the codegen queue, including
+    /// anything declared while doing so.
+    fn drain_codegen_queue(&mut self) {
+        while let Some(item) = self.codegen_queue.pop_front() {
+            match item {
+                CodegenQueueItem::Function(fn_key) => self.codegen_fn(fn_key),
+                CodegenQueueItem::TrapHandler => self.codegen_trap_handler(),
+                CodegenQueueItem::Import => self.fn_bodies.push(FnBody::default()),
+            }
+        }
+    }
+}
+
+impl Cx<'_> {
+    /// Adds a function of the given type to the function section and returns its index.
+    /// The caller is responsible for enqueuing the function for code generation.
+    fn declare_fn_index(&mut self, ty_index: FnTypeIndex) -> FnIndex {
+        let fn_index = FnIndex(u32::try_from(self.fn_decls.len()).expect("too many functions"));
+        self.fn_decls.push(ty_index);
+        fn_index
+    }
+}
+
+/// The generated code for a function, kept as instructions until the module is encoded
+/// so that function and type indices can be renumbered once unreachable functions are dropped.
+#[derive(Default)]
+pub(crate) struct FnBody {
+    /// Types of the wasm locals (beyond the parameters).
+    locals: Vec<ValType>,
+    instructions: Vec<Instruction<'static>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update)]
@@ -61,4 +159,12 @@ pub(crate) struct FnIndex(u32);
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update, FromImpls)]
 enum CodegenQueueItem<'db> {
     Function(FnKey<'db>),
+
+    #[no_from_impl]
+    TrapHandler,
+
+    /// A function imported from the host; it has no body,
+    /// but we still record an (empty) entry so that bodies line up with declarations.
+    #[no_from_impl]
+    Import,
 }
@@ -0,0 +1,324 @@
+//! A simple constant propagation and branch folding pass over the object IR,
+//! run on each function body before codegen when optimizing, unless
+//! [`CodegenOptions::no_const_prop`](`crate::CodegenOptions::no_const_prop`) is set.
+//!
+//! The pass:
+//!
+//! * replaces reads of a variable initialized with a literal and never assigned afterwards
+//!   with the literal itself;
+//! * folds `!` applied to a boolean literal;
+//! * removes the arms of an `if` whose condition is literally `false` and turns an arm
+//!   whose condition is literally `true` into the final `else`;
+//! * removes `let` bindings of literals whose variable is no longer mentioned.
+//!
+//! 💡 Only literals are propagated: they own no resources, so reading one out of a variable
+//! is indistinguishable from writing it again, and dropping one is a no-op.
+
+use dada_ir_ast::ast::PermissionOp;
+use dada_ir_sym::ir::{
+    exprs::{SymExpr, SymExprKind, SymLiteral, SymMatchArm, SymPlaceExpr, SymPlaceExprKind},
+    variables::SymVariable,
+};
+use dada_util::Map;
+
+/// Returns `body` with constants propagated, constant branches folded, and dead literal bindings removed.
+pub(crate) fn const_prop<'db>(db: &'db dyn crate::Db, body: SymExpr<'db>) -> SymExpr<'db> {
+    ConstProp {
+        db,
+        constants: Map::default(),
+    }
+    .fold(body)
+}
+
+struct ConstProp<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Variables known to hold a literal for their entire scope.
+    constants: Map<SymVariable<'db>, SymLiteral>,
+}
+
+impl<'db> ConstProp<'db> {
+    fn fold(&mut self, expr: SymExpr<'db>) -> SymExpr<'db> {
+        let db = self.db;
+        let kind = match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) => SymExprKind::Semi(self.fold(*lhs), self.fold(*rhs)),
+
+            SymExprKind::Tuple(exprs) => {
+                SymExprKind::Tuple(exprs.iter().map(|&e| self.fold(e)).collect())
+            }
+
+            SymExprKind::LetIn {
+                lv,
+                ty,
+                initializer,
+                body,
+            } => {
+                let lv = *lv;
+                let initializer = initializer.map(|i| self.fold(i));
+
+                let literal = match initializer.map(|i| i.kind(db)) {
+                    Some(&SymExprKind::Primitive(literal)) => Some(literal),
+                    _ => None,
+                };
+                if let Some(literal) = literal
+                    && !writes_variable(db, *body, lv)
+                {
+                    self.constants.insert(lv, literal);
+                }
+
+                let body = self.fold(*body);
+
+                if literal.is_some() && !mentions_variable(db, body, lv) {
+                    return body;
+                }
+
+                SymExprKind::LetIn {
+                    lv,
+                    ty: *ty,
+                    initializer,
+                    body,
+                }
+            }
+
+            // The elements of a tuple are not tracked, so neither are the variables they are given to.
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body,
+            } => SymExprKind::LetTuple {
+                lvs: lvs.clone(),
+                initializer: self.fold(*initializer),
+                body: self.fold(*body),
+            },
+
+            SymExprKind::Await {
+                future,
+                await_keyword,
+            } => SymExprKind::Await {
+                future: self.fold(*future),
+                await_keyword: *await_keyword,
+            },
+
+            SymExprKind::Assign { place, value } => SymExprKind::Assign {
+                place: *place,
+                value: self.fold(*value),
+            },
+
+            // `x.mut` of a primitive is an error at runtime, so leave it be.
+            &SymExprKind::PermissionOp(op, place) if op != PermissionOp::Mutate => {
+                match self.constant(place) {
+                    Some(literal) => SymExprKind::Primitive(literal),
+                    None => return expr,
+                }
+            }
+
+            &SymExprKind::Drop(place) => match self.constant(place) {
+                Some(_) => SymExprKind::Tuple(vec![]),
+                None => return expr,
+            },
+
+            SymExprKind::Return(value) => SymExprKind::Return(self.fold(*value)),
+
+            SymExprKind::Loop { sym_loop, body } => SymExprKind::Loop {
+                sym_loop: *sym_loop,
+                body: self.fold(*body),
+            },
+
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => SymExprKind::Break {
+                from_loop: *from_loop,
+                with_value: self.fold(*with_value),
+            },
+
+            SymExprKind::Not { operand, op_span } => {
+                let operand = self.fold(*operand);
+                match *operand.kind(db) {
+                    SymExprKind::Primitive(SymLiteral::Integral { bits }) => {
+                        SymExprKind::Primitive(SymLiteral::Integral { bits: 1 - bits })
+                    }
+                    _ => SymExprKind::Not {
+                        operand,
+                        op_span: *op_span,
+                    },
+                }
+            }
+
+            SymExprKind::BinaryOp(op, lhs, rhs) => {
+                SymExprKind::BinaryOp(*op, self.fold(*lhs), self.fold(*rhs))
+            }
+
+            SymExprKind::Cast(value) => SymExprKind::Cast(self.fold(*value)),
+
+            SymExprKind::Aggregate { ty, fields } => SymExprKind::Aggregate {
+                ty: *ty,
+                fields: fields.iter().map(|&f| self.fold(f)).collect(),
+            },
+
+            SymExprKind::Match { arms } => {
+                let mut folded_arms = vec![];
+                for arm in arms {
+                    let condition = arm.condition.map(|c| self.fold(c));
+                    let condition = match condition.map(|c| c.kind(db)) {
+                        Some(SymExprKind::Primitive(SymLiteral::Integral { bits: 0 })) => continue,
+                        Some(SymExprKind::Primitive(SymLiteral::Integral { .. })) => None,
+                        _ => condition,
+                    };
+                    folded_arms.push(SymMatchArm {
+                        condition,
+                        body: self.fold(arm.body),
+                    });
+
+                    // Later arms are unreachable.
+                    if condition.is_none() {
+                        break;
+                    }
+                }
+
+                match folded_arms.first() {
+                    Some(SymMatchArm {
+                        condition: None,
+                        body,
+                    }) => return *body,
+                    None => SymExprKind::Tuple(vec![]),
+                    Some(_) => SymExprKind::Match { arms: folded_arms },
+                }
+            }
+
+            SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::Call { .. }
+            | SymExprKind::Continue { .. }
+            | SymExprKind::PermissionOp(..)
+            | SymExprKind::IsSome(_)
+            | SymExprKind::Error(_) => return expr,
+        };
+
+        if kind == *expr.kind(db) {
+            expr
+        } else {
+            SymExpr::new(db, expr.span(db), expr.ty(db), kind)
+        }
+    }
+
+    /// If `place` is a variable known to hold a literal, returns the literal.
+    fn constant(&self, place: SymPlaceExpr<'db>) -> Option<SymLiteral> {
+        match place.kind(self.db) {
+            SymPlaceExprKind::Var(lv) => self.constants.get(lv).copied(),
+            SymPlaceExprKind::Field(..) | SymPlaceExprKind::Error(_) => None,
+        }
+    }
+}
+
+/// True if `lv` (or some place rooted in it) may be modified within `expr`.
+fn writes_variable<'db>(db: &'db dyn crate::Db, expr: SymExpr<'db>, lv: SymVariable<'db>) -> bool {
+    let mut writes = false;
+    for_each_variable_use(db, expr, &mut |var, is_write| {
+        writes |= var == lv && is_write;
+    });
+    writes
+}
+
+/// True if `lv` (or some place rooted in it) is mentioned anywhere within `expr`.
+fn mentions_variable<'db>(
+    db: &'db dyn crate::Db,
+    expr: SymExpr<'db>,
+    lv: SymVariable<'db>,
+) -> bool {
+    let mut mentions = false;
+    for_each_variable_use(db, expr, &mut |var, _| mentions |= var == lv);
+    mentions
+}
+
+/// Invokes `op` with each variable used within `expr` and whether the use may modify it.
+fn for_each_variable_use<'db>(
+    db: &'db dyn crate::Db,
+    expr: SymExpr<'db>,
+    op: &mut dyn FnMut(SymVariable<'db>, bool),
+) {
+    match expr.kind(db) {
+        SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+            for_each_variable_use(db, *lhs, op);
+            for_each_variable_use(db, *rhs, op);
+        }
+        SymExprKind::Tuple(exprs) | SymExprKind::Aggregate { fields: exprs, .. } => {
+            for &expr in exprs {
+                for_each_variable_use(db, expr, op);
+            }
+        }
+        SymExprKind::LetIn {
+            initializer, body, ..
+        } => {
+            if let Some(initializer) = initializer {
+                for_each_variable_use(db, *initializer, op);
+            }
+            for_each_variable_use(db, *body, op);
+        }
+        SymExprKind::LetTuple {
+            initializer, body, ..
+        } => {
+            for_each_variable_use(db, *initializer, op);
+            for_each_variable_use(db, *body, op);
+        }
+        SymExprKind::Await { future: expr, .. }
+        | SymExprKind::Return(expr)
+        | SymExprKind::Loop { body: expr, .. }
+        | SymExprKind::Break {
+            with_value: expr, ..
+        }
+        | SymExprKind::Not { operand: expr, .. }
+        | SymExprKind::Cast(expr) => for_each_variable_use(db, *expr, op),
+        SymExprKind::Assign { place, value } => {
+            if let Some(var) = root_variable(db, *place) {
+                op(var, true);
+            }
+            for_each_variable_use(db, *value, op);
+        }
+        &SymExprKind::PermissionOp(perm_op, place) => {
+            if let Some(var) = root_variable(db, place) {
+                op(var, perm_op == PermissionOp::Mutate);
+            }
+        }
+        &SymExprKind::IsSome(place) => {
+            if let Some(var) = root_variable(db, place) {
+                op(var, false);
+            }
+        }
+        // Nothing reads a variable after it is dropped.
+        &SymExprKind::Drop(place) => {
+            if let Some(var) = root_variable(db, place) {
+                op(var, false);
+            }
+        }
+        SymExprKind::Call { arg_temps, .. } => {
+            for &var in arg_temps {
+                op(var, false);
+            }
+        }
+        SymExprKind::Match { arms } => {
+            for arm in arms {
+                if let Some(condition) = arm.condition {
+                    for_each_variable_use(db, condition, op);
+                }
+                for_each_variable_use(db, arm.body, op);
+            }
+        }
+        SymExprKind::Primitive(_)
+        | SymExprKind::ByteLiteral(_)
+        | SymExprKind::Continue { .. }
+        | SymExprKind::Error(_) => {}
+    }
+}
+
+/// The variable at the root of `place`, if any.
+fn root_variable<'db>(
+    db: &'db dyn crate::Db,
+    place: SymPlaceExpr<'db>,
+) -> Option<SymVariable<'db>> {
+    match *place.kind(db) {
+        SymPlaceExprKind::Var(lv) => Some(lv),
+        SymPlaceExprKind::Field(owner, _) => root_variable(db, owner),
+        SymPlaceExprKind::Error(_) => None,
+    }
+}
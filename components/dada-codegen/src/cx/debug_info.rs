@@ -0,0 +1,38 @@
+use wasm_encoder::{NameMap, NameSection};
+
+use super::{Cx, FnIndex, FnKey, reachability::Reachable};
+
+impl Cx<'_> {
+    /// Appends a `name` section naming each reachable function.
+    /// Does nothing unless requested in the codegen options.
+    ///
+    /// Only functions generated from Dada code are named, after the Dada function,
+    /// so different instantiations of a generic function share a name.
+    /// Imports are already named by the import section.
+    pub(crate) fn encode_names(&self, reachable: &Reachable, module: &mut wasm_encoder::Module) {
+        if !self.options.debug_info {
+            return;
+        }
+
+        let db = self.db;
+        let mut names = self
+            .functions
+            .iter()
+            .filter(|&(_, &FnIndex(index))| reachable.is_reachable(index))
+            .map(|(FnKey(function, _), &FnIndex(index))| {
+                (reachable.fn_index(index), function.name(db).to_string())
+            })
+            .collect::<Vec<_>>();
+
+        // The name map must be sorted by index.
+        names.sort();
+
+        let mut function_names = NameMap::new();
+        for (index, text) in &names {
+            function_names.append(*index, text);
+        }
+        let mut section = NameSection::new();
+        section.functions(&function_names);
+        module.section(&section);
+    }
+}
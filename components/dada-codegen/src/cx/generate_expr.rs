@@ -1,19 +1,28 @@
 use std::sync::Arc;
 
-use dada_ir_ast::{ast::PermissionOp, diagnostic::Reported};
-use dada_ir_sym::ir::exprs::{SymBinaryOp, SymExpr, SymExprKind, SymLiteral, SymMatchArm};
+use dada_ir_ast::{ast::PermissionOp, diagnostic::Reported, span::Span};
+use dada_ir_sym::ir::exprs::{SymBinaryOp, SymExpr, SymExprKind, SymLiteral, SymLoop, SymMatchArm};
 use dada_ir_sym::ir::types::{SymGenericTerm, SymTy, SymTyKind};
 use dada_ir_sym::{
-    ir::primitive::SymPrimitiveKind, ir::subst::Subst, ir::types::SymTyName,
-    ir::variables::SymVariable,
+    ir::functions::SymFunction, ir::primitive::SymPrimitiveKind, ir::subst::Subst,
+    ir::types::SymTyName, ir::variables::SymVariable, well_known,
 };
-use dada_util::Map;
+use dada_util::{Map, Set};
+use drops::NO_VALUE_FLAG;
 use wasm_encoder::{Instruction, ValType};
 use wasm_place_repr::{WasmLocal, WasmPlaceRepr};
 
+use super::generate_fn::CodegenSignature;
+use super::peephole;
+use super::tail_calls::tail_calls;
+use super::trap::TrapKind;
 use super::wasm_repr::WasmReprCx;
-use super::{Cx, wasm_repr::WasmRepr};
+use super::{Cx, FnBody, wasm_repr::WasmRepr};
+use crate::OverflowMode;
 
+mod drops;
+mod literals;
+mod print;
 pub(crate) mod wasm_place_repr;
 
 pub(crate) struct ExprCodegen<'cx, 'db> {
@@ -22,20 +31,49 @@ pub(crate) struct ExprCodegen<'cx, 'db> {
     /// Values of any generic variables
     generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
 
-    /// Accumulates wasm locals. We make no effort to reduce the number of local variables created.
+    /// Accumulates wasm locals.
     wasm_locals: Vec<wasm_encoder::ValType>,
 
+    /// Locals whose values are no longer needed, available for reuse
+    /// by [`Self::fresh_local_index`] (see [`Self::release_place`]).
+    free_wasm_locals: Vec<WasmLocal>,
+
     /// Local variable that stores starting address in our stack frame
     wasm_stack_pointer: WasmLocal,
 
-    /// Values we are putting onto the stack frame (actually located in the WASM heap)
+    /// Values we are putting onto the stack frame (actually located in the WASM heap).
+    /// Slots are allocated and released in stack order as variables come into and go out of scope,
+    /// so this is the size of the portion of the frame in use at the current point.
     wasm_stack_frame_size: u32,
 
     /// Maps each Dada variable to a range of wasm locals. Note that a single value can be inlined into multiple wasm locals.
     variables: Map<SymVariable<'db>, Arc<WasmPlaceRepr>>,
 
+    /// The type of each variable in [`Self::variables`].
+    variable_tys: Map<SymVariable<'db>, SymTy<'db>>,
+
     /// Accumulates wasm instructions.
     instructions: Vec<Instruction<'static>>,
+
+    /// Calls in tail position that may be emitted as `return_call`.
+    /// Empty unless tail calls are enabled in the [`CodegenOptions`](`crate::CodegenOptions`).
+    tail_calls: Set<SymExpr<'db>>,
+
+    /// The wasm values returned by the function being generated.
+    /// Only populated when tail calls are enabled.
+    output_val_types: Vec<ValType>,
+
+    /// Variables known to hold a byte literal or a string created from one, with its bytes.
+    /// Lets intrinsics like `panic` use their message at compile time.
+    literal_bytes: Map<SymVariable<'db>, Arc<[u8]>>,
+
+    /// Variables that are assigned or leased with `mut` somewhere in the function body,
+    /// whose value may change after they are initialized (see [`Self::record_literal_bytes`]).
+    mutated_variables: Set<SymVariable<'db>>,
+
+    /// Loops enclosing the expression being generated, each with the index in
+    /// [`Self::instructions`] of the `block` instruction that begins it.
+    loops: Vec<(SymLoop<'db>, usize)>,
 }
 
 impl<'cx, 'db> ExprCodegen<'cx, 'db> {
@@ -45,19 +83,36 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             cx,
             generics,
             wasm_locals: vec![ValType::I32],
+            free_wasm_locals: Default::default(),
             variables: Default::default(),
+            variable_tys: Default::default(),
             instructions: Default::default(),
             wasm_stack_frame_size: 0,
             wasm_stack_pointer: WasmLocal { index: 0 },
+            tail_calls: Default::default(),
+            output_val_types: Default::default(),
+            literal_bytes: Default::default(),
+            mutated_variables: Default::default(),
+            loops: Default::default(),
         }
     }
 
-    pub fn into_function(self) -> wasm_encoder::Function {
-        let mut f = wasm_encoder::Function::new_with_locals_types(self.wasm_locals);
-        for instruction in self.instructions {
-            f.instruction(&instruction);
+    /// Permit calls in tail position within `body`, the body of a function returning
+    /// `output_ty`, to be emitted as `return_call` instructions.
+    pub fn enable_tail_calls(&mut self, body: SymExpr<'db>, output_ty: SymTy<'db>) {
+        self.tail_calls = tail_calls(self.cx.db, body);
+        self.output_val_types = self.wasm_repr_of_type(output_ty).flatten();
+    }
+
+    pub fn into_fn_body(self) -> FnBody {
+        FnBody {
+            locals: self.wasm_locals,
+            instructions: if self.cx.options.optimize() {
+                peephole::optimize(self.instructions)
+            } else {
+                self.instructions
+            },
         }
-        f
     }
 
     /// Returns the [`WasmRepr`][] for a Dada type.
@@ -84,7 +139,12 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             SymExprKind::Semi(object_expr, object_expr1) => {
                 self.push_expr(object_expr);
                 self.pop_and_drop(object_expr.ty(db));
-                self.push_expr(object_expr1);
+
+                // Code after an expression that never finishes (e.g., `return`) is dead;
+                // the type checker gives the whole expression type `!` in that case.
+                if !matches!(object_expr.ty(db).kind(db), SymTyKind::Never) {
+                    self.push_expr(object_expr1);
+                }
             }
             SymExprKind::Tuple(ref elements) => {
                 // the representation of a tuple is inlined onto the stack (like any other struct type)
@@ -99,16 +159,59 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 initializer,
                 body,
             } => {
+                let stack_frame_size = self.wasm_stack_frame_size;
                 self.insert_variable(lv, ty);
 
+                // Without an initializer there is nothing to store: the type checker's
+                // definite assignment analysis ensures `lv` is assigned before it is read.
+                // It may still be dropped (e.g., when first assigned), so mark it as not owning a value.
+                let place = self.variables[&lv].clone();
                 if let Some(initializer) = initializer {
+                    self.record_literal_bytes(lv, initializer);
                     self.push_expr(initializer);
-                    self.pop_and_store(&self.variables[&lv].clone());
+                    self.pop_and_store(&place);
                 } else {
-                    // FIXME: should zero out the values
+                    self.mark_given(&place, ty);
+                }
+
+                self.push_expr(body);
+
+                // `lv` goes out of scope (drop elaboration has placed its drop within `body`),
+                // so its locals and stack slots can be reused.
+                if self.cx.options.optimize() {
+                    self.release_place(&place);
+                    self.wasm_stack_frame_size = stack_frame_size;
+                }
+            }
+            SymExprKind::LetTuple {
+                ref lvs,
+                initializer,
+                body,
+            } => {
+                let stack_frame_size = self.wasm_stack_frame_size;
+                let places: Vec<_> = lvs
+                    .iter()
+                    .map(|&(lv, ty)| {
+                        self.insert_variable(lv, ty);
+                        self.variables[&lv].clone()
+                    })
+                    .collect();
+
+                // The elements of the tuple are inlined onto the stack in order,
+                // so the last one is on top.
+                self.push_expr(initializer);
+                for place in places.iter().rev() {
+                    self.pop_and_store(place);
                 }
 
                 self.push_expr(body);
+
+                if self.cx.options.optimize() {
+                    for place in &places {
+                        self.release_place(place);
+                    }
+                    self.wasm_stack_frame_size = stack_frame_size;
+                }
             }
             SymExprKind::Await {
                 future,
@@ -121,7 +224,7 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 let wasm_place = self.place(place);
                 self.push_expr(value);
 
-                // FIXME: have to drop the old value
+                // Drop elaboration has already dropped the old value, if needed.
 
                 self.pop_and_store(&wasm_place);
             }
@@ -138,6 +241,7 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
 
                     PermissionOp::Give => {
                         self.push_from(&wasm_place_repr);
+                        self.mark_given(&wasm_place_repr, object_place_expr.ty(db));
                     }
 
                     PermissionOp::Share => {
@@ -150,24 +254,116 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 ref substitution,
                 ref arg_temps,
             } => {
+                if well_known::panic_fn(db).ok() == Some(function) {
+                    return self.push_panic(expr.span(db), arg_temps[0]);
+                }
+
+                if well_known::none_fn(db).ok() == Some(function) {
+                    return self.push_variant(expr.ty(db), None);
+                }
+
+                if well_known::ok_fn(db).ok() == Some(function) {
+                    return self.push_variant(expr.ty(db), Some((0, arg_temps[0])));
+                }
+
+                if well_known::err_fn(db).ok() == Some(function) {
+                    return self.push_variant(expr.ty(db), Some((1, arg_temps[0])));
+                }
+
+                // FIXME: calls to the future combinators (`join`, `race`) are compiled like any
+                // other call, so they pass futures where the prelude body expects their results.
+                // Lowering them properly needs suspension, which needs a host executor to poll.
+
                 let fn_args = substitution.subst_vars(db, &self.generics);
+
+                // `print` writes its argument through the host, then calls the prelude's
+                // (empty) body like any other function.
+                if well_known::print_fn(db).ok() == Some(function) {
+                    let place = self.variables[&arg_temps[0]].clone();
+                    let literal = self.literal_bytes.get(&arg_temps[0]).cloned();
+                    self.push_print(&place, fn_args[0].assert_type(db), literal.as_deref());
+                }
+
+                let is_tail_call = self.can_tail_call(expr, function, &fn_args);
                 let fn_index = self.cx.declare_fn(function, fn_args);
 
-                // First push the stack pointer for the new function;
-                self.push_pointer(self.next_stack_frame());
+                // First push the stack pointer for the new function.
+                // A tail call reuses our stack frame, as nothing in it is needed once the callee begins.
+                if is_tail_call {
+                    self.instructions
+                        .push(Instruction::LocalGet(self.wasm_stack_pointer.index));
+                } else {
+                    self.push_pointer(self.next_stack_frame());
+                }
 
                 // Now push each of the arguments in turn.
+                // Each argument is given to the callee.
                 for arg_temp in arg_temps {
                     let place = self.variables[arg_temp].clone();
                     self.push_from(&place);
+                    self.mark_given(&place, self.variable_tys[arg_temp]);
                 }
 
-                self.instructions.push(Instruction::Call(fn_index.0));
+                if is_tail_call {
+                    self.instructions.push(Instruction::ReturnCall(fn_index.0));
+                } else {
+                    self.instructions.push(Instruction::Call(fn_index.0));
+                }
             }
             SymExprKind::Return(object_expr) => {
                 self.push_expr(object_expr);
                 self.instructions.push(Instruction::Return);
             }
+            SymExprKind::Loop { sym_loop, body } => {
+                // A Dada loop becomes a wasm `loop` within a `block`:
+                // `break` branches to the end of the `block`, with the value of the loop,
+                // and `continue` branches to the start of the `loop`.
+                let block_type = self.block_type(expr.ty(db));
+                self.loops.push((sym_loop, self.instructions.len()));
+                self.instructions.push(Instruction::Block(block_type));
+                self.instructions
+                    .push(Instruction::Loop(wasm_encoder::BlockType::Empty));
+
+                self.push_expr(body);
+                for _ in self.wasm_repr_of_type(body.ty(db)).flatten() {
+                    self.instructions.push(Instruction::Drop);
+                }
+                self.instructions.push(Instruction::Br(0));
+                self.instructions.push(Instruction::End);
+
+                // The `block` is only exited by branching to its end.
+                self.instructions.push(Instruction::Unreachable);
+                self.instructions.push(Instruction::End);
+                self.loops.pop();
+            }
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                self.push_expr(with_value);
+                let depth = self.branch_depth(from_loop, 0);
+                self.instructions.push(Instruction::Br(depth));
+            }
+            SymExprKind::Continue { to_loop } => {
+                let depth = self.branch_depth(to_loop, 1);
+                self.instructions.push(Instruction::Br(depth));
+            }
+            SymExprKind::IsSome(place) => {
+                let wasm_place = self.place(place);
+                match *wasm_place {
+                    WasmPlaceRepr::Class(flags, _) => {
+                        self.push_from_memory(ValType::I32, flags);
+                        self.instructions.push(Instruction::I32Const(NO_VALUE_FLAG));
+                        self.instructions.push(Instruction::I32And);
+                        self.instructions.push(Instruction::I32Eqz);
+                    }
+                    _ => panic!("unexpected place for an `Option` or `Result`: {wasm_place:?}"),
+                }
+            }
+            SymExprKind::Drop(place) => {
+                let wasm_place = self.place(place);
+                self.drop_place(&wasm_place, place.ty(db));
+            }
             SymExprKind::Not {
                 operand,
                 op_span: _,
@@ -179,7 +375,16 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             SymExprKind::BinaryOp(binary_op, object_expr, object_expr1) => {
                 self.push_expr(object_expr);
                 self.push_expr(object_expr1);
-                self.execute_binary_op(binary_op, object_expr.ty(db), object_expr.ty(db));
+                self.execute_binary_op(
+                    binary_op,
+                    object_expr.ty(db),
+                    object_expr.ty(db),
+                    expr.span(db),
+                );
+            }
+            SymExprKind::Cast(value) => {
+                self.push_expr(value);
+                self.execute_cast(value.ty(db), expr.ty(db));
             }
             SymExprKind::Aggregate { ty, ref fields } => {
                 let wasm_repr = self.wasm_repr_of_type(ty);
@@ -209,11 +414,66 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 self.push_match_expr(expr.ty(db), arms);
             }
             SymExprKind::Error(reported) => self.push_error(reported),
-            #[expect(unused_variables)]
-            SymExprKind::ByteLiteral(sym_byte_literal) => todo!(),
+            SymExprKind::ByteLiteral(_) => {
+                // `Pointer[u8]` has no runtime representation yet, so there is nothing to push;
+                // the bytes are only available at compile time (see `literal_bytes`).
+            }
         }
     }
 
+    /// True if the call `expr` to `function` (instantiated with `fn_args`) can be emitted
+    /// as a `return_call` that reuses our stack frame. This requires that
+    ///
+    /// * the call is in tail position;
+    /// * the callee returns the same wasm values as we do;
+    /// * none of the arguments could be a pointer into our stack frame,
+    ///   since the callee will overwrite it.
+    fn can_tail_call(
+        &self,
+        expr: SymExpr<'db>,
+        function: SymFunction<'db>,
+        fn_args: &[SymGenericTerm<'db>],
+    ) -> bool {
+        if !self.tail_calls.contains(&expr) {
+            return false;
+        }
+
+        let CodegenSignature {
+            inputs: _,
+            generics,
+            input_output,
+        } = self.cx.codegen_signature(function, fn_args);
+        let mut wrcx = WasmReprCx::new(self.cx.db, &generics);
+
+        wrcx.wasm_repr_of_type(input_output.output_ty).flatten() == self.output_val_types
+            && !input_output
+                .input_tys
+                .iter()
+                .any(|&input_ty| wrcx.contains_pointer(input_ty))
+    }
+
+    /// The label index, for a branch instruction pushed next, of the control instruction
+    /// at `offset` from the start of `sym_loop` (0 for its `block`, 1 for its `loop`).
+    fn branch_depth(&self, sym_loop: SymLoop<'db>, offset: usize) -> u32 {
+        let &(_, start) = self
+            .loops
+            .iter()
+            .rev()
+            .find(|&&(l, _)| l == sym_loop)
+            .expect("`break` or `continue` outside of its loop");
+
+        // Count the control instructions since then that are still open.
+        let mut open = 0;
+        for instruction in &self.instructions[start + offset..] {
+            match instruction {
+                Instruction::Block(_) | Instruction::Loop(_) | Instruction::If(_) => open += 1,
+                Instruction::End => open -= 1,
+                _ => {}
+            }
+        }
+        open - 1
+    }
+
     fn pop_and_drop(&mut self, _of_type: SymTy<'db>) {
         // currently everything is stack allocated, no dropping required
     }
@@ -222,16 +482,26 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         self.instructions.push(Instruction::Return);
     }
 
-    /// Push the correct instructions to execute `binary_op` on operands of type `lhs_ty` and `rhs_ty`
+    /// Push the correct instructions to execute `binary_op` on operands of type `lhs_ty` and `rhs_ty`.
+    /// `span` is the span of the binary expression, used to report runtime failures.
     fn execute_binary_op(
         &mut self,
         binary_op: SymBinaryOp,
         lhs_ty: SymTy<'db>,
         rhs_ty: SymTy<'db>,
+        span: Span<'db>,
     ) {
         match self.primitive_kind(lhs_ty) {
             Ok(prim_kind) => {
                 assert_eq!(self.primitive_kind(rhs_ty), Ok(prim_kind));
+                if let SymBinaryOp::Div = binary_op {
+                    self.push_division_checks(prim_kind, span);
+                }
+                if self.cx.options.overflow == OverflowMode::Trap
+                    && self.push_checked_arithmetic(binary_op, prim_kind, span)
+                {
+                    return;
+                }
                 self.execute_binary_op_on_primitives(binary_op, prim_kind)
             }
             Err(e) => match e {
@@ -271,7 +541,7 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             | (SymPrimitiveKind::Bool, SymBinaryOp::GreaterEqual) => Instruction::I32GeU,
 
             (SymPrimitiveKind::Char, SymBinaryOp::LessEqual)
-            | (SymPrimitiveKind::Bool, SymBinaryOp::LessEqual) => Instruction::I32GeU,
+            | (SymPrimitiveKind::Bool, SymBinaryOp::LessEqual) => Instruction::I32LeU,
 
             (SymPrimitiveKind::Char, SymBinaryOp::EqualEqual)
             | (SymPrimitiveKind::Bool, SymBinaryOp::EqualEqual) => Instruction::I32Eq,
@@ -461,6 +731,399 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         self.instructions.push(instruction);
     }
 
+    /// Given that a value of type `from_ty` is on the wasm stack,
+    /// push instructions to convert it to a value of type `to_ty`.
+    /// Both types must be numeric primitives.
+    fn execute_cast(&mut self, from_ty: SymTy<'db>, to_ty: SymTy<'db>) {
+        let (from, to) = match (self.primitive_kind(from_ty), self.primitive_kind(to_ty)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(NotPrimitive::DeadCode), _) | (_, Err(NotPrimitive::DeadCode)) => return,
+            _ => panic!("don't know how to cast from `{from_ty:?}` to `{to_ty:?}`"),
+        };
+
+        let from_repr = NumericRepr::of(from);
+        let to_repr = NumericRepr::of(to);
+
+        let instruction = match (from_repr, to_repr) {
+            (NumericRepr::I32 { .. }, NumericRepr::I32 { .. })
+            | (NumericRepr::I64 { .. }, NumericRepr::I64 { .. })
+            | (NumericRepr::F32, NumericRepr::F32)
+            | (NumericRepr::F64, NumericRepr::F64) => None,
+
+            (NumericRepr::I32 { signed: true }, NumericRepr::I64 { .. }) => {
+                Some(Instruction::I64ExtendI32S)
+            }
+            (NumericRepr::I32 { signed: false }, NumericRepr::I64 { .. }) => {
+                Some(Instruction::I64ExtendI32U)
+            }
+            (NumericRepr::I64 { .. }, NumericRepr::I32 { .. }) => Some(Instruction::I32WrapI64),
+
+            (NumericRepr::I32 { signed: true }, NumericRepr::F32) => {
+                Some(Instruction::F32ConvertI32S)
+            }
+            (NumericRepr::I32 { signed: false }, NumericRepr::F32) => {
+                Some(Instruction::F32ConvertI32U)
+            }
+            (NumericRepr::I64 { signed: true }, NumericRepr::F32) => {
+                Some(Instruction::F32ConvertI64S)
+            }
+            (NumericRepr::I64 { signed: false }, NumericRepr::F32) => {
+                Some(Instruction::F32ConvertI64U)
+            }
+            (NumericRepr::I32 { signed: true }, NumericRepr::F64) => {
+                Some(Instruction::F64ConvertI32S)
+            }
+            (NumericRepr::I32 { signed: false }, NumericRepr::F64) => {
+                Some(Instruction::F64ConvertI32U)
+            }
+            (NumericRepr::I64 { signed: true }, NumericRepr::F64) => {
+                Some(Instruction::F64ConvertI64S)
+            }
+            (NumericRepr::I64 { signed: false }, NumericRepr::F64) => {
+                Some(Instruction::F64ConvertI64U)
+            }
+
+            // 💡 We use the saturating truncations so that out-of-range floats
+            // (and NaN) convert to the nearest representable integer rather than trapping.
+            (NumericRepr::F32, NumericRepr::I32 { signed: true }) => {
+                Some(Instruction::I32TruncSatF32S)
+            }
+            (NumericRepr::F32, NumericRepr::I32 { signed: false }) => {
+                Some(Instruction::I32TruncSatF32U)
+            }
+            (NumericRepr::F64, NumericRepr::I32 { signed: true }) => {
+                Some(Instruction::I32TruncSatF64S)
+            }
+            (NumericRepr::F64, NumericRepr::I32 { signed: false }) => {
+                Some(Instruction::I32TruncSatF64U)
+            }
+            (NumericRepr::F32, NumericRepr::I64 { signed: true }) => {
+                Some(Instruction::I64TruncSatF32S)
+            }
+            (NumericRepr::F32, NumericRepr::I64 { signed: false }) => {
+                Some(Instruction::I64TruncSatF32U)
+            }
+            (NumericRepr::F64, NumericRepr::I64 { signed: true }) => {
+                Some(Instruction::I64TruncSatF64S)
+            }
+            (NumericRepr::F64, NumericRepr::I64 { signed: false }) => {
+                Some(Instruction::I64TruncSatF64U)
+            }
+
+            (NumericRepr::F32, NumericRepr::F64) => Some(Instruction::F64PromoteF32),
+            (NumericRepr::F64, NumericRepr::F32) => Some(Instruction::F32DemoteF64),
+        };
+        self.instructions.extend(instruction);
+
+        // Integers narrower than their wasm representation are wrapped to their width.
+        match to {
+            SymPrimitiveKind::Int { bits } if bits < 32 => {
+                let shift = (32 - bits) as i32;
+                self.instructions.push(Instruction::I32Const(shift));
+                self.instructions.push(Instruction::I32Shl);
+                self.instructions.push(Instruction::I32Const(shift));
+                self.instructions.push(Instruction::I32ShrS);
+            }
+            SymPrimitiveKind::Uint { bits } if bits < 32 => {
+                self.instructions
+                    .push(Instruction::I32Const(((1_u32 << bits) - 1) as i32));
+                self.instructions.push(Instruction::I32And);
+            }
+            SymPrimitiveKind::Char => self.push_char_from_u32(),
+            _ => (),
+        }
+    }
+
+    /// Given a `u32` on the wasm stack, replace it with the `char` whose scalar value it is,
+    /// or with the replacement character `�` if it is a surrogate or out of range.
+    fn push_char_from_u32(&mut self) {
+        let value = self.fresh_local_index(ValType::I32);
+        self.instructions.push(Instruction::LocalSet(value.index));
+
+        // select(replacement, value, is_invalid)
+        self.instructions
+            .push(Instruction::I32Const(char::REPLACEMENT_CHARACTER as i32));
+        self.instructions.push(Instruction::LocalGet(value.index));
+
+        // value - 0xD800 < 0x800 (a surrogate) || value > 0x10FFFF
+        self.instructions.push(Instruction::LocalGet(value.index));
+        self.instructions.push(Instruction::I32Const(0xD800));
+        self.instructions.push(Instruction::I32Sub);
+        self.instructions.push(Instruction::I32Const(0x800));
+        self.instructions.push(Instruction::I32LtU);
+        self.instructions.push(Instruction::LocalGet(value.index));
+        self.instructions
+            .push(Instruction::I32Const(char::MAX as i32));
+        self.instructions.push(Instruction::I32GtU);
+        self.instructions.push(Instruction::I32Or);
+
+        self.instructions.push(Instruction::Select);
+        self.release_local(value);
+    }
+
+    /// Given that the operands of an integer division are on the wasm stack,
+    /// push instructions that invoke the trap handler if the divisor is zero
+    /// or (for signed integers) if the division would overflow.
+    /// The operands are left on the stack in their original order.
+    ///
+    /// 💡 WebAssembly would trap on these anyway, but without any indication
+    /// of which expression failed; see [`TrapTable`](`super::trap::TrapTable`).
+    fn push_division_checks(&mut self, prim_kind: SymPrimitiveKind, span: Span<'db>) {
+        // Determine the wasm type of the operands and, for signed types,
+        // the minimum value (which overflows when divided by `-1`).
+        let (val_type, signed_min) = match prim_kind {
+            SymPrimitiveKind::Int { bits } if bits <= 32 => {
+                (ValType::I32, Some(i64::MIN >> (64 - bits)))
+            }
+            SymPrimitiveKind::Int { bits } => (ValType::I64, Some(i64::MIN >> (64 - bits))),
+            SymPrimitiveKind::Isize => (ValType::I32, Some(i64::from(i32::MIN))),
+            SymPrimitiveKind::Uint { bits } if bits <= 32 => (ValType::I32, None),
+            SymPrimitiveKind::Uint { .. } => (ValType::I64, None),
+            SymPrimitiveKind::Usize => (ValType::I32, None),
+            SymPrimitiveKind::Float { .. } | SymPrimitiveKind::Bool | SymPrimitiveKind::Char => {
+                return;
+            }
+        };
+        let push_const = |instructions: &mut Vec<Instruction<'static>>, value: i64| {
+            instructions.push(match val_type {
+                ValType::I32 => Instruction::I32Const(value as i32),
+                _ => Instruction::I64Const(value),
+            });
+        };
+        let eq = match val_type {
+            ValType::I32 => Instruction::I32Eq,
+            _ => Instruction::I64Eq,
+        };
+
+        // Stash the divisor, leaving the dividend on the stack.
+        let rhs = self.fresh_local_index(val_type);
+        self.instructions.push(Instruction::LocalSet(rhs.index));
+
+        // if rhs == 0 { trap }
+        self.instructions.push(Instruction::LocalGet(rhs.index));
+        push_const(&mut self.instructions, 0);
+        self.instructions.push(eq.clone());
+        self.push_trap_if(TrapKind::DivisionByZero, span);
+
+        // if lhs == MIN && rhs == -1 { trap }
+        // The comparison consumes the dividend, so stash it too and push it back afterwards.
+        if let Some(min) = signed_min {
+            let lhs = self.fresh_local_index(val_type);
+            self.instructions.push(Instruction::LocalSet(lhs.index));
+            self.instructions.push(Instruction::LocalGet(lhs.index));
+            push_const(&mut self.instructions, min);
+            self.instructions.push(eq.clone());
+            self.instructions.push(Instruction::LocalGet(rhs.index));
+            push_const(&mut self.instructions, -1);
+            self.instructions.push(eq);
+            self.instructions.push(Instruction::I32And);
+            self.push_trap_if(TrapKind::DivisionOverflow, span);
+            self.instructions.push(Instruction::LocalGet(lhs.index));
+            self.release_local(lhs);
+        }
+
+        // Restore the divisor.
+        self.instructions.push(Instruction::LocalGet(rhs.index));
+        self.release_local(rhs);
+    }
+
+    /// Push instructions to execute `binary_op` on operands of type `prim_kind`,
+    /// trapping if the result does not fit in `prim_kind`.
+    /// Returns false, having pushed nothing, if this is not an integer `+`, `-`, or `*`
+    /// whose overflow we know how to check.
+    fn push_checked_arithmetic(
+        &mut self,
+        binary_op: SymBinaryOp,
+        prim_kind: SymPrimitiveKind,
+        span: Span<'db>,
+    ) -> bool {
+        let (bits, signed) = match prim_kind {
+            SymPrimitiveKind::Int { bits } if bits <= 64 => (bits, true),
+            SymPrimitiveKind::Isize => (32, true),
+            SymPrimitiveKind::Uint { bits } if bits <= 64 => (bits, false),
+            SymPrimitiveKind::Usize => (32, false),
+            _ => return false,
+        };
+        let op64 = match binary_op {
+            SymBinaryOp::Add => Instruction::I64Add,
+            SymBinaryOp::Sub => Instruction::I64Sub,
+            SymBinaryOp::Mul if bits <= 32 => Instruction::I64Mul,
+            // FIXME: check 64-bit multiplication
+            _ => return false,
+        };
+
+        if bits <= 32 {
+            // Compute the exact result in 64 bits, where it cannot overflow,
+            // then check that it is in range before narrowing it again.
+            let extend = if signed {
+                Instruction::I64ExtendI32S
+            } else {
+                Instruction::I64ExtendI32U
+            };
+            let result = self.fresh_local_index(ValType::I64);
+            self.instructions.extend([
+                extend.clone(),
+                Instruction::LocalSet(result.index),
+                extend,
+                Instruction::LocalGet(result.index),
+                op64,
+                Instruction::LocalTee(result.index),
+            ]);
+            if signed {
+                let min = i64::MIN >> (64 - bits);
+                let max = i64::MAX >> (64 - bits);
+                self.instructions.extend([
+                    Instruction::I64Const(min),
+                    Instruction::I64LtS,
+                    Instruction::LocalGet(result.index),
+                    Instruction::I64Const(max),
+                    Instruction::I64GtS,
+                    Instruction::I32Or,
+                ]);
+            } else {
+                let max = (u64::MAX >> (64 - bits)) as i64;
+                self.instructions
+                    .extend([Instruction::I64Const(max), Instruction::I64GtU]);
+            }
+            self.push_trap_if(TrapKind::Overflow(binary_op), span);
+            self.instructions
+                .extend([Instruction::LocalGet(result.index), Instruction::I32WrapI64]);
+            self.release_local(result);
+            return true;
+        }
+
+        let rhs = self.fresh_local_index(ValType::I64);
+        let lhs = self.fresh_local_index(ValType::I64);
+        let result = self.fresh_local_index(ValType::I64);
+        self.instructions.extend([
+            Instruction::LocalSet(rhs.index),
+            Instruction::LocalTee(lhs.index),
+            Instruction::LocalGet(rhs.index),
+            op64,
+            Instruction::LocalSet(result.index),
+        ]);
+
+        let (get_lhs, get_rhs, get_result) = (
+            Instruction::LocalGet(lhs.index),
+            Instruction::LocalGet(rhs.index),
+            Instruction::LocalGet(result.index),
+        );
+        self.instructions.extend(match (binary_op, signed) {
+            // The result wrapped around iff it is less than either operand.
+            (SymBinaryOp::Add, false) => vec![get_result.clone(), get_lhs, Instruction::I64LtU],
+            (SymBinaryOp::Sub, false) => vec![get_lhs, get_rhs, Instruction::I64LtU],
+
+            // The operands have the same sign, and the result has the other one.
+            (SymBinaryOp::Add, true) => vec![
+                get_lhs,
+                get_result.clone(),
+                Instruction::I64Xor,
+                get_rhs,
+                get_result.clone(),
+                Instruction::I64Xor,
+                Instruction::I64And,
+                Instruction::I64Const(0),
+                Instruction::I64LtS,
+            ],
+
+            // The operands have different signs, and the result has the sign of `rhs`.
+            (SymBinaryOp::Sub, true) => vec![
+                get_lhs.clone(),
+                get_rhs,
+                Instruction::I64Xor,
+                get_lhs,
+                get_result.clone(),
+                Instruction::I64Xor,
+                Instruction::I64And,
+                Instruction::I64Const(0),
+                Instruction::I64LtS,
+            ],
+
+            _ => unreachable!(),
+        });
+        self.push_trap_if(TrapKind::Overflow(binary_op), span);
+        self.instructions.push(get_result);
+        for local in [rhs, lhs, result] {
+            self.release_local(local);
+        }
+        true
+    }
+
+    /// Push instructions for a call to the well-known `panic` function at `span`,
+    /// whose message is stored in `message_temp`: notify the host through the
+    /// [panic hook](`super::trap::PANIC_HOOK_IMPORT`) and then trap.
+    /// The message appears in the trap table if it is known at compile time.
+    fn push_panic(&mut self, span: Span<'db>, message_temp: SymVariable<'db>) {
+        let message = self
+            .literal_bytes
+            .get(&message_temp)
+            .map(|bytes| Arc::from(String::from_utf8_lossy(bytes)));
+        let trap_index = i32::from(self.cx.declare_trap(TrapKind::Panic(message), span));
+        let panic_hook = self.cx.panic_hook();
+        let trap_handler = self.cx.trap_handler();
+        self.instructions.push(Instruction::I32Const(trap_index));
+        self.instructions.push(Instruction::Call(panic_hook.0));
+        self.instructions.push(Instruction::I32Const(trap_index));
+        self.instructions.push(Instruction::Call(trap_handler.0));
+
+        // The trap handler never returns; this also lets the (nonexistent) result
+        // have whatever type the context expects.
+        self.instructions.push(Instruction::Unreachable);
+    }
+
+    /// Push an `Option` or `Result` of type `ty` (for `None`, `Ok`, or `Err`) that has a value
+    /// only for the field `present`, given as its index and the variable storing the value.
+    /// The other fields are zeroes; unless the first field has a value,
+    /// the flag word has [`NO_VALUE_FLAG`] set.
+    fn push_variant(&mut self, ty: SymTy<'db>, present: Option<(usize, SymVariable<'db>)>) {
+        let WasmRepr::Class(fields) = self.wasm_repr_of_type(ty) else {
+            panic!("expected an `Option` or `Result`, found {ty:?}");
+        };
+
+        let flags = match present {
+            Some((0, _)) => 1,
+            _ => 1 | NO_VALUE_FLAG,
+        };
+        self.instructions.push(Instruction::I32Const(flags));
+
+        for (index, field) in fields.iter().enumerate() {
+            match present {
+                Some((present_index, var)) if present_index == index => {
+                    // The value is given to the new object.
+                    let place = self.variables[&var].clone();
+                    self.push_from(&place);
+                    self.mark_given(&place, self.variable_tys[&var]);
+                }
+                _ => {
+                    for val_type in field.flatten() {
+                        self.instructions.push(match val_type {
+                            ValType::I32 => Instruction::I32Const(0),
+                            ValType::I64 => Instruction::I64Const(0),
+                            ValType::F32 => Instruction::F32Const(0.0),
+                            ValType::F64 => Instruction::F64Const(0.0),
+                            ValType::V128 | ValType::Ref(_) => {
+                                panic!("unexpected val type {val_type:?}")
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Given that an `i32` boolean is on the wasm stack, pop it and,
+    /// if it is true, invoke the trap handler to report a trap of the given kind.
+    fn push_trap_if(&mut self, kind: TrapKind, span: Span<'db>) {
+        let trap_index = self.cx.declare_trap(kind, span);
+        let trap_handler = self.cx.trap_handler();
+        self.instructions
+            .push(Instruction::If(wasm_encoder::BlockType::Empty));
+        self.instructions
+            .push(Instruction::I32Const(i32::from(trap_index)));
+        self.instructions.push(Instruction::Call(trap_handler.0));
+        self.instructions.push(Instruction::End);
+    }
+
     /// Return the primitive kind that represents `ty` or `Err` if `ty` is not a primitive.
     fn primitive_kind(&self, ty: SymTy<'db>) -> Result<SymPrimitiveKind, NotPrimitive> {
         let db = self.cx.db;
@@ -476,8 +1139,8 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             }
             SymTyKind::Never | SymTyKind::Error(_) => Err(NotPrimitive::DeadCode),
             SymTyKind::Infer(_) => panic!("unexpected inference variable"),
-            #[expect(unused_variables)]
-            SymTyKind::Perm(sym_perm, sym_ty) => todo!(),
+            // Permissions do not affect the representation of a primitive.
+            SymTyKind::Perm(_, sym_ty) => self.primitive_kind(*sym_ty),
         }
     }
 
@@ -606,3 +1269,30 @@ enum NotPrimitive {
     DeadCode,
     OtherType,
 }
+
+/// The wasm value type used to represent a numeric primitive,
+/// used by [`ExprCodegen::execute_cast`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum NumericRepr {
+    I32 { signed: bool },
+    I64 { signed: bool },
+    F32,
+    F64,
+}
+
+impl NumericRepr {
+    fn of(prim_kind: SymPrimitiveKind) -> Self {
+        match prim_kind {
+            SymPrimitiveKind::Int { bits } if bits <= 32 => NumericRepr::I32 { signed: true },
+            SymPrimitiveKind::Int { .. } => NumericRepr::I64 { signed: true },
+            SymPrimitiveKind::Isize => NumericRepr::I32 { signed: true },
+            SymPrimitiveKind::Uint { bits } if bits <= 32 => NumericRepr::I32 { signed: false },
+            SymPrimitiveKind::Uint { .. } => NumericRepr::I64 { signed: false },
+            SymPrimitiveKind::Usize | SymPrimitiveKind::Bool | SymPrimitiveKind::Char => {
+                NumericRepr::I32 { signed: false }
+            }
+            SymPrimitiveKind::Float { bits } if bits <= 32 => NumericRepr::F32,
+            SymPrimitiveKind::Float { .. } => NumericRepr::F64,
+        }
+    }
+}
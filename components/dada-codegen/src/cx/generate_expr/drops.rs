@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use dada_ir_sym::{
+    ir::{
+        classes::SymAggregate,
+        subst::Subst,
+        types::{SymGenericTerm, SymPerm, SymPermKind, SymTy, SymTyKind, SymTyName},
+    },
+    prelude::{CheckedDestructor, CheckedLayout},
+};
+use wasm_encoder::{Instruction, ValType};
+
+use super::ExprCodegen;
+use super::wasm_place_repr::WasmPlaceRepr;
+use crate::cx::wasm_repr::{WasmRepr, WasmReprCx};
+
+/// Bit of a class's flag word that is set when its first field has no value:
+/// for a `None`, an `Option` without a value, and for an `Err`.
+/// The low bit records whether the place owns the value; a shared copy keeps only this bit.
+pub(super) const NO_VALUE_FLAG: i32 = 2;
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Push instructions to drop the value of type `ty` stored in `place`.
+    ///
+    /// A class value is only dropped if its flag word says that the place still owns it;
+    /// dropping runs its destructor (if any), then drops its fields, and finally
+    /// clears the flag word so that dropping it again does nothing.
+    pub(super) fn drop_place(&mut self, place: &WasmPlaceRepr, ty: SymTy<'db>) {
+        if !self.needs_drop(ty) {
+            return;
+        }
+
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, ty) => self.drop_place(place, ty),
+            SymTyKind::Var(sym_variable) => {
+                self.drop_place(place, self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Named(SymTyName::Aggregate(aggr), ref ty_args) => match *place {
+                WasmPlaceRepr::Class(flags, ref fields) => {
+                    self.push_from_memory(ValType::I32, flags);
+                    self.instructions.push(Instruction::I32Const(1));
+                    self.instructions.push(Instruction::I32And);
+                    self.instructions
+                        .push(Instruction::If(wasm_encoder::BlockType::Empty));
+                    self.call_destructor(place, aggr, ty_args);
+                    self.drop_fields(fields, aggr, ty_args);
+                    self.instructions.push(Instruction::I32Const(0));
+                    self.pop_to_memory(ValType::I32, flags);
+                    self.instructions.push(Instruction::End);
+                }
+                WasmPlaceRepr::Struct(ref fields) => self.drop_fields(fields, aggr, ty_args),
+                _ => (),
+            },
+            SymTyKind::Named(SymTyName::Tuple { arity: _ }, ref ty_args) => {
+                if let WasmPlaceRepr::Struct(ref fields) = *place {
+                    for (field, ty_arg) in fields.iter().zip(ty_args) {
+                        self.drop_place(field, ty_arg.assert_type(db));
+                    }
+                }
+            }
+            SymTyKind::Named(SymTyName::Primitive(_) | SymTyName::Future, _)
+            | SymTyKind::Infer(_)
+            | SymTyKind::Never
+            | SymTyKind::Error(_) => (),
+        }
+    }
+
+    /// Given that the value of type `ty` in `place` was just given away
+    /// (e.g., by `x.give` or by passing it as an argument), clear the flag words
+    /// of the class values it contains, so that dropping it does nothing.
+    ///
+    /// Only values that [need dropping](`Self::needs_drop`) are affected:
+    /// for the rest there is nothing for the flag word to guard, and other code
+    /// may still read it (e.g., the [`NO_VALUE_FLAG`] of a shared `Option`).
+    pub(super) fn mark_given(&mut self, place: &WasmPlaceRepr, ty: SymTy<'db>) {
+        if !self.needs_drop(ty) {
+            return;
+        }
+
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, ty) => self.mark_given(place, ty),
+            SymTyKind::Var(sym_variable) => {
+                self.mark_given(place, self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Named(SymTyName::Aggregate(aggr), ref ty_args) => match *place {
+                WasmPlaceRepr::Class(flags, _) => {
+                    self.instructions.push(Instruction::I32Const(0));
+                    self.pop_to_memory(ValType::I32, flags);
+                }
+                WasmPlaceRepr::Struct(ref fields) => {
+                    let field_tys =
+                        WasmReprCx::new(db, &self.generics).aggr_field_tys(aggr, ty_args);
+                    for (field, field_ty) in fields.iter().zip(field_tys) {
+                        self.mark_given(field, field_ty);
+                    }
+                }
+                _ => (),
+            },
+            SymTyKind::Named(SymTyName::Tuple { arity: _ }, ref ty_args) => {
+                if let WasmPlaceRepr::Struct(ref fields) = *place {
+                    for (field, ty_arg) in fields.iter().zip(ty_args) {
+                        self.mark_given(field, ty_arg.assert_type(db));
+                    }
+                }
+            }
+            SymTyKind::Named(SymTyName::Primitive(_) | SymTyName::Future, _)
+            | SymTyKind::Infer(_)
+            | SymTyKind::Never
+            | SymTyKind::Error(_) => (),
+        }
+    }
+
+    /// True if dropping a value of type `ty` has any effect, i.e.,
+    /// if it may own a class value with a destructor.
+    fn needs_drop(&self, ty: SymTy<'db>) -> bool {
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(perm, ty) => self.perm_owns(perm) && self.needs_drop(ty),
+            SymTyKind::Var(sym_variable) => {
+                self.needs_drop(self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Named(ty_name, ref ty_args) => match ty_name {
+                SymTyName::Aggregate(aggr) if aggr.checked_layout(db).is_err() => false,
+                SymTyName::Aggregate(aggr) => {
+                    matches!(aggr.checked_destructor(db), Ok(Some(_)))
+                        || WasmReprCx::new(db, &self.generics)
+                            .aggr_field_tys(aggr, ty_args)
+                            .into_iter()
+                            .any(|field_ty| self.needs_drop(field_ty))
+                }
+                SymTyName::Tuple { arity: _ } => ty_args
+                    .iter()
+                    .any(|ty_arg| self.needs_drop(ty_arg.assert_type(db))),
+                SymTyName::Primitive(_) | SymTyName::Future => false,
+            },
+            SymTyKind::Infer(_) => panic!("unexpected inference variable"),
+            SymTyKind::Never | SymTyKind::Error(_) => false,
+        }
+    }
+
+    /// True if a value with permission `perm` owns its data (and hence must drop it).
+    fn perm_owns(&self, perm: SymPerm<'db>) -> bool {
+        let db = self.cx.db;
+        match *perm.kind(db) {
+            SymPermKind::My => true,
+            SymPermKind::Our
+            | SymPermKind::Referenced(_)
+            | SymPermKind::Mutable(_)
+            | SymPermKind::Error(_) => false,
+            SymPermKind::Var(sym_variable) => {
+                self.perm_owns(self.generics[&sym_variable].assert_perm(db))
+            }
+            SymPermKind::Apply(left, right) => self.perm_owns(left) && self.perm_owns(right),
+            SymPermKind::Or(left, right) => self.perm_owns(left) || self.perm_owns(right),
+            SymPermKind::Infer(_) => panic!("unexpected inference variable"),
+        }
+    }
+
+    /// Push instructions to call the destructor of `aggr` (if any) on the value in `place`.
+    fn call_destructor(
+        &mut self,
+        place: &WasmPlaceRepr,
+        aggr: SymAggregate<'db>,
+        ty_args: &Vec<SymGenericTerm<'db>>,
+    ) {
+        let db = self.cx.db;
+        let Ok(Some(destructor)) = aggr.checked_destructor(db) else {
+            return;
+        };
+
+        let fn_args = ty_args.subst_vars(db, &self.generics);
+        let self_ty = self
+            .cx
+            .codegen_signature(destructor, &fn_args)
+            .input_output
+            .input_tys[0];
+        let takes_ownership = matches!(self.wasm_repr_of_type(self_ty), WasmRepr::Class(_));
+        let fn_index = self.cx.declare_fn(destructor, fn_args);
+
+        self.push_pointer(self.next_stack_frame());
+        if takes_ownership {
+            // `my self`: the destructor gets a copy, but does not drop it (see drop elaboration),
+            // so the fields remain ours to drop.
+            self.push_from(place);
+        } else {
+            // `mut self`
+            self.push_leased_from(place);
+        }
+        self.instructions.push(Instruction::Call(fn_index.0));
+    }
+
+    /// Push instructions to drop each of the `fields` of a value of type `aggr[ty_args]`.
+    fn drop_fields(
+        &mut self,
+        fields: &[Arc<WasmPlaceRepr>],
+        aggr: SymAggregate<'db>,
+        ty_args: &Vec<SymGenericTerm<'db>>,
+    ) {
+        let field_tys = WasmReprCx::new(self.cx.db, &self.generics).aggr_field_tys(aggr, ty_args);
+        for (field, field_ty) in fields.iter().zip(field_tys) {
+            self.drop_place(field, field_ty);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use dada_ir_ast::ast::PermissionOp;
+use dada_ir_sym::{
+    ir::{
+        exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+        variables::SymVariable,
+        visit::{SymExprVisitor, walk_expr},
+    },
+    well_known,
+};
+use dada_util::Set;
+
+use super::ExprCodegen;
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Record the variables that are assigned or leased with `mut` within `body`,
+    /// the body of the function being generated.
+    /// Their bytes are not tracked by [`Self::record_literal_bytes`],
+    /// since the value they hold when read may not be the one they were initialized with.
+    pub fn record_mutated_variables(&mut self, body: SymExpr<'db>) {
+        let mut visitor = MutatedVariables {
+            db: self.cx.db,
+            variables: Set::default(),
+        };
+        visitor.visit_expr(body);
+        self.mutated_variables = visitor.variables;
+    }
+
+    /// If `initializer`, the initializer of `lv`, is a byte literal, a string created from one,
+    /// or a variable holding one that is never mutated, record its bytes in [`Self::literal_bytes`].
+    pub(super) fn record_literal_bytes(&mut self, lv: SymVariable<'db>, initializer: SymExpr<'db>) {
+        let db = self.cx.db;
+        let bytes = match *initializer.kind(db) {
+            SymExprKind::ByteLiteral(literal) => Arc::from(literal.data(db).value(db).as_slice()),
+            SymExprKind::Call {
+                function,
+                ref arg_temps,
+                ..
+            } if well_known::string_literal_fn(db).ok() == Some(function) => {
+                match self.literal_bytes.get(&arg_temps[0]) {
+                    Some(bytes) => bytes.clone(),
+                    None => return,
+                }
+            }
+            SymExprKind::PermissionOp(PermissionOp::Give | PermissionOp::Reference, place) => {
+                match *place.kind(db) {
+                    SymPlaceExprKind::Var(var) if !self.mutated_variables.contains(&var) => {
+                        match self.literal_bytes.get(&var) {
+                            Some(bytes) => bytes.clone(),
+                            None => return,
+                        }
+                    }
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        if !self.mutated_variables.contains(&lv) {
+            self.literal_bytes.insert(lv, bytes);
+        }
+    }
+}
+
+/// Collects the variables that are assigned or leased with `mut`,
+/// either as a whole or through one of their fields.
+struct MutatedVariables<'db> {
+    db: &'db dyn crate::Db,
+    variables: Set<SymVariable<'db>>,
+}
+
+impl<'db> MutatedVariables<'db> {
+    fn insert_root(&mut self, place: SymPlaceExpr<'db>) {
+        match *place.kind(self.db) {
+            SymPlaceExprKind::Var(var) => {
+                self.variables.insert(var);
+            }
+            SymPlaceExprKind::Field(owner, _) => self.insert_root(owner),
+            SymPlaceExprKind::Error(_) => {}
+        }
+    }
+}
+
+impl<'db> SymExprVisitor<'db> for MutatedVariables<'db> {
+    fn db(&self) -> &'db dyn crate::Db {
+        self.db
+    }
+
+    fn visit_expr(&mut self, expr: SymExpr<'db>) {
+        match *expr.kind(self.db) {
+            SymExprKind::Assign { place, value: _ }
+            | SymExprKind::PermissionOp(PermissionOp::Mutate, place) => self.insert_root(place),
+            _ => {}
+        }
+        walk_expr(self, expr)
+    }
+}
@@ -0,0 +1,151 @@
+use dada_ir_sym::{
+    ir::types::{SymTy, SymTyKind, SymTyName},
+    well_known,
+};
+use wasm_encoder::{Instruction, ValType};
+
+use super::ExprCodegen;
+use super::wasm_place_repr::WasmPlaceRepr;
+use crate::cx::imports::WRITE_UNFORMATTED_HOOK;
+use crate::cx::wasm_repr::WasmReprCx;
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Push instructions that write the text of the value of type `ty` stored in `place`,
+    /// followed by a newline, through the [print hooks](`crate::cx::imports::PRINT_HOOK_MODULE`).
+    /// `literal` holds the bytes of the value if it is known to be a string literal.
+    ///
+    /// Values are formatted the same way as the interpreter prints them.
+    /// Reading the value does not give it away; the call to `print` that follows does that.
+    pub(super) fn push_print(
+        &mut self,
+        place: &WasmPlaceRepr,
+        ty: SymTy<'db>,
+        literal: Option<&[u8]>,
+    ) {
+        match literal {
+            Some(bytes) if self.is_string(ty) => {
+                self.push_write_text(&String::from_utf8_lossy(bytes));
+            }
+            _ => self.push_write(place, ty),
+        }
+        self.push_write_text("\n");
+    }
+
+    /// Push instructions that write the text of the value of type `ty` stored in `place`.
+    fn push_write(&mut self, place: &WasmPlaceRepr, ty: SymTy<'db>) {
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(perm, ty) => {
+                // A `mut` value is a pointer to the value, which we do not follow.
+                if WasmReprCx::new(db, &self.generics).perm_is_pointer(perm) {
+                    self.push_write_unformatted();
+                } else {
+                    self.push_write(place, ty);
+                }
+            }
+            SymTyKind::Var(sym_variable) => {
+                self.push_write(place, self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Named(SymTyName::Primitive(primitive), _) => {
+                let (WasmPlaceRepr::Local(_, val_type) | WasmPlaceRepr::Heap(_, val_type)) = *place
+                else {
+                    return self.push_write_unformatted();
+                };
+                self.push_from(place);
+                let hook = self.cx.print_hook(
+                    &format!("write_{}", primitive.name(db).text(db)),
+                    vec![val_type],
+                );
+                self.instructions.push(Instruction::Call(hook.0));
+            }
+            SymTyKind::Named(SymTyName::Tuple { arity: 0 }, _) => self.push_write_text("()"),
+            SymTyKind::Named(SymTyName::Tuple { arity }, ref ty_args) => {
+                let WasmPlaceRepr::Struct(ref fields) = *place else {
+                    return self.push_write_unformatted();
+                };
+                self.push_write_text("(");
+                for (index, (field, ty_arg)) in fields.iter().zip(ty_args).enumerate() {
+                    if index > 0 {
+                        self.push_write_text(", ");
+                    }
+                    self.push_write(field, ty_arg.assert_type(db));
+                }
+                if arity == 1 {
+                    self.push_write_text(",");
+                }
+                self.push_write_text(")");
+            }
+            SymTyKind::Named(SymTyName::Aggregate(aggr), ref ty_args) => {
+                // Strings and the variants of `Option` and `Result` are displayed specially,
+                // which needs more than the fields.
+                // FIXME: format these too
+                let special = [
+                    well_known::string_class(db),
+                    well_known::option_class(db),
+                    well_known::result_class(db),
+                ];
+                if special.iter().any(|class| class.ok() == Some(aggr)) {
+                    return self.push_write_unformatted();
+                }
+
+                let name = aggr.name(db).text(db).clone();
+                if aggr.fields(db).next().is_none() {
+                    return self.push_write_text(&name);
+                }
+
+                let (WasmPlaceRepr::Struct(ref fields) | WasmPlaceRepr::Class(_, ref fields)) =
+                    *place
+                else {
+                    return self.push_write_unformatted();
+                };
+                let field_tys = WasmReprCx::new(db, &self.generics).aggr_field_tys(aggr, ty_args);
+                self.push_write_text(&format!("{name} {{ "));
+                for (index, ((field, field_place), field_ty)) in
+                    aggr.fields(db).zip(fields).zip(field_tys).enumerate()
+                {
+                    if index > 0 {
+                        self.push_write_text(", ");
+                    }
+                    self.push_write_text(&format!("{}: ", field.name(db).text(db)));
+                    self.push_write(field_place, field_ty);
+                }
+                self.push_write_text(" }");
+            }
+            SymTyKind::Named(SymTyName::Future, _)
+            | SymTyKind::Infer(_)
+            | SymTyKind::Never
+            | SymTyKind::Error(_) => self.push_write_unformatted(),
+        }
+    }
+
+    /// Push instructions that write `text`, one `char` at a time.
+    fn push_write_text(&mut self, text: &str) {
+        let hook = self.cx.print_hook("write_char", vec![ValType::I32]);
+        for c in text.chars() {
+            self.instructions.push(Instruction::I32Const(c as i32));
+            self.instructions.push(Instruction::Call(hook.0));
+        }
+    }
+
+    /// Push a call to the [`WRITE_UNFORMATTED_HOOK`], in place of writing a value
+    /// that generated code cannot format yet.
+    fn push_write_unformatted(&mut self) {
+        let hook = self.cx.print_hook(WRITE_UNFORMATTED_HOOK, vec![]);
+        self.instructions.push(Instruction::Call(hook.0));
+    }
+
+    /// True if `ty` is the `String` class, with any permission.
+    fn is_string(&self, ty: SymTy<'db>) -> bool {
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, ty) => self.is_string(ty),
+            SymTyKind::Var(sym_variable) => {
+                self.is_string(self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Named(SymTyName::Aggregate(aggr), _) => {
+                well_known::string_class(db).ok() == Some(aggr)
+            }
+            _ => false,
+        }
+    }
+}
@@ -11,6 +11,7 @@ use wasm_encoder::{Instruction, ValType};
 use crate::cx::wasm_repr::WasmRepr;
 
 use super::ExprCodegen;
+use super::drops::NO_VALUE_FLAG;
 
 /// The WASM representation for a Dada place. Dada places can be
 /// spread across the WASM local variables and WASM memory.
@@ -42,6 +43,7 @@ impl<'db> ExprCodegen<'_, 'db> {
         let ty_repr = self.wasm_repr_of_type(ty);
         let emplaced_repr = self.emplace_local(&ty_repr);
         self.variables.insert(lv, emplaced_repr);
+        self.variable_tys.insert(lv, ty);
     }
 
     /// The representation of the place represented by `local_variable`.
@@ -89,10 +91,11 @@ impl<'db> ExprCodegen<'_, 'db> {
                 fields.iter().for_each(|r| self.push_shared_from(r));
             }
             WasmPlaceRepr::Class(flags, ref fields) => {
-                // Push a 0 for the flags if shared
-                self.instructions.push(Instruction::I32Const(0));
-
+                // A shared copy does not own the value; it only keeps the `NO_VALUE_FLAG` bit.
                 self.push_from_memory(ValType::I32, flags);
+                self.instructions.push(Instruction::I32Const(NO_VALUE_FLAG));
+                self.instructions.push(Instruction::I32And);
+
                 fields.iter().for_each(|r| self.push_shared_from(r));
             }
             WasmPlaceRepr::Local(..) | WasmPlaceRepr::Heap(..) | WasmPlaceRepr::Nowhere => {
@@ -215,13 +218,44 @@ impl<'db> ExprCodegen<'_, 'db> {
         }
     }
 
-    /// Create a fresh local index storing a value of type `v`.
-    fn fresh_local_index(&mut self, v: ValType) -> WasmLocal {
+    /// Create a fresh local index storing a value of type `v`,
+    /// reusing a released local of the same type if there is one.
+    pub(super) fn fresh_local_index(&mut self, v: ValType) -> WasmLocal {
+        if let Some(position) = self
+            .free_wasm_locals
+            .iter()
+            .rposition(|local| self.wasm_locals[local.index as usize] == v)
+        {
+            return self.free_wasm_locals.remove(position);
+        }
+
         let index = u32::try_from(self.wasm_locals.len()).expect("too many locals");
         self.wasm_locals.push(v);
         WasmLocal { index }
     }
 
+    /// Make the wasm locals used by `place` available for reuse.
+    /// The caller must ensure that `place` is not used again.
+    ///
+    /// 💡 Memory slots are not released here but by resetting the stack frame size,
+    /// since they are allocated in stack order.
+    pub(super) fn release_place(&mut self, place: &WasmPlaceRepr) {
+        match *place {
+            WasmPlaceRepr::Local(local, _) => self.release_local(local),
+            WasmPlaceRepr::Struct(ref fields) => fields.iter().for_each(|f| self.release_place(f)),
+            WasmPlaceRepr::Heap(..) | WasmPlaceRepr::Class(..) | WasmPlaceRepr::Nowhere => {}
+        }
+    }
+
+    /// Make `local` available for reuse. The caller must ensure its value is not used again.
+    pub(super) fn release_local(&mut self, local: WasmLocal) {
+        if !self.cx.options.optimize() {
+            return;
+        }
+        debug_assert!(!self.free_wasm_locals.contains(&local));
+        self.free_wasm_locals.push(local);
+    }
+
     /// Create a fresh slot in memory storing a value of type `v`.
     fn fresh_memory_slot(&mut self, v: ValType) -> WasmPointer {
         let offset = self.wasm_stack_frame_size;
@@ -245,7 +279,7 @@ impl<'db> ExprCodegen<'_, 'db> {
     }
 
     /// Push a value of type `val_type` found in the given memory slot.
-    fn push_from_memory(
+    pub(super) fn push_from_memory(
         &mut self,
         v: ValType,
         WasmPointer {
@@ -293,7 +327,7 @@ impl<'db> ExprCodegen<'_, 'db> {
     }
 
     /// Pop a value of type `val_type` and store it to the given memory slot.
-    fn pop_to_memory(
+    pub(super) fn pop_to_memory(
         &mut self,
         v: ValType,
         WasmPointer {
@@ -369,7 +403,7 @@ fn val_type_size_in_bytes(v: ValType) -> u32 {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct WasmLocal {
     pub index: u32,
 }
@@ -1,34 +1,50 @@
 use dada_ir_ast::diagnostic::Err;
 use dada_ir_sym::{
     ir::{
-        functions::{SymFunction, SymInputOutput},
+        functions::{SymFunction, SymFunctionSource, SymInputOutput},
         types::{SymGenericTerm, SymPlace, SymTy},
         variables::SymVariable,
     },
-    prelude::{CheckedBody, CheckedSignature},
+    prelude::{CheckedSignature, ElaboratedBody},
 };
 use dada_util::Map;
-use wasm_encoder::ValType;
+use wasm_encoder::{Instruction, ValType};
 
-use super::{Cx, FnIndex, FnKey, generate_expr::ExprCodegen, wasm_repr::WasmReprCx};
+use super::{
+    Cx, FnBody, FnIndex, FnKey, const_prop, generate_expr::ExprCodegen, wasm_repr::WasmReprCx,
+};
 
 impl<'db> Cx<'db> {
     /// Declares an instantiation of a function with a given set of arguments and returns its index.
     /// If the function is already declared, nothing happens.
     /// If the function is not already declared, it is enqueued for code-generation.
+    /// Instantiations that differ only in ways irrelevant to codegen share one declaration
+    /// (see [`Self::canonicalize_generics`]).
+    ///
+    /// Declaring a function only requires its signature, never its body.
+    /// This is what permits recursive and mutually recursive functions:
+    /// when the body of `f` calls `f` (or calls `g` which calls `f`),
+    /// the call finds `f` already declared and just uses its index.
     pub(crate) fn declare_fn(
         &mut self,
         function: SymFunction<'db>,
         generics: Vec<SymGenericTerm<'db>>,
     ) -> FnIndex {
-        let key = FnKey(function, generics);
+        let key = FnKey(function, self.canonicalize_generics(generics));
         let generics: &Vec<SymGenericTerm<'_>> = &key.1;
 
-        // Check if we already declared this function and return the result if so
+        // Check if we already declared this function and return the result if so.
+        // This must happen before anything else so that recursive calls terminate.
         if let Some(index) = self.functions.get(&key).copied() {
             return index;
         }
 
+        // Polymorphic recursion (e.g., `f[T]` calling `f[Wrap[T]]`) would otherwise
+        // declare ever deeper instantiations of the same function without end.
+        if self.instantiation_depth(generics) > self.options.max_instantiation_depth() {
+            self.exceed_instantiation_limit(key.clone());
+        }
+
         // Extract function signature
         let CodegenSignature {
             inputs: _,
@@ -42,7 +58,7 @@ impl<'db> Cx<'db> {
         } = self.codegen_signature(function, generics);
 
         // Create the type for this function
-        let ty_index = {
+        let (input_val_types, output_val_types) = {
             let mut wrcx = WasmReprCx::new(self.db, generics);
             // The first input is the stack pointer.
             // The remainder are the values given by the user.
@@ -54,15 +70,27 @@ impl<'db> Cx<'db> {
                 )
                 .collect::<Vec<_>>();
             let output_val_types = wrcx.wasm_repr_of_type(output_ty).flatten();
-            self.declare_fn_type(input_val_types, output_val_types)
+            (input_val_types, output_val_types)
         };
 
+        // Functions implemented by the host are imported;
+        // the function we declare below is a shim that calls the import.
+        if let SymFunctionSource::Extern(ast_extern) = function.source(self.db) {
+            self.declare_import(
+                function,
+                ast_extern,
+                input_val_types[1..].to_vec(),
+                output_val_types.clone(),
+            );
+        }
+
+        let ty_index = self.declare_fn_type(input_val_types, output_val_types);
+
         // Add to the WASM function section
-        let fn_index = FnIndex(self.function_section.len());
-        self.function_section.function(u32::from(ty_index));
+        let fn_index = self.declare_fn_index(ty_index);
 
         // Record on the queue to generate code
-        self.codegen_queue.push(key.clone().into());
+        self.codegen_queue.push_back(key.clone().into());
 
         // Memoize the result for later
         self.functions.insert(key, fn_index);
@@ -70,13 +98,33 @@ impl<'db> Cx<'db> {
         fn_index
     }
 
-    pub(crate) fn codegen_fn(&mut self, FnKey(function, generics): FnKey<'db>) {
+    pub(crate) fn codegen_fn(&mut self, key: FnKey<'db>) {
         let db = self.db;
 
-        let object_check_body = match function.checked_body(self.db) {
+        if self.over_limit.contains(&key) {
+            self.fn_bodies.push(FnBody {
+                locals: vec![],
+                instructions: vec![Instruction::Unreachable, Instruction::End],
+            });
+            return;
+        }
+
+        let FnKey(function, generics) = key;
+
+        if let SymFunctionSource::Extern(_) = function.source(db) {
+            self.codegen_extern_shim(function);
+            return;
+        }
+
+        let object_check_body = match function.elaborated_body(self.db) {
             Some(body) => body,
             None => panic!("asked to codegen function with no body: {function:?}"),
         };
+        let object_check_body = if self.options.const_prop() {
+            const_prop::const_prop(db, object_check_body)
+        } else {
+            object_check_body
+        };
 
         let CodegenSignature {
             inputs,
@@ -86,17 +134,22 @@ impl<'db> Cx<'db> {
 
         // Generate the function body.
         let function = {
+            let tail_calls_enabled = self.options.tail_calls;
             let mut ecx = ExprCodegen::new(self, generics);
+            if tail_calls_enabled {
+                ecx.enable_tail_calls(object_check_body, input_output.output_ty);
+            }
+            ecx.record_mutated_variables(object_check_body);
             ecx.pop_arguments(inputs, &input_output.input_tys);
             ecx.push_expr(object_check_body);
             ecx.pop_and_return(object_check_body.ty(db));
-            ecx.into_function()
+            ecx.into_fn_body()
         };
 
-        self.code_section.function(&function);
+        self.fn_bodies.push(function);
     }
 
-    fn codegen_signature(
+    pub(super) fn codegen_signature(
         &self,
         function: SymFunction<'db>,
         generics: &[SymGenericTerm<'db>],
@@ -140,8 +193,8 @@ impl<'db> Cx<'db> {
     }
 }
 
-struct CodegenSignature<'db> {
-    inputs: &'db [SymVariable<'db>],
-    generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
-    input_output: SymInputOutput<'db>,
+pub(super) struct CodegenSignature<'db> {
+    pub(super) inputs: &'db [SymVariable<'db>],
+    pub(super) generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
+    pub(super) input_output: SymInputOutput<'db>,
 }
@@ -0,0 +1,149 @@
+use dada_ir_sym::{
+    ir::{
+        functions::SymFunction,
+        module::{SymItem, SymModule},
+    },
+    prelude::CheckedFfiSignature,
+};
+use wasm_encoder::{
+    ConstExpr, ExportKind, ExportSection, GlobalSection, GlobalType, Instruction, ValType,
+};
+
+use super::{
+    Cx, FnBody, FnIndex,
+    reachability::Reachable,
+    trap::{TRAP_INDEX_GLOBAL, TRAP_INDEX_GLOBAL_INDEX},
+};
+
+/// Index of the global holding the shadow stack pointer as of the most recent call
+/// from generated code out to the host (`0` if there is none in progress).
+///
+/// 💡 A host export may be called while Dada code is itself waiting on the host,
+/// e.g., from within an `extern fn`. The export must not start its shadow stack at `0`
+/// then, as that would overwrite the frames of the suspended Dada code.
+/// So the shims for `extern fn` record the stack pointer here for the duration of the call
+/// (see [`Cx::codegen_extern_shim`]) and host exports start their shadow stack from it.
+pub(crate) const STACK_POINTER_GLOBAL_INDEX: u32 = 1;
+
+/// A function exported from the wasm module so that the host can call it by name.
+///
+/// 💡 Dada functions take the shadow stack pointer as a hidden first argument,
+/// and that is not something the host should have to know about. Each export is
+/// therefore a small wrapper with the *host ABI*: one wasm parameter per Dada parameter,
+/// one result (or none, for `()`). The wrapper passes the stack pointer from
+/// the [`STACK_POINTER_GLOBAL_INDEX`] global and calls the real function. Restricting exports to primitive types keeps the ABI stable:
+/// it does not depend on how codegen chooses to lay out structs and classes.
+pub(crate) struct HostExport {
+    /// Name under which the function is exported.
+    name: String,
+
+    /// The wrapper implementing the host ABI.
+    wrapper: FnIndex,
+}
+
+/// Returns the functions marked `#[host_export]` in `module`, sorted by their position in the source.
+/// These are exported from the wasm module under their own names.
+///
+/// Reports an error for each one whose signature cannot be expressed in the host ABI
+/// (see [`HostExport`] and [`CheckedFfiSignature`]); such functions are left out.
+pub(crate) fn host_exports<'db>(
+    db: &'db dyn crate::Db,
+    module: SymModule<'db>,
+) -> Vec<SymFunction<'db>> {
+    let mut functions = module
+        .items(db)
+        .filter_map(|item| match item {
+            SymItem::SymFunction(function) => Some(function),
+            SymItem::SymClass(_) | SymItem::SymPrimitive(_) => None,
+        })
+        .filter(|function| function.is_host_export(db))
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|f| f.name_span(db));
+    functions.retain(|&f| f.checked_ffi_signature(db).is_ok());
+    functions
+}
+
+impl<'db> Cx<'db> {
+    /// Declares a wrapper for `function` (which must have passed [`host_exports`],
+    /// or be a `main` function without parameters) that implements the host ABI,
+    /// and records it to be exported.
+    ///
+    /// Must be called once the codegen queue is empty, as the wrapper body is generated immediately.
+    pub(crate) fn declare_host_export(&mut self, function: SymFunction<'db>) -> FnIndex {
+        let inner = self.declare_fn(function, vec![]);
+        self.drain_codegen_queue();
+
+        // The wrapper's type is the inner function's type without the stack pointer.
+        let inner_type = &self.fn_types[self.fn_decls[inner.0 as usize].0 as usize];
+        let inputs = inner_type.inputs[1..].to_vec();
+        let outputs = inner_type.outputs.clone();
+
+        let mut instructions = vec![Instruction::GlobalGet(STACK_POINTER_GLOBAL_INDEX)];
+        instructions.extend((0..inputs.len() as u32).map(Instruction::LocalGet));
+        instructions.push(Instruction::Call(inner.0));
+        instructions.push(Instruction::End);
+
+        let ty_index = self.declare_fn_type(inputs, outputs);
+        let wrapper = self.declare_fn_index(ty_index);
+        self.fn_bodies.push(FnBody {
+            locals: vec![],
+            instructions,
+        });
+
+        self.host_exports.push(HostExport {
+            name: function.name(self.db).to_string(),
+            wrapper,
+        });
+        wrapper
+    }
+
+    /// True if the generated code uses the [`STACK_POINTER_GLOBAL_INDEX`] global,
+    /// which is only needed when control passes between Dada code and the host.
+    fn uses_stack_pointer_global(&self) -> bool {
+        !self.host_exports.is_empty() || !self.extern_imports.is_empty()
+    }
+
+    /// Appends the global and export sections: the global recording which trap fired
+    /// (exported if the generated code contains checks), the global holding the
+    /// stack pointer for host exports, and the exported functions.
+    /// These must appear between the function and code sections.
+    pub(crate) fn encode_exports(&self, reachable: &Reachable, module: &mut wasm_encoder::Module) {
+        let mut exports = ExportSection::new();
+        let mut any_exports = false;
+
+        // The globals have fixed indices, so if either is needed we declare both.
+        if self.trap_table.has_handler() || self.uses_stack_pointer_global() {
+            let mut globals = GlobalSection::new();
+            let global_type = GlobalType {
+                val_type: ValType::I32,
+                mutable: true,
+                shared: false,
+            };
+            globals.global(global_type, &ConstExpr::i32_const(-1));
+            globals.global(global_type, &ConstExpr::i32_const(0));
+            module.section(&globals);
+        }
+
+        if self.trap_table.has_handler() {
+            exports.export(
+                TRAP_INDEX_GLOBAL,
+                ExportKind::Global,
+                TRAP_INDEX_GLOBAL_INDEX,
+            );
+            any_exports = true;
+        }
+
+        for host_export in &self.host_exports {
+            exports.export(
+                &host_export.name,
+                ExportKind::Func,
+                reachable.fn_index(host_export.wrapper.0),
+            );
+            any_exports = true;
+        }
+
+        if any_exports {
+            module.section(&exports);
+        }
+    }
+}
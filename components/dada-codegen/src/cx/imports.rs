@@ -0,0 +1,152 @@
+use dada_ir_ast::ast::AstExternFunction;
+use dada_ir_sym::ir::functions::SymFunction;
+use wasm_encoder::{EntityType, ImportSection, Instruction, ValType};
+
+use super::{
+    CodegenQueueItem, Cx, FnBody, FnIndex, host_exports::STACK_POINTER_GLOBAL_INDEX,
+    reachability::Reachable,
+};
+
+/// Module of the functions imported from the host that generated code calls to `print` values.
+///
+/// For each primitive type there is a function `write_<type>` (e.g., `write_u32` or `write_char`)
+/// that takes a value of that type, as represented in wasm, and writes its text.
+/// Everything else is written piece by piece: punctuation one `char` at a time,
+/// and the end of each call to `print` as a `'\n'`.
+pub const PRINT_HOOK_MODULE: &str = "dada";
+
+/// Name of the function imported from [`PRINT_HOOK_MODULE`] that is called, with no arguments,
+/// in place of writing a value that generated code cannot format yet
+/// (e.g., a `String` that is not a literal).
+pub const WRITE_UNFORMATTED_HOOK: &str = "write_unformatted";
+
+/// A function imported from the host, declared in Dada as an `extern fn`.
+///
+/// 💡 Imports use the host ABI (see [`HostExport`](`super::host_exports::HostExport`)),
+/// which has no shadow stack pointer. Rather than teaching every call site about that,
+/// calls go to a *shim* with the usual Dada calling convention that drops the
+/// stack pointer and calls the import. The shim is declared in place of the extern
+/// function itself, so callers cannot tell the difference.
+pub(crate) struct FnImport {
+    /// The module namespace to import from (e.g., `env`).
+    module: String,
+
+    /// The name of the imported function.
+    name: String,
+}
+
+impl<'db> Cx<'db> {
+    /// Declares the wasm import for the extern function `function`,
+    /// with the given (host ABI) inputs and outputs.
+    pub(crate) fn declare_import(
+        &mut self,
+        function: SymFunction<'db>,
+        ast_extern: AstExternFunction<'db>,
+        inputs: Vec<ValType>,
+        outputs: Vec<ValType>,
+    ) {
+        let db = self.db;
+        let fn_index = self.declare_host_import(
+            ast_extern.import_module_name(db),
+            &function.name(db).to_string(),
+            inputs,
+            outputs,
+        );
+        self.extern_imports.insert(function, fn_index);
+    }
+
+    /// Declares a function imported from the host as `module.name`,
+    /// with the given (host ABI) inputs and outputs, and returns its index.
+    pub(crate) fn declare_host_import(
+        &mut self,
+        module: &str,
+        name: &str,
+        inputs: Vec<ValType>,
+        outputs: Vec<ValType>,
+    ) -> FnIndex {
+        let ty_index = self.declare_fn_type(inputs, outputs);
+        let fn_index = self.declare_fn_index(ty_index);
+        self.codegen_queue.push_back(CodegenQueueItem::Import);
+        self.fn_imports.insert(
+            fn_index,
+            FnImport {
+                module: module.to_string(),
+                name: name.to_string(),
+            },
+        );
+        fn_index
+    }
+
+    /// Returns the index of the function `name` imported from [`PRINT_HOOK_MODULE`],
+    /// which takes `inputs`, declaring it if needed.
+    pub(crate) fn print_hook(&mut self, name: &str, inputs: Vec<ValType>) -> FnIndex {
+        if let Some(&index) = self.print_hooks.get(name) {
+            return index;
+        }
+
+        let fn_index = self.declare_host_import(PRINT_HOOK_MODULE, name, inputs, vec![]);
+        self.print_hooks.insert(name.to_string(), fn_index);
+        fn_index
+    }
+
+    /// Generates the body of the shim for an extern function:
+    /// forward all arguments except the stack pointer to the import.
+    /// For the duration of the call, the stack pointer is stored in the
+    /// [`STACK_POINTER_GLOBAL_INDEX`] global, so that host exports called
+    /// by the host in the meantime start their frames above ours.
+    pub(crate) fn codegen_extern_shim(&mut self, function: SymFunction<'db>) {
+        let import = self.extern_imports[&function];
+        let num_inputs = self.fn_types[self.fn_decls[import.0 as usize].0 as usize]
+            .inputs
+            .len() as u32;
+
+        // Local 0 is the stack pointer; the arguments follow.
+        // The local after them saves the previous value of the global.
+        let saved = num_inputs + 1;
+        let mut instructions = vec![
+            Instruction::GlobalGet(STACK_POINTER_GLOBAL_INDEX),
+            Instruction::LocalSet(saved),
+            Instruction::LocalGet(0),
+            Instruction::GlobalSet(STACK_POINTER_GLOBAL_INDEX),
+        ];
+        instructions.extend((1..=num_inputs).map(Instruction::LocalGet));
+        instructions.push(Instruction::Call(import.0));
+        instructions.push(Instruction::LocalGet(saved));
+        instructions.push(Instruction::GlobalSet(STACK_POINTER_GLOBAL_INDEX));
+        instructions.push(Instruction::End);
+
+        self.fn_bodies.push(FnBody {
+            locals: vec![ValType::I32],
+            instructions,
+        });
+    }
+
+    /// True if the function `index` is imported from the host.
+    pub(crate) fn is_import(&self, index: FnIndex) -> bool {
+        self.fn_imports.contains_key(&index)
+    }
+
+    /// Appends the import section, containing the reachable imports.
+    /// Must appear between the type and function sections.
+    /// Does nothing if there are no reachable imports.
+    pub(crate) fn encode_imports(&self, reachable: &Reachable, module: &mut wasm_encoder::Module) {
+        let mut imports = self
+            .fn_imports
+            .iter()
+            .filter(|&(&index, _)| reachable.is_reachable(index.0))
+            .collect::<Vec<_>>();
+        if imports.is_empty() {
+            return;
+        }
+
+        // Imported functions come first in the function index space, in the order they are imported.
+        imports.sort_by_key(|&(&index, _)| reachable.fn_index(index.0));
+
+        let mut section = ImportSection::new();
+        for (&index, import) in imports {
+            let ty_index = reachable.type_index(self.fn_decls[index.0 as usize].0);
+            section.import(&import.module, &import.name, EntityType::Function(ty_index));
+        }
+        module.section(&section);
+    }
+}
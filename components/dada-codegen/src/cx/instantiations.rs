@@ -0,0 +1,213 @@
+use dada_ir_ast::diagnostic::{Diagnostic, Level};
+use dada_ir_sym::ir::{
+    functions::SymFunction,
+    types::{SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymTy, SymTyKind},
+};
+use dada_util::Map;
+use wasm_encoder::CustomSection;
+
+use super::{Cx, FnKey};
+
+/// Name of the custom section that reports how many times each generic function was instantiated.
+/// Only emitted if [`CodegenOptions::report_instantiations`](`crate::CodegenOptions::report_instantiations`) is set.
+/// The section has one line per generic function, formatted as `path:line:column: name: count`.
+pub const INSTANTIATIONS_SECTION: &str = "dada.instantiations";
+
+impl<'db> Cx<'db> {
+    /// Canonicalize the generic arguments of a function instantiation so that
+    /// instantiations that would generate identical code share one wasm function.
+    ///
+    /// 💡 The places named in permissions (e.g., the `x` in `ref[x]`) matter to the
+    /// type checker but have no effect on how values are represented, so we erase them.
+    /// Otherwise a generic function called with `ref[a] String` and `ref[b] String`
+    /// would be generated twice.
+    pub(crate) fn canonicalize_generics(
+        &self,
+        generics: Vec<SymGenericTerm<'db>>,
+    ) -> Vec<SymGenericTerm<'db>> {
+        generics
+            .into_iter()
+            .map(|term| self.canonical_term(term))
+            .collect()
+    }
+
+    fn canonical_term(&self, term: SymGenericTerm<'db>) -> SymGenericTerm<'db> {
+        match term {
+            SymGenericTerm::Type(ty) => self.canonical_ty(ty).into(),
+            SymGenericTerm::Perm(perm) => self.canonical_perm(perm).into(),
+            SymGenericTerm::Place(_) => SymPlace::erased(self.db).into(),
+            SymGenericTerm::Error(_) => term,
+        }
+    }
+
+    fn canonical_ty(&self, ty: SymTy<'db>) -> SymTy<'db> {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Named(name, ref generics) => SymTy::named(
+                db,
+                name,
+                generics
+                    .iter()
+                    .map(|&term| self.canonical_term(term))
+                    .collect(),
+            ),
+            SymTyKind::Perm(perm, ty) => {
+                SymTy::perm(db, self.canonical_perm(perm), self.canonical_ty(ty))
+            }
+            SymTyKind::Var(_) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => ty,
+        }
+    }
+
+    fn canonical_perm(&self, perm: SymPerm<'db>) -> SymPerm<'db> {
+        let db = self.db;
+        match *perm.kind(db) {
+            SymPermKind::Referenced(ref places) => {
+                SymPerm::referenced(db, self.erase_places(places))
+            }
+            SymPermKind::Mutable(ref places) => SymPerm::mutable(db, self.erase_places(places)),
+            SymPermKind::Apply(left, right) => {
+                SymPerm::apply(db, self.canonical_perm(left), self.canonical_perm(right))
+            }
+            SymPermKind::Or(left, right) => {
+                SymPerm::or(db, self.canonical_perm(left), self.canonical_perm(right))
+            }
+            SymPermKind::My
+            | SymPermKind::Our
+            | SymPermKind::Var(_)
+            | SymPermKind::Infer(_)
+            | SymPermKind::Error(_) => perm,
+        }
+    }
+
+    fn erase_places(&self, places: &[SymPlace<'db>]) -> Vec<SymPlace<'db>> {
+        if places.is_empty() {
+            vec![]
+        } else {
+            vec![SymPlace::erased(self.db)]
+        }
+    }
+
+    /// How deeply `generics` nest, e.g., 1 for `[u32]` and 3 for `[Wrap[Wrap[u32]]]`.
+    /// Permissions count too, so `[ref T]` nests 2 levels.
+    pub(crate) fn instantiation_depth(&self, generics: &[SymGenericTerm<'db>]) -> usize {
+        generics
+            .iter()
+            .map(|&term| self.term_depth(term))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn term_depth(&self, term: SymGenericTerm<'db>) -> usize {
+        match term {
+            SymGenericTerm::Type(ty) => self.ty_depth(ty),
+            SymGenericTerm::Perm(perm) => self.perm_depth(perm),
+            SymGenericTerm::Place(_) | SymGenericTerm::Error(_) => 1,
+        }
+    }
+
+    fn ty_depth(&self, ty: SymTy<'db>) -> usize {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Named(_, ref generics) => 1 + self.instantiation_depth(generics),
+            SymTyKind::Perm(perm, ty) => self.perm_depth(perm) + self.ty_depth(ty),
+            SymTyKind::Var(_) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => 1,
+        }
+    }
+
+    fn perm_depth(&self, perm: SymPerm<'db>) -> usize {
+        match *perm.kind(self.db) {
+            SymPermKind::Apply(left, right) | SymPermKind::Or(left, right) => {
+                self.perm_depth(left) + self.perm_depth(right)
+            }
+            SymPermKind::My
+            | SymPermKind::Our
+            | SymPermKind::Referenced(_)
+            | SymPermKind::Mutable(_)
+            | SymPermKind::Var(_)
+            | SymPermKind::Infer(_)
+            | SymPermKind::Error(_) => 1,
+        }
+    }
+
+    /// Records that the instantiation `key` nests deeper than the instantiation limit,
+    /// reporting an error the first time this happens for its function.
+    /// The instantiation is still declared, but its body is only a trap,
+    /// so that nothing further is instantiated from it.
+    pub(crate) fn exceed_instantiation_limit(&mut self, key: FnKey<'db>) {
+        let db = self.db;
+        let function = key.0;
+        if !self.over_limit.iter().any(|other| other.0 == function) {
+            let name = function.name(db);
+            let limit = self.options.max_instantiation_depth();
+            Diagnostic::error(
+                db,
+                function.name_span(db),
+                format!("reached the instantiation limit while instantiating `{name}`"),
+            )
+            .label(
+                db,
+                Level::Error,
+                function.name_span(db),
+                format!(
+                    "`{name}` is instantiated with generic arguments nested more than {limit} levels deep"
+                ),
+            )
+            .child(Diagnostic::help(
+                db,
+                function.name_span(db),
+                format!(
+                    "this usually means that `{name}` calls itself with ever larger generic arguments, like `f[T]` calling `f[Wrap[T]]`"
+                ),
+            ))
+            .report(db);
+        }
+        self.over_limit.insert(key);
+    }
+
+    /// Appends the custom section reporting the number of instantiations of each generic function.
+    /// Does nothing unless requested in the codegen options.
+    pub(crate) fn encode_instantiations(&self, module: &mut wasm_encoder::Module) {
+        if !self.options.report_instantiations {
+            return;
+        }
+
+        let db = self.db;
+        let mut counts: Map<SymFunction<'db>, usize> = Map::default();
+        for key in self.functions.keys() {
+            if !key.1.is_empty() {
+                *counts.entry(key.0).or_default() += 1;
+            }
+        }
+
+        let mut entries = counts
+            .into_iter()
+            .map(|(function, count)| {
+                let name_span = function.name_span(db);
+                let span = name_span.absolute_span(db);
+                let (line, column) = span.source_file.line_col(db, span.start);
+                (
+                    name_span,
+                    format!(
+                        "{}:{}:{}: {}: {count}",
+                        span.source_file.url_display(db),
+                        line.as_u32() + 1,
+                        column.as_u32() + 1,
+                        function.name(db),
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        module.section(&CustomSection {
+            name: INSTANTIATIONS_SECTION.into(),
+            data: entries
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .into_bytes()
+                .into(),
+        });
+    }
+}
@@ -0,0 +1,236 @@
+//! A peephole optimizer for the instructions of a single function body.
+//!
+//! Codegen emits instructions one expression at a time without looking at its neighbors,
+//! which produces sequences like `local.set x; local.get x` or `i32.const 1; i32.const 2; i32.add`.
+//! We clean those up here, after the fact.
+//!
+//! 💡 Only adjacent instructions are rewritten and control instructions are never touched.
+//! Since wasm branches can only target the start or end of a block,
+//! control can never enter the middle of one of the windows we rewrite.
+
+use wasm_encoder::Instruction;
+
+/// Returns `instructions` with redundant instruction sequences simplified.
+pub(crate) fn optimize(instructions: Vec<Instruction<'static>>) -> Vec<Instruction<'static>> {
+    let mut output = Vec::with_capacity(instructions.len());
+    for instruction in instructions {
+        output.push(instruction);
+
+        // Each rewrite may expose another one at the new end of `output`.
+        while simplify_tail(&mut output) {}
+    }
+    output
+}
+
+/// Rewrites the last few instructions of `output`, if possible.
+/// Returns true if a rewrite occurred.
+fn simplify_tail(output: &mut Vec<Instruction<'static>>) -> bool {
+    use Instruction::*;
+
+    let n = output.len();
+
+    if n >= 4 {
+        // Re-associate `(x op a) op b` to `x op (a op b)`.
+        // Notably this removes double negations, as `!` is emitted as `i32.const 1; i32.xor`.
+        let replacement = match (
+            &output[n - 4],
+            &output[n - 3],
+            &output[n - 2],
+            &output[n - 1],
+        ) {
+            (I32Const(a), I32Add, I32Const(b), I32Add) => {
+                Some([I32Const(a.wrapping_add(*b)), I32Add])
+            }
+            (I32Const(a), I32Xor, I32Const(b), I32Xor) => Some([I32Const(a ^ b), I32Xor]),
+            (I64Const(a), I64Add, I64Const(b), I64Add) => {
+                Some([I64Const(a.wrapping_add(*b)), I64Add])
+            }
+            (I64Const(a), I64Xor, I64Const(b), I64Xor) => Some([I64Const(a ^ b), I64Xor]),
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            output.truncate(n - 4);
+            output.extend(replacement);
+            return true;
+        }
+    }
+
+    if n >= 3 {
+        let folded = match (&output[n - 3], &output[n - 2], &output[n - 1]) {
+            (I32Const(a), I32Const(b), op) => fold_i32(*a, *b, op),
+            (I64Const(a), I64Const(b), op) => fold_i64(*a, *b, op),
+            _ => None,
+        };
+        if let Some(folded) = folded {
+            output.truncate(n - 3);
+            output.push(folded);
+            return true;
+        }
+    }
+
+    if n >= 2 {
+        let replacement = match (&output[n - 2], &output[n - 1]) {
+            (LocalSet(a), LocalGet(b)) if a == b => Some(Some(LocalTee(*a))),
+            (LocalTee(a), Drop) => Some(Some(LocalSet(*a))),
+            (I32Const(_) | I64Const(_) | LocalGet(_), Drop) => Some(None),
+
+            // Identities
+            (I32Const(0), I32Add | I32Sub | I32Or | I32Xor)
+            | (I32Const(1), I32Mul | I32DivS | I32DivU)
+            | (I64Const(0), I64Add | I64Sub | I64Or | I64Xor)
+            | (I64Const(1), I64Mul | I64DivS | I64DivU) => Some(None),
+
+            (I32Const(a), I32Eqz) => Some(Some(I32Const((*a == 0) as i32))),
+            (I64Const(a), I64Eqz) => Some(Some(I32Const((*a == 0) as i32))),
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            output.truncate(n - 2);
+            output.extend(replacement);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Evaluates the binary operation `op` on the `i32` constants `a` and `b`,
+/// if it is one we know how to evaluate and it would not trap.
+fn fold_i32(a: i32, b: i32, op: &Instruction<'static>) -> Option<Instruction<'static>> {
+    use Instruction::*;
+
+    let (ua, ub) = (a as u32, b as u32);
+    let value = match op {
+        I32Add => a.wrapping_add(b),
+        I32Sub => a.wrapping_sub(b),
+        I32Mul => a.wrapping_mul(b),
+        I32DivS => a.checked_div(b)?,
+        I32DivU => ua.checked_div(ub)? as i32,
+        I32And => a & b,
+        I32Or => a | b,
+        I32Xor => a ^ b,
+        I32Eq => (a == b) as i32,
+        I32Ne => (a != b) as i32,
+        I32LtS => (a < b) as i32,
+        I32LtU => (ua < ub) as i32,
+        I32GtS => (a > b) as i32,
+        I32GtU => (ua > ub) as i32,
+        I32LeS => (a <= b) as i32,
+        I32LeU => (ua <= ub) as i32,
+        I32GeS => (a >= b) as i32,
+        I32GeU => (ua >= ub) as i32,
+        _ => return None,
+    };
+    Some(I32Const(value))
+}
+
+/// Evaluates the binary operation `op` on the `i64` constants `a` and `b`,
+/// if it is one we know how to evaluate and it would not trap.
+fn fold_i64(a: i64, b: i64, op: &Instruction<'static>) -> Option<Instruction<'static>> {
+    use Instruction::*;
+
+    let (ua, ub) = (a as u64, b as u64);
+    let instruction = match op {
+        I64Add => I64Const(a.wrapping_add(b)),
+        I64Sub => I64Const(a.wrapping_sub(b)),
+        I64Mul => I64Const(a.wrapping_mul(b)),
+        I64DivS => I64Const(a.checked_div(b)?),
+        I64DivU => I64Const(ua.checked_div(ub)? as i64),
+        I64And => I64Const(a & b),
+        I64Or => I64Const(a | b),
+        I64Xor => I64Const(a ^ b),
+
+        // Comparisons produce an `i32`
+        I64Eq => I32Const((a == b) as i32),
+        I64Ne => I32Const((a != b) as i32),
+        I64LtS => I32Const((a < b) as i32),
+        I64LtU => I32Const((ua < ub) as i32),
+        I64GtS => I32Const((a > b) as i32),
+        I64GtU => I32Const((ua > ub) as i32),
+        I64LeS => I32Const((a <= b) as i32),
+        I64LeU => I32Const((ua <= ub) as i32),
+        I64GeS => I32Const((a >= b) as i32),
+        I64GeU => I32Const((ua >= ub) as i32),
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_encoder::Instruction::{self, *};
+
+    use super::optimize;
+
+    /// Optimizes `input` and compares the result to `expected`.
+    /// `Instruction` does not implement `PartialEq`, so we compare the debug output.
+    fn check(input: Vec<Instruction<'static>>, expected: Vec<Instruction<'static>>) {
+        assert_eq!(format!("{:?}", optimize(input)), format!("{expected:?}"));
+    }
+
+    #[test]
+    fn set_then_get_becomes_tee() {
+        check(vec![LocalSet(3), LocalGet(3)], vec![LocalTee(3)]);
+        check(
+            vec![LocalSet(3), LocalGet(4)],
+            vec![LocalSet(3), LocalGet(4)],
+        );
+    }
+
+    #[test]
+    fn tee_then_drop_becomes_set() {
+        check(vec![LocalTee(2), Drop], vec![LocalSet(2)]);
+        check(vec![LocalSet(2), LocalGet(2), Drop], vec![LocalSet(2)]);
+    }
+
+    #[test]
+    fn unused_values_are_removed() {
+        check(vec![I32Const(22), Drop, LocalGet(0), Drop], vec![]);
+    }
+
+    #[test]
+    fn double_negation_is_removed() {
+        check(
+            vec![LocalGet(1), I32Const(1), I32Xor, I32Const(1), I32Xor],
+            vec![LocalGet(1)],
+        );
+    }
+
+    #[test]
+    fn constant_arithmetic_is_folded() {
+        check(
+            vec![I32Const(1), I32Const(2), I32Add, I32Const(3), I32Mul],
+            vec![I32Const(9)],
+        );
+        check(vec![I32Const(-1), I32Const(1), I32LtU], vec![I32Const(0)]);
+        check(vec![I64Const(4), I64Const(4), I64Eq], vec![I32Const(1)]);
+        check(
+            vec![I32Const(i32::MAX), I32Const(1), I32Add],
+            vec![I32Const(i32::MIN)],
+        );
+    }
+
+    #[test]
+    fn constants_are_reassociated() {
+        check(
+            vec![LocalGet(0), I32Const(1), I32Add, I32Const(2), I32Add],
+            vec![LocalGet(0), I32Const(3), I32Add],
+        );
+        check(
+            vec![LocalGet(0), I32Const(1), I32Add, I32Const(-1), I32Add],
+            vec![LocalGet(0)],
+        );
+    }
+
+    #[test]
+    fn trapping_operations_are_not_folded() {
+        check(
+            vec![I32Const(1), I32Const(0), I32DivU],
+            vec![I32Const(1), I32Const(0), I32DivU],
+        );
+        check(
+            vec![I32Const(i32::MIN), I32Const(-1), I32DivS],
+            vec![I32Const(i32::MIN), I32Const(-1), I32DivS],
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use wasm_encoder::{BlockType, CodeSection, FunctionSection, Instruction, TypeSection};
+
+use super::{Cx, FnIndex};
+
+/// The functions and types reachable from the entry points of a module.
+/// Only these are emitted; everything else is dropped and the survivors renumbered.
+///
+/// 💡 Functions are declared lazily, when code calling them is generated, so today nearly
+/// everything declared is reachable. This pass makes that a guarantee rather than an accident
+/// of the code generator, and keeps it true as more entry points and declarations are added.
+pub(crate) struct Reachable {
+    /// For each declared function, its index in the emitted module (if it is reachable).
+    fn_indices: Vec<Option<u32>>,
+
+    /// For each declared type, its index in the emitted module (if it is used).
+    type_indices: Vec<Option<u32>>,
+}
+
+impl<'db> Cx<'db> {
+    /// Computes the functions reachable from `entry_points` by following calls,
+    /// along with the types they use.
+    pub(crate) fn reachable_from(&self, entry_points: &[FnIndex]) -> Reachable {
+        assert_eq!(
+            self.fn_decls.len(),
+            self.fn_bodies.len(),
+            "every declared function should have been generated"
+        );
+
+        let bodies = self
+            .fn_bodies
+            .iter()
+            .map(|body| &body.instructions[..])
+            .collect::<Vec<_>>();
+        let live_fns = live_functions(&bodies, entry_points);
+
+        let mut live_types = vec![false; self.fn_types.len()];
+        for (index, _) in live_fns.iter().enumerate().filter(|&(_, &live)| live) {
+            live_types[self.fn_decls[index].0 as usize] = true;
+            for type_index in self.fn_bodies[index]
+                .instructions
+                .iter()
+                .filter_map(block_fn_type)
+            {
+                live_types[type_index as usize] = true;
+            }
+        }
+
+        // Imported functions must come before all defined functions in the index space,
+        // so we number them first.
+        let imports = (0..live_fns.len())
+            .map(|index| self.is_import(FnIndex(index as u32)))
+            .collect::<Vec<_>>();
+        let mut fn_indices = vec![None; live_fns.len()];
+        let mut next = 0;
+        for want_import in [true, false] {
+            for (index, fn_index) in fn_indices.iter_mut().enumerate() {
+                if live_fns[index] && imports[index] == want_import {
+                    *fn_index = Some(next);
+                    next += 1;
+                }
+            }
+        }
+
+        Reachable {
+            fn_indices,
+            type_indices: renumber(&live_types),
+        }
+    }
+
+    /// Appends the type, import, and function sections, containing only reachable functions.
+    pub(crate) fn encode_types_and_functions(
+        &self,
+        reachable: &Reachable,
+        module: &mut wasm_encoder::Module,
+    ) {
+        let mut types = TypeSection::new();
+        for (fn_type, new_index) in self.fn_types.iter().zip(&reachable.type_indices) {
+            if new_index.is_some() {
+                types
+                    .ty()
+                    .function(fn_type.inputs.clone(), fn_type.outputs.clone());
+            }
+        }
+        module.section(&types);
+
+        self.encode_imports(reachable, module);
+
+        let mut functions = FunctionSection::new();
+        for (index, (&ty_index, new_index)) in
+            self.fn_decls.iter().zip(&reachable.fn_indices).enumerate()
+        {
+            if new_index.is_some() && !self.is_import(FnIndex(index as u32)) {
+                functions.function(reachable.type_index(ty_index.0));
+            }
+        }
+        module.section(&functions);
+    }
+
+    /// Appends the code section, containing the bodies of reachable functions
+    /// with calls and block types renumbered.
+    pub(crate) fn encode_code(&self, reachable: &Reachable, module: &mut wasm_encoder::Module) {
+        let mut code = CodeSection::new();
+        for (index, (body, new_index)) in
+            self.fn_bodies.iter().zip(&reachable.fn_indices).enumerate()
+        {
+            if new_index.is_none() || self.is_import(FnIndex(index as u32)) {
+                continue;
+            }
+
+            let mut function =
+                wasm_encoder::Function::new_with_locals_types(body.locals.iter().copied());
+            for instruction in &body.instructions {
+                function.instruction(&reachable.renumber(instruction));
+            }
+            code.function(&function);
+        }
+        module.section(&code);
+    }
+}
+
+impl Reachable {
+    /// True if the function `index` is reachable and hence emitted.
+    pub(super) fn is_reachable(&self, index: u32) -> bool {
+        self.fn_indices[index as usize].is_some()
+    }
+
+    /// The index in the emitted module of the (reachable) function `index`.
+    pub(super) fn fn_index(&self, index: u32) -> u32 {
+        self.fn_indices[index as usize].expect("called function should be reachable")
+    }
+
+    pub(super) fn type_index(&self, index: u32) -> u32 {
+        self.type_indices[index as usize].expect("type of reachable function should be used")
+    }
+
+    fn block_type(&self, block_type: BlockType) -> BlockType {
+        match block_type {
+            BlockType::FunctionType(index) => BlockType::FunctionType(self.type_index(index)),
+            BlockType::Empty | BlockType::Result(_) => block_type,
+        }
+    }
+
+    /// Rewrites the function and type indices that appear in `instruction`.
+    fn renumber(&self, instruction: &Instruction<'static>) -> Instruction<'static> {
+        match *instruction {
+            Instruction::Call(index) => Instruction::Call(self.fn_index(index)),
+            Instruction::ReturnCall(index) => Instruction::ReturnCall(self.fn_index(index)),
+            Instruction::Block(block_type) => Instruction::Block(self.block_type(block_type)),
+            Instruction::Loop(block_type) => Instruction::Loop(self.block_type(block_type)),
+            Instruction::If(block_type) => Instruction::If(self.block_type(block_type)),
+            _ => instruction.clone(),
+        }
+    }
+}
+
+/// For each function, given by its body in `bodies`, whether it is reachable
+/// from `entry_points` by following calls.
+fn live_functions(bodies: &[&[Instruction<'static>]], entry_points: &[FnIndex]) -> Vec<bool> {
+    let mut live_fns = vec![false; bodies.len()];
+    let mut stack = entry_points.to_vec();
+    while let Some(FnIndex(index)) = stack.pop() {
+        let index = index as usize;
+        if std::mem::replace(&mut live_fns[index], true) {
+            continue;
+        }
+
+        stack.extend(bodies[index].iter().filter_map(called_fn));
+    }
+    live_fns
+}
+
+/// The function called by `instruction`, if any.
+fn called_fn(instruction: &Instruction<'static>) -> Option<FnIndex> {
+    match *instruction {
+        Instruction::Call(index) | Instruction::ReturnCall(index) => Some(FnIndex(index)),
+        _ => None,
+    }
+}
+
+/// The function type used as the block type of `instruction`, if any.
+fn block_fn_type(instruction: &Instruction<'static>) -> Option<u32> {
+    match *instruction {
+        Instruction::Block(BlockType::FunctionType(index))
+        | Instruction::Loop(BlockType::FunctionType(index))
+        | Instruction::If(BlockType::FunctionType(index)) => Some(index),
+        _ => None,
+    }
+}
+
+/// Assigns consecutive new indices to the live entries of `live`.
+fn renumber(live: &[bool]) -> Vec<Option<u32>> {
+    let mut next = 0;
+    live.iter()
+        .map(|&live| {
+            live.then(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_encoder::{
+        BlockType,
+        Instruction::{self, *},
+    };
+
+    use super::{FnIndex, Reachable, live_functions, renumber};
+
+    #[test]
+    fn only_called_functions_are_live() {
+        // 0 and 1 call each other; 2 calls 3 but nothing calls 2.
+        let bodies: [&[Instruction<'static>]; 4] =
+            [&[Call(1)], &[ReturnCall(0)], &[Call(3)], &[Nop]];
+        assert_eq!(
+            live_functions(&bodies, &[FnIndex(0)]),
+            [true, true, false, false]
+        );
+        assert_eq!(
+            live_functions(&bodies, &[FnIndex(2)]),
+            [false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn live_entries_are_numbered_consecutively() {
+        assert_eq!(
+            renumber(&[true, false, false, true, true]),
+            [Some(0), None, None, Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn calls_and_block_types_are_renumbered() {
+        let reachable = Reachable {
+            fn_indices: vec![Some(0), None, Some(1)],
+            type_indices: vec![None, Some(0)],
+        };
+        // `Instruction` does not implement `PartialEq`, so we compare the debug output.
+        let renumbered = [Call(2), Block(BlockType::FunctionType(1)), LocalGet(2)]
+            .iter()
+            .map(|instruction| reachable.renumber(instruction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            format!("{renumbered:?}"),
+            format!(
+                "{:?}",
+                [Call(1), Block(BlockType::FunctionType(0)), LocalGet(2)]
+            )
+        );
+    }
+}
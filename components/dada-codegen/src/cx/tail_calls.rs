@@ -0,0 +1,55 @@
+use dada_ir_sym::ir::exprs::{SymExpr, SymExprKind};
+use dada_util::Set;
+
+/// Returns the calls that appear in tail position within the function body `body`.
+/// The value of a call in tail position is returned directly by the function,
+/// so nothing remains to be done in the caller once the call completes.
+pub(crate) fn tail_calls<'db>(db: &'db dyn crate::Db, body: SymExpr<'db>) -> Set<SymExpr<'db>> {
+    let mut calls = Set::default();
+    collect_tail_calls(db, body, &mut calls);
+    calls
+}
+
+/// Collects the calls in tail position within `expr`, which is itself in tail position.
+fn collect_tail_calls<'db>(
+    db: &'db dyn crate::Db,
+    expr: SymExpr<'db>,
+    calls: &mut Set<SymExpr<'db>>,
+) {
+    match *expr.kind(db) {
+        SymExprKind::Call { .. } => {
+            calls.insert(expr);
+        }
+
+        // The value of these expressions is the value of the expression in tail position.
+        SymExprKind::Semi(_, expr)
+        | SymExprKind::LetIn { body: expr, .. }
+        | SymExprKind::LetTuple { body: expr, .. } => collect_tail_calls(db, expr, calls),
+        SymExprKind::Return(expr) => collect_tail_calls(db, expr, calls),
+        SymExprKind::Match { ref arms } => {
+            for arm in arms {
+                collect_tail_calls(db, arm.body, calls);
+            }
+        }
+
+        // The call inside an `await` produces a future, not the value we return.
+        SymExprKind::Await { .. } => {}
+
+        // Conservatively, nothing within a loop is in tail position,
+        // not even the value of a `break` that exits a loop in tail position.
+        SymExprKind::Loop { .. } | SymExprKind::Break { .. } | SymExprKind::Continue { .. } => {}
+
+        SymExprKind::Tuple(_)
+        | SymExprKind::Primitive(_)
+        | SymExprKind::ByteLiteral(_)
+        | SymExprKind::Assign { .. }
+        | SymExprKind::PermissionOp(..)
+        | SymExprKind::Drop(_)
+        | SymExprKind::IsSome(_)
+        | SymExprKind::Not { .. }
+        | SymExprKind::BinaryOp(..)
+        | SymExprKind::Cast(_)
+        | SymExprKind::Aggregate { .. }
+        | SymExprKind::Error(_) => {}
+    }
+}
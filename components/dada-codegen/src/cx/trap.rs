@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use dada_ir_ast::span::Span;
+use dada_ir_sym::ir::exprs::SymBinaryOp;
+use wasm_encoder::{CustomSection, Instruction, ValType};
+
+use super::{CodegenQueueItem, Cx, FnBody, FnIndex};
+
+/// Name of the custom section that stores the trap table.
+/// The table has one line per trap, formatted as `path:line:column: message`.
+pub const TRAP_TABLE_SECTION: &str = "dada.traps";
+
+/// Name of the exported global that records the index of the trap that fired.
+/// It is `-1` unless a Dada-level check has failed.
+pub const TRAP_INDEX_GLOBAL: &str = "dada_trap";
+
+/// Index of the [`TRAP_INDEX_GLOBAL`] global
+/// (see also [`STACK_POINTER_GLOBAL_INDEX`](`super::host_exports::STACK_POINTER_GLOBAL_INDEX`)).
+pub(crate) const TRAP_INDEX_GLOBAL_INDEX: u32 = 0;
+
+/// Module and name of the function imported from the host that is called when Dada code panics,
+/// just before trapping. It takes the index of the panic in the trap table,
+/// so the host can report the message and source location as soon as the panic occurs.
+pub const PANIC_HOOK_IMPORT: (&str, &str) = ("dada", "panic");
+
+/// Reasons that generated code may deliberately trap at runtime.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum TrapKind {
+    /// The divisor of an integer division was zero.
+    DivisionByZero,
+
+    /// A signed integer division of the minimum value by `-1`, which overflows.
+    DivisionOverflow,
+
+    /// Integer `+`, `-`, or `*` whose result does not fit in its type
+    /// (only checked with [`OverflowMode::Trap`](`crate::OverflowMode::Trap`)).
+    Overflow(SymBinaryOp),
+
+    /// A call to the well-known `panic` function, with its message if known at compile time.
+    Panic(Option<Arc<str>>),
+}
+
+impl TrapKind {
+    fn message(&self) -> String {
+        match self {
+            TrapKind::DivisionByZero => "attempt to divide by zero".to_string(),
+            TrapKind::DivisionOverflow => "attempt to divide with overflow".to_string(),
+            TrapKind::Overflow(op) => {
+                let verb = match op {
+                    SymBinaryOp::Add => "add",
+                    SymBinaryOp::Sub => "subtract",
+                    SymBinaryOp::Mul => "multiply",
+                    _ => "compute",
+                };
+                format!("attempt to {verb} with overflow")
+            }
+            TrapKind::Panic(Some(message)) => format!("panicked: {message}"),
+            TrapKind::Panic(None) => "explicit panic".to_string(),
+        }
+    }
+}
+
+/// Index of an entry in the trap table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct TrapIndex(u32);
+
+/// Tracks the traps that generated code may raise.
+///
+/// 💡 WebAssembly traps carry no payload, so a bare `unreachable` would leave the
+/// user with no idea *which* expression failed. Instead, checks call an injected
+/// trap handler that stores the index of the failing check into an exported global
+/// before trapping. The host can then look that index up in the trap table
+/// (emitted as a custom section) to produce an error pointing at the source.
+#[derive(Default)]
+pub(crate) struct TrapTable {
+    /// Index of the trap handler function, if it has been declared.
+    handler: Option<FnIndex>,
+
+    /// Index of the imported [panic hook](`PANIC_HOOK_IMPORT`), if it has been declared.
+    panic_hook: Option<FnIndex>,
+
+    /// One rendered entry per trap.
+    entries: Vec<String>,
+}
+
+impl TrapTable {
+    /// True if the generated code contains checks, and hence needs the trap handler,
+    /// the [`TRAP_INDEX_GLOBAL`] global, and the trap table.
+    pub(crate) fn has_handler(&self) -> bool {
+        self.handler.is_some()
+    }
+}
+
+impl<'db> Cx<'db> {
+    /// Records a trap of the given kind raised by the expression at `span`
+    /// and returns the index to pass to the trap handler.
+    pub(crate) fn declare_trap(&mut self, kind: TrapKind, span: Span<'db>) -> TrapIndex {
+        let db = self.db;
+        let span = span.absolute_span(db);
+        let (line, column) = span.source_file.line_col(db, span.start);
+        let entry = format!(
+            "{}:{}:{}: {}",
+            span.source_file.url_display(db),
+            line.as_u32() + 1,
+            column.as_u32() + 1,
+            kind.message(),
+        );
+
+        let index =
+            TrapIndex(u32::try_from(self.trap_table.entries.len()).expect("too many traps"));
+        self.trap_table.entries.push(entry);
+        index
+    }
+
+    /// Returns the index of the trap handler, declaring it if needed.
+    /// The trap handler has type `(i32) -> ()` and never returns.
+    pub(crate) fn trap_handler(&mut self) -> FnIndex {
+        if let Some(index) = self.trap_table.handler {
+            return index;
+        }
+
+        let ty_index = self.declare_fn_type(vec![ValType::I32], vec![]);
+        let fn_index = self.declare_fn_index(ty_index);
+        self.codegen_queue.push_back(CodegenQueueItem::TrapHandler);
+        self.trap_table.handler = Some(fn_index);
+        fn_index
+    }
+
+    /// Returns the index of the panic hook imported from the host, declaring it if needed.
+    /// The panic hook has type `(i32) -> ()`; see [`PANIC_HOOK_IMPORT`].
+    pub(crate) fn panic_hook(&mut self) -> FnIndex {
+        if let Some(index) = self.trap_table.panic_hook {
+            return index;
+        }
+
+        let (module, name) = PANIC_HOOK_IMPORT;
+        let fn_index = self.declare_host_import(module, name, vec![ValType::I32], vec![]);
+        self.trap_table.panic_hook = Some(fn_index);
+        fn_index
+    }
+
+    /// Generates the body of the trap handler: record the trap index and trap.
+    pub(crate) fn codegen_trap_handler(&mut self) {
+        self.fn_bodies.push(FnBody {
+            locals: vec![],
+            instructions: vec![
+                Instruction::LocalGet(0),
+                Instruction::GlobalSet(TRAP_INDEX_GLOBAL_INDEX),
+                Instruction::Unreachable,
+                Instruction::End,
+            ],
+        });
+    }
+
+    /// Appends the custom section holding the trap table.
+    /// Does nothing if the generated code contains no checks.
+    pub(crate) fn encode_trap_table(&self, module: &mut wasm_encoder::Module) {
+        if !self.trap_table.has_handler() {
+            return;
+        }
+
+        module.section(&CustomSection {
+            name: TRAP_TABLE_SECTION.into(),
+            data: self.trap_table.entries.join("\n").into_bytes().into(),
+        });
+    }
+}
+
+impl From<TrapIndex> for i32 {
+    fn from(value: TrapIndex) -> Self {
+        i32::try_from(value.0).expect("too many traps")
+    }
+}
@@ -0,0 +1,73 @@
+use dada_ir_ast::diagnostic::Diagnostic;
+use dada_ir_sym::ir::functions::SymFunction;
+use wasmparser::{Parser, Payload, Validator, WasmFeatures};
+
+use super::{Cx, FnIndex, FnKey, reachability::Reachable};
+
+impl<'db> Cx<'db> {
+    /// Validates the encoded module `bytes`, generated starting from `entry`, and reports an
+    /// internal compiler error on `entry` if it is invalid, naming the Dada function
+    /// whose generated code is at fault (if any).
+    ///
+    /// 💡 An invalid module is always a bug in codegen, but the engine that eventually
+    /// rejects it reports only a byte offset, far from the code that produced it.
+    /// The error is reported on `entry` because the function at fault may be in another
+    /// source file, such as the prelude. Only done in debug builds, as validation is not free.
+    pub(crate) fn validate(&self, entry: SymFunction<'db>, reachable: &Reachable, bytes: &[u8]) {
+        let mut validator =
+            Validator::new_with_features(WasmFeatures::default() | WasmFeatures::TAIL_CALL);
+        let Err(error) = validator.validate_all(bytes) else {
+            return;
+        };
+
+        let db = self.db;
+        let location = match self.function_at_offset(reachable, bytes, error.offset()) {
+            Some(function) => {
+                let span = function.name_span(db).absolute_span(db);
+                let (line, column) = span.source_file.line_col(db, span.start);
+                format!(
+                    " for `{}` ({}:{}:{})",
+                    function.name(db),
+                    span.source_file.url_display(db),
+                    line.as_u32() + 1,
+                    column.as_u32() + 1,
+                )
+            }
+            None => String::new(),
+        };
+        Diagnostic::error(
+            db,
+            entry.name_span(db),
+            format!("internal compiler error: generated an invalid wasm module{location}: {error}"),
+        )
+        .report(db);
+    }
+
+    /// The Dada function whose generated code contains `offset` within the module `bytes`, if any.
+    fn function_at_offset(
+        &self,
+        reachable: &Reachable,
+        bytes: &[u8],
+        offset: usize,
+    ) -> Option<SymFunction<'db>> {
+        // Bodies appear in the code section in the order of `fn_bodies` (see `encode_code`).
+        let mut defined = (0..self.fn_bodies.len() as u32)
+            .filter(|&index| reachable.is_reachable(index) && !self.is_import(FnIndex(index)));
+
+        for payload in Parser::new(0).parse_all(bytes) {
+            let Ok(Payload::CodeSectionEntry(body)) = payload else {
+                continue;
+            };
+            let index = defined.next()?;
+            if body.range().contains(&offset) {
+                return self
+                    .functions
+                    .iter()
+                    .find(|&(_, &fn_index)| fn_index == FnIndex(index))
+                    .map(|(FnKey(function, _), _)| *function);
+            }
+        }
+
+        None
+    }
+}
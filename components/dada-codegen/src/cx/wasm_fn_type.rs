@@ -2,7 +2,8 @@ use wasm_encoder::ValType;
 
 use super::Cx;
 
-pub(crate) struct FnTypeIndex(u32);
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct FnTypeIndex(pub(super) u32);
 
 impl From<FnTypeIndex> for u32 {
     fn from(value: FnTypeIndex) -> Self {
@@ -10,17 +11,29 @@ impl From<FnTypeIndex> for u32 {
     }
 }
 
+/// A wasm function type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct FnType {
+    pub(super) inputs: Vec<ValType>,
+    pub(super) outputs: Vec<ValType>,
+}
+
 impl Cx<'_> {
-    /// Declares an instantiation of a function with a given set of arguments and returns its index.
-    /// If the function is already declared, nothing happens.
-    /// If the function is not already declared, it is enqueued for code-generation.
+    /// Declares a function type with the given inputs and outputs and returns its index.
+    /// If an identical type was already declared, its index is reused.
     pub(crate) fn declare_fn_type(
         &mut self,
         inputs: Vec<ValType>,
         outputs: Vec<ValType>,
     ) -> FnTypeIndex {
-        let index = self.type_section.len();
-        self.type_section.ty().function(inputs, outputs);
-        FnTypeIndex(index)
+        let fn_type = FnType { inputs, outputs };
+        if let Some(&index) = self.fn_type_indices.get(&fn_type) {
+            return index;
+        }
+
+        let index = FnTypeIndex(u32::try_from(self.fn_types.len()).expect("too many types"));
+        self.fn_types.push(fn_type.clone());
+        self.fn_type_indices.insert(fn_type, index);
+        index
     }
 }
@@ -5,7 +5,7 @@ use dada_ir_sym::{
         types::{SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymTy, SymTyKind, SymTyName},
         variables::SymVariable,
     },
-    prelude::CheckedFieldTy,
+    prelude::{CheckedFieldTy, CheckedLayout},
 };
 use dada_util::Map;
 use wasm_encoder::ValType;
@@ -118,6 +118,9 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
             SymTyName::Primitive(sym_primitive) => {
                 WasmRepr::Val(self.wasm_valtype_for_primitive_kind(sym_primitive.kind(db)))
             }
+            // An aggregate that contains itself has no finite representation;
+            // the error was reported during checking.
+            SymTyName::Aggregate(aggr) if aggr.checked_layout(db).is_err() => WasmRepr::Nothing,
             SymTyName::Aggregate(aggr) => match aggr.style(db) {
                 // structs  have the fields inlined
                 SymAggregateStyle::Struct => {
@@ -145,6 +148,60 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
         }
     }
 
+    /// True if the representation of `of_type` includes a pointer to some other place,
+    /// i.e., it contains a `mut` value.
+    pub(super) fn contains_pointer(&mut self, of_type: SymTy<'db>) -> bool {
+        let db = self.db;
+        match *of_type.kind(db) {
+            SymTyKind::Named(ty_name, ref ty_args) => match ty_name {
+                SymTyName::Primitive(_) => false,
+                SymTyName::Aggregate(aggr) if aggr.checked_layout(db).is_err() => false,
+                SymTyName::Aggregate(aggr) => self
+                    .aggr_field_tys(aggr, ty_args)
+                    .into_iter()
+                    .any(|field_ty| self.contains_pointer(field_ty)),
+                SymTyName::Future | SymTyName::Tuple { arity: _ } => ty_args
+                    .iter()
+                    .any(|term| self.contains_pointer(term.assert_type(db))),
+            },
+            SymTyKind::Var(sym_variable) => {
+                let result = self
+                    .generics
+                    .get(&sym_variable)
+                    .expect("expected value for each generic type")
+                    .assert_type(db);
+                self.contains_pointer(result)
+            }
+            SymTyKind::Infer(_) => panic!("unexpected inference variable"),
+            SymTyKind::Never | SymTyKind::Error(_) => false,
+            SymTyKind::Perm(sym_perm, sym_ty) => {
+                self.perm_is_pointer(sym_perm) || self.contains_pointer(sym_ty)
+            }
+        }
+    }
+
+    /// True if values with the permission `sym_perm` are represented as a pointer
+    /// (see [`Self::wasm_repr_of_perm_type`]).
+    pub(super) fn perm_is_pointer(&self, sym_perm: SymPerm<'db>) -> bool {
+        let db = self.db;
+        match *sym_perm.kind(db) {
+            SymPermKind::Mutable(_) => true,
+            SymPermKind::My | SymPermKind::Our | SymPermKind::Referenced(_) => false,
+            SymPermKind::Var(sym_variable) => {
+                let result = self
+                    .generics
+                    .get(&sym_variable)
+                    .expect("expected value for each generic type")
+                    .assert_perm(db);
+                self.perm_is_pointer(result)
+            }
+            SymPermKind::Error(_) => false,
+            SymPermKind::Apply(left, _) => self.perm_is_pointer(left),
+            SymPermKind::Infer(_infer_var_index) => unreachable!(),
+            SymPermKind::Or(perm_l, _perm_r) => self.perm_is_pointer(perm_l),
+        }
+    }
+
     /// The WASM [`ValType`][] for a Dada primtive. Note that small Dada values like `i16` or whatever
     /// are just promoted up to `I32` because we are lazy.
     fn wasm_valtype_for_primitive_kind(&self, primitive: SymPrimitiveKind) -> ValType {
@@ -179,7 +236,7 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
     }
 
     /// The types of each field of some aggregate type given the values `ty_args` for its generic arguments.
-    fn aggr_field_tys<'a>(
+    pub(super) fn aggr_field_tys<'a>(
         &self,
         aggr: SymAggregate<'db>,
         ty_args: &'a Vec<SymGenericTerm<'db>>,
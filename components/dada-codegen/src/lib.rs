@@ -2,13 +2,22 @@ use dada_ir_ast::{ast::Identifier, diagnostic::Diagnostic, inputs::SourceFile};
 use dada_ir_sym::{
     Db,
     ir::{functions::SymFunction, types::SymGenericTerm},
-    prelude::Symbol,
+    prelude::{CheckedSignature, Symbol},
 };
 
 mod cx;
+mod options;
+pub use cx::imports::{PRINT_HOOK_MODULE, WRITE_UNFORMATTED_HOOK};
+pub use cx::instantiations::INSTANTIATIONS_SECTION;
+pub use cx::trap::{PANIC_HOOK_IMPORT, TRAP_INDEX_GLOBAL, TRAP_TABLE_SECTION};
+pub use options::{CodegenOptions, DEFAULT_INSTANTIATION_LIMIT, OptLevel, OverflowMode};
 
 #[salsa::tracked(return_ref)]
-pub fn codegen_main_fn<'db>(db: &'db dyn Db, source_file: SourceFile) -> Option<Vec<u8>> {
+pub fn codegen_main_fn<'db>(
+    db: &'db dyn Db,
+    source_file: SourceFile,
+    options: CodegenOptions,
+) -> Option<Vec<u8>> {
     let main = Identifier::main(db);
     let module = source_file.symbol(db);
     let main_fn = module.function_named(db, main)?;
@@ -23,17 +32,87 @@ pub fn codegen_main_fn<'db>(db: &'db dyn Db, source_file: SourceFile) -> Option<
         return None;
     }
 
-    Some(codegen(db, main_fn, vec![]).clone())
+    let host_exports = cx::host_exports::host_exports(db, module);
+
+    Some(codegen(db, main_fn, vec![], host_exports, options).clone())
+}
+
+/// Name under which [`codegen_runnable_main_fn`] exports the `main` function.
+pub const MAIN_EXPORT: &str = "main";
+
+/// Like [`codegen_main_fn`], but also exports `main` (as [`MAIN_EXPORT`]) so that
+/// a host can run the program, e.g., to compare it against the interpreter.
+///
+/// Returns `None` if there is no `main` function or if it has generics or parameters.
+#[salsa::tracked(return_ref)]
+pub fn codegen_runnable_main_fn<'db>(
+    db: &'db dyn Db,
+    source_file: SourceFile,
+    options: CodegenOptions,
+) -> Option<Vec<u8>> {
+    let main = Identifier::main(db);
+    let module = source_file.symbol(db);
+    let main_fn = module.function_named(db, main)?;
+
+    if !main_fn.symbols(db).has_generics_of_kind(db, &[]) {
+        return None;
+    }
+
+    let signature = main_fn.checked_signature(db).ok()?;
+    if !signature.symbols(db).input_variables.is_empty() {
+        return None;
+    }
+
+    let mut host_exports = cx::host_exports::host_exports(db, module);
+    if !host_exports.contains(&main_fn) {
+        host_exports.push(main_fn);
+    }
+
+    Some(codegen(db, main_fn, vec![], host_exports, options).clone())
 }
 
 /// Generate a self-contained wasm module from a starting function.
+/// Each of `host_exports` is exported from the module under its own name
+/// so that the host can call it (see the `#[host_export]` attribute in the spec).
 #[salsa::tracked(return_ref)]
 pub fn codegen<'db>(
     db: &'db dyn crate::Db,
     function: SymFunction<'db>,
     generics: Vec<SymGenericTerm<'db>>,
+    host_exports: Vec<SymFunction<'db>>,
+    options: CodegenOptions,
 ) -> Vec<u8> {
-    cx::Cx::new(db)
-        .generate_from_fn(function, generics)
-        .finish()
+    let generate = || {
+        cx::Cx::new(db, options)
+            .generate_from_fn(function, generics.clone(), &host_exports)
+            .finish()
+    };
+
+    let bytes = generate();
+    if options.check_determinism {
+        let again = generate();
+        if bytes != again {
+            let offset = bytes
+                .iter()
+                .zip(&again)
+                .position(|(a, b)| a != b)
+                .unwrap_or(bytes.len().min(again.len()));
+            panic!(
+                "codegen for `{}` is nondeterministic: outputs ({} and {} bytes) differ at byte {offset}",
+                function.name(db),
+                bytes.len(),
+                again.len(),
+            );
+        }
+    }
+    bytes
+}
+
+/// Given the contents of the [`TRAP_TABLE_SECTION`] custom section and the value of the
+/// exported [`TRAP_INDEX_GLOBAL`] after the module trapped, returns the message
+/// (including the source location) describing the failed check.
+/// Returns `None` if the trap was not raised by a Dada-level check.
+pub fn trap_message(trap_table: &str, trap_index: i32) -> Option<&str> {
+    let trap_index = usize::try_from(trap_index).ok()?;
+    trap_table.lines().nth(trap_index)
 }
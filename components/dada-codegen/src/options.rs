@@ -0,0 +1,140 @@
+use salsa::Update;
+
+/// Options that affect the generated wasm module.
+///
+/// Embedders start from [`CodegenOptions::default`][] and adjust it with the `with_*` methods,
+/// e.g., `CodegenOptions::default().with_tail_calls(true)`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Update)]
+pub struct CodegenOptions {
+    /// Emit calls in tail position as `return_call` instructions
+    /// (from the wasm tail-call proposal), reusing the caller's stack frame
+    /// where possible so that deep recursion does not exhaust the stack.
+    /// The resulting module requires a runtime that supports tail calls.
+    pub tail_calls: bool,
+
+    /// Emit a custom section ([`INSTANTIATIONS_SECTION`](`crate::INSTANTIATIONS_SECTION`)) recording how many times
+    /// each generic function was instantiated, for code-size analysis.
+    pub report_instantiations: bool,
+
+    /// Generate each module twice, from scratch, and panic if the two results differ.
+    /// Codegen is meant to be a pure function of its inputs, so this should never fire;
+    /// it exists to catch accidental dependencies on things like hash map iteration order.
+    pub check_determinism: bool,
+
+    /// Skip the constant propagation and branch folding pass normally run on each
+    /// function body before codegen, so that the generated code mirrors the source
+    /// more closely. Useful when debugging codegen itself.
+    pub no_const_prop: bool,
+
+    /// Which optimizations to perform.
+    pub opt_level: OptLevel,
+
+    /// What happens when integer arithmetic overflows.
+    pub overflow: OverflowMode,
+
+    /// Emit a `name` section giving the Dada name of each function,
+    /// so that wasm tools and stack traces show meaningful names.
+    pub debug_info: bool,
+
+    /// Also produce the module as WebAssembly text (WAT), for inspection and snapshot tests.
+    /// Codegen itself always produces the binary; it is up to the embedder to print it
+    /// (e.g., the `compile` command writes a `.wat` file next to the `.wasm`).
+    pub emit_wat: bool,
+
+    /// How deeply the generic arguments of a function instantiation may nest
+    /// (e.g., `Wrap[Wrap[u32]]` nests three levels) before codegen gives up on it.
+    /// Polymorphic recursion, like `f[T]` calling `f[Wrap[T]]`, would otherwise
+    /// instantiate `f` forever. `None` means [`DEFAULT_INSTANTIATION_LIMIT`].
+    pub instantiation_limit: Option<usize>,
+}
+
+/// The default for [`CodegenOptions::instantiation_limit`].
+pub const DEFAULT_INSTANTIATION_LIMIT: usize = 64;
+
+/// How much effort codegen spends on making the generated code smaller and faster.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Update)]
+pub enum OptLevel {
+    /// Generate code exactly as written: no constant propagation, no peephole optimization,
+    /// and a distinct wasm local and stack slot for every variable.
+    None,
+
+    /// Cheap optimizations that are always worthwhile.
+    #[default]
+    Basic,
+}
+
+/// What happens when integer addition, subtraction, or multiplication overflows.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Update)]
+pub enum OverflowMode {
+    /// Wrap around, like the interpreter.
+    #[default]
+    Wrap,
+
+    /// Trap with an "attempt to add with overflow" (etc) message.
+    ///
+    /// FIXME: 64-bit multiplication still wraps.
+    Trap,
+}
+
+impl CodegenOptions {
+    pub fn with_tail_calls(mut self, tail_calls: bool) -> Self {
+        self.tail_calls = tail_calls;
+        self
+    }
+
+    pub fn with_report_instantiations(mut self, report_instantiations: bool) -> Self {
+        self.report_instantiations = report_instantiations;
+        self
+    }
+
+    pub fn with_check_determinism(mut self, check_determinism: bool) -> Self {
+        self.check_determinism = check_determinism;
+        self
+    }
+
+    pub fn with_no_const_prop(mut self, no_const_prop: bool) -> Self {
+        self.no_const_prop = no_const_prop;
+        self
+    }
+
+    pub fn with_opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowMode) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
+    pub fn with_emit_wat(mut self, emit_wat: bool) -> Self {
+        self.emit_wat = emit_wat;
+        self
+    }
+
+    pub fn with_instantiation_limit(mut self, instantiation_limit: Option<usize>) -> Self {
+        self.instantiation_limit = instantiation_limit;
+        self
+    }
+
+    /// The maximum nesting depth of the generic arguments of an instantiation.
+    pub(crate) fn max_instantiation_depth(&self) -> usize {
+        self.instantiation_limit
+            .unwrap_or(DEFAULT_INSTANTIATION_LIMIT)
+    }
+
+    /// True if optimizations are enabled at all.
+    pub(crate) fn optimize(&self) -> bool {
+        self.opt_level != OptLevel::None
+    }
+
+    /// True if the constant propagation pass should run.
+    pub(crate) fn const_prop(&self) -> bool {
+        self.optimize() && !self.no_const_prop
+    }
+}
@@ -0,0 +1,332 @@
+//! Builders that generate Dada source, so that tests can describe the program
+//! they check without spelling it out in one large string literal:
+//!
+//! ```
+//! # use dada_compiler::builder::ModuleBuilder;
+//! let source = ModuleBuilder::new()
+//!     .class("Point", |c| c.field("x", "u32").field("y", "u32"))
+//!     .function("main", |f| f.statement("let p = Point(22, 44)"))
+//!     .build();
+//! assert_eq!(
+//!     source,
+//!     "class Point(x: u32, y: u32)\n\nfn main() {\n    let p = Point(22, 44)\n}\n",
+//! );
+//! ```
+//!
+//! Types, expressions, and statements are given as source text; the builders take care of
+//! the surrounding syntax (delimiters, commas, indentation). Builders are plain values, so
+//! they can also be produced by property-based generators to fuzz the checker.
+
+use std::fmt::{self, Display};
+
+/// Builds the source of a module, one item at a time.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleBuilder {
+    items: Vec<String>,
+}
+
+impl ModuleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `use {path}`
+    pub fn use_item(mut self, path: &str) -> Self {
+        self.items.push(format!("use {path}\n"));
+        self
+    }
+
+    /// Adds `class {name}`, configured by `build`
+    pub fn class(mut self, name: &str, build: impl FnOnce(ClassBuilder) -> ClassBuilder) -> Self {
+        self.items
+            .push(build(ClassBuilder::new("class", name)).to_string());
+        self
+    }
+
+    /// Adds `struct {name}`, configured by `build`
+    pub fn struct_(mut self, name: &str, build: impl FnOnce(ClassBuilder) -> ClassBuilder) -> Self {
+        self.items
+            .push(build(ClassBuilder::new("struct", name)).to_string());
+        self
+    }
+
+    /// Adds `fn {name}`, configured by `build`
+    pub fn function(
+        mut self,
+        name: &str,
+        build: impl FnOnce(FunctionBuilder) -> FunctionBuilder,
+    ) -> Self {
+        self.items
+            .push(build(FunctionBuilder::new(name)).to_string());
+        self
+    }
+
+    /// Adds a statement at the top level of the module.
+    /// Top-level statements form the body of an implicit `main` function.
+    pub fn statement(mut self, statement: &str) -> Self {
+        self.items.push(format!("{statement}\n"));
+        self
+    }
+
+    /// Adds `text` as it is, for items that the builders do not cover.
+    pub fn raw(mut self, text: &str) -> Self {
+        self.items.push(text.to_string());
+        self
+    }
+
+    /// Returns the source of the module.
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for ModuleBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{item}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the source of a class or struct.
+#[derive(Clone, Debug)]
+pub struct ClassBuilder {
+    keyword: &'static str,
+    name: String,
+    doc: Vec<String>,
+    public: bool,
+    generics: Vec<String>,
+    fields: Vec<String>,
+    methods: Vec<FunctionBuilder>,
+}
+
+impl ClassBuilder {
+    fn new(keyword: &'static str, name: &str) -> Self {
+        Self {
+            keyword,
+            name: name.to_string(),
+            doc: vec![],
+            public: false,
+            generics: vec![],
+            fields: vec![],
+            methods: vec![],
+        }
+    }
+
+    /// Adds a line to the doc comment.
+    pub fn doc(mut self, line: &str) -> Self {
+        self.doc.push(line.to_string());
+        self
+    }
+
+    /// Makes the class `pub`.
+    pub fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    /// Adds a generic parameter, e.g., `type T` or `perm P`.
+    pub fn generic(mut self, decl: &str) -> Self {
+        self.generics.push(decl.to_string());
+        self
+    }
+
+    /// Adds the field `{name}: {ty}`.
+    pub fn field(mut self, name: &str, ty: &str) -> Self {
+        self.fields.push(format!("{name}: {ty}"));
+        self
+    }
+
+    /// Adds the method `fn {name}`, configured by `build`.
+    pub fn method(
+        mut self,
+        name: &str,
+        build: impl FnOnce(FunctionBuilder) -> FunctionBuilder,
+    ) -> Self {
+        self.methods.push(build(FunctionBuilder::new(name)));
+        self
+    }
+}
+
+impl Display for ClassBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_doc(f, &self.doc)?;
+        if self.public {
+            write!(f, "pub ")?;
+        }
+        write!(f, "{} {}", self.keyword, self.name)?;
+        write_list(f, "[", &self.generics, "]")?;
+        write_list(f, "(", &self.fields, ")")?;
+        if !self.methods.is_empty() {
+            writeln!(f, " {{")?;
+            for (index, method) in self.methods.iter().enumerate() {
+                if index > 0 {
+                    writeln!(f)?;
+                }
+                write_indented(f, &method.to_string())?;
+            }
+            write!(f, "}}")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Builds the source of a function or method.
+#[derive(Clone, Debug)]
+pub struct FunctionBuilder {
+    name: String,
+    doc: Vec<String>,
+    public: bool,
+    is_async: bool,
+    generics: Vec<String>,
+    params: Vec<String>,
+    output_ty: Option<String>,
+    where_clauses: Vec<String>,
+    statements: Vec<String>,
+}
+
+impl FunctionBuilder {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            doc: vec![],
+            public: false,
+            is_async: false,
+            generics: vec![],
+            params: vec![],
+            output_ty: None,
+            where_clauses: vec![],
+            statements: vec![],
+        }
+    }
+
+    /// Adds a line to the doc comment.
+    pub fn doc(mut self, line: &str) -> Self {
+        self.doc.push(line.to_string());
+        self
+    }
+
+    /// Makes the function `pub`.
+    pub fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    /// Makes the function `async`.
+    pub fn async_(mut self) -> Self {
+        self.is_async = true;
+        self
+    }
+
+    /// Adds a generic parameter, e.g., `type T` or `perm P`.
+    pub fn generic(mut self, decl: &str) -> Self {
+        self.generics.push(decl.to_string());
+        self
+    }
+
+    /// Adds a `self` parameter with the given permission, e.g., `Some("mut")` for `mut self`.
+    pub fn self_param(mut self, perm: Option<&str>) -> Self {
+        self.params.push(match perm {
+            Some(perm) => format!("{perm} self"),
+            None => "self".to_string(),
+        });
+        self
+    }
+
+    /// Adds the parameter `{name}: {ty}`.
+    pub fn param(mut self, name: &str, ty: &str) -> Self {
+        self.params.push(format!("{name}: {ty}"));
+        self
+    }
+
+    /// Sets the return type.
+    pub fn returns(mut self, ty: &str) -> Self {
+        self.output_ty = Some(ty.to_string());
+        self
+    }
+
+    /// Adds a where clause, e.g., `T is shared`.
+    pub fn where_clause(mut self, clause: &str) -> Self {
+        self.where_clauses.push(clause.to_string());
+        self
+    }
+
+    /// Adds a statement to the body.
+    pub fn statement(mut self, statement: &str) -> Self {
+        self.statements.push(statement.to_string());
+        self
+    }
+}
+
+impl Display for FunctionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_doc(f, &self.doc)?;
+        if self.public {
+            write!(f, "pub ")?;
+        }
+        if self.is_async {
+            write!(f, "async ")?;
+        }
+        write!(f, "fn {}", self.name)?;
+        write_list(f, "[", &self.generics, "]")?;
+        write!(f, "(")?;
+        write_list(f, "", &self.params, "")?;
+        write!(f, ")")?;
+        if let Some(ty) = &self.output_ty {
+            write!(f, " -> {ty}")?;
+        }
+        if self.where_clauses.is_empty() {
+            write!(f, " ")?;
+        } else {
+            writeln!(f, "\nwhere")?;
+            for clause in &self.where_clauses {
+                writeln!(f, "    {clause},")?;
+            }
+        }
+        if self.statements.is_empty() {
+            return writeln!(f, "{{}}");
+        }
+        writeln!(f, "{{")?;
+        for statement in &self.statements {
+            write_indented(f, &format!("{statement}\n"))?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Writes `lines` as a doc comment.
+fn write_doc(f: &mut fmt::Formatter<'_>, lines: &[String]) -> fmt::Result {
+    for line in lines {
+        writeln!(f, "## {line}")?;
+    }
+    Ok(())
+}
+
+/// Writes `items` separated by commas and surrounded by `open` and `close`, unless it is empty.
+fn write_list(
+    f: &mut fmt::Formatter<'_>,
+    open: &str,
+    items: &[String],
+    close: &str,
+) -> fmt::Result {
+    if items.is_empty() {
+        return Ok(());
+    }
+    write!(f, "{open}{}{close}", items.join(", "))
+}
+
+/// Writes `text` with each non-empty line indented one level.
+fn write_indented(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            write!(f, "{line}")?;
+        } else {
+            write!(f, "    {line}")?;
+        }
+    }
+    Ok(())
+}
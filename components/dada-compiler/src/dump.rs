@@ -0,0 +1,216 @@
+use std::{fmt::Write, str::FromStr};
+
+use dada_ir_ast::{ast::Identifier, inputs::SourceFile};
+use dada_ir_sym::{
+    ir::{
+        functions::{SymFunction, SymFunctionSource},
+        module::SymItem,
+    },
+    prelude::{
+        CheckedBody, CheckedSignature, DumpCheckStats, DumpPermissionState, DumpRedSignature,
+        ElaboratedBody, LeaseGraph, Symbol,
+    },
+};
+use dada_parser::prelude::FunctionBlock;
+use dada_util::{Fallible, bail};
+
+use crate::{CodegenOptions, Compiler, Db};
+
+/// A stage of compilation whose intermediate representation can be dumped
+/// with [`Compiler::dump_ir`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IrStage {
+    /// The parsed AST of the function, including its body.
+    Ast,
+
+    /// The symbolic IR: the checked signature of the function.
+    Sym,
+
+    /// The reduced ("red") types and permission chains of the inputs and output.
+    Red,
+
+    /// The object IR: the checked body expression tree.
+    Object,
+
+    /// Counters from type checking the body, for profiling the type checker.
+    Stats,
+
+    /// The places given away or leased at each program point of the checked body.
+    Perms,
+
+    /// The object IR after drop elaboration, with the end of each variable's lifetime made explicit.
+    Drops,
+
+    /// The wasm generated for the function (and everything it calls), as WAT.
+    Wat,
+}
+
+impl IrStage {
+    /// All stages, in the order they occur in compilation.
+    pub const ALL: [IrStage; 8] = [
+        IrStage::Ast,
+        IrStage::Sym,
+        IrStage::Red,
+        IrStage::Object,
+        IrStage::Stats,
+        IrStage::Perms,
+        IrStage::Drops,
+        IrStage::Wat,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IrStage::Ast => "ast",
+            IrStage::Sym => "sym",
+            IrStage::Red => "red",
+            IrStage::Object => "object",
+            IrStage::Stats => "stats",
+            IrStage::Perms => "perms",
+            IrStage::Drops => "drops",
+            IrStage::Wat => "wat",
+        }
+    }
+}
+
+impl FromStr for IrStage {
+    type Err = dada_util::Error;
+
+    fn from_str(s: &str) -> Fallible<Self> {
+        match IrStage::ALL.into_iter().find(|stage| stage.name() == s) {
+            Some(stage) => Ok(stage),
+            None => bail!(
+                "unknown IR stage `{s}`, expected one of: {}",
+                IrStage::ALL.map(IrStage::name).join(", ")
+            ),
+        }
+    }
+}
+
+impl Compiler {
+    /// Dumps the requested `stages` of IR for the function at `function_path`
+    /// in `source_file`. The path is either the name of a function in the module
+    /// or `Class.method`. Each stage is introduced by a `# stage` header.
+    ///
+    /// The output is meant for debugging the compiler and for test expectations,
+    /// so it avoids anything that varies between runs (e.g., hash map ordering).
+    pub fn dump_ir(
+        &self,
+        source_file: SourceFile,
+        function_path: &str,
+        stages: &[IrStage],
+        options: CodegenOptions,
+    ) -> Fallible<String> {
+        self.attach(|_db| {
+            let db: &dyn Db = self;
+            let function = find_function(db, source_file, function_path)?;
+
+            let mut output = String::new();
+            for &stage in stages {
+                writeln!(output, "# {}", stage.name())?;
+                writeln!(output)?;
+                writeln!(output, "{}", dump_stage(db, function, stage, options)?)?;
+            }
+            Ok(output)
+        })
+    }
+}
+
+impl Compiler {
+    /// The places of the function at `function_path` in `source_file` and the leases
+    /// and references between them at each program point, as JSON.
+    /// The path is as for [`Compiler::dump_ir`].
+    ///
+    /// This is meant for tools that visualize Dada's permission model.
+    pub fn lease_graph(&self, source_file: SourceFile, function_path: &str) -> Fallible<String> {
+        self.attach(|_db| {
+            let db: &dyn Db = self;
+            let function = find_function(db, source_file, function_path)?;
+            match function.lease_graph(db) {
+                Some(graph) => Ok(graph),
+                None => bail!("`{function_path}` has no body"),
+            }
+        })
+    }
+}
+
+fn find_function<'db>(
+    db: &'db dyn Db,
+    source_file: SourceFile,
+    function_path: &str,
+) -> Fallible<SymFunction<'db>> {
+    let module = source_file.symbol(db);
+    let function = match function_path.split_once('.') {
+        None => module.function_named(db, Identifier::new(db, function_path)),
+        Some((class_name, method_name)) => module
+            .items(db)
+            .filter_map(|item| match item {
+                SymItem::SymClass(aggr) => Some(aggr),
+                SymItem::SymFunction(_) | SymItem::SymPrimitive(_) => None,
+            })
+            .find(|aggr| aggr.name(db).text(db) == class_name)
+            .and_then(|aggr| {
+                aggr.methods(db)
+                    .find(|method| method.name(db).text(db) == method_name)
+            }),
+    };
+
+    match function {
+        Some(function) => Ok(function),
+        None => bail!(
+            "no function `{function_path}` in `{}`",
+            source_file.url_display(db)
+        ),
+    }
+}
+
+fn dump_stage<'db>(
+    db: &'db dyn Db,
+    function: SymFunction<'db>,
+    stage: IrStage,
+    options: CodegenOptions,
+) -> Fallible<String> {
+    Ok(match stage {
+        IrStage::Ast => match function.source(db) {
+            SymFunctionSource::Function(ast_function) => {
+                format!("{ast_function:#?}\n{:#?}", ast_function.body_block(db))
+            }
+            SymFunctionSource::Extern(ast_extern) => format!("{ast_extern:#?}"),
+            SymFunctionSource::MainFunction(ast_main_function) => {
+                format!("{:#?}", ast_main_function.statements(db))
+            }
+            SymFunctionSource::Constructor(_, ast_aggregate) => format!("{ast_aggregate:#?}"),
+        },
+        IrStage::Sym => match function.checked_signature(db) {
+            Ok(signature) => format!("{signature:#?}"),
+            Err(_) => "<error>".to_string(),
+        },
+        IrStage::Red => match function.dump_red_signature(db) {
+            Ok(red) => red,
+            Err(_) => "<error>".to_string(),
+        },
+        IrStage::Object => match function.checked_body(db) {
+            Some(body) => format!("{body:#?}"),
+            None => "<no body>".to_string(),
+        },
+        IrStage::Stats => match function.dump_check_stats(db) {
+            Some(stats) => stats,
+            None => "<no body>".to_string(),
+        },
+        IrStage::Perms => match function.dump_permission_state(db) {
+            Some(perms) => perms,
+            None => "<no body>".to_string(),
+        },
+        IrStage::Drops => match function.elaborated_body(db) {
+            Some(body) => format!("{body:#?}"),
+            None => "<no body>".to_string(),
+        },
+        IrStage::Wat => {
+            if !function.symbols(db).generic_variables.is_empty() {
+                "<generic functions cannot be compiled on their own>".to_string()
+            } else {
+                let bytes = dada_codegen::codegen(db, function, vec![], vec![], options);
+                wasmprinter::print_bytes(bytes)?
+            }
+        }
+    })
+}
@@ -0,0 +1,103 @@
+//! How module names map to file names.
+//!
+//! Module resolution behaves the same on every file system:
+//!
+//! * Names are compared after [Unicode normalization](`normalize`), so a module written
+//!   with a precomposed `é` finds a file whose name uses `e` followed by a combining accent
+//!   (as some file systems store it), and vice versa.
+//! * Names are case-sensitive, even on file systems that are not. A module `shapes` does
+//!   not resolve to a file `Shapes.dada`, although reading `shapes.dada` would succeed
+//!   on a case-insensitive file system; instead, we report that the names differ by case.
+//! * Two files whose names differ only by case are an error, since at most one of them
+//!   could exist on a case-insensitive file system.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalizes `name` to Unicode Normalization Form C.
+pub(crate) fn normalize(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// The form of `name` used to find names that differ only by case.
+fn fold_case(name: &str) -> String {
+    normalize(name).to_lowercase()
+}
+
+/// Given the `entries` of a directory, returns the entry to read for the file `name`:
+/// `None` if there is no such entry or an error if it cannot be chosen deterministically
+/// (see the [module docs](`self`)).
+pub(crate) fn resolve_file_name<'e>(
+    name: &str,
+    entries: &'e [String],
+) -> Result<Option<&'e str>, String> {
+    let folded = fold_case(name);
+    let mut candidates: Vec<&str> = entries
+        .iter()
+        .map(|entry| &entry[..])
+        .filter(|entry| fold_case(entry) == folded)
+        .collect();
+    candidates.sort();
+
+    match &candidates[..] {
+        [] => Ok(None),
+        [entry] if normalize(entry) == normalize(name) => Ok(Some(*entry)),
+        [entry] => Err(format!(
+            "no file named `{name}`; found `{entry}`, whose name differs only by case"
+        )),
+        [first, rest @ ..] => {
+            let rest: Vec<String> = rest.iter().map(|entry| format!("`{entry}`")).collect();
+            Err(format!(
+                "`{first}` and {} have names that differ only by case; \
+                rename all but one of them so that module resolution does not \
+                depend on whether the file system is case-sensitive",
+                rest.join(" and ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_file_name;
+
+    fn entries(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn exact_match() {
+        let entries = entries(&["main.dada", "shapes.dada", "shapes"]);
+        assert_eq!(
+            resolve_file_name("shapes.dada", &entries),
+            Ok(Some("shapes.dada"))
+        );
+        assert_eq!(resolve_file_name("colors.dada", &entries), Ok(None));
+    }
+
+    #[test]
+    fn unicode_normalization() {
+        // `e` followed by a combining acute accent, as stored by some file systems
+        let entries = entries(&["cafe\u{301}.dada"]);
+        assert_eq!(
+            resolve_file_name("caf\u{e9}.dada", &entries),
+            Ok(Some("cafe\u{301}.dada"))
+        );
+    }
+
+    #[test]
+    fn differs_only_by_case() {
+        let entries = entries(&["Shapes.dada"]);
+        let message = resolve_file_name("shapes.dada", &entries).unwrap_err();
+        assert!(message.contains("differs only by case"), "{message}");
+    }
+
+    #[test]
+    fn conflicting_entries() {
+        let entries = entries(&["shapes.dada", "SHAPES.dada", "Shapes.dada"]);
+        let message = resolve_file_name("shapes.dada", &entries).unwrap_err();
+        assert!(
+            message.starts_with("`SHAPES.dada` and `Shapes.dada` and `shapes.dada`"),
+            "{message}"
+        );
+    }
+}
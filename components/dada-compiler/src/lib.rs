@@ -6,14 +6,28 @@ use std::{
 use dada_ir_ast::{
     DebugEvent,
     ast::{AstFunction, AstItem, AstMember, Identifier},
-    diagnostic::Diagnostic,
+    diagnostic::{DEFAULT_MAX_ERRORS, Diagnostic},
     inputs::{CompilationRoot, Krate, SourceFile},
-    span::AbsoluteSpan,
+    span::{AbsoluteOffset, AbsoluteSpan},
 };
 use dada_util::{Fallible, FromImpls, Map, Set, bail, debug};
 use salsa::{Database as _, Durability, Event, EventKind, Setter};
 use url::Url;
 
+pub use dada_codegen::{
+    CodegenOptions, INSTANTIATIONS_SECTION, MAIN_EXPORT, OptLevel, OverflowMode, PANIC_HOOK_IMPORT,
+    PRINT_HOOK_MODULE, TRAP_INDEX_GLOBAL, TRAP_TABLE_SECTION, WRITE_UNFORMATTED_HOOK, trap_message,
+};
+pub use dada_doc::{DocFormat, DocOptions};
+pub use dada_execute::{Execution, RuntimeError};
+pub use dada_probe::{
+    Completion, CompletionKind, InlayHint, InlayHintKind, OutlineItem, OutlineKind, SemanticToken,
+    SemanticTokenKind, SignatureHelp, TextEdit,
+};
+pub mod builder;
+mod dump;
+mod file_names;
+pub use dump::IrStage;
 mod fork;
 pub use fork::Fork;
 mod realfs;
@@ -22,6 +36,7 @@ mod vfs;
 pub use vfs::VirtualFileSystem;
 use vfs::{ToUrl, UrlPath};
 
+use dada_ir_sym::prelude::Symbol;
 use dada_parser::prelude::*;
 
 #[salsa::db]
@@ -53,6 +68,15 @@ impl Compiler {
         }
     }
 
+    /// Set the maximum number of errors reported while checking a single function
+    /// (50 by default), after which checking it stops with a "too many errors" summary.
+    pub fn set_max_errors(&mut self, max_errors: usize) {
+        let root = self.root();
+        root.set_max_errors(self)
+            .with_durability(Durability::HIGH)
+            .to(max_errors);
+    }
+
     /// Create a "fork" of the compiler that has only `&self` access.
     /// This is meant to be used from another thread.
     pub fn fork(&self) -> Fork<Self> {
@@ -65,17 +89,18 @@ impl Compiler {
     }
 
     /// Load the contents of `source_url` and then open it with those contents.
+    /// The contents of a file opened in memory take precedence over the file system.
     pub fn load_source_file(&mut self, source_url: &(impl ToUrl + ?Sized)) -> Fallible<SourceFile> {
         let source_url = &source_url.to_url(&*self.vfs)?;
-        let contents = match self.vfs.contents(source_url) {
-            Ok(s) => Ok(s),
-            Err(e) => Err(e.to_string()),
-        };
-
-        self.open_source_file(source_url, contents)
+        let contents = self.contents(source_url);
+        self.set_source_file_contents(source_url, contents)
     }
 
-    /// "Open" a source file with the given contents.
+    /// "Open" a source file with the given contents, e.g., an editor buffer with unsaved changes
+    /// or a file created by a test. The file need not exist on the file system: until it is
+    /// [closed](`Self::close_source_file`), these contents take precedence over the file system,
+    /// both for this file and when looking for the modules and crates around other files.
+    ///
     /// This will find an existing `SourceFile` if one exists and update its content.
     /// If none exists, a new `SourceFile` will be created and the containing crate will be added.
     pub fn open_source_file(
@@ -84,6 +109,38 @@ impl Compiler {
         contents: Result<String, String>,
     ) -> Fallible<SourceFile> {
         let source_url = &source_url.to_url(&*self.vfs)?;
+        {
+            let mut inputs = self.inputs.lock().unwrap();
+            match &contents {
+                Ok(text) => inputs.overlay.insert(source_url.clone(), text.clone()),
+                Err(_) => inputs.overlay.remove(source_url),
+            };
+        }
+
+        self.set_source_file_contents(source_url, contents)
+    }
+
+    /// "Close" a source file that was [opened](`Self::open_source_file`) in memory,
+    /// reverting it to its contents on the file system.
+    pub fn close_source_file(&mut self, source_url: &(impl ToUrl + ?Sized)) -> Fallible<()> {
+        let source_url = &source_url.to_url(&*self.vfs)?;
+        self.inputs.lock().unwrap().overlay.remove(source_url);
+
+        if let Some(source_file) = self.get_source_file(source_url) {
+            let contents = self.read_file(source_url);
+            self.update_contents(source_file, contents);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the contents of the source file at `source_url`, creating it (and adding
+    /// its containing crate) if needed.
+    fn set_source_file_contents(
+        &mut self,
+        source_url: &Url,
+        contents: Result<String, String>,
+    ) -> Fallible<SourceFile> {
         let source_file = match self.get_source_file(source_url) {
             Some(v) => v,
             None => {
@@ -92,10 +149,19 @@ impl Compiler {
             }
         };
 
-        let _ = source_file.set_contents(self).to(contents);
+        self.update_contents(source_file, contents);
         Ok(source_file)
     }
 
+    /// Sets the contents of `source_file`, unless they are unchanged.
+    /// Setting an input invalidates everything that depends on it,
+    /// so reopening a file with the same contents should not do so.
+    fn update_contents(&mut self, source_file: SourceFile, contents: Result<String, String>) {
+        if *source_file.contents(&*self) != contents {
+            let _ = source_file.set_contents(self).to(contents);
+        }
+    }
+
     /// Get the `SourceFile` for the given path.
     /// Errors if no source file was opened yet.
     pub fn get_previously_opened_source_file(
@@ -127,7 +193,7 @@ impl Compiler {
         while !krate_path.is_empty() {
             krate_path = krate_path.pop();
 
-            if !self.vfs.exists(&krate_path.dada_url()) {
+            if !self.exists(&krate_path.dada_url()) {
                 break;
             }
         }
@@ -155,13 +221,69 @@ impl Compiler {
     }
 
     /// Codegen the main function of a source file.
-    pub fn codegen_main_fn(&self, source_file: SourceFile) -> &Option<Vec<u8>> {
-        dada_codegen::codegen_main_fn(self, source_file)
+    pub fn codegen_main_fn(
+        &self,
+        source_file: SourceFile,
+        options: CodegenOptions,
+    ) -> &Option<Vec<u8>> {
+        dada_codegen::codegen_main_fn(self, source_file, options)
+    }
+
+    /// Codegen the main function of a source file as a module that exports `main`
+    /// (as [`MAIN_EXPORT`]), so that the host can run it.
+    /// Returns `None` if there is no suitable main function.
+    pub fn codegen_runnable_main_fn(
+        &self,
+        source_file: SourceFile,
+        options: CodegenOptions,
+    ) -> &Option<Vec<u8>> {
+        dada_codegen::codegen_runnable_main_fn(self, source_file, options)
+    }
+
+    /// Codegen the main function of a source file, rendered as WebAssembly text (WAT).
+    /// Returns `None` if there is no main function.
+    pub fn codegen_main_fn_wat(
+        &self,
+        source_file: SourceFile,
+        options: CodegenOptions,
+    ) -> Fallible<Option<String>> {
+        match self.codegen_main_fn(source_file, options) {
+            Some(bytes) => Ok(Some(wasmprinter::print_bytes(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Run the main function of a source file with the interpreter, rather than generating code for it.
+    /// Returns `None` if there is no suitable main function.
+    pub fn interpret_main(&self, source_file: SourceFile) -> Option<Execution> {
+        self.attach(|db| dada_execute::interpret_main(db, source_file))
+    }
+
+    /// Compute all diagnostics for a source file, including those that are only
+    /// detected when generating code for its main function (e.g., functions
+    /// exported to the host with an unsupported signature).
+    pub fn check_all_with_codegen(
+        &self,
+        source_file: SourceFile,
+        options: CodegenOptions,
+    ) -> Vec<&Diagnostic> {
+        let mut diagnostics = self.check_all(source_file);
+        diagnostics.extend(
+            dada_codegen::codegen_main_fn::accumulated::<Diagnostic>(self, source_file, options)
+                .into_iter()
+                .filter(|d| d.span.source_file == source_file),
+        );
+        self.sorted_and_deduplicated(diagnostics)
     }
 
     /// Compute all diagnostics for a source file.
     pub fn check_all(&self, source_file: SourceFile) -> Vec<&Diagnostic> {
-        Self::deduplicated(check_all::accumulated::<Diagnostic>(self, source_file))
+        self.sorted_and_deduplicated(check_all::accumulated::<Diagnostic>(self, source_file))
+    }
+
+    /// Renders the documentation for the module defined by `source_file`.
+    pub fn document(&self, source_file: SourceFile, options: DocOptions) -> String {
+        self.attach(|db| dada_doc::document_module(db, source_file.symbol(db), options))
     }
 
     /// Return type of the variable found at the given `span` or `None` if there is no variable there.
@@ -179,9 +301,138 @@ impl Compiler {
         self.attach(|db| dada_probe::probe_ast(db, span))
     }
 
-    fn deduplicated(mut diagnostics: Vec<&Diagnostic>) -> Vec<&Diagnostic> {
+    /// Variables dropped by the function containing `span`, in the order drop elaboration inserted them.
+    pub fn probe_drops(&self, span: AbsoluteSpan) -> Option<String> {
+        self.attach(|db| dada_probe::probe_drops(db, span))
+    }
+
+    /// Names that can complete the identifier being typed at `offset` in `source_file`.
+    pub fn completions(&self, source_file: SourceFile, offset: AbsoluteOffset) -> Vec<Completion> {
+        self.attach(|db| dada_probe::completions(db, source_file, offset))
+    }
+
+    /// Outline of the items declared in `source_file`, for an editor's outline view.
+    pub fn outline(&self, source_file: SourceFile) -> Vec<OutlineItem> {
+        self.attach(|db| dada_probe::outline(db, source_file))
+    }
+
+    /// Spans of the bodies and blocks of `source_file` that an editor can fold.
+    pub fn folding_ranges(&self, source_file: SourceFile) -> Vec<AbsoluteSpan> {
+        self.attach(|db| dada_probe::folding_ranges(db, source_file))
+    }
+
+    /// Spans enclosing `offset` in `source_file`, innermost first, for expanding the selection.
+    pub fn selection_ranges(
+        &self,
+        source_file: SourceFile,
+        offset: AbsoluteOffset,
+    ) -> Vec<AbsoluteSpan> {
+        self.attach(|db| dada_probe::selection_ranges(db, source_file, offset))
+    }
+
+    /// Inferred types and permissions for an editor to display inline in `source_file`.
+    pub fn inlay_hints(&self, source_file: SourceFile) -> Vec<InlayHint> {
+        self.attach(|db| dada_probe::inlay_hints(db, source_file))
+    }
+
+    /// Tokens of `source_file` classified for semantic highlighting, in source order.
+    pub fn semantic_tokens(&self, source_file: SourceFile) -> Vec<SemanticToken> {
+        self.attach(|db| dada_probe::semantic_tokens(db, source_file))
+    }
+
+    /// Signature of the function called by the innermost call whose arguments enclose `offset`.
+    pub fn signature_help(
+        &self,
+        source_file: SourceFile,
+        offset: AbsoluteOffset,
+    ) -> Option<SignatureHelp> {
+        self.attach(|db| dada_probe::signature_help(db, source_file, offset))
+    }
+
+    /// Edits that rename the variable, field, or function named at `offset` to `new_name`
+    /// throughout the crate containing `source_file`, or a diagnostic explaining why it
+    /// cannot be renamed. Only the source files opened so far are searched for references.
+    pub fn rename(
+        &self,
+        source_file: SourceFile,
+        offset: AbsoluteOffset,
+        new_name: &str,
+    ) -> Result<Vec<TextEdit>, Diagnostic> {
+        let crate_files = self.crate_source_files(source_file);
+        self.attach(|db| dada_probe::rename(db, source_file, offset, new_name, &crate_files))
+    }
+
+    /// Spans of every reference to the variable, field, or function named at `offset`
+    /// throughout the crate containing `source_file` (optionally including the name
+    /// in its declaration). Only the source files opened so far are searched.
+    pub fn find_references(
+        &self,
+        source_file: SourceFile,
+        offset: AbsoluteOffset,
+        include_declaration: bool,
+    ) -> Vec<AbsoluteSpan> {
+        let crate_files = self.crate_source_files(source_file);
+        self.attach(|db| {
+            let Some(referent) = dada_probe::referent_at(db, source_file, offset) else {
+                return vec![];
+            };
+            let definition = referent.definition(db);
+            let mut spans = dada_probe::references(db, referent, &crate_files);
+            if !include_declaration {
+                spans.retain(|&span| span != definition);
+            }
+            spans
+        })
+    }
+
+    /// The source files opened so far that belong to the same crate as `source_file`.
+    fn crate_source_files(&self, source_file: SourceFile) -> Vec<SourceFile> {
+        let module_path = |source_file: SourceFile| {
+            let url_path = UrlPath::from(source_file.url(self).clone());
+            url_path.is_dada_file().then(|| url_path.make_directory())
+        };
+        let Some(path) = module_path(source_file) else {
+            return vec![source_file];
+        };
+
+        let inputs = self.inputs.lock().unwrap();
+        let Some(crate_dir) = inputs.directories.values().find_map(|source| match source {
+            KrateSource::Url(url) => {
+                let dir = UrlPath::from(url.clone());
+                path.starts_with(&dir).then_some(dir)
+            }
+            KrateSource::Libdada => None,
+        }) else {
+            return vec![source_file];
+        };
+
+        inputs
+            .source_files
+            .values()
+            .copied()
+            .filter(|&file| module_path(file).is_some_and(|path| path.starts_with(&crate_dir)))
+            .collect()
+    }
+
+    /// Removes duplicate diagnostics (e.g., the same error reported by several speculative
+    /// checks) and sorts the rest by location, then by level (errors first) and message,
+    /// so that the result does not depend on the order in which they were reported.
+    fn sorted_and_deduplicated<'d>(
+        &self,
+        mut diagnostics: Vec<&'d Diagnostic>,
+    ) -> Vec<&'d Diagnostic> {
         let mut new = Set::default();
         diagnostics.retain(|&d| new.insert(d));
+        diagnostics.sort_by(|a, b| {
+            a.span
+                .source_file
+                .url(self)
+                .cmp(b.span.source_file.url(self))
+                .then(a.span.start.cmp(&b.span.start))
+                .then(a.span.end.cmp(&b.span.end))
+                .then(b.level.cmp(&a.level))
+                .then_with(|| a.message.cmp(&b.message))
+        });
         diagnostics
     }
 
@@ -212,7 +463,7 @@ impl Compiler {
         let libdada = Krate::new(self, "dada".to_string());
         inputs.directories.insert(libdada, KrateSource::Libdada);
 
-        let root = CompilationRoot::new(self, vec![libdada]);
+        let root = CompilationRoot::new(self, vec![libdada], DEFAULT_MAX_ERRORS);
         inputs.root = Some(root);
         root
     }
@@ -262,6 +513,52 @@ impl Compiler {
         self.inputs.lock().unwrap().source_files.get(url).copied()
     }
 
+    /// Loads the contents of `url`, preferring the contents of a file opened in memory.
+    fn contents(&self, url: &Url) -> Result<String, String> {
+        if let Some(text) = self.inputs.lock().unwrap().overlay.get(url) {
+            return Ok(text.clone());
+        }
+        self.read_file(url)
+    }
+
+    /// True if `url` was opened in memory or exists on the file system.
+    fn exists(&self, url: &Url) -> bool {
+        if self.inputs.lock().unwrap().overlay.contains_key(url) {
+            return true;
+        }
+        match self.resolve_file_name(url) {
+            Some(Ok(url)) => self.vfs.exists(&url),
+            Some(Err(_)) => false,
+            None => self.vfs.exists(url),
+        }
+    }
+
+    /// Reads the file at `url` from the file system.
+    fn read_file(&self, url: &Url) -> Result<String, String> {
+        let read_url = match self.resolve_file_name(url) {
+            Some(Ok(read_url)) => read_url,
+            Some(Err(message)) => return Err(format!("error reading `{url}`: {message}")),
+            None => url.clone(),
+        };
+        self.vfs
+            .contents(&read_url)
+            .map_err(|e| format!("error reading `{url}`: {e}"))
+    }
+
+    /// Finds the entry for `url` in its directory, following the policy described in
+    /// [`file_names`]: returns the URL of the entry to read, an error if there is no
+    /// unambiguous entry, or `None` if the directory cannot be listed.
+    fn resolve_file_name(&self, url: &Url) -> Option<Result<Url, String>> {
+        let url_path = UrlPath::from(url.clone());
+        let name = url_path.final_component()?;
+        let entries = self.vfs.directory_entries(&url_path.parent().url()).ok()?;
+        Some(match file_names::resolve_file_name(name, &entries) {
+            Ok(Some(entry)) => Ok(url_path.with_final_component(entry).url()),
+            Ok(None) => Ok(url.clone()),
+            Err(message) => Err(message),
+        })
+    }
+
     /// Get or create a source-file at a given path.
     fn get_or_create_source_file(&self, url: &Url) -> SourceFile {
         let mut inputs = self.inputs.lock().unwrap();
@@ -270,9 +567,9 @@ impl Compiler {
             return opt_source_file;
         }
 
-        let contents = match self.vfs.contents(url) {
-            Ok(data) => Ok(data),
-            Err(e) => Err(format!("error reading `{url}`: {e}")),
+        let contents = match inputs.overlay.get(url) {
+            Some(text) => Ok(text.clone()),
+            None => self.read_file(url),
         };
 
         let result = SourceFile::new(self, url.clone(), contents);
@@ -393,6 +690,10 @@ struct Inputs {
     source_files: Map<Url, SourceFile>,
     libdada_source_files: Map<String, SourceFile>,
     directories: Map<Krate, KrateSource>,
+
+    /// Contents of the files opened in memory (see [`Compiler::open_source_file`]),
+    /// which take precedence over the file system.
+    overlay: Map<Url, String>,
 }
 
 #[derive(FromImpls, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -447,6 +748,12 @@ fn fn_asts(db: &dyn Db, source_file: SourceFile) -> String {
                 writeln!(output).unwrap();
                 writeln!(output, "{}", fn_asts_fn(db, function)).unwrap();
             }
+            AstItem::ExternFunction(ast_extern) => {
+                let function = ast_extern.function(db);
+                writeln!(output, "## extern fn `{}`", function.name(db).id).unwrap();
+                writeln!(output).unwrap();
+                writeln!(output, "{}", fn_asts_fn(db, function)).unwrap();
+            }
             AstItem::MainFunction(ast_main_function) => {
                 writeln!(output, "## main fn").unwrap();
                 writeln!(output).unwrap();
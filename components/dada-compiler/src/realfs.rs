@@ -43,6 +43,18 @@ impl VirtualFileSystem for RealFs {
         }
     }
 
+    fn directory_entries(&self, url: &Url) -> Fallible<Vec<String>> {
+        let path = Self::validate_scheme(url)?;
+        let mut entries = vec![];
+        for entry in std::fs::read_dir(&path)? {
+            // Names that are not valid UTF-8 cannot be the name of a module.
+            if let Ok(name) = entry?.file_name().into_string() {
+                entries.push(name);
+            }
+        }
+        Ok(entries)
+    }
+
     fn path_url(&self, path: &Path) -> Fallible<Url> {
         let path = if let Some(base_dir) = &self.base_dir {
             base_dir.join(path)
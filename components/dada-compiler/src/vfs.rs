@@ -1,8 +1,11 @@
 use std::path::Path;
 
 use dada_util::Fallible;
+use percent_encoding::percent_decode_str;
 use url::Url;
 
+use crate::file_names;
+
 pub trait VirtualFileSystem: Send + Sync + 'static {
     /// Loads the contents of the given URL (or fail with a useful error).
     fn contents(&self, url: &Url) -> Fallible<String>;
@@ -10,6 +13,9 @@ pub trait VirtualFileSystem: Send + Sync + 'static {
     /// True if the given URL exists.
     fn exists(&self, url: &Url) -> bool;
 
+    /// Lists the names of the entries of the directory at the given URL.
+    fn directory_entries(&self, url: &Url) -> Fallible<Vec<String>>;
+
     /// (Try to) convert a path on the local file system to a URL
     fn path_url(&self, path: &Path) -> Fallible<Url>;
 
@@ -24,12 +30,13 @@ pub(crate) struct UrlPath {
 }
 
 impl From<Url> for UrlPath {
+    /// Splits the path of `url` into components, each decoded and [normalized](`file_names::normalize`).
     fn from(url: Url) -> Self {
         let paths = url
             .path()
             .split("/")
             .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
+            .map(|s| file_names::normalize(&percent_decode_str(s).decode_utf8_lossy()))
             .collect();
 
         Self {
@@ -51,12 +58,41 @@ impl UrlPath {
         self
     }
 
+    /// Removes the final component (if any), even if it is a dada file.
+    pub fn parent(&self) -> Self {
+        let mut parent = self.clone();
+        parent.paths.pop();
+        parent
+    }
+
+    /// The final component, if any.
+    pub fn final_component(&self) -> Option<&str> {
+        self.paths.last().map(|s| &s[..])
+    }
+
+    /// Replaces the final component with `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is empty.
+    pub fn with_final_component(&self, name: &str) -> Self {
+        let mut path = self.parent();
+        assert!(path.paths.len() + 1 == self.paths.len());
+        path.paths.push(name.to_string());
+        path
+    }
+
     /// Append a component.
     pub fn push(&mut self, s: &str) {
         assert!(!self.is_dada_file());
         self.paths.push(s.to_string());
     }
 
+    /// True if `prefix` is this path or one of its ancestors.
+    pub fn starts_with(&self, prefix: &UrlPath) -> bool {
+        self.paths.starts_with(&prefix.paths)
+    }
+
     /// True if final component ends in `.dada`
     pub fn is_dada_file(&self) -> bool {
         let Some(last) = self.paths.last() else {
@@ -135,24 +171,29 @@ impl UrlPath {
     }
 }
 
+/// Converts to a URL whose path is [normalized](`file_names::normalize`),
+/// so that a file has the same URL however its name is encoded.
 pub trait ToUrl {
     fn to_url(&self, vfs: &dyn VirtualFileSystem) -> Fallible<Url>;
 }
 
 impl ToUrl for str {
-    fn to_url(&self, _vfs: &dyn VirtualFileSystem) -> Fallible<Url> {
-        Ok(Url::parse(self)?)
+    fn to_url(&self, vfs: &dyn VirtualFileSystem) -> Fallible<Url> {
+        Url::parse(self)?.to_url(vfs)
     }
 }
 
 impl ToUrl for Url {
     fn to_url(&self, _vfs: &dyn VirtualFileSystem) -> Fallible<Url> {
-        Ok(self.clone())
+        if self.scheme() != "file" {
+            return Ok(self.clone());
+        }
+        Ok(UrlPath::from(self.clone()).url())
     }
 }
 
 impl ToUrl for Path {
     fn to_url(&self, vfs: &dyn VirtualFileSystem) -> Fallible<Url> {
-        vfs.path_url(self)
+        vfs.path_url(self)?.to_url(vfs)
     }
 }
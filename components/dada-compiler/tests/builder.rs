@@ -0,0 +1,72 @@
+//! Checks programs generated by the source builders.
+
+use dada_compiler::builder::ModuleBuilder;
+
+mod common;
+
+/// Checks `module`, returning the messages of the diagnostics reported.
+fn check(module: &ModuleBuilder) -> Vec<String> {
+    let (compiler, source_file) = common::open("builder", module.build());
+    common::messages(&compiler, source_file)
+}
+
+fn point() -> ModuleBuilder {
+    ModuleBuilder::new().class("Point", |c| {
+        c.doc("A point in the plane")
+            .field("x", "u32")
+            .field("y", "u32")
+            .method("sum", |f| {
+                f.self_param(None)
+                    .returns("u32")
+                    .statement("self.x + self.y")
+            })
+    })
+}
+
+#[test]
+fn renders_items() {
+    let module = point().function("main", |f| f.async_().statement("let p = Point(22, 44)"));
+    assert_eq!(
+        module.build(),
+        "\
+## A point in the plane
+class Point(x: u32, y: u32) {
+    fn sum(self) -> u32 {
+        self.x + self.y
+    }
+}
+
+async fn main() {
+    let p = Point(22, 44)
+}
+",
+    );
+}
+
+#[test]
+fn renders_where_clauses() {
+    let module = ModuleBuilder::new().function("is_shared", |f| {
+        f.param("t", "type T").where_clause("T is shared")
+    });
+    assert_eq!(
+        module.build(),
+        "fn is_shared(t: type T)\nwhere\n    T is shared,\n{}\n",
+    );
+}
+
+#[test]
+fn well_typed_module_checks() {
+    let module = point().function("main", |f| {
+        f.async_()
+            .statement("let p = Point(22, 44)")
+            .statement("let s: u32 = p.sum()")
+            .statement("print(s).await")
+    });
+    assert_eq!(check(&module), Vec::<String>::new());
+}
+
+#[test]
+fn ill_typed_module_reports_errors() {
+    let module = point().function("main", |f| f.statement("let s: u32 = Point(22, 44)"));
+    assert!(!check(&module).is_empty());
+}
@@ -0,0 +1,40 @@
+//! Generating wasm is a deterministic function of the source,
+//! even when it instantiates generic functions from several places.
+
+use dada_compiler::CodegenOptions;
+
+mod common;
+
+const SOURCE: &str = "\
+fn main() {
+    let a = \"hello\"
+    let x = identity(a.ref)
+    let y = identity(22)
+    let z = identity(identity(true))
+}
+
+fn identity[type T](value: T) -> T {
+    value.give
+}
+";
+
+/// Generates the wasm for the `main` function of [`SOURCE`] in a fresh compiler.
+fn codegen(options: CodegenOptions) -> Vec<u8> {
+    let (compiler, source_file) = common::open("codegen_determinism", SOURCE);
+    compiler
+        .codegen_main_fn(source_file, options)
+        .clone()
+        .unwrap()
+}
+
+#[test]
+fn same_bytes_from_separate_compilers() {
+    let options = CodegenOptions::default().with_report_instantiations(true);
+    assert_eq!(codegen(options), codegen(options));
+}
+
+#[test]
+fn check_determinism_accepts_deterministic_codegen() {
+    let options = CodegenOptions::default().with_check_determinism(true);
+    assert_eq!(codegen(options), codegen(CodegenOptions::default()));
+}
@@ -0,0 +1,73 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate includes this module but uses only some of its helpers.
+#![allow(dead_code)]
+
+use dada_compiler::{Compiler, RealFs};
+use dada_ir_ast::{
+    Db,
+    ast::{AstItem, AstMember},
+    diagnostic::Diagnostic,
+    inputs::SourceFile,
+};
+use dada_parser::prelude::{FunctionBlock, SourceFileParse};
+
+/// Creates a compiler with `text` open as `file:///{name}/input.dada`.
+pub fn open(name: &str, text: impl Into<String>) -> (Compiler, SourceFile) {
+    let mut compiler = Compiler::new(RealFs::default(), None);
+    let source_file = compiler
+        .open_source_file(
+            format!("file:///{name}/input.dada").as_str(),
+            Ok(text.into()),
+        )
+        .unwrap();
+    (compiler, source_file)
+}
+
+/// Checks `source_file`, returning the messages of the diagnostics reported.
+pub fn messages(compiler: &Compiler, source_file: SourceFile) -> Vec<String> {
+    compiler
+        .check_all(source_file)
+        .iter()
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect()
+}
+
+/// Parses everything in `source_file`, including the function bodies that are
+/// normally only parsed on demand, so that all of its syntax errors are reported.
+#[salsa::tracked]
+fn parse_all(db: &dyn Db, source_file: SourceFile) {
+    let module = source_file.parse(db);
+
+    for item in module.items(db) {
+        match *item {
+            AstItem::SourceFile(_) | AstItem::Use(_) | AstItem::MainFunction(_) => (),
+            AstItem::Aggregate(class_item) => {
+                for member in class_item.members(db) {
+                    match member {
+                        AstMember::Field(_) => (),
+                        AstMember::Function(function) => {
+                            function.body_block(db);
+                        }
+                    }
+                }
+            }
+            AstItem::Function(function) => {
+                function.body_block(db);
+            }
+            AstItem::ExternFunction(ast_extern) => {
+                ast_extern.function(db).body_block(db);
+            }
+        }
+    }
+}
+
+/// The syntax errors in `source_file`, in source order.
+pub fn syntax_errors(compiler: &Compiler, source_file: SourceFile) -> Vec<&Diagnostic> {
+    let mut diagnostics = parse_all::accumulated::<Diagnostic>(compiler, source_file);
+    diagnostics.sort_by(|a, b| {
+        (a.span.start, a.span.end, &a.message).cmp(&(b.span.start, b.span.end, &b.message))
+    });
+    diagnostics.dedup();
+    diagnostics
+}
@@ -0,0 +1,48 @@
+//! Regression tests for deeply nested source.
+//!
+//! The parser and the type checker recurse once per level of nesting, so these
+//! programs overflowed the stack before recursion was guarded with
+//! `dada_util::ensure_sufficient_stack`.
+
+mod common;
+
+/// Deep enough to overflow the default 2MiB test thread stack without the guards.
+const DEPTH: usize = 2_000;
+
+/// Returns `open` and `close` repeated [`DEPTH`] times around `inner`.
+fn nested(open: &str, inner: &str, close: &str) -> String {
+    format!("{}{inner}{}", open.repeat(DEPTH), close.repeat(DEPTH))
+}
+
+/// Parses and checks `main`, whose body is `body`, asserting that no diagnostics are reported.
+fn check_main(body: String) {
+    let (compiler, source_file) = common::open(
+        "deep_nesting",
+        format!("fn main() {{\n    let x = {body}\n}}\n"),
+    );
+    let diagnostics = compiler.check_all(source_file);
+    assert!(
+        diagnostics.is_empty(),
+        "unexpected diagnostics: {diagnostics:#?}"
+    );
+}
+
+#[test]
+fn nested_parentheses() {
+    check_main(nested("(", "22", ")"));
+}
+
+#[test]
+fn nested_binary_operators() {
+    check_main(nested("1 + (", "1", ")"));
+}
+
+#[test]
+fn nested_blocks() {
+    check_main(nested("{ ", "true", " }"));
+}
+
+#[test]
+fn nested_unary_operators() {
+    check_main(nested("!", "true", ""));
+}
@@ -0,0 +1,40 @@
+//! Diagnostics are returned without duplicates and in source order,
+//! however the checks that report them are scheduled.
+
+mod common;
+
+const SOURCE: &str = "\
+fn second() {
+    let x: u32 = \"one\"
+    let y: u32 = \"two\"
+}
+
+fn first() {
+    let z: u32 = \"three\"
+}
+";
+
+/// Checks [`SOURCE`], returning the start offset and message of each diagnostic reported.
+fn diagnostics() -> Vec<(usize, String)> {
+    let (compiler, source_file) = common::open("diagnostic_order", SOURCE);
+    compiler
+        .check_all(source_file)
+        .iter()
+        .map(|diagnostic| (diagnostic.span.start.as_usize(), diagnostic.message.clone()))
+        .collect()
+}
+
+#[test]
+fn sorted_by_location() {
+    let diagnostics = diagnostics();
+    assert_eq!(diagnostics.len(), 3, "{diagnostics:#?}");
+    assert!(
+        diagnostics.is_sorted_by_key(|(start, _)| *start),
+        "{diagnostics:#?}"
+    );
+}
+
+#[test]
+fn deterministic() {
+    assert_eq!(diagnostics(), diagnostics());
+}
@@ -0,0 +1,128 @@
+//! Dumping the IR of a function, in particular the generated wasm as WAT.
+
+use dada_compiler::{CodegenOptions, IrStage};
+
+mod common;
+
+const SOURCE: &str = "\
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn identity[type T](x: T) -> T {
+    x
+}
+
+fn chains(x: my String, y: my String, a: our our String, b: mut[x] ref[y] String, c: ref[x] mut[y] String) {
+}
+
+class Cell[type T](value: T)
+
+fn cells(c: my Cell[my String]) -> my Cell[my String] {
+    c.give
+}
+
+fn twice(c: my Cell[my String]) -> my Cell[my String] {
+    let d: ref[c] Cell[my String] = c.ref
+    let e: ref[c] Cell[my String] = c.ref
+    c.give
+}
+";
+
+/// Dumps the given `stages` of the function at `function_path` in [`SOURCE`].
+fn dump(function_path: &str, stages: &[IrStage]) -> String {
+    let (compiler, source_file) = common::open("dump", SOURCE);
+    compiler
+        .dump_ir(
+            source_file,
+            function_path,
+            stages,
+            CodegenOptions::default(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn wat() {
+    let wat = dump("add", &[IrStage::Wat]);
+    assert!(wat.starts_with("# wat\n\n(module"), "{wat}");
+
+    // The shadow stack pointer comes before the declared parameters.
+    assert!(wat.contains("(param i32 i32 i32) (result i32)"), "{wat}");
+    assert!(wat.contains("i32.add"), "{wat}");
+}
+
+#[test]
+fn wat_of_generic_function() {
+    assert_eq!(
+        dump("identity", &[IrStage::Wat]),
+        "# wat\n\n<generic functions cannot be compiled on their own>\n"
+    );
+}
+
+#[test]
+fn stages_in_order() {
+    let output = dump("add", &IrStage::ALL);
+    let headers: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("# "))
+        .collect();
+    assert_eq!(headers, IrStage::ALL.map(IrStage::name), "{output}");
+}
+
+#[test]
+fn deterministic() {
+    assert_eq!(dump("add", &IrStage::ALL), dump("add", &IrStage::ALL));
+}
+
+/// The permission chains of the input `name` in the red dump of `chains`.
+fn red_chains(name: &str) -> String {
+    let red = dump("chains", &[IrStage::Red]);
+    let line = red
+        .lines()
+        .find(|line| line.starts_with(&format!("{name}: ")))
+        .unwrap_or_else(|| panic!("no line for `{name}` in {red}"));
+    let (_, chains) = line.split_once(" @ ").unwrap();
+    chains.to_string()
+}
+
+/// Links before a copy link (`our` or `ref`) are dropped, and other links are kept.
+/// The red dump computes no liveness, so it does not show that a live `mut` link is dropped too.
+#[test]
+fn red_chains_are_normalized() {
+    assert_eq!(red_chains("x"), "my");
+    assert_eq!(red_chains("a"), "our");
+    assert_eq!(red_chains("b"), "ref[y]");
+    assert_eq!(red_chains("c"), "ref[x] mut[y]");
+}
+
+/// The two counters on the line starting with `label` in the stats dump of `function_path`,
+/// e.g. `(3, 1)` for `alternatives: started 3, pruned 1`.
+fn counters(function_path: &str, label: &str) -> (u64, u64) {
+    let stats = dump(function_path, &[IrStage::Stats]);
+    let line = stats
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{label}: ")))
+        .unwrap_or_else(|| panic!("no `{label}` in {stats}"));
+    let (first, second) = line.split_once(", ").unwrap();
+    let count = |counter: &str| counter.rsplit_once(' ').unwrap().1.parse().unwrap();
+    (count(first), count(second))
+}
+
+/// Returning `c` compares `my Cell[my String]` with itself, which checks whether
+/// `my` is shared or owned (so that `Cell` can be covariant): two alternatives.
+#[test]
+fn stats_count_alternatives() {
+    let (started, pruned) = counters("cells", "alternatives");
+    assert!(started >= 2, "started {started}");
+    assert!(pruned <= started, "started {started}, pruned {pruned}");
+}
+
+/// The second `ref[c] Cell[my String]` compares the same lien chains as the first,
+/// under the same (empty) assumptions, so it is found in the sub-chain cache.
+#[test]
+fn stats_count_sub_chain_cache_hits() {
+    let (hits, misses) = counters("twice", "sub_chain cache");
+    assert!(hits >= 1, "hits {hits}, misses {misses}");
+    assert!(misses >= 1, "hits {hits}, misses {misses}");
+}
@@ -0,0 +1,56 @@
+//! Module resolution is case-sensitive on every file system.
+
+use std::path::PathBuf;
+
+use dada_compiler::{Compiler, RealFs};
+
+mod common;
+
+/// Creates a fresh directory containing `files`, given as (relative path, contents) pairs.
+fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dada-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+/// Loads and checks `path`, returning the messages of the diagnostics reported.
+fn check(path: PathBuf) -> Vec<String> {
+    let mut compiler = Compiler::new(RealFs::default(), None);
+    let source_file = compiler.load_source_file(path.as_path()).unwrap();
+    common::messages(&compiler, source_file)
+}
+
+#[test]
+fn module_resolves_to_file_with_same_case() {
+    let dir = directory(
+        "same-case",
+        &[
+            ("app.dada", "use app.shapes.Point\n"),
+            ("app/shapes.dada", "pub class Point(x: u32, y: u32)\n"),
+        ],
+    );
+    assert_eq!(check(dir.join("app.dada")), Vec::<String>::new());
+}
+
+#[test]
+fn module_does_not_resolve_to_file_differing_in_case() {
+    let dir = directory(
+        "differing-case",
+        &[
+            ("app.dada", "use app.shapes.Point\n"),
+            ("app/Shapes.dada", "pub class Point(x: u32, y: u32)\n"),
+        ],
+    );
+    let messages = check(dir.join("app.dada"));
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.contains("found `Shapes.dada`, whose name differs only by case")),
+        "{messages:#?}"
+    );
+}
@@ -0,0 +1,414 @@
+//! Property-based fuzzing of the parser.
+//!
+//! Inputs come from three generators of increasing structure: arbitrary text,
+//! random sequences of Dada tokens, and randomly generated programs that follow
+//! the grammar, optionally damaged by deleting or truncating tokens.
+//! Whatever the input, parsing every item must terminate without panicking and
+//! report problems as diagnostics that point into the source, and tokenizing the
+//! source chunk by chunk must give the same tokens as tokenizing it as a whole.
+//!
+//! Failing inputs are shrunk and recorded by proptest, so a failure report includes
+//! a minimal program reproducing it. Use `PROPTEST_CASES` to run more cases.
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use dada_ir_ast::Db;
+use proptest::prelude::*;
+
+mod common;
+
+/// How long to wait for a parse before concluding that the parser is stuck.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Set in the environment of the child process that [`check_parse`] runs [`parse_stdin`] in.
+const CHILD_VAR: &str = "DADA_FUZZ_CHILD";
+
+/// Parses `text` in its entirety, panicking if the parser panics, does not terminate,
+/// or reports a diagnostic whose span lies outside of `text`.
+///
+/// The parse runs in a child process, so that it can be killed if it does not terminate.
+fn check_parse(text: String) {
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args(["parse_stdin", "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_VAR, "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(text.as_bytes())
+        .unwrap();
+
+    let start = Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if start.elapsed() > TIMEOUT {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            panic!("parser did not terminate within {TIMEOUT:?} on {text:?}")
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "parser panicked on {text:?}:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    for line in stdout.lines() {
+        let Some(span) = line.strip_prefix("span ") else {
+            continue;
+        };
+        let (start, end) = span.split_once(' ').unwrap();
+        let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+        assert!(
+            start <= end && end <= text.len(),
+            "diagnostic span {start}..{end} is out of bounds for {text:?}"
+        );
+    }
+}
+
+/// The child process of [`check_parse`]: parses the text on standard input
+/// and prints the span of each diagnostic. Does nothing when run as an ordinary test.
+#[test]
+fn parse_stdin() {
+    if std::env::var_os(CHILD_VAR).is_none() {
+        return;
+    }
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text).unwrap();
+    let (compiler, source_file) = common::open("fuzz", text);
+    for diagnostic in common::syntax_errors(&compiler, source_file) {
+        let spans = std::iter::once(diagnostic.span)
+            .chain(diagnostic.labels.iter().map(|label| label.span));
+        for span in spans {
+            println!("span {} {}", span.start.as_usize(), span.end.as_usize());
+        }
+    }
+}
+
+/// Panics if tokenizing `text` chunk by chunk gives different tokens than tokenizing it
+/// as a whole (see [`dada_parser::check_chunked_tokens`]).
+fn check_chunked_tokens(text: String) {
+    let (compiler, source_file) = common::open("fuzz", text.as_str());
+    let db: &dyn Db = &compiler;
+    if let Err(difference) = dada_parser::check_chunked_tokens(db, source_file) {
+        panic!("{difference} in {text:?}");
+    }
+}
+
+/// The `.dada` files under `dir`, recursively.
+fn dada_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(dada_files(&path));
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "dada")
+        {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Every test program of the repository tokenizes the same chunk by chunk as it does whole.
+#[test]
+fn chunked_tokens_of_test_programs() {
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests");
+    let files = dada_files(&tests);
+    assert!(!files.is_empty(), "no test programs in {}", tests.display());
+    for path in files {
+        let text = std::fs::read_to_string(&path).unwrap();
+        check_chunked_tokens(text);
+    }
+}
+
+/// Tokens from which [`token_soup`] builds its inputs, covering every kind of token
+/// the tokenizer produces, including unbalanced delimiters and unterminated literals.
+const TOKENS: &[&str] = &[
+    // keywords
+    "as",
+    "assert",
+    "async",
+    "atomic",
+    "await",
+    "class",
+    "else",
+    "export",
+    "extern",
+    "false",
+    "fn",
+    "give",
+    "if",
+    "let",
+    "match",
+    "mut",
+    "my",
+    "our",
+    "pub",
+    "ref",
+    "return",
+    "self",
+    "share",
+    "spawn",
+    "struct",
+    "true",
+    "type",
+    "use",
+    "where",
+    // identifiers and literals
+    "x",
+    "y",
+    "Point",
+    "u32",
+    "String",
+    "0",
+    "22",
+    "3.5",
+    "\"hi\"",
+    "\"",
+    "\"unterminated",
+    "'a'",
+    "'{'",
+    "'}'",
+    "'\"'",
+    "'#'",
+    "'\\''",
+    "'\\n'",
+    "'\\",
+    "'outer",
+    // operators and punctuation
+    "+",
+    "-",
+    "*",
+    "/",
+    "=",
+    "==",
+    "<",
+    ">",
+    "<=",
+    ">=",
+    "!",
+    "&&",
+    "||",
+    ".",
+    ",",
+    ":",
+    "->",
+    "#[",
+    "@",
+    // delimiters
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    // whitespace and comments
+    "\n",
+    "# comment\n",
+    "## doc comment\n",
+];
+
+/// Random sequences of tokens, with no regard for the grammar.
+fn token_soup() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(TOKENS), 0..64).prop_map(|tokens| tokens.join(" "))
+}
+
+/// Lines of [`token_soup`], so that tokens like `fn` often begin a line and start a chunk.
+fn token_lines() -> impl Strategy<Value = String> {
+    prop::collection::vec(token_soup(), 0..8).prop_map(|lines| lines.join("\n"))
+}
+
+fn identifier() -> impl Strategy<Value = String> {
+    prop::sample::select(&["x", "y", "z", "point", "value"][..]).prop_map(str::to_string)
+}
+
+fn ty() -> impl Strategy<Value = String> {
+    let leaf = prop::sample::select(&["u32", "i64", "bool", "String", "Point"][..])
+        .prop_map(str::to_string);
+    leaf.prop_recursive(3, 8, 2, |inner| {
+        prop_oneof![
+            (
+                prop::sample::select(&["my", "our", "ref", "mut"][..]),
+                inner.clone()
+            )
+                .prop_map(|(perm, ty)| format!("{perm} {ty}")),
+            (inner.clone(), inner).prop_map(|(a, b)| format!("Pair[{a}, {b}]")),
+        ]
+    })
+}
+
+fn expr() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        identifier(),
+        (0u32..1000).prop_map(|n| n.to_string()),
+        Just("true".to_string()),
+        Just("\"text\"".to_string()),
+    ];
+    leaf.prop_recursive(4, 32, 3, |inner| {
+        prop_oneof![
+            (
+                inner.clone(),
+                prop::sample::select(&["+", "-", "*", "/", "==", "<", ">=", "&&", "||"][..]),
+                inner.clone(),
+            )
+                .prop_map(|(lhs, op, rhs)| format!("{lhs} {op} {rhs}")),
+            inner.clone().prop_map(|e| format!("({e})")),
+            inner.clone().prop_map(|e| format!("!{e}")),
+            (inner.clone(), identifier()).prop_map(|(e, field)| format!("{e}.{field}")),
+            inner.clone().prop_map(|e| format!("{e}.give")),
+            inner.clone().prop_map(|e| format!("{e}.await")),
+            (identifier(), prop::collection::vec(inner.clone(), 0..3))
+                .prop_map(|(f, args)| format!("{f}({})", args.join(", "))),
+            (inner.clone(), inner.clone(), inner)
+                .prop_map(|(c, t, e)| format!("if {c} {{ {t} }} else {{ {e} }}")),
+        ]
+    })
+}
+
+fn statement() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (identifier(), expr()).prop_map(|(x, e)| format!("let {x} = {e}")),
+        (identifier(), ty(), expr()).prop_map(|(x, t, e)| format!("let {x}: {t} = {e}")),
+        (identifier(), expr()).prop_map(|(x, e)| format!("{x} = {e}")),
+        expr().prop_map(|e| format!("assert {e}")),
+        expr().prop_map(|e| format!("return {e}")),
+        expr(),
+    ]
+}
+
+/// Functions, or methods (taking `self`) if `is_method` is set.
+fn function(is_method: bool) -> impl Strategy<Value = String> {
+    (
+        any::<bool>(),
+        identifier(),
+        prop::collection::vec((identifier(), ty()), 0..3),
+        prop::option::of(ty()),
+        prop::collection::vec(statement(), 0..6),
+    )
+        .prop_map(|(is_async, name, params, output, body)| {
+            let params = is_method
+                .then(|| "self".to_string())
+                .into_iter()
+                .chain(params.iter().map(|(x, t)| format!("{x}: {t}")))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let output = output.map(|t| format!(" -> {t}")).unwrap_or_default();
+            let is_async = if is_async { "async " } else { "" };
+            format!(
+                "{is_async}fn {name}({params}){output} {{\n    {}\n}}",
+                body.join("\n    ")
+            )
+        })
+}
+
+fn class() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(&["class", "struct"][..]),
+        prop::collection::vec((identifier(), ty()), 0..3),
+        prop::collection::vec(function(true), 0..2),
+    )
+        .prop_map(|(keyword, fields, methods)| {
+            let fields = fields
+                .iter()
+                .map(|(x, t)| format!("    {x}: {t}\n"))
+                .collect::<String>();
+            let methods = methods.join("\n\n");
+            format!("{keyword} Point {{\n{fields}\n{methods}\n}}")
+        })
+}
+
+/// Programs that follow the grammar, more or less.
+fn program() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop_oneof![function(false), class()], 0..4)
+        .prop_map(|items| items.join("\n\n"))
+}
+
+/// Ways to damage a program so that it exercises the parser's error recovery.
+#[derive(Clone, Debug)]
+enum Damage {
+    None,
+
+    /// Keep only the first `n` bytes (rounded down to a character boundary).
+    Truncate(usize),
+
+    /// Delete the `n`th whitespace-separated word.
+    DeleteWord(usize),
+}
+
+fn damaged_program() -> impl Strategy<Value = String> {
+    let damage = prop_oneof![
+        Just(Damage::None),
+        any::<usize>().prop_map(Damage::Truncate),
+        any::<usize>().prop_map(Damage::DeleteWord),
+    ];
+    (program(), damage).prop_map(|(text, damage)| match damage {
+        Damage::None => text,
+        Damage::Truncate(n) => {
+            let mut n = n % (text.len() + 1);
+            while !text.is_char_boundary(n) {
+                n -= 1;
+            }
+            text[..n].to_string()
+        }
+        Damage::DeleteWord(n) => {
+            let words = text.split(' ').collect::<Vec<_>>();
+            let n = n % words.len();
+            words
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != n)
+                .map(|(_, word)| *word)
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    })
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_text(text in "\\PC*") {
+        check_parse(text);
+    }
+
+    #[test]
+    fn arbitrary_tokens(text in token_soup()) {
+        check_parse(text);
+    }
+
+    #[test]
+    fn generated_programs(text in damaged_program()) {
+        check_parse(text);
+    }
+
+    #[test]
+    fn chunked_tokens_of_arbitrary_text(text in "\\PC*") {
+        check_chunked_tokens(text);
+    }
+
+    #[test]
+    fn chunked_tokens_of_arbitrary_tokens(text in token_lines()) {
+        check_chunked_tokens(text);
+    }
+
+    #[test]
+    fn chunked_tokens_of_generated_programs(text in damaged_program()) {
+        check_chunked_tokens(text);
+    }
+}
@@ -0,0 +1,33 @@
+//! Checking a function stops once it has reported too many errors.
+//!
+//! The limit itself is tested by `tests/type_check/max_errors`;
+//! this checks that changing it invalidates earlier results.
+
+mod common;
+
+const SOURCE: &str = "\
+fn main() {
+    let x: u32 = \"one\"
+    let y: u32 = \"two\"
+    let z: u32 = \"three\"
+}
+";
+
+/// The limit is an input of the compilation, so changing it after checking takes effect.
+#[test]
+fn changed_after_checking() {
+    let (mut compiler, source_file) = common::open("max_errors", SOURCE);
+    let messages = common::messages(&compiler, source_file);
+    assert!(
+        !messages.iter().any(|m| m == "too many errors"),
+        "{messages:#?}"
+    );
+
+    compiler.set_max_errors(2);
+    let messages = common::messages(&compiler, source_file);
+    assert_eq!(
+        messages.iter().filter(|m| *m == "too many errors").count(),
+        1,
+        "{messages:#?}"
+    );
+}
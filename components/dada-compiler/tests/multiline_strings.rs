@@ -0,0 +1,58 @@
+//! Inconsistent indentation in multiline strings.
+//!
+//! These are not `.dada` tests because the expected diagnostics point into the string,
+//! where a `#!` annotation would be part of its contents.
+
+mod common;
+
+/// Parses `text`, returning the message and source text of each diagnostic reported.
+fn diagnostics(text: &str) -> Vec<(String, String)> {
+    let (compiler, source_file) = common::open("multiline_strings", text);
+    common::syntax_errors(&compiler, source_file)
+        .iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span;
+            (
+                diagnostic.message.clone(),
+                text[span.start.as_usize()..span.end.as_usize()].to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn consistent_indentation() {
+    let text = "fn main() {\n    let s = \"\n        a\n          b\n        \"\n}\n";
+    assert_eq!(diagnostics(text), vec![]);
+}
+
+#[test]
+fn tabs_and_spaces() {
+    let text = "fn main() {\n    let s = \"\n        a\n\t\t\t\t\t\t\t\t\tb\n        \"\n}\n";
+    assert_eq!(
+        diagnostics(text),
+        vec![(
+            "inconsistent indentation in multiline string".to_string(),
+            "\t\t\t\t\t\t\t\t\t".to_string()
+        )],
+    );
+}
+
+#[test]
+fn reference_is_least_indented_line() {
+    // The common indentation is the tab, which the spaces on the first line do not begin with.
+    let text = "let s = \"\"\"\n    a\n\tb\n\t\tc\n\"\"\"\n";
+    assert_eq!(
+        diagnostics(text),
+        vec![(
+            "inconsistent indentation in multiline string".to_string(),
+            "    ".to_string()
+        )],
+    );
+}
+
+#[test]
+fn blank_lines_are_never_inconsistent() {
+    let text = "let s = \"\n    a\n\t\n    b\n\"\n";
+    assert_eq!(diagnostics(text), vec![]);
+}
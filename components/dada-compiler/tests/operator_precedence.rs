@@ -0,0 +1,203 @@
+//! Binary operator precedence and associativity.
+//!
+//! Random sequences of operands and binary operators are parsed both by the parser
+//! and by a reference parser with one function per grammar rule in the spec,
+//! and the two must agree on how the operators group.
+
+use dada_ir_ast::{
+    Db,
+    ast::{AstExpr, AstExprKind, AstItem, AstStatement},
+};
+use dada_parser::prelude::{FunctionBlock, SourceFileParse};
+use proptest::prelude::*;
+
+mod common;
+
+const OPERATORS: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "||", "&&", "==", "<", ">", "<=", ">=", "+", "-", "*", "/",
+];
+
+const ASSIGNMENTS: &[&str] = &["=", "+=", "-=", "*=", "/="];
+
+const COMPARISONS: &[&str] = &["==", "<", ">", "<=", ">="];
+
+/// The name of the `index`th operand.
+fn operand(index: usize) -> String {
+    format!("x{index}")
+}
+
+/// The text `x0 op0 x1 op1 x2 ...`.
+fn source(ops: &[&str]) -> String {
+    let mut text = operand(0);
+    for (index, op) in ops.iter().enumerate() {
+        text.push_str(&format!(" {op} {}", operand(index + 1)));
+    }
+    text
+}
+
+/// A recursive-descent parser for [`source`]`(ops)`, with one method per grammar rule.
+/// Each method returns the expression it parsed with every operator parenthesized.
+struct Reference<'a> {
+    ops: &'a [&'static str],
+
+    /// Index of the next operator; also the index of the next operand.
+    next: usize,
+}
+
+impl Reference<'_> {
+    /// Parses `ops`, returning `None` if the comparisons in it are chained.
+    fn parse(ops: &[&'static str]) -> Option<String> {
+        let mut parser = Reference { ops, next: 0 };
+        let expr = parser.assign_expr();
+
+        // A comparison following another leaves operators unparsed.
+        if parser.next < ops.len() {
+            return None;
+        }
+        Some(expr)
+    }
+
+    fn eat(&mut self, ops: &[&str]) -> Option<&'static str> {
+        let op = *self.ops.get(self.next)?;
+        if !ops.contains(&op) {
+            return None;
+        }
+        self.next += 1;
+        Some(op)
+    }
+
+    /// `OrExpr (AssignOp AssignExpr)?`
+    fn assign_expr(&mut self) -> String {
+        let lhs = self.or_expr();
+        match self.eat(ASSIGNMENTS) {
+            Some(op) => format!("({lhs} {op} {})", self.assign_expr()),
+            None => lhs,
+        }
+    }
+
+    /// `AndExpr (|| AndExpr)*`
+    fn or_expr(&mut self) -> String {
+        self.left_associative(&["||"], Self::and_expr)
+    }
+
+    /// `CompareExpr (&& CompareExpr)*`
+    fn and_expr(&mut self) -> String {
+        self.left_associative(&["&&"], Self::compare_expr)
+    }
+
+    /// `AddExpr (CompareOp AddExpr)?`
+    fn compare_expr(&mut self) -> String {
+        let lhs = self.add_expr();
+        match self.eat(COMPARISONS) {
+            Some(op) => format!("({lhs} {op} {})", self.add_expr()),
+            None => lhs,
+        }
+    }
+
+    /// `MulExpr ((+ | -) MulExpr)*`
+    fn add_expr(&mut self) -> String {
+        self.left_associative(&["+", "-"], Self::mul_expr)
+    }
+
+    /// `Operand ((* | /) Operand)*`
+    fn mul_expr(&mut self) -> String {
+        self.left_associative(&["*", "/"], |parser| operand(parser.next))
+    }
+
+    fn left_associative(&mut self, ops: &[&str], operand: fn(&mut Self) -> String) -> String {
+        let mut lhs = operand(self);
+        while let Some(op) = self.eat(ops) {
+            let rhs = operand(self);
+            lhs = format!("({lhs} {op} {rhs})");
+        }
+        lhs
+    }
+}
+
+/// Renders `expr` in the same form as [`Reference`].
+fn render<'db>(db: &'db dyn Db, expr: &AstExpr<'db>) -> String {
+    match &*expr.kind {
+        AstExprKind::Id(id) => id.id.text(db).clone(),
+        AstExprKind::BinaryOp(op, lhs, rhs) => {
+            format!("({} {} {})", render(db, lhs), op.op, render(db, rhs))
+        }
+        kind => panic!("unexpected expression: {kind:?}"),
+    }
+}
+
+/// Parses `text` as the body of a function, returning the messages of the diagnostics
+/// reported and the rendered expression.
+fn parse(text: &str) -> (Vec<String>, String) {
+    let (compiler, source_file) = common::open(
+        "operator_precedence",
+        format!("fn main() {{\n    {text}\n}}\n"),
+    );
+    let messages = common::syntax_errors(&compiler, source_file)
+        .iter()
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect();
+
+    let db: &dyn Db = &compiler;
+    let body = source_file
+        .parse(db)
+        .items(db)
+        .iter()
+        .find_map(|item| match item {
+            AstItem::Function(f) => f.body_block(db),
+            _ => None,
+        })
+        .unwrap();
+    let AstStatement::Expr(expr) = &body.statements(db)[0] else {
+        panic!("expected an expression statement")
+    };
+    (messages, render(db, expr))
+}
+
+/// Checks that the parser groups `ops` like the reference parser
+/// or, if they chain comparisons, reports it.
+fn check(ops: &[&'static str]) {
+    let text = source(ops);
+    let (messages, expr) = parse(&text);
+    match Reference::parse(ops) {
+        Some(expected) => {
+            assert_eq!(messages, Vec::<String>::new(), "{text}");
+            assert_eq!(expr, expected, "{text}");
+        }
+        None => {
+            // Each chain is reported once, and there may be more than one.
+            assert!(!messages.is_empty(), "{text}");
+            assert!(
+                messages
+                    .iter()
+                    .all(|m| m == "comparison operators cannot be chained"),
+                "{text}: {messages:#?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn standard_precedence() {
+    check(&["+", "*"]);
+    check(&["||", "&&"]);
+    check(&["=", "+"]);
+    check(&["+", "<", "*"]);
+}
+
+#[test]
+fn associativity() {
+    check(&["-", "-"]);
+    check(&["/", "*"]);
+    check(&["=", "="]);
+    check(&["+=", "*="]);
+    check(&["<", "<"]);
+}
+
+proptest! {
+    #[test]
+    fn agrees_with_reference(
+        ops in prop::collection::vec(prop::sample::select(OPERATORS), 0..8),
+    ) {
+        check(&ops);
+    }
+}
@@ -0,0 +1,67 @@
+//! Source files opened in memory, which do not exist on the file system.
+
+use dada_compiler::{Compiler, RealFs};
+
+mod common;
+
+const APP: &str = "file:///virtual/app.dada";
+const SHAPES: &str = "file:///virtual/app/shapes.dada";
+
+const APP_SOURCE: &str = "\
+use app.shapes.Point
+
+fn main() {
+    let p = Point(1, 2)
+}
+";
+
+const SHAPES_SOURCE: &str = "pub class Point(x: u32, y: u32)\n";
+
+/// Checks the file at `url`, returning the messages of the diagnostics reported.
+fn messages(compiler: &mut Compiler, url: &str) -> Vec<String> {
+    let source_file = compiler.get_previously_opened_source_file(url).unwrap();
+    common::messages(compiler, source_file)
+}
+
+#[test]
+fn submodule_opened_before_it_is_used() {
+    let mut compiler = Compiler::new(RealFs::default(), None);
+    compiler
+        .open_source_file(SHAPES, Ok(SHAPES_SOURCE.to_string()))
+        .unwrap();
+    compiler
+        .open_source_file(APP, Ok(APP_SOURCE.to_string()))
+        .unwrap();
+    assert_eq!(messages(&mut compiler, APP), Vec::<String>::new());
+}
+
+#[test]
+fn submodule_opened_after_its_crate() {
+    let mut compiler = Compiler::new(RealFs::default(), None);
+    compiler
+        .open_source_file(APP, Ok(APP_SOURCE.to_string()))
+        .unwrap();
+
+    // `app.dada` is only in memory, but it is still found as the root of the crate.
+    compiler
+        .open_source_file(SHAPES, Ok(SHAPES_SOURCE.to_string()))
+        .unwrap();
+    assert_eq!(messages(&mut compiler, APP), Vec::<String>::new());
+    assert_eq!(messages(&mut compiler, SHAPES), Vec::<String>::new());
+}
+
+#[test]
+fn closing_reverts_to_the_file_system() {
+    let mut compiler = Compiler::new(RealFs::default(), None);
+    compiler
+        .open_source_file(APP, Ok(APP_SOURCE.to_string()))
+        .unwrap();
+    compiler
+        .open_source_file(SHAPES, Ok(SHAPES_SOURCE.to_string()))
+        .unwrap();
+    assert_eq!(messages(&mut compiler, APP), Vec::<String>::new());
+
+    // `shapes.dada` does not exist on disk, so `Point` can no longer be found.
+    compiler.close_source_file(SHAPES).unwrap();
+    assert!(!messages(&mut compiler, APP).is_empty());
+}
@@ -0,0 +1,141 @@
+//! Tests for the syntax tree visitor and folder.
+
+use dada_ir_ast::{
+    Db,
+    ast::{AstBlock, AstExpr, AstExprKind, AstItem, AstStatement, Identifier, SpannedIdentifier},
+    inputs::SourceFile,
+};
+use dada_parser::{
+    prelude::{FunctionBlock, SourceFileParse},
+    visit::{AstFolder, AstVisitor, super_fold_expr, walk_expr},
+};
+
+mod common;
+
+/// Collects the identifiers that appear as expressions, in the order visited.
+struct Ids<'db> {
+    db: &'db dyn Db,
+    ids: Vec<String>,
+}
+
+impl<'db> AstVisitor<'db> for Ids<'db> {
+    fn db(&self) -> &'db dyn Db {
+        self.db
+    }
+
+    fn visit_expr(&mut self, expr: &AstExpr<'db>) {
+        if let AstExprKind::Id(id) = &*expr.kind {
+            self.ids.push(id.id.text(self.db).clone());
+        }
+        walk_expr(self, expr)
+    }
+}
+
+/// Renames every use of the variable `from` to `to`.
+struct Rename<'db> {
+    db: &'db dyn Db,
+    from: Identifier<'db>,
+    to: Identifier<'db>,
+}
+
+impl<'db> AstFolder<'db> for Rename<'db> {
+    fn db(&self) -> &'db dyn Db {
+        self.db
+    }
+
+    fn fold_expr(&mut self, expr: AstExpr<'db>) -> AstExpr<'db> {
+        match &*expr.kind {
+            AstExprKind::Id(id) if id.id == self.from => AstExpr::new(
+                expr.span,
+                AstExprKind::Id(SpannedIdentifier {
+                    span: id.span,
+                    id: self.to,
+                }),
+            ),
+            _ => super_fold_expr(self, expr),
+        }
+    }
+}
+
+/// Returns the body of the function `main` in `source_file`.
+fn main_body<'db>(db: &'db dyn Db, source_file: SourceFile) -> AstBlock<'db> {
+    source_file
+        .parse(db)
+        .items(db)
+        .iter()
+        .find_map(|item| match item {
+            AstItem::Function(f) if f.name(db).id.text(db) == "main" => f.body_block(db),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn visitor_reaches_every_body() {
+    let (compiler, source_file) = common::open(
+        "visit",
+        "\
+fn main() {
+    let p = Point(x: a, y: b.ref)
+    if c { d() } else { spawn { e } }
+}
+
+class Point(x: u32, y: u32) {
+    fn sum(self) -> u32 {
+        fn helper(v: u32) -> u32 { f + v }
+        helper(self.x) + g
+    }
+}
+",
+    );
+    let db: &dyn Db = &compiler;
+    let mut visitor = Ids { db, ids: vec![] };
+    visitor.visit_module(source_file.parse(db));
+    assert_eq!(
+        visitor.ids,
+        vec![
+            "Point", "a", "b", "c", "d", "e", "f", "v", "helper", "self", "g"
+        ],
+    );
+}
+
+#[test]
+fn folder_renames_variable() {
+    let (compiler, source_file) = common::open(
+        "visit",
+        "fn main() {\n    let z = x + f(x, y: 1).await\n}\n",
+    );
+    let db: &dyn Db = &compiler;
+    let AstStatement::Let(s) = &main_body(db, source_file).statements(db)[0] else {
+        panic!("expected a `let` statement")
+    };
+    let initializer = s.initializer(db).unwrap();
+
+    let mut rename = Rename {
+        db,
+        from: Identifier::new(db, "x"),
+        to: Identifier::new(db, "w"),
+    };
+    let renamed = rename.fold_expr(initializer.clone());
+    assert_eq!(renamed.span, initializer.span);
+
+    let mut visitor = Ids { db, ids: vec![] };
+    visitor.visit_expr(&renamed);
+    assert_eq!(visitor.ids, vec!["w", "f", "w"]);
+}
+
+#[test]
+fn folder_leaves_unchanged_blocks_alone() {
+    let (compiler, source_file) =
+        common::open("visit", "fn main() {\n    let z = { 1 + y }\n    z\n}\n");
+    let db: &dyn Db = &compiler;
+    let body = main_body(db, source_file);
+
+    // Nothing is renamed, so the fold does not need to create any new blocks.
+    let mut rename = Rename {
+        db,
+        from: Identifier::new(db, "x"),
+        to: Identifier::new(db, "w"),
+    };
+    assert_eq!(rename.fold_block(body), body);
+}
@@ -0,0 +1,269 @@
+//! Generates documentation for a Dada module from the doc comments (`## ...`) on its items
+//! and their signatures as the type checker sees them, permissions included.
+//! Classes and structs declared in the module are cross-linked wherever they appear in a signature.
+
+use std::str::FromStr;
+
+use dada_ir_ast::{ast::VisibilityKind, span::SourceSpanned};
+pub use dada_ir_sym::Db;
+use dada_ir_sym::{
+    ir::{
+        classes::{SymAggregate, SymClassMember, SymField},
+        functions::{SymFunction, SymFunctionSource},
+        module::{SymItem, SymModule},
+    },
+    prelude::Documentation,
+};
+use dada_util::{Fallible, Set, bail};
+
+use signature::Signature;
+
+mod render;
+mod signature;
+
+/// The output format of the documentation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DocFormat {
+    /// A single markdown file.
+    #[default]
+    Markdown,
+
+    /// A standalone HTML page.
+    Html,
+}
+
+impl DocFormat {
+    pub const ALL: [DocFormat; 2] = [DocFormat::Markdown, DocFormat::Html];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "markdown",
+            DocFormat::Html => "html",
+        }
+    }
+
+    /// Conventional file extension for documentation in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+        }
+    }
+}
+
+impl FromStr for DocFormat {
+    type Err = dada_util::Error;
+
+    fn from_str(s: &str) -> Fallible<Self> {
+        match DocFormat::ALL.into_iter().find(|format| format.name() == s) {
+            Some(format) => Ok(format),
+            None => bail!(
+                "unknown documentation format `{s}`, expected one of: {}",
+                DocFormat::ALL.map(DocFormat::name).join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DocOptions {
+    pub format: DocFormat,
+
+    /// Also document items that have no visibility modifier
+    /// (by default only `pub` and `export` items are documented).
+    pub include_private: bool,
+}
+
+/// Renders the documentation for `module`.
+///
+/// Types are rendered with their `Display` impls, so this must be called
+/// with the database attached.
+pub fn document_module<'db>(
+    db: &'db dyn crate::Db,
+    module: SymModule<'db>,
+    options: DocOptions,
+) -> String {
+    let doc = DocModule::new(db, module, options);
+    match options.format {
+        DocFormat::Markdown => render::markdown(&doc),
+        DocFormat::Html => render::html(&doc),
+    }
+}
+
+/// Documentation for a module, independent of the output format.
+struct DocModule {
+    name: String,
+
+    /// Classes and structs, in source order
+    aggregates: Vec<DocItem>,
+
+    /// Functions, in source order
+    functions: Vec<DocItem>,
+}
+
+/// Documentation for an item or a member of a class.
+struct DocItem {
+    kind: DocItemKind,
+    name: String,
+
+    /// Unique name of the item within the module, used as the target of links.
+    anchor: String,
+
+    signature: Signature,
+
+    /// Markdown text of the doc comment
+    docs: Option<String>,
+
+    /// Fields and methods (for classes and structs)
+    fields: Vec<DocItem>,
+    methods: Vec<DocItem>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DocItemKind {
+    Class,
+    Struct,
+    Function,
+    Field,
+    Method,
+}
+
+impl DocItemKind {
+    fn label(self) -> &'static str {
+        match self {
+            DocItemKind::Class => "class",
+            DocItemKind::Struct => "struct",
+            DocItemKind::Function => "fn",
+            DocItemKind::Field => "field",
+            DocItemKind::Method => "method",
+        }
+    }
+}
+
+impl DocModule {
+    fn new<'db>(db: &'db dyn crate::Db, module: SymModule<'db>, options: DocOptions) -> Self {
+        let documented =
+            |visibility: Option<VisibilityKind>| options.include_private || visibility.is_some();
+
+        // Module items are stored in hash maps, so sort them into source order.
+        let mut items = module.items(db).collect::<Vec<_>>();
+        items.sort_by_key(|item| item.source_span(db).absolute_span(db).start);
+
+        let aggregates = items
+            .iter()
+            .filter_map(|&item| match item {
+                SymItem::SymClass(aggregate) => Some(aggregate),
+                SymItem::SymFunction(_) | SymItem::SymPrimitive(_) => None,
+            })
+            .filter(|aggregate| documented(aggregate.visibility(db)))
+            .collect::<Vec<_>>();
+
+        // Only types that get documentation of their own can be linked to.
+        let linkable: Set<SymAggregate<'db>> = aggregates.iter().copied().collect();
+
+        DocModule {
+            name: module.name(db).to_string(),
+            aggregates: aggregates
+                .iter()
+                .map(|&aggregate| document_aggregate(db, aggregate, &linkable, &documented))
+                .collect(),
+            functions: items
+                .iter()
+                .filter_map(|&item| match item {
+                    SymItem::SymFunction(function) => Some(function),
+                    SymItem::SymClass(_) | SymItem::SymPrimitive(_) => None,
+                })
+                .filter(|function| {
+                    !matches!(function.source(db), SymFunctionSource::MainFunction(_))
+                })
+                .filter(|function| documented(function.visibility(db)))
+                .map(|function| {
+                    let name = function.name(db).to_string();
+                    DocItem {
+                        kind: DocItemKind::Function,
+                        anchor: format!("fn.{name}"),
+                        name,
+                        signature: Signature::function(db, function, 0, &linkable),
+                        docs: function.documentation(db).map(str::to_string),
+                        fields: vec![],
+                        methods: vec![],
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn document_aggregate<'db>(
+    db: &'db dyn crate::Db,
+    aggregate: SymAggregate<'db>,
+    linkable: &Set<SymAggregate<'db>>,
+    documented: &impl Fn(Option<VisibilityKind>) -> bool,
+) -> DocItem {
+    let name = aggregate.name(db).to_string();
+    let kind = if aggregate.is_struct(db) {
+        DocItemKind::Struct
+    } else {
+        DocItemKind::Class
+    };
+
+    let members = aggregate.members(db);
+
+    let fields = members
+        .iter()
+        .filter_map(|&member| match member {
+            SymClassMember::SymField(field) => Some(field),
+            SymClassMember::SymFunction(_) => None,
+        })
+        .filter(|field| documented(field_visibility(db, *field)))
+        .map(|field| {
+            let field_name = field.name(db).to_string();
+            DocItem {
+                kind: DocItemKind::Field,
+                anchor: format!("field.{name}.{field_name}"),
+                name: field_name,
+                signature: Signature::field(db, field, linkable),
+                docs: field.documentation(db).map(str::to_string),
+                fields: vec![],
+                methods: vec![],
+            }
+        })
+        .collect();
+
+    // The generated constructor is described by the class signature itself.
+    let methods = members
+        .iter()
+        .filter_map(|&member| match member {
+            SymClassMember::SymFunction(function) => Some(function),
+            SymClassMember::SymField(_) => None,
+        })
+        .filter(|function| !matches!(function.source(db), SymFunctionSource::Constructor(..)))
+        .filter(|function| documented(function.visibility(db)))
+        .map(|method| {
+            let method_name = method.name(db).to_string();
+            DocItem {
+                kind: DocItemKind::Method,
+                anchor: format!("method.{name}.{method_name}"),
+                name: method_name,
+                signature: Signature::function(db, method, aggregate.len_generics(db), linkable),
+                docs: method.documentation(db).map(str::to_string),
+                fields: vec![],
+                methods: vec![],
+            }
+        })
+        .collect();
+
+    DocItem {
+        kind,
+        anchor: signature::aggregate_anchor(db, aggregate),
+        name,
+        signature: Signature::aggregate(db, aggregate, linkable),
+        docs: aggregate.documentation(db).map(str::to_string),
+        fields,
+        methods,
+    }
+}
+
+fn field_visibility<'db>(db: &'db dyn crate::Db, field: SymField<'db>) -> Option<VisibilityKind> {
+    field.source(db).visibility(db).map(|v| v.kind)
+}
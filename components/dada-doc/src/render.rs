@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use crate::{
+    DocItem, DocModule,
+    signature::{Fragment, Signature},
+};
+
+/// Renders `doc` as a single markdown file. Each item is preceded by an
+/// `<a id>` so that signatures can link to it.
+pub(crate) fn markdown(doc: &DocModule) -> String {
+    let mut output = String::new();
+    writeln!(output, "# Module `{}`", doc.name).unwrap();
+
+    for (title, items) in [("Classes", &doc.aggregates), ("Functions", &doc.functions)] {
+        if items.is_empty() {
+            continue;
+        }
+
+        writeln!(output, "\n## {title}").unwrap();
+        for item in items {
+            markdown_item(&mut output, item, "###");
+
+            for (title, members) in [("Fields", &item.fields), ("Methods", &item.methods)] {
+                if members.is_empty() {
+                    continue;
+                }
+                writeln!(output, "\n#### {title}").unwrap();
+                for member in members {
+                    markdown_item(&mut output, member, "#####");
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn markdown_item(output: &mut String, item: &DocItem, heading: &str) {
+    writeln!(output, "\n<a id=\"{}\"></a>", item.anchor).unwrap();
+    writeln!(output, "{heading} {} `{}`\n", item.kind.label(), item.name).unwrap();
+    writeln!(output, "{}", markdown_signature(&item.signature)).unwrap();
+    if let Some(docs) = &item.docs {
+        writeln!(output, "\n{docs}").unwrap();
+    }
+}
+
+/// Code spans cannot contain links, so the signature is rendered as a series of
+/// code spans with links in between.
+fn markdown_signature(signature: &Signature) -> String {
+    signature
+        .fragments
+        .iter()
+        .map(|fragment| match fragment {
+            Fragment::Text(text) => markdown_code(text),
+            Fragment::Link { text, anchor } => format!("[{}](#{anchor})", markdown_code(text)),
+        })
+        .collect()
+}
+
+fn markdown_code(text: &str) -> String {
+    if text.contains('`') {
+        format!("`` {text} ``")
+    } else {
+        format!("`{text}`")
+    }
+}
+
+/// Renders `doc` as a standalone HTML page.
+pub(crate) fn html(doc: &DocModule) -> String {
+    let mut output = String::new();
+    let name = escape(&doc.name);
+    writeln!(output, "<!DOCTYPE html>").unwrap();
+    writeln!(output, "<html>").unwrap();
+    writeln!(output, "<head>").unwrap();
+    writeln!(output, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(output, "<title>Module {name}</title>").unwrap();
+    writeln!(output, "</head>").unwrap();
+    writeln!(output, "<body>").unwrap();
+    writeln!(output, "<h1>Module <code>{name}</code></h1>").unwrap();
+
+    for (title, items) in [("Classes", &doc.aggregates), ("Functions", &doc.functions)] {
+        if items.is_empty() {
+            continue;
+        }
+
+        writeln!(output, "<h2>{title}</h2>").unwrap();
+        for item in items {
+            writeln!(output, "<section id=\"{}\">", escape(&item.anchor)).unwrap();
+            html_item(&mut output, item, "h3");
+
+            for (title, members) in [("Fields", &item.fields), ("Methods", &item.methods)] {
+                if members.is_empty() {
+                    continue;
+                }
+                writeln!(output, "<h4>{title}</h4>").unwrap();
+                for member in members {
+                    writeln!(output, "<div id=\"{}\">", escape(&member.anchor)).unwrap();
+                    html_item(&mut output, member, "h5");
+                    writeln!(output, "</div>").unwrap();
+                }
+            }
+
+            writeln!(output, "</section>").unwrap();
+        }
+    }
+
+    writeln!(output, "</body>").unwrap();
+    writeln!(output, "</html>").unwrap();
+    output
+}
+
+fn html_item(output: &mut String, item: &DocItem, heading: &str) {
+    writeln!(
+        output,
+        "<{heading}>{} <code>{}</code></{heading}>",
+        item.kind.label(),
+        escape(&item.name)
+    )
+    .unwrap();
+
+    output.push_str("<pre><code>");
+    for fragment in &item.signature.fragments {
+        match fragment {
+            Fragment::Text(text) => output.push_str(&escape(text)),
+            Fragment::Link { text, anchor } => write!(
+                output,
+                "<a href=\"#{}\">{}</a>",
+                escape(anchor),
+                escape(text)
+            )
+            .unwrap(),
+        }
+    }
+    output.push_str("</code></pre>\n");
+
+    if let Some(docs) = &item.docs {
+        pulldown_cmark::html::push_html(output, pulldown_cmark::Parser::new(docs));
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
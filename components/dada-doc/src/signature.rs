@@ -0,0 +1,238 @@
+use std::fmt::Display;
+
+use dada_ir_ast::ast::VisibilityKind;
+use dada_ir_sym::{
+    ir::{
+        classes::{SymAggregate, SymField},
+        functions::SymFunction,
+        types::{SymGenericTerm, SymTy, SymTyKind, SymTyName},
+        variables::SymVariable,
+    },
+    prelude::{CheckedFieldTy, CheckedSignature},
+};
+use dada_util::Set;
+
+/// A signature rendered as text, in which the names of documented types
+/// link to their documentation.
+#[derive(Default)]
+pub(crate) struct Signature {
+    pub(crate) fragments: Vec<Fragment>,
+}
+
+pub(crate) enum Fragment {
+    Text(String),
+    Link { text: String, anchor: String },
+}
+
+/// The anchor under which the documentation for `aggregate` is found.
+pub(crate) fn aggregate_anchor<'db>(
+    db: &'db dyn crate::Db,
+    aggregate: SymAggregate<'db>,
+) -> String {
+    let kind = if aggregate.is_struct(db) {
+        "struct"
+    } else {
+        "class"
+    };
+    format!("{kind}.{}", aggregate.name(db))
+}
+
+impl Signature {
+    /// `class Name[type T]` (or `struct`)
+    pub(crate) fn aggregate<'db>(
+        db: &'db dyn crate::Db,
+        aggregate: SymAggregate<'db>,
+        linkable: &Set<SymAggregate<'db>>,
+    ) -> Self {
+        let mut builder = SignatureBuilder::new(db, linkable);
+        builder.visibility(aggregate.visibility(db));
+        builder.text(if aggregate.is_struct(db) {
+            "struct "
+        } else {
+            "class "
+        });
+        builder.text(aggregate.name(db));
+        builder.generics(aggregate.generic_variables(db));
+        builder.signature
+    }
+
+    /// `name: Ty`
+    pub(crate) fn field<'db>(
+        db: &'db dyn crate::Db,
+        field: SymField<'db>,
+        linkable: &Set<SymAggregate<'db>>,
+    ) -> Self {
+        let mut builder = SignatureBuilder::new(db, linkable);
+        builder.visibility(field.source(db).visibility(db).map(|v| v.kind));
+        builder.text(field.name(db));
+        builder.text(": ");
+        builder.ty(field.checked_field_ty(db).bound_value.bound_value);
+        builder.signature
+    }
+
+    /// `async fn name[type T](x: Ty) -> Ty`, omitting the first `skip_generics`
+    /// generic parameters (those of the enclosing class, for methods).
+    pub(crate) fn function<'db>(
+        db: &'db dyn crate::Db,
+        function: SymFunction<'db>,
+        skip_generics: usize,
+        linkable: &Set<SymAggregate<'db>>,
+    ) -> Self {
+        let mut builder = SignatureBuilder::new(db, linkable);
+        builder.visibility(function.visibility(db));
+        if function.effects(db).async_effect {
+            builder.text("async ");
+        }
+        builder.text("fn ");
+        builder.text(function.name(db));
+
+        let Ok(signature) = function.checked_signature(db) else {
+            // The error has been reported elsewhere; show what we can.
+            builder.text("(...)");
+            return builder.signature;
+        };
+
+        let symbols = signature.symbols(db);
+        builder.generics(&symbols.generic_variables[skip_generics..]);
+
+        let input_output = &signature.input_output(db).bound_value.bound_value;
+        builder.text("(");
+        for (index, (&input, &input_ty)) in symbols
+            .input_variables
+            .iter()
+            .zip(&input_output.input_tys)
+            .enumerate()
+        {
+            if index > 0 {
+                builder.text(", ");
+            }
+            builder.text(input);
+            builder.text(": ");
+            builder.ty(input_ty);
+        }
+        builder.text(")");
+
+        if !is_unit(db, input_output.output_ty) {
+            builder.text(" -> ");
+            builder.ty(input_output.output_ty);
+        }
+
+        builder.signature
+    }
+}
+
+fn is_unit<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> bool {
+    matches!(
+        ty.kind(db),
+        SymTyKind::Named(SymTyName::Tuple { arity: 0 }, _)
+    )
+}
+
+struct SignatureBuilder<'a, 'db> {
+    db: &'db dyn crate::Db,
+
+    /// Types that have documentation to link to
+    linkable: &'a Set<SymAggregate<'db>>,
+
+    signature: Signature,
+}
+
+impl<'a, 'db> SignatureBuilder<'a, 'db> {
+    fn new(db: &'db dyn crate::Db, linkable: &'a Set<SymAggregate<'db>>) -> Self {
+        Self {
+            db,
+            linkable,
+            signature: Signature::default(),
+        }
+    }
+
+    fn text(&mut self, text: impl Display) {
+        let text = text.to_string();
+        match self.signature.fragments.last_mut() {
+            Some(Fragment::Text(previous)) => previous.push_str(&text),
+            _ => self.signature.fragments.push(Fragment::Text(text)),
+        }
+    }
+
+    fn link(&mut self, text: impl Display, anchor: String) {
+        self.signature.fragments.push(Fragment::Link {
+            text: text.to_string(),
+            anchor,
+        });
+    }
+
+    fn visibility(&mut self, visibility: Option<VisibilityKind>) {
+        match visibility {
+            Some(VisibilityKind::Export) => self.text("export "),
+            Some(VisibilityKind::Pub) => self.text("pub "),
+            None => {}
+        }
+    }
+
+    /// `[type T, perm P]`. Variables without names (e.g., the permissions
+    /// introduced for types written without one) are implicit and not shown.
+    fn generics(&mut self, variables: &[SymVariable<'db>]) {
+        let db = self.db;
+        let named = variables
+            .iter()
+            .filter(|variable| variable.name(db).is_some())
+            .collect::<Vec<_>>();
+        if named.is_empty() {
+            return;
+        }
+
+        self.text("[");
+        for (index, variable) in named.into_iter().enumerate() {
+            if index > 0 {
+                self.text(", ");
+            }
+            self.text(format!("{} {variable}", variable.kind(db)));
+        }
+        self.text("]");
+    }
+
+    fn ty(&mut self, ty: SymTy<'db>) {
+        let db = self.db;
+        match ty.kind(db) {
+            SymTyKind::Perm(perm, ty) => {
+                self.text(format!("{perm} "));
+                self.ty(*ty);
+            }
+            SymTyKind::Named(SymTyName::Tuple { .. }, generics) => {
+                self.text("(");
+                self.terms(generics);
+                self.text(")");
+            }
+            SymTyKind::Named(name, generics) => {
+                match name {
+                    SymTyName::Aggregate(aggregate) if self.linkable.contains(aggregate) => {
+                        self.link(name, aggregate_anchor(db, *aggregate));
+                    }
+                    _ => self.text(name),
+                }
+                if !generics.is_empty() {
+                    self.text("[");
+                    self.terms(generics);
+                    self.text("]");
+                }
+            }
+            SymTyKind::Infer(_) | SymTyKind::Var(_) | SymTyKind::Never | SymTyKind::Error(_) => {
+                self.text(ty)
+            }
+        }
+    }
+
+    fn terms(&mut self, terms: &[SymGenericTerm<'db>]) {
+        for (index, &term) in terms.iter().enumerate() {
+            if index > 0 {
+                self.text(", ");
+            }
+            match term {
+                SymGenericTerm::Type(ty) => self.ty(ty),
+                SymGenericTerm::Perm(_) | SymGenericTerm::Place(_) | SymGenericTerm::Error(_) => {
+                    self.text(term)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! An interpreter for Dada programs that evaluates the checked (and drop-elaborated)
+//! bodies of functions directly, without generating wasm.
+//!
+//! Unlike generated code, which relies on the type checker to rule out misuse of permissions,
+//! the interpreter tracks the permission of every object reference at runtime
+//! (see [`permission`]) and reports a runtime error when an expired permission is used.
+//! This makes it useful both for running tests without a wasm runtime and as a reference
+//! to cross-check the semantics of generated code against.
+
+use dada_ir_ast::{
+    ast::Identifier,
+    inputs::SourceFile,
+    span::{AbsoluteSpan, Span},
+};
+pub use dada_ir_sym::Db;
+use dada_ir_sym::prelude::Symbol;
+
+mod machine;
+mod permission;
+mod primitive;
+mod value;
+
+/// The result of interpreting a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Execution {
+    /// Everything the program printed, one line per call to `print`.
+    pub output: String,
+
+    /// `Err` if the program stopped early because of a runtime error (including a panic).
+    pub result: Result<(), RuntimeError>,
+}
+
+/// An error that stopped the interpreted program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeError {
+    /// Location of the expression that failed.
+    pub span: AbsoluteSpan,
+
+    /// Description of the failure, e.g., `panicked: out of range`.
+    pub message: String,
+}
+
+impl RuntimeError {
+    fn new<'db>(db: &'db dyn Db, span: Span<'db>, message: impl ToString) -> Self {
+        Self {
+            span: span.absolute_span(db),
+            message: message.to_string(),
+        }
+    }
+
+    /// Renders the error as `path:line:column: message`,
+    /// the same format as the trap table of generated code.
+    pub fn render(&self, db: &dyn Db) -> String {
+        let (line, column) = self.span.source_file.line_col(db, self.span.start);
+        format!(
+            "{}:{}:{}: {}",
+            self.span.source_file.url_display(db),
+            line.as_u32() + 1,
+            column.as_u32() + 1,
+            self.message,
+        )
+    }
+}
+
+/// Interprets the `main` function of `source_file`.
+///
+/// Returns `None` if there is no `main` function or if it is generic;
+/// code generation reports the latter as an error.
+pub fn interpret_main(db: &dyn Db, source_file: SourceFile) -> Option<Execution> {
+    let module = source_file.symbol(db);
+    let main_fn = module.function_named(db, Identifier::main(db))?;
+    if !main_fn.symbols(db).has_generics_of_kind(db, &[]) {
+        return None;
+    }
+
+    let mut machine = machine::Machine::new(db);
+    let result = machine.call_main(main_fn);
+    Some(Execution {
+        output: machine.into_output(),
+        result,
+    })
+}
@@ -0,0 +1,929 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, sync::Arc};
+
+use dada_ir_ast::{
+    ast::{AstFunctionInput, AstPermKind, PermissionOp},
+    diagnostic::Errors,
+    span::Span,
+};
+use dada_ir_sym::{
+    ir::{
+        classes::{SymAggregate, SymField},
+        exprs::{SymExpr, SymExprKind, SymLoop, SymPlaceExpr, SymPlaceExprKind},
+        functions::{SymFunction, SymFunctionSource},
+        primitive::SymPrimitiveKind,
+        subst::Subst,
+        types::{SymGenericTerm, SymTy, SymTyKind, SymTyName},
+        variables::SymVariable,
+    },
+    prelude::{CheckedDestructor, CheckedSignature, ElaboratedBody},
+    well_known,
+};
+use dada_util::Map;
+
+use crate::{
+    Db, RuntimeError,
+    permission::{Permission, PermissionError, PermissionKind},
+    primitive,
+    value::{Object, ObjectRef, Primitive, Value},
+};
+
+/// Maximum number of nested calls. Deeper recursion is reported as a runtime error
+/// rather than overflowing the stack of the interpreter itself.
+const MAX_CALL_DEPTH: usize = 256;
+
+pub(crate) struct Machine<'db> {
+    db: &'db dyn Db,
+
+    /// One frame per active call, innermost last.
+    frames: Vec<Frame<'db>>,
+
+    /// Text printed by the program so far.
+    output: String,
+}
+
+struct Frame<'db> {
+    /// Values of the generic variables of the function being executed.
+    generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
+
+    /// Values of its inputs and local variables (including temporaries).
+    locals: Map<SymVariable<'db>, Value<'db>>,
+}
+
+/// Why evaluation of an expression stopped without producing a value.
+enum Unwind<'db> {
+    /// A `return` expression, with the value being returned.
+    Return(Value<'db>),
+
+    /// A `break` expression, with the loop it exits and the value of that loop.
+    Break(SymLoop<'db>, Value<'db>),
+
+    /// A `continue` expression, with the loop whose next iteration begins.
+    Continue(SymLoop<'db>),
+
+    /// A runtime error, which stops the program.
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind<'_> {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+type Eval<'db, T = Value<'db>> = Result<T, Unwind<'db>>;
+
+/// Ways that accessing a place or value can fail.
+#[derive(Copy, Clone, Debug)]
+enum AccessError {
+    Given,
+    Uninitialized,
+    NotLeasable,
+    Permission(PermissionError),
+
+    /// The value does not have the shape its type promises, which the type checker rules out.
+    Internal(&'static str),
+}
+
+impl From<PermissionError> for AccessError {
+    fn from(error: PermissionError) -> Self {
+        AccessError::Permission(error)
+    }
+}
+
+impl std::fmt::Display for AccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessError::Given => write!(f, "use of a value that has been given away"),
+            AccessError::Uninitialized => {
+                write!(f, "use of a variable before it has been assigned")
+            }
+            AccessError::NotLeasable => write!(f, "only class objects can be leased"),
+            AccessError::Permission(error) => write!(f, "{error}"),
+            AccessError::Internal(message) => write!(f, "internal error: {message}"),
+        }
+    }
+}
+
+/// Whether a place is being read from or written to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// The permission through which a place is reached: the combination of the permissions
+/// of the objects traversed to get there. For example, the field `x.f` is reached through
+/// a `ref` if `x` stores a `ref` to an object.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PathMode {
+    /// The place is owned by the current function (e.g., a local variable).
+    Owned,
+
+    /// The place is part of a shared object.
+    Our,
+
+    /// The place is reached through a `ref` lease.
+    Ref,
+
+    /// The place is reached through a `mut` lease.
+    Mut,
+}
+
+impl PathMode {
+    /// The mode of a field of an object with permission `kind`, reached with this mode.
+    fn through(self, kind: PermissionKind) -> Self {
+        match (self, kind) {
+            (PathMode::Our, _) | (_, PermissionKind::Our) => PathMode::Our,
+            (PathMode::Ref, _) | (_, PermissionKind::Ref) => PathMode::Ref,
+            (PathMode::Mut, _) | (_, PermissionKind::Mut) => PathMode::Mut,
+            (PathMode::Owned, PermissionKind::My) => PathMode::Owned,
+        }
+    }
+}
+
+impl<'db> Machine<'db> {
+    pub(crate) fn new(db: &'db dyn Db) -> Self {
+        Self {
+            db,
+            frames: vec![],
+            output: String::new(),
+        }
+    }
+
+    pub(crate) fn into_output(self) -> String {
+        self.output
+    }
+
+    /// Calls `main_fn`, which takes no arguments.
+    pub(crate) fn call_main(&mut self, main_fn: SymFunction<'db>) -> Result<(), RuntimeError> {
+        let span = main_fn.name_span(self.db);
+        let value = self.call(span, main_fn, vec![], vec![])?;
+        self.drop_value(span, value)
+    }
+
+    /// Calls `function`, instantiated with `generics`, with the given arguments.
+    /// `span` is the span of the call, used to report errors.
+    fn call(
+        &mut self,
+        span: Span<'db>,
+        function: SymFunction<'db>,
+        generics: Vec<SymGenericTerm<'db>>,
+        args: Vec<Value<'db>>,
+    ) -> Result<Value<'db>, RuntimeError> {
+        let db = self.db;
+
+        if well_known::panic_fn(db).ok() == Some(function) {
+            let message = match self.string_text(&args[0]) {
+                Some(text) => format!("panicked: {text}"),
+                None => "explicit panic".to_string(),
+            };
+            return Err(RuntimeError::new(db, span, message));
+        }
+
+        if well_known::print_fn(db).ok() == Some(function) {
+            for arg in args {
+                let text = self
+                    .display(&arg)
+                    .map_err(|error| RuntimeError::new(db, span, error))?;
+                self.output.push_str(&text);
+                self.output.push('\n');
+                self.drop_value(span, arg)?;
+            }
+            return Ok(Value::unit());
+        }
+
+        if well_known::none_fn(db).ok() == Some(function) {
+            let fields = vec![Value::Uninitialized];
+            return variant_object(db, span, well_known::option_class(db), generics, fields);
+        }
+
+        if well_known::ok_fn(db).ok() == Some(function) {
+            let [value] = <[Value<'db>; 1]>::try_from(args).unwrap();
+            let fields = vec![value, Value::Uninitialized];
+            return variant_object(db, span, well_known::result_class(db), generics, fields);
+        }
+
+        if well_known::err_fn(db).ok() == Some(function) {
+            let [error] = <[Value<'db>; 1]>::try_from(args).unwrap();
+            let fields = vec![Value::Uninitialized, error];
+            return variant_object(db, span, well_known::result_class(db), generics, fields);
+        }
+
+        if well_known::channel_send_fn(db).ok() == Some(function) {
+            let [channel, value] = <[Value<'db>; 2]>::try_from(args).unwrap();
+            channel_object(&channel)
+                .map_err(|error| RuntimeError::new(db, span, error))?
+                .borrow_mut()
+                .queue
+                .push_back(value);
+            self.drop_value(span, channel)?;
+            return Ok(Value::unit());
+        }
+
+        if well_known::channel_recv_fn(db).ok() == Some(function) {
+            let [channel] = <[Value<'db>; 1]>::try_from(args).unwrap();
+            let value = channel_object(&channel)
+                .map_err(|error| RuntimeError::new(db, span, error))?
+                .borrow_mut()
+                .queue
+                .pop_front();
+            self.drop_value(span, channel)?;
+            // Nothing else runs while we wait, so an empty channel would wait forever.
+            return value.ok_or_else(|| {
+                RuntimeError::new(db, span, "deadlock: receive from an empty channel")
+            });
+        }
+
+        if let SymFunctionSource::Extern(_) = function.source(db) {
+            return Err(RuntimeError::new(
+                db,
+                span,
+                format!(
+                    "cannot call `{}`, which is implemented by the host",
+                    function.name(db)
+                ),
+            ));
+        }
+
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(
+                db,
+                span,
+                format!("stack overflow: more than {MAX_CALL_DEPTH} nested calls"),
+            ));
+        }
+
+        let (Ok(signature), Some(body)) =
+            (function.checked_signature(db), function.elaborated_body(db))
+        else {
+            return Err(compile_error(db, span));
+        };
+        let symbols = signature.symbols(db);
+        assert_eq!(symbols.input_variables.len(), args.len());
+
+        self.frames.push(Frame {
+            generics: symbols
+                .generic_variables
+                .iter()
+                .copied()
+                .zip(generics)
+                .collect(),
+            locals: symbols.input_variables.iter().copied().zip(args).collect(),
+        });
+        let result = self.eval(body);
+        self.frames.pop();
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            Err(Unwind::Break(..) | Unwind::Continue(_)) => {
+                unreachable!("`break` or `continue` outside of its loop")
+            }
+        }
+    }
+
+    fn frame(&self) -> &Frame<'db> {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame<'db> {
+        self.frames.last_mut().unwrap()
+    }
+
+    /// Evaluates `expr` in the innermost frame.
+    fn eval(&mut self, expr: SymExpr<'db>) -> Eval<'db> {
+        let db = self.db;
+        let span = expr.span(db);
+        match *expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) => {
+                // Like generated code, we do not drop the value of `lhs`:
+                // drop elaboration decides what gets dropped.
+                self.eval(lhs)?;
+                self.eval(rhs)
+            }
+            SymExprKind::Tuple(ref elements) => {
+                let mut values = Vec::with_capacity(elements.len());
+                for &element in elements {
+                    values.push(self.eval(element)?);
+                }
+                Ok(Value::Tuple(values))
+            }
+            SymExprKind::Primitive(literal) => {
+                let kind = self.primitive_kind(span, expr.ty(db))?;
+                let value = primitive::literal(kind, literal)
+                    .ok_or_else(|| internal_error(db, span, "literal of the wrong type"))?;
+                Ok(Value::Primitive(value))
+            }
+            SymExprKind::ByteLiteral(literal) => Ok(Value::Bytes(Arc::from(
+                literal.data(db).value(db).as_slice(),
+            ))),
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                let value = match initializer {
+                    Some(initializer) => self.eval(initializer)?,
+                    None => Value::Uninitialized,
+                };
+                self.frame_mut().locals.insert(lv, value);
+                self.eval(body)
+            }
+            SymExprKind::LetTuple {
+                ref lvs,
+                initializer,
+                body,
+            } => {
+                let Value::Tuple(values) = self.eval(initializer)? else {
+                    return Err(internal_error(
+                        db,
+                        span,
+                        "tuple pattern matched against a non-tuple",
+                    )
+                    .into());
+                };
+                for (&(lv, _), value) in lvs.iter().zip(values) {
+                    self.frame_mut().locals.insert(lv, value);
+                }
+                self.eval(body)
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword: _,
+            } => {
+                // Like generated code, we execute async functions when they are called,
+                // so the "future" is already the result.
+                self.eval(future)
+            }
+            SymExprKind::Assign { place, value } => {
+                let value = self.eval(value)?;
+
+                // Drop elaboration has already dropped the old value, if needed.
+                let mut value = Some(value);
+                self.with_place(place, Access::Write, &mut |_, slot| {
+                    *slot = value.take().unwrap();
+                    Ok(())
+                })?;
+                Ok(Value::unit())
+            }
+            SymExprKind::PermissionOp(op, place) => match op {
+                PermissionOp::Reference => {
+                    Ok(self.with_place(place, Access::Read, &mut |_, slot| ref_of(slot))?)
+                }
+                PermissionOp::Mutate => {
+                    Ok(
+                        self.with_place(place, Access::Write, &mut |_, slot| match slot {
+                            Value::Object(obj) => Ok(Value::Object(ObjectRef {
+                                object: obj.object.clone(),
+                                permission: obj.permission.lease(PermissionKind::Mut)?,
+                            })),
+                            Value::Given => Err(AccessError::Given),
+                            Value::Uninitialized => Err(AccessError::Uninitialized),
+                            _ => Err(AccessError::NotLeasable),
+                        })?,
+                    )
+                }
+                PermissionOp::Give => {
+                    Ok(self.with_place(place, Access::Read, &mut |mode, slot| give(mode, slot))?)
+                }
+                PermissionOp::Share => {
+                    Ok(self.with_place(place, Access::Read, &mut |mode, slot| {
+                        let value = give(mode, slot)?;
+                        Ok(share(value))
+                    })?)
+                }
+            },
+            SymExprKind::Call {
+                function,
+                ref substitution,
+                ref arg_temps,
+            } => {
+                let generics = substitution.subst_vars(db, &self.frame().generics);
+
+                // Each argument is given to the callee.
+                let mut args = Vec::with_capacity(arg_temps.len());
+                for arg_temp in arg_temps {
+                    let slot = self.frame_mut().locals.get_mut(arg_temp).unwrap();
+                    let arg = give(PathMode::Owned, slot)
+                        .map_err(|error| RuntimeError::new(db, span, error))?;
+                    args.push(arg);
+                }
+
+                Ok(self.call(span, function, generics, args)?)
+            }
+            SymExprKind::Return(value) => {
+                let value = self.eval(value)?;
+                Err(Unwind::Return(value))
+            }
+            SymExprKind::Loop { sym_loop, body } => loop {
+                match self.eval(body) {
+                    Ok(_) => {}
+                    Err(Unwind::Continue(to_loop)) if to_loop == sym_loop => {}
+                    Err(Unwind::Break(from_loop, value)) if from_loop == sym_loop => {
+                        break Ok(value);
+                    }
+                    Err(unwind) => break Err(unwind),
+                }
+            },
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                let value = self.eval(with_value)?;
+                Err(Unwind::Break(from_loop, value))
+            }
+            SymExprKind::Continue { to_loop } => Err(Unwind::Continue(to_loop)),
+            SymExprKind::IsSome(place) => {
+                let is_some = self.with_place(place, Access::Read, &mut |_, slot| match slot {
+                    Value::Object(obj) => Ok(!matches!(
+                        obj.object.borrow().fields[0],
+                        Value::Uninitialized
+                    )),
+                    Value::Given => Err(AccessError::Given),
+                    Value::Uninitialized => Err(AccessError::Uninitialized),
+                    _ => Err(AccessError::Internal("expected an `Option` or a `Result`")),
+                })?;
+                Ok(Value::Primitive(Primitive::Bool(is_some)))
+            }
+            SymExprKind::Drop(place) => {
+                let value = self.with_place(place, Access::Write, &mut |_, slot| {
+                    Ok(std::mem::replace(slot, Value::Given))
+                })?;
+                self.drop_value(span, value)?;
+                Ok(Value::unit())
+            }
+            SymExprKind::Not {
+                operand,
+                op_span: _,
+            } => {
+                let value = self.eval_bool(operand)?;
+                Ok(Value::bool(!value))
+            }
+            SymExprKind::BinaryOp(op, lhs, rhs) => {
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                match (lhs, rhs) {
+                    (Value::Primitive(lhs), Value::Primitive(rhs)) => {
+                        match primitive::binary_op(op, lhs, rhs) {
+                            Ok(value) => Ok(Value::Primitive(value)),
+                            Err(error) => Err(RuntimeError::new(db, span, error).into()),
+                        }
+                    }
+                    _ => Err(internal_error(db, span, "binary operation on non-primitives").into()),
+                }
+            }
+            SymExprKind::Cast(value) => {
+                let kind = self.primitive_kind(span, expr.ty(db))?;
+                match self.eval(value)? {
+                    Value::Primitive(value) => match primitive::cast(kind, value) {
+                        Some(value) => Ok(Value::Primitive(value)),
+                        None => Err(internal_error(db, span, "cast to a non-numeric type").into()),
+                    },
+                    _ => Err(internal_error(db, span, "cast of a non-primitive").into()),
+                }
+            }
+            SymExprKind::Aggregate { ty, ref fields } => {
+                let mut values = Vec::with_capacity(fields.len());
+                for &field in fields {
+                    values.push(self.eval(field)?);
+                }
+                let (aggr, ty_args) = self.aggregate_ty(span, ty)?;
+                if aggr.is_class(db) {
+                    Ok(Value::Object(ObjectRef {
+                        object: Rc::new(RefCell::new(Object {
+                            aggr,
+                            ty_args,
+                            fields: values,
+                            queue: VecDeque::new(),
+                        })),
+                        permission: Permission::new(PermissionKind::My),
+                    }))
+                } else {
+                    Ok(Value::Struct(aggr, values))
+                }
+            }
+            SymExprKind::Match { ref arms } => {
+                for arm in arms {
+                    if let Some(condition) = arm.condition
+                        && !self.eval_bool(condition)?
+                    {
+                        continue;
+                    }
+                    return self.eval(arm.body);
+                }
+
+                // An `if` without an `else` whose conditions were all false.
+                Ok(Value::unit())
+            }
+            SymExprKind::Error(_) => Err(compile_error(db, span).into()),
+        }
+    }
+
+    /// Evaluates `expr`, which has type `bool`.
+    fn eval_bool(&mut self, expr: SymExpr<'db>) -> Eval<'db, bool> {
+        let db = self.db;
+        match self.eval(expr)? {
+            Value::Primitive(Primitive::Bool(value)) => Ok(value),
+            _ => Err(internal_error(db, expr.span(db), "expected a boolean").into()),
+        }
+    }
+
+    /// Invokes `op` on the value stored in `place`, along with the [`PathMode`] through which it was reached.
+    /// The permissions of the objects traversed along the way must permit `access`.
+    fn with_place<R>(
+        &mut self,
+        place: SymPlaceExpr<'db>,
+        access: Access,
+        op: &mut dyn FnMut(PathMode, &mut Value<'db>) -> Result<R, AccessError>,
+    ) -> Result<R, RuntimeError> {
+        let db = self.db;
+        let span = place.span(db);
+        match *place.kind(db) {
+            SymPlaceExprKind::Var(lv) => {
+                let slot = self.frame_mut().locals.get_mut(&lv).unwrap();
+                op(PathMode::Owned, slot).map_err(|error| RuntimeError::new(db, span, error))
+            }
+            SymPlaceExprKind::Field(owner, field) => {
+                let result =
+                    self.with_place(owner, access, &mut |mode, owner_slot| match owner_slot {
+                        Value::Struct(aggr, fields) => {
+                            op(mode, &mut fields[field_index(db, *aggr, field)?])
+                        }
+                        Value::Object(obj) => {
+                            match access {
+                                Access::Read => obj.permission.read()?,
+                                Access::Write => obj.permission.write()?,
+                            }
+                            let mut object = obj.object.borrow_mut();
+                            let index = field_index(db, object.aggr, field)?;
+                            op(
+                                mode.through(obj.permission.kind()),
+                                &mut object.fields[index],
+                            )
+                        }
+                        Value::Given => Err(AccessError::Given),
+                        Value::Uninitialized => Err(AccessError::Uninitialized),
+                        Value::Primitive(_) | Value::Bytes(_) | Value::Tuple(_) => Err(
+                            AccessError::Internal("field access on a value without fields"),
+                        ),
+                    });
+
+                // Errors are reported at the span of the outermost place.
+                result.map_err(|error| RuntimeError {
+                    span: span.absolute_span(db),
+                    ..error
+                })
+            }
+            SymPlaceExprKind::Error(_) => Err(compile_error(db, span)),
+        }
+    }
+
+    /// Drops `value`: runs the destructors of the objects it owns and then drops their fields.
+    /// Objects accessed through other permissions are left alone.
+    fn drop_value(&mut self, span: Span<'db>, value: Value<'db>) -> Result<(), RuntimeError> {
+        let db = self.db;
+        match value {
+            Value::Object(obj) if obj.permission.kind() == PermissionKind::My => {
+                if obj.permission.is_expired() {
+                    return Ok(());
+                }
+
+                // Outstanding leases end when the object is dropped.
+                obj.permission.expire_tenants();
+
+                let (aggr, ty_args) = {
+                    let object = obj.object.borrow();
+                    (object.aggr, object.ty_args.clone())
+                };
+                if let Ok(Some(destructor)) = aggr.checked_destructor(db) {
+                    let self_arg = if takes_mut_self(db, destructor) {
+                        Value::Object(ObjectRef {
+                            object: obj.object.clone(),
+                            permission: obj
+                                .permission
+                                .lease(PermissionKind::Mut)
+                                .expect("owned object is valid"),
+                        })
+                    } else {
+                        // `my self`: the destructor does not drop `self` (see drop elaboration),
+                        // so the fields remain ours to drop.
+                        Value::Object(obj.clone())
+                    };
+                    self.call(span, destructor, ty_args, vec![self_arg])?;
+                }
+
+                let (fields, queue) = {
+                    let mut object = obj.object.borrow_mut();
+                    (
+                        std::mem::take(&mut object.fields),
+                        std::mem::take(&mut object.queue),
+                    )
+                };
+                for value in fields.into_iter().chain(queue) {
+                    self.drop_value(span, value)?;
+                }
+                obj.permission.expire();
+                Ok(())
+            }
+            Value::Tuple(values) | Value::Struct(_, values) => {
+                for value in values {
+                    self.drop_value(span, value)?;
+                }
+                Ok(())
+            }
+            Value::Object(_)
+            | Value::Primitive(_)
+            | Value::Bytes(_)
+            | Value::Uninitialized
+            | Value::Given => Ok(()),
+        }
+    }
+
+    /// The primitive type `ty`, with generics replaced by their values in the current frame.
+    fn primitive_kind(
+        &self,
+        span: Span<'db>,
+        ty: SymTy<'db>,
+    ) -> Result<SymPrimitiveKind, RuntimeError> {
+        let db = self.db;
+        match *self.resolve_ty(ty).kind(db) {
+            SymTyKind::Named(SymTyName::Primitive(primitive), _) => Ok(primitive.kind(db)),
+            SymTyKind::Error(_) => Err(compile_error(db, span)),
+            _ => Err(internal_error(db, span, "expected a primitive type")),
+        }
+    }
+
+    /// The aggregate type `ty` and its generic arguments, with generics replaced by their values in the current frame.
+    fn aggregate_ty(
+        &self,
+        span: Span<'db>,
+        ty: SymTy<'db>,
+    ) -> Result<(SymAggregate<'db>, Vec<SymGenericTerm<'db>>), RuntimeError> {
+        let db = self.db;
+        match *self.resolve_ty(ty).kind(db) {
+            SymTyKind::Named(SymTyName::Aggregate(aggr), ref ty_args) => {
+                Ok((aggr, ty_args.clone()))
+            }
+            SymTyKind::Error(_) => Err(compile_error(db, span)),
+            _ => Err(internal_error(db, span, "expected an aggregate type")),
+        }
+    }
+
+    /// Replaces the generics in `ty` with their values in the current frame
+    /// and strips any permissions, leaving the type of the underlying value.
+    fn resolve_ty(&self, ty: SymTy<'db>) -> SymTy<'db> {
+        let db = self.db;
+        let ty = ty.subst_vars(db, &self.frame().generics);
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, ty) => self.resolve_ty(ty),
+            _ => ty,
+        }
+    }
+
+    /// If `value` is a `String`, returns its text.
+    fn string_text(&self, value: &Value<'db>) -> Option<String> {
+        let db = self.db;
+        let Value::Object(obj) = value else {
+            return None;
+        };
+        let object = obj.object.borrow();
+        if well_known::string_class(db).ok() != Some(object.aggr) {
+            return None;
+        }
+        match object.fields[..] {
+            [
+                Value::Bytes(ref bytes),
+                Value::Primitive(Primitive::Int(_, length)),
+                ..,
+            ] => {
+                let length = usize::try_from(length).ok()?.min(bytes.len());
+                Some(String::from_utf8_lossy(&bytes[..length]).into_owned())
+            }
+            _ => None,
+        }
+    }
+
+    /// Renders `value` as `print` does.
+    fn display(&self, value: &Value<'db>) -> Result<String, AccessError> {
+        match value {
+            Value::Primitive(primitive) => Ok(primitive.to_string()),
+            Value::Bytes(bytes) => Ok(format!("b\"{}\"", bytes.escape_ascii())),
+            Value::Tuple(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| self.display(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() == 1 {
+                    Ok(format!("({},)", values[0]))
+                } else {
+                    Ok(format!("({})", values.join(", ")))
+                }
+            }
+            Value::Struct(aggr, fields) => self.display_aggregate(*aggr, fields),
+            Value::Object(obj) => {
+                obj.permission.read()?;
+                if let Some(text) = self.string_text(value) {
+                    return Ok(text);
+                }
+                let object = obj.object.borrow();
+                if let Some(text) = self.display_variant(object.aggr, &object.fields)? {
+                    return Ok(text);
+                }
+                self.display_aggregate(object.aggr, &object.fields)
+            }
+            Value::Uninitialized => Err(AccessError::Uninitialized),
+            Value::Given => Err(AccessError::Given),
+        }
+    }
+
+    /// Displays an `Option` or `Result` like the call that created it, e.g., `Some(1)` or `None`.
+    /// Returns `None` if `aggr` is some other class.
+    fn display_variant(
+        &self,
+        aggr: SymAggregate<'db>,
+        fields: &[Value<'db>],
+    ) -> Result<Option<String>, AccessError> {
+        let db = self.db;
+        let names: &[&str] = if well_known::option_class(db).ok() == Some(aggr) {
+            &["Some"]
+        } else if well_known::result_class(db).ok() == Some(aggr) {
+            &["Ok", "Err"]
+        } else {
+            return Ok(None);
+        };
+        for (name, value) in names.iter().zip(fields) {
+            if !matches!(value, Value::Uninitialized) {
+                return Ok(Some(format!("{name}({})", self.display(value)?)));
+            }
+        }
+        Ok(Some("None".to_string()))
+    }
+
+    fn display_aggregate(
+        &self,
+        aggr: SymAggregate<'db>,
+        fields: &[Value<'db>],
+    ) -> Result<String, AccessError> {
+        let db = self.db;
+        if fields.is_empty() {
+            return Ok(aggr.name(db).to_string());
+        }
+        let fields = aggr
+            .fields(db)
+            .zip(fields)
+            .map(|(field, value)| Ok(format!("{}: {}", field.name(db), self.display(value)?)))
+            .collect::<Result<Vec<_>, AccessError>>()?;
+        Ok(format!("{} {{ {} }}", aggr.name(db), fields.join(", ")))
+    }
+}
+
+/// Gives the value stored in `slot`, which was reached with `mode`.
+///
+/// Giving an owned value moves it, leaving `slot` marked as given.
+/// Giving a value reached through a lease or a shared object gives a lease or a copy instead.
+fn give<'db>(mode: PathMode, slot: &mut Value<'db>) -> Result<Value<'db>, AccessError> {
+    match mode {
+        PathMode::Owned => match slot {
+            Value::Object(obj) if obj.permission.kind().is_unique() => {
+                // The object moves to a new owner, which ends any leases of the old one.
+                obj.permission.read()?;
+                if obj.permission.kind() == PermissionKind::My {
+                    obj.permission.expire_tenants();
+                }
+                Ok(std::mem::replace(slot, Value::Given))
+            }
+            Value::Tuple(values) | Value::Struct(_, values) => {
+                let values = values
+                    .iter_mut()
+                    .map(|value| give(mode, value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                rebuild(slot, values)
+            }
+            _ => ref_of(slot),
+        },
+        PathMode::Mut => match slot {
+            Value::Object(obj) if obj.permission.kind().is_unique() => {
+                Ok(Value::Object(ObjectRef {
+                    object: obj.object.clone(),
+                    permission: obj.permission.lease(PermissionKind::Mut)?,
+                }))
+            }
+            Value::Tuple(values) | Value::Struct(_, values) => {
+                let values = values
+                    .iter_mut()
+                    .map(|value| give(mode, value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                rebuild(slot, values)
+            }
+            _ => ref_of(slot),
+        },
+        PathMode::Our | PathMode::Ref => ref_of(slot),
+    }
+}
+
+/// A shared copy of the value stored in `slot`: objects are leased as `ref`, other values copied.
+fn ref_of<'db>(slot: &Value<'db>) -> Result<Value<'db>, AccessError> {
+    match slot {
+        Value::Primitive(_) | Value::Bytes(_) => Ok(slot.clone()),
+        Value::Tuple(values) | Value::Struct(_, values) => {
+            let values = values.iter().map(ref_of).collect::<Result<Vec<_>, _>>()?;
+            rebuild(slot, values)
+        }
+        Value::Object(obj) => Ok(Value::Object(ObjectRef {
+            object: obj.object.clone(),
+            permission: obj.permission.lease(PermissionKind::Ref)?,
+        })),
+        Value::Uninitialized => Err(AccessError::Uninitialized),
+        Value::Given => Err(AccessError::Given),
+    }
+}
+
+/// Converts the objects owned by `value` to shared ownership (`our`).
+fn share<'db>(value: Value<'db>) -> Value<'db> {
+    match value {
+        Value::Object(obj) if obj.permission.kind() == PermissionKind::My => {
+            Value::Object(ObjectRef {
+                object: obj.object,
+                permission: Permission::new(PermissionKind::Our),
+            })
+        }
+        Value::Tuple(values) => Value::Tuple(values.into_iter().map(share).collect()),
+        Value::Struct(aggr, values) => Value::Struct(aggr, values.into_iter().map(share).collect()),
+        value => value,
+    }
+}
+
+/// A tuple or struct like `template`, but with the given `values`.
+fn rebuild<'db>(template: &Value<'db>, values: Vec<Value<'db>>) -> Result<Value<'db>, AccessError> {
+    match *template {
+        Value::Tuple(_) => Ok(Value::Tuple(values)),
+        Value::Struct(aggr, _) => Ok(Value::Struct(aggr, values)),
+        _ => Err(AccessError::Internal("expected a tuple or a struct")),
+    }
+}
+
+fn field_index<'db>(
+    db: &'db dyn Db,
+    aggr: SymAggregate<'db>,
+    field: SymField<'db>,
+) -> Result<usize, AccessError> {
+    aggr.fields(db)
+        .position(|f| f == field)
+        .ok_or(AccessError::Internal("field of another aggregate"))
+}
+
+/// True if `destructor` takes `mut self` (rather than `my self`).
+fn takes_mut_self<'db>(db: &'db dyn Db, destructor: SymFunction<'db>) -> bool {
+    match destructor.source(db).inputs(db).first() {
+        Some(AstFunctionInput::SelfArg(self_arg)) => matches!(
+            self_arg.perm(db).map(|perm| perm.kind(db)),
+            Some(AstPermKind::Mutable(_))
+        ),
+        _ => false,
+    }
+}
+
+/// Creates an owned instance of `aggr` (an `Option` or a `Result`),
+/// only some of whose `fields` have a value, as `None`, `Ok`, and `Err` do.
+fn variant_object<'db>(
+    db: &'db dyn Db,
+    span: Span<'db>,
+    aggr: Errors<SymAggregate<'db>>,
+    ty_args: Vec<SymGenericTerm<'db>>,
+    fields: Vec<Value<'db>>,
+) -> Result<Value<'db>, RuntimeError> {
+    let Ok(aggr) = aggr else {
+        return Err(compile_error(db, span));
+    };
+    Ok(Value::Object(ObjectRef {
+        object: Rc::new(RefCell::new(Object {
+            aggr,
+            ty_args,
+            fields,
+            queue: VecDeque::new(),
+        })),
+        permission: Permission::new(PermissionKind::My),
+    }))
+}
+
+/// The error for code that cannot execute because it failed to type check.
+fn compile_error<'db>(db: &'db dyn Db, span: Span<'db>) -> RuntimeError {
+    RuntimeError::new(db, span, "cannot execute code that failed to type check")
+}
+
+/// The error for a value that does not have the shape its type promises.
+/// The type checker rules these out, so they indicate a bug in the compiler.
+fn internal_error<'db>(db: &'db dyn Db, span: Span<'db>, message: &str) -> RuntimeError {
+    RuntimeError::new(db, span, format!("internal error: {message}"))
+}
+
+/// The object of the well-known `Channel` referenced by the `self` argument of one of its methods.
+fn channel_object<'db>(channel: &Value<'db>) -> Result<Rc<RefCell<Object<'db>>>, AccessError> {
+    match channel {
+        Value::Object(obj) => Ok(obj.object.clone()),
+        _ => Err(AccessError::Internal(
+            "channel method called on a non-object",
+        )),
+    }
+}
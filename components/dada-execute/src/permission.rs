@@ -0,0 +1,186 @@
+//! Runtime permissions, which track whether a reference to an object may still be used.
+//!
+//! Every object value carries a [`Permission`]. Owned (`my`) and shared (`our`) permissions
+//! are created along with the object; leases (`ref` and `mut`) are created from another
+//! permission and become its *tenants*. A tenant expires when its lessor is used in a way
+//! that conflicts with it:
+//!
+//! * writing through a permission, or leasing it as `mut`, expires all of its tenants;
+//! * reading through a permission expires its `mut` tenants;
+//! * giving away or dropping an owned object expires all of its tenants.
+//!
+//! Expiring a permission expires its tenants in turn. Using an expired permission is
+//! a runtime error: the type checker is meant to rule such programs out, so the interpreter
+//! reports them rather than silently reading stale data.
+
+use std::{cell::RefCell, rc::Rc};
+
+/// The kinds of permission a value can have at runtime.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PermissionKind {
+    /// Unique ownership: the holder drops the object.
+    My,
+
+    /// Shared ownership: the object is immutable and is never dropped.
+    Our,
+
+    /// A shared lease (`ref`): the object may be read, but not written.
+    Ref,
+
+    /// A mutable lease (`mut`): the object may be read and written.
+    Mut,
+}
+
+impl PermissionKind {
+    /// True if the holder may write to the object.
+    pub(crate) fn is_unique(self) -> bool {
+        match self {
+            PermissionKind::My | PermissionKind::Mut => true,
+            PermissionKind::Our | PermissionKind::Ref => false,
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionKind::My => write!(f, "my"),
+            PermissionKind::Our => write!(f, "our"),
+            PermissionKind::Ref => write!(f, "ref"),
+            PermissionKind::Mut => write!(f, "mut"),
+        }
+    }
+}
+
+/// A permission to access an object.
+/// Clones refer to the same permission (e.g., copies of a shared value).
+#[derive(Clone, Debug)]
+pub(crate) struct Permission {
+    data: Rc<RefCell<PermissionData>>,
+}
+
+#[derive(Debug)]
+struct PermissionData {
+    kind: PermissionKind,
+
+    /// Set once this permission can no longer be used.
+    expired: bool,
+
+    /// Leases created from this permission.
+    tenants: Vec<Permission>,
+}
+
+/// Reasons that using a permission fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PermissionError {
+    /// The permission has expired.
+    Expired(PermissionKind),
+
+    /// The permission does not allow writing (or leasing as `mut`).
+    NotUnique(PermissionKind),
+}
+
+impl std::fmt::Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionError::Expired(kind) => {
+                write!(f, "use of a `{kind}` permission that has expired")
+            }
+            PermissionError::NotUnique(kind) => {
+                write!(f, "cannot mutate through a `{kind}` permission")
+            }
+        }
+    }
+}
+
+impl Permission {
+    /// Creates a fresh permission of the given kind, with no tenants.
+    pub(crate) fn new(kind: PermissionKind) -> Self {
+        Self {
+            data: Rc::new(RefCell::new(PermissionData {
+                kind,
+                expired: false,
+                tenants: vec![],
+            })),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> PermissionKind {
+        self.data.borrow().kind
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.data.borrow().expired
+    }
+
+    /// Checks that the object may be read through this permission,
+    /// expiring any `mut` leases created from it.
+    pub(crate) fn read(&self) -> Result<(), PermissionError> {
+        self.check_valid()?;
+        self.data.borrow_mut().tenants.retain(|tenant| {
+            if tenant.kind() == PermissionKind::Mut {
+                tenant.expire();
+                false
+            } else {
+                true
+            }
+        });
+        Ok(())
+    }
+
+    /// Checks that the object may be written through this permission,
+    /// expiring all leases created from it.
+    pub(crate) fn write(&self) -> Result<(), PermissionError> {
+        self.check_valid()?;
+        if !self.kind().is_unique() {
+            return Err(PermissionError::NotUnique(self.kind()));
+        }
+        self.expire_tenants();
+        Ok(())
+    }
+
+    /// Creates a lease of the given kind (`Ref` or `Mut`) from this permission.
+    ///
+    /// Leasing a shared owner (`our`) as `ref` just copies it,
+    /// since the object can never be mutated or dropped anyway.
+    pub(crate) fn lease(&self, kind: PermissionKind) -> Result<Permission, PermissionError> {
+        match kind {
+            PermissionKind::Ref => {
+                self.read()?;
+                if self.kind() == PermissionKind::Our {
+                    return Ok(self.clone());
+                }
+            }
+            PermissionKind::Mut => self.write()?,
+            PermissionKind::My | PermissionKind::Our => {
+                panic!("cannot lease a `{kind}` permission")
+            }
+        }
+
+        let tenant = Permission::new(kind);
+        self.data.borrow_mut().tenants.push(tenant.clone());
+        Ok(tenant)
+    }
+
+    /// Expires this permission and, transitively, all of its tenants.
+    pub(crate) fn expire(&self) {
+        self.data.borrow_mut().expired = true;
+        self.expire_tenants();
+    }
+
+    /// Expires all tenants of this permission, but not the permission itself.
+    pub(crate) fn expire_tenants(&self) {
+        let tenants = std::mem::take(&mut self.data.borrow_mut().tenants);
+        for tenant in tenants {
+            tenant.expire();
+        }
+    }
+
+    fn check_valid(&self) -> Result<(), PermissionError> {
+        if self.is_expired() {
+            Err(PermissionError::Expired(self.kind()))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use dada_ir_sym::ir::{
+    exprs::{SymBinaryOp, SymLiteral},
+    primitive::SymPrimitiveKind,
+};
+
+use crate::value::{IntKind, Primitive};
+
+/// Interprets the bits of `literal` as a value of the primitive type `kind`,
+/// or returns `None` if a literal of its kind cannot have that type.
+pub(crate) fn literal(kind: SymPrimitiveKind, literal: SymLiteral) -> Option<Primitive> {
+    match (kind, literal) {
+        (SymPrimitiveKind::Bool, SymLiteral::Integral { bits }) => Some(Primitive::Bool(bits != 0)),
+        (SymPrimitiveKind::Char, SymLiteral::Integral { bits }) => Some(to_char(bits)),
+        (SymPrimitiveKind::Float { bits }, SymLiteral::Float { bits: value }) => {
+            Some(float(bits, value.0))
+        }
+        (_, SymLiteral::Integral { bits }) => {
+            let int_kind = IntKind::from_primitive(kind)?;
+            Some(Primitive::Int(int_kind, int_kind.wrap(i128::from(bits))))
+        }
+        (_, SymLiteral::Float { .. }) => None,
+    }
+}
+
+/// Errors that a binary operation on primitives can raise.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ArithmeticError {
+    DivisionByZero,
+    DivisionOverflow,
+
+    /// The operands are not both of a type that the operation applies to,
+    /// which the type checker rules out.
+    InvalidOperands,
+}
+
+impl std::fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Same messages as the trap table of generated code.
+        match self {
+            ArithmeticError::DivisionByZero => write!(f, "attempt to divide by zero"),
+            ArithmeticError::DivisionOverflow => write!(f, "attempt to divide with overflow"),
+            ArithmeticError::InvalidOperands => {
+                write!(
+                    f,
+                    "internal error: binary operation on mismatched primitives"
+                )
+            }
+        }
+    }
+}
+
+/// Applies `op` to two primitives of the same type.
+/// Integer arithmetic wraps around, as it does in generated code.
+pub(crate) fn binary_op(
+    op: SymBinaryOp,
+    lhs: Primitive,
+    rhs: Primitive,
+) -> Result<Primitive, ArithmeticError> {
+    match (lhs, rhs) {
+        (Primitive::Int(kind, a), Primitive::Int(_, b)) => {
+            let value = match op {
+                SymBinaryOp::Add => a.wrapping_add(b),
+                SymBinaryOp::Sub => a.wrapping_sub(b),
+                SymBinaryOp::Mul => a.wrapping_mul(b),
+                SymBinaryOp::Div => {
+                    if b == 0 {
+                        return Err(ArithmeticError::DivisionByZero);
+                    }
+                    if kind.signed && a == kind.min() && b == -1 {
+                        return Err(ArithmeticError::DivisionOverflow);
+                    }
+                    a / b
+                }
+                _ => return compare(op, a.cmp(&b)),
+            };
+            Ok(Primitive::Int(kind, kind.wrap(value)))
+        }
+        (Primitive::Float(bits, a), Primitive::Float(_, b)) => match op {
+            SymBinaryOp::Add => Ok(float(bits, a + b)),
+            SymBinaryOp::Sub => Ok(float(bits, a - b)),
+            SymBinaryOp::Mul => Ok(float(bits, a * b)),
+            SymBinaryOp::Div => Ok(float(bits, a / b)),
+            _ => match a.partial_cmp(&b) {
+                Some(ordering) => compare(op, ordering),
+                // Every comparison with NaN is false.
+                None => Ok(Primitive::Bool(false)),
+            },
+        },
+        (Primitive::Bool(a), Primitive::Bool(b)) => compare(op, a.cmp(&b)),
+        (Primitive::Char(a), Primitive::Char(b)) => compare(op, a.cmp(&b)),
+        _ => Err(ArithmeticError::InvalidOperands),
+    }
+}
+
+/// Converts the numeric primitive `value` to the numeric type `kind`,
+/// with the same semantics as generated code: integers wrap around,
+/// and floats are truncated towards zero and saturate at the bounds of the integer type.
+/// A `char` converts to and from its Unicode scalar value.
+/// Returns `None` for casts that are not between numeric types.
+pub(crate) fn cast(kind: SymPrimitiveKind, value: Primitive) -> Option<Primitive> {
+    match (kind, value) {
+        (SymPrimitiveKind::Char, Primitive::Int(_, value)) => Some(to_char(value)),
+        (_, Primitive::Char(value)) => {
+            let int_kind = IntKind::from_primitive(kind)?;
+            Some(Primitive::Int(int_kind, i128::from(u32::from(value))))
+        }
+        (SymPrimitiveKind::Float { bits }, Primitive::Int(_, value)) => {
+            Some(float(bits, value as f64))
+        }
+        (SymPrimitiveKind::Float { bits }, Primitive::Float(_, value)) => Some(float(bits, value)),
+        (_, Primitive::Int(_, value)) => {
+            let int_kind = IntKind::from_primitive(kind)?;
+            Some(Primitive::Int(int_kind, int_kind.wrap(value)))
+        }
+        (_, Primitive::Float(_, value)) => {
+            let int_kind = IntKind::from_primitive(kind)?;
+            // NaN converts to zero, as with Rust's `as`.
+            Some(Primitive::Int(
+                int_kind,
+                (value as i128).clamp(int_kind.min(), int_kind.max()),
+            ))
+        }
+        (_, Primitive::Bool(_)) => None,
+    }
+}
+
+/// The result of the comparison `op` on operands ordered by `ordering`.
+/// Arithmetic does not apply to the operands that reach here (e.g., `bool`s).
+fn compare(op: SymBinaryOp, ordering: std::cmp::Ordering) -> Result<Primitive, ArithmeticError> {
+    Ok(Primitive::Bool(match op {
+        SymBinaryOp::GreaterThan => ordering.is_gt(),
+        SymBinaryOp::LessThan => ordering.is_lt(),
+        SymBinaryOp::GreaterEqual => ordering.is_ge(),
+        SymBinaryOp::LessEqual => ordering.is_le(),
+        SymBinaryOp::EqualEqual => ordering.is_eq(),
+        SymBinaryOp::Add | SymBinaryOp::Sub | SymBinaryOp::Mul | SymBinaryOp::Div => {
+            return Err(ArithmeticError::InvalidOperands);
+        }
+    }))
+}
+
+/// The `char` whose scalar value is `value`, or the replacement character `�`
+/// if there is none (as in generated code).
+fn to_char(value: impl TryInto<u32>) -> Primitive {
+    Primitive::Char(
+        value
+            .try_into()
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+    )
+}
+
+fn float(bits: u32, value: f64) -> Primitive {
+    if bits <= 32 {
+        Primitive::Float(bits, f64::from(value as f32))
+    } else {
+        Primitive::Float(bits, value)
+    }
+}
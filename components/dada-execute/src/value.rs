@@ -0,0 +1,149 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, sync::Arc};
+
+use dada_ir_sym::ir::{classes::SymAggregate, primitive::SymPrimitiveKind, types::SymGenericTerm};
+
+use crate::permission::Permission;
+
+/// A value stored in a variable or field.
+#[derive(Clone, Debug)]
+pub(crate) enum Value<'db> {
+    /// A value of primitive type.
+    Primitive(Primitive),
+
+    /// A `Pointer[u8]` to the bytes of a byte literal.
+    Bytes(Arc<[u8]>),
+
+    /// A tuple, including `()`.
+    Tuple(Vec<Value<'db>>),
+
+    /// A struct value, stored inline like a tuple.
+    Struct(SymAggregate<'db>, Vec<Value<'db>>),
+
+    /// A class object, accessed through a permission.
+    Object(ObjectRef<'db>),
+
+    /// A variable declared without an initializer that has not been assigned yet.
+    Uninitialized,
+
+    /// A place whose value has been given away.
+    Given,
+}
+
+impl<'db> Value<'db> {
+    pub(crate) fn unit() -> Self {
+        Value::Tuple(vec![])
+    }
+
+    pub(crate) fn bool(value: bool) -> Self {
+        Value::Primitive(Primitive::Bool(value))
+    }
+}
+
+/// A reference to a class object together with the permission it grants.
+#[derive(Clone, Debug)]
+pub(crate) struct ObjectRef<'db> {
+    pub(crate) object: Rc<RefCell<Object<'db>>>,
+    pub(crate) permission: Permission,
+}
+
+/// The data of a class object.
+#[derive(Debug)]
+pub(crate) struct Object<'db> {
+    /// The class of the object.
+    pub(crate) aggr: SymAggregate<'db>,
+
+    /// The generic arguments of the class, needed to call its destructor.
+    pub(crate) ty_args: Vec<SymGenericTerm<'db>>,
+
+    /// The values of its fields, in declaration order.
+    pub(crate) fields: Vec<Value<'db>>,
+
+    /// Values sent on a well-known `Channel` and not yet received.
+    /// Always empty for objects of other classes.
+    pub(crate) queue: VecDeque<Value<'db>>,
+}
+
+/// A value of primitive type.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Primitive {
+    Bool(bool),
+    Char(char),
+
+    /// An integer, always in the range of its kind.
+    Int(IntKind, i128),
+
+    /// A floating point number with the given number of bits.
+    /// 32-bit floats are rounded to `f32` after each operation.
+    Float(u32, f64),
+}
+
+/// The size and signedness of an integer type.
+/// `isize` and `usize` have 32 bits, as in the wasm we generate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct IntKind {
+    pub(crate) signed: bool,
+    pub(crate) bits: u32,
+}
+
+impl IntKind {
+    pub(crate) fn from_primitive(kind: SymPrimitiveKind) -> Option<Self> {
+        match kind {
+            SymPrimitiveKind::Int { bits } => Some(IntKind { signed: true, bits }),
+            SymPrimitiveKind::Uint { bits } => Some(IntKind {
+                signed: false,
+                bits,
+            }),
+            SymPrimitiveKind::Isize => Some(IntKind {
+                signed: true,
+                bits: 32,
+            }),
+            SymPrimitiveKind::Usize => Some(IntKind {
+                signed: false,
+                bits: 32,
+            }),
+            SymPrimitiveKind::Bool | SymPrimitiveKind::Char | SymPrimitiveKind::Float { .. } => {
+                None
+            }
+        }
+    }
+
+    /// Wraps `value` into the range of this kind, as two's complement arithmetic would.
+    pub(crate) fn wrap(self, value: i128) -> i128 {
+        let modulus = 1i128 << self.bits;
+        let value = value.rem_euclid(modulus);
+        if self.signed && value >= modulus / 2 {
+            value - modulus
+        } else {
+            value
+        }
+    }
+
+    /// The smallest value of this kind.
+    pub(crate) fn min(self) -> i128 {
+        if self.signed {
+            -(1i128 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    /// The largest value of this kind.
+    pub(crate) fn max(self) -> i128 {
+        if self.signed {
+            (1i128 << (self.bits - 1)) - 1
+        } else {
+            (1i128 << self.bits) - 1
+        }
+    }
+}
+
+impl std::fmt::Display for Primitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Primitive::Bool(b) => write!(f, "{b}"),
+            Primitive::Char(c) => write!(f, "{c}"),
+            Primitive::Int(_, i) => write!(f, "{i}"),
+            Primitive::Float(_, x) => write!(f, "{x}"),
+        }
+    }
+}
@@ -10,6 +10,10 @@ use crate::{
     span::{Span, Spanned},
 };
 
+mod attribute;
+pub use attribute::*;
+mod doc_comment;
+pub use doc_comment::*;
 mod use_item;
 pub use use_item::*;
 mod class_item;
@@ -49,6 +53,16 @@ impl<'db> Identifier<'db> {
         Identifier::new(db, "new")
     }
 
+    /// Create interned "drop" identifier, the name of a class's destructor
+    pub fn drop_ident(db: &'db dyn crate::Db) -> Identifier<'db> {
+        Identifier::new(db, "drop")
+    }
+
+    /// Create interned "_" identifier, the placeholder for an inferred generic argument
+    pub fn underscore(db: &'db dyn crate::Db) -> Identifier<'db> {
+        Identifier::new(db, "_")
+    }
+
     /// Create interned "self" identifier
     pub fn self_ident(db: &'db dyn crate::Db) -> Identifier<'db> {
         Identifier::new(db, "self")
@@ -72,6 +86,10 @@ impl std::fmt::Display for Identifier<'_> {
 pub struct AstModule<'db> {
     pub name: Identifier<'db>,
 
+    /// Attributes applying to the whole module (`#![...]`)
+    #[return_ref]
+    pub attributes: Vec<AstAttribute<'db>>,
+
     #[return_ref]
     pub items: SpanVec<'db, AstItem<'db>>,
 }
@@ -90,6 +108,7 @@ pub enum AstItem<'db> {
     Use(AstUse<'db>),
     Aggregate(AstAggregate<'db>),
     Function(AstFunction<'db>),
+    ExternFunction(AstExternFunction<'db>),
     MainFunction(AstMainFunction<'db>),
 }
 
@@ -0,0 +1,28 @@
+use salsa::Update;
+use serde::Serialize;
+
+use super::{SpanVec, SpannedIdentifier};
+use crate::span::{Span, Spanned};
+
+/// `#[name(arg, ...)]` attached to the item that follows it,
+/// or `#![name(arg, ...)]` at the start of a file, applying to the whole module.
+///
+/// The only attributes currently understood are the lint levels
+/// `allow`, `warn`, and `deny`, whose arguments name lints.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstAttribute<'db> {
+    /// Span of the entire attribute, including the `#[` and `]`
+    pub span: Span<'db>,
+
+    /// Name of the attribute (e.g., `allow`)
+    pub name: SpannedIdentifier<'db>,
+
+    /// Arguments in parentheses (if any)
+    pub args: Option<SpanVec<'db, SpannedIdentifier<'db>>>,
+}
+
+impl<'db> Spanned<'db> for AstAttribute<'db> {
+    fn span(&self, _db: &'db dyn crate::Db) -> Span<'db> {
+        self.span
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     span::{Span, Spanned},
 };
 
-use super::{AstGenericDecl, AstWhereClauses, Identifier, SpanVec};
+use super::{AstAttribute, AstDocComment, AstGenericDecl, AstWhereClauses, Identifier, SpanVec};
 
 /// Some kind of aggregate, like a class, struct, etc.
 ///
@@ -16,6 +16,14 @@ use super::{AstGenericDecl, AstWhereClauses, Identifier, SpanVec};
 pub struct AstAggregate<'db> {
     pub span: Span<'db>,
 
+    /// Doc comment preceding the declaration
+    #[return_ref]
+    pub doc_comment: Option<AstDocComment<'db>>,
+
+    /// Attributes (e.g., `#[allow(...)]`) preceding the declaration
+    #[return_ref]
+    pub attributes: Vec<AstAttribute<'db>>,
+
     /// Visibility of the class
     pub visibility: Option<AstVisibility<'db>>,
 
@@ -0,0 +1,21 @@
+use salsa::Update;
+use serde::Serialize;
+
+use crate::span::{Span, Spanned};
+
+/// The `##` comments preceding an item, which document it.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstDocComment<'db> {
+    /// Span from the first `##` to the end of the last line
+    pub span: Span<'db>,
+
+    /// The markdown text: each line with its leading `##` (and one space) removed,
+    /// joined by newlines.
+    pub text: String,
+}
+
+impl<'db> Spanned<'db> for AstDocComment<'db> {
+    fn span(&self, _db: &'db dyn crate::Db) -> Span<'db> {
+        self.span
+    }
+}
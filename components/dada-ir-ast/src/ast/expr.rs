@@ -4,19 +4,47 @@ use serde::Serialize;
 
 use crate::span::{Span, Spanned};
 
-use super::{AstGenericTerm, AstPath, AstTy, DeferredParse, SpanVec, SpannedIdentifier};
+use super::{
+    AstAggregate, AstFunction, AstGenericTerm, AstPath, AstTy, DeferredParse, SpanVec,
+    SpannedIdentifier,
+};
 
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct AstBlock<'db> {
+    /// Span of the block, including the `{}`
+    pub span: Span<'db>,
+
     #[return_ref]
     pub statements: SpanVec<'db, AstStatement<'db>>,
 }
 
+impl<'db> AstBlock<'db> {
+    /// The last statement of this block if it is not an expression.
+    /// A block's value is its trailing expression, so such a block evaluates to `()`.
+    pub fn trailing_statement(self, db: &'db dyn crate::Db) -> Option<&'db AstStatement<'db>> {
+        self.statements(db)
+            .last()
+            .filter(|s| !matches!(s, AstStatement::Expr(_)))
+    }
+}
+
+impl<'db> Spanned<'db> for AstBlock<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        AstBlock::span(*self, db)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, FromImpls, Serialize)]
 pub enum AstStatement<'db> {
     Let(AstLetStatement<'db>),
     Expr(AstExpr<'db>),
+
+    /// A class or struct declared within a function body
+    Aggregate(AstAggregate<'db>),
+
+    /// A function declared within a function body
+    Function(AstFunction<'db>),
 }
 
 impl<'db> Spanned<'db> for AstStatement<'db> {
@@ -24,21 +52,56 @@ impl<'db> Spanned<'db> for AstStatement<'db> {
         match self {
             AstStatement::Let(s) => s.span(db),
             AstStatement::Expr(e) => e.span,
+            AstStatement::Aggregate(a) => a.span(db),
+            AstStatement::Function(f) => f.span(db),
         }
     }
 }
 
-/// `let x = v`, `let x: t = v`, etc
+/// `let x = v`, `let x: t = v`, `let (a, b) = v`, `let Point { x, y } = v`, etc
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct AstLetStatement<'db> {
     pub span: Span<'db>,
     pub mutable: Option<Span<'db>>,
-    pub name: SpannedIdentifier<'db>,
+    #[return_ref]
+    pub pattern: AstPattern<'db>,
     pub ty: Option<AstTy<'db>>,
     pub initializer: Option<AstExpr<'db>>,
 }
 
+/// The pattern bound by a `let`, e.g., `x`, `(a, b)`, or `Point { x, y }`
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub enum AstPattern<'db> {
+    /// `x`
+    Id(SpannedIdentifier<'db>),
+
+    /// `(p0, p1, ...)`, destructuring a tuple. The span includes the `()`.
+    Tuple(SpanVec<'db, AstPattern<'db>>),
+
+    /// `Class { f0, f1.ref, ... }`, destructuring an instance of a class.
+    /// The span of the fields includes the `{}`.
+    Aggregate(AstPath<'db>, SpanVec<'db, AstFieldPattern<'db>>),
+}
+
+impl<'db> Spanned<'db> for AstPattern<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        match self {
+            AstPattern::Id(id) => id.span,
+            AstPattern::Tuple(patterns) => patterns.span,
+            AstPattern::Aggregate(path, fields) => path.span(db).to(db, fields.span),
+        }
+    }
+}
+
+/// A field in a class pattern, binding a variable with the same name as the field.
+/// `f` gives the field to the variable; `f.ref` (etc) applies the given permission instead.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstFieldPattern<'db> {
+    pub name: SpannedIdentifier<'db>,
+    pub op: Option<PermissionOp>,
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub struct AstExpr<'db> {
     pub span: Span<'db>,
@@ -77,30 +140,81 @@ pub enum AstExprKind<'db> {
     /// until we have resolved the expression `E`.
     SquareBracketOp(AstExpr<'db>, SquareBracketArgs<'db>),
 
-    /// `E(expr0, expr1, ..., exprN)`
+    /// `E(expr0, expr1, ..., exprN)` or `E(name: expr)`
     ///
     /// Note that the callee expression could also be
     /// a `DotId` in which case this is a method call
     /// as well as a `SquareBracketsOp`.
-    ParenthesisOp(AstExpr<'db>, SpanVec<'db, AstExpr<'db>>),
+    ParenthesisOp(AstExpr<'db>, SpanVec<'db, AstCallArg<'db>>),
 
     /// `(a, b, c)`
     ///
     /// Could also be `(a)`.
     Tuple(SpanVec<'db, AstExpr<'db>>),
 
-    /// `a { field: value }`
-    Constructor(AstPath<'db>, SpanVec<'db, AstConstructorField<'db>>),
+    /// `a { field: value }` or `a { ..base, field: value }`
+    ///
+    /// The final component is the `base` expression, if any.
+    Constructor(
+        AstPath<'db>,
+        SpanVec<'db, AstConstructorField<'db>>,
+        Option<AstExpr<'db>>,
+    ),
 
     /// `return x`
     Return(Option<AstExpr<'db>>),
 
+    /// `assert x`
+    Assert(AstExpr<'db>),
+
+    /// `atomic { ... }`
+    Atomic(AstBlock<'db>),
+
+    /// `spawn { ... }`
+    Spawn(AstBlock<'db>),
+
+    /// `{ ... }` following a call, as in `list.each() { ... }`
+    ///
+    /// Parsed as the final argument of the call.
+    /// Closures are not yet supported, so the type checker rejects these.
+    Closure(AstBlock<'db>),
+
+    /// `loop { ... }` or `'label: loop { ... }`
+    ///
+    /// The label's span includes the `'`.
+    Loop {
+        label: Option<SpannedIdentifier<'db>>,
+        body: AstBlock<'db>,
+    },
+
+    /// `while condition { ... }` or `'label: while condition { ... }`
+    While {
+        label: Option<SpannedIdentifier<'db>>,
+        condition: AstExpr<'db>,
+        body: AstBlock<'db>,
+    },
+
+    /// `break`, `break value`, `break 'label`, or `break 'label value`
+    Break {
+        label: Option<SpannedIdentifier<'db>>,
+        value: Option<AstExpr<'db>>,
+    },
+
+    /// `continue` or `continue 'label`
+    Continue(Option<SpannedIdentifier<'db>>),
+
     /// `x.await`
     Await {
         future: AstExpr<'db>,
         await_keyword: Span<'db>,
     },
 
+    /// `x?`, which returns the error from the enclosing function if `x` is an `Err`
+    Try {
+        value: AstExpr<'db>,
+        question_mark: Span<'db>,
+    },
+
     /// `x.lease`, `x.ref`, or `x.give`
     PermissionOp {
         value: AstExpr<'db>,
@@ -113,6 +227,9 @@ pub enum AstExprKind<'db> {
     /// `!foo` etc
     UnaryOp(SpannedUnaryOp<'db>, AstExpr<'db>),
 
+    /// `x as T`
+    Cast(AstExpr<'db>, AstTy<'db>),
+
     /// If/else-if chain
     If(Vec<IfArm<'db>>),
 }
@@ -130,10 +247,24 @@ pub struct IfArm<'db> {
     /// if None, this is an `else` (and should come last)
     pub condition: Option<AstExpr<'db>>,
 
+    /// for `if let Some(x) = condition`, the `Some(x)`
+    pub binding: Option<AstIfLet<'db>>,
+
     /// the value
     pub result: AstBlock<'db>,
 }
 
+/// `Some(pattern)` in `if let Some(pattern) = value { ... }`,
+/// which executes the block if the option `value` holds a value.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstIfLet<'db> {
+    /// the `Some`
+    pub some: SpannedIdentifier<'db>,
+
+    /// the pattern that binds the value
+    pub pattern: AstPattern<'db>,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub struct SpannedBinaryOp<'db> {
     pub span: Span<'db>,
@@ -154,6 +285,50 @@ pub enum AstBinaryOp {
     LessEqual,
     EqualEqual,
     Assign,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+}
+
+impl AstBinaryOp {
+    /// True for the operators that compare their operands and produce a `bool`.
+    pub fn is_comparison(self) -> bool {
+        match self {
+            AstBinaryOp::GreaterThan
+            | AstBinaryOp::LessThan
+            | AstBinaryOp::GreaterEqual
+            | AstBinaryOp::LessEqual
+            | AstBinaryOp::EqualEqual => true,
+            AstBinaryOp::Add
+            | AstBinaryOp::Sub
+            | AstBinaryOp::Mul
+            | AstBinaryOp::Div
+            | AstBinaryOp::AndAnd
+            | AstBinaryOp::OrOr
+            | AstBinaryOp::Assign
+            | AstBinaryOp::AddAssign
+            | AstBinaryOp::SubAssign
+            | AstBinaryOp::MulAssign
+            | AstBinaryOp::DivAssign => false,
+        }
+    }
+
+    /// True for `=` and the compound assignments like `+=`.
+    pub fn is_assignment(self) -> bool {
+        self == AstBinaryOp::Assign || self.compound_assignment_op().is_some()
+    }
+
+    /// For a compound assignment like `+=`, the arithmetic operator it applies (`+`).
+    pub fn compound_assignment_op(self) -> Option<AstBinaryOp> {
+        match self {
+            AstBinaryOp::AddAssign => Some(AstBinaryOp::Add),
+            AstBinaryOp::SubAssign => Some(AstBinaryOp::Sub),
+            AstBinaryOp::MulAssign => Some(AstBinaryOp::Mul),
+            AstBinaryOp::DivAssign => Some(AstBinaryOp::Div),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AstBinaryOp {
@@ -171,6 +346,10 @@ impl std::fmt::Display for AstBinaryOp {
             AstBinaryOp::LessEqual => write!(f, "<="),
             AstBinaryOp::EqualEqual => write!(f, "=="),
             AstBinaryOp::Assign => write!(f, "="),
+            AstBinaryOp::AddAssign => write!(f, "+="),
+            AstBinaryOp::SubAssign => write!(f, "-="),
+            AstBinaryOp::MulAssign => write!(f, "*="),
+            AstBinaryOp::DivAssign => write!(f, "/="),
         }
     }
 }
@@ -197,6 +376,23 @@ pub struct SquareBracketArgs<'db> {
     pub deferred: DeferredParse<'db>,
 }
 
+/// An argument to a call: either positional (`expr`) or named (`name: expr`).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstCallArg<'db> {
+    /// The parameter name, if this is a named argument.
+    pub name: Option<SpannedIdentifier<'db>>,
+    pub value: AstExpr<'db>,
+}
+
+impl<'db> Spanned<'db> for AstCallArg<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        match &self.name {
+            Some(name) => name.span.to(db, self.value.span),
+            None => self.value.span,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub struct AstConstructorField<'db> {
     pub name: SpannedIdentifier<'db>,
@@ -214,6 +410,7 @@ pub struct Literal<'db> {
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub enum LiteralKind {
     Boolean,
+    Char,
     Integer,
     String,
 }
@@ -2,7 +2,10 @@ use dada_util::{FromImpls, SalsaSerialize};
 use salsa::Update;
 use serde::Serialize;
 
-use super::{AstGenericDecl, AstPerm, AstStatement, AstTy, SpanVec, SpannedIdentifier};
+use super::{
+    AstAttribute, AstDocComment, AstExpr, AstGenericDecl, AstPerm, AstStatement, AstTy, Literal,
+    SpanVec, SpannedIdentifier,
+};
 use crate::{
     ast::{AstVisibility, AstWhereClauses, DeferredParse},
     span::{Span, Spanned},
@@ -15,6 +18,14 @@ pub struct AstFunction<'db> {
     /// Overall span of the function declaration
     pub span: Span<'db>,
 
+    /// Doc comment preceding the declaration
+    #[return_ref]
+    pub doc_comment: Option<AstDocComment<'db>>,
+
+    /// Attributes (e.g., `#[allow(...)]`) preceding the declaration
+    #[return_ref]
+    pub attributes: Vec<AstAttribute<'db>>,
+
     /// Declared effects (e.g., `async`)
     pub effects: AstFunctionEffects<'db>,
 
@@ -47,6 +58,43 @@ pub struct AstFunction<'db> {
     pub body: Option<DeferredParse<'db>>,
 }
 
+/// `extern "module" fn foo(x: u32)` -- a function implemented by the host
+/// and imported into the wasm module.
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct AstExternFunction<'db> {
+    /// Overall span of the declaration, including the `extern` keyword
+    pub span: Span<'db>,
+
+    /// Span of the `extern` keyword
+    pub extern_span: Span<'db>,
+
+    /// The string literal naming the module to import from, if provided
+    pub import_module: Option<Literal<'db>>,
+
+    /// The function signature (extern functions are not meant to have a body)
+    pub function: AstFunction<'db>,
+}
+
+impl<'db> AstExternFunction<'db> {
+    /// The module to import from when none is given.
+    pub const DEFAULT_IMPORT_MODULE: &'static str = "env";
+
+    /// The name of the module the function is imported from.
+    pub fn import_module_name(self, db: &'db dyn crate::Db) -> &'db str {
+        match self.import_module(db) {
+            Some(literal) => literal.text(db),
+            None => Self::DEFAULT_IMPORT_MODULE,
+        }
+    }
+}
+
+impl<'db> Spanned<'db> for AstExternFunction<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        AstExternFunction::span(*self, db)
+    }
+}
+
 /// `print("Hello world")` appearing at the top of a module.
 /// This creates an implicit `fn main() { ... }` later on.
 #[derive(SalsaSerialize)]
@@ -127,6 +175,10 @@ pub struct VariableDecl<'db> {
     /// Variable type, excluding any permission,
     /// which can be found in `perm`.
     pub base_ty: AstTy<'db>,
+
+    /// Default value (`name: T = expr`), if any.
+    /// Only permitted on function parameters.
+    pub default: Option<AstExpr<'db>>,
 }
 
 impl<'db> Spanned<'db> for VariableDecl<'db> {
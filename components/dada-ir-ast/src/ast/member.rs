@@ -7,7 +7,7 @@ use crate::{
     span::{Span, Spanned},
 };
 
-use super::{AstFunction, VariableDecl};
+use super::{AstDocComment, AstFunction, VariableDecl};
 
 #[derive(
     Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, FromImpls, Serialize,
@@ -30,6 +30,11 @@ impl<'db> Spanned<'db> for AstMember<'db> {
 #[salsa::tracked(debug)]
 pub struct AstFieldDecl<'db> {
     pub span: Span<'db>,
+
+    /// Doc comment preceding the field
+    #[return_ref]
+    pub doc_comment: Option<AstDocComment<'db>>,
+
     pub visibility: Option<AstVisibility<'db>>,
     pub variable: VariableDecl<'db>,
 }
@@ -29,6 +29,9 @@ pub enum AstTyKind<'db> {
 
     /// `type T`
     GenericDecl(AstGenericDecl<'db>),
+
+    /// `!`, the type of expressions that never produce a value
+    Never,
 }
 
 #[derive(SalsaSerialize)]
@@ -82,6 +85,10 @@ pub enum AstGenericTerm<'db> {
 
     /// A single identifier is ambiguous and must be disambiguated by the type checker
     Id(SpannedIdentifier<'db>),
+
+    /// User wrote `_`, asking for the term to be inferred (only permitted at call sites)
+    #[no_from_impl]
+    Infer(Span<'db>),
 }
 
 impl<'db> Spanned<'db> for AstGenericTerm<'db> {
@@ -90,6 +97,7 @@ impl<'db> Spanned<'db> for AstGenericTerm<'db> {
             AstGenericTerm::Ty(ty) => ty.span(db),
             AstGenericTerm::Perm(perm) => perm.span(db),
             AstGenericTerm::Id(id) => id.span(db),
+            AstGenericTerm::Infer(span) => *span,
         }
     }
 }
@@ -2,16 +2,25 @@ use dada_util::SalsaSerialize;
 
 use crate::span::{Span, Spanned};
 
-use super::{AstPath, SpannedIdentifier};
+use super::{AstPath, AstVisibility, SpannedIdentifier};
 
-/// `use $crate.$path [as $id]`
+/// `[pub] use $crate.$path [as $id]` or `[pub] use $crate.$path.*`
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct AstUse<'db> {
     pub span: Span<'db>,
+
+    /// Visibility of the use; a `pub use` re-exports the name from this module.
+    pub visibility: Option<AstVisibility<'db>>,
+
     pub crate_name: SpannedIdentifier<'db>,
     #[return_ref]
     pub path: AstPath<'db>,
+
+    /// Span of the trailing `.*`, if this is a glob import that imports
+    /// every item exported by the module named by `path`.
+    pub glob: Option<Span<'db>>,
+
     pub as_id: Option<SpannedIdentifier<'db>>,
 }
 
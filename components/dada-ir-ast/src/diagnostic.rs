@@ -20,6 +20,9 @@ impl Reported {
     }
 }
 
+/// Default for [`Db::max_errors`](`crate::Db::max_errors`).
+pub const DEFAULT_MAX_ERRORS: usize = 50;
+
 /// Signals that this may complete or report a diagnostic.
 /// In practice we use this to mean an error.
 pub type Errors<T> = Result<T, Reported>;
@@ -14,6 +14,9 @@ use crate::{
 pub struct CompilationRoot {
     #[return_ref]
     pub crates: Vec<Krate>,
+
+    /// See [`Db::max_errors`](`crate::Db::max_errors`).
+    pub max_errors: usize,
 }
 
 impl CompilationRoot {
@@ -34,6 +34,15 @@ pub trait Db: salsa::Database {
     /// The `dada_debug` crate will monitor this directory
     /// and serve up the information for use in debugging.
     fn debug_tx(&self) -> Option<Sender<DebugEvent>>;
+
+    /// Maximum number of errors reported while checking a single function.
+    /// Once it is exceeded, checking the function stops and a summary error is reported instead.
+    ///
+    /// This is an input of the [`CompilationRoot`], so that queries depending on it
+    /// are re-executed when it changes.
+    fn max_errors(&self) -> usize {
+        self.root().max_errors(self)
+    }
 }
 
 /// A debug event
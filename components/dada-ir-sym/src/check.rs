@@ -8,16 +8,29 @@ use runtime::Runtime;
 use crate::ir::types::SymTy;
 
 pub(crate) mod blocks;
+mod captures;
+mod coercions;
 mod debug;
+mod definite_assignment;
+pub(crate) mod destructors;
+pub(crate) mod drop_elaboration;
+pub(crate) mod dump;
 mod env;
 mod exprs;
+pub(crate) mod ffi;
 pub(crate) mod fields;
 pub(crate) mod functions;
-mod generics;
+pub(crate) mod generics;
 pub(crate) mod inference;
+pub(crate) mod layout;
+mod lease_invalidation;
+pub(crate) mod lints;
 mod live_places;
-mod member_lookup;
+mod match_arms;
+pub(crate) mod member_lookup;
 mod modules;
+mod patterns;
+pub(crate) mod permission_state;
 mod places;
 pub(crate) mod predicates;
 pub(crate) mod red;
@@ -36,6 +49,9 @@ mod to_red;
 mod types;
 mod universe;
 
+pub use inference::{InferVarKind, InferredValue};
+pub use scope::NameResolutionSym;
+
 /// Check an expression in a full environment.
 /// This is an async operation -- it may block if insufficient inference data is available.
 trait CheckTyInEnv<'db> {
@@ -0,0 +1,88 @@
+//! Captures: the variables from an enclosing scope that a block uses,
+//! e.g., the variables that a `spawn` block shares with the task it creates.
+
+use dada_ir_ast::span::Span;
+use dada_util::{Set, ensure_sufficient_stack};
+
+use crate::ir::{
+    exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+    variables::SymVariable,
+    visit::{SymExprVisitor, walk_expr},
+};
+
+/// Returns each variable used within `body` but declared outside of it,
+/// together with the span of its first use, in order of first use.
+pub(crate) fn captured_variables<'db>(
+    db: &'db dyn crate::Db,
+    body: SymExpr<'db>,
+) -> Vec<(SymVariable<'db>, Span<'db>)> {
+    let mut captures = Captures {
+        db,
+        declared: Default::default(),
+        captured: vec![],
+    };
+    captures.visit_expr(body);
+    captures.captured
+}
+
+struct Captures<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Variables declared within the body.
+    declared: Set<SymVariable<'db>>,
+
+    /// Variables captured so far, with the span of their first use.
+    captured: Vec<(SymVariable<'db>, Span<'db>)>,
+}
+
+impl<'db> SymExprVisitor<'db> for Captures<'db> {
+    fn db(&self) -> &'db dyn crate::Db {
+        self.db
+    }
+
+    fn visit_expr(&mut self, expr: SymExpr<'db>) {
+        let db = self.db;
+        ensure_sufficient_stack(|| match expr.kind(db) {
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                if let Some(initializer) = initializer {
+                    self.visit_expr(*initializer);
+                }
+                self.declared.insert(*lv);
+                self.visit_expr(*body);
+            }
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body,
+            } => {
+                self.visit_expr(*initializer);
+                self.declared.extend(lvs.iter().map(|&(lv, _)| lv));
+                self.visit_expr(*body);
+            }
+            _ => walk_expr(self, expr),
+        })
+    }
+
+    /// Records the root variable of `place` if it is declared outside the body.
+    fn visit_place(&mut self, place: SymPlaceExpr<'db>) {
+        let db = self.db;
+        let mut root = place;
+        let var = loop {
+            match *root.kind(db) {
+                SymPlaceExprKind::Var(var) => break var,
+                SymPlaceExprKind::Field(owner, _) => root = owner,
+                SymPlaceExprKind::Error(_) => return,
+            }
+        };
+
+        if self.declared.contains(&var) || self.captured.iter().any(|&(v, _)| v == var) {
+            return;
+        }
+        self.captured.push((var, place.span(db)));
+    }
+}
@@ -0,0 +1,132 @@
+//! Implicit numeric widening.
+//!
+//! When a value of one numeric type is stored into a place of another numeric type,
+//! we insert a [`SymExprKind::Cast`][] if the conversion is lossless (e.g., `u8` to `u32`)
+//! and report an error suggesting an explicit `as` if it is not (e.g., `i64` to `i32`).
+//!
+//! 💡 Widening is a coercion at the point of use and not a subtyping relation:
+//! `u8` is not a subtype of `u32`, since the two have different representations.
+//! This also means it only applies when the type of the value is already known
+//! to be a numeric type; values whose type is still being inferred (e.g., literals)
+//! simply take on the type of the place.
+
+use dada_ir_ast::diagnostic::{Diagnostic, Err, Level};
+
+use crate::{
+    check::env::Env,
+    ir::{
+        exprs::{SymExpr, SymExprKind},
+        primitive::SymPrimitiveKind,
+        types::{SymTy, SymTyKind, SymTyName},
+    },
+};
+
+/// If `value` has a numeric type that differs from the numeric type `expected_ty`,
+/// returns `value` converted to `expected_ty`, or an error expression if the
+/// conversion may lose information. Otherwise returns `value` unchanged.
+pub(crate) fn coerce_numeric<'db>(
+    env: &Env<'db>,
+    value: SymExpr<'db>,
+    expected_ty: SymTy<'db>,
+) -> SymExpr<'db> {
+    let db = env.db();
+    let value_ty = value.ty(db);
+
+    let (Some(from), Some(to)) = (
+        numeric_primitive(db, value_ty),
+        numeric_primitive(db, expected_ty),
+    ) else {
+        return value;
+    };
+
+    if from == to {
+        return value;
+    }
+
+    if is_lossless_widening(from, to) {
+        return SymExpr::new(db, value.span(db), expected_ty, SymExprKind::Cast(value));
+    }
+
+    // 💡 Name the primitive types, since `value_ty` may include a permission like `ref[x]`.
+    let from = from.intern(db).name(db);
+    let to = to.intern(db).name(db);
+    let reported = env.report(
+        Diagnostic::error(
+            db,
+            value.span(db),
+            format!("implicit conversion from `{from}` to `{to}` may lose information"),
+        )
+        .label(
+            db,
+            Level::Error,
+            value.span(db),
+            format!("this expression has type `{from}`"),
+        )
+        .label(
+            db,
+            Level::Help,
+            value.span(db).at_end(),
+            format!("if the conversion is intended, write `as {to}` here"),
+        ),
+    );
+    SymExpr::err(db, reported)
+}
+
+/// If `ty` is a numeric primitive type (with any permission), returns its kind.
+pub(crate) fn numeric_primitive<'db>(
+    db: &'db dyn crate::Db,
+    ty: SymTy<'db>,
+) -> Option<SymPrimitiveKind> {
+    primitive_kind(db, ty)
+        .filter(|kind| !matches!(kind, SymPrimitiveKind::Bool | SymPrimitiveKind::Char))
+}
+
+/// True if one of `from` and `to` is `char` and the other is `u32`,
+/// the type of its Unicode scalar value. These convert to one another only with `as`.
+pub(crate) fn is_char_conversion<'db>(
+    db: &'db dyn crate::Db,
+    from: SymTy<'db>,
+    to: SymTy<'db>,
+) -> bool {
+    match (primitive_kind(db, from), primitive_kind(db, to)) {
+        (Some(SymPrimitiveKind::Char), Some(other))
+        | (Some(other), Some(SymPrimitiveKind::Char)) => {
+            other == SymPrimitiveKind::Uint { bits: 32 }
+        }
+        _ => false,
+    }
+}
+
+/// If `ty` is a primitive type (with any permission), returns its kind.
+fn primitive_kind<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> Option<SymPrimitiveKind> {
+    match ty.kind(db) {
+        SymTyKind::Perm(_, ty) => primitive_kind(db, *ty),
+        SymTyKind::Named(SymTyName::Primitive(primitive), _) => Some(primitive.kind(db)),
+        _ => None,
+    }
+}
+
+/// True if every value of type `from` can be represented exactly as a value of type `to`.
+///
+/// `isize` and `usize` have a target-dependent size, so conversions to or from them
+/// are never implicit.
+fn is_lossless_widening(from: SymPrimitiveKind, to: SymPrimitiveKind) -> bool {
+    match (from, to) {
+        (SymPrimitiveKind::Uint { bits: from }, SymPrimitiveKind::Uint { bits: to })
+        | (SymPrimitiveKind::Int { bits: from }, SymPrimitiveKind::Int { bits: to }) => from < to,
+
+        // An unsigned value fits in a signed type with at least one more bit.
+        (SymPrimitiveKind::Uint { bits: from }, SymPrimitiveKind::Int { bits: to }) => from < to,
+
+        (SymPrimitiveKind::Float { bits: from }, SymPrimitiveKind::Float { bits: to }) => from < to,
+
+        // An integer fits in a float if it fits in the float's significand
+        // (24 bits for `f32`, 53 bits for `f64`).
+        (
+            SymPrimitiveKind::Uint { bits: from } | SymPrimitiveKind::Int { bits: from },
+            SymPrimitiveKind::Float { bits: to },
+        ) => from <= 16 || (from <= 32 && to >= 64),
+
+        _ => false,
+    }
+}
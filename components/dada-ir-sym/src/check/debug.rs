@@ -549,6 +549,7 @@ pub enum TaskDescription<'db> {
     RequireEqualTypes(SymTy<'db>, SymTy<'db>),
     RequireMyNumericType(SymTy<'db>),
     RequireNumericType(SymTy<'db>),
+    RequireComparableType(SymTy<'db>),
     RequireFutureType(SymTy<'db>),
     RequireBoundsProvablyPredicate(InferVarIndex, Predicate),
     RequireBoundsNotProvablyPredicate(InferVarIndex, Predicate),
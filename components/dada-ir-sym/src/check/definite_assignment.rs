@@ -0,0 +1,214 @@
+//! Definite assignment: a variable declared with `let x` and no initializer
+//! must be assigned on every path that leads to a read of `x`.
+
+use dada_ir_ast::diagnostic::{Diagnostic, Level};
+use dada_util::{Map, Set, ensure_sufficient_stack};
+
+use crate::ir::{
+    exprs::{SymExpr, SymExprKind, SymLoop, SymPlaceExpr, SymPlaceExprKind},
+    variables::SymVariable,
+};
+
+/// The variables (declared without an initializer) that are assigned on every path
+/// to some point in the body, or `None` if no path reaches it (e.g., after a `return`).
+type Assigned<'db> = Option<Set<SymVariable<'db>>>;
+
+/// Reports each read of a variable declared without an initializer
+/// that may happen before the variable is assigned.
+/// Only the first such read of each variable is reported.
+pub(crate) fn check_definite_assignment<'db>(db: &'db dyn crate::Db, body: SymExpr<'db>) {
+    let mut checker = DefiniteAssignment {
+        db,
+        uninitialized: Default::default(),
+        reported: Default::default(),
+        breaks: Default::default(),
+    };
+    checker.expr(body, &mut Some(Set::default()));
+}
+
+struct DefiniteAssignment<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Variables declared without an initializer.
+    uninitialized: Set<SymVariable<'db>>,
+
+    /// Variables for which we have already reported a read.
+    reported: Set<SymVariable<'db>>,
+
+    /// For each loop being analyzed, the join of the states at the `break`s that exit it.
+    breaks: Map<SymLoop<'db>, Assigned<'db>>,
+}
+
+impl<'db> DefiniteAssignment<'db> {
+    /// Updates `assigned` to reflect the state after evaluating `expr`.
+    fn expr(&mut self, expr: SymExpr<'db>, assigned: &mut Assigned<'db>) {
+        let db = self.db;
+        ensure_sufficient_stack(|| match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+                self.expr(*lhs, assigned);
+                self.expr(*rhs, assigned);
+            }
+            SymExprKind::Tuple(exprs)
+            | SymExprKind::Aggregate {
+                ty: _,
+                fields: exprs,
+            } => {
+                for &expr in exprs {
+                    self.expr(expr, assigned);
+                }
+            }
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                match initializer {
+                    Some(initializer) => self.expr(*initializer, assigned),
+                    None => {
+                        self.uninitialized.insert(*lv);
+                    }
+                }
+                self.expr(*body, assigned);
+            }
+            SymExprKind::LetTuple {
+                lvs: _,
+                initializer,
+                body,
+            } => {
+                self.expr(*initializer, assigned);
+                self.expr(*body, assigned);
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword: _,
+            } => self.expr(*future, assigned),
+            SymExprKind::Assign { place, value } => {
+                self.expr(*value, assigned);
+                match *place.kind(db) {
+                    SymPlaceExprKind::Var(var) => {
+                        if let Some(assigned) = assigned {
+                            assigned.insert(var);
+                        }
+                    }
+
+                    // Assigning to a field of `x` requires that `x` is already initialized.
+                    SymPlaceExprKind::Field(..) => self.read(*place, assigned),
+                    SymPlaceExprKind::Error(_) => {}
+                }
+            }
+            SymExprKind::PermissionOp(_, place) | SymExprKind::IsSome(place) => {
+                self.read(*place, assigned)
+            }
+            SymExprKind::Return(value) => {
+                self.expr(*value, assigned);
+                *assigned = None;
+            }
+            SymExprKind::Loop { sym_loop, body } => {
+                // Assignments only ever add to the state, so the state at the start
+                // of each later iteration includes the state on entry. Analyzing the
+                // body once, starting from the state on entry, is therefore enough.
+                self.expr(*body, &mut assigned.clone());
+                *assigned = self.breaks.remove(sym_loop).flatten();
+            }
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                self.expr(*with_value, assigned);
+                let after_loop = self.breaks.remove(from_loop).flatten();
+                self.breaks
+                    .insert(*from_loop, join(after_loop, assigned.take()));
+            }
+            SymExprKind::Continue { to_loop: _ } => {
+                *assigned = None;
+            }
+            SymExprKind::Not {
+                operand,
+                op_span: _,
+            }
+            | SymExprKind::Cast(operand) => self.expr(*operand, assigned),
+            SymExprKind::Match { arms } => {
+                // `fallthrough` is the state when none of the arms so far has been taken.
+                let mut fallthrough = assigned.clone();
+                let mut after_arms = None;
+                for arm in arms {
+                    if let Some(condition) = arm.condition {
+                        self.expr(condition, &mut fallthrough);
+                    }
+                    let mut in_arm = fallthrough.clone();
+                    self.expr(arm.body, &mut in_arm);
+                    after_arms = join(after_arms, in_arm);
+                    if arm.condition.is_none() {
+                        fallthrough = None;
+                        break;
+                    }
+                }
+                *assigned = join(after_arms, fallthrough);
+            }
+
+            // The arguments of a call are temporaries, initialized by an enclosing `LetIn`.
+            // Drops are only inserted after this check, and may drop unassigned variables.
+            SymExprKind::Call { .. }
+            | SymExprKind::Drop(_)
+            | SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::Error(_) => {}
+        })
+    }
+
+    /// Reports `place` if its root variable may not be assigned yet.
+    fn read(&mut self, place: SymPlaceExpr<'db>, assigned: &Assigned<'db>) {
+        let db = self.db;
+        let mut root = place;
+        let var = loop {
+            match *root.kind(db) {
+                SymPlaceExprKind::Var(var) => break var,
+                SymPlaceExprKind::Field(owner, _) => root = owner,
+                SymPlaceExprKind::Error(_) => return,
+            }
+        };
+
+        let Some(assigned) = assigned else {
+            return;
+        };
+        if !self.uninitialized.contains(&var)
+            || assigned.contains(&var)
+            || !self.reported.insert(var)
+        {
+            return;
+        }
+
+        let span = place.span(db);
+        Diagnostic::error(
+            db,
+            span,
+            format!("`{var}` may be used before it is assigned"),
+        )
+        .label(
+            db,
+            Level::Error,
+            span,
+            format!("`{var}` is read here, but some paths to this point do not assign it"),
+        )
+        .label(
+            db,
+            Level::Info,
+            var.span(db),
+            format!("`{var}` is declared here without an initial value"),
+        )
+        .report(db);
+    }
+}
+
+/// The state where two paths meet: the variables assigned on both.
+fn join<'db>(a: Assigned<'db>, b: Assigned<'db>) -> Assigned<'db> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(b)) => {
+            a.retain(|var| b.contains(var));
+            Some(a)
+        }
+    }
+}
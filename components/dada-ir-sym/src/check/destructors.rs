@@ -0,0 +1,122 @@
+//! Destructors are methods named `drop` that run when a class value is dropped,
+//! see [`crate::check::drop_elaboration`][] for where drops occur.
+
+use dada_ir_ast::{
+    ast::{AstFunctionInput, AstPermKind, Identifier},
+    diagnostic::{Diagnostic, Errors, Level},
+    span::Spanned,
+};
+
+use crate::{
+    check::scope_tree::ScopeItem,
+    ir::{
+        classes::SymAggregate,
+        functions::{SymFunction, SymFunctionSource},
+    },
+    prelude::CheckedDestructor,
+};
+
+/// Returns the destructor declared by `aggr`, if any, after checking that it
+/// is a method of a class that takes `my self` or `mut self`, has no other inputs
+/// nor generic parameters, is not `async`, and does not return a value.
+pub(crate) fn check_destructor<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+) -> Errors<Option<SymFunction<'db>>> {
+    let Some(destructor) = aggr
+        .methods(db)
+        .find(|method| method.name(db) == Identifier::drop_ident(db))
+    else {
+        return Ok(None);
+    };
+    let SymFunctionSource::Function(ast_function) = destructor.source(db) else {
+        return Ok(None);
+    };
+    let error = |message: String| Diagnostic::error(db, destructor.name_span(db), message);
+
+    if aggr.is_struct(db) {
+        return Err(error(format!("struct `{aggr}` cannot have a destructor"))
+            .label(
+                db,
+                Level::Error,
+                destructor.name_span(db),
+                "a method named `drop` is a destructor, which only classes can have",
+            )
+            .report(db));
+    }
+
+    let inputs = destructor.source(db).inputs(db);
+    let self_perm = match inputs.first() {
+        Some(AstFunctionInput::SelfArg(self_arg)) => self_arg.perm(db),
+        _ => {
+            return Err(
+                error("destructor must take `my self` or `mut self`".to_string())
+                    .label(
+                        db,
+                        Level::Error,
+                        destructor.name_span(db),
+                        "this destructor does not take `self`",
+                    )
+                    .report(db),
+            );
+        }
+    };
+    match self_perm.map(|perm| perm.kind(db)) {
+        Some(AstPermKind::My | AstPermKind::Mutable(None)) => (),
+        _ => {
+            return Err(
+                error("destructor must take `my self` or `mut self`".to_string())
+                    .label(
+                        db,
+                        Level::Error,
+                        inputs[0].span(db),
+                        "a destructor needs to own or mutate the value being dropped",
+                    )
+                    .report(db),
+            );
+        }
+    }
+
+    if let Some(input) = inputs.get(1) {
+        return Err(
+            error("destructor cannot take arguments other than `self`".to_string())
+                .label(
+                    db,
+                    Level::Error,
+                    input.span(db),
+                    "there is no value to supply for this argument when a value is dropped",
+                )
+                .report(db),
+        );
+    }
+
+    if let Some(generics) = ast_function.generics(db) {
+        return Err(
+            error("destructor cannot have generic parameters".to_string())
+                .label(db, Level::Error, generics.span, "declared here")
+                .report(db),
+        );
+    }
+
+    if let Some(async_span) = ast_function.effects(db).async_effect {
+        return Err(error("destructor cannot be `async`".to_string())
+            .label(db, Level::Error, async_span, "declared here")
+            .report(db));
+    }
+
+    if let Some(output_ty) = ast_function.output_ty(db) {
+        return Err(error("destructor cannot return a value".to_string())
+            .label(db, Level::Error, output_ty.span(db), "declared here")
+            .report(db));
+    }
+
+    Ok(Some(destructor))
+}
+
+/// True if `function` is the (valid) destructor of the class that declares it.
+pub(crate) fn is_destructor<'db>(db: &'db dyn crate::Db, function: SymFunction<'db>) -> bool {
+    match function.super_scope_item(db) {
+        ScopeItem::Class(aggr) => aggr.checked_destructor(db) == Ok(Some(function)),
+        _ => false,
+    }
+}
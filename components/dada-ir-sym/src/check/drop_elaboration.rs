@@ -0,0 +1,379 @@
+//! Drop elaboration makes the end of each value's lifetime explicit
+//! by inserting [`SymExprKind::Drop`][] expressions into a checked body.
+//!
+//! Every variable whose type may own a value (see [`may_own`]) is dropped when
+//! its scope ends: inputs after the function body, locals after the body of their `let`,
+//! and all of them (innermost first) before a `return`, `break`, or `continue` that leaves their scope.
+//! Variables in the same scope are therefore dropped in the reverse of the order they were declared.
+//! Assigning an owned value to a place drops the value it previously held.
+//!
+//! Dropping a class value runs its [destructor](`crate::check::destructors`), if any.
+//! The `self` of a destructor is not dropped at the end of the destructor,
+//! as that would run the destructor again; its fields are dropped once the destructor returns.
+//!
+//! 💡 A variable is dropped even if its value may have been given away (or never assigned).
+//! Dropping a place that no longer owns a value does nothing, so the backends
+//! track which places still own their value.
+
+use dada_ir_ast::ast::Identifier;
+use dada_util::ensure_sufficient_stack;
+
+use crate::{
+    check::destructors::is_destructor,
+    ir::{
+        exprs::{SymExpr, SymExprKind, SymLoop, SymMatchArm, SymPlaceExpr, SymPlaceExprKind},
+        functions::SymFunction,
+        types::{SymGenericKind, SymPerm, SymPermKind, SymTy, SymTyKind, SymTyName},
+        variables::SymVariable,
+    },
+    prelude::CheckedSignature,
+};
+
+/// Returns `body`, the checked body of `function`, with drops inserted.
+pub(crate) fn elaborate_drops<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+    body: SymExpr<'db>,
+) -> SymExpr<'db> {
+    let inputs = match function.checked_signature(db) {
+        Ok(signature) => {
+            let input_output = &signature.input_output(db).bound_value;
+            input_output
+                .variables
+                .iter()
+                .zip(&input_output.bound_value.input_tys)
+                .filter(|&(_, &ty)| may_own(db, ty))
+                .filter(|&(&var, _)| !(is_destructor(db, function) && is_self(db, var)))
+                .map(|(&var, &ty)| (var, ty))
+                .collect()
+        }
+        Err(_) => vec![],
+    };
+
+    let mut elaborator = DropElaborator {
+        db,
+        in_scope: inputs.clone(),
+        loops: vec![],
+    };
+    let body = elaborator.expr(body);
+    elaborator.drop_after(body, &inputs)
+}
+
+struct DropElaborator<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Variables (and their types) that will be dropped when their scope ends,
+    /// in the order they were declared.
+    in_scope: Vec<(SymVariable<'db>, SymTy<'db>)>,
+
+    /// Loops enclosing the current expression, each with the length of
+    /// `in_scope` on entry: variables after that are declared within the loop.
+    loops: Vec<(SymLoop<'db>, usize)>,
+}
+
+impl<'db> DropElaborator<'db> {
+    fn expr(&mut self, expr: SymExpr<'db>) -> SymExpr<'db> {
+        ensure_sufficient_stack(|| self.elaborate_expr(expr))
+    }
+
+    fn elaborate_expr(&mut self, expr: SymExpr<'db>) -> SymExpr<'db> {
+        let db = self.db;
+        let kind = match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) => SymExprKind::Semi(self.expr(*lhs), self.expr(*rhs)),
+            SymExprKind::Tuple(exprs) => {
+                SymExprKind::Tuple(exprs.iter().map(|&expr| self.expr(expr)).collect())
+            }
+            SymExprKind::LetIn {
+                lv,
+                ty,
+                initializer,
+                body,
+            } => {
+                let initializer = initializer.map(|initializer| self.expr(initializer));
+                let body = if may_own(db, *ty) {
+                    self.in_scope.push((*lv, *ty));
+                    let body = self.expr(*body);
+                    self.in_scope.pop();
+                    self.drop_after(body, &[(*lv, *ty)])
+                } else {
+                    self.expr(*body)
+                };
+                SymExprKind::LetIn {
+                    lv: *lv,
+                    ty: *ty,
+                    initializer,
+                    body,
+                }
+            }
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body,
+            } => {
+                let initializer = self.expr(*initializer);
+                let owned: Vec<_> = lvs
+                    .iter()
+                    .copied()
+                    .filter(|&(_, ty)| may_own(db, ty))
+                    .collect();
+                let depth = self.in_scope.len();
+                self.in_scope.extend(owned.iter().copied());
+                let body = self.expr(*body);
+                self.in_scope.truncate(depth);
+                SymExprKind::LetTuple {
+                    lvs: lvs.clone(),
+                    initializer,
+                    body: self.drop_after(body, &owned),
+                }
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword,
+            } => SymExprKind::Await {
+                future: self.expr(*future),
+                await_keyword: *await_keyword,
+            },
+            SymExprKind::Assign { place, value } => {
+                return self.drop_before_assign(expr, *place, self.expr(*value));
+            }
+            SymExprKind::Return(value) => {
+                let value = self.expr(*value);
+                return self.drop_before_exit(expr, value, &self.in_scope, SymExprKind::Return);
+            }
+            SymExprKind::Loop { sym_loop, body } => {
+                self.loops.push((*sym_loop, self.in_scope.len()));
+                let body = self.expr(*body);
+                self.loops.pop();
+                SymExprKind::Loop {
+                    sym_loop: *sym_loop,
+                    body,
+                }
+            }
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                let with_value = self.expr(*with_value);
+                let from_loop = *from_loop;
+                return self.drop_before_exit(
+                    expr,
+                    with_value,
+                    self.declared_within(from_loop),
+                    |with_value| SymExprKind::Break {
+                        from_loop,
+                        with_value,
+                    },
+                );
+            }
+            SymExprKind::Continue { to_loop } => {
+                return self.drops_then(self.declared_within(*to_loop), expr);
+            }
+            SymExprKind::Not { operand, op_span } => SymExprKind::Not {
+                operand: self.expr(*operand),
+                op_span: *op_span,
+            },
+            SymExprKind::BinaryOp(op, lhs, rhs) => {
+                SymExprKind::BinaryOp(*op, self.expr(*lhs), self.expr(*rhs))
+            }
+            SymExprKind::Cast(value) => SymExprKind::Cast(self.expr(*value)),
+            SymExprKind::Aggregate { ty, fields } => SymExprKind::Aggregate {
+                ty: *ty,
+                fields: fields.iter().map(|&field| self.expr(field)).collect(),
+            },
+            SymExprKind::Match { arms } => SymExprKind::Match {
+                arms: arms
+                    .iter()
+                    .map(|arm| SymMatchArm {
+                        condition: arm.condition.map(|condition| self.expr(condition)),
+                        body: self.expr(arm.body),
+                    })
+                    .collect(),
+            },
+            SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::PermissionOp(..)
+            | SymExprKind::IsSome(_)
+            | SymExprKind::Call { .. }
+            | SymExprKind::Drop(_)
+            | SymExprKind::Error(_) => return expr,
+        };
+        SymExpr::new(db, expr.span(db), expr.ty(db), kind)
+    }
+
+    /// Returns an expression that evaluates `body`, then drops `vars` (last first),
+    /// and produces the value of `body`.
+    fn drop_after(
+        &self,
+        body: SymExpr<'db>,
+        vars: &[(SymVariable<'db>, SymTy<'db>)],
+    ) -> SymExpr<'db> {
+        let db = self.db;
+
+        // If `body` never finishes, any `return` or `break` within it already dropped `vars`.
+        if vars.is_empty() || matches!(body.ty(db).kind(db), SymTyKind::Never) {
+            return body;
+        }
+
+        let span = body.span(db);
+        let result = SymVariable::new(db, SymGenericKind::Place, None, span);
+        let result_place = SymPlaceExpr::new(db, span, body.ty(db), SymPlaceExprKind::Var(result));
+        SymExpr::new(
+            db,
+            span,
+            body.ty(db),
+            SymExprKind::LetIn {
+                lv: result,
+                ty: body.ty(db),
+                initializer: Some(body),
+                body: self.drops_then(vars, result_place.give(db)),
+            },
+        )
+    }
+
+    /// Returns the elaborated version of the `return` or `break` expression `expr`,
+    /// whose (elaborated) value is `value` and which leaves the scope of `vars`:
+    /// the value is computed first, since it may use `vars`, and then they are dropped.
+    /// `exit` creates the `return` or `break` of a given value.
+    fn drop_before_exit(
+        &self,
+        expr: SymExpr<'db>,
+        value: SymExpr<'db>,
+        vars: &[(SymVariable<'db>, SymTy<'db>)],
+        exit: impl FnOnce(SymExpr<'db>) -> SymExprKind<'db>,
+    ) -> SymExpr<'db> {
+        let db = self.db;
+        let span = expr.span(db);
+
+        if vars.is_empty() {
+            return SymExpr::new(db, span, expr.ty(db), exit(value));
+        }
+
+        let result = SymVariable::new(db, SymGenericKind::Place, None, value.span(db));
+        let result_place = SymPlaceExpr::new(
+            db,
+            value.span(db),
+            value.ty(db),
+            SymPlaceExprKind::Var(result),
+        );
+        let exit_expr = SymExpr::new(db, span, expr.ty(db), exit(result_place.give(db)));
+        SymExpr::new(
+            db,
+            span,
+            expr.ty(db),
+            SymExprKind::LetIn {
+                lv: result,
+                ty: value.ty(db),
+                initializer: Some(value),
+                body: self.drops_then(vars, exit_expr),
+            },
+        )
+    }
+
+    /// The variables in scope that are declared within `sym_loop`,
+    /// which are dropped by a `break` or `continue` of that loop.
+    fn declared_within(&self, sym_loop: SymLoop<'db>) -> &[(SymVariable<'db>, SymTy<'db>)] {
+        let &(_, depth) = self
+            .loops
+            .iter()
+            .rev()
+            .find(|&&(l, _)| l == sym_loop)
+            .expect("`break` or `continue` outside of its loop");
+        &self.in_scope[depth..]
+    }
+
+    /// Returns the elaborated version of the assignment `expr` of `value` to `place`.
+    /// If `value` may be owned, the value previously stored in `place` is dropped
+    /// after `value` is computed (it may read `place`) and before it is stored.
+    fn drop_before_assign(
+        &self,
+        expr: SymExpr<'db>,
+        place: SymPlaceExpr<'db>,
+        value: SymExpr<'db>,
+    ) -> SymExpr<'db> {
+        let db = self.db;
+        let span = expr.span(db);
+
+        if !may_own(db, value.ty(db)) {
+            return SymExpr::new(db, span, expr.ty(db), SymExprKind::Assign { place, value });
+        }
+
+        let new_value = SymVariable::new(db, SymGenericKind::Place, None, value.span(db));
+        let new_value_place = SymPlaceExpr::new(
+            db,
+            value.span(db),
+            value.ty(db),
+            SymPlaceExprKind::Var(new_value),
+        );
+        let drop = SymExpr::new(db, span, SymTy::unit(db), SymExprKind::Drop(place));
+        let assign = SymExpr::new(
+            db,
+            span,
+            expr.ty(db),
+            SymExprKind::Assign {
+                place,
+                value: new_value_place.give(db),
+            },
+        );
+        SymExpr::new(
+            db,
+            span,
+            expr.ty(db),
+            SymExprKind::LetIn {
+                lv: new_value,
+                ty: value.ty(db),
+                initializer: Some(value),
+                body: SymExpr::new(db, span, expr.ty(db), SymExprKind::Semi(drop, assign)),
+            },
+        )
+    }
+
+    /// Returns an expression that drops `vars` (last first) and then evaluates `expr`.
+    fn drops_then(
+        &self,
+        vars: &[(SymVariable<'db>, SymTy<'db>)],
+        expr: SymExpr<'db>,
+    ) -> SymExpr<'db> {
+        let db = self.db;
+        let span = expr.span(db);
+        vars.iter().fold(expr, |rest, &(var, ty)| {
+            let place = SymPlaceExpr::new(db, var.span(db), ty, SymPlaceExprKind::Var(var));
+            let drop = SymExpr::new(db, span, SymTy::unit(db), SymExprKind::Drop(place));
+            SymExpr::new(db, span, rest.ty(db), SymExprKind::Semi(drop, rest))
+        })
+    }
+}
+
+fn is_self<'db>(db: &'db dyn crate::Db, var: SymVariable<'db>) -> bool {
+    var.name(db) == Some(Identifier::self_ident(db))
+}
+
+/// True unless a value of type `ty` is known to own nothing that needs to be freed:
+/// primitives, tuples of them, and values that are borrowed (`ref`, `mut`) or shared (`our`).
+///
+/// 💡 Generic types and permissions may own values, so they are dropped;
+/// whether that does anything is known once they are instantiated.
+fn may_own<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> bool {
+    match ty.kind(db) {
+        SymTyKind::Perm(perm, ty) => perm_may_own(db, *perm) && may_own(db, *ty),
+        SymTyKind::Named(SymTyName::Primitive(_), _) => false,
+        SymTyKind::Named(SymTyName::Tuple { arity: _ }, args) => {
+            args.iter().any(|&arg| may_own(db, arg.assert_type(db)))
+        }
+        SymTyKind::Named(SymTyName::Aggregate(_) | SymTyName::Future, _) => true,
+        SymTyKind::Var(_) => true,
+        SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => false,
+    }
+}
+
+fn perm_may_own<'db>(db: &'db dyn crate::Db, perm: SymPerm<'db>) -> bool {
+    match perm.kind(db) {
+        SymPermKind::My | SymPermKind::Var(_) => true,
+        SymPermKind::Our
+        | SymPermKind::Referenced(_)
+        | SymPermKind::Mutable(_)
+        | SymPermKind::Infer(_)
+        | SymPermKind::Error(_) => false,
+        SymPermKind::Apply(lhs, rhs) => perm_may_own(db, *lhs) && perm_may_own(db, *rhs),
+        SymPermKind::Or(lhs, rhs) => perm_may_own(db, *lhs) || perm_may_own(db, *rhs),
+    }
+}
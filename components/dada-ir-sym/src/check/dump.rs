@@ -0,0 +1,133 @@
+//! Textual dumps of internal analysis results, for debugging the compiler.
+
+use std::fmt::Write;
+
+use dada_ir_ast::diagnostic::Errors;
+
+use crate::ir::{functions::SymFunction, types::SymTy};
+
+use super::{
+    env::Env,
+    functions::checked_function_body,
+    inference::Direction,
+    live_places::LivePlaces,
+    red::{RedChain, RedLink, RedPerm},
+    runtime::{CheckStats, Runtime},
+    signature::{PreparedEnv, prepare_env},
+    stream::Consumer,
+    to_red::{RedTyExt, ToRedPerm, ToRedTy},
+};
+
+/// Renders the reduced ("red") form of the input and output types of `function`:
+/// one line per input plus one for the return type, each giving the red type
+/// and the permission chains that apply to it.
+///
+/// The format is `name: red_ty @ chain | chain`, where each chain is a space-separated
+/// list of links (`our`, `ref[place]`, `mut[place]`, or a permission variable)
+/// and an empty chain is written `my`.
+pub(crate) fn dump_red_signature<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> Errors<String> {
+    Runtime::execute(
+        db,
+        function.name_span(db),
+        "dump_red_signature",
+        &[&function],
+        async move |runtime| -> Errors<String> {
+            let PreparedEnv {
+                mut env,
+                input_symbols,
+                input_tys,
+                output_ty_caller,
+                ..
+            } = prepare_env(db, runtime, function).await;
+
+            let mut output = String::new();
+            for (input, &input_ty) in input_symbols.iter().zip(&input_tys) {
+                let line = red_line(&mut env, input_ty).await?;
+                writeln!(output, "{input}: {line}").unwrap();
+            }
+            let line = red_line(&mut env, output_ty_caller).await?;
+            writeln!(output, "return: {line}").unwrap();
+            Ok(output)
+        },
+        |output| output,
+    )
+}
+
+/// Renders the counters from checking the body of `function`, if it has one:
+/// how many alternatives the speculative combinators (e.g., `Env::either`) started,
+/// and how many of those were pruned because the result was known without them;
+/// and how many comparisons of lien chains were found in the sub-chain cache.
+pub(crate) fn dump_check_stats<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> Option<String> {
+    let checked = checked_function_body(db, function);
+    checked.body?;
+
+    let CheckStats {
+        alternatives,
+        sub_chain_cache,
+    } = checked.stats;
+    let mut output = String::new();
+    writeln!(
+        output,
+        "alternatives: started {}, pruned {}",
+        alternatives.started, alternatives.pruned
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "sub_chain cache: hits {}, misses {}",
+        sub_chain_cache.hits, sub_chain_cache.misses
+    )
+    .unwrap();
+    Some(output)
+}
+
+async fn red_line<'db>(env: &mut Env<'db>, ty: SymTy<'db>) -> Errors<String> {
+    let (red_ty, perm) = ty.to_red_ty(env);
+
+    let mut red_perms = vec![];
+    perm.to_red_perm(
+        env,
+        LivePlaces::none(env),
+        Direction::FromBelow,
+        Consumer::new(async |_env, red_perm: RedPerm<'db>| {
+            red_perms.push(red_perm);
+            Ok(())
+        }),
+    )
+    .await?;
+
+    let db = env.db();
+    let chains = red_perms
+        .iter()
+        .flat_map(|red_perm| red_perm.chains(db))
+        .map(|&chain| render_chain(db, chain))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    Ok(format!("{} @ {chains}", red_ty.display(env)))
+}
+
+fn render_chain<'db>(db: &'db dyn crate::Db, chain: RedChain<'db>) -> String {
+    let links = chain.links(db);
+    if links.is_empty() {
+        return "my".to_string();
+    }
+
+    links
+        .iter()
+        .map(|link| match link {
+            RedLink::Our => "our".to_string(),
+            RedLink::Ref(_, place) => format!("ref[{place}]"),
+            RedLink::Mut(_, place) => format!("mut[{place}]"),
+            RedLink::Var(variable) => format!("{variable}"),
+            RedLink::Err(_) => "<error>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
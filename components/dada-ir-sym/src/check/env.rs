@@ -1,4 +1,4 @@
-use std::{cell::Cell, ops::AsyncFnOnce, panic::Location, sync::Arc};
+use std::{cell::Cell, ops::AsyncFnOnce, panic::Location, rc::Rc, sync::Arc};
 
 use crate::{
     check::{
@@ -21,12 +21,16 @@ use crate::{
 };
 use dada_ir_ast::{
     ast::VariableDecl,
-    diagnostic::{Diagnostic, Err, Reported},
+    diagnostic::{Diagnostic, Err, Level, Reported},
     span::Span,
 };
 use dada_util::{Map, debug};
 
-use crate::{check::runtime::Runtime, check::universe::Universe, ir::exprs::SymExpr};
+use crate::{
+    check::runtime::Runtime,
+    check::universe::Universe,
+    ir::exprs::{SymExpr, SymLoop},
+};
 
 use super::{
     CheckTyInEnv,
@@ -39,10 +43,11 @@ use super::{
     runtime::DeferResult,
     subtype::{
         is_future::require_future_type,
-        is_numeric::{require_my_numeric_type, require_numeric_type},
+        is_numeric::{require_comparable_type, require_my_numeric_type, require_numeric_type},
         relate_infer_bounds::relate_infer_bounds,
         terms::reconcile_ty_bounds,
     },
+    to_red::ToRedTy,
 };
 
 pub mod combinator;
@@ -71,10 +76,29 @@ pub(crate) struct Env<'db> {
     /// If `None`, not type checking a function or method.
     pub return_ty: Option<SymTy<'db>>,
 
+    /// If `Some`, we are checking the body of the `atomic` block with the given span.
+    pub atomic: Option<Span<'db>>,
+
+    /// If `Some`, we are checking the body of the `spawn` block with the given span.
+    pub spawned: Option<Span<'db>>,
+
+    /// Loops enclosing the expression being checked, innermost last.
+    pub loops: Vec<Rc<EnclosingLoop<'db>>>,
+
     /// Assumptions declared
     assumptions: Arc<Vec<Assumption<'db>>>,
 }
 
+/// A loop enclosing the expression being checked, see [`Env::loops`].
+pub(crate) struct EnclosingLoop<'db> {
+    pub sym_loop: SymLoop<'db>,
+
+    /// The type of the loop expression. For a `loop`, this is created when
+    /// the first `break` that exits the loop is checked: if it is still `None`
+    /// once the body has been checked, nothing exits the loop and its type is `!`.
+    pub ty: Cell<Option<SymTy<'db>>>,
+}
+
 impl<'db> Env<'db> {
     /// Create an empty environment
     pub(crate) fn new(runtime: &Runtime<'db>, scope: Scope<'db, 'db>) -> Self {
@@ -86,6 +110,9 @@ impl<'db> Env<'db> {
             variable_tys: Default::default(),
             variable_universes: Default::default(),
             return_ty: Default::default(),
+            atomic: None,
+            spawned: None,
+            loops: vec![],
             assumptions: Arc::new(vec![]), // FIXME
         }
     }
@@ -126,10 +153,18 @@ impl<'db> Env<'db> {
             variable_universes: self.variable_universes.clone(),
             variable_tys: self.variable_tys.clone(),
             return_ty: self.return_ty,
+            atomic: self.atomic,
+            spawned: self.spawned,
+            loops: self.loops.clone(),
             assumptions: self.assumptions.clone(),
         }
     }
 
+    /// The assumptions declared about the generic variables in scope.
+    pub fn assumptions(&self) -> &Arc<Vec<Assumption<'db>>> {
+        &self.assumptions
+    }
+
     /// True if the given variable is declared to meet the given predicate.
     pub fn var_is_declared_to_be(&self, var: SymVariable<'db>, predicate: Predicate) -> bool {
         let result = match predicate {
@@ -426,6 +461,21 @@ impl<'db> Env<'db> {
         )
     }
 
+    /// Check that the value is a numeric type or `char` with any permission.
+    #[track_caller]
+    pub(super) fn spawn_require_comparable_type(
+        &mut self,
+        ty: SymTy<'db>,
+        or_else: &dyn OrElse<'db>,
+    ) {
+        let or_else = or_else.to_arc();
+        self.runtime.spawn(
+            self,
+            TaskDescription::RequireComparableType(ty),
+            async move |env| require_comparable_type(env, ty, &or_else).await,
+        )
+    }
+
     #[track_caller]
     pub(super) fn spawn_require_future_type(
         &self,
@@ -465,16 +515,22 @@ impl<'db> Env<'db> {
         tys: &[SymTy<'db>],
         op: impl AsyncFnOnce(&mut Env<'db>) + 'db,
     ) {
-        let _tys = tys.to_vec();
+        let tys = tys.to_vec();
         self.runtime
             .spawn(self, TaskDescription::IfNotNever, async move |env| {
-                // FIXME: check for never
+                for ty in tys {
+                    let (red_ty, _) = ty.to_red_ty(env);
+                    if let RedTy::Never | RedTy::Error(_) = red_ty {
+                        return;
+                    }
+                }
                 op(env).await
             })
     }
 
+    /// Describes `ty` for use in a diagnostic, in the surface syntax (e.g., `ref[x] Pair[u32, T]`).
     pub fn describe_ty<'a>(&'a self, ty: SymTy<'db>) -> impl std::fmt::Display + 'a {
-        format!("{ty:?}") // FIXME
+        ty
     }
 
     #[track_caller]
@@ -534,6 +590,12 @@ impl<'db> Env<'db> {
     #[track_caller]
     pub fn report(&self, diagnostic: Diagnostic) -> Reported {
         self.log("report diagnostic", &[&diagnostic]);
+        if diagnostic.level >= Level::Error
+            && let Err(too_many_errors) = self.runtime.record_error()
+        {
+            // Past the limit, the check stops and reports a summary instead.
+            return too_many_errors;
+        }
         diagnostic.report(self.db())
     }
 
@@ -127,15 +127,15 @@ impl<'db> Env<'db> {
 
         async move {
             let a = pin!(async {
-                let mut env = self.fork(|handle| handle.spawn(caller, TaskDescription::Any(0)));
+                let mut env = self.fork_alternative(caller, TaskDescription::Any(0));
                 let result = a(&mut env).await;
-                env.log_result(caller, result)
+                env.finish_alternative(caller, result)
             });
 
             let b = pin!(async {
-                let mut env = self.fork(|handle| handle.spawn(caller, TaskDescription::Any(1)));
+                let mut env = self.fork_alternative(caller, TaskDescription::Any(1));
                 let result = b(&mut env).await;
-                env.log_result(caller, result)
+                env.finish_alternative(caller, result)
             });
 
             match futures::future::select(a, b).await {
@@ -166,11 +166,10 @@ impl<'db> Env<'db> {
             let unordered = FuturesUnordered::new();
             for (item, index) in items.into_iter().zip(0..) {
                 unordered.push(async move {
-                    let mut env = this.fork(|handle| {
-                        handle.spawn(compiler_location, TaskDescription::All(index))
-                    });
+                    let mut env =
+                        this.fork_alternative(compiler_location, TaskDescription::All(index));
                     let result = test_fn(&mut env, item).await;
-                    env.log_result(compiler_location, result)
+                    env.finish_alternative(compiler_location, result)
                 });
             }
             let mut unordered = pin!(unordered);
@@ -202,11 +201,10 @@ impl<'db> Env<'db> {
             let unordered = FuturesUnordered::new();
             for (item, index) in items.into_iter().zip(0..) {
                 unordered.push(async move {
-                    let mut env = this.fork(|handle| {
-                        handle.spawn(compiler_location, TaskDescription::Any(index))
-                    });
+                    let mut env =
+                        this.fork_alternative(compiler_location, TaskDescription::Any(index));
                     let result = test_fn(&mut env, item).await;
-                    env.log_result(compiler_location, result)
+                    env.finish_alternative(compiler_location, result)
                 });
             }
             let mut unordered = pin!(unordered);
@@ -232,17 +230,15 @@ impl<'db> Env<'db> {
 
         async move {
             let a = async {
-                let mut env =
-                    self.fork(|handle| handle.spawn(compiler_location, TaskDescription::All(0)));
+                let mut env = self.fork_alternative(compiler_location, TaskDescription::All(0));
                 let result = a(&mut env).await;
-                env.log_result(compiler_location, result)
+                env.finish_alternative(compiler_location, result)
             };
 
             let b = async {
-                let mut env =
-                    self.fork(|handle| handle.spawn(compiler_location, TaskDescription::All(1)));
+                let mut env = self.fork_alternative(compiler_location, TaskDescription::All(1));
                 let result = b(&mut env).await;
-                env.log_result(compiler_location, result)
+                env.finish_alternative(compiler_location, result)
             };
 
             match futures::future::select(pin!(a), pin!(b)).await {
@@ -330,6 +326,27 @@ impl<'db> Env<'db> {
             }
         }
     }
+
+    /// Fork the environment to explore one alternative of a speculative combinator
+    /// (e.g., [`Env::either`]), whose result may turn out not to be needed.
+    fn fork_alternative(
+        &self,
+        compiler_location: &'static Location<'static>,
+        task_description: TaskDescription<'db>,
+    ) -> Env<'db> {
+        self.runtime().alternative_started();
+        self.fork(|handle| handle.spawn(compiler_location, task_description))
+    }
+
+    /// Record the result of an alternative forked with [`Env::fork_alternative`].
+    fn finish_alternative(
+        &mut self,
+        compiler_location: &'static Location<'static>,
+        result: Errors<bool>,
+    ) -> Errors<bool> {
+        self.runtime().alternative_finished();
+        self.log_result(compiler_location, result)
+    }
 }
 
 pub struct RequireAll<'env, 'db> {
@@ -47,28 +47,33 @@ impl<'db> SymGenericTermBoundIterator<'db> {
         }
     }
 
-    pub async fn next(&mut self, env: &Env<'db>) -> Option<(Direction, SymGenericTerm<'db>)> {
+    /// Returns the next bound along with the [`ArcOrElse`] recorded when it was set,
+    /// which explains where the bound came from.
+    pub async fn next(
+        &mut self,
+        env: &Env<'db>,
+    ) -> Option<(Direction, SymGenericTerm<'db>, ArcOrElse<'db>)> {
         let db = env.db();
         match &mut self.kind {
             SymGenericTermBoundIteratorKind::Ty(iter) => {
-                let (direction, red_ty) = iter.next(env).await?;
+                let (direction, red_ty, or_else) = iter.next(env).await?;
                 let sym_ty = red_ty.into_sym_ty(db);
                 let result = self.perm.apply_to(db, sym_ty);
                 env.log(
                     "next_bound",
                     &[&self.infer, &InferVarKind::Type, &direction, &result],
                 );
-                Some((direction, result.into()))
+                Some((direction, result.into(), or_else))
             }
             SymGenericTermBoundIteratorKind::Perm(iter) => {
-                let (direction, red_perm) = iter.next(env).await?;
+                let (direction, red_perm, or_else) = iter.next(env).await?;
                 let sym_perm = red_perm.to_sym_perm(db);
                 let result = self.perm.apply_to(db, sym_perm);
                 env.log(
                     "next_bound",
                     &[&self.infer, &InferVarKind::Perm, &direction, &result],
                 );
-                Some((direction, result.into()))
+                Some((direction, result.into(), or_else))
             }
         }
     }
@@ -94,7 +99,7 @@ impl<'db> RedTyBoundIterator<'db> {
     pub fn next(
         &mut self,
         env: &Env<'db>,
-    ) -> impl Future<Output = Option<(Direction, RedTy<'db>)>> {
+    ) -> impl Future<Output = Option<(Direction, RedTy<'db>, ArcOrElse<'db>)>> {
         env.log("next", &[&self.infer]);
         next_bound(
             env,
@@ -125,7 +130,7 @@ impl<'db> RedPermBoundIterator<'db> {
     pub fn next(
         &mut self,
         env: &Env<'db>,
-    ) -> impl Future<Output = Option<(Direction, RedPerm<'db>)>> {
+    ) -> impl Future<Output = Option<(Direction, RedPerm<'db>, ArcOrElse<'db>)>> {
         env.log("next", &[&self.infer]);
         next_bound(
             env,
@@ -142,21 +147,21 @@ fn next_bound<'db, B>(
     infer: InferVarIndex,
     bound_op: impl Fn(&InferenceVarData<'db>, Direction) -> Option<(B, ArcOrElse<'db>)>,
     storage: &mut [Option<B>; 2],
-) -> impl Future<Output = Option<(Direction, B)>>
+) -> impl Future<Output = Option<(Direction, B, ArcOrElse<'db>)>>
 where
     B: PartialEq + Serialize + 'db + Clone,
 {
     env.loop_on_inference_var(infer, move |data| {
-        if let Some((bound, _)) = bound_op(data, Direction::FromAbove)
+        if let Some((bound, or_else)) = bound_op(data, Direction::FromAbove)
             && Some(&bound) != storage[0].as_ref()
         {
             storage[0] = Some(bound.clone());
-            Some((Direction::FromAbove, bound))
-        } else if let Some((bound, _)) = bound_op(data, Direction::FromBelow)
+            Some((Direction::FromAbove, bound, or_else))
+        } else if let Some((bound, or_else)) = bound_op(data, Direction::FromBelow)
             && Some(&bound) != storage[1].as_ref()
         {
             storage[1] = Some(bound.clone());
-            Some((Direction::FromBelow, bound))
+            Some((Direction::FromBelow, bound, or_else))
         } else {
             None
         }
@@ -1,32 +1,37 @@
-use std::panic::Location;
+use std::{cell::Cell, panic::Location, rc::Rc};
 
 use crate::{
     check::{
-        env::Env,
-        member_lookup::MemberLookup,
-        scope::{NameResolution, NameResolutionSym},
-        scope_tree::ScopeTreeNode,
+        env::{EnclosingLoop, Env},
+        member_lookup::{MemberLookup, non_infer_lower_bound},
+        red::RedTy,
+        scope::{NameResolution, NameResolutionSym, Resolve},
+        scope_tree::{ScopeItem, ScopeTreeNode},
+        signature::signature_for_call,
     },
     ir::{
         binder::Binder,
-        classes::SymAggregate,
+        classes::{SymAggregate, SymField},
         exprs::{
             SymBinaryOp, SymByteLiteral, SymByteLiteralData, SymExpr, SymExprKind, SymLiteral,
-            SymMatchArm, SymPlaceExpr, SymPlaceExprKind,
+            SymLoop, SymMatchArm, SymPlaceExpr, SymPlaceExprKind,
         },
-        functions::{SymFunction, SymInputOutput},
+        functions::{SymFunction, SymFunctionSource, SymInputOutput},
+        generics::{SymWhereClause, SymWhereClauseKind},
+        primitive::SymPrimitiveKind,
         types::{SymGenericKind, SymGenericTerm, SymTy, SymTyKind, SymTyName},
         variables::{FromVar, SymVariable},
     },
-    prelude::CheckedSignature,
+    prelude::CheckedInputDefaults,
     well_known,
 };
 use dada_ir_ast::{
     ast::{
-        AstBinaryOp, AstExpr, AstExprKind, AstGenericTerm, Identifier, LiteralKind, PermissionOp,
-        SpanVec, SpannedBinaryOp, SpannedIdentifier, UnaryOp,
+        AstBinaryOp, AstCallArg, AstConstructorField, AstExpr, AstExprKind, AstGenericTerm,
+        AstPath, AstTy, Identifier, IfArm, LiteralKind, PermissionOp, SpanVec, SpannedBinaryOp,
+        SpannedIdentifier, UnaryOp,
     },
-    diagnostic::{Diagnostic, Err, Level, Reported},
+    diagnostic::{Diagnostic, Err, Errors, Level, Reported},
     span::{Span, Spanned},
 };
 use dada_parser::prelude::*;
@@ -35,16 +40,75 @@ use serde::Serialize;
 
 use super::{
     CheckExprInEnv, CheckTyInEnv,
+    captures::captured_variables,
+    coercions::{coerce_numeric, is_char_conversion, numeric_primitive},
     debug::TaskDescription,
     live_places::LivePlaces,
+    match_arms::check_arm_reachability,
+    patterns::{check_if_let, give_field, let_in},
+    places::field_ty_with_perm,
     report::{
-        AwaitNonFuture, BadSubtermError, InvalidAssignmentType, InvalidReturnValue,
+        AwaitNonFuture, BadSubtermError, IfWithoutElse, IncompatibleTryError,
+        InvalidAssignmentType, InvalidBreakValue, InvalidCast, InvalidReturnValue,
         NumericTypeExpected, OperatorArgumentsMustHaveSameType, OperatorRequiresNumericType,
-        WhereClauseError,
+        SpawnCaptureError, WhereClauseError,
     },
     temporaries::Temporary,
 };
 
+/// Returns the loop exited or continued by the `break` or `continue` (as given by `keyword`)
+/// at `span`: the loop with the given label or, if there is no label, the innermost loop.
+/// Reports an error if there is no such loop.
+fn enclosing_loop<'db>(
+    env: &mut Env<'db>,
+    span: Span<'db>,
+    keyword: &str,
+    label: Option<SpannedIdentifier<'db>>,
+) -> Result<Rc<EnclosingLoop<'db>>, Reported> {
+    let db = env.db();
+
+    let enclosing = match label {
+        None => env.loops.last(),
+        Some(label) => env
+            .loops
+            .iter()
+            .rev()
+            .find(|enclosing| enclosing.sym_loop.label(db) == Some(label.id)),
+    };
+    if let Some(enclosing) = enclosing {
+        return Ok(enclosing.clone());
+    }
+
+    let mut diagnostic = match label {
+        None => Diagnostic::error(db, span, format!("`{keyword}` outside of a loop")).label(
+            db,
+            Level::Error,
+            span,
+            format!("there is no loop for this `{keyword}` to refer to"),
+        ),
+        Some(label) => Diagnostic::error(
+            db,
+            label.span,
+            format!("no enclosing loop is labeled `'{}`", label.id.text(db)),
+        )
+        .label(
+            db,
+            Level::Error,
+            label.span,
+            "there is no enclosing loop with this label",
+        ),
+    };
+    if let Some(spawn_span) = env.spawned {
+        diagnostic = diagnostic.label(
+            db,
+            Level::Info,
+            spawn_span,
+            "a spawned task cannot refer to loops outside of its spawn block",
+        );
+    }
+    Err(env.report(diagnostic))
+}
+
 #[derive(Clone, Serialize)]
 pub(crate) struct ExprResult<'db> {
     /// List of [`Temporary`][] variables created by this expression.
@@ -87,6 +151,271 @@ impl<'db> CheckExprInEnv<'db> for AstExpr<'db> {
     }
 }
 
+/// Checks `expr` in statement position, which is the only place an assignment may appear.
+/// If `discarded` is true, the statement is not the last in its block,
+/// so its value is thrown away rather than becoming the value of the block.
+pub(crate) async fn check_statement_expr<'db>(
+    expr: &AstExpr<'db>,
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    discarded: bool,
+) -> ExprResult<'db> {
+    env.indent("check_statement_expr", &[expr], async |env| {
+        match &*expr.kind {
+            AstExprKind::BinaryOp(span_op, lhs, rhs) if span_op.op.is_assignment() => {
+                check_assignment(env, expr.span, *span_op, lhs, rhs).await
+            }
+            AstExprKind::If(ast_arms) => {
+                check_if(env, live_after, expr.span, ast_arms, discarded).await
+            }
+            _ => expr.check_in_env(env, live_after).await,
+        }
+    })
+    .await
+}
+
+/// Checks the assignment `lhs = rhs` (or compound assignment, like `lhs += rhs`) spanning `expr_span`.
+async fn check_assignment<'db>(
+    env: &mut Env<'db>,
+    expr_span: Span<'db>,
+    span_op: SpannedBinaryOp<'db>,
+    lhs: &AstExpr<'db>,
+    rhs: &AstExpr<'db>,
+) -> ExprResult<'db> {
+    let db = env.db();
+    let mut temporaries: Vec<Temporary<'db>> = vec![];
+    let place: SymPlaceExpr<'db> = lhs
+        .check_in_env(env, LivePlaces::fixme())
+        .await
+        .into_place_expr(env, &mut temporaries);
+    let value: SymExpr<'db> = rhs
+        .check_in_env(env, LivePlaces::fixme())
+        .await
+        .into_expr(env, &mut temporaries);
+
+    // `lhs += rhs` assigns `lhs + rhs` to `lhs`. Evaluating `lhs` as a place has already
+    // put anything it computes (e.g., the call in `f().x += 1`) into a temporary,
+    // so reading the place again does not evaluate `lhs` a second time.
+    let value = if span_op.op.compound_assignment_op().is_some() {
+        let current = ExprResult {
+            temporaries: vec![],
+            span: lhs.span,
+            kind: ExprResultKind::PlaceExpr(place),
+        }
+        .into_expr(env, &mut temporaries);
+        check_arithmetic(env, LivePlaces::fixme(), expr_span, span_op, current, value)
+    } else {
+        value
+    };
+    let value = coerce_numeric(env, value, place.ty(db));
+
+    env.spawn_require_assignable_type(
+        LivePlaces::fixme(),
+        value.ty(db),
+        place.ty(db),
+        &InvalidAssignmentType::new(place, value),
+    );
+
+    ExprResult::from_expr(
+        env.db(),
+        SymExpr::new(
+            db,
+            expr_span,
+            SymTy::unit(db),
+            SymExprKind::Assign { place, value },
+        ),
+        temporaries,
+    )
+}
+
+/// Checks the `if` expression with the arms `ast_arms` spanning `expr_span`.
+///
+/// If `discarded` is true, the `if` is a statement whose value is thrown away,
+/// so its arms may produce values of any type (see `check_statement_expr`).
+/// Otherwise, the arms must all produce values of the type of the `if` or,
+/// if there is no `else`, must all produce `()`.
+async fn check_if<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    expr_span: Span<'db>,
+    ast_arms: &[IfArm<'db>],
+    discarded: bool,
+) -> ExprResult<'db> {
+    let db = env.db();
+    let mut arms = vec![];
+    let mut scrutinees = vec![];
+    let mut has_else = false;
+    for arm in ast_arms {
+        let Some(c) = &arm.condition else {
+            has_else = true;
+            let body = arm.result.check_in_env(env, live_after).await;
+            arms.push(SymMatchArm {
+                condition: None,
+                body,
+            });
+            scrutinees.push(None);
+            continue;
+        };
+
+        let expr = c
+            .check_in_env(env, LivePlaces::fixme())
+            .await
+            .into_expr_with_enclosed_temporaries(env);
+
+        // For `if let Some(p) = expr`, the condition tests the option
+        // stored in a temporary, from which the body takes the value.
+        let (condition, body) = match &arm.binding {
+            Some(binding) => {
+                let temp = SymVariable::new(db, SymGenericKind::Place, None, c.span);
+                let body =
+                    check_if_let(env, live_after, binding, temp, expr.ty(db), arm.result).await;
+                let temp_place =
+                    SymPlaceExpr::new(db, c.span, expr.ty(db), SymPlaceExprKind::Var(temp));
+                let condition = SymExpr::new(
+                    db,
+                    c.span,
+                    SymTy::boolean(db),
+                    SymExprKind::IsSome(temp_place),
+                );
+                scrutinees.push(Some((temp, expr)));
+                (condition, body)
+            }
+            None => {
+                env.require_expr_has_bool_ty(LivePlaces::fixme(), expr);
+                let body = arm.result.check_in_env(env, live_after).await;
+                scrutinees.push(None);
+                (expr, body)
+            }
+        };
+
+        arms.push(SymMatchArm {
+            condition: Some(condition),
+            body,
+        });
+    }
+
+    // If there is an `else` and every arm is known to diverge
+    // (e.g., `if c { return 1 } else { return 2 }`), then so does the `if`.
+    // Typing it as `!` up front (rather than leaving it to inference)
+    // lets enclosing blocks see that the code after it is unreachable.
+    let if_ty = if has_else
+        && arms
+            .iter()
+            .all(|arm| matches!(arm.body.ty(db).kind(db), SymTyKind::Never))
+    {
+        SymTy::never(db)
+    } else if !has_else || discarded {
+        SymTy::unit(db)
+    } else {
+        env.fresh_ty_inference_var(expr_span)
+    };
+
+    if discarded && !matches!(if_ty.kind(db), SymTyKind::Never) {
+        // The value of each arm is discarded, so the arms need not agree on a type:
+        // `if c { 1 } else { "one" }` is fine as a statement.
+        for arm in &mut arms {
+            let body_span = arm.body.span(db);
+            let unit = SymExpr::new(db, body_span, SymTy::unit(db), SymExprKind::Tuple(vec![]));
+            arm.body = SymExpr::new(
+                db,
+                body_span,
+                SymTy::unit(db),
+                SymExprKind::Semi(arm.body, unit),
+            );
+        }
+    } else {
+        for arm in &arms {
+            if has_else {
+                env.spawn_require_assignable_type(
+                    live_after,
+                    arm.body.ty(db),
+                    if_ty,
+                    &BadSubtermError::new(arm.body.span(db), arm.body.ty(db), if_ty),
+                );
+            } else {
+                env.spawn_require_assignable_type(
+                    live_after,
+                    arm.body.ty(db),
+                    if_ty,
+                    &IfWithoutElse::new(expr_span, arm.body),
+                );
+            }
+        }
+    }
+
+    let arm_spans = ast_arms
+        .iter()
+        .map(|arm| match &arm.condition {
+            Some(condition) => condition.span.to(db, arm.result.span(db)),
+            None => arm.result.span(db),
+        })
+        .collect::<Vec<_>>();
+    check_arm_reachability(env, &arms, &arm_spans);
+
+    ExprResult {
+        temporaries: vec![],
+        span: expr_span,
+        kind: if_let_match(db, expr_span, if_ty, arms, scrutinees).into(),
+    }
+}
+
+/// Checks the arithmetic operation `lhs op rhs` spanning `expr_span`, where `op` is `+`, `-`, `*`, or `/`
+/// or, when checking a compound assignment like `lhs += rhs`, the corresponding compound assignment.
+fn check_arithmetic<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    expr_span: Span<'db>,
+    span_op: SpannedBinaryOp<'db>,
+    lhs: SymExpr<'db>,
+    rhs: SymExpr<'db>,
+) -> SymExpr<'db> {
+    let db = env.db();
+    let op = span_op.op.compound_assignment_op().unwrap_or(span_op.op);
+
+    // Dividing by a literal zero is guaranteed to trap at runtime,
+    // so we may as well tell the user now.
+    if let AstBinaryOp::Div = op
+        && let SymExprKind::Primitive(SymLiteral::Integral { bits: 0 }) = *rhs.kind(db)
+    {
+        env.report(Diagnostic::error(db, expr_span, "division by zero").label(
+            db,
+            Level::Error,
+            rhs.span(db),
+            "this divisor is always zero",
+        ));
+    }
+
+    // For now, let's do a dumb rule that operands must be
+    // of the same primitive (and scalar) type.
+
+    env.spawn_require_numeric_type(lhs.ty(db), &OperatorRequiresNumericType::new(span_op, lhs));
+    env.spawn_require_numeric_type(rhs.ty(db), &OperatorRequiresNumericType::new(span_op, rhs));
+    env.spawn_if_not_never(&[lhs.ty(db), rhs.ty(db)], async move |env| {
+        env.spawn_require_equal_types(
+            live_after,
+            lhs.ty(db),
+            rhs.ty(db),
+            &OperatorArgumentsMustHaveSameType::new(span_op, lhs, rhs),
+        );
+    });
+
+    // What type do we want these operators to have?
+    // For now I'll just take the LHS, but that seems
+    // wrong if e.g. one side is `!`, then we probably
+    // want `!`, right?
+
+    SymExpr::new(
+        db,
+        expr_span,
+        lhs.ty(db),
+        SymExprKind::BinaryOp(
+            SymBinaryOp::try_from(op).expect("invalid binary op"),
+            lhs,
+            rhs,
+        ),
+    )
+}
+
 #[boxed_async_fn]
 async fn check_expr<'db>(
     expr: &AstExpr<'db>,
@@ -123,62 +452,27 @@ async fn check_expr<'db>(
                     }
                 }
 
-                LiteralKind::String => {
-                    // Generate `String.literal(b"...", length)`
-
-                    // Generate `b"..."`
-                    let bytes = literal.text(db).as_bytes();
-                    let byte_literal_expr = {
-                        let pointer_struct = match well_known::pointer_struct(db) {
-                            Ok(v) => v,
-                            Err(reported) => return ExprResult::err(db, reported),
-                        };
-                        let data = SymByteLiteralData::new(db, bytes);
-                        let byte_literal = SymByteLiteral::new(db, expr_span, data);
-                        SymExpr::new(
-                            db,
-                            expr_span,
-                            SymTy::named(db, pointer_struct.into(), vec![SymTy::u8(db).into()]),
-                            SymExprKind::ByteLiteral(byte_literal),
-                        )
-                    };
-
-                    // Generate `length`
-                    let len_literal_expr = {
-                        let value = bytes.len() as u64;
-                        SymExpr::new(
-                            db,
-                            expr_span,
-                            SymTy::u32(db),
-                            SymExprKind::Primitive(SymLiteral::Integral { bits: value }),
-                        )
-                    };
+                LiteralKind::String => string_literal(db, expr_span, literal.text(db).as_bytes()),
 
-                    // Generate and return `String.literal(b"...", length)`
-                    let mut temporaries = vec![];
-                    let ctor_call_expr = {
-                        let literal_fn = match well_known::string_literal_fn(db) {
-                            Ok(v) => v,
-                            Err(reported) => return ExprResult::err(db, reported),
-                        };
-                        SymExpr::new(
+                LiteralKind::Char => {
+                    // 💡 The tokenizer has already reported any malformed literal.
+                    let ch = literal
+                        .text(db)
+                        .chars()
+                        .next()
+                        .unwrap_or(char::REPLACEMENT_CHARACTER);
+                    ExprResult {
+                        temporaries: vec![],
+                        span: expr_span,
+                        kind: SymExpr::new(
                             db,
                             expr_span,
-                            SymTy::string(db),
-                            SymExprKind::Call {
-                                function: literal_fn,
-                                substitution: vec![],
-                                arg_temps: vec![
-                                    byte_literal_expr.into_temporary_var(db, &mut temporaries),
-                                    len_literal_expr.into_temporary_var(db, &mut temporaries),
-                                ],
-                            },
+                            SymTy::primitive(db, SymPrimitiveKind::Char),
+                            SymExprKind::Primitive(SymLiteral::Integral {
+                                bits: u64::from(ch),
+                            }),
                         )
-                    };
-                    ExprResult {
-                        temporaries,
-                        span: expr_span,
-                        kind: ctor_call_expr.into(),
+                        .into(),
                     }
                 }
 
@@ -248,43 +542,9 @@ async fn check_expr<'db>(
                             .await
                             .into_expr(env, &mut temporaries);
 
-                        // For now, let's do a dumb rule that operands must be
-                        // of the same primitive (and scalar) type.
-
-                        env.spawn_require_numeric_type(
-                            lhs.ty(db),
-                            &OperatorRequiresNumericType::new(span_op, lhs),
-                        );
-                        env.spawn_require_numeric_type(
-                            rhs.ty(db),
-                            &OperatorRequiresNumericType::new(span_op, rhs),
-                        );
-                        env.spawn_if_not_never(&[lhs.ty(db), rhs.ty(db)], async move |env| {
-                            env.spawn_require_equal_types(
-                                live_after,
-                                lhs.ty(db),
-                                rhs.ty(db),
-                                &OperatorArgumentsMustHaveSameType::new(span_op, lhs, rhs),
-                            );
-                        });
-
-                        // What type do we want these operators to have?
-                        // For now I'll just take the LHS, but that seems
-                        // wrong if e.g. one side is `!`, then we probably
-                        // want `!`, right?
-
                         ExprResult::from_expr(
                             env.db(),
-                            SymExpr::new(
-                                db,
-                                expr_span,
-                                lhs.ty(db),
-                                SymExprKind::BinaryOp(
-                                    SymBinaryOp::try_from(span_op.op).expect("invalid binary op"),
-                                    lhs,
-                                    rhs,
-                                ),
-                            ),
+                            check_arithmetic(env, live_after, expr_span, span_op, lhs, rhs),
                             temporaries,
                         )
                     }
@@ -375,12 +635,13 @@ async fn check_expr<'db>(
 
                         // For now, let's do a dumb rule that operands must be
                         // of the same primitive (and scalar) type.
+                        // Unlike arithmetic, comparisons also accept `char`.
 
-                        env.spawn_require_numeric_type(
+                        env.spawn_require_comparable_type(
                             lhs.ty(db),
                             &OperatorRequiresNumericType::new(span_op, lhs),
                         );
-                        env.spawn_require_numeric_type(
+                        env.spawn_require_comparable_type(
                             rhs.ty(db),
                             &OperatorRequiresNumericType::new(span_op, rhs),
                         );
@@ -414,36 +675,28 @@ async fn check_expr<'db>(
                         )
                     }
 
-                    AstBinaryOp::Assign => {
-                        let mut temporaries: Vec<Temporary<'db>> = vec![];
-                        let place: SymPlaceExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
-                            .await
-                            .into_place_expr(env, &mut temporaries);
-                        let value: SymExpr<'db> = rhs
-                            .check_in_env(env, LivePlaces::fixme())
-                            .await
-                            .into_expr(env, &mut temporaries);
-
-                        // For now, let's do a dumb rule that operands must be
-                        // of the same primitive (and scalar) type.
-
-                        env.spawn_require_assignable_type(
-                            LivePlaces::fixme(),
-                            value.ty(db),
-                            place.ty(db),
-                            &InvalidAssignmentType::new(place, value),
-                        );
-
-                        ExprResult::from_expr(
-                            env.db(),
-                            SymExpr::new(
-                                db,
-                                expr_span,
-                                SymTy::unit(db),
-                                SymExprKind::Assign { place, value },
+                    AstBinaryOp::Assign
+                    | AstBinaryOp::AddAssign
+                    | AstBinaryOp::SubAssign
+                    | AstBinaryOp::MulAssign
+                    | AstBinaryOp::DivAssign => {
+                        // Assignments are only permitted as statements (see `check_statement_expr`),
+                        // so `if x = y` is most likely a typo for `if x == y`.
+                        let message = if span_op.op == AstBinaryOp::Assign {
+                            "assignment is not an expression; did you mean `==`?"
+                        } else {
+                            "assignment is not an expression"
+                        };
+                        ExprResult::err(
+                            db,
+                            env.report(
+                                Diagnostic::error(db, expr_span, message).label(
+                                    db,
+                                    Level::Error,
+                                    span_op.span,
+                                    "an assignment cannot be used as a value",
+                                ),
                             ),
-                            temporaries,
                         )
                     }
                 }
@@ -553,6 +806,16 @@ async fn check_expr<'db>(
             }
 
             AstExprKind::ParenthesisOp(owner, ast_args) => {
+                // A trailing block is a closure. Until closures are supported,
+                // report that instead of checking a call whose arguments can't be typed.
+                if let Some(closure) = ast_args
+                    .values
+                    .iter()
+                    .find(|arg| matches!(*arg.value.kind, AstExprKind::Closure(_)))
+                {
+                    return closure.value.check_in_env(env, live_after).await;
+                }
+
                 let owner_result = owner.check_in_env(env, live_after).await;
                 match owner_result {
                     ExprResult {
@@ -638,7 +901,10 @@ async fn check_expr<'db>(
                 }
             }
 
-            AstExprKind::Constructor(_ast_path, _span_vec) => todo!(),
+            AstExprKind::Constructor(ast_path, ast_fields, ast_base) => {
+                check_constructor(env, expr_span, *ast_path, ast_fields, ast_base.as_ref()).await
+            }
+            AstExprKind::Assert(ast_condition) => check_assert(env, expr_span, ast_condition).await,
             AstExprKind::Return(ast_expr) => {
                 let mut temporaries = vec![];
 
@@ -652,6 +918,22 @@ async fn check_expr<'db>(
                     SymExpr::new(db, expr_span, SymTy::unit(db), SymExprKind::Tuple(vec![]))
                 };
 
+                if let Some(spawn_span) = env.spawned {
+                    return ExprResult::err(
+                        db,
+                        env.report(
+                            Diagnostic::error(db, expr_span, "`return` inside a `spawn` block")
+                                .label(
+                                    db,
+                                    Level::Error,
+                                    expr_span,
+                                    "a spawned task cannot return from the function that spawned it",
+                                )
+                                .label(db, Level::Info, spawn_span, "the spawn block is here"),
+                        ),
+                    );
+                }
+
                 let Some(expected_return_ty) = env.return_ty else {
                     return ExprResult::err(
                         db,
@@ -671,6 +953,7 @@ async fn check_expr<'db>(
                     );
                 };
 
+                let return_expr = coerce_numeric(env, return_expr, expected_return_ty);
                 env.spawn_require_assignable_type(
                     LivePlaces::none(env),
                     return_expr.ty(db),
@@ -707,6 +990,19 @@ async fn check_expr<'db>(
 
                 let awaited_ty = env.fresh_ty_inference_var(await_span);
 
+                if let Some(atomic_span) = env.atomic {
+                    env.report(
+                        Diagnostic::error(db, await_span, "`.await` inside an `atomic` block")
+                            .label(
+                                db,
+                                Level::Error,
+                                await_span,
+                                "awaiting could suspend in the middle of the atomic block",
+                            )
+                            .label(db, Level::Info, atomic_span, "the atomic block is here"),
+                    );
+                }
+
                 env.spawn_require_future_type(
                     live_after,
                     future_ty,
@@ -756,81 +1052,683 @@ async fn check_expr<'db>(
                 UnaryOp::Negate => todo!(),
             },
 
+            AstExprKind::Cast(ast_value, ast_ty) => {
+                check_cast(env, live_after, expr_span, ast_value, *ast_ty).await
+            }
+
             AstExprKind::Block(ast_block) => ExprResult {
                 temporaries: vec![],
                 span: expr_span,
                 kind: ast_block.check_in_env(env, live_after).await.into(),
             },
 
-            AstExprKind::If(ast_arms) => {
-                let mut arms = vec![];
-                let mut has_else = false;
-                for arm in ast_arms {
-                    let condition = if let Some(c) = &arm.condition {
-                        let expr = c
-                            .check_in_env(env, LivePlaces::fixme())
-                            .await
-                            .into_expr_with_enclosed_temporaries(env);
-                        env.require_expr_has_bool_ty(LivePlaces::fixme(), expr);
-                        Some(expr)
-                    } else {
-                        has_else = true;
-                        None
-                    };
+            // 💡 Dada programs run on a single thread for now, so an atomic block needs no
+            // transaction at runtime: it lowers to its body. Checking the body with
+            // `env.atomic` set enforces the restrictions that a real transaction will need.
+            AstExprKind::Atomic(ast_block) => {
+                let outer_atomic = env.atomic.replace(expr_span);
+                let block = ast_block.check_in_env(env, live_after).await;
+                env.atomic = outer_atomic;
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: block.into(),
+                }
+            }
 
-                    let body = arm.result.check_in_env(env, live_after).await;
+            AstExprKind::Closure(_) => ExprResult::err(
+                db,
+                env.report(
+                    Diagnostic::error(db, expr_span, "closures are not yet supported").label(
+                        db,
+                        Level::Error,
+                        expr_span,
+                        "this block is passed to the call as a closure",
+                    ),
+                ),
+            ),
 
-                    arms.push(SymMatchArm { condition, body });
+            // 💡 Until Dada has threads, a spawned task runs to completion when it is spawned,
+            // so a spawn block lowers to its body. The restrictions on what it captures
+            // are the ones that real threads will need.
+            AstExprKind::Spawn(ast_block) => {
+                let outer_spawned = env.spawned.replace(expr_span);
+                let outer_loops = std::mem::take(&mut env.loops);
+                let body = ast_block.check_in_env(env, live_after).await;
+                env.spawned = outer_spawned;
+                env.loops = outer_loops;
+
+                for (var, use_span) in captured_variables(db, body) {
+                    let ty = env.variable_ty(var).await;
+                    let where_clause =
+                        SymWhereClause::new(db, ty.into(), SymWhereClauseKind::Owned);
+                    env.spawn_require_where_clause(
+                        where_clause,
+                        &SpawnCaptureError::new(use_span, var, ty, expr_span),
+                    );
                 }
 
-                let if_ty = if !has_else {
-                    SymTy::unit(db)
-                } else {
-                    env.fresh_ty_inference_var(expr_span)
-                };
+                let unit_ty = SymTy::unit(db);
+                env.spawn_require_assignable_type(
+                    live_after,
+                    body.ty(db),
+                    unit_ty,
+                    &BadSubtermError::new(body.span(db), body.ty(db), unit_ty),
+                );
 
-                for arm in &arms {
-                    env.spawn_require_assignable_type(
-                        live_after,
-                        arm.body.ty(db),
-                        if_ty,
-                        &BadSubtermError::new(arm.body.span(db), arm.body.ty(db), if_ty),
-                    );
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: body.into(),
                 }
+            }
+
+            // The type of a loop is inferred from the values given to the `break`s
+            // that exit it. A loop that no `break` exits never finishes, so its type is `!`.
+            AstExprKind::Loop { label, body } => {
+                let sym_loop = SymLoop::new(db, expr_span, label.map(|label| label.id));
+                let enclosing = Rc::new(EnclosingLoop {
+                    sym_loop,
+                    ty: Cell::new(None),
+                });
 
+                env.loops.push(enclosing.clone());
+                let body = body.check_in_env(env, LivePlaces::fixme()).await;
+                env.loops.pop();
+
+                let loop_ty = enclosing.ty.get().unwrap_or_else(|| SymTy::never(db));
                 ExprResult {
                     temporaries: vec![],
                     span: expr_span,
-                    kind: SymExpr::new(db, expr_span, if_ty, SymExprKind::Match { arms }).into(),
+                    kind: SymExpr::new(db, expr_span, loop_ty, SymExprKind::Loop { sym_loop, body })
+                        .into(),
                 }
             }
 
-            AstExprKind::PermissionOp { value, op } => {
-                let mut temporaries = vec![];
-                let value_result = value.check_in_env(env, live_after).await;
-                let place_expr = value_result.into_place_expr(env, &mut temporaries);
-                let sym_place = place_expr.into_sym_place(db);
+            // A `while` loop has type `()`, including when it never finishes,
+            // and lowers to `loop { if condition { body } else { break } }`.
+            AstExprKind::While {
+                label,
+                condition,
+                body,
+            } => {
+                let unit_ty = SymTy::unit(db);
+                let sym_loop = SymLoop::new(db, expr_span, label.map(|label| label.id));
+                env.loops.push(Rc::new(EnclosingLoop {
+                    sym_loop,
+                    ty: Cell::new(Some(unit_ty)),
+                }));
+                let condition = condition
+                    .check_in_env(env, LivePlaces::fixme())
+                    .await
+                    .into_expr_with_enclosed_temporaries(env);
+                env.require_expr_has_bool_ty(LivePlaces::fixme(), condition);
+                let body = body.check_in_env(env, LivePlaces::fixme()).await;
+                env.loops.pop();
+
+                env.spawn_require_assignable_type(
+                    LivePlaces::fixme(),
+                    body.ty(db),
+                    unit_ty,
+                    &BadSubtermError::new(body.span(db), body.ty(db), unit_ty),
+                );
+
+                let exit = SymExpr::new(
+                    db,
+                    expr_span,
+                    SymTy::never(db),
+                    SymExprKind::Break {
+                        from_loop: sym_loop,
+                        with_value: SymExpr::new(
+                            db,
+                            expr_span,
+                            unit_ty,
+                            SymExprKind::Tuple(vec![]),
+                        ),
+                    },
+                );
+                let arms = vec![
+                    SymMatchArm {
+                        condition: Some(condition),
+                        body,
+                    },
+                    SymMatchArm {
+                        condition: None,
+                        body: exit,
+                    },
+                ];
+                let iteration = SymExpr::new(db, expr_span, unit_ty, SymExprKind::Match { arms });
                 ExprResult {
-                    temporaries,
+                    temporaries: vec![],
                     span: expr_span,
                     kind: SymExpr::new(
                         db,
                         expr_span,
-                        match op {
-                            PermissionOp::Mutate => place_expr.ty(db).mutable(db, sym_place),
-                            PermissionOp::Reference => place_expr.ty(db).referenced(db, sym_place),
-                            PermissionOp::Give => place_expr.ty(db),
-                            PermissionOp::Share => place_expr.ty(db).shared(db),
+                        unit_ty,
+                        SymExprKind::Loop {
+                            sym_loop,
+                            body: iteration,
                         },
-                        SymExprKind::PermissionOp(*op, place_expr),
                     )
                     .into(),
                 }
             }
-        }
-    })
-    .await
-}
+
+            AstExprKind::Break { label, value } => {
+                let mut temporaries = vec![];
+
+                let with_value = if let Some(value) = value {
+                    value
+                        .check_in_env(env, LivePlaces::fixme())
+                        .await
+                        .into_expr(env, &mut temporaries)
+                } else {
+                    // the default is `break ()`
+                    SymExpr::new(db, expr_span, SymTy::unit(db), SymExprKind::Tuple(vec![]))
+                };
+
+                let enclosing = match enclosing_loop(env, expr_span, "break", *label) {
+                    Ok(enclosing) => enclosing,
+                    Err(reported) => return ExprResult::err(db, reported),
+                };
+                let from_loop = enclosing.sym_loop;
+                let loop_ty = match enclosing.ty.get() {
+                    Some(ty) => ty,
+                    None => {
+                        let ty = env.fresh_ty_inference_var(from_loop.span(db));
+                        enclosing.ty.set(Some(ty));
+                        ty
+                    }
+                };
+
+                env.spawn_require_assignable_type(
+                    LivePlaces::fixme(),
+                    with_value.ty(db),
+                    loop_ty,
+                    &InvalidBreakValue::new(with_value, from_loop.span(db)),
+                );
+
+                ExprResult {
+                    temporaries,
+                    span: expr_span,
+                    kind: SymExpr::new(
+                        db,
+                        expr_span,
+                        SymTy::never(db),
+                        SymExprKind::Break {
+                            from_loop,
+                            with_value,
+                        },
+                    )
+                    .into(),
+                }
+            }
+
+            AstExprKind::Continue(label) => {
+                let to_loop = match enclosing_loop(env, expr_span, "continue", *label) {
+                    Ok(enclosing) => enclosing.sym_loop,
+                    Err(reported) => return ExprResult::err(db, reported),
+                };
+
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: SymExpr::new(
+                        db,
+                        expr_span,
+                        SymTy::never(db),
+                        SymExprKind::Continue { to_loop },
+                    )
+                    .into(),
+                }
+            }
+
+            AstExprKind::If(ast_arms) => {
+                check_if(env, live_after, expr_span, ast_arms, false).await
+            }
+
+            AstExprKind::Try {
+                value,
+                question_mark,
+            } => check_try(env, live_after, expr_span, value, *question_mark).await,
+
+            AstExprKind::PermissionOp { value, op } => {
+                let mut temporaries = vec![];
+                let value_result = value.check_in_env(env, live_after).await;
+                let place_expr = value_result.into_place_expr(env, &mut temporaries);
+                ExprResult {
+                    temporaries,
+                    span: expr_span,
+                    kind: permission_op_expr(db, expr_span, *op, place_expr).into(),
+                }
+            }
+        }
+    })
+    .await
+}
+
+/// Creates the match for an `if` with the given `arms`, of type `ty`.
+/// The arm for `if let Some(p) = expr` stores `expr` in a temporary,
+/// so it and the arms after it are nested within `let temp = expr in ...`:
+///
+/// ```text
+/// match {
+///     c0 => b0,
+///     else => let temp = expr in match {
+///         IsSome(temp) => b1,
+///         ...
+///     }
+/// }
+/// ```
+fn if_let_match<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    ty: SymTy<'db>,
+    arms: Vec<SymMatchArm<'db>>,
+    scrutinees: Vec<Option<(SymVariable<'db>, SymExpr<'db>)>>,
+) -> SymExpr<'db> {
+    let mut nested = vec![];
+    for (arm, scrutinee) in arms.into_iter().zip(scrutinees).rev() {
+        nested.insert(0, arm);
+        if let Some((temp, expr)) = scrutinee {
+            let arms = std::mem::take(&mut nested);
+            let body = SymExpr::new(db, span, ty, SymExprKind::Match { arms });
+            nested.push(SymMatchArm {
+                condition: None,
+                body: let_in(db, span, temp, expr.ty(db), expr, body),
+            });
+        }
+    }
+
+    match &nested[..] {
+        [
+            SymMatchArm {
+                condition: None,
+                body,
+            },
+        ] => *body,
+        _ => SymExpr::new(db, span, ty, SymExprKind::Match { arms: nested }),
+    }
+}
+
+/// Generate `place_expr.op` (e.g., `place_expr.ref`), whose type is the type of
+/// `place_expr` with the permission from `op` applied.
+pub(crate) fn permission_op_expr<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    op: PermissionOp,
+    place_expr: SymPlaceExpr<'db>,
+) -> SymExpr<'db> {
+    let sym_place = place_expr.into_sym_place(db);
+    SymExpr::new(
+        db,
+        span,
+        match op {
+            PermissionOp::Mutate => place_expr.ty(db).mutable(db, sym_place),
+            PermissionOp::Reference => place_expr.ty(db).referenced(db, sym_place),
+            PermissionOp::Give => place_expr.ty(db),
+            PermissionOp::Share => place_expr.ty(db).shared(db),
+        },
+        SymExprKind::PermissionOp(op, place_expr),
+    )
+}
+
+/// Generate `String.literal(b"...", length)` for the string with the given `bytes`.
+fn string_literal<'db>(
+    db: &'db dyn crate::Db,
+    expr_span: Span<'db>,
+    bytes: &[u8],
+) -> ExprResult<'db> {
+    // Generate `b"..."`
+    let byte_literal_expr = {
+        let pointer_struct = match well_known::pointer_struct(db) {
+            Ok(v) => v,
+            Err(reported) => return ExprResult::err(db, reported),
+        };
+        let data = SymByteLiteralData::new(db, bytes);
+        let byte_literal = SymByteLiteral::new(db, expr_span, data);
+        SymExpr::new(
+            db,
+            expr_span,
+            SymTy::named(db, pointer_struct.into(), vec![SymTy::u8(db).into()]),
+            SymExprKind::ByteLiteral(byte_literal),
+        )
+    };
+
+    // Generate `length`
+    let len_literal_expr = {
+        let value = bytes.len() as u64;
+        SymExpr::new(
+            db,
+            expr_span,
+            SymTy::u32(db),
+            SymExprKind::Primitive(SymLiteral::Integral { bits: value }),
+        )
+    };
+
+    // Generate and return `String.literal(b"...", length)`
+    let mut temporaries = vec![];
+    let ctor_call_expr = {
+        let literal_fn = match well_known::string_literal_fn(db) {
+            Ok(v) => v,
+            Err(reported) => return ExprResult::err(db, reported),
+        };
+        SymExpr::new(
+            db,
+            expr_span,
+            SymTy::string(db),
+            SymExprKind::Call {
+                function: literal_fn,
+                substitution: vec![],
+                arg_temps: vec![
+                    byte_literal_expr.into_temporary_var(db, &mut temporaries),
+                    len_literal_expr.into_temporary_var(db, &mut temporaries),
+                ],
+            },
+        )
+    };
+    ExprResult {
+        temporaries,
+        span: expr_span,
+        kind: ctor_call_expr.into(),
+    }
+}
+
+/// Check `assert condition`, which lowers to
+/// `if !condition { panic("assertion failed: <condition>") }`,
+/// where `<condition>` is the source text of the condition.
+async fn check_assert<'db>(
+    env: &mut Env<'db>,
+    expr_span: Span<'db>,
+    ast_condition: &AstExpr<'db>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    let condition = ast_condition
+        .check_in_env(env, LivePlaces::fixme())
+        .await
+        .into_expr_with_enclosed_temporaries(env);
+    env.require_expr_has_bool_ty(LivePlaces::fixme(), condition);
+
+    let panic_fn = match well_known::panic_fn(db) {
+        Ok(v) => v,
+        Err(reported) => return ExprResult::err(db, reported),
+    };
+
+    // Capture the text of the condition now, since it is not available at runtime.
+    let condition_text = {
+        let span = ast_condition.span.absolute_span(db);
+        let contents = span.source_file.contents_if_ok(db);
+        match contents.get(span.start.as_usize()..span.end.as_usize()) {
+            Some(text) => text,
+            None => {
+                return ExprResult::err(
+                    db,
+                    env.report(
+                        Diagnostic::error(
+                            db,
+                            ast_condition.span,
+                            "could not read the source text of the assertion",
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            ast_condition.span,
+                            "the panic message includes the text of this condition",
+                        ),
+                    ),
+                );
+            }
+        }
+    };
+    let message = format!("assertion failed: {condition_text}");
+
+    // Generate `panic("assertion failed: ...")`
+    let mut temporaries = vec![];
+    let message_expr =
+        string_literal(db, ast_condition.span, message.as_bytes()).into_expr(env, &mut temporaries);
+    let panic_call_expr = SymExpr::new(
+        db,
+        expr_span,
+        SymTy::never(db),
+        SymExprKind::Call {
+            function: panic_fn,
+            substitution: vec![],
+            arg_temps: vec![message_expr.into_temporary_var(db, &mut temporaries)],
+        },
+    );
+    let panic_expr = ExprResult {
+        temporaries,
+        span: expr_span,
+        kind: panic_call_expr.into(),
+    }
+    .into_expr_with_enclosed_temporaries(env);
+
+    // Generate `if !condition { panic(...) }`
+    let not_condition = SymExpr::new(
+        db,
+        condition.span(db),
+        SymTy::boolean(db),
+        SymExprKind::Not {
+            operand: condition,
+            op_span: expr_span,
+        },
+    );
+    let arms = vec![
+        SymMatchArm {
+            condition: Some(not_condition),
+            body: panic_expr,
+        },
+        SymMatchArm {
+            condition: None,
+            body: SymExpr::new(db, expr_span, SymTy::unit(db), SymExprKind::Tuple(vec![])),
+        },
+    ];
+
+    ExprResult {
+        temporaries: vec![],
+        span: expr_span,
+        kind: SymExpr::new(db, expr_span, SymTy::unit(db), SymExprKind::Match { arms }).into(),
+    }
+}
+
+/// Check `value?`, where `value` is a `Result`, which becomes
+///
+/// ```text
+/// match {
+///     IsSome(temp) => temp.value.give,
+///     else => return Err(temp.error.give),
+/// }
+/// ```
+///
+/// where `temp` is a temporary storing `value`.
+/// The enclosing function must return a `Result` to which the error can be given.
+async fn check_try<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    expr_span: Span<'db>,
+    ast_value: &AstExpr<'db>,
+    question_mark: Span<'db>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    let mut temporaries = vec![];
+    let value = ast_value
+        .check_in_env(env, live_after)
+        .await
+        .into_expr(env, &mut temporaries);
+    let value_ty = value.ty(db);
+    let temp = value.into_temporary(db, &mut temporaries);
+
+    let (result_class, err_fn) = match (well_known::result_class(db), well_known::err_fn(db)) {
+        (Ok(result_class), Ok(err_fn)) => (result_class, err_fn),
+        (Err(reported), _) | (_, Err(reported)) => return ExprResult::err(db, reported),
+    };
+
+    match non_infer_lower_bound(env, value_ty).await.0 {
+        RedTy::Named(SymTyName::Aggregate(aggr), _) if aggr == result_class => {}
+        RedTy::Error(reported) => return ExprResult::err(db, reported),
+        _ => {
+            return ExprResult::err(
+                db,
+                env.report(
+                    Diagnostic::error(db, question_mark, "`?` requires a `Result`").label(
+                        db,
+                        Level::Error,
+                        ast_value.span,
+                        format!("I expected a `Result` here, but this has type `{value_ty}`"),
+                    ),
+                ),
+            );
+        }
+    }
+
+    // Like `return`, `?` cannot return from within a `spawn` block.
+    if let Some(spawn_span) = env.spawned {
+        return ExprResult::err(
+            db,
+            env.report(
+                Diagnostic::error(db, question_mark, "`?` inside a `spawn` block")
+                    .label(
+                        db,
+                        Level::Error,
+                        question_mark,
+                        "a spawned task cannot return an error from the function that spawned it",
+                    )
+                    .label(db, Level::Info, spawn_span, "the spawn block is here"),
+            ),
+        );
+    }
+
+    let return_generics = match env.return_ty {
+        Some(return_ty) => match non_infer_lower_bound(env, return_ty).await.0 {
+            RedTy::Named(SymTyName::Aggregate(aggr), generics) if aggr == result_class => {
+                Some((return_ty, generics))
+            }
+            RedTy::Error(reported) => return ExprResult::err(db, reported),
+            _ => None,
+        },
+        None => None,
+    };
+    let Some((return_ty, return_generics)) = return_generics else {
+        return ExprResult::err(
+            db,
+            env.report(
+                Diagnostic::error(db, question_mark, "`?` outside of a function returning `Result`")
+                    .label(
+                        db,
+                        Level::Error,
+                        question_mark,
+                        "`?` returns the error from the enclosing function, which does not return a `Result`",
+                    ),
+            ),
+        );
+    };
+
+    let ok_value = give_field(env, temp, "value", question_mark).await;
+    let error = give_field(env, temp, "error", question_mark).await;
+    env.spawn_require_assignable_type(
+        live_after,
+        error.ty(db),
+        return_generics[1].assert_type(db),
+        &IncompatibleTryError::new(question_mark, error.ty(db), return_ty),
+    );
+
+    // Generate `return Err(temp.error.give)`
+    let mut error_temporaries = vec![];
+    let err_call_expr = SymExpr::new(
+        db,
+        question_mark,
+        return_ty,
+        SymExprKind::Call {
+            function: err_fn,
+            substitution: return_generics,
+            arg_temps: vec![error.into_temporary_var(db, &mut error_temporaries)],
+        },
+    );
+    let return_expr = ExprResult {
+        temporaries: error_temporaries,
+        span: question_mark,
+        kind: SymExpr::new(
+            db,
+            question_mark,
+            SymTy::never(db),
+            SymExprKind::Return(err_call_expr),
+        )
+        .into(),
+    }
+    .into_expr_with_enclosed_temporaries(env);
+
+    let is_ok = SymExpr::new(
+        db,
+        question_mark,
+        SymTy::boolean(db),
+        SymExprKind::IsSome(temp),
+    );
+    let arms = vec![
+        SymMatchArm {
+            condition: Some(is_ok),
+            body: ok_value,
+        },
+        SymMatchArm {
+            condition: None,
+            body: return_expr,
+        },
+    ];
+
+    ExprResult {
+        temporaries,
+        span: expr_span,
+        kind: SymExpr::new(db, expr_span, ok_value.ty(db), SymExprKind::Match { arms }).into(),
+    }
+}
+
+/// Check `value as ty`.
+///
+/// Between two different numeric types, this converts the value,
+/// as it does between `char` and `u32`.
+/// Otherwise it is an ascription: the value must be assignable to `ty`
+/// and the result is the value itself.
+async fn check_cast<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    expr_span: Span<'db>,
+    ast_value: &AstExpr<'db>,
+    ast_ty: AstTy<'db>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    let mut temporaries = vec![];
+    let value = ast_value
+        .check_in_env(env, live_after)
+        .await
+        .into_expr(env, &mut temporaries);
+    let value_ty = value.ty(db);
+    let ty = ast_ty.check_in_env(env).await;
+
+    let expr = match (numeric_primitive(db, value_ty), numeric_primitive(db, ty)) {
+        (Some(from), Some(to)) if from != to => {
+            SymExpr::new(db, expr_span, ty, SymExprKind::Cast(value))
+        }
+        (Some(_), Some(_)) => value,
+        _ if is_char_conversion(db, value_ty, ty) => {
+            SymExpr::new(db, expr_span, ty, SymExprKind::Cast(value))
+        }
+        _ => {
+            let or_else = InvalidCast::new(value, ast_ty.span(db), ty);
+            if let SymTyKind::Infer(_) = value_ty.kind(db) {
+                // 💡 A value whose type is not yet known, like a literal,
+                // takes on the ascribed type, so `22 as i64` is simply an `i64`.
+                env.spawn_require_equal_types(live_after, ty, value_ty, &or_else);
+            } else {
+                env.spawn_require_assignable_type(live_after, value_ty, ty, &or_else);
+            }
+            value
+        }
+    };
+
+    ExprResult::from_expr(db, expr, temporaries)
+}
 
 #[boxed_async_fn]
 async fn check_class_call<'db>(
@@ -840,7 +1738,7 @@ async fn check_class_call<'db>(
     expr_span: Span<'db>,
     name_resolution: NameResolution<'db>,
     class_sym: SymAggregate<'db>,
-    ast_args: &SpanVec<'db, AstExpr<'db>>,
+    ast_args: &SpanVec<'db, AstCallArg<'db>>,
     temporaries: Vec<Temporary<'db>>,
 ) -> ExprResult<'db> {
     let db = env.db();
@@ -934,6 +1832,284 @@ fn report_no_new_method<'db>(
     diag.report(db)
 }
 
+/// Check a constructor expression like `Point { x: 1, y: 2 }`.
+///
+/// If a base expression is given, as in `Point { ..p, x: 1 }`, then any fields
+/// not listed explicitly are given from the corresponding field of the base.
+/// The base must have the same class; its permission is applied to the fields
+/// taken from it, so e.g. a `ref` base can only supply fields that can be copied.
+#[boxed_async_fn]
+async fn check_constructor<'db>(
+    env: &mut Env<'db>,
+    expr_span: Span<'db>,
+    ast_path: AstPath<'db>,
+    ast_fields: &SpanVec<'db, AstConstructorField<'db>>,
+    ast_base: Option<&AstExpr<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    let (aggr, mut generics) = match ast_path.resolve_in(env).await {
+        Ok(NameResolution {
+            generics,
+            sym: NameResolutionSym::SymAggregate(aggr),
+        }) => (aggr, generics),
+        Ok(name_resolution) => {
+            let reported = report_not_constructible(db, ast_path.span(db), &name_resolution);
+            check_ignored_constructor_args(env, ast_fields, ast_base).await;
+            return ExprResult::err(db, reported);
+        }
+        Err(reported) => {
+            check_ignored_constructor_args(env, ast_fields, ast_base).await;
+            return ExprResult::err(db, reported);
+        }
+    };
+
+    // Create inference variables for any generic arguments not provided.
+    let generic_variables = &aggr.symbols(db).generic_variables;
+    generics.extend(env.existential_substitution(expr_span, &generic_variables[generics.len()..]));
+    let aggr_ty = SymTy::named(db, aggr.into(), generics.clone());
+
+    // Field types may refer to `self`, which here is the value under construction.
+    let self_var = SymVariable::new(db, SymGenericKind::Place, None, expr_span);
+    let self_term = SymGenericTerm::var(db, self_var);
+    let field_ty = |field: SymField<'db>| {
+        field
+            .checked_field_ty(db)
+            .substitute(db, &generics)
+            .substitute(db, &[self_term])
+    };
+
+    let mut temporaries = vec![];
+
+    // Check the base expression (if any) and confirm it has the same class.
+    let base = match ast_base {
+        Some(ast_base) => {
+            let base_result = ast_base.check_in_env(env, LivePlaces::fixme()).await;
+            let base_ty = base_result.ty(env);
+            let base_place = base_result.into_place_expr(env, &mut temporaries);
+            match non_infer_lower_bound(env, base_ty).await {
+                (RedTy::Named(SymTyName::Aggregate(base_aggr), base_generics), base_perm)
+                    if base_aggr == aggr =>
+                {
+                    Some((base_place, base_generics, base_perm))
+                }
+                (RedTy::Error(reported), _) => {
+                    check_ignored_constructor_args(env, ast_fields, None).await;
+                    return ExprResult::err(db, reported);
+                }
+                _ => {
+                    let reported = env.report(
+                        Diagnostic::error(
+                            db,
+                            ast_base.span,
+                            format!("base expression must be a `{aggr}`"),
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            ast_base.span,
+                            format!(
+                                "this has type `{ty}`, but I expected a `{aggr}`",
+                                ty = env.describe_ty(base_ty),
+                            ),
+                        ),
+                    );
+                    check_ignored_constructor_args(env, ast_fields, None).await;
+                    return ExprResult::err(db, reported);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Match each field initializer to a field of the class.
+    let fields = aggr.fields(db).collect::<Vec<_>>();
+    let mut field_exprs: Vec<Option<SymExpr<'db>>> = vec![None; fields.len()];
+    let mut field_spans: Vec<Option<Span<'db>>> = vec![None; fields.len()];
+    for ast_field in &ast_fields.values {
+        let value = ast_field
+            .value
+            .check_in_env(env, LivePlaces::fixme())
+            .await
+            .into_expr(env, &mut temporaries);
+        let name = ast_field.name;
+
+        let Some(index) = fields.iter().position(|f| f.name(db) == name.id) else {
+            env.report(
+                Diagnostic::error(
+                    db,
+                    name.span,
+                    format!("`{aggr}` has no field named `{}`", name.id),
+                )
+                .label(
+                    db,
+                    Level::Error,
+                    name.span,
+                    format!("I could not find a field named `{}`", name.id),
+                )
+                .label(
+                    db,
+                    Level::Info,
+                    aggr.name_span(db),
+                    format!("`{aggr}` defined here"),
+                ),
+            );
+            continue;
+        };
+
+        if let Some(previous_span) = field_spans[index] {
+            env.report(
+                Diagnostic::error(
+                    db,
+                    name.span,
+                    format!("field `{}` was given more than once", name.id),
+                )
+                .label(
+                    db,
+                    Level::Error,
+                    name.span,
+                    format!("this is another value for `{}`", name.id),
+                )
+                .label(
+                    db,
+                    Level::Info,
+                    previous_span,
+                    format!("`{}` was already given a value here", name.id),
+                ),
+            );
+            continue;
+        }
+
+        let expected_ty = field_ty(fields[index]);
+        let value = coerce_numeric(env, value, expected_ty);
+        env.spawn_require_assignable_type(
+            LivePlaces::fixme(),
+            value.ty(db),
+            expected_ty,
+            &BadSubtermError::new(value.span(db), value.ty(db), expected_ty),
+        );
+        field_exprs[index] = Some(value);
+        field_spans[index] = Some(name.span);
+    }
+
+    // Fill in the remaining fields from the base, or report them as missing.
+    let mut missing = vec![];
+    for (index, &field) in fields.iter().enumerate() {
+        if field_exprs[index].is_some() {
+            continue;
+        }
+
+        let Some((base_place, ref base_generics, base_perm)) = base else {
+            missing.push(field);
+            continue;
+        };
+
+        // As with a field access `base.field`, the type of the field is the declared
+        // type with `self` replaced by the base and the base's permission applied.
+        let base_field_ty = field_ty_with_perm(
+            db,
+            field,
+            base_generics,
+            base_place.into_sym_place(db),
+            base_perm,
+        );
+        let base_field = SymPlaceExpr::new(
+            db,
+            base_place.span(db),
+            base_field_ty,
+            SymPlaceExprKind::Field(base_place, field),
+        )
+        .give(db);
+
+        let expected_ty = field_ty(field);
+        env.spawn_require_assignable_type(
+            LivePlaces::fixme(),
+            base_field_ty,
+            expected_ty,
+            &BadSubtermError::new(base_place.span(db), base_field_ty, expected_ty),
+        );
+        field_exprs[index] = Some(base_field);
+    }
+
+    if !missing.is_empty() {
+        let names = missing
+            .iter()
+            .map(|field| format!("`{}`", field.name(db)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut diagnostic = Diagnostic::error(
+            db,
+            expr_span,
+            format!("missing fields in constructor for `{aggr}`: {names}"),
+        )
+        .label(
+            db,
+            Level::Error,
+            expr_span,
+            format!("no value was given for {names}"),
+        );
+        for field in missing {
+            diagnostic = diagnostic.label(
+                db,
+                Level::Info,
+                field.name_span(db),
+                format!("`{}` declared here", field.name(db)),
+            );
+        }
+        return ExprResult::err(db, env.report(diagnostic));
+    }
+
+    let fields = field_exprs.into_iter().map(Option::unwrap).collect();
+
+    ExprResult {
+        temporaries,
+        span: expr_span,
+        kind: SymExpr::new(
+            db,
+            expr_span,
+            aggr_ty,
+            SymExprKind::Aggregate {
+                ty: aggr_ty,
+                fields,
+            },
+        )
+        .into(),
+    }
+}
+
+/// Check the fields and base of a constructor expression whose class could not be
+/// determined, so that errors within them are still reported.
+async fn check_ignored_constructor_args<'db>(
+    env: &mut Env<'db>,
+    ast_fields: &SpanVec<'db, AstConstructorField<'db>>,
+    ast_base: Option<&AstExpr<'db>>,
+) {
+    for ast_expr in ast_base
+        .into_iter()
+        .chain(ast_fields.values.iter().map(|f| &f.value))
+    {
+        let _ = ast_expr.check_in_env(env, LivePlaces::fixme()).await;
+    }
+}
+
+fn report_not_constructible<'db>(
+    db: &'db dyn crate::Db,
+    path_span: Span<'db>,
+    name_resolution: &NameResolution<'db>,
+) -> Reported {
+    Diagnostic::error(db, path_span, "expected a class or struct")
+        .label(
+            db,
+            Level::Error,
+            path_span,
+            format!(
+                "I expected a class or struct here, but this is {}",
+                name_resolution.categorize(db)
+            ),
+        )
+        .report(db)
+}
+
 #[boxed_async_fn]
 async fn check_function_call<'db>(
     env: &mut Env<'db>,
@@ -941,7 +2117,7 @@ async fn check_function_call<'db>(
     function_span: Span<'db>,
     expr_span: Span<'db>,
     function: SymFunction<'db>,
-    ast_args: &SpanVec<'db, AstExpr<'db>>,
+    ast_args: &SpanVec<'db, AstCallArg<'db>>,
     generics: Vec<SymGenericTerm<'db>>,
     temporaries: Vec<Temporary<'db>>,
 ) -> ExprResult<'db> {
@@ -951,11 +2127,11 @@ async fn check_function_call<'db>(
     env.log("generics", &[&generics]);
 
     // Get the signature.
-    let signature = match function.checked_signature(db) {
+    let signature = match signature_for_call(env, function) {
         Ok(signature) => signature,
         Err(reported) => {
             for ast_arg in ast_args {
-                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
+                let _ = ast_arg.value.check_in_env(env, LivePlaces::fixme()).await;
             }
             return ExprResult::err(db, reported);
         }
@@ -1001,21 +2177,29 @@ async fn check_method_call<'db>(
     expr_span: Span<'db>,
     function: SymFunction<'db>,
     self_expr: Option<SymExpr<'db>>,
-    ast_args: &[AstExpr<'db>],
+    ast_args: &[AstCallArg<'db>],
     generics: Option<SpanVec<'db, AstGenericTerm<'db>>>,
     temporaries: Vec<Temporary<'db>>,
 ) -> ExprResult<'db> {
     let db = env.db();
 
-    // Get the signature.
-    let signature = match function.checked_signature(db) {
+    // Get the signature. Associated functions have no `self`,
+    // so they cannot be invoked on an instance.
+    let signature = if self_expr.is_some() && !function.has_self(db) {
+        Err(report_associated_fn_called_as_method(
+            env, id_span, function,
+        ))
+    } else {
+        signature_for_call(env, function)
+    };
+    let signature = match signature {
         Ok(signature) => signature,
         Err(reported) => {
             for &generic in generics.iter().flatten() {
                 let _ = generic.check_in_env(env).await;
             }
             for ast_arg in ast_args {
-                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
+                let _ = ast_arg.value.check_in_env(env, LivePlaces::fixme()).await;
             }
             return ExprResult::err(db, reported);
         }
@@ -1084,7 +2268,10 @@ async fn check_method_call<'db>(
             // Convert each generic to a `SymGenericTerm` and check it has the correct kind.
             // If everything looks good, add it to the substitution.
             for (&ast_generic_term, &var) in generics.iter().zip(function_generics.iter()) {
-                let generic_term = ast_generic_term.check_in_env(env).await;
+                let generic_term = match ast_generic_term {
+                    AstGenericTerm::Infer(span) => env.fresh_inference_var_term(var.kind(db), span),
+                    _ => ast_generic_term.check_in_env(env).await,
+                };
                 if !generic_term.has_kind(db, var.kind(db)) {
                     return ExprResult::err(
                         db,
@@ -1151,7 +2338,7 @@ async fn check_call_common<'db>(
     callee_span: Span<'db>,
     input_output: &Binder<'db, Binder<'db, SymInputOutput<'db>>>,
     substitution: Vec<SymGenericTerm<'db>>,
-    ast_args: &[AstExpr<'db>],
+    ast_args: &[AstCallArg<'db>],
     self_expr: Option<SymExpr<'db>>,
     mut temporaries: Vec<Temporary<'db>>,
 ) -> ExprResult<'db> {
@@ -1160,48 +2347,60 @@ async fn check_call_common<'db>(
     env.log("check_call_common", &[]);
     env.log("substitution", &[&substitution]);
 
+    // The effects of host functions cannot be rolled back, so they cannot be part of a transaction.
+    if let Some(atomic_span) = env.atomic
+        && let SymFunctionSource::Extern(_) = function.source(db)
+    {
+        env.report(
+            Diagnostic::error(
+                db,
+                callee_span,
+                format!(
+                    "call to extern function `{}` inside an `atomic` block",
+                    function.name(db)
+                ),
+            )
+            .label(
+                db,
+                Level::Error,
+                callee_span,
+                "the effects of this call could not be undone if the block were rolled back",
+            )
+            .label(db, Level::Info, atomic_span, "the atomic block is here"),
+        );
+    }
+
     // Instantiate the input-output with the substitution.
     let input_output = input_output.substitute(db, &substitution);
 
-    // Check the arity of the actual arguments.
+    // `print(a, b, c)` is shorthand for `print((a, b, c))`.
+    let packed_args;
+    let ast_args = match pack_print_args(db, function, expr_span, ast_args) {
+        Some(args) => {
+            packed_args = args;
+            &packed_args[..]
+        }
+        None => ast_args,
+    };
+
+    // Match the actual arguments to the declared parameters.
     let self_args: usize = self_expr.is_some() as usize;
     let expected_inputs = input_output.bound_value.input_tys.len();
-    let found_inputs = self_args + ast_args.len();
-    if found_inputs != expected_inputs {
-        let function_name = function.name(db);
-        return ExprResult::err(
-            db,
-            env.report(
-                Diagnostic::error(
-                    db,
-                    callee_span,
-                    format!("expected {expected_inputs} arguments, found {found_inputs}"),
-                )
-                .label(
-                    db,
-                    Level::Error,
-                    callee_span,
-                    format!("I expected `{function_name}` to take {expected_inputs} arguments but I found {found_inputs}",),
-                )
-                .label(
-                    db,
-                    Level::Info,
-                    function.name_span(db),
-                    format!("`{function_name}` defined here"),
-                )
-            ),
-        );
-    }
+    let args = match match_call_args(env, function, callee_span, self_args, ast_args) {
+        Ok(args) => args,
+        Err(reported) => return ExprResult::err(db, reported),
+    };
+    assert_eq!(self_args + args.len(), expected_inputs);
 
     // Create the temporaries that will hold the values for each argument.
     let arg_temp_span = |i: usize| {
         if i < self_args {
             self_expr.unwrap().span(db)
         } else {
-            ast_args
-                .get(i - self_args)
-                .map(|a| a.span)
-                .unwrap_or(callee_span)
+            match args[i - self_args] {
+                CallArg::Ast(_, ast_expr) => ast_expr.span,
+                CallArg::Default(_) => callee_span,
+            }
         }
     };
     let arg_temp_symbols = (0..expected_inputs)
@@ -1213,7 +2412,15 @@ async fn check_call_common<'db>(
         .collect::<Vec<_>>();
 
     // Instantiate the final level of binding with those temporaries
-    let input_output: SymInputOutput<'_> = input_output.substitute(db, &arg_temp_terms);
+    let mut input_output: SymInputOutput<'_> = input_output.substitute(db, &arg_temp_terms);
+
+    // The future combinators declare their parameters with the awaited types,
+    // but the arguments are the futures themselves.
+    if well_known::future_combinator(db, function) {
+        for input_ty in &mut input_output.input_tys {
+            *input_ty = SymTy::named(db, SymTyName::Future, vec![(*input_ty).into()]);
+        }
+    }
 
     env.log("arg_temp_symbols", &[&arg_temp_symbols]);
     env.log("arg_temp_terms", &[&arg_temp_terms]);
@@ -1226,12 +2433,15 @@ async fn check_call_common<'db>(
         let expr = if i < self_args {
             self_expr.unwrap()
         } else {
-            let ast_arg = &ast_args[i - self_args];
-            ast_arg
-                .check_in_env(&mut env, LivePlaces::fixme())
-                .await
-                .into_expr(&mut env, &mut arg_temporaries)
+            match args[i - self_args] {
+                CallArg::Ast(_, ast_expr) => ast_expr
+                    .check_in_env(&mut env, LivePlaces::fixme())
+                    .await
+                    .into_expr(&mut env, &mut arg_temporaries),
+                CallArg::Default(default_expr) => default_expr,
+            }
         };
+        let expr = coerce_numeric(&env, expr, input_output.input_tys[i]);
         env.spawn_require_assignable_type(
             LivePlaces::fixme(),
             expr.ty(db),
@@ -1252,7 +2462,7 @@ async fn check_call_common<'db>(
     // Type check the arguments; these can proceed concurrently.
     let mut arg_exprs = vec![];
     arg_exprs.extend(self_expr);
-    for arg_result in futures::future::join_all((0..found_inputs).map(check_arg)).await {
+    for arg_result in futures::future::join_all((0..expected_inputs).map(check_arg)).await {
         arg_exprs.push(arg_result.into_expr(env, &mut temporaries));
     }
 
@@ -1262,6 +2472,10 @@ async fn check_call_common<'db>(
     //     let tmp2 = arg2 in
     //     ...
     //     call(tmp1, tmp2, ...)
+    //
+    // except that the `let`s are ordered by where the arguments appear in the source,
+    // so that named arguments are evaluated in the order they are written.
+    // Default values come last.
     let mut call_expr = SymExpr::new(
         db,
         expr_span,
@@ -1272,11 +2486,22 @@ async fn check_call_common<'db>(
             arg_temps: arg_temp_symbols.clone(),
         },
     );
-    for (arg_temp_symbol, arg_expr) in arg_temp_symbols
+    let mut evaluation_order = arg_temp_symbols
         .into_iter()
-        .rev()
-        .zip(arg_exprs.into_iter().rev())
-    {
+        .zip(arg_exprs)
+        .enumerate()
+        .collect::<Vec<_>>();
+    evaluation_order.sort_by_key(|&(i, _)| {
+        if i < self_args {
+            0
+        } else {
+            match args[i - self_args] {
+                CallArg::Ast(position, _) => 1 + position,
+                CallArg::Default(_) => usize::MAX,
+            }
+        }
+    });
+    for (_, (arg_temp_symbol, arg_expr)) in evaluation_order.into_iter().rev() {
         call_expr = SymExpr::new(
             db,
             call_expr.span(db),
@@ -1294,6 +2519,221 @@ async fn check_call_common<'db>(
     ExprResult::from_expr(env.db(), call_expr, temporaries)
 }
 
+/// If `function` is the well-known `print` function and it is called with anything other
+/// than a single positional argument, returns a single argument that packs
+/// the arguments into a tuple. Otherwise returns `None`.
+///
+/// 💡 This gives `print` a variadic feel without adding variadics to the type system:
+/// `print` takes one argument of any type, and a tuple is a perfectly good type.
+fn pack_print_args<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+    expr_span: Span<'db>,
+    ast_args: &[AstCallArg<'db>],
+) -> Option<Vec<AstCallArg<'db>>> {
+    if well_known::print_fn(db).ok() != Some(function) {
+        return None;
+    }
+
+    if ast_args.len() == 1 || ast_args.iter().any(|arg| arg.name.is_some()) {
+        return None;
+    }
+
+    let span = match (ast_args.first(), ast_args.last()) {
+        (Some(first), Some(last)) => first.value.span.to(db, last.value.span),
+        _ => expr_span,
+    };
+    let values = ast_args.iter().map(|arg| arg.value.clone()).collect();
+    Some(vec![AstCallArg {
+        name: None,
+        value: AstExpr::new(span, AstExprKind::Tuple(SpanVec { span, values })),
+    }])
+}
+
+/// The source of the value for a parameter at a call site.
+#[derive(Copy, Clone)]
+enum CallArg<'a, 'db> {
+    /// The argument provided by the user at the given position in the call.
+    Ast(usize, &'a AstExpr<'db>),
+
+    /// The parameter's default value.
+    Default(SymExpr<'db>),
+}
+
+/// Match the arguments `ast_args` given at a call site to the parameters of `function`,
+/// returning the source for each parameter (excluding the `self` parameter, if `self_args` is 1).
+///
+/// Positional arguments are matched to parameters in order.
+/// They may be followed by named arguments (`name: value`), which are matched by name.
+/// Any remaining parameters take their default value.
+///
+/// The arguments are evaluated in the order they appear in the call
+/// (see [`check_call_common`]), whatever the order of the parameters they are matched to.
+fn match_call_args<'a, 'db>(
+    env: &mut Env<'db>,
+    function: SymFunction<'db>,
+    callee_span: Span<'db>,
+    self_args: usize,
+    ast_args: &'a [AstCallArg<'db>],
+) -> Errors<Vec<CallArg<'a, 'db>>> {
+    let db = env.db();
+    let function_name = function.name(db);
+    let parameters = &function.symbols(db).input_variables[self_args..];
+    let mut slots: Vec<Option<CallArg<'a, 'db>>> = vec![None; parameters.len()];
+
+    let mut num_positional = 0;
+    let mut first_named_span = None;
+    for (position, ast_arg) in ast_args.iter().enumerate() {
+        match ast_arg.name {
+            None => {
+                if let Some(named_span) = first_named_span {
+                    return Err(env.report(
+                        Diagnostic::error(
+                            db,
+                            ast_arg.value.span,
+                            "positional argument after named argument",
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            ast_arg.value.span,
+                            "this positional argument follows a named argument",
+                        )
+                        .label(
+                            db,
+                            Level::Info,
+                            named_span,
+                            "positional arguments must come before this named argument",
+                        ),
+                    ));
+                }
+                if let Some(slot) = slots.get_mut(num_positional) {
+                    *slot = Some(CallArg::Ast(position, &ast_arg.value));
+                }
+                num_positional += 1;
+            }
+
+            Some(name) => {
+                first_named_span.get_or_insert(ast_arg.span(db));
+                let Some(index) = parameters.iter().position(|p| p.name(db) == Some(name.id))
+                else {
+                    return Err(env.report(
+                        Diagnostic::error(
+                            db,
+                            name.span,
+                            format!("`{function_name}` has no parameter named `{}`", name.id),
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            name.span,
+                            format!("I could not find a parameter named `{}`", name.id),
+                        )
+                        .label(
+                            db,
+                            Level::Info,
+                            function.name_span(db),
+                            format!("`{function_name}` defined here"),
+                        ),
+                    ));
+                };
+
+                if let Some(previous) = slots[index] {
+                    let previous_span = match previous {
+                        CallArg::Ast(_, ast_expr) => ast_expr.span,
+                        CallArg::Default(_) => unreachable!(),
+                    };
+                    return Err(env.report(
+                        Diagnostic::error(
+                            db,
+                            name.span,
+                            format!("parameter `{}` was given more than one argument", name.id),
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            name.span,
+                            format!("this is another argument for `{}`", name.id),
+                        )
+                        .label(
+                            db,
+                            Level::Info,
+                            previous_span,
+                            format!("`{}` was already given this argument", name.id),
+                        ),
+                    ));
+                }
+
+                slots[index] = Some(CallArg::Ast(position, &ast_arg.value));
+            }
+        }
+    }
+
+    // Check the arity of the actual arguments.
+    // Too many positional arguments is always an error;
+    // too few is fine if the missing parameters have defaults.
+    let defaults = function.checked_input_defaults(db);
+    let missing = slots
+        .iter()
+        .enumerate()
+        .filter(|&(index, slot)| slot.is_none() && defaults[self_args + index].is_none())
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if num_positional > parameters.len() || (first_named_span.is_none() && !missing.is_empty()) {
+        let expected_inputs = self_args + parameters.len();
+        let found_inputs = self_args + ast_args.len();
+        return Err(env.report(
+            Diagnostic::error(
+                db,
+                callee_span,
+                format!("expected {expected_inputs} arguments, found {found_inputs}"),
+            )
+            .label(
+                db,
+                Level::Error,
+                callee_span,
+                format!("I expected `{function_name}` to take {expected_inputs} arguments but I found {found_inputs}",),
+            )
+            .label(
+                db,
+                Level::Info,
+                function.name_span(db),
+                format!("`{function_name}` defined here"),
+            )
+        ));
+    }
+    if let Some(&index) = missing.first() {
+        let parameter = parameters[index];
+        return Err(env.report(
+            Diagnostic::error(
+                db,
+                callee_span,
+                format!("missing argument for parameter `{parameter}`"),
+            )
+            .label(
+                db,
+                Level::Error,
+                callee_span,
+                format!("no argument was given for `{parameter}`, which has no default value"),
+            )
+            .label(
+                db,
+                Level::Info,
+                parameter.span(db),
+                format!("`{parameter}` declared here"),
+            ),
+        ));
+    }
+
+    Ok(slots
+        .into_iter()
+        .enumerate()
+        .map(|(index, slot)| {
+            slot.unwrap_or_else(|| CallArg::Default(defaults[self_args + index].unwrap()))
+        })
+        .collect())
+}
+
 impl<'db> Err<'db> for ExprResult<'db> {
     fn err(db: &'db dyn dada_ir_ast::Db, r: Reported) -> Self {
         Self {
@@ -1514,6 +2954,37 @@ fn report_missing_call_to_method<'db>(
         .report(db)
 }
 
+fn report_associated_fn_called_as_method<'db>(
+    env: &Env<'db>,
+    id_span: Span<'db>,
+    function: SymFunction<'db>,
+) -> Reported {
+    let db = env.db();
+    let name = function.name(db);
+    let mut diag = Diagnostic::error(
+        db,
+        id_span,
+        format!("`{name}` is an associated function, not a method"),
+    )
+    .label(
+        db,
+        Level::Error,
+        id_span,
+        format!("`{name}` does not take `self`, so it cannot be called on a value"),
+    );
+
+    if let ScopeItem::Class(class_sym) = function.super_scope_item(db) {
+        diag = diag.label(
+            db,
+            Level::Help,
+            id_span,
+            format!("call it through the class instead, as in `{class_sym}.{name}(...)`"),
+        );
+    }
+
+    env.report(diag)
+}
+
 fn report_not_callable<'db>(db: &'db dyn crate::Db, owner_span: Span<'db>) -> Reported {
     Diagnostic::error(db, owner_span, "not callable".to_string())
         .label(
@@ -0,0 +1,112 @@
+use dada_ir_ast::diagnostic::{Diagnostic, Errors, Level};
+
+use crate::{
+    ir::{
+        functions::{SymFunction, SymFunctionSource},
+        types::{SymGenericTerm, SymPermKind, SymPlace, SymTy, SymTyKind, SymTyName},
+    },
+    prelude::CheckedSignature,
+};
+
+/// Name of the attribute marking a function that the host can call
+/// when its module is compiled (see [`SymFunction::is_host_export`]).
+pub(crate) const HOST_EXPORT_ATTRIBUTE: &str = "host_export";
+
+/// Check that `function` can cross the boundary between Dada and the host,
+/// either because it is implemented by the host (`extern fn`)
+/// or because the host calls it (`#[host_export]`).
+///
+/// Such functions use a stable calling convention with one wasm value per
+/// parameter and at most one result, so they must not be generic or `async`
+/// and can only take and return primitive values or `()`.
+/// Extern functions additionally cannot have a body.
+pub(crate) fn check_ffi_signature<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> Errors<()> {
+    let what = match function.source(db) {
+        SymFunctionSource::Extern(_) => "extern function",
+        _ => "exported function",
+    };
+    let name = function.name(db);
+    let error = |message: String| Diagnostic::error(db, function.name_span(db), message);
+
+    if let SymFunctionSource::Extern(ast_extern) = function.source(db)
+        && let Some(body) = ast_extern.function(db).body(db)
+    {
+        return Err(error(format!("{what} `{name}` cannot have a body"))
+            .label(
+                db,
+                Level::Error,
+                body.span,
+                "the body of an extern function is provided by the host",
+            )
+            .report(db));
+    }
+
+    if !function.symbols(db).has_generics_of_kind(db, &[]) {
+        return Err(error(format!("{what} `{name}` cannot have generics")).report(db));
+    }
+
+    if function.effects(db).async_effect {
+        return Err(error(format!("{what} `{name}` cannot be `async`")).report(db));
+    }
+
+    let signature = function.checked_signature(db)?;
+    let symbols = signature.symbols(db);
+    let dummy_places = symbols
+        .input_variables
+        .iter()
+        .map(|_| SymGenericTerm::Place(SymPlace::erased(db)))
+        .collect::<Vec<_>>();
+    let input_output = signature
+        .input_output(db)
+        .substitute(db, &[] as &[SymGenericTerm<'db>])
+        .substitute(db, &dummy_places);
+
+    let mut labels = vec![];
+    for (&input, &input_ty) in symbols.input_variables.iter().zip(&input_output.input_tys) {
+        if !is_ffi_safe(db, input_ty) {
+            labels.push((
+                input.span(db),
+                format!("parameter `{input}` has type `{input_ty}`"),
+            ));
+        }
+    }
+
+    let output_ty = input_output.output_ty;
+    if !is_ffi_safe(db, output_ty) {
+        labels.push((
+            function.name_span(db),
+            format!("returns a value of type `{output_ty}`"),
+        ));
+    }
+
+    if labels.is_empty() {
+        return Ok(());
+    }
+
+    let diagnostic = labels.into_iter().fold(
+        error(format!(
+            "{what} `{name}` can only take and return primitive values"
+        )),
+        |diagnostic, (span, message)| diagnostic.label(db, Level::Error, span, message),
+    );
+    Err(diagnostic.report(db))
+}
+
+/// True if values of type `ty` can be passed to or returned from the host:
+/// primitives and `()`, given or shared.
+fn is_ffi_safe<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> bool {
+    match *ty.kind(db) {
+        SymTyKind::Named(SymTyName::Primitive(_), _) => true,
+        SymTyKind::Named(SymTyName::Tuple { arity: 0 }, _) => true,
+        SymTyKind::Named(..) => false,
+        SymTyKind::Perm(perm, ty) => match perm.kind(db) {
+            SymPermKind::My | SymPermKind::Our => is_ffi_safe(db, ty),
+            _ => false,
+        },
+        SymTyKind::Never | SymTyKind::Error(_) => true,
+        SymTyKind::Var(_) | SymTyKind::Infer(_) => false,
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::{
     check::{CheckTyInEnv, signature::PreparedEnv},
     ir::{
@@ -5,32 +7,136 @@ use crate::{
         functions::{SymFunction, SymFunctionSource},
     },
 };
+
 use dada_ir_ast::{
-    ast::{AstAggregate, AstBlock, AstMainFunction},
-    diagnostic::{Diagnostic, Err, Level},
+    ast::{AstAggregate, AstBlock, AstExpr, AstFunctionInput, AstMainFunction},
+    diagnostic::{Diagnostic, Err, Errors, Level, Reported},
 };
 use dada_parser::prelude::FunctionBlock;
+use salsa::Update;
+use serde::Serialize;
 
 use crate::{
-    check::runtime::Runtime,
+    check::runtime::{CheckStats, Runtime},
     check::signature::prepare_env,
     ir::exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
 };
 
 use super::{
-    CheckExprInEnv, env::Env, live_places::LivePlaces, report::InvalidReturnValue,
-    resolve::Resolver, statements::check_block_statements,
+    CheckExprInEnv,
+    coercions::coerce_numeric,
+    definite_assignment::check_definite_assignment,
+    env::Env,
+    inference::InferredValue,
+    lease_invalidation::check_lease_invalidation,
+    live_places::LivePlaces,
+    permission_state::check_permission_state,
+    report::{BadSubtermError, InvalidReturnValue},
+    resolve::Resolver,
+    statements::check_block_statements,
+    types::mentions_generic_variable,
 };
 
-pub(crate) fn check_function_body<'db>(
+/// The result of checking the body of a function.
+/// See [`CheckedBody`](`crate::prelude::CheckedBody`).
+#[derive(Clone, PartialEq, Eq, Debug, Update, Serialize)]
+pub(crate) struct CheckedFunctionBody<'db> {
+    /// The checked body, if the function has one.
+    pub body: Option<SymExpr<'db>>,
+
+    /// The final value of each inference variable created while checking the body,
+    /// indexed by [`InferVarIndex`](`crate::ir::indices::InferVarIndex`).
+    pub inferred_values: Vec<InferredValue<'db>>,
+
+    /// Functions with inferred return types whose return types the body depends on,
+    /// directly or through theirs, see [`InferredOutputTy`](`crate::check::signature::InferredOutputTy`).
+    pub output_ty_dependencies: Vec<SymFunction<'db>>,
+
+    /// Counters from checking the body, for the `stats` dump stage.
+    pub stats: CheckStats,
+}
+
+impl<'db> CheckedFunctionBody<'db> {
+    fn new(
+        body: SymExpr<'db>,
+        inferred_values: Vec<InferredValue<'db>>,
+        output_ty_dependencies: Vec<SymFunction<'db>>,
+        stats: CheckStats,
+    ) -> Self {
+        Self {
+            body: Some(body),
+            inferred_values,
+            output_ty_dependencies,
+            stats,
+        }
+    }
+}
+
+impl<'db> Err<'db> for CheckedFunctionBody<'db> {
+    fn err(db: &'db dyn dada_ir_ast::Db, reported: Reported) -> Self {
+        Self {
+            body: Some(SymExpr::err(db, reported)),
+            inferred_values: vec![],
+            output_ty_dependencies: vec![],
+            stats: CheckStats::default(),
+        }
+    }
+}
+
+/// 💡 Checking the body of a function whose return type is inferred may need that
+/// return type, and so the body itself (see [`InferredOutputTy`](`crate::check::signature::InferredOutputTy`)).
+/// Salsa resolves the cycle by starting from a body that depends on its own return type.
+#[salsa::tracked(return_ref, cycle_fn = checked_function_body_cycle, cycle_initial = checked_function_body_initial)]
+pub(crate) fn checked_function_body<'db>(
     db: &'db dyn crate::Db,
     function: SymFunction<'db>,
-) -> Option<SymExpr<'db>> {
+) -> CheckedFunctionBody<'db> {
+    let checked = check_function_body(db, function).unwrap_or(CheckedFunctionBody {
+        body: None,
+        inferred_values: vec![],
+        output_ty_dependencies: vec![],
+        stats: CheckStats::default(),
+    });
+    if let Some(body) = checked.body {
+        check_definite_assignment(db, body);
+        check_lease_invalidation(db, body);
+        check_permission_state(db, body, &checked.inferred_values);
+    }
+    checked
+}
+
+fn checked_function_body_initial<'db>(
+    _db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> CheckedFunctionBody<'db> {
+    CheckedFunctionBody {
+        body: None,
+        inferred_values: vec![],
+        output_ty_dependencies: vec![function],
+        stats: CheckStats::default(),
+    }
+}
+
+fn checked_function_body_cycle<'db>(
+    _db: &'db dyn crate::Db,
+    _value: &CheckedFunctionBody<'db>,
+    _count: u32,
+    _function: SymFunction<'db>,
+) -> salsa::CycleRecoveryAction<CheckedFunctionBody<'db>> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+fn check_function_body<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> Option<CheckedFunctionBody<'db>> {
     match function.source(db) {
         SymFunctionSource::Function(ast_function) => {
             let block = ast_function.body_block(db)?;
             Some(check_function_body_ast_block(db, function, block))
         }
+        // The body of an extern function is provided by the host.
+        SymFunctionSource::Extern(_) => None,
         SymFunctionSource::MainFunction(mfunc) => Some(check_main_function(db, function, mfunc)),
         SymFunctionSource::Constructor(sym_class, ast_class_item) => Some(
             check_function_body_class_constructor(db, function, sym_class, ast_class_item),
@@ -38,23 +144,142 @@ pub(crate) fn check_function_body<'db>(
     }
 }
 
+/// Check the default values of the function's parameters (e.g., `x: u32 = 22`).
+/// Returns one entry per parameter, with `None` for parameters that have no default
+/// and an error expression for defaults that were rejected, so that calls omitting
+/// the argument do not also report it as missing.
+///
+/// Default values are inserted at each call site that omits the argument.
+/// They are checked in a scope that contains the function's generics but not its
+/// parameters, so they cannot refer to the values of other arguments.
+///
+/// Parameters whose type mentions a generic parameter cannot have defaults:
+/// the default's type would be the declared type, not the one instantiated at the call site.
+pub(crate) fn check_input_defaults<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> Vec<Option<SymExpr<'db>>> {
+    let source = function.source(db);
+    let inputs = source.inputs(db);
+    if !inputs
+        .iter()
+        .any(|input| input_default(db, input).is_some())
+    {
+        return vec![None; inputs.len()];
+    }
+
+    Runtime::execute(
+        db,
+        function.name_span(db),
+        "check_input_defaults",
+        &[&function],
+        async move |runtime| {
+            let PreparedEnv {
+                mut env, input_tys, ..
+            } = prepare_env(db, runtime, function).await;
+
+            // Remove the parameters from scope now that their types are known.
+            env.scope = Arc::new(function.generics_scope(db));
+
+            let mut defaults = vec![];
+            for (input, &input_ty) in source.inputs(db).iter().zip(&input_tys) {
+                let Some(ast_default) = input_default(db, input) else {
+                    defaults.push(None);
+                    continue;
+                };
+
+                // The default would have the callee's generic type at every call site,
+                // so we only allow defaults whose type does not depend on the generics.
+                if mentions_generic_variable(db, input_ty.into()) {
+                    let span = ast_default.span;
+                    let reported = env.report(
+                        Diagnostic::error(
+                            db,
+                            span,
+                            "parameters with a generic type cannot have default values",
+                        )
+                        .label(
+                            db,
+                            Level::Error,
+                            span,
+                            format!("the type of this parameter is `{input_ty}`"),
+                        ),
+                    );
+                    defaults.push(Some(SymExpr::err(db, reported)));
+                    continue;
+                }
+
+                let live_after = LivePlaces::none(&env);
+                let expr = ast_default
+                    .check_in_env(&mut env, live_after)
+                    .await
+                    .into_expr_with_enclosed_temporaries(&mut env);
+                let expr = coerce_numeric(&env, expr, input_ty);
+                env.spawn_require_assignable_type(
+                    live_after,
+                    expr.ty(db),
+                    input_ty,
+                    &BadSubtermError::new(expr.span(db), expr.ty(db), input_ty),
+                );
+                defaults.push(Some(expr));
+            }
+            (env, defaults)
+        },
+        |(mut env, defaults)| -> Errors<Vec<_>> {
+            let mut resolver = Resolver::new(&mut env);
+            Ok(defaults
+                .into_iter()
+                .map(|default| default.map(|expr| resolver.resolve(expr)))
+                .collect())
+        },
+    )
+    .unwrap_or_else(|reported| {
+        inputs
+            .iter()
+            .map(|input| input_default(db, input).map(|_| SymExpr::err(db, reported)))
+            .collect()
+    })
+}
+
+/// The AST for the default value of a parameter, if any.
+fn input_default<'db>(
+    db: &'db dyn crate::Db,
+    input: &AstFunctionInput<'db>,
+) -> Option<AstExpr<'db>> {
+    match input {
+        AstFunctionInput::SelfArg(_) => None,
+        AstFunctionInput::Variable(decl) => decl.default(db),
+    }
+}
+
 fn check_main_function<'db>(
     db: &'db dyn crate::Db,
     function: SymFunction<'db>,
     mfunc: AstMainFunction<'db>,
-) -> SymExpr<'db> {
+) -> CheckedFunctionBody<'db> {
     Runtime::execute(
         db,
         mfunc.statements(db).span,
         "check_main_function",
         &[&mfunc],
-        async move |runtime| -> SymExpr<'db> {
+        async move |runtime| {
             let mut env: Env<'db> = Env::new(runtime, function.scope(db));
             let statements = mfunc.statements(db);
             let live_after = LivePlaces::none(&env);
-            check_block_statements(&mut env, live_after, statements.span, statements).await
+            let expr =
+                check_block_statements(&mut env, live_after, statements.span, statements).await;
+            (env, expr)
+        },
+        |(mut env, expr)| {
+            let output_ty_dependencies = env.runtime().output_ty_dependencies();
+            let stats = env.runtime().check_stats();
+            CheckedFunctionBody::new(
+                expr,
+                Resolver::new(&mut env).resolve_all(),
+                output_ty_dependencies,
+                stats,
+            )
         },
-        |expr| expr,
     )
 }
 
@@ -64,22 +289,22 @@ fn check_function_body_class_constructor<'db>(
     function: SymFunction<'db>,
     sym_class: SymAggregate<'db>,
     ast_class_item: AstAggregate<'db>,
-) -> SymExpr<'db> {
+) -> CheckedFunctionBody<'db> {
     Runtime::execute(
         db,
         function.name_span(db),
         "check_function_body_class_constructor",
         &[&function, &sym_class, &ast_class_item],
-        async move |runtime| -> SymExpr<'db> {
+        async move |runtime| {
             let PreparedEnv {
-                ref mut env,
+                mut env,
                 input_symbols,
                 input_tys,
                 ..
             } = prepare_env(db, runtime, function).await;
 
             let scope = env.scope.clone();
-            let self_ty = sym_class.self_ty(db, &scope).check_in_env(env).await;
+            let self_ty = sym_class.self_ty(db, &scope).check_in_env(&mut env).await;
             let span = ast_class_item.inputs(db).as_ref().unwrap().span;
             let fields = sym_class.fields(db).collect::<Vec<_>>();
             assert_eq!(input_symbols.len(), input_tys.len());
@@ -116,7 +341,7 @@ fn check_function_body_class_constructor<'db>(
                 )
             });
 
-            SymExpr::new(
+            let expr = SymExpr::new(
                 db,
                 span,
                 self_ty,
@@ -124,9 +349,19 @@ fn check_function_body_class_constructor<'db>(
                     ty: self_ty,
                     fields: parameter_exprs.chain(other_exprs).collect(),
                 },
+            );
+            (env, expr)
+        },
+        |(mut env, expr)| {
+            let output_ty_dependencies = env.runtime().output_ty_dependencies();
+            let stats = env.runtime().check_stats();
+            CheckedFunctionBody::new(
+                expr,
+                Resolver::new(&mut env).resolve_all(),
+                output_ty_dependencies,
+                stats,
             )
         },
-        |expr| expr,
     )
 }
 
@@ -134,7 +369,7 @@ fn check_function_body_ast_block<'db>(
     db: &'db dyn crate::Db,
     function: SymFunction<'db>,
     body: AstBlock<'db>,
-) -> SymExpr<'db> {
+) -> CheckedFunctionBody<'db> {
     Runtime::execute(
         db,
         function.name_span(db),
@@ -150,6 +385,7 @@ fn check_function_body_ast_block<'db>(
             env.log("check_function_body_ast_block", &[&function, &body]);
             let live_after = LivePlaces::none(&env);
             let expr = body.check_in_env(&mut env, live_after).await;
+            let expr = coerce_numeric(&env, expr, output_ty_body);
             env.spawn_require_assignable_type(
                 live_after,
                 expr.ty(db),
@@ -158,6 +394,12 @@ fn check_function_body_ast_block<'db>(
             );
             (env, expr)
         },
-        |(mut env, expr)| Resolver::new(&mut env).resolve(expr),
+        |(mut env, expr)| {
+            let output_ty_dependencies = env.runtime().output_ty_dependencies();
+            let stats = env.runtime().check_stats();
+            let mut resolver = Resolver::new(&mut env);
+            let expr = resolver.resolve(expr);
+            CheckedFunctionBody::new(expr, resolver.resolve_all(), output_ty_dependencies, stats)
+        },
     )
 }
@@ -1,8 +1,42 @@
-use dada_ir_ast::ast::{AstWhereClause, AstWhereClauseKind};
+use dada_ir_ast::{
+    ast::{AstWhereClause, AstWhereClauseKind},
+    diagnostic::Errors,
+};
 
-use crate::ir::generics::{SymWhereClause, SymWhereClauseKind};
+use crate::ir::{
+    classes::SymAggregate,
+    generics::{SymWhereClause, SymWhereClauseKind},
+};
 
-use super::{CheckTyInEnv, env::Env};
+use super::{CheckTyInEnv, env::Env, runtime::Runtime, scope_tree::ScopeTreeNode};
+
+/// Symbolify the where-clauses declared on a class.
+/// The result references the class's generic variables,
+/// which are substituted with the generic arguments wherever the class is named.
+pub(crate) fn check_class_where_clauses<'db>(
+    db: &'db dyn crate::Db,
+    aggregate: SymAggregate<'db>,
+) -> Errors<Vec<SymWhereClause<'db>>> {
+    Runtime::execute(
+        db,
+        aggregate.name_span(db),
+        "check_class_where_clauses",
+        &[&aggregate],
+        async move |runtime| -> Errors<Vec<SymWhereClause<'db>>> {
+            let mut env = Env::new(runtime, aggregate.class_scope(db));
+
+            let mut ast_where_clauses = vec![];
+            aggregate.push_direct_ast_where_clauses(db, &mut ast_where_clauses);
+
+            let mut where_clauses = vec![];
+            for ast_where_clause in ast_where_clauses {
+                symbolify_ast_where_clause(&mut env, ast_where_clause, &mut where_clauses).await;
+            }
+            Ok(where_clauses)
+        },
+        |where_clauses| where_clauses,
+    )
+}
 
 pub async fn symbolify_ast_where_clause<'db>(
     env: &mut Env<'db>,
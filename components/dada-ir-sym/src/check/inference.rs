@@ -5,7 +5,10 @@ use dada_ir_ast::span::Span;
 use salsa::Update;
 use serde::Serialize;
 
-use crate::ir::{indices::InferVarIndex, types::SymGenericKind};
+use crate::ir::{
+    indices::InferVarIndex,
+    types::{SymGenericKind, SymGenericTerm},
+};
 
 use super::{
     red::{RedPerm, RedTy},
@@ -211,6 +214,20 @@ impl From<InferVarKind> for SymGenericKind {
     }
 }
 
+/// The final value of an inference variable created while checking a function body.
+/// See [`CheckedBody::inferred_values`](`crate::prelude::CheckedBody::inferred_values`).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Update, Debug, Serialize)]
+pub struct InferredValue<'db> {
+    /// The code which triggered the inference variable to be created.
+    pub span: Span<'db>,
+
+    pub kind: InferVarKind,
+
+    /// The type or permission chosen from the variable's bounds,
+    /// which contains no inference variables.
+    pub value: SymGenericTerm<'db>,
+}
+
 pub enum InferenceVarBounds<'db> {
     /// Bounds for a permission:
     ///
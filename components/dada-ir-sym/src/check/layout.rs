@@ -0,0 +1,166 @@
+use dada_ir_ast::diagnostic::{Diagnostic, Errors, Level, Reported};
+
+use crate::{
+    ir::{
+        classes::{SymAggregate, SymField},
+        types::{SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymTy, SymTyKind, SymTyName},
+        variables::FromVar,
+    },
+    prelude::CheckedFieldTy,
+};
+
+/// Check that `aggr` does not contain itself by value, either directly
+/// (`class A(x: my A)`) or by way of the fields of other aggregates, tuples, or futures.
+/// The layout of such an aggregate would be infinitely large.
+///
+/// A field with a `mut` permission is represented as a pointer, so it breaks the cycle.
+///
+/// Only cycles that lead back to `aggr` are reported; if `aggr` merely contains
+/// some other aggregate that contains itself, the error is reported when checking that aggregate.
+pub(crate) fn check_layout<'db>(db: &'db dyn crate::Db, aggr: SymAggregate<'db>) -> Errors<()> {
+    let generics = aggr
+        .symbols(db)
+        .generic_variables
+        .iter()
+        .map(|&v| SymGenericTerm::var(db, v))
+        .collect::<Vec<_>>();
+
+    let mut search = LayoutCycleSearch {
+        db,
+        target: aggr,
+        visiting: vec![aggr],
+        path: vec![],
+    };
+
+    if search.fields_contain_target(aggr, &generics) {
+        Err(report_layout_cycle(db, aggr, &search.path))
+    } else {
+        Ok(())
+    }
+}
+
+struct LayoutCycleSearch<'db> {
+    db: &'db dyn crate::Db,
+
+    /// The aggregate we are checking.
+    target: SymAggregate<'db>,
+
+    /// Aggregates whose fields we are currently searching.
+    /// We do not search them again, as that would not terminate if they contain themselves.
+    visiting: Vec<SymAggregate<'db>>,
+
+    /// The fields traversed to reach the current point in the search.
+    /// When a cycle is found, this is the path from `target` back to itself.
+    path: Vec<SymField<'db>>,
+}
+
+impl<'db> LayoutCycleSearch<'db> {
+    /// True if any field of `aggr` (instantiated with `generics`) stores a `target` by value.
+    fn fields_contain_target(
+        &mut self,
+        aggr: SymAggregate<'db>,
+        generics: &[SymGenericTerm<'db>],
+    ) -> bool {
+        let db = self.db;
+        if generics.len() != aggr.len_generics(db) {
+            // Ill-formed type, an error has been reported elsewhere.
+            return false;
+        }
+
+        for field in aggr.fields(db) {
+            // As in codegen, the `self` place in the field type is not relevant to its layout.
+            let field_ty = field
+                .checked_field_ty(db)
+                .substitute(db, generics)
+                .substitute(db, &[SymGenericTerm::Place(SymPlace::erased(db))]);
+
+            self.path.push(field);
+            if self.ty_contains_target(field_ty) {
+                return true;
+            }
+            self.path.pop();
+        }
+
+        false
+    }
+
+    /// True if a value of type `ty` stores a `target` by value.
+    fn ty_contains_target(&mut self, ty: SymTy<'db>) -> bool {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Named(name, ref generics) => match name {
+                SymTyName::Primitive(_) => false,
+                SymTyName::Aggregate(aggr) => {
+                    if aggr == self.target {
+                        return true;
+                    }
+
+                    if self.visiting.contains(&aggr) {
+                        return false;
+                    }
+
+                    self.visiting.push(aggr);
+                    let result = self.fields_contain_target(aggr, generics);
+                    self.visiting.pop();
+                    result
+                }
+                SymTyName::Future | SymTyName::Tuple { arity: _ } => generics
+                    .iter()
+                    .any(|&generic| self.ty_contains_target(generic.assert_type(db))),
+            },
+            SymTyKind::Perm(perm, ty) => self.stored_by_value(perm) && self.ty_contains_target(ty),
+            SymTyKind::Var(_) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => {
+                false
+            }
+        }
+    }
+
+    /// True if a value with permission `perm` stores its data inline.
+    /// Mirrors the representation chosen by codegen: only `mut` values are pointers.
+    fn stored_by_value(&self, perm: SymPerm<'db>) -> bool {
+        match *perm.kind(self.db) {
+            SymPermKind::My | SymPermKind::Our | SymPermKind::Referenced(_) => true,
+
+            // A generic permission could be instantiated with `my`, so we must assume the worst.
+            SymPermKind::Var(_) => true,
+
+            SymPermKind::Mutable(_) => false,
+            SymPermKind::Apply(left, _) => self.stored_by_value(left),
+            SymPermKind::Or(left, _) => self.stored_by_value(left),
+            SymPermKind::Infer(_) | SymPermKind::Error(_) => false,
+        }
+    }
+}
+
+fn report_layout_cycle<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+    path: &[SymField<'db>],
+) -> Reported {
+    let name = aggr.name(db);
+    let mut diagnostic = Diagnostic::error(
+        db,
+        aggr.name_span(db),
+        format!("`{name}` contains itself and so would have infinite size"),
+    )
+    .label(
+        db,
+        Level::Error,
+        aggr.name_span(db),
+        format!("`{name}` contains itself by value"),
+    );
+
+    for field in path {
+        diagnostic = diagnostic.label(
+            db,
+            Level::Info,
+            field.name_span(db),
+            format!(
+                "field `{field_name}` stores its value inline",
+                field_name = field.name(db)
+            ),
+        );
+    }
+
+    diagnostic.report(db)
+}
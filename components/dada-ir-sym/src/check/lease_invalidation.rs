@@ -0,0 +1,314 @@
+//! Lease invalidation: a variable initialized with `x.mut` holds a lease on `x`.
+//! Reassigning `x` (or a place that overlaps it) or leasing it again while the lease
+//! is still live invalidates the lease, so a later use of the variable is reported.
+//!
+//! Only uses count: a lease that is invalidated but never used again is fine,
+//! which is what makes the check liveness-aware.
+
+use dada_ir_ast::{ast::PermissionOp, diagnostic::Level, span::Span};
+use dada_util::{Map, Set, ensure_sufficient_stack};
+
+use crate::{
+    check::lints::{Lint, lint_diagnostic},
+    ir::{
+        exprs::{SymExpr, SymExprKind, SymLoop, SymPlaceExpr, SymPlaceExprKind},
+        types::SymField,
+        variables::SymVariable,
+    },
+};
+
+/// The leases held by each variable at some point in the body,
+/// or `None` if no path reaches it (e.g., after a `return`).
+type Leases<'db> = Option<Map<SymVariable<'db>, Lease<'db>>>;
+
+#[derive(Clone, PartialEq, Eq)]
+struct Lease<'db> {
+    /// The leased place (the lessor).
+    lessor: SymPlaceExpr<'db>,
+
+    /// The `x.mut` expression that created the lease.
+    created: Span<'db>,
+
+    /// Where the lessor was modified on some path since the lease was created, if anywhere.
+    invalidated: Option<Span<'db>>,
+}
+
+/// Reports uses of variables holding a `mut` lease after the leased place
+/// was reassigned or leased again. Only the first such use of each variable is reported.
+pub(crate) fn check_lease_invalidation<'db>(db: &'db dyn crate::Db, body: SymExpr<'db>) {
+    let mut checker = LeaseInvalidation {
+        db,
+        reported: Default::default(),
+        breaks: Default::default(),
+    };
+    checker.expr(body, &mut Some(Map::default()));
+}
+
+struct LeaseInvalidation<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Variables for which we have already reported a use.
+    reported: Set<SymVariable<'db>>,
+
+    /// For each loop being analyzed, the join of the states at the `break`s that exit it.
+    breaks: Map<SymLoop<'db>, Leases<'db>>,
+}
+
+impl<'db> LeaseInvalidation<'db> {
+    /// Updates `leases` to reflect the state after evaluating `expr`.
+    fn expr(&mut self, expr: SymExpr<'db>, leases: &mut Leases<'db>) {
+        let db = self.db;
+        ensure_sufficient_stack(|| match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+                self.expr(*lhs, leases);
+                self.expr(*rhs, leases);
+            }
+            SymExprKind::Tuple(exprs)
+            | SymExprKind::Aggregate {
+                ty: _,
+                fields: exprs,
+            } => {
+                for &expr in exprs {
+                    self.expr(expr, leases);
+                }
+            }
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                if let Some(initializer) = initializer {
+                    self.expr(*initializer, leases);
+                    self.bind(*lv, *initializer, leases);
+                }
+                self.expr(*body, leases);
+            }
+            SymExprKind::LetTuple {
+                lvs: _,
+                initializer,
+                body,
+            } => {
+                self.expr(*initializer, leases);
+                self.expr(*body, leases);
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword: _,
+            } => self.expr(*future, leases),
+            SymExprKind::Assign { place, value } => {
+                self.expr(*value, leases);
+                match *place.kind(db) {
+                    SymPlaceExprKind::Var(var) => self.bind(var, *value, leases),
+
+                    // Assigning to a field of `x` uses `x`.
+                    SymPlaceExprKind::Field(owner, _) => self.use_place(owner, leases),
+                    SymPlaceExprKind::Error(_) => {}
+                }
+                self.invalidate(*place, leases);
+            }
+            SymExprKind::PermissionOp(op, place) => {
+                self.use_place(*place, leases);
+                if let PermissionOp::Mutate = op {
+                    self.invalidate(*place, leases);
+                }
+            }
+            SymExprKind::IsSome(place) => self.use_place(*place, leases),
+            SymExprKind::Return(value) => {
+                self.expr(*value, leases);
+                *leases = None;
+            }
+            SymExprKind::Loop { sym_loop, body } => {
+                // Analyze the body twice, so that an invalidation at the end of one iteration
+                // is seen by a use at the start of the next.
+                let mut in_body = leases.clone();
+                self.expr(*body, &mut in_body);
+                let mut in_body = join(leases.clone(), in_body);
+                self.expr(*body, &mut in_body);
+                *leases = self.breaks.remove(sym_loop).flatten();
+            }
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                self.expr(*with_value, leases);
+                let after_loop = self.breaks.remove(from_loop).flatten();
+                self.breaks
+                    .insert(*from_loop, join(after_loop, leases.take()));
+            }
+            SymExprKind::Continue { to_loop: _ } => {
+                *leases = None;
+            }
+            SymExprKind::Not {
+                operand,
+                op_span: _,
+            }
+            | SymExprKind::Cast(operand) => self.expr(*operand, leases),
+            SymExprKind::Match { arms } => {
+                // `fallthrough` is the state when none of the arms so far has been taken.
+                let mut fallthrough = leases.clone();
+                let mut after_arms = None;
+                for arm in arms {
+                    if let Some(condition) = arm.condition {
+                        self.expr(condition, &mut fallthrough);
+                    }
+                    let mut in_arm = fallthrough.clone();
+                    self.expr(arm.body, &mut in_arm);
+                    after_arms = join(after_arms, in_arm);
+                    if arm.condition.is_none() {
+                        fallthrough = None;
+                        break;
+                    }
+                }
+                *leases = join(after_arms, fallthrough);
+            }
+
+            // The arguments of a call are temporaries, initialized by an enclosing `LetIn`.
+            SymExprKind::Call { .. }
+            | SymExprKind::Drop(_)
+            | SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::Error(_) => {}
+        })
+    }
+
+    /// Records the lease (if any) that `var` holds once `value` is stored in it:
+    /// either a fresh lease created by `x.mut` or the lease held by the variable being given.
+    fn bind(&mut self, var: SymVariable<'db>, value: SymExpr<'db>, leases: &mut Leases<'db>) {
+        let db = self.db;
+        let Some(leases) = leases else {
+            return;
+        };
+        let lease = match *value.kind(db) {
+            SymExprKind::PermissionOp(PermissionOp::Mutate, lessor) => Some(Lease {
+                lessor,
+                created: value.span(db),
+                invalidated: None,
+            }),
+            SymExprKind::PermissionOp(PermissionOp::Give, place) => match *place.kind(db) {
+                SymPlaceExprKind::Var(from) => leases.get(&from).cloned(),
+                SymPlaceExprKind::Field(..) | SymPlaceExprKind::Error(_) => None,
+            },
+            _ => None,
+        };
+        match lease {
+            Some(lease) => leases.insert(var, lease),
+            None => leases.remove(&var),
+        };
+    }
+
+    /// Marks every lease whose lessor overlaps `place` as invalidated by `place`.
+    /// The leases held by the root variable of `place` are not affected:
+    /// modifying a place through the lease is what the lease is for.
+    fn invalidate(&mut self, place: SymPlaceExpr<'db>, leases: &mut Leases<'db>) {
+        let db = self.db;
+        let Some(leases) = leases else {
+            return;
+        };
+        let Some((root, fields)) = path(db, place) else {
+            return;
+        };
+        for (&var, lease) in leases.iter_mut() {
+            if var == root {
+                continue;
+            }
+            let Some((lessor_root, lessor_fields)) = path(db, lease.lessor) else {
+                continue;
+            };
+            if lessor_root == root
+                && (lessor_fields.starts_with(&fields) || fields.starts_with(&lessor_fields))
+            {
+                lease.invalidated = Some(place.span(db));
+            }
+        }
+    }
+
+    /// Reports `place` if its root variable holds a lease that may have been invalidated.
+    fn use_place(&mut self, place: SymPlaceExpr<'db>, leases: &Leases<'db>) {
+        let db = self.db;
+        let Some((var, _)) = path(db, place) else {
+            return;
+        };
+        let Some(leases) = leases else {
+            return;
+        };
+        let Some(Lease {
+            lessor,
+            created,
+            invalidated: Some(invalidated),
+        }) = leases.get(&var)
+        else {
+            return;
+        };
+        if !self.reported.insert(var) {
+            return;
+        }
+
+        let span = place.span(db);
+        let lessor = lessor.into_sym_place(db);
+        let Some(diagnostic) = lint_diagnostic(
+            db,
+            Lint::InvalidatedLeases,
+            span,
+            format!("`{var}` is used after the lease it holds on `{lessor}` was invalidated"),
+        ) else {
+            return;
+        };
+        let level = diagnostic.level;
+        diagnostic
+            .label(db, level, span, format!("`{var}` is used here"))
+            .label(
+                db,
+                Level::Info,
+                *invalidated,
+                format!("`{lessor}` is modified here, which invalidates the lease"),
+            )
+            .label(
+                db,
+                Level::Info,
+                *created,
+                format!("`{var}` leases `{lessor}` here"),
+            )
+            .report(db);
+    }
+}
+
+/// The root variable of `place` and the fields accessed from it, outermost first
+/// (e.g., `a` and `[b, c]` for `a.b.c`).
+fn path<'db>(
+    db: &'db dyn crate::Db,
+    place: SymPlaceExpr<'db>,
+) -> Option<(SymVariable<'db>, Vec<SymField<'db>>)> {
+    match *place.kind(db) {
+        SymPlaceExprKind::Var(var) => Some((var, vec![])),
+        SymPlaceExprKind::Field(owner, field) => {
+            let (var, mut fields) = path(db, owner)?;
+            fields.push(field);
+            Some((var, fields))
+        }
+        SymPlaceExprKind::Error(_) => None,
+    }
+}
+
+/// The state where two paths meet: the leases held on either path,
+/// invalidated if they were invalidated on either path.
+fn join<'db>(a: Leases<'db>, b: Leases<'db>) -> Leases<'db> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(b)) => {
+            for (var, lease_b) in b {
+                match a.get_mut(&var) {
+                    Some(lease_a) if lease_a.lessor == lease_b.lessor => {
+                        lease_a.invalidated = lease_a.invalidated.or(lease_b.invalidated);
+                    }
+                    Some(_) => {}
+                    None => {
+                        a.insert(var, lease_b);
+                    }
+                }
+            }
+            Some(a)
+        }
+    }
+}
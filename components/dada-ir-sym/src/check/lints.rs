@@ -0,0 +1,332 @@
+//! Lints are diagnostics whose level the user can adjust with
+//! `#[allow(...)]`, `#[warn(...)]`, and `#[deny(...)]` attributes on items
+//! (or `#![...]` at the start of the file for the whole module).
+//!
+//! This is also where attributes are checked, including the one other attribute,
+//! `#[host_export]`.
+
+use std::fmt::Display;
+
+use dada_ir_ast::{
+    ast::{AstAttribute, AstItem, AstMember},
+    diagnostic::{Diagnostic, Level},
+    span::{AbsoluteSpan, Span},
+};
+use dada_parser::prelude::{ClassItemMembers, SourceFileParse};
+
+use crate::{
+    check::{ffi::HOST_EXPORT_ATTRIBUTE, scope_tree::ScopeItem},
+    ir::{
+        classes::SymAggregate,
+        functions::{SymFunction, SymFunctionSource},
+        module::SymModule,
+    },
+};
+
+/// A diagnostic whose level can be controlled with attributes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lint {
+    /// A lint attribute names a lint that does not exist.
+    UnknownLints,
+
+    /// Statements in a block follow an expression that never finishes (e.g., `return`).
+    UnreachableCode,
+
+    /// An arm of an `if`/`else if`/`else` chain can never be taken.
+    UnreachableArms,
+
+    /// A variable holding a `mut` lease is used after the leased place was modified.
+    InvalidatedLeases,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 4] = [
+        Lint::UnknownLints,
+        Lint::UnreachableCode,
+        Lint::UnreachableArms,
+        Lint::InvalidatedLeases,
+    ];
+
+    /// The name used to refer to the lint in attributes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnknownLints => "unknown_lints",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::UnreachableArms => "unreachable_arms",
+            Lint::InvalidatedLeases => "invalidated_leases",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+
+    /// Level of the lint when no attribute applies.
+    fn default_level(self) -> LintLevel {
+        match self {
+            Lint::UnknownLints
+            | Lint::UnreachableCode
+            | Lint::UnreachableArms
+            | Lint::InvalidatedLeases => LintLevel::Warn,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    /// Lint is not reported.
+    Allow,
+
+    /// Lint is reported as a warning.
+    Warn,
+
+    /// Lint is reported as an error.
+    Deny,
+}
+
+impl LintLevel {
+    /// The lint level set by an attribute named `name`, if it is a lint level attribute.
+    fn from_attribute_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Creates the diagnostic for `lint` at `span`, taking into account the attributes
+/// on the enclosing items. Returns `None` if the lint is allowed at `span`;
+/// otherwise the caller can add labels and then report it.
+pub fn lint_diagnostic<'db>(
+    db: &'db dyn crate::Db,
+    lint: Lint,
+    span: Span<'db>,
+    message: impl Display,
+) -> Option<Diagnostic> {
+    let level = match lint_level(db, lint, span) {
+        LintLevel::Allow => return None,
+        LintLevel::Warn => Level::Warning,
+        LintLevel::Deny => Level::Error,
+    };
+    Some(Diagnostic::new(db, level, span, message))
+}
+
+/// Determines the level of `lint` at `span`.
+/// Attributes on inner items override those on outer items,
+/// and within one list of attributes the last one naming the lint wins.
+pub fn lint_level<'db>(db: &'db dyn crate::Db, lint: Lint, span: Span<'db>) -> LintLevel {
+    let mut level = lint.default_level();
+    for attributes in enclosing_attributes(db, span) {
+        for attribute in attributes {
+            let Some(attribute_level) = LintLevel::from_attribute_name(attribute.name.id.text(db))
+            else {
+                continue;
+            };
+            if attribute
+                .args
+                .iter()
+                .flat_map(|args| &args.values)
+                .any(|arg| arg.id.text(db) == lint.name())
+            {
+                level = attribute_level;
+            }
+        }
+    }
+    level
+}
+
+/// The attribute lists of the module and items enclosing `span`, outermost first.
+fn enclosing_attributes<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+) -> Vec<&'db [AstAttribute<'db>]> {
+    let target = span.absolute_span(db);
+    let module = span.anchor.source_file(db).parse(db);
+
+    let mut result: Vec<&'db [AstAttribute<'db>]> = vec![module.attributes(db)];
+    for item in &module.items(db).values {
+        match *item {
+            AstItem::Aggregate(aggregate) => {
+                if !extent(db, aggregate.attributes(db), aggregate.span(db)).contains(target) {
+                    continue;
+                }
+                result.push(aggregate.attributes(db));
+                for member in &aggregate.members(db).values {
+                    if let AstMember::Function(function) = *member
+                        && extent(db, function.attributes(db), function.span(db)).contains(target)
+                    {
+                        result.push(function.attributes(db));
+                    }
+                }
+            }
+            AstItem::Function(function) => {
+                if extent(db, function.attributes(db), function.span(db)).contains(target) {
+                    result.push(function.attributes(db));
+                }
+            }
+            AstItem::ExternFunction(extern_function) => {
+                let function = extern_function.function(db);
+                if extent(db, function.attributes(db), extern_function.span(db)).contains(target) {
+                    result.push(function.attributes(db));
+                }
+            }
+            AstItem::SourceFile(_) | AstItem::Use(_) | AstItem::MainFunction(_) => {}
+        }
+    }
+    result
+}
+
+/// The span of an item including the attributes that precede it,
+/// so that an item's attributes also govern lints reported on its attributes.
+fn extent<'db>(
+    db: &'db dyn crate::Db,
+    attributes: &[AstAttribute<'db>],
+    span: Span<'db>,
+) -> AbsoluteSpan {
+    match attributes.first() {
+        Some(first) => first.span.to(db, span).absolute_span(db),
+        None => span.absolute_span(db),
+    }
+}
+
+/// Reports attributes that are not understood and lint names that do not exist.
+/// `host_export_allowed` is true if the attributes belong to a function that can be
+/// a host export (see [`HOST_EXPORT_ATTRIBUTE`]).
+fn check_attributes<'db>(
+    db: &'db dyn crate::Db,
+    attributes: &[AstAttribute<'db>],
+    host_export_allowed: bool,
+) {
+    for attribute in attributes {
+        let name = attribute.name.id.text(db);
+        if name == HOST_EXPORT_ATTRIBUTE {
+            check_host_export_attribute(db, attribute, host_export_allowed);
+            continue;
+        }
+
+        if LintLevel::from_attribute_name(name).is_none() {
+            Diagnostic::error(
+                db,
+                attribute.name.span,
+                format!("unknown attribute `{name}`"),
+            )
+            .label(
+                db,
+                Level::Error,
+                attribute.name.span,
+                format!(
+                    "the supported attributes are `allow`, `warn`, `deny`, and `{HOST_EXPORT_ATTRIBUTE}`"
+                ),
+            )
+            .report(db);
+            continue;
+        }
+
+        let Some(args) = &attribute.args else {
+            Diagnostic::error(
+                db,
+                attribute.span,
+                format!("`{name}` attribute requires a list of lints"),
+            )
+            .label(
+                db,
+                Level::Error,
+                attribute.span,
+                format!("expected something like `#[{name}(unknown_lints)]`"),
+            )
+            .report(db);
+            continue;
+        };
+
+        for arg in &args.values {
+            let lint_name = arg.id.text(db);
+            if Lint::from_name(lint_name).is_some() {
+                continue;
+            }
+            if let Some(diagnostic) = lint_diagnostic(
+                db,
+                Lint::UnknownLints,
+                arg.span,
+                format!("unknown lint `{lint_name}`"),
+            ) {
+                diagnostic
+                    .label(
+                        db,
+                        Level::Help,
+                        arg.span,
+                        format!(
+                            "the known lints are: {}",
+                            Lint::ALL.map(Lint::name).join(", ")
+                        ),
+                    )
+                    .report(db);
+            }
+        }
+    }
+}
+
+/// Reports a `#[host_export]` attribute with arguments, or on something that cannot be
+/// a host export. Whether the function itself can cross to the host is checked separately
+/// (see [`crate::check::ffi`]).
+fn check_host_export_attribute<'db>(
+    db: &'db dyn crate::Db,
+    attribute: &AstAttribute<'db>,
+    allowed: bool,
+) {
+    if !allowed {
+        Diagnostic::error(
+            db,
+            attribute.span,
+            format!("`{HOST_EXPORT_ATTRIBUTE}` can only be applied to functions in a module"),
+        )
+        .label(
+            db,
+            Level::Error,
+            attribute.span,
+            "only functions defined directly in a module can be called by the host",
+        )
+        .report(db);
+    } else if let Some(args) = &attribute.args {
+        Diagnostic::error(
+            db,
+            args.span,
+            format!("`{HOST_EXPORT_ATTRIBUTE}` attribute takes no arguments"),
+        )
+        .label(
+            db,
+            Level::Error,
+            args.span,
+            format!("write `#[{HOST_EXPORT_ATTRIBUTE}]`"),
+        )
+        .report(db);
+    }
+}
+
+pub(crate) fn check_module_attributes<'db>(db: &'db dyn crate::Db, module: SymModule<'db>) {
+    check_attributes(db, module.source(db).attributes(db), false);
+}
+
+pub(crate) fn check_aggregate_attributes<'db>(
+    db: &'db dyn crate::Db,
+    aggregate: SymAggregate<'db>,
+) {
+    check_attributes(db, aggregate.attributes(db), false);
+}
+
+pub(crate) fn check_function_attributes<'db>(db: &'db dyn crate::Db, function: SymFunction<'db>) {
+    match function.source(db) {
+        SymFunctionSource::Function(ast_function) => {
+            let in_module = matches!(
+                function.super_scope_item(db),
+                ScopeItem::SymModule(_) | ScopeItem::AstModule(_)
+            );
+            check_attributes(db, ast_function.attributes(db), in_module)
+        }
+        SymFunctionSource::Extern(ast_extern) => {
+            check_attributes(db, ast_extern.function(db).attributes(db), false)
+        }
+        SymFunctionSource::MainFunction(_) | SymFunctionSource::Constructor(..) => {}
+    }
+}
@@ -0,0 +1,93 @@
+//! Reachability of the arms of a [`SymExprKind::Match`][], which is what
+//! `if`/`else if`/`else` chains are lowered to.
+
+use dada_ir_ast::{
+    diagnostic::{Diagnostic, Level},
+    span::Span,
+};
+
+use crate::{
+    check::{
+        env::Env,
+        lints::{Lint, lint_diagnostic},
+    },
+    ir::{
+        exprs::{SymExpr, SymExprKind, SymLiteral, SymMatchArm},
+        types::SymTy,
+    },
+};
+
+/// Reports the arms of a match that can never be taken:
+/// those whose condition is the literal `false`,
+/// and those that follow an arm that is always taken
+/// (an `else`, or a condition that is the literal `true`).
+///
+/// `arm_spans` has the span of each arm in the source, from its condition
+/// (if any) to the end of its block.
+pub(crate) fn check_arm_reachability<'db>(
+    env: &Env<'db>,
+    arms: &[SymMatchArm<'db>],
+    arm_spans: &[Span<'db>],
+) {
+    let db = env.db();
+    let mut always_taken: Option<Span<'db>> = None;
+
+    for (arm, &arm_span) in arms.iter().zip(arm_spans) {
+        if let Some(taken_span) = always_taken {
+            report_unreachable_arm(env, arm_span, |diagnostic| {
+                diagnostic.label(db, Level::Info, taken_span, "this arm is always taken")
+            });
+            continue;
+        }
+
+        match known_condition(db, arm.condition) {
+            Some(true) => always_taken = Some(arm_span),
+            Some(false) => report_unreachable_arm(env, arm_span, |diagnostic| {
+                // A `false` condition is always present.
+                let condition = arm.condition.unwrap();
+                diagnostic.label(
+                    db,
+                    Level::Info,
+                    condition.span(db),
+                    "this condition is always `false`",
+                )
+            }),
+            None => {}
+        }
+    }
+}
+
+/// The value of the condition of an arm, if it is known without running the program.
+/// An arm with no condition (e.g., `else`) is always taken.
+fn known_condition<'db>(db: &'db dyn crate::Db, condition: Option<SymExpr<'db>>) -> Option<bool> {
+    let Some(condition) = condition else {
+        return Some(true);
+    };
+    match *condition.kind(db) {
+        SymExprKind::Primitive(SymLiteral::Integral { bits })
+            if condition.ty(db) == SymTy::boolean(db) =>
+        {
+            Some(bits != 0)
+        }
+        _ => None,
+    }
+}
+
+fn report_unreachable_arm<'db>(
+    env: &Env<'db>,
+    span: Span<'db>,
+    explain: impl FnOnce(Diagnostic) -> Diagnostic,
+) {
+    let db = env.db();
+    let Some(diagnostic) = lint_diagnostic(db, Lint::UnreachableArms, span, "unreachable arm")
+    else {
+        return;
+    };
+    let level = diagnostic.level;
+    env.report(explain(diagnostic.label(
+        db,
+        level,
+        span,
+        "this arm will never be taken",
+    )));
+}
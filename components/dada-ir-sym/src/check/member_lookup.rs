@@ -1,6 +1,5 @@
 use crate::ir::{
-    binder::Binder,
-    classes::{SymAggregate, SymClassMember, SymField},
+    classes::{SymClassMember, SymField},
     functions::SymFunction,
     types::{SymGenericTerm, SymPerm, SymTy, SymTyName},
 };
@@ -14,8 +13,8 @@ use dada_util::{debug, debug_heading};
 use crate::{
     check::env::Env,
     check::exprs::{ExprResult, ExprResultKind},
+    check::places::field_ty_with_perm,
     ir::exprs::{SymPlaceExpr, SymPlaceExprKind},
-    prelude::CheckedFieldTy,
 };
 
 use super::{inference::Direction, red::RedTy, to_red::ToRedTy};
@@ -62,19 +61,23 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
 
         // Construct the result
         match member {
-            SearchResult::Field {
-                owner: _,
-                field,
-                field_ty,
-            } => {
+            SearchResult::Field { field, generics } => {
                 let mut temporaries = vec![];
 
                 // The type of the field will be the declared type `F` with...
                 // * `self` replaced with the place `owner`
                 // * the permission from the owner applied
+                //
+                // If `owner` is itself a field access, its type already carries the
+                // permissions of its own owner, so those compose as well.
                 let owner_place_expr = owner.into_place_expr(self.env, &mut temporaries);
-                let field_ty = field_ty.substitute(db, &[owner_place_expr.into_sym_place(db)]);
-                let field_ty_with_perm = owner_perm.apply_to(db, field_ty);
+                let field_ty_with_perm = field_ty_with_perm(
+                    db,
+                    field,
+                    &generics,
+                    owner_place_expr.into_sym_place(db),
+                    owner_perm,
+                );
 
                 // construct the place expression
                 let place_expr = SymPlaceExpr::new(
@@ -85,7 +88,7 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
                 );
                 ExprResult::from_place_expr(db, place_expr, temporaries)
             }
-            SearchResult::Method { owner: _, method } => {
+            SearchResult::Method { method } => {
                 let mut temporaries = vec![];
                 let owner = owner.into_expr(self.env, &mut temporaries);
                 ExprResult {
@@ -145,19 +148,7 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
     ) -> Errors<Option<SearchResult<'db>>> {
         debug_heading!("search_lower_bound_for_member", lower_bound, id);
         match lower_bound {
-            RedTy::Named(name, ref generics) => match name {
-                // Primitive types don't have members.
-                SymTyName::Primitive(_) => Ok(None),
-
-                // Tuples have indexed members, not named ones.
-                SymTyName::Tuple { arity: _ } => Ok(None),
-
-                // Classes have members.
-                SymTyName::Aggregate(owner) => self.search_aggr_for_member(owner, generics, id),
-
-                // Future types have no members.
-                SymTyName::Future => Ok(None),
-            },
+            RedTy::Named(name, ref generics) => self.search_class_for_member(name, generics, id),
             RedTy::Error(reported) => Err(reported),
             RedTy::Never => Ok(None),
             RedTy::Infer(_) => panic!("did not expect inference variable"),
@@ -166,24 +157,23 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
         }
     }
 
-    fn search_aggr_for_member(
+    fn search_class_for_member(
         &mut self,
-        owner: SymAggregate<'db>,
+        name: SymTyName<'db>,
         generics: &[SymGenericTerm<'db>],
         id: Identifier<'db>,
     ) -> Errors<Option<SearchResult<'db>>> {
         let db = self.env.db();
-        debug_heading!("search_class_for_member", id, owner);
+        debug_heading!("search_class_for_member", id, name);
 
-        for &member in owner.members(db) {
+        for &member in named_members(db, name) {
             match member {
                 SymClassMember::SymField(field) => {
                     if field.name(db) == id {
                         debug!("found field", field);
                         return Ok(Some(SearchResult::Field {
-                            owner,
                             field,
-                            field_ty: field.checked_field_ty(db).substitute(db, generics),
+                            generics: generics.to_vec(),
                         }));
                     } else {
                         debug!("found field with wrong name", field.name(db));
@@ -193,7 +183,7 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
                 SymClassMember::SymFunction(method) => {
                     if method.name(db) == id {
                         debug!("found method", method);
-                        return Ok(Some(SearchResult::Method { owner, method }));
+                        return Ok(Some(SearchResult::Method { method }));
                     } else {
                         debug!("found method with wrong name", method.name(db));
                     }
@@ -205,15 +195,34 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
     }
 }
 
+/// The members that can be accessed with `.` on a value of a type named `name`,
+/// i.e., the members that [`MemberLookup`] searches.
+pub(crate) fn named_members<'db>(
+    db: &'db dyn crate::Db,
+    name: SymTyName<'db>,
+) -> &'db [SymClassMember<'db>] {
+    match name {
+        // Primitive types don't have members.
+        SymTyName::Primitive(_) => &[],
+
+        // Tuples have indexed members, not named ones.
+        SymTyName::Tuple { arity: _ } => &[],
+
+        // Classes have members.
+        SymTyName::Aggregate(owner) => owner.members(db),
+
+        // Future types have no members.
+        SymTyName::Future => &[],
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 enum SearchResult<'db> {
     Field {
-        owner: SymAggregate<'db>,
         field: SymField<'db>,
-        field_ty: Binder<'db, SymTy<'db>>,
+        generics: Vec<SymGenericTerm<'db>>,
     },
     Method {
-        owner: SymAggregate<'db>,
         method: SymFunction<'db>,
     },
 }
@@ -224,7 +233,7 @@ enum SearchResult<'db> {
 /// # Returns
 ///
 /// A [`RedTy`][] that is a lower bound for `ty` and which is not an inference variable.
-async fn non_infer_lower_bound<'db>(
+pub(crate) async fn non_infer_lower_bound<'db>(
     env: &mut Env<'db>,
     ty: SymTy<'db>,
 ) -> (RedTy<'db>, SymPerm<'db>) {
@@ -1,7 +1,7 @@
 use crate::{ir::module::SymModule, prelude::CheckUseItems};
-use dada_ir_ast::{diagnostic::Errors, span::Spanned};
+use dada_ir_ast::span::Spanned;
 
-use super::{Env, Runtime, scope::Resolve};
+use super::scope::{check_glob_use, resolve_ast_use};
 
 /// Resolve all use items found in this module.
 /// This is executed by `dada-ir-check` crate
@@ -10,19 +10,15 @@ use super::{Env, Runtime, scope::Resolve};
 impl<'db> CheckUseItems<'db> for SymModule<'db> {
     #[salsa::tracked]
     fn check_use_items(self, db: &'db dyn crate::Db) {
-        let _: Errors<()> = Runtime::execute(
-            db,
-            self.span(db),
-            "check_use_items",
-            &[&self],
-            async move |runtime| {
-                let mut env = Env::new(runtime, self.mod_scope(db));
-                for item in self.ast_use_map(db).values() {
-                    let _ = item.path(db).resolve_in(&mut env).await;
-                }
-                Ok(())
-            },
-            |v| v,
-        );
+        // Resolve in source order so that errors (e.g., cycles) are reported deterministically.
+        let mut ast_uses = self.ast_use_map(db).values().copied().collect::<Vec<_>>();
+        ast_uses.sort_by_key(|ast_use| ast_use.span(db));
+        for ast_use in ast_uses {
+            let _ = resolve_ast_use(db, ast_use);
+        }
+
+        for &ast_use in self.ast_glob_uses(db) {
+            check_glob_use(db, ast_use);
+        }
     }
 }
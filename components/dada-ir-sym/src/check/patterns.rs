@@ -0,0 +1,477 @@
+//! `let` statements whose pattern destructures the value,
+//! e.g., `let (a, b) = v` or `let Point { x, y } = v`,
+//! and `if let Some(pattern) = v` arms.
+
+use dada_ir_ast::{
+    ast::{
+        AstBlock, AstFieldPattern, AstIfLet, AstLetStatement, AstPath, AstPattern, AstStatement,
+        Identifier, PermissionOp, SpannedIdentifier,
+    },
+    diagnostic::{Diagnostic, Errors, Level},
+    span::{Span, Spanned},
+};
+use dada_util::boxed_async_fn;
+
+use crate::{
+    check::{
+        CheckExprInEnv, CheckTyInEnv,
+        env::Env,
+        exprs::{ExprResult, permission_op_expr},
+        live_places::LivePlaces,
+        member_lookup::{MemberLookup, non_infer_lower_bound},
+        red::RedTy,
+        report::{BadSubtermError, InvalidPatternType},
+        scope::{NameResolution, NameResolutionSym, Resolve},
+        statements::check_block_statements,
+    },
+    ir::{
+        classes::SymAggregate,
+        exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+        types::{SymGenericKind, SymTy, SymTyName},
+        variables::SymVariable,
+    },
+    well_known,
+};
+
+/// Checks `let pattern[: ty] = initializer`, where `pattern` destructures a tuple
+/// or an instance of a class, followed by the statements `rest`.
+pub(crate) async fn check_let_pattern<'db>(
+    env: &mut Env<'db>,
+    block_span: Span<'db>,
+    s: AstLetStatement<'db>,
+    rest: &[AstStatement<'db>],
+) -> SymExpr<'db> {
+    let db = env.db();
+    let pattern = s.pattern(db);
+    let initializer = s
+        .initializer(db)
+        .expect("parser requires an initializer for a destructuring pattern");
+
+    let ty = match s.ty(db) {
+        Some(ty) => Some(ty.check_in_env(env).await),
+        None => None,
+    };
+    let (mut bound, pattern_ty) = declare_pattern(env, pattern);
+
+    let (initializer, body) = env
+        .join(
+            async |env| {
+                let initializer = initializer
+                    .check_in_env(env, LivePlaces::fixme())
+                    .await
+                    .into_expr_with_enclosed_temporaries(env);
+                let value_ty = match ty {
+                    Some(ty) => {
+                        env.spawn_require_assignable_type(
+                            LivePlaces::fixme(),
+                            initializer.ty(db),
+                            ty,
+                            &BadSubtermError::new(initializer.span(db), initializer.ty(db), ty),
+                        );
+                        ty
+                    }
+                    None => initializer.ty(db),
+                };
+                require_pattern_ty(env, pattern, initializer.span(db), value_ty, pattern_ty);
+                initializer
+            },
+            async |env| {
+                bound.push_variables(env, pattern_ty).await;
+                check_block_statements(env, LivePlaces::fixme(), block_span, rest).await
+            },
+        )
+        .await;
+
+    let span = s.span(db).to(db, body.span(db));
+    lower_pattern(db, span, bound, pattern_ty, initializer, body)
+}
+
+/// Checks `body`, the block of the arm `if let Some(pattern) = scrutinee`.
+/// The scrutinee, of type `scrutinee_ty`, is stored in `temp`; within `body`,
+/// `pattern` is bound to the value that the option holds.
+pub(crate) async fn check_if_let<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    binding: &AstIfLet<'db>,
+    temp: SymVariable<'db>,
+    scrutinee_ty: SymTy<'db>,
+    body: AstBlock<'db>,
+) -> SymExpr<'db> {
+    let db = env.db();
+    let pattern = &binding.pattern;
+    let span = binding.some.span.to(db, pattern.span(db));
+    let (mut bound, pattern_ty) = declare_pattern(env, pattern);
+
+    let (_, (value, body)) = env
+        .join(
+            async |env| check_some(env, binding.some),
+            async |env| {
+                env.push_program_variable_with_ty(temp, scrutinee_ty);
+                let value = match check_option(env, span, scrutinee_ty).await {
+                    Ok(()) => {
+                        let owner =
+                            SymPlaceExpr::new(db, span, scrutinee_ty, SymPlaceExprKind::Var(temp));
+                        give_field(env, owner, "value", span).await
+                    }
+                    Err(reported) => SymExpr::err(db, reported),
+                };
+                require_pattern_ty(env, pattern, span, value.ty(db), pattern_ty);
+                bound.push_variables(env, pattern_ty).await;
+                let body = body.check_in_env(env, live_after).await;
+                (value, body)
+            },
+        )
+        .await;
+
+    lower_pattern(db, body.span(db), bound, pattern_ty, value, body)
+}
+
+/// Checks that `some`, the `Some` in `if let Some(..) = ...`, names the well-known `Some` function.
+fn check_some<'db>(env: &mut Env<'db>, some: SpannedIdentifier<'db>) -> Errors<()> {
+    let db = env.db();
+    let NameResolution { sym, generics: _ } = env.scope.resolve_name(db, some.id, some.span)?;
+    match sym {
+        NameResolutionSym::SymFunction(function)
+            if well_known::some_fn(db).ok() == Some(function) =>
+        {
+            Ok(())
+        }
+        _ => Err(
+            env.report(Diagnostic::error(db, some.span, "expected `Some`").label(
+                db,
+                Level::Error,
+                some.span,
+                format!(
+                    "`if let` can only match `Some`, but this is {}",
+                    sym.describe(db)
+                ),
+            )),
+        ),
+    }
+}
+
+/// Checks that a value of type `ty` is an `Option`, as expected by the `if let` pattern at `span`.
+async fn check_option<'db>(env: &mut Env<'db>, span: Span<'db>, ty: SymTy<'db>) -> Errors<()> {
+    let option = well_known::option_class(env.db())?;
+    check_instance_of(env, option, span, ty).await
+}
+
+/// Requires that the value of type `value_ty` found at `value_span` can be
+/// matched by `pattern`, which matches values of type `pattern_ty`.
+fn require_pattern_ty<'db>(
+    env: &mut Env<'db>,
+    pattern: &AstPattern<'db>,
+    value_span: Span<'db>,
+    value_ty: SymTy<'db>,
+    pattern_ty: SymTy<'db>,
+) {
+    let db = env.db();
+    match pattern {
+        AstPattern::Tuple(patterns) => env.spawn_require_assignable_type(
+            LivePlaces::fixme(),
+            value_ty,
+            pattern_ty,
+            &InvalidPatternType::new(pattern.span(db), patterns.len(), value_ty),
+        ),
+
+        // The pattern type is an inference variable, so this cannot fail;
+        // the class of the value is checked when the fields are bound.
+        AstPattern::Id(_) | AstPattern::Aggregate(..) => env.spawn_require_assignable_type(
+            LivePlaces::fixme(),
+            value_ty,
+            pattern_ty,
+            &BadSubtermError::new(value_span, value_ty, pattern_ty),
+        ),
+    }
+}
+
+/// The variables bound by a `let` pattern.
+enum BoundPattern<'db> {
+    /// `x`
+    Var(SymVariable<'db>),
+
+    /// `(p0, p1, ...)`, with the type of each element
+    Tuple(Vec<(BoundPattern<'db>, SymTy<'db>)>),
+
+    /// `Class { f0, f1.ref, ... }`, where the value is stored in `temp`.
+    /// Each field in `fields` becomes a variable initialized from `temp`,
+    /// which are recorded in `bindings` by [`BoundPattern::push_variables`].
+    Aggregate {
+        temp: SymVariable<'db>,
+        class: AstPath<'db>,
+        span: Span<'db>,
+        fields: Vec<AstFieldPattern<'db>>,
+        bindings: Vec<(SymVariable<'db>, SymExpr<'db>)>,
+    },
+}
+
+impl<'db> BoundPattern<'db> {
+    /// Brings the variables into scope, given that the pattern matches a value of type `ty`.
+    ///
+    /// The fields of a class pattern take their types (and permissions) from the value,
+    /// so this waits until the type of the value is known.
+    #[boxed_async_fn]
+    async fn push_variables(&mut self, env: &mut Env<'db>, ty: SymTy<'db>) {
+        match self {
+            BoundPattern::Var(lv) => env.push_program_variable_with_ty(*lv, ty),
+            BoundPattern::Tuple(elements) => {
+                for (element, ty) in elements {
+                    element.push_variables(env, *ty).await;
+                }
+            }
+            BoundPattern::Aggregate {
+                temp,
+                class,
+                span,
+                fields,
+                bindings,
+            } => {
+                let db = env.db();
+                env.push_program_variable_with_ty(*temp, ty);
+                let class_ok = check_pattern_class(env, *class, *span, ty).await;
+                let owner = SymPlaceExpr::new(db, *span, ty, SymPlaceExprKind::Var(*temp));
+                for field in fields.iter() {
+                    let value = match class_ok {
+                        Ok(()) => bind_field(env, owner, field).await,
+                        Err(reported) => SymExpr::err(db, reported),
+                    };
+                    let lv = SymVariable::new_local(db, field.name.id, field.name.span);
+                    env.push_program_variable_with_ty(lv, value.ty(db));
+                    bindings.push((lv, value));
+                }
+            }
+        }
+    }
+}
+
+/// Creates the variables bound by `pattern`, returning them along with the type
+/// of the values that `pattern` matches: a fresh inference variable for a variable
+/// or a class pattern and a tuple of the element types for a tuple pattern.
+fn declare_pattern<'db>(
+    env: &mut Env<'db>,
+    pattern: &AstPattern<'db>,
+) -> (BoundPattern<'db>, SymTy<'db>) {
+    let db = env.db();
+    match pattern {
+        AstPattern::Id(name) => (
+            BoundPattern::Var(SymVariable::new_local(db, name.id, name.span)),
+            env.fresh_ty_inference_var(name.span),
+        ),
+        AstPattern::Tuple(patterns) => {
+            let elements: Vec<_> = patterns.iter().map(|p| declare_pattern(env, p)).collect();
+            let ty = SymTy::named(
+                db,
+                SymTyName::Tuple {
+                    arity: elements.len(),
+                },
+                elements.iter().map(|&(_, ty)| ty.into()).collect(),
+            );
+            (BoundPattern::Tuple(elements), ty)
+        }
+        AstPattern::Aggregate(class, fields) => {
+            let span = pattern.span(db);
+            (
+                BoundPattern::Aggregate {
+                    temp: SymVariable::new(db, SymGenericKind::Place, None, span),
+                    class: *class,
+                    span,
+                    fields: fields.values.clone(),
+                    bindings: vec![],
+                },
+                env.fresh_ty_inference_var(span),
+            )
+        }
+    }
+}
+
+/// Checks that a value of type `ty` is an instance of `class`, the class named
+/// by the class pattern at `span`.
+async fn check_pattern_class<'db>(
+    env: &mut Env<'db>,
+    class: AstPath<'db>,
+    span: Span<'db>,
+    ty: SymTy<'db>,
+) -> Errors<()> {
+    let db = env.db();
+    let class_span = class.span(db);
+    let aggregate = match class.resolve_in(env).await?.sym {
+        NameResolutionSym::SymAggregate(aggregate) => aggregate,
+        sym => {
+            return Err(
+                env.report(Diagnostic::error(db, class_span, "expected a class").label(
+                    db,
+                    Level::Error,
+                    class_span,
+                    format!(
+                        "I expected a class here, but I found {}",
+                        sym.categorize(db)
+                    ),
+                )),
+            );
+        }
+    };
+
+    check_instance_of(env, aggregate, span, ty).await
+}
+
+/// Checks that a value of type `ty` is an instance of `aggregate`, as expected
+/// by the pattern at `span`.
+async fn check_instance_of<'db>(
+    env: &mut Env<'db>,
+    aggregate: SymAggregate<'db>,
+    span: Span<'db>,
+    ty: SymTy<'db>,
+) -> Errors<()> {
+    let db = env.db();
+    match non_infer_lower_bound(env, ty).await.0 {
+        RedTy::Named(SymTyName::Aggregate(a), _) if a == aggregate => Ok(()),
+        RedTy::Error(reported) => Err(reported),
+        _ => Err(env.report(
+            Diagnostic::error(db, span, "class pattern does not match value").label(
+                db,
+                Level::Error,
+                span,
+                format!(
+                    "this pattern expects an instance of `{aggregate}`, but the value has type `{ty}`"
+                ),
+            ),
+        )),
+    }
+}
+
+/// Creates `owner.name.give`, where `name` names a field of a prelude class
+/// (e.g., the `value` of an `Option`); `span` is the code that reads it.
+pub(crate) async fn give_field<'db>(
+    env: &mut Env<'db>,
+    owner: SymPlaceExpr<'db>,
+    name: &str,
+    span: Span<'db>,
+) -> SymExpr<'db> {
+    let field = AstFieldPattern {
+        name: SpannedIdentifier {
+            span,
+            id: Identifier::new(env.db(), name),
+        },
+        op: None,
+    };
+    bind_field(env, owner, &field).await
+}
+
+/// Creates the initializer for the variable bound by `field`:
+/// `owner.f.give` or, if the field pattern has a permission, `owner.f.ref` (etc).
+async fn bind_field<'db>(
+    env: &mut Env<'db>,
+    owner: SymPlaceExpr<'db>,
+    field: &AstFieldPattern<'db>,
+) -> SymExpr<'db> {
+    let db = env.db();
+    let owner = ExprResult::from_place_expr(db, owner, vec![]);
+    let member = MemberLookup::new(env)
+        .lookup_member(owner, field.name)
+        .await;
+
+    // The owner is a variable, so accessing its field requires no temporaries.
+    let mut temporaries = vec![];
+    let place_expr = member.into_place_expr(env, &mut temporaries);
+    assert!(temporaries.is_empty());
+
+    let op = field.op.unwrap_or(PermissionOp::Give);
+    permission_op_expr(db, field.name.span, op, place_expr)
+}
+
+/// Creates `let bound: ty = initializer in body`.
+/// A tuple pattern becomes a [`SymExprKind::LetTuple`][] that gives each element
+/// to its variable or, for a nested pattern, to a temporary that is destructured in turn.
+/// A class pattern stores the value in a temporary and then binds each field
+/// with a [`SymExprKind::LetIn`][].
+fn lower_pattern<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    bound: BoundPattern<'db>,
+    ty: SymTy<'db>,
+    initializer: SymExpr<'db>,
+    body: SymExpr<'db>,
+) -> SymExpr<'db> {
+    let elements = match bound {
+        BoundPattern::Var(lv) => return let_in(db, span, lv, ty, initializer, body),
+        BoundPattern::Tuple(elements) => elements,
+        BoundPattern::Aggregate { temp, bindings, .. } => {
+            let body = let_fields(db, span, bindings, body);
+            return let_in(db, span, temp, ty, initializer, body);
+        }
+    };
+
+    let mut lvs = vec![];
+    let mut nested = vec![];
+    for (element, ty) in elements {
+        match element {
+            BoundPattern::Var(lv) => lvs.push((lv, ty)),
+            BoundPattern::Tuple(_) => {
+                let temp = SymVariable::new(db, SymGenericKind::Place, None, initializer.span(db));
+                lvs.push((temp, ty));
+                nested.push((temp, ty, element));
+            }
+            BoundPattern::Aggregate { temp, .. } => {
+                lvs.push((temp, ty));
+                nested.push((temp, ty, element));
+            }
+        }
+    }
+
+    let body = nested
+        .into_iter()
+        .rev()
+        .fold(body, |body, (temp, ty, element)| match element {
+            // The element is already stored in the temporary of the class pattern.
+            BoundPattern::Aggregate { bindings, .. } => let_fields(db, span, bindings, body),
+            _ => {
+                let temp_place = SymPlaceExpr::new(db, span, ty, SymPlaceExprKind::Var(temp));
+                lower_pattern(db, span, element, ty, temp_place.give(db), body)
+            }
+        });
+
+    SymExpr::new(
+        db,
+        span,
+        body.ty(db),
+        SymExprKind::LetTuple {
+            lvs,
+            initializer,
+            body,
+        },
+    )
+}
+
+/// Creates `let f0 = e0 in let f1 = e1 in ... body` for the fields bound by a class pattern.
+fn let_fields<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    bindings: Vec<(SymVariable<'db>, SymExpr<'db>)>,
+    body: SymExpr<'db>,
+) -> SymExpr<'db> {
+    bindings.into_iter().rev().fold(body, |body, (lv, value)| {
+        let_in(db, span, lv, value.ty(db), value, body)
+    })
+}
+
+/// Creates `let lv: ty = initializer in body`.
+pub(crate) fn let_in<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    lv: SymVariable<'db>,
+    ty: SymTy<'db>,
+    initializer: SymExpr<'db>,
+    body: SymExpr<'db>,
+) -> SymExpr<'db> {
+    SymExpr::new(
+        db,
+        span,
+        body.ty(db),
+        SymExprKind::LetIn {
+            lv,
+            ty,
+            initializer: Some(initializer),
+            body,
+        },
+    )
+}
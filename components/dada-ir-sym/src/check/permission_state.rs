@@ -0,0 +1,629 @@
+//! Flow-sensitive permission state: which places have been given away,
+//! leased, or referenced at each point in a checked body.
+//!
+//! Giving (or sharing) an owned class value moves it, so the place it was given from
+//! cannot be used again until it is reassigned. This includes passing `x.give` or `x.share`
+//! as the argument of a call. Where the branches of an `if`
+//! meet, a place given away in only some branches is *maybe given*;
+//! using it afterwards is an error that points at the branch that gave it away.
+//!
+//! The same analysis can record the state at each program point, which is
+//! rendered by the `perms` dump stage and exported as a [lease graph](`lease_graph`) for tooling.
+
+use std::fmt::Write;
+
+use dada_ir_ast::{
+    ast::PermissionOp,
+    diagnostic::{Diagnostic, Level},
+    span::Span,
+};
+use dada_util::{Map, Set, ensure_sufficient_stack};
+use serde::Serialize;
+
+use crate::ir::{
+    classes::SymAggregateStyle,
+    exprs::{SymExpr, SymExprKind, SymLoop, SymPlaceExpr, SymPlaceExprKind},
+    functions::SymFunction,
+    subst::Subst,
+    types::{SymGenericTerm, SymPermKind, SymPlace, SymTy, SymTyKind},
+    variables::SymVariable,
+};
+
+use super::inference::InferredValue;
+
+/// What is known about a place at some point in the body.
+/// Places that are not mentioned own their value (or hold whatever permission they were given).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum PlaceState<'db> {
+    /// The value was given away on every path to this point.
+    Given(Give<'db>),
+
+    /// The value was given away on some, but not all, paths to this point.
+    MaybeGiven(Give<'db>),
+
+    /// The place was leased to the variable by the `x.mut` at `span`, on some path to this point.
+    Leased(SymVariable<'db>, Span<'db>),
+
+    /// The place is referenced by the variable through the `x.ref` at `span`,
+    /// on some path to this point.
+    Referenced(SymVariable<'db>, Span<'db>),
+}
+
+/// Where a value was given away.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Give<'db> {
+    /// The `x.give` or `x.share` expression.
+    span: Span<'db>,
+
+    /// The function the value was given to, if it was the argument of a call.
+    callee: Option<SymFunction<'db>>,
+}
+
+impl<'db> PlaceState<'db> {
+    /// True if `self` and `other` describe the same fact about a place (on different paths),
+    /// so that they are combined where the paths meet.
+    fn same_fact(self, other: Self) -> bool {
+        match (self, other) {
+            (
+                PlaceState::Given(_) | PlaceState::MaybeGiven(_),
+                PlaceState::Given(_) | PlaceState::MaybeGiven(_),
+            ) => true,
+            (PlaceState::Leased(a, _), PlaceState::Leased(b, _))
+            | (PlaceState::Referenced(a, _), PlaceState::Referenced(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The state of each place at some point in the body, in the order the facts were recorded,
+/// or `None` if no path reaches it (e.g., after a `return`).
+/// A place may be referenced by several variables at once.
+type State<'db> = Option<Vec<(SymPlace<'db>, PlaceState<'db>)>>;
+
+/// The state after a give, lease, reference, assignment, or join.
+struct ProgramPoint<'db> {
+    span: Span<'db>,
+    event: String,
+    state: State<'db>,
+}
+
+/// Reports uses of places whose value was given away on some path to the use.
+/// Only the first such use of each place is reported.
+pub(crate) fn check_permission_state<'db>(
+    db: &'db dyn crate::Db,
+    body: SymExpr<'db>,
+    inferred_values: &[InferredValue<'db>],
+) {
+    let mut analysis = PermissionState::new(db, inferred_values, None);
+    analysis.expr(body, &mut Some(vec![]));
+}
+
+/// Renders the permission state after each give, lease, reference, assignment, and join
+/// in `body`, one line per program point, as `line:column: event => state`.
+/// For debugging the compiler.
+pub(crate) fn dump_permission_state<'db>(
+    db: &'db dyn crate::Db,
+    body: SymExpr<'db>,
+    inferred_values: &[InferredValue<'db>],
+) -> String {
+    let mut output = String::new();
+    for point in program_points(db, body, inferred_values) {
+        let (line, column) = line_column(db, point.span);
+        let state = match &point.state {
+            None => "unreachable".to_string(),
+            Some(state) if state.is_empty() => "-".to_string(),
+            Some(state) => state
+                .iter()
+                .map(|&(place, s)| match s {
+                    PlaceState::Given(give) => format!("{place}: given{}", to_callee(db, give)),
+                    PlaceState::MaybeGiven(give) => {
+                        format!("{place}: maybe given{}", to_callee(db, give))
+                    }
+                    PlaceState::Leased(var, _) => format!("{place}: leased to {var}"),
+                    PlaceState::Referenced(var, _) => format!("{place}: referenced by {var}"),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        writeln!(output, "{line}:{column}: {} => {state}", point.event).unwrap();
+    }
+    output
+}
+
+/// The places of `body` and the leases and references between them at each program point,
+/// serialized as JSON. Meant for tools that visualize the permission model;
+/// see [`LeaseGraphPoint`] for the format.
+pub(crate) fn lease_graph<'db>(
+    db: &'db dyn crate::Db,
+    body: SymExpr<'db>,
+    inferred_values: &[InferredValue<'db>],
+) -> String {
+    let points: Vec<LeaseGraphPoint> = program_points(db, body, inferred_values)
+        .into_iter()
+        .map(|point| {
+            let (line, column) = line_column(db, point.span);
+            let mut places: Vec<LeaseGraphPlace> = vec![];
+            let mut edges = vec![];
+            let mut node = |name: String, given: Option<&'static str>| match places
+                .iter_mut()
+                .find(|place| place.name == name)
+            {
+                Some(place) => place.given = given.or(place.given),
+                None => places.push(LeaseGraphPlace { name, given }),
+            };
+            for &(place, s) in point.state.iter().flatten() {
+                match s {
+                    PlaceState::Given(_) => node(place.to_string(), Some("yes")),
+                    PlaceState::MaybeGiven(_) => node(place.to_string(), Some("maybe")),
+                    PlaceState::Leased(var, _) | PlaceState::Referenced(var, _) => {
+                        node(place.to_string(), None);
+                        node(var.to_string(), None);
+                        edges.push(LeaseGraphEdge {
+                            lessor: place.to_string(),
+                            lessee: var.to_string(),
+                            kind: match s {
+                                PlaceState::Leased(..) => "mut",
+                                _ => "ref",
+                            },
+                        });
+                    }
+                }
+            }
+            LeaseGraphPoint {
+                line,
+                column,
+                event: point.event,
+                reachable: point.state.is_some(),
+                places,
+                edges,
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&points).unwrap()
+}
+
+/// One program point of a [lease graph](`lease_graph`).
+#[derive(Serialize)]
+struct LeaseGraphPoint {
+    /// One-based line and column of the expression that produced the state.
+    line: u32,
+    column: u32,
+
+    /// What happened, e.g., `x.mut` or `join`.
+    event: String,
+
+    /// False if no path reaches this point.
+    reachable: bool,
+
+    /// The places that are given away, leased, or referenced, and the variables holding the loans.
+    places: Vec<LeaseGraphPlace>,
+
+    /// The leases and references between places.
+    edges: Vec<LeaseGraphEdge>,
+}
+
+#[derive(Serialize)]
+struct LeaseGraphPlace {
+    name: String,
+
+    /// `"yes"` or `"maybe"` if the value was given away on every or some path, else `null`.
+    given: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct LeaseGraphEdge {
+    lessor: String,
+    lessee: String,
+
+    /// `"mut"` for a lease, `"ref"` for a reference.
+    kind: &'static str,
+}
+
+/// Runs the analysis over `body`, recording the state at each program point.
+fn program_points<'db>(
+    db: &'db dyn crate::Db,
+    body: SymExpr<'db>,
+    inferred_values: &[InferredValue<'db>],
+) -> Vec<ProgramPoint<'db>> {
+    let mut analysis = PermissionState::new(db, inferred_values, Some(vec![]));
+    analysis.expr(body, &mut Some(vec![]));
+    analysis.trace.unwrap()
+}
+
+/// ` to f` if `give` passed the value to the function `f`, else the empty string.
+fn to_callee<'db>(db: &'db dyn crate::Db, give: Give<'db>) -> String {
+    match give.callee {
+        Some(callee) => format!(" to {}", callee.name(db)),
+        None => String::new(),
+    }
+}
+
+/// One-based line and column where `span` starts.
+fn line_column<'db>(db: &'db dyn crate::Db, span: Span<'db>) -> (u32, u32) {
+    let span = span.absolute_span(db);
+    let (line, column) = span.source_file.line_col(db, span.start);
+    (line.as_u32() + 1, column.as_u32() + 1)
+}
+
+struct PermissionState<'a, 'db> {
+    db: &'db dyn crate::Db,
+
+    /// Values of the inference variables in the types of the body.
+    inferred_values: &'a [InferredValue<'db>],
+
+    /// When recording, the program points so far. Errors are only reported when not recording.
+    trace: Option<Vec<ProgramPoint<'db>>>,
+
+    /// Places for which we have already reported a use.
+    reported: Set<SymPlace<'db>>,
+
+    /// Temporaries initialized by giving away a place, such as the arguments of calls.
+    given_to_temps: Map<SymVariable<'db>, SymPlace<'db>>,
+
+    /// For each loop being analyzed, the join of the states at the `break`s that exit it.
+    breaks: Map<SymLoop<'db>, State<'db>>,
+}
+
+impl<'a, 'db> PermissionState<'a, 'db> {
+    fn new(
+        db: &'db dyn crate::Db,
+        inferred_values: &'a [InferredValue<'db>],
+        trace: Option<Vec<ProgramPoint<'db>>>,
+    ) -> Self {
+        Self {
+            db,
+            inferred_values,
+            trace,
+            reported: Default::default(),
+            given_to_temps: Default::default(),
+            breaks: Default::default(),
+        }
+    }
+
+    /// Updates `state` to reflect the state after evaluating `expr`.
+    fn expr(&mut self, expr: SymExpr<'db>, state: &mut State<'db>) {
+        let db = self.db;
+        ensure_sufficient_stack(|| match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+                self.expr(*lhs, state);
+                self.expr(*rhs, state);
+            }
+            SymExprKind::Tuple(exprs)
+            | SymExprKind::Aggregate {
+                ty: _,
+                fields: exprs,
+            } => {
+                for &expr in exprs {
+                    self.expr(expr, state);
+                }
+            }
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                if let Some(initializer) = initializer {
+                    self.expr(*initializer, state);
+                    self.lend(*lv, *initializer, state);
+
+                    // Remember the place given to `lv`, in case it is the argument of a call.
+                    if let Some(given) = state.iter().flatten().find_map(|&(p, s)| match s {
+                        PlaceState::Given(give) if give.span == initializer.span(db) => Some(p),
+                        _ => None,
+                    }) {
+                        self.given_to_temps.insert(*lv, given);
+                    }
+                }
+                self.expr(*body, state);
+            }
+            SymExprKind::LetTuple {
+                lvs: _,
+                initializer,
+                body,
+            } => {
+                self.expr(*initializer, state);
+                self.expr(*body, state);
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword: _,
+            } => self.expr(*future, state),
+            SymExprKind::Assign { place, value } => {
+                self.expr(*value, state);
+
+                // Assigning to a field of `x` uses `x`.
+                if let SymPlaceExprKind::Field(owner, _) = *place.kind(db) {
+                    self.use_place(owner, state);
+                }
+
+                // The place (and everything within it) holds a value again.
+                let assigned = place.into_sym_place(db);
+                if let Some(state) = state {
+                    state.retain(|&(p, _)| !assigned.is_prefix_of(db, p));
+                }
+                if let SymPlaceExprKind::Var(var) = *place.kind(db) {
+                    self.lend(var, *value, state);
+                }
+                self.trace(place.span(db), format!("{assigned} = ..."), state);
+            }
+            SymExprKind::PermissionOp(op, place) => {
+                self.use_place(*place, state);
+                if let PermissionOp::Give | PermissionOp::Share = op
+                    && self.moves(place.ty(db))
+                {
+                    let given = place.into_sym_place(db);
+                    let give = Give {
+                        span: expr.span(db),
+                        callee: None,
+                    };
+                    if let Some(state) = state {
+                        state.retain(|&(p, _)| !given.is_prefix_of(db, p));
+                        state.push((given, PlaceState::Given(give)));
+                    }
+                    let event = match op {
+                        PermissionOp::Share => format!("{given}.share"),
+                        _ => format!("{given}.give"),
+                    };
+                    self.trace(expr.span(db), event, state);
+                }
+            }
+            SymExprKind::IsSome(place) => self.use_place(*place, state),
+            SymExprKind::Return(value) => {
+                self.expr(*value, state);
+                *state = None;
+            }
+            SymExprKind::Loop { sym_loop, body } => {
+                // Analyze the body twice, so that a give at the end of one iteration
+                // is seen by a use at the start of the next. Only the second pass is recorded.
+                let trace_len = self.trace.as_ref().map(|trace| trace.len());
+                let mut in_body = state.clone();
+                self.expr(*body, &mut in_body);
+                if let (Some(trace), Some(len)) = (&mut self.trace, trace_len) {
+                    trace.truncate(len);
+                }
+                let mut in_body = join(state.clone(), in_body);
+                self.expr(*body, &mut in_body);
+                *state = self.breaks.remove(sym_loop).flatten();
+                self.trace(expr.span(db), "after loop".to_string(), state);
+            }
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => {
+                self.expr(*with_value, state);
+                let after_loop = self.breaks.remove(from_loop).flatten();
+                self.breaks
+                    .insert(*from_loop, join(after_loop, state.take()));
+            }
+            SymExprKind::Continue { to_loop: _ } => {
+                *state = None;
+            }
+            SymExprKind::Not {
+                operand,
+                op_span: _,
+            }
+            | SymExprKind::Cast(operand) => self.expr(*operand, state),
+            SymExprKind::Match { arms } => {
+                // `fallthrough` is the state when none of the arms so far has been taken.
+                let mut fallthrough = state.clone();
+                let mut after_arms = None;
+                for arm in arms {
+                    if let Some(condition) = arm.condition {
+                        self.expr(condition, &mut fallthrough);
+                    }
+                    let mut in_arm = fallthrough.clone();
+                    self.expr(arm.body, &mut in_arm);
+                    after_arms = join(after_arms, in_arm);
+                    if arm.condition.is_none() {
+                        fallthrough = None;
+                        break;
+                    }
+                }
+                *state = join(after_arms, fallthrough);
+                self.trace(expr.span(db), "join".to_string(), state);
+            }
+
+            // The arguments of a call are temporaries, initialized by an enclosing `LetIn`.
+            // Places given to those temporaries were given to the callee.
+            SymExprKind::Call {
+                function,
+                substitution: _,
+                arg_temps,
+            } => {
+                let Some(state) = state else {
+                    return;
+                };
+                for arg_temp in arg_temps {
+                    let Some(&given) = self.given_to_temps.get(arg_temp) else {
+                        continue;
+                    };
+                    for (p, s) in state.iter_mut() {
+                        if let PlaceState::Given(give) = s
+                            && *p == given
+                        {
+                            give.callee = Some(*function);
+                        }
+                    }
+                }
+            }
+            SymExprKind::Drop(_)
+            | SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::Error(_) => {}
+        })
+    }
+
+    /// If `value` is `x.mut` or `x.ref`, records that `x` is leased to (or referenced by) `var`.
+    ///
+    /// A new lease or reference ends any lease of an overlapping place, and a new lease
+    /// also ends any reference to one.
+    fn lend(&mut self, var: SymVariable<'db>, value: SymExpr<'db>, state: &mut State<'db>) {
+        let db = self.db;
+        let SymExprKind::PermissionOp(op, place) = *value.kind(db) else {
+            return;
+        };
+        let lent = place.into_sym_place(db);
+        let span = value.span(db);
+        let (fact, event) = match op {
+            PermissionOp::Mutate => (PlaceState::Leased(var, span), format!("{lent}.mut")),
+            PermissionOp::Reference => (PlaceState::Referenced(var, span), format!("{lent}.ref")),
+            PermissionOp::Give | PermissionOp::Share => return,
+        };
+        if let Some(state) = state {
+            state.retain(|&(p, s)| {
+                let overlaps = p.is_prefix_of(db, lent) || lent.is_prefix_of(db, p);
+                match s {
+                    PlaceState::Leased(..) => !overlaps,
+                    PlaceState::Referenced(..) => !(overlaps && op == PermissionOp::Mutate),
+                    PlaceState::Given(_) | PlaceState::MaybeGiven(_) => true,
+                }
+            });
+            state.push((lent, fact));
+        }
+        self.trace(span, event, state);
+    }
+
+    /// True if giving a value of type `ty` moves it, leaving the place it was given from empty.
+    /// This is the case for owned class values (and tuples containing them).
+    fn moves(&self, ty: SymTy<'db>) -> bool {
+        let db = self.db;
+        let ty = ty.resolve_infer_var(db, &mut vec![], |infer| {
+            self.inferred_values
+                .get(infer.as_usize())
+                .map(|inferred| inferred.value)
+        });
+        moves(db, ty)
+    }
+
+    /// Reports `place` if it overlaps a place that was given away on some path to this point.
+    fn use_place(&mut self, place: SymPlaceExpr<'db>, state: &State<'db>) {
+        let db = self.db;
+        let Some(state) = state else {
+            return;
+        };
+        let used = place.into_sym_place(db);
+        let Some((given, (give, always))) = state.iter().find_map(|&(p, s)| {
+            let given = match s {
+                PlaceState::Given(give) => (give, true),
+                PlaceState::MaybeGiven(give) => (give, false),
+                PlaceState::Leased(..) | PlaceState::Referenced(..) => return None,
+            };
+            (p.is_prefix_of(db, used) || used.is_prefix_of(db, p)).then_some((p, given))
+        }) else {
+            return;
+        };
+        if self.trace.is_some() || !self.reported.insert(used) {
+            return;
+        }
+
+        let span = place.span(db);
+        let given_span = give.span;
+        let given_here = match give.callee {
+            Some(callee) => format!("value was given to `{}` here", callee.name(db)),
+            None => "value was given here".to_string(),
+        };
+        let diagnostic = if always {
+            Diagnostic::error(
+                db,
+                span,
+                format!("`{used}` is used after `{given}` was given away"),
+            )
+            .label(db, Level::Error, span, format!("`{used}` is used here"))
+            .label(db, Level::Info, given_span, given_here)
+        } else {
+            Diagnostic::error(
+                db,
+                span,
+                format!("`{used}` is used after `{given}` may have been given away"),
+            )
+            .label(
+                db,
+                Level::Error,
+                span,
+                format!("`{used}` is used here, but some paths to this point give away `{given}`"),
+            )
+            .label(
+                db,
+                Level::Info,
+                given_span,
+                format!("{given_here}, on one of those paths"),
+            )
+        };
+        diagnostic
+            .label(
+                db,
+                Level::Help,
+                given_span,
+                format!(
+                    "to keep using `{given}`, reference it with `.ref` instead of giving it away"
+                ),
+            )
+            .report(db);
+    }
+
+    /// When recording, records the state after the event `what` at `span`.
+    fn trace(&mut self, span: Span<'db>, what: String, state: &State<'db>) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(ProgramPoint {
+                span,
+                event: what,
+                state: state.clone(),
+            });
+        }
+    }
+}
+
+/// True if giving a value of type `ty` (which contains no inference variables) moves it.
+fn moves<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> bool {
+    match ty.kind(db) {
+        SymTyKind::Perm(perm, ty) => matches!(perm.kind(db), SymPermKind::My) && moves(db, *ty),
+        SymTyKind::Named(name, args) => match name.style(db) {
+            SymAggregateStyle::Class => true,
+            SymAggregateStyle::Struct => args.iter().any(|&arg| match arg {
+                SymGenericTerm::Type(arg) => moves(db, arg),
+                _ => false,
+            }),
+        },
+        SymTyKind::Var(_) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => false,
+    }
+}
+
+/// The state where two paths meet.
+///
+/// A place given away on both paths stays given; one given away on only one path
+/// (or maybe given on either) is maybe given. A lease or reference on either path is kept.
+fn join<'db>(a: State<'db>, b: State<'db>) -> State<'db> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(a), Some(b)) => {
+            let find =
+                |state: &[(SymPlace<'db>, PlaceState<'db>)], place, fact: PlaceState<'db>| {
+                    state
+                        .iter()
+                        .find(|&&(p, s)| p == place && s.same_fact(fact))
+                        .map(|&(_, s)| s)
+                };
+            let mut joined = vec![];
+            for &(place, state_a) in &a {
+                joined.push((place, join_place(state_a, find(&b, place, state_a))));
+            }
+            for &(place, state_b) in &b {
+                if find(&a, place, state_b).is_none() {
+                    joined.push((place, join_place(state_b, None)));
+                }
+            }
+            Some(joined)
+        }
+    }
+}
+
+/// Combines the facts `a` and `b` (if present on the other path) about a place.
+fn join_place<'db>(a: PlaceState<'db>, b: Option<PlaceState<'db>>) -> PlaceState<'db> {
+    match (a, b) {
+        (PlaceState::Given(give), Some(PlaceState::Given(_))) => PlaceState::Given(give),
+        (PlaceState::Given(give) | PlaceState::MaybeGiven(give), _) => PlaceState::MaybeGiven(give),
+        (PlaceState::Leased(..) | PlaceState::Referenced(..), _) => a,
+    }
+}
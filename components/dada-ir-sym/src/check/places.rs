@@ -48,12 +48,7 @@ fn field_ty<'db>(
 
         RedTy::Named(_name, generics) => {
             // FIXME: eventually we probably want to upcast here
-            let field_ty = sym_field.checked_field_ty(db);
-            let field_ty = field_ty
-                .substitute(env.db(), &generics)
-                .substitute(env.db(), &[SymGenericTerm::Place(owner_place)]);
-
-            owner_perm.apply_to(db, field_ty)
+            field_ty_with_perm(db, sym_field, &generics, owner_place, owner_perm)
         }
 
         RedTy::Infer(infer) => {
@@ -70,3 +65,25 @@ fn field_ty<'db>(
         }
     }
 }
+
+/// The type of `field` when accessed through an owner of type `owner_perm N[generics]`
+/// stored in `owner_place`: the declared type of the field, with the generics of `N`
+/// and `self` (i.e., `owner_place`) substituted, and with `owner_perm` applied.
+///
+/// Since the owner's permission is applied on top of whatever permission the field declares,
+/// permissions compose through chains of field accesses. If `x: ref[y] A` and `A` has a
+/// field `f: mut[self.g] F`, then `x.f` has type `ref[y] mut[x.g] F`; accessing a field
+/// of `x.f` in turn applies `ref[y] mut[x.g]` to that field's type, and so on.
+pub(crate) fn field_ty_with_perm<'db>(
+    db: &'db dyn crate::Db,
+    field: SymField<'db>,
+    generics: &[SymGenericTerm<'db>],
+    owner_place: SymPlace<'db>,
+    owner_perm: SymPerm<'db>,
+) -> SymTy<'db> {
+    let field_ty = field
+        .checked_field_ty(db)
+        .substitute(db, generics)
+        .substitute(db, &[owner_place]);
+    owner_perm.apply_to(db, field_ty)
+}
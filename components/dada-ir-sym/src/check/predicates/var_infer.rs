@@ -97,10 +97,20 @@ pub async fn require_infer_is<'db>(
 
             let mut bounds = env.term_bounds(perm, infer);
             let mut observed_lower_bound = false;
-            while let Some((direction, bound)) = bounds.next(env).await {
+            while let Some((direction, bound, bound_or_else)) = bounds.next(env).await {
                 env.log("observed bound", &[&infer, &direction, &bound, &predicate]);
 
-                require_term_is(env, bound, predicate, or_else).await?;
+                // If the bound is not `predicate`, the conflict is between this requirement
+                // and whatever produced the bound, so report both.
+                require_term_is(
+                    env,
+                    bound,
+                    predicate,
+                    &or_else.map_because(move |_| {
+                        Because::InferredIsnt(direction, bound, predicate, bound_or_else.clone())
+                    }),
+                )
+                .await?;
 
                 match direction {
                     Direction::FromBelow => observed_lower_bound = true,
@@ -177,7 +187,7 @@ async fn exists_bounding_term<'db>(
     env.indent("exists_bounding_term", &[&perm, &infer], async |env| {
         let db = env.db();
         let mut bounds = env.term_bounds(perm, infer);
-        while let Some((direction_bound, bound)) = bounds.next(env).await {
+        while let Some((direction_bound, bound, _)) = bounds.next(env).await {
             if let Some(direction) = direction
                 && direction_bound != direction
             {
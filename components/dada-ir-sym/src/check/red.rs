@@ -1,9 +1,16 @@
 //! "Chains" are a canonicalized form of types/permissions.
 //! They can only be produced after inference is complete as they require enumerating the bounds of inference variables.
 //! They are used in borrow checking and for producing the final version of each inference variable.
+//!
+//! Chains are interned, including the transient ones created while solving.
+//! Interning makes comparing and hashing a chain cheap, which the sub-chain cache
+//! (see `Runtime::memoize_sub_chain`) and the deduplication in [`RedPerm::normalized`] rely on.
+//! Allocating transient chains in an arena and interning only the final results would
+//! need a second representation with its own equality; we have not measured interning
+//! to be a bottleneck that would justify that.
 
 use dada_ir_ast::diagnostic::{Err, Errors, Reported};
-use dada_util::SalsaSerialize;
+use dada_util::{SalsaSerialize, Set};
 use salsa::Update;
 use serde::Serialize;
 
@@ -30,6 +37,17 @@ pub(crate) struct RedPerm<'db> {
 }
 
 impl<'db> RedPerm<'db> {
+    /// Creates a permission from `chains`, dropping duplicate chains
+    /// (which add nothing to the union) while preserving the order of the rest.
+    pub fn normalized(db: &'db dyn crate::Db, chains: Vec<RedChain<'db>>) -> Self {
+        let mut seen = Set::default();
+        let chains = chains
+            .into_iter()
+            .filter(|&chain| seen.insert(chain))
+            .collect::<Vec<_>>();
+        RedPerm::new(db, chains)
+    }
+
     /// Returns the fallback bound for a permission (`my`)
     pub fn fallback(db: &'db dyn crate::Db) -> Self {
         RedPerm::new(db, vec![RedChain::new(db, vec![])])
@@ -74,6 +92,36 @@ pub(crate) struct RedChain<'db> {
 }
 
 impl<'db> RedChain<'db> {
+    /// Creates a chain from `links` in normal form, so that equivalent chains are interned
+    /// as the same value and are displayed as simply as possible:
+    ///
+    /// * An `our` or `ref[p]` link discards the links before it,
+    ///   since copying data yields the same permission however the data was reached.
+    ///   So `our our` becomes `our` and `mut[p] ref[q]` becomes `ref[q]`.
+    ///   (This is the same rule [`to_red_linkvecs`](`super::to_red::ToRedLinkVecs`)
+    ///   applies when concatenating links, restricted to links that are copy in every environment.)
+    ///
+    ///   Discarded `mut` links are dropped even if they are [`Live`]. That keeps liveness correct:
+    ///   in `mut[p] ref[q]`, the data that `p` holds is itself only shared from `q`,
+    ///   so nothing done with `p` later can mutate or drop it. Only `q` matters,
+    ///   and the `ref[q]` link records whether `q` is live (`our` data depends on no place at all).
+    ///   `to_red_linkvecs` drops the same links.
+    /// * An error link absorbs the rest of the chain.
+    pub fn normalized(db: &'db dyn crate::Db, links: Vec<RedLink<'db>>) -> Self {
+        if let Some(&reported) = links.iter().find_map(|link| match link {
+            RedLink::Err(reported) => Some(reported),
+            _ => None,
+        }) {
+            return RedChain::err(db, reported);
+        }
+
+        let start = links
+            .iter()
+            .rposition(|link| matches!(link, RedLink::Our | RedLink::Ref(..)))
+            .unwrap_or(0);
+        RedChain::new(db, links[start..].to_vec())
+    }
+
     pub fn our(db: &'db dyn crate::Db) -> Self {
         RedChain::new(db, [RedLink::Our])
     }
@@ -88,7 +136,7 @@ impl<'db> RedChain<'db> {
         }
     }
 
-    fn to_sym_perm(self, db: &'db dyn crate::Db) -> SymPerm<'db> {
+    pub fn to_sym_perm(self, db: &'db dyn crate::Db) -> SymPerm<'db> {
         self.links(db)
             .iter()
             .map(|&link| link.to_sym_perm(db))
@@ -249,3 +297,150 @@ impl<'db> RedTy<'db> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dada_ir_ast::{
+        diagnostic::Reported,
+        inputs::SourceFile,
+        span::{AbsoluteOffset, AbsoluteSpan},
+    };
+    use url::Url;
+
+    use super::{Live, RedChain, RedLink, RedPerm};
+    use crate::{
+        ir::types::{SymPlace, SymPlaceKind},
+        test_db::TestDb,
+    };
+
+    #[salsa::input]
+    struct TestInput {}
+
+    /// Two distinct places, `p` and `q`.
+    /// Places are tracked structs, so they have to be created within a tracked function.
+    #[salsa::tracked]
+    fn places<'db>(db: &'db dyn crate::Db, _input: TestInput) -> (SymPlace<'db>, SymPlace<'db>) {
+        let p = SymPlace::erased(db);
+        (p, SymPlace::new(db, SymPlaceKind::Index(p)))
+    }
+
+    #[test]
+    fn our_is_idempotent() {
+        let db = TestDb::new();
+        let (p, _) = places(&db, TestInput::new(&db));
+
+        // `our our` is `our`
+        assert_eq!(
+            RedChain::normalized(&db, vec![RedLink::Our, RedLink::Our]),
+            RedChain::our(&db),
+        );
+
+        // `ref[p] our` is `our`
+        assert_eq!(
+            RedChain::normalized(&db, vec![RedLink::Ref(Live(true), p), RedLink::Our]),
+            RedChain::our(&db),
+        );
+    }
+
+    #[test]
+    fn copy_links_drop_redundant_links() {
+        let db = TestDb::new();
+        let (p, q) = places(&db, TestInput::new(&db));
+        let ref_q = RedLink::Ref(Live(false), q);
+
+        // `mut[p] ref[q]` is `ref[q]`, whether or not `p` is live
+        for live in [Live(false), Live(true)] {
+            assert_eq!(
+                RedChain::normalized(&db, vec![RedLink::Mut(live, p), ref_q]),
+                RedChain::new(&db, vec![ref_q]),
+            );
+        }
+
+        // `ref[p] mut[p] ref[q]` is `ref[q]`
+        assert_eq!(
+            RedChain::normalized(
+                &db,
+                vec![
+                    RedLink::Ref(Live(false), p),
+                    RedLink::Mut(Live(false), p),
+                    ref_q
+                ],
+            ),
+            RedChain::new(&db, vec![ref_q]),
+        );
+    }
+
+    #[test]
+    fn live_links_are_kept() {
+        let db = TestDb::new();
+        let (p, q) = places(&db, TestInput::new(&db));
+        let ref_p = RedLink::Ref(Live(true), p);
+        let mut_q = RedLink::Mut(Live(true), q);
+
+        for links in [
+            vec![],
+            vec![ref_p],
+            vec![RedLink::Our, mut_q],
+            vec![ref_p, mut_q],
+            vec![mut_q, RedLink::Mut(Live(false), p)],
+        ] {
+            let chain = RedChain::normalized(&db, links.clone());
+            assert_eq!(chain.links(&db), &links);
+
+            // Normalizing an already normalized chain changes nothing.
+            assert_eq!(RedChain::normalized(&db, chain.links(&db).clone()), chain);
+        }
+    }
+
+    #[test]
+    fn error_links_absorb_the_chain() {
+        let db = TestDb::new();
+        let (p, _) = places(&db, TestInput::new(&db));
+        let reported = reported(&db);
+
+        assert_eq!(
+            RedChain::normalized(
+                &db,
+                vec![
+                    RedLink::Mut(Live(false), p),
+                    RedLink::Err(reported),
+                    RedLink::Our
+                ],
+            ),
+            RedChain::new(&db, vec![RedLink::Err(reported)]),
+        );
+    }
+
+    #[test]
+    fn duplicate_chains_are_dropped() {
+        let db = TestDb::new();
+        let (p, q) = places(&db, TestInput::new(&db));
+        let ref_p = RedChain::new(&db, vec![RedLink::Ref(Live(false), p)]);
+        let mut_q = RedChain::new(&db, vec![RedLink::Mut(Live(false), q)]);
+
+        assert_eq!(
+            RedPerm::normalized(&db, vec![ref_p, mut_q, ref_p, mut_q]),
+            RedPerm::new(&db, vec![ref_p, mut_q]),
+        );
+
+        // Chains that become equal once normalized are deduplicated too.
+        let our_ref_p = RedChain::normalized(&db, vec![RedLink::Our, RedLink::Ref(Live(false), p)]);
+        assert_eq!(
+            RedPerm::normalized(&db, vec![ref_p, our_ref_p]).chains(&db),
+            &vec![ref_p],
+        );
+    }
+
+    fn reported(db: &TestDb) -> Reported {
+        let source_file = SourceFile::new(
+            db,
+            Url::parse("file:///test.dada").unwrap(),
+            Ok(String::new()),
+        );
+        Reported(AbsoluteSpan {
+            source_file,
+            start: AbsoluteOffset::ZERO,
+            end: AbsoluteOffset::ZERO,
+        })
+    }
+}
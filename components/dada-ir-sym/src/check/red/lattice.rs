@@ -51,7 +51,7 @@ pub fn lub_perms<'db>(env: &Env<'db>, perm1: RedPerm<'db>, perm2: RedPerm<'db>)
         }
     }
 
-    RedPerm::new(db, candidates)
+    RedPerm::normalized(db, candidates)
 }
 
 /// The **greatest lower bound** (GLB) of two permissions `(perm1, perm2)`, if it exists.
@@ -136,7 +136,7 @@ impl<'db> GreatestLowerBound<'db> for RedPerm<'db> {
         if candidates.is_empty() {
             Err(NoGlb)
         } else {
-            Ok(RedPerm::new(db, candidates))
+            Ok(RedPerm::normalized(db, candidates))
         }
     }
 }
@@ -200,7 +200,7 @@ impl<'db> GreatestLowerBound<'db> for RedChain<'db> {
             }
         }
 
-        Ok(RedChain::new(db, links_glb))
+        Ok(RedChain::normalized(db, links_glb))
     }
 }
 
@@ -10,7 +10,10 @@ pub fn chain_sub_chain<'db>(
     upper_chain: RedChain<'db>,
 ) -> Errors<bool> {
     let db = env.db();
-    links_sub_links(env, lower_chain.links(db), upper_chain.links(db))
+    env.runtime()
+        .memoize_sub_chain(env.assumptions(), lower_chain, upper_chain, || {
+            links_sub_links(env, lower_chain.links(db), upper_chain.links(db))
+        })
 }
 
 fn links_sub_links<'db>(
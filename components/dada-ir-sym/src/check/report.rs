@@ -10,6 +10,7 @@ use serde::Serialize;
 use crate::{
     check::{debug::export, env::Env, predicates::Predicate},
     ir::{
+        classes::SymAggregate,
         exprs::{SymExpr, SymPlaceExpr},
         generics::SymWhereClause,
         indices::InferVarIndex,
@@ -20,7 +21,7 @@ use crate::{
 
 use super::{
     inference::{Direction, InferVarKind},
-    red::{RedPerm, RedTy},
+    red::{RedChain, RedLink, RedPerm, RedTy},
     to_red::RedTyExt,
 };
 
@@ -184,6 +185,10 @@ pub enum Because<'db> {
     /// Leasing from a copy place yields a copy permission (which is not desired here)
     LeasedFromCopyIsCopy(Vec<SymPlace<'db>>),
 
+    /// No chain in the upper permission can be reached from this chain of the lower permission,
+    /// e.g., `ref[x]` where `mut[x]` is required.
+    PermChainMismatch(RedChain<'db>, RedPerm<'db>),
+
     /// Universal mismatch
     UniversalMismatch(SymVariable<'db>, SymVariable<'db>),
 
@@ -198,6 +203,24 @@ pub enum Because<'db> {
     /// this lower bound "or else" the given error would occur.
     InferredLowerBound(RedTy<'db>, ArcOrElse<'db>),
 
+    /// Inference determined that the variable must be assignable from (or to) this term
+    /// "or else" the given error would occur.
+    InferredIs(Direction, SymGenericTerm<'db>, ArcOrElse<'db>),
+
+    /// Inference determined that the variable must be assignable from the first term
+    /// and assignable to the second, but the first is not a subtype of the second.
+    /// Each term comes with the error that would occur without it.
+    InferredConflict(
+        SymGenericTerm<'db>,
+        ArcOrElse<'db>,
+        SymGenericTerm<'db>,
+        ArcOrElse<'db>,
+    ),
+
+    /// Inference determined that the variable must be assignable from (or to) this term,
+    /// which does not meet the given predicate, "or else" the given error would occur.
+    InferredIsnt(Direction, SymGenericTerm<'db>, Predicate, ArcOrElse<'db>),
+
     /// The given inference variable needs more constraints
     UnconstrainedInfer(InferVarIndex),
 }
@@ -255,40 +278,66 @@ impl<'db> Because<'db> {
                 span,
                 format!("I cannot know whether `{v1}` and `{v2}` are the same"),
             )),
+            Because::PermChainMismatch(lower, upper) => {
+                Some(chain_comparison(env, span, *lower, *upper))
+            }
             Because::NameMismatch(n1, n2) => Some(Diagnostic::info(
                 db,
                 span,
                 format!("`{n1}` and `{n2}` are distinct types"),
             )),
-            Because::InferredPermBound(direction, red_perm, or_else) => {
-                let or_else_diagnostic = or_else.or_else(env, Because::JustSo);
-                Some(
-                            Diagnostic::info(
-                                db,
-                                span,
-                                format!(
-                                    "I inferred that the perm must be {assignable_from_or_to} `{bound}` or else this error will occur",
-                                    assignable_from_or_to = match direction {
-                                        Direction::FromBelow => "assignable from",
-                                        Direction::FromAbove => "assignable to",
-                                    },
-                                    bound = format!("{red_perm:?}"), // FIXME
-                                ),
-                            )
-                            .child(or_else_diagnostic),
-                        )
-            }
+            Because::InferredPermBound(direction, red_perm, or_else) => Some(inferred_annotation(
+                env,
+                span,
+                format!(
+                    "I inferred that the perm must be {assignable_from_or_to} `{bound}` or else this error will occur",
+                    assignable_from_or_to = assignable_from_or_to(*direction),
+                    bound = red_perm.to_sym_perm(db),
+                ),
+                or_else,
+            )),
             Because::InferredLowerBound(red_ty, or_else) => {
-                let or_else_diagnostic = or_else.or_else(env, Because::JustSo);
-                Some(Diagnostic::info(
-                                db,
-                                span,
-                                format!(
-                                    "I inferred that the type `{red_ty}` is required because otherwise it would cause this error",
-                                    red_ty = red_ty.display(env),
-                                ),
-                            )
-                            .child(or_else_diagnostic))
+                let message = format!(
+                    "I inferred that the type `{red_ty}` is required because otherwise it would cause this error",
+                    red_ty = red_ty.display(env),
+                );
+                Some(inferred_annotation(env, span, message, or_else))
+            }
+            Because::InferredIs(direction, bound, or_else) => Some(inferred_annotation(
+                env,
+                span,
+                format!(
+                    "I inferred that the {kind} must be {assignable_from_or_to} `{bound}`, or else this error will occur",
+                    kind = term_kind(bound),
+                    assignable_from_or_to = assignable_from_or_to(*direction),
+                ),
+                or_else,
+            )),
+            Because::InferredConflict(lower, lower_or_else, upper, upper_or_else) => {
+                let lower =
+                    Because::InferredIs(Direction::FromBelow, *lower, lower_or_else.clone());
+                let upper =
+                    Because::InferredIs(Direction::FromAbove, *upper, upper_or_else.clone());
+                let mut diagnostic =
+                    Diagnostic::info(db, span, "I inferred bounds that conflict with one another");
+                for because in [lower, upper] {
+                    if let Some(child) = because.to_annotation(env, span) {
+                        diagnostic = diagnostic.child(child);
+                    }
+                }
+                Some(diagnostic)
+            }
+            Because::InferredIsnt(direction, bound, predicate, or_else) => {
+                Some(inferred_annotation(
+                    env,
+                    span,
+                    format!(
+                        "I inferred that the {kind} must be {assignable_from_or_to} `{bound}`, which is not `{predicate}`, or else this error will occur",
+                        kind = term_kind(bound),
+                        assignable_from_or_to = assignable_from_or_to(*direction),
+                    ),
+                    or_else,
+                ))
             }
             Because::UnconstrainedInfer(infer) => Some(Diagnostic::info(
                 db,
@@ -314,6 +363,127 @@ impl<'db> Because<'db> {
     }
 }
 
+/// Annotation explaining a bound that inference recorded "or else" the error `or_else` would occur.
+///
+/// The error is rendered as a child of the annotation, so the diagnostic points both at the
+/// current constraint and at the one that produced the bound. If that error was itself caused
+/// by an inferred bound, its own annotation follows in turn, so the whole chain is shown.
+fn inferred_annotation<'db>(
+    env: &mut Env<'db>,
+    span: Span<'db>,
+    message: String,
+    or_else: &ArcOrElse<'db>,
+) -> Diagnostic {
+    let or_else_diagnostic = or_else.or_else(env, Because::JustSo);
+    Diagnostic::info(env.db(), span, message).child(or_else_diagnostic)
+}
+
+/// Annotation comparing a chain of the lower permission against the upper permission,
+/// both in surface syntax (e.g., `ref[x]` vs `mut[x]`).
+///
+/// We line the chain up against the first upper chain to find the first link that is weaker
+/// than what is required. If that link was created from a place or a permission variable,
+/// a child annotation points at its declaration.
+fn chain_comparison<'db>(
+    env: &mut Env<'db>,
+    span: Span<'db>,
+    lower: RedChain<'db>,
+    upper: RedPerm<'db>,
+) -> Diagnostic {
+    let db = env.db();
+    let lower_links = lower.links(db);
+    let upper_links = upper
+        .chains(db)
+        .first()
+        .map(|c| c.links(db).as_slice())
+        .unwrap_or(&[]);
+
+    let mut diagnostic = Diagnostic::info(
+        db,
+        span,
+        format!(
+            "I cannot use the permission `{lower}` where `{upper}` is required",
+            lower = lower.to_sym_perm(db),
+            upper = upper.to_sym_perm(db),
+        ),
+    );
+
+    let weaker = lower_links
+        .iter()
+        .zip(upper_links.iter().map(Some).chain(std::iter::repeat(None)))
+        .find(|(l, u)| !link_matches(db, l, *u));
+    let Some((&weaker_link, upper_link)) = weaker else {
+        return diagnostic;
+    };
+
+    let weaker_perm = weaker_link.to_sym_perm(db);
+    let reason = match (weaker_link, upper_link) {
+        (RedLink::Ref(..), Some(RedLink::Mut(..))) => Some(format!(
+            "`{weaker_perm}` only permits reading, so it can never become `mut`"
+        )),
+        (RedLink::Our, Some(RedLink::Mut(..))) => Some(format!(
+            "`{weaker_perm}` is shared, so it can never become `mut`"
+        )),
+        (RedLink::Ref(_, place_l), Some(RedLink::Ref(_, place_u)))
+        | (RedLink::Mut(_, place_l), Some(RedLink::Mut(_, place_u))) => Some(format!(
+            "`{weaker_perm}` borrows from `{place_l}`, which is not covered by `{place_u}`"
+        )),
+        (_, None) => Some(format!(
+            "`{weaker_perm}` is not owned, but `{upper}` requires ownership",
+            upper = upper.to_sym_perm(db),
+        )),
+        _ => None,
+    };
+    if let Some(reason) = reason {
+        diagnostic = diagnostic.child(Diagnostic::info(db, span, reason));
+    }
+
+    let origin = match weaker_link {
+        RedLink::Ref(_, place) | RedLink::Mut(_, place) => place.root_var(db).map(|var| {
+            (
+                var,
+                format!("the `{weaker_perm}` permission was created from `{place}`, declared here"),
+            )
+        }),
+        RedLink::Var(var) => Some((var, format!("the permission `{var}` is declared here"))),
+        RedLink::Our | RedLink::Err(_) => None,
+    };
+    if let Some((var, message)) = origin {
+        diagnostic = diagnostic.child(Diagnostic::info(db, var.span(db), message));
+    }
+
+    diagnostic
+}
+
+/// True if the lower link `l` can stand in for the upper link `u` when the rest of the
+/// chains match up (see [`chain_sub_chain`](`super::red::sub::chain_sub_chain`)).
+fn link_matches<'db>(db: &'db dyn crate::Db, l: &RedLink<'db>, u: Option<&RedLink<'db>>) -> bool {
+    match (l, u) {
+        (RedLink::Our, Some(RedLink::Our)) => true,
+        (RedLink::Ref(_, place_l), Some(RedLink::Ref(_, place_u)))
+        | (RedLink::Mut(_, place_l), Some(RedLink::Mut(_, place_u))) => {
+            place_u.is_prefix_of(db, *place_l)
+        }
+        (RedLink::Var(var_l), Some(RedLink::Var(var_u))) => var_l == var_u,
+        (RedLink::Err(_), _) => true,
+        _ => false,
+    }
+}
+
+fn assignable_from_or_to(direction: Direction) -> &'static str {
+    match direction {
+        Direction::FromBelow => "assignable from",
+        Direction::FromAbove => "assignable to",
+    }
+}
+
+fn term_kind(term: &SymGenericTerm<'_>) -> &'static str {
+    match term {
+        SymGenericTerm::Perm(_) => "perm",
+        SymGenericTerm::Type(_) | SymGenericTerm::Place(_) | SymGenericTerm::Error(_) => "type",
+    }
+}
+
 fn anded_list<T>(v: &[T]) -> String
 where
     T: std::fmt::Display,
@@ -333,6 +503,128 @@ where
     s
 }
 
+/// A `spawn` block captures a variable that is neither `my` nor `our`.
+#[derive(Copy, Clone, Debug)]
+pub struct SpawnCaptureError<'db> {
+    use_span: Span<'db>,
+    var: SymVariable<'db>,
+    ty: SymTy<'db>,
+    spawn_span: Span<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> SpawnCaptureError<'db> {
+    #[track_caller]
+    pub fn new(
+        use_span: Span<'db>,
+        var: SymVariable<'db>,
+        ty: SymTy<'db>,
+        spawn_span: Span<'db>,
+    ) -> Self {
+        Self {
+            use_span,
+            var,
+            ty,
+            spawn_span,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for SpawnCaptureError<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        let Self {
+            use_span,
+            var,
+            ty,
+            spawn_span,
+            compiler_location: _,
+        } = *self;
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(
+                db,
+                use_span,
+                format!("`{var}` cannot be captured by a spawned task"),
+            )
+            .label(
+                db,
+                Level::Error,
+                use_span,
+                format!("`{var}` has type `{ty}`, but a spawned task can only capture `my` or `our` values"),
+            )
+            .label(
+                db,
+                Level::Info,
+                spawn_span,
+                "the task may run at the same time as the rest of this function, so leases cannot be shared with it",
+            ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
+/// An arm of an `if` without an `else` produces a value other than `()`.
+#[derive(Copy, Clone, Debug)]
+pub struct IfWithoutElse<'db> {
+    if_span: Span<'db>,
+    body: SymExpr<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> IfWithoutElse<'db> {
+    #[track_caller]
+    pub fn new(if_span: Span<'db>, body: SymExpr<'db>) -> Self {
+        Self {
+            if_span,
+            body,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for IfWithoutElse<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        let body_span = self.body.span(db);
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(db, body_span, "`if` without `else` cannot produce a value")
+                .label(
+                    db,
+                    Level::Error,
+                    body_span,
+                    format!(
+                        "this has type `{ty}`, but there is no `else`, so I expected `()`",
+                        ty = self.body.ty(db),
+                    ),
+                )
+                .label(
+                    db,
+                    Level::Help,
+                    self.if_span,
+                    "add an `else` so that every case produces a value",
+                ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
 /// Give a really bad subtype error.
 ///
 /// Every usage of this is a bug.
@@ -425,11 +717,73 @@ impl<'db> OrElse<'db> for WhereClauseError<'db> {
                 span,
                 "where clause on function not satisfied".to_string(),
             )
+            .label(db, Level::Error, span, format!("expected `{where_clause}`")),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
+/// Reported when the generic arguments written for a class
+/// do not satisfy the where-clauses declared on the class.
+#[derive(Copy, Clone, Debug)]
+pub struct ClassWhereClauseError<'db> {
+    span: Span<'db>,
+    aggregate: SymAggregate<'db>,
+    where_clause: SymWhereClause<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> ClassWhereClauseError<'db> {
+    #[track_caller]
+    pub fn new(
+        span: Span<'db>,
+        aggregate: SymAggregate<'db>,
+        where_clause: SymWhereClause<'db>,
+    ) -> Self {
+        Self {
+            span,
+            aggregate,
+            where_clause,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for ClassWhereClauseError<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        let Self {
+            span,
+            aggregate,
+            where_clause,
+            compiler_location: _,
+        } = *self;
+        let name = aggregate.name(db);
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(
+                db,
+                span,
+                format!("where clause on class `{name}` not satisfied"),
+            )
             .label(
                 db,
                 Level::Error,
                 span,
-                format!("expected `{where_clause:?}`"),
+                format!("`{name}` requires `{where_clause}`"),
+            )
+            .label(
+                db,
+                Level::Info,
+                aggregate.name_span(db),
+                format!("`{name}` is declared here"),
             ),
         )
     }
@@ -512,6 +866,53 @@ impl<'db> OrElse<'db> for InvalidInitializerType<'db> {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidPatternType<'db> {
+    pattern_span: Span<'db>,
+    arity: usize,
+    value_ty: SymTy<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> InvalidPatternType<'db> {
+    #[track_caller]
+    pub fn new(pattern_span: Span<'db>, arity: usize, value_ty: SymTy<'db>) -> Self {
+        Self {
+            pattern_span,
+            arity,
+            value_ty,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for InvalidPatternType<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(db, self.pattern_span, "tuple pattern does not match value").label(
+                db,
+                Level::Error,
+                self.pattern_span,
+                format!(
+                    "this pattern expects a tuple of {arity} elements, but the value has type `{value_ty}`",
+                    arity = self.arity,
+                    value_ty = self.value_ty,
+                ),
+            ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct InvalidAssignmentType<'db> {
     lhs: SymPlaceExpr<'db>,
@@ -566,6 +967,110 @@ impl<'db> OrElse<'db> for InvalidAssignmentType<'db> {
     }
 }
 
+/// `value as ty` where `value` is not assignable to `ty`
+/// (and the two are not both numeric types).
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidCast<'db> {
+    value: SymExpr<'db>,
+    ty_span: Span<'db>,
+    ty: SymTy<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> InvalidCast<'db> {
+    #[track_caller]
+    pub fn new(value: SymExpr<'db>, ty_span: Span<'db>, ty: SymTy<'db>) -> Self {
+        Self {
+            value,
+            ty_span,
+            ty,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for InvalidCast<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        let value_ty = self.value.ty(db);
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(
+                db,
+                self.value.span(db),
+                format!("cannot cast `{value_ty}` to `{ty}`", ty = self.ty),
+            )
+            .label(
+                db,
+                Level::Error,
+                self.value.span(db),
+                format!("this expression has type `{value_ty}`"),
+            )
+            .label(
+                db,
+                Level::Info,
+                self.ty_span,
+                "only numeric types can be converted with `as`; other values must already be assignable to this type",
+            ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct IncompatibleTryError<'db> {
+    question_mark: Span<'db>,
+    error_ty: SymTy<'db>,
+    return_ty: SymTy<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> IncompatibleTryError<'db> {
+    #[track_caller]
+    pub fn new(question_mark: Span<'db>, error_ty: SymTy<'db>, return_ty: SymTy<'db>) -> Self {
+        Self {
+            question_mark,
+            error_ty,
+            return_ty,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for IncompatibleTryError<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(db, self.question_mark, "incompatible error type").label(
+                db,
+                Level::Error,
+                self.question_mark,
+                format!(
+                    "this returns an error of type `{error_ty}`, but the function returns `{return_ty}`",
+                    error_ty = self.error_ty,
+                    return_ty = self.return_ty,
+                ),
+            ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct InvalidReturnValue<'db> {
     value: SymExpr<'db>,
@@ -621,6 +1126,57 @@ impl<'db> OrElse<'db> for InvalidReturnValue<'db> {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidBreakValue<'db> {
+    value: SymExpr<'db>,
+    loop_span: Span<'db>,
+    compiler_location: &'static Location<'static>,
+}
+
+impl<'db> InvalidBreakValue<'db> {
+    #[track_caller]
+    pub fn new(value: SymExpr<'db>, loop_span: Span<'db>) -> Self {
+        Self {
+            value,
+            loop_span,
+            compiler_location: Location::caller(),
+        }
+    }
+}
+
+impl<'db> OrElse<'db> for InvalidBreakValue<'db> {
+    fn or_else(&self, env: &mut Env<'db>, because: Because<'db>) -> Diagnostic {
+        let db = env.db();
+        let value_ty = self.value.ty(db);
+        because.annotate_diagnostic(
+            env,
+            Diagnostic::error(db, self.value.span(db), "invalid break value".to_string())
+                .label(
+                    db,
+                    Level::Error,
+                    self.value.span(db),
+                    format!(
+                        "I expected a value of the loop's type, but this has type `{value_ty}`"
+                    ),
+                )
+                .label(
+                    db,
+                    Level::Info,
+                    self.loop_span,
+                    "this is the loop being exited".to_string(),
+                ),
+        )
+    }
+
+    fn to_arc(&self) -> ArcOrElse<'db> {
+        Arc::new(*self).into()
+    }
+
+    fn compiler_location(&self) -> &'static Location<'static> {
+        self.compiler_location
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct AwaitNonFuture<'db> {
     await_span: Span<'db>,
@@ -796,15 +1352,22 @@ impl<'db> OrElse<'db> for OperatorRequiresNumericType<'db> {
             compiler_location: _,
         } = *self;
 
+        // Comparisons also accept `char`.
+        let (expected, arguments) = if op.is_comparison() {
+            ("numeric type or `char`", "numeric or `char`")
+        } else {
+            ("numeric type", "numeric")
+        };
+
         because.annotate_diagnostic(
             env,
-            Diagnostic::error(db, expr.span(db), "numeric type expected")
+            Diagnostic::error(db, expr.span(db), format!("{expected} expected"))
                 .label(
                     db,
                     Level::Error,
                     expr.span(db),
                     format!(
-                        "I expected this to have a numeric type but it had the type `{}`",
+                        "I expected this to have a {expected} but it had the type `{}`",
                         expr.ty(db)
                     ),
                 )
@@ -812,7 +1375,7 @@ impl<'db> OrElse<'db> for OperatorRequiresNumericType<'db> {
                     db,
                     Level::Info,
                     op_span,
-                    format!("the operator `{op}` requires numeric arguments"),
+                    format!("the operator `{op}` requires {arguments} arguments"),
                 ),
         )
     }
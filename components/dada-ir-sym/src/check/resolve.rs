@@ -14,7 +14,7 @@ use crate::ir::{
 
 use super::{
     Env,
-    inference::{Direction, InferVarKind},
+    inference::{Direction, InferVarKind, InferredValue},
     red::RedTy,
 };
 
@@ -43,6 +43,10 @@ pub struct Resolver<'env, 'db> {
     // bounds do not reference other inference variables or
     // have recursive structure.
     memoized_perm: Map<InferVarIndex, SymPerm<'db>>,
+
+    // Errors already reported for inference variables with cyclic bounds,
+    // so that resolving such a variable again does not report it twice.
+    reported_cycles: Map<InferVarIndex, Reported>,
 }
 
 impl<'env, 'db> Resolver<'env, 'db> {
@@ -57,6 +61,7 @@ impl<'env, 'db> Resolver<'env, 'db> {
             env,
             memoized_ty: Default::default(),
             memoized_perm: Default::default(),
+            reported_cycles: Default::default(),
         }
     }
 
@@ -74,6 +79,21 @@ impl<'env, 'db> Resolver<'env, 'db> {
         })
     }
 
+    /// Return the value of each inference variable created thus far, indexed by [`InferVarIndex`].
+    pub fn resolve_all(&mut self) -> Vec<InferredValue<'db>> {
+        let count = self.env.runtime().inference_var_count();
+        InferVarIndex::range(InferVarIndex::from(count))
+            .map(|infer| InferredValue {
+                span: self.env.infer_var_span(infer),
+                kind: self.env.infer_var_kind(infer),
+                value: match self.resolve_infer_var(infer) {
+                    Ok(v) => v,
+                    Result::Err(error) => SymGenericTerm::err(self.db, self.report(infer, error)),
+                },
+            })
+            .collect()
+    }
+
     /// Resolve an inference variable to a generic term, given the variance of the location in which it appears
     fn resolve_infer_var(
         &mut self,
@@ -172,10 +192,16 @@ impl<'env, 'db> Resolver<'env, 'db> {
         })
     }
 
-    fn report(&self, infer: InferVarIndex, _err: ResolverCycle) -> Reported {
+    fn report(&mut self, infer: InferVarIndex, _err: ResolverCycle) -> Reported {
+        if let Some(&reported) = self.reported_cycles.get(&infer) {
+            return reported;
+        }
         let span = self.env.infer_var_span(infer);
-        Diagnostic::error(self.db, span, "cyclic bounds found for inference variable")
-            .report(self.db)
+        let reported =
+            Diagnostic::error(self.db, span, "cyclic bounds found for inference variable")
+                .report(self.db);
+        self.reported_cycles.insert(infer, reported);
+        reported
     }
 }
 
@@ -6,22 +6,22 @@ use std::{
     panic::Location,
     rc::Rc,
     sync::{
-        Arc, Mutex, RwLock,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
     task::{Context, Poll, Waker},
 };
 
-use crate::ir::indices::InferVarIndex;
+use crate::ir::{functions::SymFunction, indices::InferVarIndex, types::Assumption};
 use check_task::CheckTask;
 use dada_ir_ast::{
-    diagnostic::{Diagnostic, Err, Errors, Level},
+    diagnostic::{Diagnostic, Err, Errors, Level, Reported},
     span::Span,
 };
 use dada_util::{Map, Set, vecext::VecExt};
 use serde::Serialize;
 
-use crate::{check::env::Env, check::inference::InferenceVarData};
+use crate::{check::env::Env, check::inference::InferenceVarData, check::red::RedChain};
 
 use super::{
     debug::{LogHandle, RootTaskDescription, TaskDescription, event_argument},
@@ -44,6 +44,18 @@ pub(crate) struct RuntimeData<'db> {
     /// If it is a new relation, then we know we must propagate bounds.
     sub_inference_var_pairs: Mutex<Set<(InferVarIndex, InferVarIndex)>>,
 
+    /// Memoized results of comparing two lien chains under some assumptions,
+    /// see [`Runtime::memoize_sub_chain`].
+    sub_chain_cache: Mutex<Map<SubChainKey<'db>, Errors<bool>>>,
+
+    /// Number of lookups in `sub_chain_cache`, for profiling.
+    sub_chain_cache_stats: SubChainCacheStats<AtomicU64>,
+
+    /// Number of alternatives started by speculative combinators like [`Env::either`],
+    /// and how many of them ran to completion, see [`Runtime::alternative_stats`].
+    alternatives_started: AtomicU64,
+    alternatives_finished: AtomicU64,
+
     /// List of tasks that are ready to execute.
     ready_to_execute: Mutex<Vec<Arc<CheckTask>>>,
 
@@ -51,6 +63,20 @@ pub(crate) struct RuntimeData<'db> {
     /// When the data for `InferVarIndex` changes, the tasks will be awoken.
     waiting_on_inference_var: Mutex<Map<InferVarIndex, Vec<EqWaker>>>,
 
+    /// Number of errors reported so far, see [`Runtime::record_error`].
+    errors_reported: AtomicUsize,
+
+    /// Span of the check, where the "too many errors" summary is reported.
+    span: Span<'db>,
+
+    /// The "too many errors" summary, once it has been reported,
+    /// see [`Runtime::report_too_many_errors`].
+    too_many_errors_reported: OnceLock<Reported>,
+
+    /// Functions with inferred return types whose return types were needed,
+    /// see [`Runtime::record_output_ty_dependency`].
+    output_ty_dependencies: Mutex<Vec<SymFunction<'db>>>,
+
     /// If true, inference state is frozen and will not change further.
     complete: AtomicBool,
 
@@ -64,6 +90,34 @@ pub(crate) struct RuntimeData<'db> {
     root_log: LogHandle<'db>,
 }
 
+/// Key of the sub-chain cache: the assumptions in effect and the two chains compared.
+type SubChainKey<'db> = (Arc<Vec<Assumption<'db>>>, RedChain<'db>, RedChain<'db>);
+
+/// Number of lookups in the sub-chain cache that did (`hits`) and
+/// did not (`misses`) find a memoized result.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Serialize)]
+pub(crate) struct SubChainCacheStats<N> {
+    pub hits: N,
+    pub misses: N,
+}
+
+/// Number of alternatives explored by speculative combinators like [`Env::either`]
+/// that were started (`started`) and that were dropped before finishing because
+/// the result was already known (`pruned`).
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Serialize)]
+pub(crate) struct AlternativeStats {
+    pub started: u64,
+    pub pruned: u64,
+}
+
+/// Counters from one check, for profiling the type checker,
+/// see [`Runtime::check_stats`].
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Serialize)]
+pub(crate) struct CheckStats {
+    pub alternatives: AlternativeStats,
+    pub sub_chain_cache: SubChainCacheStats<u64>,
+}
+
 /// Wrapper around waker to compare its data/vtable fields by pointer equality.
 /// This suffices to identify the waker for one of our tasks,
 /// as we always use the same data/vtable pointer for a given task.
@@ -128,13 +182,27 @@ impl<'db> Runtime<'db> {
         runtime.mark_complete();
         runtime.drain();
 
-        let result = match channel_rx.try_recv() {
-            Ok(v) => cleanup(v),
+        let result = if runtime.too_many_errors() {
+            R::err(db, runtime.report_too_many_errors())
+        } else {
+            match channel_rx.try_recv() {
+                Ok(v) => cleanup(v),
 
-            // FIXME: Obviously we need a better error message than this!
-            Err(_) => R::err(db, runtime.report_type_annotations_needed(span)),
+                // FIXME: Obviously we need a better error message than this!
+                Err(_) => R::err(db, runtime.report_type_annotations_needed(span)),
+            }
         };
 
+        runtime.root_log.log(
+            compiler_location,
+            "sub_chain cache",
+            &[&runtime.sub_chain_cache_stats()],
+        );
+        runtime.root_log.log(
+            compiler_location,
+            "alternatives",
+            &[&runtime.alternative_stats()],
+        );
         runtime
             .root_log
             .log(compiler_location, "final result", &[&result]);
@@ -155,8 +223,16 @@ impl<'db> Runtime<'db> {
             data: Rc::new(RuntimeData {
                 db,
                 complete: Default::default(),
+                errors_reported: Default::default(),
+                span,
+                too_many_errors_reported: Default::default(),
+                output_ty_dependencies: Default::default(),
                 inference_vars: Default::default(),
                 sub_inference_var_pairs: Default::default(),
+                sub_chain_cache: Default::default(),
+                sub_chain_cache_stats: Default::default(),
+                alternatives_started: Default::default(),
+                alternatives_finished: Default::default(),
                 ready_to_execute: Default::default(),
                 waiting_on_inference_var: Default::default(),
                 next_task_id: Default::default(),
@@ -194,9 +270,12 @@ impl<'db> Runtime<'db> {
         self.ready_to_execute.lock().unwrap().pop()
     }
 
-    /// Continues running tasks until no more are left.
+    /// Continues running tasks until no more are left
+    /// or [too many errors](`Self::too_many_errors`) have been reported.
     fn drain(&self) {
-        while let Some(ready) = self.pop_task() {
+        while !self.too_many_errors()
+            && let Some(ready) = self.pop_task()
+        {
             ready.execute(self);
         }
     }
@@ -218,12 +297,57 @@ impl<'db> Runtime<'db> {
         }
     }
 
+    /// Record that an error is being reported. If it exceeds the
+    /// [maximum](`dada_ir_ast::Db::max_errors`), it should not be reported;
+    /// instead we report the "too many errors" summary and return `Err` with it.
+    pub fn record_error(&self) -> Errors<()> {
+        if self.errors_reported.fetch_add(1, Ordering::Relaxed) < self.db.max_errors() {
+            Ok(())
+        } else {
+            Err(self.report_too_many_errors())
+        }
+    }
+
+    /// Record that the inferred return type of `function` was needed, and with it
+    /// the return types of the functions in `dependencies` that it depends on
+    /// (see [`InferredOutputTy`](`crate::check::signature::InferredOutputTy`)).
+    pub fn record_output_ty_dependency(
+        &self,
+        function: SymFunction<'db>,
+        dependencies: &[SymFunction<'db>],
+    ) {
+        let mut recorded = self.output_ty_dependencies.lock().unwrap();
+        for &f in std::iter::once(&function).chain(dependencies) {
+            recorded.push_if_not_contained(f);
+        }
+    }
+
+    /// The functions recorded with [`Self::record_output_ty_dependency`],
+    /// ordered by the span of their names.
+    pub fn output_ty_dependencies(&self) -> Vec<SymFunction<'db>> {
+        let db = self.db;
+        let mut dependencies = self.output_ty_dependencies.lock().unwrap().clone();
+        dependencies.sort_by_key(|function| function.name_span(db));
+        dependencies
+    }
+
+    /// Returns `true` if more errors than the maximum have been reported,
+    /// in which case no more tasks are executed.
+    fn too_many_errors(&self) -> bool {
+        self.errors_reported.load(Ordering::Relaxed) > self.db.max_errors()
+    }
+
     /// Returns `true` if we have fully constructed the object IR for a given function.
     /// Once this returns true, no more bounds will be added to inference variables.
     pub fn check_complete(&self) -> bool {
         self.complete.load(Ordering::Relaxed)
     }
 
+    /// Returns the number of inference variables created thus far.
+    pub fn inference_var_count(&self) -> usize {
+        self.inference_vars.read().unwrap().len()
+    }
+
     /// Creates a fresh inference variable of the given kind and universe.
     ///
     /// Low-level routine not to be directly invoked.
@@ -355,6 +479,73 @@ impl<'db> Runtime<'db> {
             .insert((lower, upper))
     }
 
+    /// Returns the memoized result of comparing the lien chains `lower` and `upper`
+    /// in an environment with the given `assumptions`, computing it with `op` the first time.
+    ///
+    /// Many tasks end up comparing the same pairs of chains as bounds are propagated.
+    /// The comparison consults no inference variables, only the chains and what the
+    /// assumptions declare about the generic variables in them, so its result can be
+    /// reused for the rest of the check by any environment with the same assumptions.
+    pub fn memoize_sub_chain(
+        &self,
+        assumptions: &Arc<Vec<Assumption<'db>>>,
+        lower: RedChain<'db>,
+        upper: RedChain<'db>,
+        op: impl FnOnce() -> Errors<bool>,
+    ) -> Errors<bool> {
+        let stats = &self.sub_chain_cache_stats;
+        let key = (assumptions.clone(), lower, upper);
+        if let Some(&result) = self.sub_chain_cache.lock().unwrap().get(&key) {
+            stats.hits.fetch_add(1, Ordering::Relaxed);
+            return result;
+        }
+
+        // Release the lock while computing, as `op` may compare other chains.
+        stats.misses.fetch_add(1, Ordering::Relaxed);
+        let result = op();
+        self.sub_chain_cache.lock().unwrap().insert(key, result);
+        result
+    }
+
+    /// Returns the number of hits and misses in the sub-chain cache so far.
+    pub fn sub_chain_cache_stats(&self) -> SubChainCacheStats<u64> {
+        let stats = &self.sub_chain_cache_stats;
+        SubChainCacheStats {
+            hits: stats.hits.load(Ordering::Relaxed),
+            misses: stats.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Record that a speculative combinator started exploring an alternative.
+    pub fn alternative_started(&self) {
+        self.alternatives_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that an alternative ran to completion.
+    pub fn alternative_finished(&self) {
+        self.alternatives_finished.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of alternatives started and pruned so far.
+    /// An alternative that has been started but has not finished counts as pruned,
+    /// which is accurate once all tasks have run.
+    pub fn alternative_stats(&self) -> AlternativeStats {
+        let started = self.alternatives_started.load(Ordering::Relaxed);
+        let finished = self.alternatives_finished.load(Ordering::Relaxed);
+        AlternativeStats {
+            started,
+            pruned: started - finished,
+        }
+    }
+
+    /// Returns the counters of this check so far.
+    pub fn check_stats(&self) -> CheckStats {
+        CheckStats {
+            alternatives: self.alternative_stats(),
+            sub_chain_cache: self.sub_chain_cache_stats(),
+        }
+    }
+
     fn wake_tasks_monitoring_inference_var(&self, infer: InferVarIndex) {
         let mut waiting_on_inference_var = self.waiting_on_inference_var.lock().unwrap();
         let wakers = waiting_on_inference_var.remove(&infer);
@@ -406,6 +597,26 @@ impl<'db> Runtime<'db> {
             .push_if_not_contained(EqWaker::new(cx.waker()));
     }
 
+    /// Reports that checking stopped because of too many errors, the first time it is called;
+    /// later calls return what was reported then.
+    fn report_too_many_errors(&self) -> Reported {
+        let db = self.db;
+        let span = self.span;
+        *self.too_many_errors_reported.get_or_init(|| {
+            Diagnostic::error(db, span, "too many errors")
+                .label(
+                    db,
+                    Level::Error,
+                    span,
+                    format!(
+                        "stopped checking after the first {} errors",
+                        db.max_errors()
+                    ),
+                )
+                .report(db)
+        })
+    }
+
     fn report_type_annotations_needed(&self, span: Span<'db>) -> dada_ir_ast::diagnostic::Reported {
         let db = self.db;
         let mut diag = Diagnostic::error(db, span, "type annotations needed").label(
@@ -644,3 +855,66 @@ impl<T: DeferResult> DeferResult for Errors<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{panic::Location, sync::Arc};
+
+    use dada_ir_ast::{inputs::SourceFile, span::Anchor};
+    use url::Url;
+
+    use super::Runtime;
+    use crate::{
+        check::red::RedChain,
+        ir::{
+            types::{Assumption, AssumptionKind, SymGenericKind},
+            variables::SymVariable,
+        },
+        test_db::TestDb,
+    };
+
+    /// A permission variable. Variables are tracked structs,
+    /// so they have to be created within a tracked function.
+    #[salsa::tracked]
+    fn perm_variable<'db>(db: &'db dyn crate::Db, source_file: SourceFile) -> SymVariable<'db> {
+        let span = Anchor::SourceFile(source_file).span(db);
+        SymVariable::new(db, SymGenericKind::Perm, None, span)
+    }
+
+    #[test]
+    fn sub_chain_cache_is_keyed_on_assumptions() {
+        let db = TestDb::new();
+        let source_file = SourceFile::new(
+            &db,
+            Url::parse("file:///test.dada").unwrap(),
+            Ok(String::new()),
+        );
+        let span = Anchor::SourceFile(source_file).span(&db);
+        let runtime = Runtime::new(&db, Location::caller(), span, "test", &[]);
+
+        let our = RedChain::our(&db);
+        let my = RedChain::new(&db, vec![]);
+        let var = perm_variable(&db, source_file);
+        let shared = Arc::new(vec![Assumption::new(&db, AssumptionKind::Shared, var)]);
+
+        assert_eq!(
+            runtime.memoize_sub_chain(&Arc::new(vec![]), our, my, || Ok(false)),
+            Ok(false)
+        );
+
+        // Repeating the comparison under equal assumptions is a hit...
+        assert_eq!(
+            runtime.memoize_sub_chain(&Arc::new(vec![]), our, my, || unreachable!()),
+            Ok(false),
+        );
+
+        // ...but different assumptions may give a different answer, so they are a miss.
+        assert_eq!(
+            runtime.memoize_sub_chain(&shared, our, my, || Ok(true)),
+            Ok(true)
+        );
+
+        let stats = runtime.sub_chain_cache_stats();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+    }
+}
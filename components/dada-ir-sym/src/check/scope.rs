@@ -6,7 +6,7 @@ use dada_ir_ast::{
     inputs::Krate,
     span::{Span, Spanned},
 };
-use dada_util::{FromImpls, boxed_async_fn};
+use dada_util::{FromImpls, Set, boxed_async_fn};
 use salsa::Update;
 use serde::Serialize;
 
@@ -16,12 +16,13 @@ use crate::{
         binder::BoundTerm,
         classes::{SymAggregate, SymAggregateStyle, SymClassMember},
         functions::SymFunction,
-        module::SymModule,
+        module::{SymItem, SymModule, SymPrelude},
         primitive::{SymPrimitive, primitives},
-        types::{SymGenericKind, SymGenericTerm},
+        types::{HasKind, SymGenericKind, SymGenericTerm},
         variables::SymVariable,
     },
     prelude::Symbol,
+    well_known,
 };
 
 use super::Env;
@@ -34,16 +35,16 @@ pub struct Scope<'scope, 'db> {
 }
 
 impl<'scope, 'db> Scope<'scope, 'db> {
-    /// A base scope containing only the primitive names.
+    /// A base scope containing the primitive names and the items from the prelude.
     pub(crate) fn new(db: &'db dyn crate::Db, span: Span<'db>) -> Self {
         let mut this = Scope {
             span,
             chain: ScopeChain::primitives(),
         };
 
-        let root = db.root();
-        let crate_source = root.libdada_crate(db);
-        this = this.with_prelude(db, span, crate_source);
+        // The prelude sits just above the primitives, so that anything
+        // defined or imported by a module shadows it.
+        this.push_link(well_known::prelude(db));
 
         this
     }
@@ -65,38 +66,6 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         self.span
     }
 
-    /// Extend this scope with the prelude from a crate.
-    /// Crates can define a module named `prelude`.
-    fn with_prelude(self, db: &'db dyn crate::Db, span: Span<'db>, crate_source: Krate) -> Self {
-        let prelude_id = Identifier::prelude(db);
-        match resolve_name_against_crate(
-            db,
-            crate_source,
-            SpannedIdentifier {
-                id: prelude_id,
-                span,
-            },
-        ) {
-            Ok(NameResolutionSym::SymModule(sym)) => self.with_link(ScopeChainKind::SymModule(sym)),
-            Ok(sym) => {
-                let span = sym.span(db).unwrap_or(span);
-                Diagnostic::error(db, span, "prelude is not a module".to_string())
-                    .label(
-                        db,
-                        Level::Error,
-                        span,
-                        format!(
-                            "I expected `prelude` to be a module, but I found {category}",
-                            category = sym.categorize(db)
-                        ),
-                    )
-                    .report(db);
-                self
-            }
-            Err(Reported(_)) => self,
-        }
-    }
-
     /// Extend this scope with another link in the name resolution chain
     pub fn push_link(&mut self, kind: impl Into<ScopeChainKind<'scope, 'db>>) {
         let chain = ScopeChain {
@@ -117,6 +86,16 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         None
     }
 
+    /// Return the innermost function whose body items are in scope (if any).
+    pub fn function_body(&self) -> Option<SymFunction<'db>> {
+        for link in self.chain.iter() {
+            if let ScopeChainKind::SymFunctionBody(function) = &link.kind {
+                return Some(*function);
+            }
+        }
+        None
+    }
+
     /// Resolve identifier `id` (found at `span`) in the scope.
     /// Reports errors if nothing is found and returns `Err(Reported)`.
     pub(crate) fn resolve_name(
@@ -125,20 +104,69 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         id: Identifier<'db>,
         span: Span<'db>,
     ) -> Errors<NameResolution<'db>> {
-        if let Some(resolution) = self.chain.iter().find_map(|link| link.resolve_name(db, id)) {
-            return Ok(resolution);
+        if let Some(resolution) = self
+            .chain
+            .iter()
+            .find_map(|link| link.resolve_name(db, id, span))
+        {
+            return resolution;
         }
 
-        Err(
-            Diagnostic::error(db, span, format!("could not find anything named `{id}`",))
-                .label(
-                    db,
-                    Level::Error,
-                    span,
-                    "I could not find anything with this name :(",
-                )
-                .report(db),
-        )
+        let mut diagnostic =
+            Diagnostic::error(db, span, format!("could not find anything named `{id}`",)).label(
+                db,
+                Level::Error,
+                span,
+                "I could not find anything with this name :(",
+            );
+        if let Some(similar) = self.similar_name(db, id) {
+            diagnostic =
+                diagnostic.label(db, Level::Help, span, format!("did you mean `{similar}`?"));
+        }
+        Err(diagnostic.report(db))
+    }
+
+    /// Iterates over the names visible in this scope, innermost first.
+    /// A name appears more than once if an inner binding shadows an outer one.
+    /// Names that are only available through glob imports (`use a.b.*`) are not included.
+    pub fn visible_names(
+        &self,
+        db: &'db dyn crate::Db,
+    ) -> impl Iterator<Item = Identifier<'db>> + '_ {
+        self.chain.iter().flat_map(move |link| link.names(db))
+    }
+
+    /// Returns each name visible in this scope along with the symbol it resolves to,
+    /// innermost first. Shadowed bindings are omitted, as are names that fail to
+    /// resolve (e.g., a `use` of something that does not exist).
+    pub fn visible_symbols(
+        &self,
+        db: &'db dyn crate::Db,
+    ) -> Vec<(Identifier<'db>, NameResolutionSym<'db>)> {
+        let mut seen = Set::default();
+        self.visible_names(db)
+            .filter(|&id| seen.insert(id))
+            .filter_map(|id| {
+                let resolution = self
+                    .chain
+                    .iter()
+                    .find_map(|link| link.resolve_name(db, id, self.span))?;
+                Some((id, resolution.ok()?.sym))
+            })
+            .collect()
+    }
+
+    /// Finds the visible name that is most similarly spelled to `id` (which failed to resolve),
+    /// if any is close enough to plausibly be a typo. Ties are broken alphabetically.
+    fn similar_name(&self, db: &'db dyn crate::Db, id: Identifier<'db>) -> Option<Identifier<'db>> {
+        let text = id.text(db);
+        let len = text.chars().count();
+        let max_distance = std::cmp::max(1, len / 3);
+        self.visible_names(db)
+            .map(|name| (strsim::levenshtein(text, name.text(db)), name))
+            .filter(|&(distance, _)| distance > 0 && distance <= max_distance && distance < len)
+            .min_by(|(d1, n1), (d2, n2)| d1.cmp(d2).then_with(|| n1.text(db).cmp(n2.text(db))))
+            .map(|(_, name)| name)
     }
 
     /// True if `sym` is in scope.
@@ -211,8 +239,10 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         for link in self.chain.iter() {
             match &link.kind {
                 ScopeChainKind::Primitives
+                | ScopeChainKind::SymPrelude(_)
                 | ScopeChainKind::SymModule(_)
-                | ScopeChainKind::SymAggr(_) => {}
+                | ScopeChainKind::SymAggr(_)
+                | ScopeChainKind::SymFunctionBody(_) => {}
                 ScopeChainKind::ForAll(cow) => {
                     vec.push(cow.iter().copied().collect());
                 }
@@ -242,12 +272,19 @@ pub enum ScopeChainKind<'scope, 'db> {
     #[no_from_impl]
     Primitives,
 
+    /// Introduces the items of the prelude into scope (always present).
+    SymPrelude(SymPrelude<'db>),
+
     /// Records that we are in the scope of a module.
     SymModule(SymModule<'db>),
 
     /// Records that we are in the scope of a class
     SymAggr(SymAggregate<'db>),
 
+    /// Introduces the items nested in a function body into scope.
+    #[no_from_impl]
+    SymFunctionBody(SymFunction<'db>),
+
     /// Introduces the given symbols into scope.
     ForAll(Cow<'scope, [SymVariable<'db>]>),
 }
@@ -334,8 +371,49 @@ impl<'db> NameResolution<'db> {
         .report(db));
         }
 
+        let generic_parameters = self.sym.generic_parameters(db);
         for v in generics.values.iter() {
-            self.generics.push(v.check_in_env(env).await);
+            let var = generic_parameters[self.generics.len()];
+            let term = match *v {
+                // Generic arguments of a function call can be `_`, to be inferred like omitted ones.
+                AstGenericTerm::Infer(span)
+                    if matches!(self.sym, NameResolutionSym::SymFunction(_)) =>
+                {
+                    env.fresh_inference_var_term(var.kind(db), span)
+                }
+                _ => v.check_in_env(env).await,
+            };
+            let term = if term.has_kind(db, var.kind(db)) {
+                term
+            } else {
+                let span = v.span(db);
+                let expected_kind = var.kind(db);
+                let found_kind = term.kind().unwrap();
+                SymGenericTerm::Error(
+                    Diagnostic::error(
+                        db,
+                        span,
+                        format!("expected a `{expected_kind}`, found a `{found_kind}`"),
+                    )
+                    .label(
+                        db,
+                        Level::Error,
+                        span,
+                        format!(
+                            "{sym} expects a `{expected_kind}` here, but I found a `{found_kind}`",
+                            sym = self.sym.describe(db),
+                        ),
+                    )
+                    .label(
+                        db,
+                        Level::Info,
+                        var.span(db),
+                        format!("the generic parameter is declared as a `{expected_kind}` here"),
+                    )
+                    .report(db),
+                )
+            };
+            self.generics.push(term);
         }
 
         Ok(self)
@@ -394,9 +472,9 @@ impl<'db> NameResolutionSym<'db> {
     ) -> Errors<Result<NameResolutionSym<'db>, NameResolutionSym<'db>>> {
         match self {
             NameResolutionSym::SymModule(sym_module) => match sym_module
-                .resolve_name_against_definitions(db, id.id)
+                .resolve_exported_name(db, id.id, id.span)
             {
-                Some(sym) => Ok(Ok(sym)),
+                Some(resolution) => Ok(Ok(resolution?.sym)),
                 None => Err(
                     Diagnostic::error(db, id.span, "nothing named `{}` found in module")
                         .label(
@@ -465,6 +543,18 @@ impl<'db> NameResolutionSym<'db> {
         }
     }
 
+    /// The generic parameters that generic arguments supplied to this symbol are matched against,
+    /// outermost first.
+    fn generic_parameters(&self, db: &'db dyn crate::Db) -> Vec<SymVariable<'db>> {
+        match self {
+            NameResolutionSym::SymModule(sym) => sym.transitive_generic_parameters(db),
+            NameResolutionSym::SymAggregate(sym) => sym.transitive_generic_parameters(db),
+            NameResolutionSym::SymFunction(sym) => sym.transitive_generic_parameters(db),
+            NameResolutionSym::SymPrimitive(_) => vec![],
+            NameResolutionSym::SymVariable(_) => vec![],
+        }
+    }
+
     fn span(&self, db: &'db dyn crate::Db) -> Option<Span<'db>> {
         match self {
             NameResolutionSym::SymModule(sym) => Some(sym.span(db)),
@@ -618,38 +708,84 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
         })
     }
 
+    /// Resolve `id` against this link. Returns `None` if the name is not bound here
+    /// and `Some(Err)` if it is bound but resolving it reported an error (e.g., a bad `use`).
     fn resolve_name(
         &self,
         db: &'db dyn crate::Db,
         id: Identifier<'db>,
-    ) -> Option<NameResolution<'db>> {
+        span: Span<'db>,
+    ) -> Option<Errors<NameResolution<'db>>> {
         match &self.kind {
             ScopeChainKind::Primitives => primitives(db)
                 .iter()
                 .copied()
                 .filter(|p| p.name(db) == id)
-                .map(|p| NameResolution {
-                    generics: vec![],
-                    sym: p.into(),
+                .map(|p| {
+                    Ok(NameResolution {
+                        generics: vec![],
+                        sym: p.into(),
+                    })
                 })
                 .next(),
 
-            ScopeChainKind::SymAggr(_) => None,
+            ScopeChainKind::SymPrelude(prelude) => prelude
+                .items(db)
+                .iter()
+                .copied()
+                .find(|item| item.name(db) == id)
+                .map(|item| {
+                    Ok(NameResolution {
+                        generics: vec![],
+                        sym: match item {
+                            SymItem::SymClass(sym) => sym.into(),
+                            SymItem::SymFunction(sym) => sym.into(),
+                            SymItem::SymPrimitive(sym) => sym.into(),
+                        },
+                    })
+                }),
+
+            // `Self` names the enclosing class, applied to its own generic parameters.
+            ScopeChainKind::SymAggr(aggr) => (id == Identifier::self_ty_ident(db)).then(|| {
+                Ok(NameResolution {
+                    generics: aggr
+                        .symbols(db)
+                        .generic_variables
+                        .iter()
+                        .map(|&v| SymGenericTerm::var(db, v))
+                        .collect(),
+                    sym: (*aggr).into(),
+                })
+            }),
+
+            ScopeChainKind::SymFunctionBody(function) => {
+                function.nested_items(db).get(&id).map(|&item| {
+                    Ok(NameResolution {
+                        generics: vec![],
+                        sym: match item {
+                            SymItem::SymClass(sym) => sym.into(),
+                            SymItem::SymFunction(sym) => sym.into(),
+                            SymItem::SymPrimitive(sym) => sym.into(),
+                        },
+                    })
+                })
+            }
 
             ScopeChainKind::SymModule(sym) => {
                 // Somewhat subtle: we give definitions precedence over uses. If the same name appears
                 // in both locations, an error is reported by checking.
+                // Glob imports have the lowest precedence of all; anything else shadows them.
 
                 if let Some(sym) = sym.resolve_name_against_definitions(db, id) {
                     match sym {
                         NameResolutionSym::SymModule(sym) => {
-                            Some(self.internal_module_item(db, sym))
+                            Some(Ok(self.internal_module_item(db, sym)))
                         }
                         NameResolutionSym::SymAggregate(sym) => {
-                            Some(self.internal_module_item(db, sym))
+                            Some(Ok(self.internal_module_item(db, sym)))
                         }
                         NameResolutionSym::SymFunction(sym) => {
-                            Some(self.internal_module_item(db, sym))
+                            Some(Ok(self.internal_module_item(db, sym)))
                         }
                         NameResolutionSym::SymPrimitive(_) | NameResolutionSym::SymVariable(_) => {
                             // cannot be members of a module
@@ -658,16 +794,17 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
                     }
                 } else {
                     sym.resolve_name_against_uses(db, id)
+                        .or_else(|| sym.resolve_name_against_globs(db, id, span, false))
                 }
             }
 
             ScopeChainKind::ForAll(symbols) => {
                 if let Some(index) = symbols.iter().position(|&s| s.name(db) == Some(id)) {
                     let sym = symbols[index];
-                    Some(NameResolution {
+                    Some(Ok(NameResolution {
                         generics: vec![],
                         sym: NameResolutionSym::SymVariable(sym),
-                    })
+                    }))
                 } else {
                     None
                 }
@@ -675,6 +812,33 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
         }
     }
 
+    /// The names bound by this link (see [`Scope::visible_names`]).
+    fn names(&self, db: &'db dyn crate::Db) -> Vec<Identifier<'db>> {
+        match &self.kind {
+            ScopeChainKind::Primitives => primitives(db).iter().map(|p| p.name(db)).collect(),
+
+            ScopeChainKind::SymPrelude(prelude) => {
+                prelude.items(db).iter().map(|item| item.name(db)).collect()
+            }
+
+            ScopeChainKind::SymAggr(_) => vec![Identifier::self_ty_ident(db)],
+
+            ScopeChainKind::SymFunctionBody(function) => {
+                function.nested_items(db).keys().copied().collect()
+            }
+
+            ScopeChainKind::SymModule(sym) => sym
+                .class_map(db)
+                .keys()
+                .chain(sym.function_map(db).keys())
+                .chain(sym.ast_use_map(db).keys())
+                .copied()
+                .collect(),
+
+            ScopeChainKind::ForAll(symbols) => symbols.iter().filter_map(|s| s.name(db)).collect(),
+        }
+    }
+
     /// Resolve an identifier like `x` that we mapped to some item in a module.
     fn internal_module_item(
         &self,
@@ -692,7 +856,9 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
         match &self.kind {
             ScopeChainKind::SymAggr(_)
             | ScopeChainKind::Primitives
-            | ScopeChainKind::SymModule(_) => false,
+            | ScopeChainKind::SymPrelude(_)
+            | ScopeChainKind::SymModule(_)
+            | ScopeChainKind::SymFunctionBody(_) => false,
 
             ScopeChainKind::ForAll(symbols) => symbols.contains(&sym),
         }
@@ -716,24 +882,268 @@ impl<'db> SymModule<'db> {
         None
     }
 
+    /// Resolve `id` against the `use` declarations in this module.
     fn resolve_name_against_uses(
         self,
         db: &'db dyn crate::Db,
         id: Identifier<'db>,
-    ) -> Option<NameResolution<'db>> {
+    ) -> Option<Errors<NameResolution<'db>>> {
         let ast_use = self.ast_use_map(db).get(&id)?;
-        resolve_ast_use(db, *ast_use)
+        Some(resolve_ast_use(db, *ast_use))
+    }
+
+    /// Resolve `id` as referenced from outside this module (e.g., `module.id`):
+    /// first its definitions, then its re-exports.
+    fn resolve_exported_name(
+        self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+        span: Span<'db>,
+    ) -> Option<Errors<NameResolution<'db>>> {
+        if let Some(sym) = self.resolve_name_against_definitions(db, id) {
+            return Some(Ok(NameResolution {
+                generics: vec![],
+                sym,
+            }));
+        }
+
+        self.resolve_name_against_reexports(db, id, span)
+    }
+
+    /// Resolve `id` against the `pub use` declarations in this module,
+    /// which re-export items defined elsewhere. Unlike [`Self::resolve_name_against_uses`][],
+    /// this is used when the name is referenced from outside the module.
+    fn resolve_name_against_reexports(
+        self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+        span: Span<'db>,
+    ) -> Option<Errors<NameResolution<'db>>> {
+        if let Some(&ast_use) = self.ast_use_map(db).get(&id)
+            && ast_use.visibility(db).is_some()
+        {
+            return Some(resolve_ast_use(db, ast_use));
+        }
+
+        self.resolve_name_against_globs(db, id, span, true)
+    }
+
+    /// Resolve `id` (referenced at `span`) against the glob imports (`use a.b.*`) in this module.
+    /// If `reexports_only` is true, only `pub` glob imports are considered.
+    ///
+    /// Reports an error if more than one glob import provides an item named `id`
+    /// and they do not agree on what that item is.
+    fn resolve_name_against_globs(
+        self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+        span: Span<'db>,
+        reexports_only: bool,
+    ) -> Option<Errors<NameResolution<'db>>> {
+        let candidates = match glob_candidates(db, self, id, reexports_only) {
+            Ok(candidates) => candidates,
+            Err(reported) => return Some(Err(*reported)),
+        };
+
+        match &candidates[..] {
+            [] => None,
+            [(_, resolution)] => Some(Ok(resolution.clone())),
+            _ => Some(Err(report_ambiguous_glob_import(db, id, span, candidates))),
+        }
+    }
+
+    /// The items named `id` that this module exports (see [`Self::resolve_exported_name`]),
+    /// without reporting an error if its glob imports provide more than one.
+    fn exported_name_candidates(
+        self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+    ) -> Errors<Vec<NameResolution<'db>>> {
+        if let Some(sym) = self.resolve_name_against_definitions(db, id) {
+            return Ok(vec![NameResolution {
+                generics: vec![],
+                sym,
+            }]);
+        }
+
+        if let Some(&ast_use) = self.ast_use_map(db).get(&id)
+            && ast_use.visibility(db).is_some()
+        {
+            return Ok(vec![resolve_ast_use(db, ast_use)?]);
+        }
+
+        Ok(glob_candidates(db, self, id, true)
+            .as_ref()
+            .map_err(|&reported| reported)?
+            .iter()
+            .map(|(_, resolution)| resolution.clone())
+            .collect())
     }
 }
 
-#[salsa::tracked]
-fn resolve_ast_use<'db>(
+/// The distinct items named `id` that the glob imports in `module` provide,
+/// each paired with the glob import that provides it.
+/// If `reexports_only` is true, only `pub` glob imports are considered.
+///
+/// 💡 A glob may import (transitively) from its own module. Salsa resolves the
+/// resulting cycle by starting from no candidates and iterating until the
+/// candidates stop changing, so the cycle adds nothing beyond what the
+/// modules along it export by other means.
+#[salsa::tracked(return_ref, cycle_fn = glob_candidates_cycle, cycle_initial = glob_candidates_initial)]
+fn glob_candidates<'db>(
+    db: &'db dyn crate::Db,
+    module: SymModule<'db>,
+    id: Identifier<'db>,
+    reexports_only: bool,
+) -> Errors<Vec<(AstUse<'db>, NameResolution<'db>)>> {
+    let mut candidates: Vec<(AstUse<'db>, NameResolution<'db>)> = vec![];
+    for &glob in module.ast_glob_uses(db) {
+        if reexports_only && glob.visibility(db).is_none() {
+            continue;
+        }
+
+        // Errors in the glob's path are reported when checking the `use`, so skip it here.
+        let Ok(NameResolution {
+            sym: NameResolutionSym::SymModule(imported),
+            ..
+        }) = resolve_ast_use(db, glob)
+        else {
+            continue;
+        };
+
+        for resolution in imported.exported_name_candidates(db, id)? {
+            if !candidates.iter().any(|(_, c)| c.sym == resolution.sym) {
+                candidates.push((glob, resolution));
+            }
+        }
+    }
+    Ok(candidates)
+}
+
+fn glob_candidates_initial<'db>(
+    _db: &'db dyn crate::Db,
+    _module: SymModule<'db>,
+    _id: Identifier<'db>,
+    _reexports_only: bool,
+) -> Errors<Vec<(AstUse<'db>, NameResolution<'db>)>> {
+    Ok(vec![])
+}
+
+fn glob_candidates_cycle<'db>(
+    _db: &'db dyn crate::Db,
+    _value: &Errors<Vec<(AstUse<'db>, NameResolution<'db>)>>,
+    _count: u32,
+    _module: SymModule<'db>,
+    _id: Identifier<'db>,
+    _reexports_only: bool,
+) -> salsa::CycleRecoveryAction<Errors<Vec<(AstUse<'db>, NameResolution<'db>)>>> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+fn report_ambiguous_glob_import<'db>(
+    db: &'db dyn crate::Db,
+    id: Identifier<'db>,
+    span: Span<'db>,
+    candidates: &[(AstUse<'db>, NameResolution<'db>)],
+) -> Reported {
+    let mut diagnostic = Diagnostic::error(db, span, format!("`{id}` is ambiguous")).label(
+        db,
+        Level::Error,
+        span,
+        format!("`{id}` is imported by more than one glob import"),
+    );
+    for (glob, resolution) in candidates {
+        diagnostic = diagnostic.label(
+            db,
+            Level::Info,
+            glob.span(db),
+            format!(
+                "this imports {} from module `{}`",
+                resolution.sym.describe(db),
+                glob.path(db).last_id(db).id,
+            ),
+        );
+    }
+    diagnostic.report(db)
+}
+
+/// Check that the glob import `ast_use` names a module, reporting an error if not.
+pub(crate) fn check_glob_use<'db>(db: &'db dyn crate::Db, ast_use: AstUse<'db>) {
+    let Ok(resolution) = resolve_ast_use(db, ast_use) else {
+        return;
+    };
+
+    if let NameResolutionSym::SymModule(_) = resolution.sym {
+        return;
+    }
+
+    let span = ast_use.path(db).span(db);
+    Diagnostic::error(db, span, "glob imports must name a module")
+        .label(
+            db,
+            Level::Error,
+            span,
+            format!(
+                "this is {}, but I expected a module",
+                resolution.sym.describe(db)
+            ),
+        )
+        .report(db);
+}
+
+/// Resolve the item named by `ast_use`. If the path goes through a `pub use`
+/// in another module, that re-export is followed (transitively).
+/// Reports an error if the re-exports form a cycle.
+pub(crate) fn resolve_ast_use<'db>(
     db: &'db dyn crate::Db,
     ast_use: AstUse<'db>,
-) -> Option<NameResolution<'db>> {
+) -> Errors<NameResolution<'db>> {
+    match resolve_ast_use_query(db, ast_use) {
+        UseResolution::Resolved(resolution) => resolution.clone(),
+        UseResolution::Cycle(via) => Err(report_use_cycle(db, ast_use, *via)),
+    }
+}
+
+/// The result of [`resolve_ast_use_query`].
+#[derive(Clone, Debug, PartialEq, Eq, Update)]
+enum UseResolution<'db> {
+    /// The path was resolved (or an error was reported).
+    Resolved(Errors<NameResolution<'db>>),
+
+    /// Resolving the path requires resolving the `use` itself, by way of the given
+    /// re-export (`None` only before the cycle has been explored).
+    Cycle(Option<AstUse<'db>>),
+}
+
+fn report_use_cycle<'db>(
+    db: &'db dyn crate::Db,
+    ast_use: AstUse<'db>,
+    via: Option<AstUse<'db>>,
+) -> Reported {
+    let span = ast_use.span(db);
+    let mut diagnostic = Diagnostic::error(db, span, "cyclic `use` declarations").label(
+        db,
+        Level::Error,
+        span,
+        "this `use` refers back to itself",
+    );
+    if let Some(via) = via
+        && via != ast_use
+    {
+        diagnostic = diagnostic.label(db, Level::Info, via.span(db), "...by way of this `use`");
+    }
+    diagnostic.report(db)
+}
+
+/// 💡 Re-exports may refer back to themselves. Salsa resolves the resulting cycle by
+/// starting from [`UseResolution::Cycle`] and iterating; since a `use` on the cycle
+/// only resolves once the `use` it follows does, every `use` on it stays a cycle,
+/// whichever one resolution started from.
+#[salsa::tracked(return_ref, cycle_fn = resolve_ast_use_cycle, cycle_initial = resolve_ast_use_initial)]
+fn resolve_ast_use_query<'db>(db: &'db dyn crate::Db, ast_use: AstUse<'db>) -> UseResolution<'db> {
     let crate_name = ast_use.crate_name(db);
     let Some(crate_source) = db.root().crate_source(db, crate_name.id) else {
-        Diagnostic::error(
+        return UseResolution::Resolved(Err(Diagnostic::error(
             db,
             crate_name.span,
             format!(
@@ -747,13 +1157,66 @@ fn resolve_ast_use<'db>(
             crate_name.span,
             "could not find this crate",
         )
-        .report(db);
-        return None;
+        .report(db)));
+    };
+
+    resolve_use_path(db, crate_source, ast_use.path(db))
+}
+
+fn resolve_ast_use_initial<'db>(
+    _db: &'db dyn crate::Db,
+    _ast_use: AstUse<'db>,
+) -> UseResolution<'db> {
+    UseResolution::Cycle(None)
+}
+
+fn resolve_ast_use_cycle<'db>(
+    _db: &'db dyn crate::Db,
+    _value: &UseResolution<'db>,
+    _count: u32,
+    _ast_use: AstUse<'db>,
+) -> salsa::CycleRecoveryAction<UseResolution<'db>> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+/// Resolve `path`, whose first identifier names a module of `krate`.
+///
+/// Like [`ResolveAgainst::resolve_against`], except that a `pub use` named by a member
+/// of the path is followed with [`resolve_ast_use_query`], so that a cycle is seen as such.
+fn resolve_use_path<'db>(
+    db: &'db dyn crate::Db,
+    krate: Krate,
+    path: AstPath<'db>,
+) -> UseResolution<'db> {
+    let AstPathKind::Member { path: base, id } = path.kind(db) else {
+        return UseResolution::Resolved(
+            path.resolve_against(db, |id| resolve_name_against_crate(db, krate, id)),
+        );
+    };
+
+    let base = match resolve_use_path(db, krate, *base) {
+        UseResolution::Resolved(Ok(base)) => base,
+        resolution => return resolution,
     };
 
-    ast_use
-        .path(db)
-        .resolve_against(db, |id| resolve_name_against_crate(db, crate_source, id))
+    if let NameResolutionSym::SymModule(sym_module) = base.sym
+        && sym_module
+            .resolve_name_against_definitions(db, id.id)
+            .is_none()
+        && let Some(&reexport) = sym_module.ast_use_map(db).get(&id.id)
+        && reexport.visibility(db).is_some()
+    {
+        return match resolve_ast_use_query(db, reexport) {
+            UseResolution::Resolved(resolution) => UseResolution::Resolved(resolution.clone()),
+            UseResolution::Cycle(_) => UseResolution::Cycle(Some(reexport)),
+        };
+    }
+
+    UseResolution::Resolved(match base.resolve_relative_id(db, *id) {
+        Ok(Ok(r)) => Ok(r),
+        Ok(Err(base)) => Err(report_path_referencing_field(db, id, base.sym)),
+        Err(reported) => Err(reported),
+    })
 }
 
 fn resolve_name_against_crate<'db>(
@@ -777,15 +1240,42 @@ trait ResolveAgainst<'db> {
         self,
         db: &'db dyn crate::Db,
         op: impl FnOnce(SpannedIdentifier<'db>) -> Errors<NameResolutionSym<'db>>,
-    ) -> Option<NameResolution<'db>>;
+    ) -> Errors<NameResolution<'db>>;
 }
 
 impl<'db> ResolveAgainst<'db> for AstPath<'db> {
+    /// Resolve a path like `a.b.c`, using `op` to resolve the first identifier `a`.
+    /// This is used for `use` declarations, where `a` is a module within a crate
+    /// rather than a name in scope.
     fn resolve_against(
         self,
-        _db: &'db dyn crate::Db,
-        _op: impl FnOnce(SpannedIdentifier<'db>) -> Errors<NameResolutionSym<'db>>,
-    ) -> Option<NameResolution<'db>> {
-        todo!()
+        db: &'db dyn crate::Db,
+        op: impl FnOnce(SpannedIdentifier<'db>) -> Errors<NameResolutionSym<'db>>,
+    ) -> Errors<NameResolution<'db>> {
+        match self.kind(db) {
+            AstPathKind::Identifier(first_id) => Ok(NameResolution {
+                generics: vec![],
+                sym: op(*first_id)?,
+            }),
+            AstPathKind::GenericArgs { path: _, args } => Err(Diagnostic::error(
+                db,
+                args.span,
+                "generic arguments are not permitted in a `use`",
+            )
+            .label(
+                db,
+                Level::Error,
+                args.span,
+                "I did not expect generic arguments here",
+            )
+            .report(db)),
+            AstPathKind::Member { path, id } => {
+                let base = path.resolve_against(db, op)?;
+                match base.resolve_relative_id(db, *id)? {
+                    Ok(r) => Ok(r),
+                    Err(base) => Err(report_path_referencing_field(db, id, base.sym)),
+                }
+            }
+        }
     }
 }
@@ -24,6 +24,12 @@ pub enum ScopeItem<'db> {
 
     /// A function or method
     SymFunction(SymFunction<'db>),
+
+    /// The body of a function, in which nested classes and functions are declared.
+    /// Unlike [`ScopeItem::SymFunction`], this does not bring the function's generics
+    /// or parameters into scope (nor those of an enclosing class): nested items cannot refer to them.
+    #[no_from_impl]
+    FunctionBody(SymFunction<'db>),
 }
 
 pub trait ScopeTreeNode<'db>: Sized + Into<ScopeItem<'db>> {
@@ -88,6 +94,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::SymModule(sym) => sym.direct_super_scope(db),
             ScopeItem::Class(sym) => sym.direct_super_scope(db),
             ScopeItem::SymFunction(sym) => sym.direct_super_scope(db),
+            ScopeItem::FunctionBody(sym) => Some(sym.items_super_scope(db)),
         }
     }
 
@@ -97,6 +104,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::SymModule(sym) => sym.direct_generic_parameters(db),
             ScopeItem::Class(sym) => sym.direct_generic_parameters(db),
             ScopeItem::SymFunction(sym) => sym.direct_generic_parameters(db),
+            ScopeItem::FunctionBody(_) => no_generic_parameters(db),
         }
     }
 
@@ -106,6 +114,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::SymModule(sym) => sym.into_scope(db),
             ScopeItem::Class(sym) => sym.into_scope(db),
             ScopeItem::SymFunction(sym) => sym.into_scope(db),
+            ScopeItem::FunctionBody(sym) => sym.items_scope(db),
         }
     }
 
@@ -117,6 +126,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
         match self {
             ScopeItem::AstModule(_) => {}
             ScopeItem::SymModule(_) => {}
+            ScopeItem::FunctionBody(_) => {}
             ScopeItem::Class(sym) => {
                 sym.push_direct_ast_where_clauses(db, out);
             }
@@ -126,3 +136,8 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
         }
     }
 }
+
+#[salsa::tracked(return_ref)]
+fn no_generic_parameters<'db>(_db: &'db dyn crate::Db) -> Vec<SymVariable<'db>> {
+    vec![]
+}
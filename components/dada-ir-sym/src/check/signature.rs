@@ -1,11 +1,12 @@
 use dada_ir_ast::{
     ast::AstFunctionInput,
-    diagnostic::{Diagnostic, Err, Errors},
+    diagnostic::{Diagnostic, Err, Errors, Level, Reported},
     span::Spanned,
 };
+use salsa::Update;
 
 use crate::{
-    check::{env::Env, runtime::Runtime},
+    check::{env::Env, functions::checked_function_body, runtime::Runtime},
     ir::{
         functions::{SymFunction, SymFunctionSignature, SymFunctionSource, SymInputOutput},
         generics::SymWhereClause,
@@ -13,15 +14,143 @@ use crate::{
         types::{AnonymousPermSymbol, SymPerm, SymTy, SymTyName},
         variables::SymVariable,
     },
-    prelude::Symbol,
+    prelude::{CheckedSignature, Symbol},
 };
 
 use super::{CheckTyInEnv, generics::symbolify_ast_where_clause, scope_tree::ScopeTreeNode};
 
+/// True if `function` has no declared return type, in which case
+/// the return type is inferred from the body.
+pub(crate) fn has_inferred_output_ty<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> bool {
+    match function.source(db) {
+        SymFunctionSource::Function(ast_function) => {
+            ast_function.output_ty(db).is_none() && ast_function.body(db).is_some()
+        }
+        SymFunctionSource::Extern(_)
+        | SymFunctionSource::MainFunction(_)
+        | SymFunctionSource::Constructor(..) => false,
+    }
+}
+
+/// The return type of a function whose return type is [inferred](`has_inferred_output_ty`).
+#[derive(Clone, Debug, PartialEq, Eq, Update)]
+pub(crate) struct InferredOutputTy<'db> {
+    /// The type of the body, if it has one.
+    ty: Option<SymTy<'db>>,
+
+    /// Functions with inferred return types whose return types this one depends on,
+    /// because the body calls them, directly or through the bodies of the functions it calls.
+    /// If the function is among them, its return type depends on itself and cannot be inferred.
+    dependencies: Vec<SymFunction<'db>>,
+}
+
+/// Infers the return type of `function` from its body.
+///
+/// 💡 The body may call the function itself, directly or through other functions
+/// whose return types are inferred, creating a cycle. Salsa resolves it by starting
+/// from a return type that depends on itself and iterating. Every function on the cycle
+/// then depends on every other, and so on itself, whichever one inference started from.
+#[salsa::tracked(return_ref, cycle_fn = inferred_output_ty_cycle, cycle_initial = inferred_output_ty_initial)]
+pub(crate) fn inferred_output_ty<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> InferredOutputTy<'db> {
+    let checked = checked_function_body(db, function);
+    InferredOutputTy {
+        ty: checked.body.map(|body| body.ty(db)),
+        dependencies: checked.output_ty_dependencies.clone(),
+    }
+}
+
+fn inferred_output_ty_initial<'db>(
+    _db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+) -> InferredOutputTy<'db> {
+    InferredOutputTy {
+        ty: None,
+        dependencies: vec![function],
+    }
+}
+
+fn inferred_output_ty_cycle<'db>(
+    _db: &'db dyn crate::Db,
+    _value: &InferredOutputTy<'db>,
+    _count: u32,
+    _function: SymFunction<'db>,
+) -> salsa::CycleRecoveryAction<InferredOutputTy<'db>> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+/// Returns the signature of `function` for a call checked in `env`.
+///
+/// If the return type of `function` is inferred, the return type of the function
+/// being checked depends on it, which we record.
+pub(crate) fn signature_for_call<'db>(
+    env: &Env<'db>,
+    function: SymFunction<'db>,
+) -> Errors<SymFunctionSignature<'db>> {
+    let db = env.db();
+    if has_inferred_output_ty(db, function) {
+        let inferred = inferred_output_ty(db, function);
+        env.runtime()
+            .record_output_ty_dependency(function, &inferred.dependencies);
+
+        // 💡 Check this before asking for the signature: while inferring the return type,
+        // computing the signature may be what led to this call.
+        if inferred.dependencies.contains(&function) {
+            return Err(report_recursive_output_ty(db, function));
+        }
+    }
+
+    function.checked_signature(db)
+}
+
+/// Reports that the return type of `function` cannot be inferred because it depends on itself.
+fn report_recursive_output_ty<'db>(db: &'db dyn crate::Db, function: SymFunction<'db>) -> Reported {
+    let name_span = function.name_span(db);
+    Diagnostic::error(
+        db,
+        name_span,
+        format!(
+            "cannot infer the return type of `{name}` because it is called recursively",
+            name = function.name(db),
+        ),
+    )
+    .label(
+        db,
+        Level::Error,
+        name_span,
+        "the return type of this function is inferred from its body, which calls it",
+    )
+    .label(
+        db,
+        Level::Help,
+        name_span,
+        "consider declaring the return type with `-> T`",
+    )
+    .report(db)
+}
+
 pub fn check_function_signature<'db>(
     db: &'db dyn crate::Db,
     function: SymFunction<'db>,
 ) -> Errors<SymFunctionSignature<'db>> {
+    // 💡 If the return type is inferred, we type check the body *first*
+    // (outside of the runtime below). The body check uses an inference variable
+    // for the return type, so it does not depend on the signature.
+    let inferred_output_ty = if has_inferred_output_ty(db, function) {
+        let inferred = inferred_output_ty(db, function);
+        if inferred.dependencies.contains(&function) {
+            return Err(report_recursive_output_ty(db, function));
+        }
+        inferred.ty
+    } else {
+        None
+    };
+
     Runtime::execute(
         db,
         function.name_span(db),
@@ -36,6 +165,11 @@ pub fn check_function_signature<'db>(
                 ..
             } = prepare_env(db, runtime, function).await;
 
+            let output_ty_caller = match inferred_output_ty {
+                Some(output_ty_body) => async_output_ty(db, function, output_ty_body),
+                None => output_ty_caller,
+            };
+
             let scope = env.into_scope();
             Ok(SymFunctionSignature::new(
                 db,
@@ -108,11 +242,7 @@ pub async fn prepare_env<'db>(
     let output_ty_body: SymTy<'db> = output_ty(&mut env, &function).await;
     env.set_return_ty(output_ty_body);
 
-    let output_ty_caller = if function.effects(db).async_effect {
-        SymTy::named(db, SymTyName::Future, vec![output_ty_body.into()])
-    } else {
-        output_ty_body
-    };
+    let output_ty_caller = async_output_ty(db, function, output_ty_body);
 
     // Symbolify the where-clauses
     let mut ast_where_clauses = vec![];
@@ -165,11 +295,37 @@ async fn set_variable_ty_from_input<'db>(env: &mut Env<'db>, input: &AstFunction
     }
 }
 
+/// The return type of the fn from the perspective of the caller.
+/// For an async fn, this is a future.
+fn async_output_ty<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+    output_ty_body: SymTy<'db>,
+) -> SymTy<'db> {
+    if function.effects(db).async_effect {
+        SymTy::named(db, SymTyName::Future, vec![output_ty_body.into()])
+    } else {
+        output_ty_body
+    }
+}
+
 async fn output_ty<'db>(env: &mut Env<'db>, function: &SymFunction<'db>) -> SymTy<'db> {
     let db = env.db();
     match function.source(db) {
         SymFunctionSource::Function(ast_function) => match ast_function.output_ty(db) {
             Some(ast_ty) => ast_ty.check_in_env(env).await,
+
+            // No declared return type: infer it from the body.
+            // (When computing the signature, this is replaced by the type of the checked body.)
+            None if ast_function.body(db).is_some() => {
+                env.fresh_ty_inference_var(ast_function.name(db).span)
+            }
+
+            // No body to infer from (e.g., a declaration).
+            None => SymTy::unit(db),
+        },
+        SymFunctionSource::Extern(ast_extern) => match ast_extern.function(db).output_ty(db) {
+            Some(ast_ty) => ast_ty.check_in_env(env).await,
             None => SymTy::unit(db),
         },
         SymFunctionSource::MainFunction(_) => SymTy::unit(env.db()),
@@ -1,16 +1,27 @@
-use dada_ir_ast::{ast::AstStatement, span::Span};
+use dada_ir_ast::{
+    ast::{AstExprKind, AstPattern, AstStatement},
+    diagnostic::{Diagnostic, Level},
+    span::{Span, Spanned},
+};
 use dada_util::boxed_async_fn;
 
 use crate::{
-    check::{CheckExprInEnv, env::Env, report::InvalidInitializerType},
+    check::{
+        CheckExprInEnv,
+        coercions::coerce_numeric,
+        env::Env,
+        exprs::check_statement_expr,
+        lints::{Lint, lint_diagnostic},
+        report::InvalidInitializerType,
+    },
     ir::{
         exprs::{SymExpr, SymExprKind},
-        types::SymTy,
+        types::{SymTy, SymTyKind},
         variables::SymVariable,
     },
 };
 
-use super::{CheckTyInEnv, live_places::LivePlaces};
+use super::{CheckTyInEnv, live_places::LivePlaces, patterns::check_let_pattern};
 
 #[boxed_async_fn]
 pub async fn check_block_statements<'db>(
@@ -27,27 +38,39 @@ pub async fn check_block_statements<'db>(
 
     match first {
         AstStatement::Let(s) => {
-            let lv = SymVariable::new_local(db, s.name(db).id, s.name(db).span);
+            let name = match s.pattern(db) {
+                AstPattern::Id(name) => *name,
+                AstPattern::Tuple(_) | AstPattern::Aggregate(..) => {
+                    return check_let_pattern(env, block_span, *s, rest).await;
+                }
+            };
+            let lv = SymVariable::new_local(db, name.id, name.span);
 
             // For explicit local variables, we compute their type as a full symbol type first.
             let ty = match s.ty(db) {
                 Some(ty) => ty.check_in_env(env).await,
-                None => env.fresh_ty_inference_var(s.name(db).span),
+                None => env.fresh_ty_inference_var(name.span),
             };
 
             let (initializer, body) = env
                 .join(
                     async |env| match s.initializer(db) {
                         Some(initializer) => {
+                            if let AstExprKind::Block(block) = &*initializer.kind
+                                && let Some(last) = block.trailing_statement(db)
+                            {
+                                report_valueless_block(env, initializer.span, last);
+                            }
                             let initializer = initializer
                                 .check_in_env(env, LivePlaces::fixme())
                                 .await
                                 .into_expr_with_enclosed_temporaries(env);
+                            let initializer = coerce_numeric(env, initializer, ty);
                             env.spawn_require_assignable_type(
                                 LivePlaces::fixme(),
                                 initializer.ty(db),
                                 ty,
-                                &InvalidInitializerType::new(lv, s.name(db).span, ty, initializer),
+                                &InvalidInitializerType::new(lv, name.span, ty, initializer),
                             );
                             Some(initializer)
                         }
@@ -78,7 +101,7 @@ pub async fn check_block_statements<'db>(
 
         AstStatement::Expr(e) => {
             let check_e = async |env: &mut Env<'db>| {
-                e.check_in_env(env, LivePlaces::fixme())
+                check_statement_expr(e, env, LivePlaces::fixme(), !rest.is_empty())
                     .await
                     .into_expr_with_enclosed_temporaries(env)
             };
@@ -92,13 +115,100 @@ pub async fn check_block_statements<'db>(
                         check_block_statements(env, live_after, block_span, rest).await
                     })
                     .await;
+
+                // If `ce` never finishes, neither does the block,
+                // whatever the type of the (dead) statements after it.
+                let ty = if diverges(db, ce) {
+                    report_unreachable(env, ce, rest);
+                    SymTy::never(db)
+                } else {
+                    re.ty(db)
+                };
                 SymExpr::new(
                     db,
                     ce.span(db).to(db, re.span(db)),
-                    re.ty(db),
+                    ty,
                     SymExprKind::Semi(ce, re),
                 )
             }
         }
+
+        AstStatement::Aggregate(_) | AstStatement::Function(_) => {
+            // Nested items are created along with the enclosing function
+            // (see `SymFunction::nested_items`) and checked on their own,
+            // so they contribute nothing to the block itself.
+            if !env
+                .scope
+                .function_body()
+                .is_some_and(|function| function.body_statements(db).contains(first))
+            {
+                report_misplaced_item(env, first);
+            }
+            check_block_statements(env, live_after, block_span, rest).await
+        }
     }
 }
+
+/// Reports an item declared in a nested block rather than at the top of a function body.
+fn report_misplaced_item<'db>(env: &Env<'db>, item: &AstStatement<'db>) {
+    let db = env.db();
+    let span = item.span(db);
+    env.report(
+        Diagnostic::error(db, span, "item declared in a nested block").label(
+            db,
+            Level::Error,
+            span,
+            "classes and functions can only be declared at the top level of a function body",
+        ),
+    );
+}
+
+/// Reports a block used as a value that ends with `last`, a statement other than an expression.
+fn report_valueless_block<'db>(env: &Env<'db>, block_span: Span<'db>, last: &AstStatement<'db>) {
+    let db = env.db();
+    let last_span = last.span(db);
+    env.report(
+        Diagnostic::error(db, last_span, "block used as a value ends with a statement")
+            .label(
+                db,
+                Level::Error,
+                last_span,
+                "a block's value is its trailing expression, but this is not an expression",
+            )
+            .label(db, Level::Info, block_span, "this block is used as a value"),
+    );
+}
+
+/// True if `expr` is known to never finish, e.g., a `return` or a block ending in one.
+///
+/// 💡 We only look at the type as it was computed for `expr`, so an expression
+/// whose type is an inference variable later inferred to be `!` does not count.
+fn diverges<'db>(db: &'db dyn crate::Db, expr: SymExpr<'db>) -> bool {
+    matches!(expr.ty(db).kind(db), SymTyKind::Never)
+}
+
+/// Reports the `unreachable_code` lint for the statements `rest`
+/// that follow the diverging expression `expr`.
+fn report_unreachable<'db>(env: &Env<'db>, expr: SymExpr<'db>, rest: &[AstStatement<'db>]) {
+    let db = env.db();
+    let (Some(first), Some(last)) = (rest.first(), rest.last()) else {
+        return;
+    };
+    let dead_span = first.span(db).to(db, last.span(db));
+    let Some(diagnostic) =
+        lint_diagnostic(db, Lint::UnreachableCode, dead_span, "unreachable code")
+    else {
+        return;
+    };
+    let level = diagnostic.level;
+    env.report(
+        diagnostic
+            .label(db, level, dead_span, "this code will never be executed")
+            .label(
+                db,
+                Level::Info,
+                expr.span(db),
+                "any code following this expression is unreachable",
+            ),
+    );
+}
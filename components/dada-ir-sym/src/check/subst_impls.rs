@@ -2,8 +2,8 @@ use dada_ir_ast::ast::PermissionOp;
 
 use crate::ir::{
     exprs::{
-        SymBinaryOp, SymByteLiteral, SymExpr, SymExprKind, SymLiteral, SymMatchArm, SymPlaceExpr,
-        SymPlaceExprKind,
+        SymBinaryOp, SymByteLiteral, SymExpr, SymExprKind, SymLiteral, SymLoop, SymMatchArm,
+        SymPlaceExpr, SymPlaceExprKind,
     },
     functions::SymFunctionSignature,
     subst::{Subst, SubstWith, SubstitutionFns, identity_subst},
@@ -79,6 +79,26 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymExprKind<'db> {
                     body.subst_with(db, bound_vars, subst_fns)
                 }),
             },
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body,
+            } => {
+                let lvs: Vec<_> = lvs
+                    .iter()
+                    .map(|&(lv, ty)| (lv, ty.subst_with(db, bound_vars, subst_fns)))
+                    .collect();
+                let initializer = initializer.subst_with(db, bound_vars, subst_fns);
+                let depth = bound_vars.len();
+                bound_vars.extend(lvs.iter().map(|&(lv, _)| lv));
+                let body = body.subst_with(db, bound_vars, subst_fns);
+                bound_vars.truncate(depth);
+                SymExprKind::LetTuple {
+                    lvs,
+                    initializer,
+                    body,
+                }
+            }
             SymExprKind::Await {
                 future,
                 await_keyword,
@@ -109,6 +129,26 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymExprKind<'db> {
             SymExprKind::Return(sym_expr) => {
                 SymExprKind::Return(sym_expr.subst_with(db, bound_vars, subst_fns))
             }
+            SymExprKind::Loop { sym_loop, body } => SymExprKind::Loop {
+                sym_loop: sym_loop.subst_with(db, bound_vars, subst_fns),
+                body: body.subst_with(db, bound_vars, subst_fns),
+            },
+            SymExprKind::Break {
+                from_loop,
+                with_value,
+            } => SymExprKind::Break {
+                from_loop: from_loop.subst_with(db, bound_vars, subst_fns),
+                with_value: with_value.subst_with(db, bound_vars, subst_fns),
+            },
+            SymExprKind::Continue { to_loop } => SymExprKind::Continue {
+                to_loop: to_loop.subst_with(db, bound_vars, subst_fns),
+            },
+            SymExprKind::IsSome(sym_place_expr) => {
+                SymExprKind::IsSome(sym_place_expr.subst_with(db, bound_vars, subst_fns))
+            }
+            SymExprKind::Drop(sym_place_expr) => {
+                SymExprKind::Drop(sym_place_expr.subst_with(db, bound_vars, subst_fns))
+            }
             SymExprKind::Not { operand, op_span } => SymExprKind::Not {
                 operand: operand.subst_with(db, bound_vars, subst_fns),
                 op_span: op_span.subst_with(db, bound_vars, subst_fns),
@@ -118,6 +158,9 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymExprKind<'db> {
                 sym_expr.subst_with(db, bound_vars, subst_fns),
                 sym_expr1.subst_with(db, bound_vars, subst_fns),
             ),
+            SymExprKind::Cast(sym_expr) => {
+                SymExprKind::Cast(sym_expr.subst_with(db, bound_vars, subst_fns))
+            }
             SymExprKind::Aggregate { ty, fields } => SymExprKind::Aggregate {
                 ty: ty.subst_with(db, bound_vars, subst_fns),
                 fields: fields.subst_with(db, bound_vars, subst_fns),
@@ -244,6 +287,7 @@ identity_subst! {
         PermissionOp,
         SymLiteral,
         SymByteLiteral<'db>,
+        SymLoop<'db>,
     }
 }
 
@@ -28,7 +28,7 @@ pub async fn require_my_numeric_type<'db>(
     let my_perm = SymPerm::my(env.db());
     env.require_both(
         async |env| require_sub_perms(env, live_after, my_perm, perm, or_else).await,
-        async |env| require_numeric_red_type(env, red_ty, or_else).await,
+        async |env| require_numeric_red_type(env, red_ty, false, or_else).await,
     )
     .await
 }
@@ -39,13 +39,26 @@ pub async fn require_numeric_type<'db>(
     or_else: &dyn OrElse<'db>,
 ) -> Errors<()> {
     let (red_ty, _) = ty.to_red_ty(env);
-    require_numeric_red_type(env, red_ty, or_else).await
+    require_numeric_red_type(env, red_ty, false, or_else).await
 }
 
+/// Requires `ty` to be a type whose values can be compared with operators like `<`:
+/// a numeric type or `char`.
+pub async fn require_comparable_type<'db>(
+    env: &mut Env<'db>,
+    ty: SymTy<'db>,
+    or_else: &dyn OrElse<'db>,
+) -> Errors<()> {
+    let (red_ty, _) = ty.to_red_ty(env);
+    require_numeric_red_type(env, red_ty, true, or_else).await
+}
+
+/// Requires `red_ty` to be numeric or, if `allow_char` is true, `char`.
 #[boxed_async_fn]
 async fn require_numeric_red_type<'db>(
     env: &mut Env<'db>,
     red_ty: RedTy<'db>,
+    allow_char: bool,
     or_else: &dyn OrElse<'db>,
 ) -> Errors<()> {
     let db = env.db();
@@ -53,6 +66,7 @@ async fn require_numeric_red_type<'db>(
         RedTy::Error(reported) => Err(reported),
         RedTy::Named(sym_ty_name, _) => match sym_ty_name {
             SymTyName::Primitive(sym_primitive) => match sym_primitive.kind(db) {
+                SymPrimitiveKind::Char if allow_char => Ok(()),
                 SymPrimitiveKind::Bool | SymPrimitiveKind::Char => {
                     Err(or_else.report(env, Because::JustSo))
                 }
@@ -67,7 +81,11 @@ async fn require_numeric_red_type<'db>(
             }
         },
 
-        RedTy::Var(_) | RedTy::Never => Err(or_else.report(env, Because::JustSo)),
+        // `!` has no values, so it is trivially as numeric as the other operand.
+        // This lets a `return` (say) appear as the operand of an operator.
+        RedTy::Never => Ok(()),
+
+        RedTy::Var(_) => Err(or_else.report(env, Because::JustSo)),
 
         RedTy::Infer(infer) => {
             // For inference variables: find the current lower bound
@@ -81,6 +99,7 @@ async fn require_numeric_red_type<'db>(
             require_numeric_red_type(
                 env,
                 lower_red_ty.clone(),
+                allow_char,
                 &or_else.map_because(move |_| {
                     Because::InferredLowerBound(lower_red_ty.clone(), arc_or_else.clone())
                 }),
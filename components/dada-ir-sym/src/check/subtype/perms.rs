@@ -218,7 +218,7 @@ fn require_red_perm_sub_red_perm<'db>(
         }
 
         // No suitable upper chain for `lower_chain`
-        return Err(or_else.report(env, Because::JustSo));
+        return Err(or_else.report(env, Because::PermChainMismatch(lower_chain, upper_perm)));
     }
 
     Ok(())
@@ -1,7 +1,12 @@
 use dada_ir_ast::diagnostic::Errors;
 
 use crate::{
-    check::{env::Env, inference::Direction, live_places::LivePlaces, report},
+    check::{
+        env::Env,
+        inference::Direction,
+        live_places::LivePlaces,
+        report::{self, Because, OrElse, OrElseHelper},
+    },
     ir::{indices::InferVarIndex, types::SymPerm},
 };
 
@@ -14,20 +19,30 @@ pub async fn relate_infer_bounds<'db>(env: &mut Env<'db>, infer: InferVarIndex)
     let mut upper_bound = None;
 
     let mut bounds = env.term_bounds(SymPerm::my(env.db()), infer);
-    while let Some((direction, new_bound)) = bounds.next(env).await {
+    while let Some((direction, new_bound, new_or_else)) = bounds.next(env).await {
         match direction {
-            Direction::FromBelow => lower_bound = Some(new_bound),
-            Direction::FromAbove => upper_bound = Some(new_bound),
+            Direction::FromBelow => lower_bound = Some((new_bound, new_or_else)),
+            Direction::FromAbove => upper_bound = Some((new_bound, new_or_else)),
         }
 
-        if let (Some(lower), Some(upper)) = (lower_bound, upper_bound) {
-            // FIXME: the iterator should be yielding up ArcOrElse values
+        if let (Some((lower, lower_or_else)), Some((upper, upper_or_else))) =
+            (lower_bound.clone(), upper_bound.clone())
+        {
             require_sub_terms(
                 env,
                 LivePlaces::infer_bounds(),
                 lower,
                 upper,
-                &report::BadSubtermError::new(env.infer_var_span(infer), lower, upper),
+                &(&report::BadSubtermError::new(env.infer_var_span(infer), lower, upper)
+                    as &dyn OrElse<'db>)
+                    .map_because(move |_| {
+                        Because::InferredConflict(
+                            lower,
+                            lower_or_else.clone(),
+                            upper,
+                            upper_or_else.clone(),
+                        )
+                    }),
             )
             .await?;
         }
@@ -8,6 +8,9 @@ use crate::{
         env::Env,
         inference::{Direction, InferVarKind},
         live_places::LivePlaces,
+        predicates::{
+            is_provably_owned::perm_is_provably_owned, is_provably_shared::perm_is_provably_shared,
+        },
         red::RedTy,
         report::{Because, OrElse},
         subtype::perms::require_sub_perms,
@@ -123,6 +126,14 @@ pub async fn require_sub_red_terms<'db>(
                             upper_generic = upper_perm.apply_to(db, upper_generic);
                         }
 
+                        // Values can be written through a `mut` permission, so
+                        // `mut C[A] <: mut C[B]` would let a `B` be stored where an `A`
+                        // is expected. Covariance is only sound when the permission is
+                        // known to rule that out, so anything else (e.g., a generic
+                        // permission `P`) requires invariance.
+                        let at_least_contravariant = variance.at_least_contravariant
+                            || !perm_permits_covariance(env, upper_perm).await?;
+
                         env.require_both(
                             async |env| {
                                 if variance.at_least_covariant {
@@ -139,7 +150,7 @@ pub async fn require_sub_red_terms<'db>(
                                 }
                             },
                             async |env| {
-                                if variance.at_least_contravariant {
+                                if at_least_contravariant {
                                     require_sub_terms(
                                         env,
                                         live_after,
@@ -194,6 +205,16 @@ pub async fn require_sub_red_terms<'db>(
     }
 }
 
+/// True if `perm` is known to be shared or owned,
+/// so that the value it applies to cannot be overwritten by someone else holding it.
+async fn perm_permits_covariance<'db>(env: &mut Env<'db>, perm: SymPerm<'db>) -> Errors<bool> {
+    env.either(
+        async |env| perm_is_provably_shared(env, perm).await,
+        async |env| perm_is_provably_owned(env, perm).await,
+    )
+    .await
+}
+
 /// Require that `lower <: upper`, where both are type inference variables.
 /// This will insert record `upper` as an upper bound of `lower`.
 /// If `upper` is a new upper bound, it will begin looping,
@@ -29,24 +29,16 @@ impl<'db> RedTyExt<'db> for RedTy<'db> {
     fn display<'a>(&'a self, env: &'a Env<'db>) -> impl std::fmt::Display {
         struct Wrapper<'a, 'db> {
             ty: &'a RedTy<'db>,
-            #[expect(dead_code)] // FIXME?
             env: &'a Env<'db>,
         }
 
         impl std::fmt::Display for Wrapper<'_, '_> {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                match &self.ty {
-                    RedTy::Error(_reported) => write!(f, "<error>"),
-                    RedTy::Named(sym_ty_name, sym_generic_terms) => {
-                        write!(f, "{sym_ty_name}[{sym_generic_terms:?}]")
-                    }
-                    RedTy::Never => write!(f, "!"),
-
-                    // FIXME: do better by querying the env state
-                    RedTy::Infer(v) => write!(f, "?{}", v.as_usize()),
-
-                    RedTy::Var(sym_variable) => write!(f, "{sym_variable}"),
+                match self.ty {
                     RedTy::Perm => write!(f, "<perm>"),
+
+                    // FIXME: do better for inference variables by querying the env state
+                    ty => write!(f, "{}", ty.clone().into_sym_ty(self.env.db())),
                 }
             }
         }
@@ -140,7 +132,7 @@ impl<'db, T: ToRedChainVec<'db>> ToRedPerm<'db> for T {
             direction,
             Consumer::new(async |env, chainvec| {
                 consumer
-                    .consume(env, RedPerm::new(env.db(), chainvec))
+                    .consume(env, RedPerm::normalized(env.db(), chainvec))
                     .await
             }),
         )
@@ -235,7 +227,7 @@ async fn expand_tail<'db>(
         // or there is no last link (i.e., we have `my`),
         // then we are done expanding. Push resulting chain and recurse.
         Some(RedLink::Our) | Some(RedLink::Var(_)) | None => {
-            expanded_chains.push(RedChain::new(db, linkvec));
+            expanded_chains.push(RedChain::normalized(db, linkvec));
             return expand_tail(
                 env,
                 live_after,
@@ -267,7 +259,7 @@ async fn expand_tail<'db>(
                         // If the link vec we get back is `my`, then push a fully expanded chain.
                         // This corresponds to e.g. `mut[a]` where `a: my String` -- the permission
                         // is complete.
-                        expanded_chains.push(RedChain::new(db, linkvec.clone()));
+                        expanded_chains.push(RedChain::normalized(db, linkvec.clone()));
                     } else {
                         // Otherwise, concatenate this new link vec with our original
                         // and push it back onto the "unexpanded" list. We will recursively
@@ -361,7 +353,7 @@ impl<'db> ToRedLinkVecs<'db> for SymPerm<'db> {
             }
             SymPermKind::Infer(v) => {
                 let mut bounds = env.red_perm_bounds(v);
-                while let Some((direction_bound, red_perm)) = bounds.next(env).await {
+                while let Some((direction_bound, red_perm, _)) = bounds.next(env).await {
                     if direction_bound == direction {
                         for &chain in red_perm.chains(db) {
                             let links = chain.links(db).to_vec();
@@ -3,22 +3,24 @@ use dada_ir_ast::{
     diagnostic::{Diagnostic, Err, Level, ordinal},
     span::{Span, Spanned},
 };
-use dada_util::{boxed_async_fn, indirect};
+use dada_util::{Map, boxed_async_fn, indirect};
 
 use crate::{
     check::{
         env::Env,
         exprs::ExprResultKind,
+        report::ClassWhereClauseError,
         scope::{NameResolution, NameResolutionSym, Resolve},
     },
     ir::{
+        subst::{Subst, SubstWith, SubstitutionFns},
         types::{
             AnonymousPermSymbol, HasKind, SymGenericKind, SymGenericTerm, SymPerm, SymPermKind,
-            SymPlace, SymTy,
+            SymPlace, SymTy, SymTyKind, SymTyName,
         },
         variables::FromVar,
     },
-    prelude::Symbol,
+    prelude::{CheckedWhereClauses, Symbol},
 };
 
 use super::{CheckTyInEnv, exprs::ExprResult, member_lookup::MemberLookup};
@@ -45,7 +47,11 @@ impl<'db> CheckTyInEnv<'db> for AstTy<'db> {
                     }
                 }
                 match ast_path.resolve_in(env).await {
-                    Ok(r) => name_resolution_to_sym_ty(db, r, ast_path, generics),
+                    Ok(r) => {
+                        let sym_ty = name_resolution_to_sym_ty(db, r, ast_path, generics);
+                        require_class_where_clauses(env, self.span(db), sym_ty);
+                        sym_ty
+                    }
                     Err(r) => SymTy::err(db, r),
                 }
             }
@@ -54,11 +60,65 @@ impl<'db> CheckTyInEnv<'db> for AstTy<'db> {
                 let symbol = decl.symbol(db);
                 SymTy::var(db, symbol)
             }
+
+            AstTyKind::Never => SymTy::never(db),
         })
         .await
     }
 }
 
+/// If `sym_ty` names a class, require that its generic arguments satisfy
+/// the where-clauses declared on the class, reporting errors at `span`.
+///
+/// Where-clauses whose subject is a generic variable are skipped:
+/// the where-clauses in scope are not yet available as assumptions,
+/// so they would always be reported.
+fn require_class_where_clauses<'db>(env: &Env<'db>, span: Span<'db>, sym_ty: SymTy<'db>) {
+    let db = env.db();
+    let SymTyKind::Named(SymTyName::Aggregate(aggregate), generics) = sym_ty.kind(db) else {
+        return;
+    };
+    let Ok(where_clauses) = aggregate.checked_where_clauses(db) else {
+        return;
+    };
+    let map: Map<_, _> = aggregate
+        .generic_variables(db)
+        .iter()
+        .copied()
+        .zip(generics.iter().copied())
+        .collect();
+    for where_clause in where_clauses {
+        let where_clause = where_clause.subst_vars(db, &map);
+        if mentions_generic_variable(db, where_clause.subject(db)) {
+            continue;
+        }
+        env.spawn_require_where_clause(
+            where_clause,
+            &ClassWhereClauseError::new(span, *aggregate, where_clause),
+        );
+    }
+}
+
+/// True if `term` references a generic type or permission variable.
+pub(crate) fn mentions_generic_variable<'db>(
+    db: &'db dyn crate::Db,
+    term: SymGenericTerm<'db>,
+) -> bool {
+    let mut found = false;
+    term.subst_with(
+        db,
+        &mut Default::default(),
+        &mut SubstitutionFns {
+            free_var: &mut |var| {
+                found |= var.kind(db) != SymGenericKind::Place;
+                None
+            },
+            infer_var: &mut |_| None,
+        },
+    );
+    found
+}
+
 fn name_resolution_to_sym_ty<'db>(
     db: &'db dyn crate::Db,
     name_resolution: NameResolution<'db>,
@@ -95,6 +155,27 @@ fn name_resolution_to_sym_ty<'db>(
         }
 
         NameResolutionSym::SymAggregate(sym_class) => {
+            // `Self` arrives with the class generics already supplied.
+            if !name_resolution.generics.is_empty() {
+                if let Some(&(span, _)) = generics.first() {
+                    return SymTy::err(
+                        db,
+                        Diagnostic::error(db, span, "`Self` does not expect generic arguments")
+                            .label(
+                                db,
+                                Level::Error,
+                                span,
+                                format!(
+                                    "`Self` already refers to `{}` with its generic arguments",
+                                    sym_class.name(db)
+                                ),
+                            )
+                            .report(db),
+                    );
+                }
+                return SymTy::named(db, sym_class.into(), name_resolution.generics);
+            }
+
             let expected = sym_class.len_generics(db);
             let found = generics.len();
             if found != expected {
@@ -197,6 +278,12 @@ fn name_resolution_to_sym_ty<'db>(
                         source.span(db),
                         format!("I expected a type here, but I found a `{generic_kind}`"),
                     )
+                    .label(
+                        db,
+                        Level::Info,
+                        var.span(db),
+                        format!("`{var}` is declared as a `{generic_kind}` here"),
+                    )
                     .report(db),
                 );
             }
@@ -245,6 +332,21 @@ impl<'db> CheckTyInEnv<'db> for AstGenericTerm<'db> {
                 Ok(r) => name_resolution_to_generic_term(env.db(), r, id),
                 Err(r) => r.into(),
             },
+            AstGenericTerm::Infer(span) => {
+                // Call sites handle `_` themselves, since they know the kind of term to infer.
+                let db = env.db();
+                SymGenericTerm::err(
+                    db,
+                    Diagnostic::error(db, span, "`_` is not allowed here")
+                        .label(
+                            db,
+                            Level::Error,
+                            span,
+                            "only the generic arguments of a function call can be inferred",
+                        )
+                        .report(db),
+                )
+            }
         }
     }
 }
@@ -316,9 +418,8 @@ fn name_resolution_to_sym_perm<'db>(
         | NameResolutionSym::SymAggregate(_)
         | NameResolutionSym::SymFunction(_)
         | NameResolutionSym::SymVariable(_)
-        | NameResolutionSym::SymPrimitive(_) => SymPerm::err(
-            db,
-            Diagnostic::error(
+        | NameResolutionSym::SymPrimitive(_) => {
+            let mut diagnostic = Diagnostic::error(
                 db,
                 source.span(db),
                 format!(
@@ -334,9 +435,17 @@ fn name_resolution_to_sym_perm<'db>(
                     "I expected a permission, but I found {}",
                     name_resolution.sym.describe(db)
                 ),
-            )
-            .report(db),
-        ),
+            );
+            if let NameResolutionSym::SymVariable(var) = name_resolution.sym {
+                diagnostic = diagnostic.label(
+                    db,
+                    Level::Info,
+                    var.span(db),
+                    format!("`{var}` is declared as a `{}` here", var.kind(db)),
+                );
+            }
+            SymPerm::err(db, diagnostic.report(db))
+        }
     }
 }
 
@@ -4,6 +4,7 @@
 
 pub mod binder;
 pub mod classes;
+mod documentation;
 pub mod exprs;
 pub mod functions;
 pub mod generics;
@@ -14,3 +15,4 @@ pub mod primitive;
 pub mod subst;
 pub mod types;
 pub mod variables;
+pub mod visit;
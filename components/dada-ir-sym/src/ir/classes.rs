@@ -1,7 +1,10 @@
 use std::borrow::Cow;
 
 use dada_ir_ast::{
-    ast::{AstAggregate, AstAggregateKind, AstFieldDecl, AstMember, Identifier, SpannedIdentifier},
+    ast::{
+        AstAggregate, AstAggregateKind, AstAttribute, AstDocComment, AstFieldDecl, AstMember,
+        Identifier, SpannedIdentifier, VisibilityKind,
+    },
     span::{SourceSpanned, Span, Spanned},
 };
 use dada_parser::prelude::*;
@@ -64,10 +67,14 @@ impl<'db> SymAggregate<'db> {
         }
     }
 
-    /// Variance of generic parameters
+    /// Variance of generic parameters.
+    ///
+    /// Generic parameters are covariant. Subtyping through a permission that is not
+    /// known to be shared or owned (such as `mut`) additionally requires them to be
+    /// contravariant (and hence invariant), see `require_sub_red_terms`.
     pub fn variances(&self, db: &'db dyn crate::Db) -> Vec<Variance> {
         let len_generics = self.len_generics(db);
-        // FIXME
+        // FIXME: support explicit variance annotations
         vec![Variance::covariant(); len_generics]
     }
 
@@ -89,6 +96,26 @@ impl<'db> SymAggregate<'db> {
         self.source(db).name_span(db)
     }
 
+    /// The visibility modifier declared on the class, if any.
+    pub fn visibility(self, db: &'db dyn crate::Db) -> Option<VisibilityKind> {
+        self.source(db).visibility(db).map(|v| v.kind)
+    }
+
+    /// The generic parameters declared on the class.
+    pub fn generic_variables(self, db: &'db dyn crate::Db) -> &'db [SymVariable<'db>] {
+        &self.symbols(db).generic_variables
+    }
+
+    /// Doc comment written on the class declaration.
+    pub fn doc_comment(self, db: &'db dyn crate::Db) -> &'db Option<AstDocComment<'db>> {
+        self.source(db).doc_comment(db)
+    }
+
+    /// Attributes (e.g., `#[allow(...)]`) written on the class declaration.
+    pub fn attributes(self, db: &'db dyn crate::Db) -> &'db [AstAttribute<'db>] {
+        self.source(db).attributes(db)
+    }
+
     /// Span where generics are declared (possibly the name span, if there are no generics)
     pub fn generics_span(&self, db: &'db dyn crate::Db) -> Span<'db> {
         if let Some(generics) = self.source(db).generics(db) {
@@ -0,0 +1,64 @@
+//! Access to the doc comments (`## ...`) written on items, for tools like
+//! the documentation generator and the language server.
+
+use dada_ir_ast::ast::AstDocComment;
+
+use crate::{
+    ir::{
+        classes::{SymAggregate, SymClassMember, SymField},
+        functions::{SymFunction, SymFunctionSource},
+        module::SymItem,
+    },
+    prelude::Documentation,
+};
+
+fn text<'db>(doc_comment: &'db Option<AstDocComment<'db>>) -> Option<&'db str> {
+    doc_comment
+        .as_ref()
+        .map(|doc_comment| doc_comment.text.as_str())
+}
+
+impl<'db> Documentation<'db> for SymAggregate<'db> {
+    fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str> {
+        text(self.doc_comment(db))
+    }
+}
+
+impl<'db> Documentation<'db> for SymFunction<'db> {
+    fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str> {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => text(ast_function.doc_comment(db)),
+            SymFunctionSource::Extern(ast_extern) => text(ast_extern.function(db).doc_comment(db)),
+
+            // The constructor is documented by the class it constructs.
+            SymFunctionSource::Constructor(_, ast_aggregate) => text(ast_aggregate.doc_comment(db)),
+
+            SymFunctionSource::MainFunction(_) => None,
+        }
+    }
+}
+
+impl<'db> Documentation<'db> for SymField<'db> {
+    fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str> {
+        text(self.source(db).doc_comment(db))
+    }
+}
+
+impl<'db> Documentation<'db> for SymClassMember<'db> {
+    fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str> {
+        match self {
+            SymClassMember::SymField(field) => field.documentation(db),
+            SymClassMember::SymFunction(function) => function.documentation(db),
+        }
+    }
+}
+
+impl<'db> Documentation<'db> for SymItem<'db> {
+    fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str> {
+        match self {
+            SymItem::SymClass(aggregate) => aggregate.documentation(db),
+            SymItem::SymFunction(function) => function.documentation(db),
+            SymItem::SymPrimitive(_) => None,
+        }
+    }
+}
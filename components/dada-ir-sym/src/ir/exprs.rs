@@ -20,7 +20,7 @@ use crate::{
     ir::variables::{FromVar, SymVariable},
 };
 use dada_ir_ast::{
-    ast::{AstBinaryOp, PermissionOp},
+    ast::{AstBinaryOp, Identifier, PermissionOp},
     diagnostic::{Err, Reported},
     span::{SourceSpanned, Span},
 };
@@ -130,6 +130,15 @@ pub enum SymExprKind<'db> {
         body: SymExpr<'db>,
     },
 
+    /// `let ($lv0: $ty0, $lv1: $ty1, ...) = $initializer in $body`,
+    /// giving each element of the tuple `$initializer` to the corresponding variable.
+    /// Nested tuple patterns bind the inner tuple to a temporary that is destructured in turn.
+    LetTuple {
+        lvs: Vec<(SymVariable<'db>, SymTy<'db>)>,
+        initializer: SymExpr<'db>,
+        body: SymExpr<'db>,
+    },
+
     /// `future.await`
     Await {
         future: SymExpr<'db>,
@@ -145,6 +154,11 @@ pub enum SymExprKind<'db> {
     /// `$0.lease` etc
     PermissionOp(PermissionOp, SymPlaceExpr<'db>),
 
+    /// True if the `Option` stored in the place holds a value
+    /// or the `Result` stored there is an `Ok`, i.e., if its first field has a value.
+    /// Created for the condition of `if let Some(x) = ...` and for `x?`.
+    IsSome(SymPlaceExpr<'db>),
+
     /// `$0[$1..]($2..)`
     ///
     /// During construction we ensure that the arities match and terms are well-kinded
@@ -158,6 +172,28 @@ pub enum SymExprKind<'db> {
     /// Return a value from this function
     Return(SymExpr<'db>),
 
+    /// `loop { $body }`, executing `body` repeatedly until it
+    /// breaks out of the loop (or returns).
+    Loop {
+        sym_loop: SymLoop<'db>,
+        body: SymExpr<'db>,
+    },
+
+    /// `break $with_value`, exiting `from_loop` so that it produces `with_value`.
+    /// `with_value` is `()` if no value was given.
+    Break {
+        from_loop: SymLoop<'db>,
+        with_value: SymExpr<'db>,
+    },
+
+    /// `continue`, starting the next iteration of `to_loop`.
+    Continue { to_loop: SymLoop<'db> },
+
+    /// Drop the value owned by the place, if it still owns one
+    /// (it may have been given away, for example).
+    /// Only present after drop elaboration, see [`ElaboratedBody`](`crate::prelude::ElaboratedBody`).
+    Drop(SymPlaceExpr<'db>),
+
     /// Boolean not
     Not {
         operand: SymExpr<'db>,
@@ -167,6 +203,11 @@ pub enum SymExprKind<'db> {
     /// `a + b` etc
     BinaryOp(SymBinaryOp, SymExpr<'db>, SymExpr<'db>),
 
+    /// `a as T`, converting a value of one numeric type to the numeric type
+    /// of the cast expression itself. Casts between other types are only
+    /// ascriptions and do not appear in the IR.
+    Cast(SymExpr<'db>),
+
     /// Something like `Point { x: ..., y: ... }`
     Aggregate {
         ty: SymTy<'db>,
@@ -180,6 +221,18 @@ pub enum SymExprKind<'db> {
     Error(Reported),
 }
 
+/// Identifies a loop so that `break` and `continue` expressions
+/// within its body can refer to it.
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct SymLoop<'db> {
+    /// Span of the loop expression.
+    pub span: Span<'db>,
+
+    /// The loop's label, if any (e.g., `outer` for `'outer: loop { ... }`)
+    pub label: Option<Identifier<'db>>,
+}
+
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct SymByteLiteral<'db> {
@@ -229,7 +282,13 @@ impl TryFrom<AstBinaryOp> for SymBinaryOp {
             AstBinaryOp::GreaterEqual => Ok(SymBinaryOp::GreaterEqual),
             AstBinaryOp::LessEqual => Ok(SymBinaryOp::LessEqual),
             AstBinaryOp::EqualEqual => Ok(SymBinaryOp::EqualEqual),
-            AstBinaryOp::AndAnd | AstBinaryOp::OrOr | AstBinaryOp::Assign => {
+            AstBinaryOp::AndAnd
+            | AstBinaryOp::OrOr
+            | AstBinaryOp::Assign
+            | AstBinaryOp::AddAssign
+            | AstBinaryOp::SubAssign
+            | AstBinaryOp::MulAssign
+            | AstBinaryOp::DivAssign => {
                 dada_util::bail!("no equivalent object binary op")
             }
         }
@@ -2,23 +2,26 @@ use std::borrow::Cow;
 
 use dada_ir_ast::{
     ast::{
-        AstAggregate, AstFunction, AstFunctionEffects, AstFunctionInput, AstMainFunction,
-        Identifier, SpannedIdentifier,
+        AstAggregate, AstExternFunction, AstFunction, AstFunctionEffects, AstFunctionInput,
+        AstMainFunction, AstStatement, Identifier, SpannedIdentifier, VisibilityKind,
     },
     span::{SourceSpanned, Span, Spanned},
 };
-use dada_util::{FromImpls, SalsaSerialize};
+use dada_parser::prelude::FunctionBlock;
+use dada_util::{FromImpls, Map, SalsaSerialize};
 use salsa::Update;
 use serde::Serialize;
 
 use crate::{
     check::{
-        scope::Scope,
+        ffi::HOST_EXPORT_ATTRIBUTE,
+        scope::{Scope, ScopeChainKind},
         scope_tree::{ScopeItem, ScopeTreeNode},
     },
     ir::{
         binder::{Binder, LeafBoundTerm},
         classes::SymAggregate,
+        module::{SymItem, insert},
         populate::{PopulateDefaultSymbols, PopulateSignatureSymbols},
         types::SymTy,
         variables::SymVariable,
@@ -71,6 +74,7 @@ impl<'db> ScopeTreeNode<'db> for SymFunction<'db> {
     ) {
         let wc = match self.source(db) {
             SymFunctionSource::Function(ast) => ast.where_clauses(db),
+            SymFunctionSource::Extern(ast) => ast.function(db).where_clauses(db),
             SymFunctionSource::Constructor(_, ast) => ast.where_clauses(db),
             SymFunctionSource::MainFunction(_) => &None,
         };
@@ -105,17 +109,61 @@ impl<'db> SymFunction<'db> {
         self.source(db).name(db).span
     }
 
+    /// The visibility modifier declared on the function, if any.
+    /// Generated functions (`main`, constructors) have none.
+    pub fn visibility(self, db: &'db dyn crate::Db) -> Option<VisibilityKind> {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => {
+                ast_function.visibility(db).map(|v| v.kind)
+            }
+            SymFunctionSource::Extern(ast_extern) => {
+                ast_extern.function(db).visibility(db).map(|v| v.kind)
+            }
+            SymFunctionSource::MainFunction(_) | SymFunctionSource::Constructor(..) => None,
+        }
+    }
+
+    /// True if the function is marked `#[host_export]`, so that compiling its module
+    /// exports it to the host (see [`crate::check::ffi`]).
+    pub fn is_host_export(self, db: &'db dyn crate::Db) -> bool {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => ast_function
+                .attributes(db)
+                .iter()
+                .any(|attribute| attribute.name.id.text(db) == HOST_EXPORT_ATTRIBUTE),
+            SymFunctionSource::Extern(_)
+            | SymFunctionSource::MainFunction(_)
+            | SymFunctionSource::Constructor(..) => false,
+        }
+    }
+
+    /// True if the function declares a `self` input and is therefore a method.
+    /// Functions declared in a class without `self` are *associated functions*,
+    /// called through the class name (`Foo.new()`).
+    pub fn has_self(self, db: &'db dyn crate::Db) -> bool {
+        matches!(
+            self.source(db).inputs(db).first(),
+            Some(AstFunctionInput::SelfArg(_))
+        )
+    }
+
     fn scope_from_symbols<'sym>(
         self,
         db: &'db dyn crate::Db,
         symbols: &'sym SignatureSymbols<'db>,
     ) -> Scope<'sym, 'db> {
-        self.super_scope_item(db)
-            .into_scope(db)
+        self.outer_scope(db)
             .with_link(Cow::Borrowed(&symbols.generic_variables[..]))
             .with_link(Cow::Borrowed(&symbols.input_variables[..]))
     }
 
+    /// The scope enclosing this function, extended with the items nested in its body.
+    fn outer_scope(self, db: &'db dyn crate::Db) -> Scope<'db, 'db> {
+        self.super_scope_item(db)
+            .into_scope(db)
+            .with_link(ScopeChainKind::SymFunctionBody(self))
+    }
+
     #[salsa::tracked(return_ref)]
     pub fn symbols(self, db: &'db dyn crate::Db) -> SignatureSymbols<'db> {
         let source = self.source(db);
@@ -140,12 +188,94 @@ impl<'db> SymFunction<'db> {
         let symbols = self.symbols(db);
         self.scope_from_symbols(db, symbols)
     }
+
+    /// Returns the scope for this function with only the function generics in scope
+    /// (not its parameters). Used when checking parameter default values,
+    /// which are evaluated at the call site.
+    pub fn generics_scope(self, db: &'db dyn crate::Db) -> Scope<'db, 'db> {
+        let symbols = self.symbols(db);
+        self.outer_scope(db)
+            .with_link(Cow::Borrowed(&symbols.generic_variables[..]))
+    }
+
+    /// Returns the scope for the items nested in this function's body.
+    /// This includes the items themselves and anything visible from the
+    /// [`items_super_scope`](Self::items_super_scope), but not the generics or
+    /// parameters of this function or of the classes and functions around it.
+    pub fn items_scope(self, db: &'db dyn crate::Db) -> Scope<'db, 'db> {
+        self.items_super_scope(db)
+            .into_scope(db)
+            .with_link(ScopeChainKind::SymFunctionBody(self))
+    }
+
+    /// The super scope of [`ScopeItem::FunctionBody`][]: the nearest enclosing module
+    /// or function body, skipping the classes and functions in between.
+    pub fn items_super_scope(self, db: &'db dyn crate::Db) -> ScopeItem<'db> {
+        self.iter_super_scopes(db)
+            .skip(1)
+            .find(|item| match item {
+                ScopeItem::AstModule(_) | ScopeItem::SymModule(_) | ScopeItem::FunctionBody(_) => {
+                    true
+                }
+                ScopeItem::Class(_) | ScopeItem::SymFunction(_) => false,
+            })
+            .expect("every function is nested in a module")
+    }
+
+    /// The statements at the top level of this function's body (empty if it has none).
+    pub fn body_statements(self, db: &'db dyn crate::Db) -> &'db [AstStatement<'db>] {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => match ast_function.body_block(db) {
+                Some(block) => &block.statements(db).values,
+                None => &[],
+            },
+            SymFunctionSource::MainFunction(mfunc) => &mfunc.statements(db).values,
+            SymFunctionSource::Extern(_) | SymFunctionSource::Constructor(..) => &[],
+        }
+    }
+
+    /// Classes and functions declared at the top level of this function's body.
+    /// Their super scope is [`ScopeItem::FunctionBody`][], so they are visible
+    /// to one another and to this function, but cannot see its generics or parameters.
+    #[salsa::tracked(return_ref)]
+    pub fn nested_items(self, db: &'db dyn crate::Db) -> Map<Identifier<'db>, SymItem<'db>> {
+        let mut items = Map::default();
+        for statement in self.body_statements(db) {
+            match *statement {
+                AstStatement::Aggregate(ast_aggregate) => insert(
+                    db,
+                    &mut items,
+                    ast_aggregate.name(db),
+                    SymItem::from(SymAggregate::new(
+                        db,
+                        ScopeItem::FunctionBody(self),
+                        ast_aggregate,
+                    )),
+                ),
+                AstStatement::Function(ast_function) => insert(
+                    db,
+                    &mut items,
+                    ast_function.name(db).id,
+                    SymItem::from(SymFunction::new(
+                        db,
+                        ScopeItem::FunctionBody(self),
+                        ast_function.into(),
+                    )),
+                ),
+                AstStatement::Let(_) | AstStatement::Expr(_) => {}
+            }
+        }
+        items
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Update, FromImpls, Serialize)]
 pub enum SymFunctionSource<'db> {
     Function(AstFunction<'db>),
 
+    /// `extern fn` implemented by the host
+    Extern(AstExternFunction<'db>),
+
     /// Generated `fn main()` from statements appearing at the top of a module
     MainFunction(AstMainFunction<'db>),
 
@@ -158,6 +288,7 @@ impl<'db> SymFunctionSource<'db> {
     fn effects(self, db: &'db dyn crate::Db) -> AstFunctionEffects<'db> {
         match self {
             Self::Function(ast_function) => ast_function.effects(db),
+            Self::Extern(ast_extern) => ast_extern.function(db).effects(db),
             Self::MainFunction(_) | Self::Constructor(..) => AstFunctionEffects::default(),
         }
     }
@@ -165,6 +296,7 @@ impl<'db> SymFunctionSource<'db> {
     fn name(self, db: &'db dyn dada_ir_ast::Db) -> SpannedIdentifier<'db> {
         match self {
             Self::Function(ast_function) => ast_function.name(db),
+            Self::Extern(ast_extern) => ast_extern.function(db).name(db),
             Self::Constructor(class, _) => SpannedIdentifier {
                 span: class.name_span(db),
                 id: Identifier::new_ident(db),
@@ -179,6 +311,7 @@ impl<'db> SymFunctionSource<'db> {
     pub fn inputs(self, db: &'db dyn crate::Db) -> Cow<'db, [AstFunctionInput<'db>]> {
         match self {
             Self::Function(ast_function) => Cow::Borrowed(&ast_function.inputs(db).values),
+            Self::Extern(ast_extern) => Cow::Borrowed(&ast_extern.function(db).inputs(db).values),
             Self::Constructor(_, class) => Cow::Owned(
                 class
                     .inputs(db)
@@ -197,6 +330,7 @@ impl<'db> SourceSpanned<'db> for SymFunctionSource<'db> {
     fn source_span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
         match self {
             SymFunctionSource::Function(ast_function) => ast_function.span(db),
+            SymFunctionSource::Extern(ast_extern) => ast_extern.span(db),
             SymFunctionSource::Constructor(_, ast_aggregate) => ast_aggregate.span(db),
             SymFunctionSource::MainFunction(mfunc) => mfunc.span(db),
         }
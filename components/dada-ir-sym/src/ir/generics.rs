@@ -18,3 +18,24 @@ pub enum SymWhereClauseKind {
     Owned,
     Lent,
 }
+
+impl std::fmt::Display for SymWhereClause<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        salsa::with_attached_database(|db| {
+            let db: &dyn crate::Db = db.as_view();
+            write!(f, "{} is {}", self.subject(db), self.kind(db))
+        })
+        .unwrap_or_else(|| write!(f, "{self:?}"))
+    }
+}
+
+impl std::fmt::Display for SymWhereClauseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymWhereClauseKind::Unique => write!(f, "unique"),
+            SymWhereClauseKind::Shared => write!(f, "shared"),
+            SymWhereClauseKind::Owned => write!(f, "owned"),
+            SymWhereClauseKind::Lent => write!(f, "lent"),
+        }
+    }
+}
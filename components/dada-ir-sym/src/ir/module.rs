@@ -35,6 +35,11 @@ pub struct SymModule<'db> {
     #[tracked]
     #[return_ref]
     pub(crate) ast_use_map: Map<Identifier<'db>, AstUse<'db>>,
+
+    // Glob imports (`use a.b.*`) come last; they are shadowed by everything above.
+    #[tracked]
+    #[return_ref]
+    pub(crate) ast_glob_uses: Vec<AstUse<'db>>,
 }
 
 impl<'db> Spanned<'db> for SymModule<'db> {
@@ -120,9 +125,13 @@ impl<'db> Symbol<'db> for AstModule<'db> {
         let mut class_map = Map::default();
         let mut function_map = Map::default();
         let mut ast_use_map = Map::default();
+        let mut ast_glob_uses = vec![];
         for item in self.items(db) {
             match *item {
                 AstItem::SourceFile(_) => {}
+                AstItem::Use(ast_use) if ast_use.glob(db).is_some() => {
+                    ast_glob_uses.push(ast_use);
+                }
                 AstItem::Use(ast_use) => {
                     let id = match ast_use.as_id(db) {
                         Some(as_id) => as_id.id,
@@ -147,6 +156,14 @@ impl<'db> Symbol<'db> for AstModule<'db> {
                         SymFunction::new(db, self.into(), func.into()),
                     );
                 }
+                AstItem::ExternFunction(ast_extern) => {
+                    insert(
+                        db,
+                        &mut function_map,
+                        ast_extern.function(db).name(db).id,
+                        SymFunction::new(db, self.into(), ast_extern.into()),
+                    );
+                }
                 AstItem::MainFunction(mfunc) => {
                     insert(
                         db,
@@ -165,7 +182,14 @@ impl<'db> Symbol<'db> for AstModule<'db> {
         insert_into_canonical_map(db, canonical_map, &function_map);
         insert_into_canonical_map(db, canonical_map, &ast_use_map);
 
-        SymModule::new(db, self, class_map, function_map, ast_use_map)
+        SymModule::new(
+            db,
+            self,
+            class_map,
+            function_map,
+            ast_use_map,
+            ast_glob_uses,
+        )
     }
 }
 
@@ -191,7 +215,8 @@ impl<'db> ScopeTreeNode<'db> for AstModule<'db> {
     }
 }
 
-fn insert<'db, V: Spanned<'db>>(
+/// Inserts `value` under `id`, reporting a duplicate (and ignoring `value`) if `id` is taken.
+pub(crate) fn insert<'db, V: Spanned<'db>>(
     db: &'db dyn crate::Db,
     map: &mut Map<Identifier<'db>, V>,
     id: Identifier<'db>,
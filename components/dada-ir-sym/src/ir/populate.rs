@@ -49,6 +49,7 @@ impl<'db> PopulateSignatureSymbols<'db> for AstTy<'db> {
             AstTyKind::GenericDecl(ast_generic_decl) => {
                 ast_generic_decl.populate_signature_symbols(db, symbols)
             }
+            AstTyKind::Never => {}
         }
     }
 }
@@ -91,7 +92,7 @@ impl<'db> PopulateSignatureSymbols<'db> for AstGenericTerm<'db> {
         match self {
             AstGenericTerm::Ty(ast_ty) => ast_ty.populate_signature_symbols(db, symbols),
             AstGenericTerm::Perm(ast_perm) => ast_perm.populate_signature_symbols(db, symbols),
-            AstGenericTerm::Id(_) => {}
+            AstGenericTerm::Id(_) | AstGenericTerm::Infer(_) => {}
         }
     }
 }
@@ -193,6 +194,9 @@ impl<'db> PopulateSignatureSymbols<'db> for SymFunctionSource<'db> {
     ) {
         match self {
             Self::Function(ast_function) => ast_function.populate_signature_symbols(db, symbols),
+            Self::Extern(ast_extern) => ast_extern
+                .function(db)
+                .populate_signature_symbols(db, symbols),
             Self::Constructor(..) => {
                 self.inputs(db)
                     .iter()
@@ -319,7 +323,7 @@ pub(crate) fn variable_decl_requires_default_perm<'db>(
                 false
             }
         }
-        AstTyKind::GenericDecl(..) => {
+        AstTyKind::GenericDecl(..) | AstTyKind::Never => {
             // No default symbol in this case.
             false
         }
@@ -337,6 +341,9 @@ impl<'db> PopulateDefaultSymbols<'db> for SymFunctionSource<'db> {
             Self::Function(ast_function) => {
                 ast_function.populate_default_symbols(db, scope, symbols)
             }
+            Self::Extern(ast_extern) => ast_extern
+                .function(db)
+                .populate_default_symbols(db, scope, symbols),
             Self::Constructor(..) => {
                 self.inputs(db)
                     .iter()
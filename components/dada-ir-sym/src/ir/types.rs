@@ -1,9 +1,9 @@
 use crate::{
     Db,
-    check::inference::InferVarKind,
+    check::{inference::InferVarKind, member_lookup::named_members},
     ir::{
         binder::LeafBoundTerm,
-        classes::{SymAggregate, SymField},
+        classes::{SymAggregate, SymClassMember, SymField},
         indices::{FromInfer, FromInferVar, InferVarIndex},
         primitive::{SymPrimitive, SymPrimitiveKind},
         variables::{FromVar, SymVariable},
@@ -376,6 +376,16 @@ impl<'db> SymTy<'db> {
     pub fn shared(self, db: &'db dyn Db) -> Self {
         SymTy::new(db, SymTyKind::Perm(SymPerm::our(db), self))
     }
+
+    /// Returns the fields and methods that can be accessed with `.` on a value of this type,
+    /// ignoring its permissions. Types that are not (yet) known to be a class have none.
+    pub fn members(self, db: &'db dyn Db) -> &'db [SymClassMember<'db>] {
+        match self.kind(db) {
+            SymTyKind::Perm(_, ty) => ty.members(db),
+            SymTyKind::Named(name, _) => named_members(db, *name),
+            SymTyKind::Infer(_) | SymTyKind::Var(_) | SymTyKind::Never | SymTyKind::Error(_) => &[],
+        }
+    }
 }
 
 impl<'db> FromInfer<'db> for SymTy<'db> {
@@ -387,19 +397,17 @@ impl<'db> FromInfer<'db> for SymTy<'db> {
 impl std::fmt::Display for SymTy<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         salsa::with_attached_database(|db| match self.kind(db) {
+            SymTyKind::Named(SymTyName::Tuple { arity: 1 }, generics) => {
+                write!(f, "({},)", generics[0])
+            }
+            SymTyKind::Named(SymTyName::Tuple { arity: _ }, generics) => {
+                write!(f, "({})", comma_separated(generics))
+            }
             SymTyKind::Named(name, generics) => {
                 if generics.is_empty() {
                     write!(f, "{name}")
                 } else {
-                    write!(
-                        f,
-                        "{name}[{}]",
-                        generics
-                            .iter()
-                            .map(|g| g.to_string())
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    )
+                    write!(f, "{name}[{}]", comma_separated(generics))
                 }
             }
             SymTyKind::Perm(sym_perm, sym_ty) => write!(f, "{sym_perm} {sym_ty}"),
@@ -605,26 +613,8 @@ impl std::fmt::Display for SymPerm<'_> {
             match self.kind(db) {
                 SymPermKind::My => write!(f, "my"),
                 SymPermKind::Our => write!(f, "our"),
-                SymPermKind::Referenced(places) => {
-                    write!(f, "ref[")?;
-                    for (i, place) in places.iter().enumerate() {
-                        if i > 0 {
-                            write!(f, ", ")?;
-                        }
-                        write!(f, "{place}")?;
-                    }
-                    write!(f, "]")
-                }
-                SymPermKind::Mutable(places) => {
-                    write!(f, "mutable[")?;
-                    for (i, place) in places.iter().enumerate() {
-                        if i > 0 {
-                            write!(f, ", ")?;
-                        }
-                        write!(f, "{place}")?;
-                    }
-                    write!(f, "]")
-                }
+                SymPermKind::Referenced(places) => write!(f, "ref[{}]", comma_separated(places)),
+                SymPermKind::Mutable(places) => write!(f, "mut[{}]", comma_separated(places)),
                 SymPermKind::Apply(perm1, perm2) => write!(f, "{perm1} {perm2}"),
                 SymPermKind::Infer(infer_var_index) => write!(f, "?{}", infer_var_index.as_usize()),
                 SymPermKind::Var(sym_variable) => write!(f, "{sym_variable}"),
@@ -728,6 +718,15 @@ impl<'db> SymPlace<'db> {
                 _ => false,
             }
     }
+
+    /// The variable at the root of this place (e.g., `a` for `a.b[_]`), if any.
+    pub fn root_var(self, db: &'db dyn crate::Db) -> Option<SymVariable<'db>> {
+        match self.kind(db) {
+            SymPlaceKind::Var(var) => Some(*var),
+            SymPlaceKind::Field(place, _) | SymPlaceKind::Index(place) => place.root_var(db),
+            SymPlaceKind::Erased | SymPlaceKind::Error(_) => None,
+        }
+    }
 }
 
 impl std::fmt::Display for SymPlace<'_> {
@@ -748,6 +747,15 @@ impl std::fmt::Display for SymPlace<'_> {
 
 impl<'db> LeafBoundTerm<'db> for SymPlace<'db> {}
 
+/// Formats `items` separated by commas, as in the generic arguments of `Pair[u32, String]`.
+fn comma_separated(items: &[impl std::fmt::Display]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl<'db> Err<'db> for SymPlace<'db> {
     fn err(db: &'db dyn dada_ir_ast::Db, reported: Reported) -> Self {
         SymPlace::new(db, SymPlaceKind::Error(reported))
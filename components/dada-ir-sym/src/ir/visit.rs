@@ -0,0 +1,232 @@
+//! Visiting and rewriting [`SymExpr`][] trees.
+//!
+//! [`SymExprVisitor`][] visits the subexpressions and places of an expression.
+//! Each `visit_*` method defaults to the `walk_*` function of the same name,
+//! which visits the node's children; override a method to act on one kind of node,
+//! calling the `walk_*` function from the override to keep descending.
+//!
+//! [`SymExprFolder`][] rewrites expressions in the same way, with `super_fold_*` functions
+//! that rebuild an expression from its folded children.
+
+use dada_util::ensure_sufficient_stack;
+
+use super::exprs::{SymExpr, SymExprKind, SymMatchArm, SymPlaceExpr, SymPlaceExprKind};
+
+/// Visits an expression tree, see the [module docs](`self`).
+pub trait SymExprVisitor<'db> {
+    fn db(&self) -> &'db dyn crate::Db;
+
+    fn visit_expr(&mut self, expr: SymExpr<'db>) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_place(&mut self, place: SymPlaceExpr<'db>) {
+        walk_place(self, place)
+    }
+}
+
+/// Visits the subexpressions and places of `expr` in evaluation order,
+/// except that the place of an assignment is visited before its value.
+pub fn walk_expr<'db, V: SymExprVisitor<'db> + ?Sized>(visitor: &mut V, expr: SymExpr<'db>) {
+    let db = visitor.db();
+    ensure_sufficient_stack(|| match expr.kind(db) {
+        SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+            visitor.visit_expr(*lhs);
+            visitor.visit_expr(*rhs);
+        }
+        SymExprKind::Tuple(exprs)
+        | SymExprKind::Aggregate {
+            ty: _,
+            fields: exprs,
+        } => {
+            for &expr in exprs {
+                visitor.visit_expr(expr);
+            }
+        }
+        SymExprKind::LetIn {
+            lv: _,
+            ty: _,
+            initializer,
+            body,
+        } => {
+            if let Some(initializer) = initializer {
+                visitor.visit_expr(*initializer);
+            }
+            visitor.visit_expr(*body);
+        }
+        SymExprKind::LetTuple {
+            lvs: _,
+            initializer,
+            body,
+        } => {
+            visitor.visit_expr(*initializer);
+            visitor.visit_expr(*body);
+        }
+        SymExprKind::Await {
+            future: operand,
+            await_keyword: _,
+        }
+        | SymExprKind::Return(operand)
+        | SymExprKind::Loop {
+            sym_loop: _,
+            body: operand,
+        }
+        | SymExprKind::Break {
+            from_loop: _,
+            with_value: operand,
+        }
+        | SymExprKind::Not {
+            operand,
+            op_span: _,
+        }
+        | SymExprKind::Cast(operand) => visitor.visit_expr(*operand),
+        SymExprKind::Assign { place, value } => {
+            visitor.visit_place(*place);
+            visitor.visit_expr(*value);
+        }
+        SymExprKind::PermissionOp(_, place)
+        | SymExprKind::IsSome(place)
+        | SymExprKind::Drop(place) => visitor.visit_place(*place),
+        SymExprKind::Match { arms } => {
+            for arm in arms {
+                if let Some(condition) = arm.condition {
+                    visitor.visit_expr(condition);
+                }
+                visitor.visit_expr(arm.body);
+            }
+        }
+
+        // The arguments of a call are temporaries, declared by an enclosing `LetIn`.
+        SymExprKind::Call { .. }
+        | SymExprKind::Primitive(_)
+        | SymExprKind::ByteLiteral(_)
+        | SymExprKind::Continue { .. }
+        | SymExprKind::Error(_) => {}
+    })
+}
+
+/// Visits the place that `place` is a field of, if any.
+pub fn walk_place<'db, V: SymExprVisitor<'db> + ?Sized>(visitor: &mut V, place: SymPlaceExpr<'db>) {
+    match place.kind(visitor.db()) {
+        SymPlaceExprKind::Field(owner, _) => visitor.visit_place(*owner),
+        SymPlaceExprKind::Var(_) | SymPlaceExprKind::Error(_) => {}
+    }
+}
+
+/// Rewrites an expression tree, see the [module docs](`self`).
+///
+/// Expressions are tracked structs, so folds must run within a tracked function.
+/// Expressions whose children are unchanged are returned as they are.
+pub trait SymExprFolder<'db> {
+    fn db(&self) -> &'db dyn crate::Db;
+
+    fn fold_expr(&mut self, expr: SymExpr<'db>) -> SymExpr<'db> {
+        super_fold_expr(self, expr)
+    }
+
+    /// Places are not folded by default.
+    fn fold_place(&mut self, place: SymPlaceExpr<'db>) -> SymPlaceExpr<'db> {
+        place
+    }
+}
+
+/// Rebuilds `expr` from its folded children, folding them in the order [`walk_expr`][] visits them.
+pub fn super_fold_expr<'db, F: SymExprFolder<'db> + ?Sized>(
+    folder: &mut F,
+    expr: SymExpr<'db>,
+) -> SymExpr<'db> {
+    let db = folder.db();
+    let kind = ensure_sufficient_stack(|| match expr.kind(db) {
+        &SymExprKind::Semi(lhs, rhs) => {
+            let lhs = folder.fold_expr(lhs);
+            SymExprKind::Semi(lhs, folder.fold_expr(rhs))
+        }
+        &SymExprKind::BinaryOp(op, lhs, rhs) => {
+            let lhs = folder.fold_expr(lhs);
+            SymExprKind::BinaryOp(op, lhs, folder.fold_expr(rhs))
+        }
+        SymExprKind::Tuple(exprs) => {
+            SymExprKind::Tuple(exprs.iter().map(|&e| folder.fold_expr(e)).collect())
+        }
+        SymExprKind::Aggregate { ty, fields } => SymExprKind::Aggregate {
+            ty: *ty,
+            fields: fields.iter().map(|&e| folder.fold_expr(e)).collect(),
+        },
+        &SymExprKind::LetIn {
+            lv,
+            ty,
+            initializer,
+            body,
+        } => SymExprKind::LetIn {
+            lv,
+            ty,
+            initializer: initializer.map(|e| folder.fold_expr(e)),
+            body: folder.fold_expr(body),
+        },
+        SymExprKind::LetTuple {
+            lvs,
+            initializer,
+            body,
+        } => SymExprKind::LetTuple {
+            lvs: lvs.clone(),
+            initializer: folder.fold_expr(*initializer),
+            body: folder.fold_expr(*body),
+        },
+        &SymExprKind::Await {
+            future,
+            await_keyword,
+        } => SymExprKind::Await {
+            future: folder.fold_expr(future),
+            await_keyword,
+        },
+        &SymExprKind::Return(value) => SymExprKind::Return(folder.fold_expr(value)),
+        &SymExprKind::Loop { sym_loop, body } => SymExprKind::Loop {
+            sym_loop,
+            body: folder.fold_expr(body),
+        },
+        &SymExprKind::Break {
+            from_loop,
+            with_value,
+        } => SymExprKind::Break {
+            from_loop,
+            with_value: folder.fold_expr(with_value),
+        },
+        &SymExprKind::Not { operand, op_span } => SymExprKind::Not {
+            operand: folder.fold_expr(operand),
+            op_span,
+        },
+        &SymExprKind::Cast(operand) => SymExprKind::Cast(folder.fold_expr(operand)),
+        &SymExprKind::Assign { place, value } => {
+            let place = folder.fold_place(place);
+            SymExprKind::Assign {
+                place,
+                value: folder.fold_expr(value),
+            }
+        }
+        &SymExprKind::PermissionOp(op, place) => {
+            SymExprKind::PermissionOp(op, folder.fold_place(place))
+        }
+        &SymExprKind::IsSome(place) => SymExprKind::IsSome(folder.fold_place(place)),
+        &SymExprKind::Drop(place) => SymExprKind::Drop(folder.fold_place(place)),
+        SymExprKind::Match { arms } => SymExprKind::Match {
+            arms: arms
+                .iter()
+                .map(|arm| SymMatchArm {
+                    condition: arm.condition.map(|c| folder.fold_expr(c)),
+                    body: folder.fold_expr(arm.body),
+                })
+                .collect(),
+        },
+        kind @ (SymExprKind::Call { .. }
+        | SymExprKind::Primitive(_)
+        | SymExprKind::ByteLiteral(_)
+        | SymExprKind::Continue { .. }
+        | SymExprKind::Error(_)) => kind.clone(),
+    });
+
+    if kind == *expr.kind(db) {
+        expr
+    } else {
+        SymExpr::new(db, expr.span(db), expr.ty(db), kind)
+    }
+}
@@ -7,11 +7,17 @@ pub mod check;
 pub mod ir;
 pub mod well_known;
 
+#[cfg(test)]
+mod test_db;
+
 pub mod prelude {
+    use crate::check::InferredValue;
     use crate::ir::binder::Binder;
-    use crate::ir::classes::SymField;
+    use crate::ir::classes::{SymAggregate, SymField};
     use crate::ir::exprs::SymExpr;
     use crate::ir::functions::{SymFunction, SymFunctionSignature};
+    use crate::ir::generics::SymWhereClause;
+    use crate::ir::module::SymModule;
     use crate::ir::types::SymTy;
     use dada_ir_ast::diagnostic::Errors;
 
@@ -23,19 +29,61 @@ pub mod prelude {
         fn symbol(self, db: &'db dyn crate::Db) -> Self::Output;
     }
 
+    /// The markdown text of the doc comment (`## ...`) on an item, if any.
+    /// Implemented for items, class members, and the symbols that make them up.
+    pub trait Documentation<'db>: Copy {
+        fn documentation(self, db: &'db dyn crate::Db) -> Option<&'db str>;
+    }
+
     pub trait CheckUseItems<'db> {
         fn check_use_items(self, db: &'db dyn crate::Db);
     }
 
     pub trait CheckedBody<'db> {
         fn checked_body(self, db: &'db dyn crate::Db) -> Option<SymExpr<'db>>;
+
+        /// The final value of each inference variable created while checking the body,
+        /// indexed by [`InferVarIndex`](`crate::ir::indices::InferVarIndex`).
+        /// Empty if there is no body.
+        fn inferred_values(self, db: &'db dyn crate::Db) -> &'db [InferredValue<'db>];
     }
 
-    #[salsa::tracked]
     impl<'db> CheckedBody<'db> for SymFunction<'db> {
-        #[salsa::tracked]
         fn checked_body(self, db: &'db dyn crate::Db) -> Option<SymExpr<'db>> {
-            crate::check::functions::check_function_body(db, self)
+            crate::check::functions::checked_function_body(db, self).body
+        }
+
+        fn inferred_values(self, db: &'db dyn crate::Db) -> &'db [InferredValue<'db>] {
+            &crate::check::functions::checked_function_body(db, self).inferred_values
+        }
+    }
+
+    pub trait ElaboratedBody<'db> {
+        /// The checked body with drops inserted, see [`crate::check::drop_elaboration`][]
+        fn elaborated_body(self, db: &'db dyn crate::Db) -> Option<SymExpr<'db>>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> ElaboratedBody<'db> for SymFunction<'db> {
+        #[salsa::tracked]
+        fn elaborated_body(self, db: &'db dyn crate::Db) -> Option<SymExpr<'db>> {
+            let body = self.checked_body(db)?;
+            Some(crate::check::drop_elaboration::elaborate_drops(
+                db, self, body,
+            ))
+        }
+    }
+
+    pub trait CheckedInputDefaults<'db> {
+        /// See [`crate::check::functions::check_input_defaults`][]
+        fn checked_input_defaults(self, db: &'db dyn crate::Db) -> Vec<Option<SymExpr<'db>>>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedInputDefaults<'db> for SymFunction<'db> {
+        #[salsa::tracked]
+        fn checked_input_defaults(self, db: &'db dyn crate::Db) -> Vec<Option<SymExpr<'db>>> {
+            crate::check::functions::check_input_defaults(db, self)
         }
     }
 
@@ -54,6 +102,144 @@ pub mod prelude {
             }
         }
     }
+
+    pub trait CheckedLayout<'db> {
+        /// See [`crate::check::layout::check_layout`][]
+        fn checked_layout(self, db: &'db dyn crate::Db) -> Errors<()>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedLayout<'db> for SymAggregate<'db> {
+        #[salsa::tracked]
+        fn checked_layout(self, db: &'db dyn crate::Db) -> Errors<()> {
+            crate::check::layout::check_layout(db, self)
+        }
+    }
+
+    pub trait CheckedWhereClauses<'db> {
+        /// See [`crate::check::generics::check_class_where_clauses`][]
+        fn checked_where_clauses(self, db: &'db dyn crate::Db) -> Errors<Vec<SymWhereClause<'db>>>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedWhereClauses<'db> for SymAggregate<'db> {
+        #[salsa::tracked]
+        fn checked_where_clauses(self, db: &'db dyn crate::Db) -> Errors<Vec<SymWhereClause<'db>>> {
+            crate::check::generics::check_class_where_clauses(db, self)
+        }
+    }
+
+    pub trait CheckedDestructor<'db> {
+        /// See [`crate::check::destructors::check_destructor`][]
+        fn checked_destructor(self, db: &'db dyn crate::Db) -> Errors<Option<SymFunction<'db>>>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedDestructor<'db> for SymAggregate<'db> {
+        #[salsa::tracked]
+        fn checked_destructor(self, db: &'db dyn crate::Db) -> Errors<Option<SymFunction<'db>>> {
+            crate::check::destructors::check_destructor(db, self)
+        }
+    }
+
+    pub trait CheckedFfiSignature<'db> {
+        /// See [`crate::check::ffi::check_ffi_signature`][]
+        fn checked_ffi_signature(self, db: &'db dyn crate::Db) -> Errors<()>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedFfiSignature<'db> for SymFunction<'db> {
+        #[salsa::tracked]
+        fn checked_ffi_signature(self, db: &'db dyn crate::Db) -> Errors<()> {
+            crate::check::ffi::check_ffi_signature(db, self)
+        }
+    }
+
+    pub trait CheckedAttributes<'db> {
+        /// Reports unknown attributes and lints, see [`crate::check::lints`][]
+        fn checked_attributes(self, db: &'db dyn crate::Db);
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedAttributes<'db> for SymModule<'db> {
+        #[salsa::tracked]
+        fn checked_attributes(self, db: &'db dyn crate::Db) {
+            crate::check::lints::check_module_attributes(db, self)
+        }
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedAttributes<'db> for SymAggregate<'db> {
+        #[salsa::tracked]
+        fn checked_attributes(self, db: &'db dyn crate::Db) {
+            crate::check::lints::check_aggregate_attributes(db, self)
+        }
+    }
+
+    #[salsa::tracked]
+    impl<'db> CheckedAttributes<'db> for SymFunction<'db> {
+        #[salsa::tracked]
+        fn checked_attributes(self, db: &'db dyn crate::Db) {
+            crate::check::lints::check_function_attributes(db, self)
+        }
+    }
+
+    pub trait DumpRedSignature<'db> {
+        /// See [`crate::check::dump::dump_red_signature`][]
+        fn dump_red_signature(self, db: &'db dyn crate::Db) -> Errors<String>;
+    }
+
+    impl<'db> DumpRedSignature<'db> for SymFunction<'db> {
+        fn dump_red_signature(self, db: &'db dyn crate::Db) -> Errors<String> {
+            crate::check::dump::dump_red_signature(db, self)
+        }
+    }
+
+    pub trait DumpCheckStats<'db> {
+        /// See [`crate::check::dump::dump_check_stats`][]
+        fn dump_check_stats(self, db: &'db dyn crate::Db) -> Option<String>;
+    }
+
+    impl<'db> DumpCheckStats<'db> for SymFunction<'db> {
+        fn dump_check_stats(self, db: &'db dyn crate::Db) -> Option<String> {
+            crate::check::dump::dump_check_stats(db, self)
+        }
+    }
+
+    pub trait DumpPermissionState<'db> {
+        /// See [`crate::check::permission_state::dump_permission_state`][]
+        fn dump_permission_state(self, db: &'db dyn crate::Db) -> Option<String>;
+    }
+
+    impl<'db> DumpPermissionState<'db> for SymFunction<'db> {
+        fn dump_permission_state(self, db: &'db dyn crate::Db) -> Option<String> {
+            let body = self.checked_body(db)?;
+            Some(crate::check::permission_state::dump_permission_state(
+                db,
+                body,
+                self.inferred_values(db),
+            ))
+        }
+    }
+
+    pub trait LeaseGraph<'db> {
+        /// See [`crate::check::permission_state::lease_graph`][]
+        fn lease_graph(self, db: &'db dyn crate::Db) -> Option<String>;
+    }
+
+    #[salsa::tracked]
+    impl<'db> LeaseGraph<'db> for SymFunction<'db> {
+        #[salsa::tracked]
+        fn lease_graph(self, db: &'db dyn crate::Db) -> Option<String> {
+            let body = self.checked_body(db)?;
+            Some(crate::check::permission_state::lease_graph(
+                db,
+                body,
+                self.inferred_values(db),
+            ))
+        }
+    }
+
     pub trait CheckedSignature<'db> {
         fn checked_signature(self, db: &'db dyn crate::Db) -> Errors<SymFunctionSignature<'db>>;
     }
@@ -0,0 +1,60 @@
+//! A database for unit tests of the checker's data structures.
+
+use std::sync::mpsc::Sender;
+
+use dada_ir_ast::{
+    DebugEvent,
+    ast::Identifier,
+    diagnostic::DEFAULT_MAX_ERRORS,
+    inputs::{CompilationRoot, Krate, SourceFile},
+};
+use itertools::Itertools;
+use url::Url;
+
+/// A database with no crates, just enough to create symbols and intern values.
+#[salsa::db]
+#[derive(Clone)]
+pub(crate) struct TestDb {
+    storage: salsa::Storage<Self>,
+    root: Option<CompilationRoot>,
+}
+
+impl TestDb {
+    pub(crate) fn new() -> Self {
+        let mut db = TestDb {
+            storage: Default::default(),
+            root: None,
+        };
+        db.root = Some(CompilationRoot::new(&db, vec![], DEFAULT_MAX_ERRORS));
+        db
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for TestDb {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::db]
+impl dada_ir_ast::Db for TestDb {
+    fn root(&self) -> CompilationRoot {
+        self.root.expect("root is created by `TestDb::new`")
+    }
+
+    fn source_file<'db>(&'db self, _krate: Krate, modules: &[Identifier<'db>]) -> SourceFile {
+        let path = modules.iter().map(|module| module.text(self)).join("/");
+        SourceFile::new(
+            self,
+            Url::parse(&format!("file:///{path}.dada")).unwrap(),
+            Err("the test database has no source files".to_string()),
+        )
+    }
+
+    fn url_display(&self, url: &Url) -> String {
+        url.to_string()
+    }
+
+    fn debug_tx(&self) -> Option<Sender<DebugEvent>> {
+        None
+    }
+}
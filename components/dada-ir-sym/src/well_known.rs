@@ -8,7 +8,7 @@ use crate::{
     ir::{
         classes::{SymAggregate, SymClassMember},
         functions::SymFunction,
-        module::{SymItem, SymModule},
+        module::{SymItem, SymModule, SymPrelude},
         types::SymGenericKind,
     },
     prelude::Symbol,
@@ -27,6 +27,15 @@ fn prelude_module(db: &dyn crate::Db) -> SymModule<'_> {
     db.source_file(krate, &[identifier]).symbol(db)
 }
 
+/// Returns the items that are in scope in every module without a `use`:
+/// everything defined by the `libdada` prelude module.
+#[salsa::tracked]
+pub fn prelude<'db>(db: &'db dyn crate::Db) -> SymPrelude<'db> {
+    let mut items: Vec<SymItem<'db>> = prelude_module(db).items(db).collect();
+    items.sort_by_key(|item| item.span(db));
+    SymPrelude::new(db, items)
+}
+
 /// Returns the member of the `libdada` prelude with the given name,
 /// reporting an error if it is not found.
 fn prelude_member<'db>(db: &'db dyn crate::Db, name: &str) -> Errors<SymItem<'db>> {
@@ -92,6 +101,275 @@ pub fn string_literal_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>
     }
 }
 
+/// Returns the `print` function from the `libdada` prelude.
+#[salsa::tracked]
+pub fn print_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    match prelude_member(db, "print")? {
+        SymItem::SymFunction(function) => {
+            if !function
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    function,
+                    "print",
+                    "it should have 1 generic parameter",
+                ));
+            }
+            Ok(function)
+        }
+        m => Err(report_unexpected(db, m, "print", "it is not a function")),
+    }
+}
+
+/// Returns the `panic` function from the `libdada` prelude.
+/// Calls to it have type `!`, as they never return, and codegen lowers them to a trap.
+#[salsa::tracked]
+pub fn panic_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    match prelude_member(db, "panic")? {
+        SymItem::SymFunction(function) => {
+            if !function.symbols(db).has_generics_of_kind(db, &[]) {
+                return Err(report_unexpected(
+                    db,
+                    function,
+                    "panic",
+                    "it should not have generic parameters",
+                ));
+            }
+            Ok(function)
+        }
+        m => Err(report_unexpected(db, m, "panic", "it is not a function")),
+    }
+}
+
+/// Returns the `join` function from the `libdada` prelude.
+/// Its parameters are declared with the awaited types; see [`future_combinator`].
+#[salsa::tracked]
+pub fn join_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    match prelude_member(db, "join")? {
+        SymItem::SymFunction(function) => {
+            if !function
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type, SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    function,
+                    "join",
+                    "it should have 2 generic parameters",
+                ));
+            }
+            Ok(function)
+        }
+        m => Err(report_unexpected(db, m, "join", "it is not a function")),
+    }
+}
+
+/// Returns the `race` function from the `libdada` prelude.
+/// Its parameters are declared with the awaited types; see [`future_combinator`].
+#[salsa::tracked]
+pub fn race_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    match prelude_member(db, "race")? {
+        SymItem::SymFunction(function) => {
+            if !function
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    function,
+                    "race",
+                    "it should have 1 generic parameter",
+                ));
+            }
+            Ok(function)
+        }
+        m => Err(report_unexpected(db, m, "race", "it is not a function")),
+    }
+}
+
+/// True if `function` is one of the well-known future combinators (`join` or `race`).
+///
+/// `Future` cannot be named in Dada source, so these functions declare each parameter
+/// with the type it produces when awaited. At a call site, the checker instead requires
+/// each argument to be a future of that type.
+pub fn future_combinator<'db>(db: &'db dyn crate::Db, function: SymFunction<'db>) -> bool {
+    join_fn(db).ok() == Some(function) || race_fn(db).ok() == Some(function)
+}
+
+/// Returns the `Channel` class from the `libdada` prelude.
+#[salsa::tracked]
+pub fn channel_class<'db>(db: &'db dyn crate::Db) -> Errors<SymAggregate<'db>> {
+    match prelude_member(db, "Channel")? {
+        SymItem::SymClass(class) if class.is_class(db) => {
+            if !class
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    class,
+                    "Channel",
+                    "it should have 1 generic parameter",
+                ));
+            }
+            Ok(class)
+        }
+        m => Err(report_unexpected(db, m, "Channel", "it is not a class")),
+    }
+}
+
+/// Returns the `send` method of the `Channel` class from the `libdada` prelude.
+/// The interpreter implements it; the prelude only declares its signature.
+#[salsa::tracked]
+pub fn channel_send_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    channel_method(db, "send")
+}
+
+/// Returns the `recv` method of the `Channel` class from the `libdada` prelude.
+/// The interpreter implements it; the prelude only declares its signature.
+#[salsa::tracked]
+pub fn channel_recv_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    channel_method(db, "recv")
+}
+
+/// Returns the method `name` of the `Channel` class.
+fn channel_method<'db>(db: &'db dyn crate::Db, name: &str) -> Errors<SymFunction<'db>> {
+    let channel_class = channel_class(db)?;
+    let method = channel_class.inherent_member_str(db, name).ok_or_else(|| {
+        report_unexpected(
+            db,
+            channel_class,
+            "Channel",
+            &format!("does not have a `{name}` member"),
+        )
+    })?;
+    match method {
+        SymClassMember::SymFunction(function) => Ok(function),
+        m => Err(report_unexpected(
+            db,
+            m,
+            "Channel",
+            &format!("`{name}` is not a function"),
+        )),
+    }
+}
+
+/// Returns the `Option` class from the `libdada` prelude.
+/// An option holding no value (see [`none_fn`]) has no value for its field.
+#[salsa::tracked]
+pub fn option_class<'db>(db: &'db dyn crate::Db) -> Errors<SymAggregate<'db>> {
+    match prelude_member(db, "Option")? {
+        SymItem::SymClass(class) if class.is_class(db) => {
+            if !class
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    class,
+                    "Option",
+                    "it should have 1 generic parameter",
+                ));
+            }
+            if class.fields(db).count() != 1 {
+                return Err(report_unexpected(
+                    db,
+                    class,
+                    "Option",
+                    "it should have 1 field",
+                ));
+            }
+            Ok(class)
+        }
+        m => Err(report_unexpected(db, m, "Option", "it is not a class")),
+    }
+}
+
+/// Returns the `Some` function from the `libdada` prelude,
+/// which is what an `if let Some(x) = ...` must name.
+#[salsa::tracked]
+pub fn some_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    variant_fn(db, "Some", &[SymGenericKind::Type])
+}
+
+/// Returns the `None` function from the `libdada` prelude.
+/// The interpreter and codegen implement it; the prelude only declares its signature.
+#[salsa::tracked]
+pub fn none_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    variant_fn(db, "None", &[SymGenericKind::Type])
+}
+
+/// Returns the `Result` class from the `libdada` prelude.
+/// A result holds a value for exactly one of its fields: the first (`value`)
+/// for an `Ok` and the second (`error`) for an `Err`.
+#[salsa::tracked]
+pub fn result_class<'db>(db: &'db dyn crate::Db) -> Errors<SymAggregate<'db>> {
+    match prelude_member(db, "Result")? {
+        SymItem::SymClass(class) if class.is_class(db) => {
+            if !class
+                .symbols(db)
+                .has_generics_of_kind(db, &[SymGenericKind::Type, SymGenericKind::Type])
+            {
+                return Err(report_unexpected(
+                    db,
+                    class,
+                    "Result",
+                    "it should have 2 generic parameters",
+                ));
+            }
+            if class.fields(db).count() != 2 {
+                return Err(report_unexpected(
+                    db,
+                    class,
+                    "Result",
+                    "it should have 2 fields",
+                ));
+            }
+            Ok(class)
+        }
+        m => Err(report_unexpected(db, m, "Result", "it is not a class")),
+    }
+}
+
+/// Returns the `Ok` function from the `libdada` prelude.
+/// The interpreter and codegen implement it; the prelude only declares its signature.
+#[salsa::tracked]
+pub fn ok_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    variant_fn(db, "Ok", &[SymGenericKind::Type, SymGenericKind::Type])
+}
+
+/// Returns the `Err` function from the `libdada` prelude,
+/// which `?` calls to return an error from the enclosing function.
+/// The interpreter and codegen implement it; the prelude only declares its signature.
+#[salsa::tracked]
+pub fn err_fn<'db>(db: &'db dyn crate::Db) -> Errors<SymFunction<'db>> {
+    variant_fn(db, "Err", &[SymGenericKind::Type, SymGenericKind::Type])
+}
+
+/// Returns the function `name` that creates an `Option` or a `Result`,
+/// which has the generic parameters `generics`.
+fn variant_fn<'db>(
+    db: &'db dyn crate::Db,
+    name: &str,
+    generics: &[SymGenericKind],
+) -> Errors<SymFunction<'db>> {
+    match prelude_member(db, name)? {
+        SymItem::SymFunction(function) => {
+            if !function.symbols(db).has_generics_of_kind(db, generics) {
+                let problem = match generics.len() {
+                    1 => "it should have 1 generic parameter".to_string(),
+                    n => format!("it should have {n} generic parameters"),
+                };
+                return Err(report_unexpected(db, function, name, &problem));
+            }
+            Ok(function)
+        }
+        m => Err(report_unexpected(db, m, name, "it is not a function")),
+    }
+}
+
 /// Returns the `Pointer` struct from the `libdada` prelude.
 #[salsa::tracked]
 pub fn pointer_struct<'db>(db: &'db dyn crate::Db) -> Errors<SymAggregate<'db>> {
@@ -7,7 +7,7 @@ use structopt::StructOpt;
 
 mod main_lib;
 
-use dada_compiler::Db;
+use dada_compiler::{CodegenOptions, Db, DocFormat, DocOptions, IrStage, OptLevel, OverflowMode};
 
 #[derive(Debug, StructOpt)]
 pub struct Options {
@@ -22,11 +22,19 @@ pub struct Options {
 pub struct GlobalOptions {
     #[structopt(long)]
     no_color: bool,
+
+    /// Maximum number of errors reported for a single function
+    /// before checking it stops (default: 50).
+    #[structopt(long)]
+    max_errors: Option<usize>,
 }
 
 impl GlobalOptions {
     pub(crate) fn test_options() -> Self {
-        Self { no_color: false }
+        Self {
+            no_color: false,
+            max_errors: None,
+        }
     }
 
     pub(crate) fn render_opts(&self) -> RenderOptions {
@@ -60,18 +68,141 @@ pub enum Command {
         #[structopt(flatten)]
         compile_options: CompileOptions,
     },
+
+    /// Print the intermediate representations of a function.
+    Dump {
+        #[structopt(flatten)]
+        dump_options: DumpOptions,
+    },
+
+    /// Generate documentation for a module from its doc comments.
+    Doc {
+        #[structopt(flatten)]
+        doc_options: DocCommandOptions,
+    },
 }
 
 #[derive(Debug, StructOpt)]
 pub struct CompileOptions {
     /// Main source file to compile.
     input: String,
+
+    /// Emit calls in tail position as wasm tail calls (`return_call`).
+    #[structopt(long)]
+    tail_calls: bool,
+
+    /// Record the number of instantiations of each generic function
+    /// in a custom section of the generated module.
+    #[structopt(long)]
+    report_instantiations: bool,
+
+    /// Generate the wasm module twice and fail if the two results differ.
+    #[structopt(long)]
+    check_determinism: bool,
+
+    /// Skip constant propagation and branch folding before codegen.
+    #[structopt(long)]
+    no_const_prop: bool,
+
+    /// Disable all optimizations.
+    #[structopt(long)]
+    no_opt: bool,
+
+    /// Trap when integer arithmetic overflows, rather than wrapping around.
+    #[structopt(long)]
+    trap_on_overflow: bool,
+
+    /// Emit a `name` section with the name of each function.
+    #[structopt(long)]
+    debug_info: bool,
+
+    /// How deeply the generic arguments of a function instantiation may nest
+    /// before codegen reports an error (default: 64).
+    #[structopt(long)]
+    instantiation_limit: Option<usize>,
+
+    /// File to write the generated wasm module to.
+    #[structopt(long, short)]
+    output: Option<String>,
+
+    /// Also write the generated module as WebAssembly text, next to the output
+    /// (with a `.wat` extension), or to stdout if there is no output file.
+    #[structopt(long)]
+    emit_wat: bool,
+}
+
+impl CompileOptions {
+    pub(crate) fn codegen_options(&self) -> CodegenOptions {
+        CodegenOptions::default()
+            .with_tail_calls(self.tail_calls)
+            .with_report_instantiations(self.report_instantiations)
+            .with_check_determinism(self.check_determinism)
+            .with_no_const_prop(self.no_const_prop)
+            .with_opt_level(if self.no_opt {
+                OptLevel::None
+            } else {
+                OptLevel::Basic
+            })
+            .with_overflow(if self.trap_on_overflow {
+                OverflowMode::Trap
+            } else {
+                OverflowMode::Wrap
+            })
+            .with_debug_info(self.debug_info)
+            .with_emit_wat(self.emit_wat)
+            .with_instantiation_limit(self.instantiation_limit)
+    }
 }
 
 #[derive(Debug, StructOpt)]
 pub struct RunOptions {
     #[structopt(flatten)]
     compile_options: CompileOptions,
+
+    /// Execute the program with the interpreter instead of generating wasm.
+    #[structopt(long)]
+    interpret: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DumpOptions {
+    #[structopt(flatten)]
+    compile_options: CompileOptions,
+
+    /// Function to dump: a function name or `Class.method`.
+    #[structopt(long, default_value = "main")]
+    function: String,
+
+    /// Stage(s) to dump (`ast`, `sym`, `red`, `object`, `stats`, `perms`, `drops`, `wat`); all stages if omitted.
+    #[structopt(long)]
+    stage: Vec<IrStage>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DocCommandOptions {
+    /// Source file of the module to document.
+    input: String,
+
+    /// Output format (`markdown` or `html`).
+    #[structopt(long, default_value = "markdown")]
+    format: DocFormat,
+
+    /// File to write the documentation to; printed to stdout if omitted.
+    #[structopt(long, short)]
+    output: Option<String>,
+
+    /// Also document items that are not `pub` or `export`.
+    #[structopt(long)]
+    document_private_items: bool,
+}
+
+impl DocCommandOptions {
+    fn doc_options(&self) -> DocOptions {
+        DocOptions {
+            format: self.format,
+            include_private: self.document_private_items,
+        }
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -84,6 +215,22 @@ pub struct TestOptions {
     #[structopt(long)]
     porcelain: bool,
 
+    /// Also run the `main` function of each test with both the interpreter and the generated wasm,
+    /// failing the test if they print different output or end differently
+    /// (tests marked `#:differential` always do this)
+    #[structopt(long)]
+    differential: bool,
+
+    /// Seed that picks the codegen options of differential runs; random if omitted.
+    /// Failure reports include the seed that was used.
+    #[structopt(long)]
+    differential_seed: Option<u64>,
+
+    /// Also generate the wasm for each test a second time, from scratch,
+    /// failing the test if the two results differ (CI runs the tests this way)
+    #[structopt(long)]
+    check_determinism: bool,
+
     /// Test file(s) or directory
     inputs: Vec<String>,
 }
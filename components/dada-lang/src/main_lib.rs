@@ -1,8 +1,14 @@
+use std::sync::mpsc::Sender;
+
+use dada_compiler::{Compiler, RealFs};
+use dada_ir_ast::DebugEvent;
 use dada_util::Fallible;
 
 use crate::{Command, GlobalOptions};
 
 mod compile;
+mod doc;
+mod dump;
 mod run;
 mod test;
 
@@ -16,11 +22,22 @@ impl Main {
         Self { global_options }
     }
 
+    /// Creates a compiler configured by the global options.
+    fn compiler(&self, debug_tx: Option<Sender<DebugEvent>>) -> Compiler {
+        let mut compiler = Compiler::new(RealFs::default(), debug_tx);
+        if let Some(max_errors) = self.global_options.max_errors {
+            compiler.set_max_errors(max_errors);
+        }
+        compiler
+    }
+
     pub fn run(mut self, command: Command) -> Fallible<()> {
         match command {
             Command::Compile { compile_options } => self.compile(&compile_options, None)?,
             Command::Test { test_options } => self.test(test_options)?,
             Command::Run { run_options } => self.run_command(&run_options)?,
+            Command::Dump { dump_options } => self.dump(&dump_options)?,
+            Command::Doc { doc_options } => self.doc(&doc_options)?,
             Command::Debug {
                 debug_options,
                 compile_options,
@@ -1,8 +1,7 @@
 use std::{path::Path, sync::mpsc::Sender};
 
-use dada_compiler::{Compiler, RealFs};
 use dada_ir_ast::{DebugEvent, diagnostic::Level};
-use dada_util::{Fallible, bail};
+use dada_util::{Context, Fallible, bail};
 
 use crate::CompileOptions;
 
@@ -15,7 +14,7 @@ impl Main {
         debug_tx: Option<Sender<DebugEvent>>,
     ) -> Fallible<()> {
         let debug_mode = debug_tx.is_some();
-        let mut compiler = Compiler::new(RealFs::default(), debug_tx);
+        let mut compiler = self.compiler(debug_tx);
         let source_url = Path::new(&compile_options.input);
         let source_file = compiler.load_source_file(source_url)?;
         let diagnostics = compiler.check_all(source_file);
@@ -32,6 +31,35 @@ impl Main {
             bail!("compilation failed due to errors");
         }
 
+        let codegen_options = compile_options.codegen_options();
+        let output = compile_options.output.as_deref().map(Path::new);
+        if output.is_none() && !codegen_options.emit_wat {
+            return Ok(());
+        }
+
+        let Some(bytes) = compiler.codegen_main_fn(source_file, codegen_options) else {
+            bail!("no `main` function to compile");
+        };
+
+        if let Some(output) = output {
+            std::fs::write(output, bytes)
+                .with_context(|| format!("writing `{}`", output.display()))?;
+        }
+
+        if codegen_options.emit_wat {
+            let wat = compiler
+                .codegen_main_fn_wat(source_file, codegen_options)?
+                .expect("main function was compiled above");
+            match output {
+                Some(output) => {
+                    let wat_path = output.with_extension("wat");
+                    std::fs::write(&wat_path, wat)
+                        .with_context(|| format!("writing `{}`", wat_path.display()))?;
+                }
+                None => print!("{wat}"),
+            }
+        }
+
         Ok(())
     }
 }
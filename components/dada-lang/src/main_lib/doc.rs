@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use dada_ir_ast::diagnostic::Level;
+use dada_util::{Context, Fallible, bail};
+
+use crate::DocCommandOptions;
+
+use super::Main;
+
+impl Main {
+    pub(super) fn doc(&mut self, doc_options: &DocCommandOptions) -> Fallible<()> {
+        let mut compiler = self.compiler(None);
+        let source_url = Path::new(&doc_options.input);
+        let source_file = compiler.load_source_file(source_url)?;
+
+        // Signatures are only meaningful if the module type checks.
+        let diagnostics = compiler.check_all(source_file);
+        for diagnostic in &diagnostics {
+            eprintln!(
+                "{}",
+                diagnostic.render(&compiler, &self.global_options.render_opts())
+            );
+        }
+        if diagnostics.iter().any(|d| d.level >= Level::Error) {
+            bail!("cannot document a module with errors");
+        }
+
+        let documentation = compiler.document(source_file, doc_options.doc_options());
+        match &doc_options.output {
+            Some(output) => std::fs::write(output, documentation)
+                .with_context(|| format!("writing documentation to `{output}`"))?,
+            None => print!("{documentation}"),
+        }
+        Ok(())
+    }
+}
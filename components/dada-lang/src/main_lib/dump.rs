@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use dada_compiler::IrStage;
+use dada_util::Fallible;
+
+use crate::DumpOptions;
+
+use super::Main;
+
+impl Main {
+    pub(super) fn dump(&mut self, dump_options: &DumpOptions) -> Fallible<()> {
+        let mut compiler = self.compiler(None);
+        let source_url = Path::new(&dump_options.compile_options.input);
+        let source_file = compiler.load_source_file(source_url)?;
+
+        // Diagnostics go to stderr so that the dump itself can be redirected.
+        for diagnostic in compiler
+            .check_all_with_codegen(source_file, dump_options.compile_options.codegen_options())
+        {
+            eprintln!(
+                "{}",
+                diagnostic.render(&compiler, &self.global_options.render_opts())
+            );
+        }
+
+        let stages = if dump_options.stage.is_empty() {
+            &IrStage::ALL[..]
+        } else {
+            &dump_options.stage[..]
+        };
+
+        print!(
+            "{}",
+            compiler.dump_ir(
+                source_file,
+                &dump_options.function,
+                stages,
+                dump_options.compile_options.codegen_options(),
+            )?
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 use std::path::Path;
 
-use dada_compiler::{Compiler, RealFs};
-use dada_util::Fallible;
+use dada_compiler::Compiler;
+use dada_ir_ast::{diagnostic::Level, inputs::SourceFile};
+use dada_util::{Fallible, bail};
 
 use crate::RunOptions;
 
@@ -9,11 +10,17 @@ use super::Main;
 
 impl Main {
     pub(super) fn run_command(&mut self, run_options: &RunOptions) -> Fallible<()> {
-        let mut compiler = Compiler::new(RealFs::default(), None);
+        let mut compiler = self.compiler(None);
         let source_url = Path::new(&run_options.compile_options.input);
         let source_file = compiler.load_source_file(source_url)?;
-        let bytes = compiler.codegen_main_fn(source_file);
-        let diagnostics = compiler.check_all(source_file);
+
+        if run_options.interpret {
+            return self.interpret(&compiler, source_file);
+        }
+
+        let codegen_options = run_options.compile_options.codegen_options();
+        let bytes = compiler.codegen_main_fn(source_file, codegen_options);
+        let diagnostics = compiler.check_all_with_codegen(source_file, codegen_options);
 
         for diagnostic in &diagnostics {
             eprintln!(
@@ -26,4 +33,29 @@ impl Main {
 
         Ok(())
     }
+
+    /// Runs the main function of `source_file` with the interpreter,
+    /// provided that it type checks without errors.
+    fn interpret(&self, compiler: &Compiler, source_file: SourceFile) -> Fallible<()> {
+        let diagnostics = compiler.check_all(source_file);
+        for diagnostic in &diagnostics {
+            eprintln!(
+                "{}",
+                diagnostic.render(compiler, &self.global_options.render_opts())
+            );
+        }
+        if diagnostics.iter().any(|d| d.level >= Level::Error) {
+            bail!("not running the program because it has errors");
+        }
+
+        let Some(execution) = compiler.interpret_main(source_file) else {
+            bail!("no `main` function to run");
+        };
+        print!("{}", execution.output);
+        if let Err(error) = execution.result {
+            bail!("{}", error.render(compiler));
+        }
+
+        Ok(())
+    }
 }
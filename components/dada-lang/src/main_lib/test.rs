@@ -1,10 +1,11 @@
 use std::{
+    hash::{BuildHasher, Hasher, RandomState},
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     time::Instant,
 };
 
-use dada_compiler::{Compiler, RealFs};
+use dada_compiler::{Compiler, OptLevel, OverflowMode, RealFs};
 use dada_ir_ast::diagnostic::{Diagnostic, Level};
 use dada_util::{Fallible, bail};
 use expected::{ExpectedDiagnostic, Probe, ProbeKind};
@@ -18,6 +19,7 @@ use crate::{GlobalOptions, TestOptions};
 
 use super::Main;
 
+mod differential;
 mod expected;
 mod spec_validation;
 
@@ -75,6 +77,29 @@ enum Failure {
     /// Invalid spec reference in #:spec comment
     InvalidSpecReference(String),
 
+    /// The interpreter and the generated wasm printed different output or ended the program
+    /// differently (see `--differential`).
+    Divergence {
+        /// Seed that picked the codegen options for the wasm run.
+        seed: u64,
+
+        /// How the program ended in the interpreter.
+        interpreter: String,
+
+        /// What the program printed in the interpreter.
+        interpreter_output: String,
+
+        /// How the program ended when running the generated wasm.
+        wasm: String,
+
+        /// What the program printed when running the generated wasm.
+        wasm_output: String,
+    },
+
+    /// A test marked `#:differential` could not be run with both the interpreter
+    /// and the generated wasm, for the given reason.
+    NotDifferential(String),
+
     /// Auxiliary file at `path` did not have expected contents.
     ///
     /// See `diff`.
@@ -274,6 +299,12 @@ impl Main {
             self.assemble_tests(&options.inputs, &mut options.verbose)?
         };
 
+        // Differential runs vary the codegen options from one run of the suite to the next
+        // (unless given a seed), so that over time they cover all of them.
+        if options.differential_seed.is_none() {
+            options.differential_seed = Some(RandomState::new().build_hasher().finish());
+        }
+
         let start_time = Instant::now();
 
         // Create appropriate formatter
@@ -288,7 +319,7 @@ impl Main {
         // Run tests
         let test_results: Vec<Fallible<DetailedTestResult>> = panic_hook::recording_panics(|| {
             let runner = |input: &Path| -> Fallible<DetailedTestResult> {
-                let result = self.run_test(input)?;
+                let result = self.run_test(input, &options)?;
                 formatter.show_progress(input, &result, options.verbose);
                 Ok(result)
             };
@@ -352,8 +383,9 @@ impl Main {
     ///
     /// * `Err(e)` for some failure in the test harness itself.
     /// * `Ok(result)` with the detailed test result including timing and annotations.
-    fn run_test(&self, input: &Path) -> Fallible<DetailedTestResult> {
+    fn run_test(&self, input: &Path, options: &TestOptions) -> Fallible<DetailedTestResult> {
         let start_time = Instant::now();
+        let differential_seed = options.differential_seed.unwrap_or_default();
 
         assert!(is_dada_file(input));
         let mut compiler = Compiler::new(RealFs::default(), None);
@@ -365,8 +397,14 @@ impl Main {
         let is_fixme_ice = expectations.fixme_ice();
 
         // Run the test and capture panics
-        let result =
-            std::panic::catch_unwind(AssertUnwindSafe(|| expectations.compare(&mut compiler)));
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            expectations.compare(
+                &mut compiler,
+                options.differential,
+                differential_seed,
+                options.check_determinism,
+            )
+        }));
 
         let duration_ms = start_time.elapsed().as_millis() as u64;
 
@@ -428,15 +466,39 @@ fn extract_annotations(expectations: &expected::TestExpectations) -> Vec<String>
     if expectations.fn_asts() {
         annotations.push("#:fn_asts".to_string());
     }
+    if expectations.interpret() {
+        annotations.push("#:interpret".to_string());
+    }
     if !expectations.codegen() {
         annotations.push("#:skip_codegen".to_string());
     }
+    if expectations.codegen_options().tail_calls {
+        annotations.push("#:tail_calls".to_string());
+    }
+    if expectations.codegen_options().no_const_prop {
+        annotations.push("#:no_const_prop".to_string());
+    }
+    if expectations.codegen_options().opt_level == OptLevel::None {
+        annotations.push("#:no_opt".to_string());
+    }
+    if expectations.codegen_options().emit_wat {
+        annotations.push("#:emit_wat".to_string());
+    }
+    if expectations.codegen_options().overflow == OverflowMode::Trap {
+        annotations.push("#:trap_on_overflow".to_string());
+    }
+    if expectations.differential() {
+        annotations.push("#:differential".to_string());
+    }
     if expectations.fixme() {
         annotations.push("#:FIXME".to_string());
     }
     if expectations.fixme_ice() {
         annotations.push("#:FIXME_ICE".to_string());
     }
+    if let Some(max_errors) = expectations.max_errors() {
+        annotations.push(format!("#:max_errors {max_errors}"));
+    }
 
     // Add spec references
     for spec_ref in expectations.spec_refs() {
@@ -708,6 +770,45 @@ impl FailedTest {
                     writeln!(result)?;
                     writeln!(result, "Perhaps the bug was fixed?")?;
                 }
+                Failure::Divergence {
+                    seed,
+                    interpreter,
+                    interpreter_output,
+                    wasm,
+                    wasm_output,
+                } => {
+                    writeln!(result)?;
+                    writeln!(result, "# Interpreter and wasm diverged")?;
+                    writeln!(result)?;
+                    writeln!(
+                        result,
+                        "Running `main` of `{}` with seed {seed} gave different results in the two backends:",
+                        self.path.display()
+                    )?;
+                    writeln!(result)?;
+                    writeln!(result, "* interpreter: {interpreter}")?;
+                    writeln!(result, "* wasm: {wasm}")?;
+                    writeln!(result)?;
+                    writeln!(result, "Output of the interpreter:")?;
+                    writeln!(result)?;
+                    writeln!(result, "```\n{interpreter_output}```")?;
+                    writeln!(result)?;
+                    writeln!(result, "Output of the wasm:")?;
+                    writeln!(result)?;
+                    writeln!(result, "```\n{wasm_output}```")?;
+                    writeln!(result)?;
+                    writeln!(
+                        result,
+                        "Reproduce with `dada test --differential --differential-seed {seed} {}`.",
+                        self.path.display()
+                    )?;
+                }
+                Failure::NotDifferential(reason) => {
+                    writeln!(result)?;
+                    writeln!(result, "# Cannot run differentially")?;
+                    writeln!(result)?;
+                    writeln!(result, "The test is marked `#:differential`, but {reason}.")?;
+                }
                 Failure::InvalidSpecReference(spec_ref) => {
                     writeln!(result)?;
                     writeln!(result, "# Invalid spec reference")?;
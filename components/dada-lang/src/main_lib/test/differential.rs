@@ -0,0 +1,272 @@
+//! Differential testing: run the `main` function of a test both with the interpreter
+//! and as generated wasm, and check that the two print the same output and end the same way.
+//!
+//! 💡 The interpreter is a straightforward reading of the checked IR, so when the two
+//! disagree it is usually codegen that is wrong. The comparison looks at what the program
+//! printed and how it ended (normally, or with a trap and its `path:line:column: message`).
+//!
+//! To cover more of codegen, a seed picks some of the codegen options for the wasm run
+//! (see [`vary_options`]); a divergence report includes the seed so that it can be reproduced
+//! with `--differential-seed`.
+
+use std::sync::{Arc, Mutex};
+
+use dada_compiler::{
+    CodegenOptions, Compiler, MAIN_EXPORT, OptLevel, PANIC_HOOK_IMPORT, PRINT_HOOK_MODULE,
+    TRAP_INDEX_GLOBAL, TRAP_TABLE_SECTION,
+};
+use dada_ir_ast::inputs::SourceFile;
+use dada_util::{Fallible, anyhow, bail};
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, Val};
+
+use super::Failure;
+
+/// The result of [`compare_executions`].
+pub(super) enum Comparison {
+    /// Both runs printed the same output and ended the same way.
+    Agreed,
+
+    /// The runs differed; the failure describes how.
+    Diverged(Failure),
+
+    /// The program cannot be run both ways, for the given reason.
+    NotRunnable(String),
+}
+
+/// Runs `main` both ways and returns a [`Failure::Divergence`] if they print different output
+/// or end differently. The wasm is generated with `options`, varied by `seed`.
+pub(super) fn compare_executions(
+    compiler: &Compiler,
+    source_file: SourceFile,
+    options: CodegenOptions,
+    seed: u64,
+) -> Fallible<Comparison> {
+    let options = vary_options(options, seed);
+    let Some(bytes) = compiler.codegen_runnable_main_fn(source_file, options) else {
+        return Ok(Comparison::NotRunnable(
+            "there is no `main` function without generics or parameters".to_string(),
+        ));
+    };
+    let wasm = match run_wasm(bytes)? {
+        Ok(run) => run,
+        Err(reason) => return Ok(Comparison::NotRunnable(reason)),
+    };
+    let Some(execution) = compiler.interpret_main(source_file) else {
+        return Ok(Comparison::NotRunnable(
+            "the interpreter found no `main` function".to_string(),
+        ));
+    };
+
+    let interpreter = Run {
+        output: execution.output,
+        outcome: match execution.result {
+            Ok(()) => Outcome::Completed,
+            Err(error) => Outcome::Trapped(error.render(compiler)),
+        },
+    };
+
+    if interpreter == wasm {
+        Ok(Comparison::Agreed)
+    } else {
+        Ok(Comparison::Diverged(Failure::Divergence {
+            seed,
+            interpreter: interpreter.outcome.to_string(),
+            interpreter_output: interpreter.output,
+            wasm: wasm.outcome.to_string(),
+            wasm_output: wasm.output,
+        }))
+    }
+}
+
+/// Picks some of the codegen options for a differential run from the bits of `seed`,
+/// on top of those the test asked for. None of them may change what the program does.
+fn vary_options(options: CodegenOptions, seed: u64) -> CodegenOptions {
+    let mut options = options;
+    if seed & 1 != 0 {
+        options = options.with_opt_level(OptLevel::None);
+    }
+    if seed & 2 != 0 {
+        options = options.with_tail_calls(true);
+    }
+    if seed & 4 != 0 {
+        options = options.with_no_const_prop(true);
+    }
+    options
+}
+
+/// What a run of the program printed and how it ended.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    output: String,
+    outcome: Outcome,
+}
+
+/// How a run of the program ended.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Completed,
+
+    /// The program stopped early with the given message.
+    Trapped(String),
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Completed => write!(f, "completed normally"),
+            Outcome::Trapped(message) => write!(f, "trapped: {message}"),
+        }
+    }
+}
+
+/// Instantiates the wasm module `bytes` and calls its `main` export,
+/// capturing what it writes through the print hooks (see [`PRINT_HOOK_MODULE`]).
+///
+/// Returns `Err` with the reason if the module imports anything the harness does not provide,
+/// e.g., a host function declared with `extern fn`, or the hook for values that
+/// generated code cannot format yet.
+fn run_wasm(bytes: &[u8]) -> Fallible<Result<Run, String>> {
+    let mut config = Config::new();
+    config.wasm_tail_call(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, bytes)?;
+
+    let output = Arc::new(Mutex::new(String::new()));
+    let mut linker = Linker::new(&engine);
+
+    // The panic hook receives the index of the panic's entry in the trap table
+    // before the program traps; we check that it agrees with the trap that is reported.
+    let panicked = Arc::new(Mutex::new(None));
+    let (hook_module, hook_name) = PANIC_HOOK_IMPORT;
+    let hook_panicked = panicked.clone();
+    linker.func_wrap(
+        hook_module,
+        hook_name,
+        move |_: Caller<'_, ()>, trap_index: i32| {
+            *hook_panicked.lock().unwrap() = Some(trap_index);
+        },
+    )?;
+    define_print_hooks(&mut linker, &output)?;
+
+    let mut store = Store::new(&engine, ());
+    if let Some(import) = module
+        .imports()
+        .find(|import| linker.get_by_import(&mut store, import).is_none())
+    {
+        return Ok(Err(format!(
+            "the generated module imports `{}.{}`, which the harness does not provide",
+            import.module(),
+            import.name(),
+        )));
+    }
+
+    let instance = linker.instantiate(&mut store, &module)?;
+    let main = instance
+        .get_func(&mut store, MAIN_EXPORT)
+        .ok_or_else(|| anyhow!("generated module does not export `{MAIN_EXPORT}`"))?;
+
+    let mut results = vec![Val::I32(0); main.ty(&store).results().len()];
+    let result = main.call(&mut store, &[], &mut results);
+    let output = std::mem::take(&mut *output.lock().unwrap());
+    let Err(error) = result else {
+        return Ok(Ok(Run {
+            output,
+            outcome: Outcome::Completed,
+        }));
+    };
+
+    let trap_index = instance
+        .get_global(&mut store, TRAP_INDEX_GLOBAL)
+        .and_then(|global| global.get(&mut store).i32());
+    let message = match (custom_section(bytes, TRAP_TABLE_SECTION)?, trap_index) {
+        (Some(trap_table), Some(trap_index)) => {
+            dada_compiler::trap_message(&trap_table, trap_index).map(str::to_string)
+        }
+        _ => None,
+    };
+    match (*panicked.lock().unwrap(), &message) {
+        (Some(panic_index), _) if trap_index != Some(panic_index) => bail!(
+            "the panic hook was called with trap {panic_index}, but the module reported {trap_index:?}"
+        ),
+        (None, Some(message)) if is_panic_message(message) => {
+            bail!("the module panicked without calling the panic hook: {message}")
+        }
+        _ => (),
+    }
+
+    // Traps that are not Dada-level checks (e.g., a stack overflow) have no entry in the table.
+    Ok(Ok(Run {
+        output,
+        outcome: Outcome::Trapped(
+            message.unwrap_or_else(|| format!("wasm trap: {}", error.root_cause())),
+        ),
+    }))
+}
+
+/// True if the trap table entry `message` (`path:line:column: message`) describes a panic.
+fn is_panic_message(message: &str) -> bool {
+    message.contains(": panicked: ") || message.ends_with(": explicit panic")
+}
+
+/// Defines the `write_<type>` hook for each primitive type (`write_char` also writes text),
+/// appending the text of the value to `output` in the format the interpreter prints it.
+fn define_print_hooks(linker: &mut Linker<()>, output: &Arc<Mutex<String>>) -> Fallible<()> {
+    let write = |linker: &mut Linker<()>, ty: &str, format: fn(i64) -> String| -> Fallible<()> {
+        let output = output.clone();
+        let name = format!("write_{ty}");
+        if matches!(ty, "i64" | "u64") {
+            linker.func_wrap(PRINT_HOOK_MODULE, &name, move |value: i64| {
+                output.lock().unwrap().push_str(&format(value))
+            })?;
+        } else {
+            linker.func_wrap(PRINT_HOOK_MODULE, &name, move |value: i32| {
+                output.lock().unwrap().push_str(&format(i64::from(value)))
+            })?;
+        }
+        Ok(())
+    };
+
+    write(linker, "bool", |value| (value != 0).to_string())?;
+    write(linker, "char", |value| {
+        char::from_u32(value as u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+            .to_string()
+    })?;
+    write(linker, "i8", |value| (value as i8).to_string())?;
+    write(linker, "i16", |value| (value as i16).to_string())?;
+    write(linker, "i32", |value| (value as i32).to_string())?;
+    write(linker, "i64", |value| value.to_string())?;
+    write(linker, "isize", |value| (value as i32).to_string())?;
+    write(linker, "u8", |value| (value as u8).to_string())?;
+    write(linker, "u16", |value| (value as u16).to_string())?;
+    write(linker, "u32", |value| (value as u32).to_string())?;
+    write(linker, "u64", |value| (value as u64).to_string())?;
+    write(linker, "usize", |value| (value as u32).to_string())?;
+
+    let f32_output = output.clone();
+    linker.func_wrap(PRINT_HOOK_MODULE, "write_f32", move |value: f32| {
+        f32_output
+            .lock()
+            .unwrap()
+            .push_str(&f64::from(value).to_string())
+    })?;
+    let f64_output = output.clone();
+    linker.func_wrap(PRINT_HOOK_MODULE, "write_f64", move |value: f64| {
+        f64_output.lock().unwrap().push_str(&value.to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Returns the contents of the custom section `name` of the wasm module `bytes`, if any
+/// (e.g., the [`TRAP_TABLE_SECTION`]).
+pub(super) fn custom_section(bytes: &[u8], name: &str) -> Fallible<Option<String>> {
+    for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+        if let wasmparser::Payload::CustomSection(section) = payload?
+            && section.name() == name
+        {
+            return Ok(Some(String::from_utf8(section.data().to_vec())?));
+        }
+    }
+    Ok(None)
+}
@@ -1,8 +1,10 @@
 use std::path::{Path, PathBuf};
 
-use dada_compiler::Compiler;
+use dada_compiler::{
+    CodegenOptions, Compiler, INSTANTIATIONS_SECTION, OptLevel, OutlineKind, OverflowMode,
+};
 use dada_ir_ast::{
-    diagnostic::Diagnostic,
+    diagnostic::{Diagnostic, Level},
     inputs::SourceFile,
     span::{AbsoluteOffset, AbsoluteSpan},
 };
@@ -12,6 +14,7 @@ use regex::Regex;
 
 use crate::GlobalOptions;
 
+use super::differential::{Comparison, custom_section};
 use super::spec_validation::SpecValidator;
 use super::{FailedTest, Failure};
 
@@ -39,9 +42,13 @@ pub struct TestExpectations {
     bless: Bless,
     expected_diagnostics: Vec<ExpectedDiagnostic>,
     fn_asts: bool,
+    interpret: bool,
+    differential: bool,
     codegen: bool,
+    codegen_options: CodegenOptions,
     fixme: bool,
     fixme_ice: bool,
+    max_errors: Option<usize>,
     probes: Vec<Probe>,
     spec_refs: Vec<String>,
 }
@@ -86,6 +93,43 @@ pub enum ProbeKind {
 
     /// Dumps the compact AST representation of the smallest containing expression
     Ast,
+
+    /// Lists the completions offered with the cursor at the end of the span
+    Completions,
+
+    /// Shows the signature help (and active parameter) with the cursor at the end of the span
+    SignatureHelp,
+
+    /// Renames the name at the start of the span to `renamed` and lists the
+    /// `line:column` of each edit (prefixed by the file name for edits in other files),
+    /// or the reason the rename was refused
+    Rename,
+
+    /// Lists the `line:column` of each reference to the name at the start of the span,
+    /// including its declaration, in the same format as `Rename`
+    References,
+
+    /// Lists the outline items enclosing the span, outermost first,
+    /// e.g. `class Point > method norm` (breadcrumbs)
+    Outline,
+
+    /// Shows the semantic highlighting class of the token at the span,
+    /// e.g. `Permission` or `Literal(Integer)`
+    SemanticToken,
+
+    /// Shows the lines of the innermost foldable range enclosing the span, e.g. `3-7`
+    FoldingRange,
+
+    /// Lists the ranges that "expand selection" grows through from the start of the span,
+    /// innermost first, each as `line:column-line:column`
+    SelectionRanges,
+
+    /// Lists the labels of the inlay hints placed within the span (including its end)
+    InlayHints,
+
+    /// Lists the named variables dropped by the function containing the span,
+    /// in the order their drops appear (innermost scope first)
+    Drops,
 }
 
 enum Bless {
@@ -98,6 +142,10 @@ lazy_static::lazy_static! {
     static ref UNINTERESTING_RE: Regex = Regex::new(r"^\s*(#.*)?$").unwrap();
 }
 
+lazy_static::lazy_static! {
+    static ref ATTRIBUTE_RE: Regex = Regex::new(r"^\s*#!?\[").unwrap();
+}
+
 lazy_static::lazy_static! {
     static ref DIAGNOSTIC_RE: Regex = Regex::new(r"^(?P<pre>[^#]*)#!(?P<pad>\s*)(?P<col>\^+)?\s*(?P<re>/)?(?P<msg>.*)").unwrap();
 }
@@ -128,9 +176,13 @@ impl TestExpectations {
             bless,
             expected_diagnostics: vec![],
             fn_asts: false,
+            interpret: false,
+            differential: false,
             codegen: true,
+            codegen_options: CodegenOptions::default(),
             fixme: false,
             fixme_ice: false,
+            max_errors: None,
             probes: vec![],
             spec_refs: vec![],
         };
@@ -152,6 +204,13 @@ impl TestExpectations {
         let mut in_header = true;
         let mut last_interesting_line = None;
         for (line, line_index) in source.lines().zip(0..) {
+            // Attributes (`#[...]`, `#![...]`) look like comments but are code.
+            if ATTRIBUTE_RE.is_match(line) {
+                in_header = false;
+                last_interesting_line = Some(line_index);
+                continue;
+            }
+
             // Allow `#:` configuration lines, but only at the start of the file.
             if in_header {
                 if let Some(suffix) = line.strip_prefix("#:") {
@@ -265,6 +324,16 @@ impl TestExpectations {
                     ("VariableType", ProbeKind::VariableType),
                     ("ExprType", ProbeKind::ExprType),
                     ("Ast", ProbeKind::Ast),
+                    ("Completions", ProbeKind::Completions),
+                    ("SignatureHelp", ProbeKind::SignatureHelp),
+                    ("Rename", ProbeKind::Rename),
+                    ("References", ProbeKind::References),
+                    ("Outline", ProbeKind::Outline),
+                    ("SemanticToken", ProbeKind::SemanticToken),
+                    ("FoldingRange", ProbeKind::FoldingRange),
+                    ("SelectionRanges", ProbeKind::SelectionRanges),
+                    ("InlayHints", ProbeKind::InlayHints),
+                    ("Drops", ProbeKind::Drops),
                 ];
                 let user_probe_kind = c.name("kind").unwrap().as_str();
                 let Some(&(_, kind)) = valid_probe_kinds
@@ -327,11 +396,75 @@ impl TestExpectations {
             return Ok(());
         }
 
+        if line == "interpret" {
+            self.interpret = true;
+            return Ok(());
+        }
+
+        if line == "differential" {
+            self.differential = true;
+            return Ok(());
+        }
+
         if line == "skip_codegen" {
             self.codegen = false;
             return Ok(());
         }
 
+        if line == "tail_calls" {
+            self.codegen_options = self.codegen_options.with_tail_calls(true);
+            return Ok(());
+        }
+
+        if line == "no_const_prop" {
+            self.codegen_options = self.codegen_options.with_no_const_prop(true);
+            return Ok(());
+        }
+
+        if line == "no_opt" {
+            self.codegen_options = self.codegen_options.with_opt_level(OptLevel::None);
+            return Ok(());
+        }
+
+        if line == "emit_wat" {
+            self.codegen_options = self.codegen_options.with_emit_wat(true);
+            return Ok(());
+        }
+
+        if line == "report_instantiations" {
+            self.codegen_options = self.codegen_options.with_report_instantiations(true);
+            return Ok(());
+        }
+
+        if line == "trap_on_overflow" {
+            self.codegen_options = self.codegen_options.with_overflow(OverflowMode::Trap);
+            return Ok(());
+        }
+
+        if let Some(limit) = line.strip_prefix("instantiation_limit ") {
+            let limit = limit.trim().parse().with_context(|| {
+                format!(
+                    "{}:{}: invalid instantiation limit",
+                    self.source_file.url_display(db),
+                    line_index + 1,
+                )
+            })?;
+            self.codegen_options = self.codegen_options.with_instantiation_limit(Some(limit));
+            return Ok(());
+        }
+
+        if let Some(limit) = line.strip_prefix("max_errors ") {
+            let limit = limit.trim().parse().with_context(|| {
+                format!(
+                    "{}:{}: invalid error limit",
+                    self.source_file.url_display(db),
+                    line_index + 1,
+                )
+            })?;
+            self.max_errors = Some(limit);
+            return Ok(());
+        }
+
         if line == "FIXME" {
             self.fixme = true;
             return Ok(());
@@ -358,10 +491,22 @@ impl TestExpectations {
         self.fn_asts
     }
 
+    pub fn interpret(&self) -> bool {
+        self.interpret
+    }
+
+    pub fn differential(&self) -> bool {
+        self.differential
+    }
+
     pub fn codegen(&self) -> bool {
         self.codegen
     }
 
+    pub fn codegen_options(&self) -> CodegenOptions {
+        self.codegen_options
+    }
+
     pub fn fixme(&self) -> bool {
         self.fixme
     }
@@ -370,13 +515,33 @@ impl TestExpectations {
         self.fixme_ice
     }
 
+    pub fn max_errors(&self) -> Option<usize> {
+        self.max_errors
+    }
+
     pub fn spec_refs(&self) -> &[String] {
         &self.spec_refs
     }
 
-    pub fn compare(self, compiler: &mut Compiler) -> Fallible<(Option<FailedTest>, bool)> {
+    /// Compares the actual results of compiling the test against the expectations.
+    /// If `differential` is set, or the test is marked `#:differential`, also checks that
+    /// the interpreter and generated wasm agree, varying codegen options by `differential_seed`
+    /// (see `--differential`). If `check_determinism` is set, the wasm is generated twice
+    /// and a difference between the two is reported as an internal compiler error
+    /// (see `--check-determinism`).
+    pub fn compare(
+        self,
+        compiler: &mut Compiler,
+        differential: bool,
+        differential_seed: u64,
+        check_determinism: bool,
+    ) -> Fallible<(Option<FailedTest>, bool)> {
         use std::fmt::Write;
 
+        if let Some(max_errors) = self.max_errors {
+            compiler.set_max_errors(max_errors);
+        }
+
         let is_fixme = self.fixme;
         let mut test = FailedTest {
             path: self.source_file.url(compiler).to_file_path().unwrap(),
@@ -391,10 +556,57 @@ impl TestExpectations {
             Self::generate_fn_asts,
         )?);
 
-        let actual_diagnostics = compiler.check_all(self.source_file);
+        test.failures.extend(self.compare_auxiliary(
+            compiler,
+            "interpret",
+            self.interpret,
+            Self::generate_interpret,
+        )?);
 
-        if self.codegen {
-            let _wasm_bytes = compiler.codegen_main_fn(self.source_file);
+        test.failures.extend(self.compare_auxiliary(
+            compiler,
+            "wat",
+            self.codegen && self.codegen_options.emit_wat,
+            Self::generate_wat,
+        )?);
+
+        test.failures.extend(self.compare_auxiliary(
+            compiler,
+            "instantiations",
+            self.codegen && self.codegen_options.report_instantiations,
+            Self::generate_instantiations,
+        )?);
+
+        let codegen_options = self
+            .codegen_options
+            .with_check_determinism(check_determinism);
+        let actual_diagnostics = if self.codegen {
+            let _wasm_bytes = compiler.codegen_main_fn(self.source_file, codegen_options);
+            compiler.check_all_with_codegen(self.source_file, codegen_options)
+        } else {
+            compiler.check_all(self.source_file)
+        };
+
+        if (differential || self.differential)
+            && self.codegen
+            && !actual_diagnostics.iter().any(|d| d.level >= Level::Error)
+        {
+            match super::differential::compare_executions(
+                compiler,
+                self.source_file,
+                codegen_options,
+                differential_seed,
+            )? {
+                Comparison::Agreed => (),
+                Comparison::Diverged(failure) => test.failures.push(failure),
+                // Under `--differential`, tests that cannot run both ways are skipped;
+                // one that asks to run both ways must be able to.
+                Comparison::NotRunnable(reason) => {
+                    if self.differential {
+                        test.failures.push(Failure::NotDifferential(reason));
+                    }
+                }
+            }
         }
 
         test.failures.extend(self.perform_probes(compiler));
@@ -432,6 +644,93 @@ impl TestExpectations {
                     ProbeKind::Ast => compiler
                         .probe_ast(probe.span)
                         .unwrap_or_else(|| "<no expression found>".to_string()),
+                    ProbeKind::Completions => compiler
+                        .completions(probe.span.source_file, probe.span.end)
+                        .iter()
+                        .map(|completion| completion.label.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ProbeKind::SignatureHelp => {
+                        match compiler.signature_help(probe.span.source_file, probe.span.end) {
+                            Some(help) => match help.active_parameter {
+                                Some(index) => format!("{}; active: {index}", help.label),
+                                None => format!("{}; active: none", help.label),
+                            },
+                            None => "<no call found>".to_string(),
+                        }
+                    }
+                    ProbeKind::Rename => {
+                        match compiler.rename(probe.span.source_file, probe.span.start, "renamed") {
+                            Ok(edits) => format_positions(
+                                compiler,
+                                probe.span.source_file,
+                                edits.iter().map(|edit| edit.span),
+                            ),
+                            Err(diagnostic) => diagnostic.message,
+                        }
+                    }
+                    ProbeKind::References => format_positions(
+                        compiler,
+                        probe.span.source_file,
+                        compiler.find_references(probe.span.source_file, probe.span.start, true),
+                    ),
+                    ProbeKind::Outline => {
+                        let mut breadcrumbs = vec![];
+                        let mut items = compiler.outline(probe.span.source_file);
+                        while let Some(item) = items
+                            .into_iter()
+                            .find(|item| item.span.contains(probe.span))
+                        {
+                            breadcrumbs.push(format!("{} {}", outline_kind(item.kind), item.name));
+                            items = item.children;
+                        }
+                        if breadcrumbs.is_empty() {
+                            "<top level>".to_string()
+                        } else {
+                            breadcrumbs.join(" > ")
+                        }
+                    }
+                    ProbeKind::SemanticToken => compiler
+                        .semantic_tokens(probe.span.source_file)
+                        .into_iter()
+                        .find(|token| token.span.contains(probe.span))
+                        .map(|token| format!("{:?}", token.kind))
+                        .unwrap_or_else(|| "<no token>".to_string()),
+                    ProbeKind::FoldingRange => compiler
+                        .folding_ranges(probe.span.source_file)
+                        .into_iter()
+                        .filter(|span| span.contains(probe.span))
+                        .min_by_key(|span| span.end.as_usize() - span.start.as_usize())
+                        .map(|span| {
+                            let (start, _) = span.source_file.line_col(compiler, span.start);
+                            let (end, _) = span.source_file.line_col(compiler, span.end);
+                            format!("{}-{}", start.as_u32() + 1, end.as_u32() + 1)
+                        })
+                        .unwrap_or_else(|| "<no folding range>".to_string()),
+                    ProbeKind::SelectionRanges => compiler
+                        .selection_ranges(probe.span.source_file, probe.span.start)
+                        .into_iter()
+                        .map(|span| {
+                            let position = |offset| {
+                                let (line, column) = span.source_file.line_col(compiler, offset);
+                                format!("{}:{}", line.as_u32() + 1, column.as_u32() + 1)
+                            };
+                            format!("{}-{}", position(span.start), position(span.end))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ProbeKind::InlayHints => compiler
+                        .inlay_hints(probe.span.source_file)
+                        .into_iter()
+                        .filter(|hint| {
+                            probe.span.start <= hint.offset && hint.offset <= probe.span.end
+                        })
+                        .map(|hint| format!("{:?}", hint.label))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ProbeKind::Drops => compiler
+                        .probe_drops(probe.span)
+                        .unwrap_or_else(|| "<no function body found>".to_string()),
                 };
 
                 if probe.message.is_match(&actual) {
@@ -450,6 +749,51 @@ impl TestExpectations {
         compiler.fn_asts(self.source_file)
     }
 
+    /// Output of the program when run with the interpreter, followed by the error that stopped it (if any).
+    /// Errors are reported as `line:column: message`, so that the output does not depend on the path of the test.
+    fn generate_interpret(&self, compiler: &mut Compiler) -> String {
+        let Some(execution) = compiler.interpret_main(self.source_file) else {
+            return "no `main` function to run\n".to_string();
+        };
+        let mut output = execution.output;
+        if let Err(error) = execution.result {
+            let (line, column) = self.source_file.line_col(compiler, error.span.start);
+            output.push_str(&format!(
+                "error: {}:{}: {}\n",
+                line.as_u32() + 1,
+                column.as_u32() + 1,
+                error.message
+            ));
+        }
+        output
+    }
+
+    /// The module generated for `main`, as WebAssembly text.
+    fn generate_wat(&self, compiler: &mut Compiler) -> String {
+        match compiler.codegen_main_fn_wat(self.source_file, self.codegen_options) {
+            Ok(Some(wat)) => wat,
+            Ok(None) => "no `main` function to compile\n".to_string(),
+            Err(error) => format!("error: {error}\n"),
+        }
+    }
+
+    /// How many times each generic function was instantiated in the module generated for `main`,
+    /// one `LINE:COL: name: count` line per function.
+    fn generate_instantiations(&self, compiler: &mut Compiler) -> String {
+        let prefix = format!("{}:", self.source_file.url_display(compiler));
+        let Some(bytes) = compiler.codegen_main_fn(self.source_file, self.codegen_options) else {
+            return "no `main` function to compile\n".to_string();
+        };
+        match custom_section(bytes, INSTANTIATIONS_SECTION) {
+            Ok(Some(report)) => report
+                .lines()
+                .map(|line| format!("{}\n", line.strip_prefix(&prefix).unwrap_or(line)))
+                .collect(),
+            Ok(None) => format!("no `{INSTANTIATIONS_SECTION}` section\n"),
+            Err(error) => format!("error: {error}\n"),
+        }
+    }
+
     fn compare_auxiliary(
         &self,
         compiler: &mut Compiler,
@@ -648,3 +992,37 @@ impl Bless {
         }
     }
 }
+
+/// Formats each span as `line:column` (one-based), prefixed by the file name
+/// if it is not in `source_file`.
+fn format_positions(
+    compiler: &Compiler,
+    source_file: SourceFile,
+    spans: impl IntoIterator<Item = AbsoluteSpan>,
+) -> String {
+    spans
+        .into_iter()
+        .map(|span| {
+            let (line, column) = span.source_file.line_col(compiler, span.start);
+            let position = format!("{}:{}", line.as_u32() + 1, column.as_u32() + 1);
+            if span.source_file == source_file {
+                position
+            } else {
+                let url = span.source_file.url(compiler);
+                let file_name = url.path_segments().and_then(|mut s| s.next_back());
+                format!("{}:{position}", file_name.unwrap_or_default())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn outline_kind(kind: OutlineKind) -> &'static str {
+    match kind {
+        OutlineKind::Class => "class",
+        OutlineKind::Struct => "struct",
+        OutlineKind::Field => "field",
+        OutlineKind::Function => "fn",
+        OutlineKind::Method => "method",
+    }
+}
@@ -45,12 +45,82 @@ pub trait Lsp: Sized {
         item: lsp_types::DidChangeTextDocumentParams,
     ) -> Fallible<()>;
 
+    /// Close reported for the given URI.
+    fn did_close(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        item: lsp_types::DidCloseTextDocumentParams,
+    ) -> Fallible<()>;
+
     /// Handle hover requests.
     fn hover(
         &mut self,
         editor: &mut dyn Editor<Self>,
         params: lsp_types::HoverParams,
     ) -> Fallible<Option<lsp_types::Hover>>;
+
+    /// Handle completion requests.
+    fn completion(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::CompletionParams,
+    ) -> Fallible<Option<lsp_types::CompletionResponse>>;
+
+    /// Handle signature help requests.
+    fn signature_help(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::SignatureHelpParams,
+    ) -> Fallible<Option<lsp_types::SignatureHelp>>;
+
+    /// Handle rename requests.
+    fn rename(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::RenameParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceEdit>>;
+
+    /// Handle find-references requests.
+    fn references(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::ReferenceParams,
+    ) -> Fallible<Option<Vec<lsp_types::Location>>>;
+
+    /// Handle document symbol (outline) requests.
+    fn document_symbol(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::DocumentSymbolParams,
+    ) -> Fallible<Option<lsp_types::DocumentSymbolResponse>>;
+
+    /// Handle requests for the semantic tokens of a whole document.
+    fn semantic_tokens_full(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::SemanticTokensParams,
+    ) -> Fallible<Option<lsp_types::SemanticTokensResult>>;
+
+    /// Handle folding range requests.
+    fn folding_range(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::FoldingRangeParams,
+    ) -> Fallible<Option<Vec<lsp_types::FoldingRange>>>;
+
+    /// Handle selection range ("expand selection") requests.
+    fn selection_range(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::SelectionRangeParams,
+    ) -> Fallible<Option<Vec<lsp_types::SelectionRange>>>;
+
+    /// Handle inlay hint requests.
+    fn inlay_hint(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::InlayHintParams,
+    ) -> Fallible<Option<Vec<lsp_types::InlayHint>>>;
 }
 
 pub trait LspFork: Sized + Send {
@@ -88,7 +158,17 @@ pub fn run_server<L: Lsp>() -> Fallible<()> {
     LspDispatch::new(connection, lsp)
         .on_notification::<notification::DidOpenTextDocument>(Lsp::did_open)
         .on_notification::<notification::DidChangeTextDocument>(Lsp::did_change)
+        .on_notification::<notification::DidCloseTextDocument>(Lsp::did_close)
         .on_request::<request::HoverRequest>(Lsp::hover)
+        .on_request::<request::Completion>(Lsp::completion)
+        .on_request::<request::SignatureHelpRequest>(Lsp::signature_help)
+        .on_request::<request::Rename>(Lsp::rename)
+        .on_request::<request::References>(Lsp::references)
+        .on_request::<request::DocumentSymbolRequest>(Lsp::document_symbol)
+        .on_request::<request::SemanticTokensFullRequest>(Lsp::semantic_tokens_full)
+        .on_request::<request::FoldingRangeRequest>(Lsp::folding_range)
+        .on_request::<request::SelectionRangeRequest>(Lsp::selection_range)
+        .on_request::<request::InlayHintRequest>(Lsp::inlay_hint)
         .execute()?;
 
     io_threads.join()?;
@@ -1,21 +1,27 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
-use dada_compiler::{Compiler, Fork, RealFs};
+use dada_compiler::{
+    Compiler, CompletionKind, Fork, InlayHintKind, OutlineItem, OutlineKind, RealFs,
+    SemanticTokenKind,
+};
+use dada_ir_ast::ast::LiteralKind;
 use dada_ir_ast::diagnostic::{Diagnostic, DiagnosticLabel, Level};
 use dada_ir_ast::inputs::SourceFile;
 use dada_ir_ast::span::{AbsoluteOffset, AbsoluteSpan};
 use dada_util::{Fallible, Map, Set, bail};
 use lsp::{Editor, Lsp, LspFork};
 use lsp_types::{
-    DidChangeTextDocumentParams, DidOpenTextDocumentParams, HoverProviderCapability, MessageType,
-    OneOf, PublishDiagnosticsParams, TextDocumentContentChangeEvent, TextDocumentItem,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, Uri,
-    VersionedTextDocumentIdentifier,
+    CompletionOptions, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, HoverProviderCapability, MessageType, OneOf,
+    PublishDiagnosticsParams, SignatureHelpOptions, TextDocumentContentChangeEvent,
+    TextDocumentItem, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    Uri, VersionedTextDocumentIdentifier,
 };
 use lsp_types::{InitializeParams, ServerCapabilities};
 
-use salsa::{Database, Setter};
+use salsa::Database;
 use url::Url;
 
 mod lsp;
@@ -59,6 +65,37 @@ impl lsp::Lsp for Server {
                 },
             )),
             definition_provider: Some(OneOf::Left(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            rename_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(lsp_types::FoldingRangeProviderCapability::Simple(true)),
+            selection_range_provider: Some(lsp_types::SelectionRangeProviderCapability::Simple(
+                true,
+            )),
+            semantic_tokens_provider: Some(
+                lsp_types::SemanticTokensOptions {
+                    legend: lsp_types::SemanticTokensLegend {
+                        token_types: SEMANTIC_TOKEN_KINDS
+                            .iter()
+                            .map(|&kind| ServerFork::lsp_semantic_token_type(kind))
+                            .collect(),
+                        token_modifiers: vec![],
+                    },
+                    full: Some(lsp_types::SemanticTokensFullOptions::Bool(true)),
+                    range: None,
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                ..SignatureHelpOptions::default()
+            }),
             ..ServerCapabilities::default()
         })
     }
@@ -122,7 +159,7 @@ impl lsp::Lsp for Server {
                     bail!("we requested full content change events");
                 }
                 None => {
-                    let _old_contents = source_file.set_contents(&mut self.db).to(Ok(text));
+                    self.db.open_source_file(uri_str, Ok(text))?;
                 }
             }
         }
@@ -134,6 +171,27 @@ impl lsp::Lsp for Server {
         Ok(())
     }
 
+    fn did_close(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: DidCloseTextDocumentParams,
+    ) -> Fallible<()> {
+        let DidCloseTextDocumentParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri },
+        } = params;
+        let uri_str = uri.as_str();
+
+        // The file may have unsaved changes; go back to checking what is on disk.
+        let source_file = self.db.get_previously_opened_source_file(uri_str)?;
+        self.db.close_source_file(uri_str)?;
+
+        editor.show_message(MessageType::INFO, format!("did close {uri_str}"))?;
+
+        editor.spawn(ServerFork::check_all_task(source_file));
+
+        Ok(())
+    }
+
     fn hover(
         &mut self,
         _editor: &mut dyn Editor<Self>,
@@ -152,22 +210,9 @@ impl lsp::Lsp for Server {
         let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
 
         // Convert LSP position to absolute offset
-        let line = position.line as usize;
-        let character = position.character as usize;
-
-        // Get line starts
-        let line_starts = source_file.line_starts(&self.db);
-
-        // Make sure the line is valid
-        if line >= line_starts.len() - 1 {
+        let Some(offset) = ServerFork::lsp_offset(&self.db, source_file, position) else {
             return Ok(None);
-        }
-
-        // Get the start offset of the line
-        let line_start = line_starts[line];
-
-        // Calculate the absolute offset
-        let offset = AbsoluteOffset::from(line_start.as_usize() + character);
+        };
 
         // Create a span at the position
         let span = AbsoluteSpan {
@@ -192,8 +237,344 @@ impl lsp::Lsp for Server {
             Ok(None)
         })
     }
+
+    fn completion(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::CompletionParams,
+    ) -> Fallible<Option<lsp_types::CompletionResponse>> {
+        let lsp_types::CompletionParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            partial_result_params: _,
+            context: _,
+        } = params;
+
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+        let Some(offset) = ServerFork::lsp_offset(&self.db, source_file, position) else {
+            return Ok(None);
+        };
+
+        let items = self
+            .db
+            .completions(source_file, offset)
+            .into_iter()
+            .map(|completion| lsp_types::CompletionItem {
+                kind: Some(ServerFork::lsp_completion_kind(completion.kind)),
+                label: completion.label,
+                ..Default::default()
+            })
+            .collect();
+        Ok(Some(lsp_types::CompletionResponse::Array(items)))
+    }
+
+    fn signature_help(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::SignatureHelpParams,
+    ) -> Fallible<Option<lsp_types::SignatureHelp>> {
+        let lsp_types::SignatureHelpParams {
+            text_document_position_params:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            context: _,
+        } = params;
+
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+        let Some(offset) = ServerFork::lsp_offset(&self.db, source_file, position) else {
+            return Ok(None);
+        };
+        let Some(help) = self.db.signature_help(source_file, offset) else {
+            return Ok(None);
+        };
+
+        let active_parameter = help.active_parameter.map(|index| index as u32);
+        let parameters = help
+            .parameters
+            .into_iter()
+            .map(|parameter| lsp_types::ParameterInformation {
+                label: lsp_types::ParameterLabel::Simple(parameter),
+                documentation: None,
+            })
+            .collect();
+        Ok(Some(lsp_types::SignatureHelp {
+            signatures: vec![lsp_types::SignatureInformation {
+                label: help.label,
+                documentation: None,
+                parameters: Some(parameters),
+                active_parameter,
+            }],
+            active_signature: Some(0),
+            active_parameter,
+        }))
+    }
+
+    fn rename(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::RenameParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceEdit>> {
+        let lsp_types::RenameParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            new_name,
+            work_done_progress_params: _,
+        } = params;
+
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+        let Some(offset) = ServerFork::lsp_offset(&self.db, source_file, position) else {
+            return Ok(None);
+        };
+
+        // A rename that is refused is not an error in the server, so report it to the user.
+        let edits = match self.db.rename(source_file, offset, &new_name) {
+            Ok(edits) => edits,
+            Err(diagnostic) => {
+                editor.show_message(MessageType::ERROR, diagnostic.message)?;
+                return Ok(None);
+            }
+        };
+
+        let mut changes: HashMap<Uri, Vec<lsp_types::TextEdit>> = HashMap::new();
+        for edit in edits {
+            changes
+                .entry(ServerFork::lsp_uri(edit.span.source_file.url(&self.db)))
+                .or_default()
+                .push(lsp_types::TextEdit {
+                    range: ServerFork::lsp_range(&self.db, edit.span),
+                    new_text: edit.new_text,
+                });
+        }
+        Ok(Some(lsp_types::WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    fn references(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::ReferenceParams,
+    ) -> Fallible<Option<Vec<lsp_types::Location>>> {
+        let lsp_types::ReferenceParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            partial_result_params: _,
+            context:
+                lsp_types::ReferenceContext {
+                    include_declaration,
+                },
+        } = params;
+
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+        let Some(offset) = ServerFork::lsp_offset(&self.db, source_file, position) else {
+            return Ok(None);
+        };
+
+        let locations = self
+            .db
+            .find_references(source_file, offset, include_declaration)
+            .into_iter()
+            .map(|span| lsp_types::Location {
+                uri: ServerFork::lsp_uri(span.source_file.url(&self.db)),
+                range: ServerFork::lsp_range(&self.db, span),
+            })
+            .collect();
+        Ok(Some(locations))
+    }
+
+    fn document_symbol(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::DocumentSymbolParams,
+    ) -> Fallible<Option<lsp_types::DocumentSymbolResponse>> {
+        let source_file = self
+            .db
+            .get_previously_opened_source_file(params.text_document.uri.as_str())?;
+        let symbols = self
+            .db
+            .outline(source_file)
+            .into_iter()
+            .map(|item| ServerFork::lsp_document_symbol(&self.db, item))
+            .collect();
+        Ok(Some(lsp_types::DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    fn semantic_tokens_full(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::SemanticTokensParams,
+    ) -> Fallible<Option<lsp_types::SemanticTokensResult>> {
+        let source_file = self
+            .db
+            .get_previously_opened_source_file(params.text_document.uri.as_str())?;
+
+        // Each token's position is encoded relative to the previous token.
+        let mut data = vec![];
+        let mut previous = lsp_types::Position::default();
+        for token in self.db.semantic_tokens(source_file) {
+            let start = ServerFork::lsp_position(&self.db, source_file, token.span.start);
+            let end = ServerFork::lsp_position(&self.db, source_file, token.span.end);
+
+            // Not all editors support tokens that span lines (e.g., multiline strings).
+            if start.line != end.line {
+                continue;
+            }
+
+            let token_type = SEMANTIC_TOKEN_KINDS
+                .iter()
+                .position(|&kind| kind == token.kind)
+                .unwrap();
+            data.push(lsp_types::SemanticToken {
+                delta_line: start.line - previous.line,
+                delta_start: if start.line == previous.line {
+                    start.character - previous.character
+                } else {
+                    start.character
+                },
+                length: end.character - start.character,
+                token_type: token_type as u32,
+                token_modifiers_bitset: 0,
+            });
+            previous = start;
+        }
+
+        Ok(Some(
+            lsp_types::SemanticTokens {
+                result_id: None,
+                data,
+            }
+            .into(),
+        ))
+    }
+
+    fn folding_range(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::FoldingRangeParams,
+    ) -> Fallible<Option<Vec<lsp_types::FoldingRange>>> {
+        let source_file = self
+            .db
+            .get_previously_opened_source_file(params.text_document.uri.as_str())?;
+        let ranges = self
+            .db
+            .folding_ranges(source_file)
+            .into_iter()
+            .map(|span| ServerFork::lsp_range(&self.db, span))
+            .filter(|range| range.start.line < range.end.line)
+            .map(|range| lsp_types::FoldingRange {
+                start_line: range.start.line,
+                start_character: Some(range.start.character),
+                end_line: range.end.line,
+                end_character: Some(range.end.character),
+                kind: None,
+                collapsed_text: None,
+            })
+            .collect();
+        Ok(Some(ranges))
+    }
+
+    fn selection_range(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::SelectionRangeParams,
+    ) -> Fallible<Option<Vec<lsp_types::SelectionRange>>> {
+        let source_file = self
+            .db
+            .get_previously_opened_source_file(params.text_document.uri.as_str())?;
+
+        // There must be one result per position, so a position past
+        // the end of the file selects nothing.
+        let mut results = vec![];
+        for position in params.positions {
+            let spans = match ServerFork::lsp_offset(&self.db, source_file, position) {
+                Some(offset) => self.db.selection_ranges(source_file, offset),
+                None => vec![],
+            };
+            let empty = lsp_types::SelectionRange {
+                range: lsp_types::Range::new(position, position),
+                parent: None,
+            };
+            let result = spans.into_iter().rev().fold(None, |parent, span| {
+                Some(lsp_types::SelectionRange {
+                    range: ServerFork::lsp_range(&self.db, span),
+                    parent: parent.map(Box::new),
+                })
+            });
+            results.push(result.unwrap_or(empty));
+        }
+        Ok(Some(results))
+    }
+
+    fn inlay_hint(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::InlayHintParams,
+    ) -> Fallible<Option<Vec<lsp_types::InlayHint>>> {
+        let source_file = self
+            .db
+            .get_previously_opened_source_file(params.text_document.uri.as_str())?;
+        let hints = self
+            .db
+            .inlay_hints(source_file)
+            .into_iter()
+            .map(|hint| {
+                (
+                    ServerFork::lsp_position(&self.db, source_file, hint.offset),
+                    hint,
+                )
+            })
+            .filter(|(position, _)| {
+                params.range.start <= *position && *position <= params.range.end
+            })
+            .map(|(position, hint)| lsp_types::InlayHint {
+                position,
+                label: lsp_types::InlayHintLabel::String(hint.label),
+                kind: Some(match hint.kind {
+                    InlayHintKind::Type => lsp_types::InlayHintKind::TYPE,
+                    InlayHintKind::Permission => lsp_types::InlayHintKind::PARAMETER,
+                }),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            })
+            .collect();
+        Ok(Some(hints))
+    }
 }
 
+/// The semantic token kinds in the order of the legend we send to the editor.
+const SEMANTIC_TOKEN_KINDS: &[SemanticTokenKind] = &[
+    SemanticTokenKind::Keyword,
+    SemanticTokenKind::Permission,
+    SemanticTokenKind::Type,
+    SemanticTokenKind::Function,
+    SemanticTokenKind::Variable,
+    SemanticTokenKind::Field,
+    SemanticTokenKind::Module,
+    SemanticTokenKind::Literal(LiteralKind::Boolean),
+    SemanticTokenKind::Literal(LiteralKind::Integer),
+    SemanticTokenKind::Literal(LiteralKind::String),
+    SemanticTokenKind::Literal(LiteralKind::Char),
+    SemanticTokenKind::Operator,
+];
+
 struct ServerFork {
     db: Fork<Compiler>,
     diagnostics: Arc<Mutex<EditorDiagnostics>>,
@@ -340,6 +721,96 @@ impl EditorDiagnostics {
         }
     }
 
+    /// Converts an LSP position into an offset in `source_file`,
+    /// returning `None` if it is past the end of the file.
+    fn lsp_offset(
+        db: &Compiler,
+        source_file: SourceFile,
+        position: lsp_types::Position,
+    ) -> Option<AbsoluteOffset> {
+        let line = position.line as usize;
+        let character = position.character as usize;
+
+        // Get line starts
+        let line_starts = source_file.line_starts(db);
+
+        // Make sure the line is valid
+        if line >= line_starts.len() - 1 {
+            return None;
+        }
+
+        // Get the start offset of the line
+        let line_start = line_starts[line];
+
+        // Calculate the absolute offset
+        Some(AbsoluteOffset::from(line_start.as_usize() + character))
+    }
+
+    fn lsp_completion_kind(kind: CompletionKind) -> lsp_types::CompletionItemKind {
+        match kind {
+            CompletionKind::Variable => lsp_types::CompletionItemKind::VARIABLE,
+            CompletionKind::Function => lsp_types::CompletionItemKind::FUNCTION,
+            CompletionKind::Class => lsp_types::CompletionItemKind::CLASS,
+            CompletionKind::Struct => lsp_types::CompletionItemKind::STRUCT,
+            CompletionKind::Module => lsp_types::CompletionItemKind::MODULE,
+            CompletionKind::Primitive => lsp_types::CompletionItemKind::KEYWORD,
+            CompletionKind::TypeParameter | CompletionKind::PermParameter => {
+                lsp_types::CompletionItemKind::TYPE_PARAMETER
+            }
+            CompletionKind::Field => lsp_types::CompletionItemKind::FIELD,
+            CompletionKind::Method => lsp_types::CompletionItemKind::METHOD,
+        }
+    }
+
+    #[expect(deprecated)] // the `deprecated` field must still be given
+    fn lsp_document_symbol(db: &Compiler, item: OutlineItem) -> lsp_types::DocumentSymbol {
+        lsp_types::DocumentSymbol {
+            name: item.name,
+            detail: None,
+            kind: match item.kind {
+                OutlineKind::Class => lsp_types::SymbolKind::CLASS,
+                OutlineKind::Struct => lsp_types::SymbolKind::STRUCT,
+                OutlineKind::Field => lsp_types::SymbolKind::FIELD,
+                OutlineKind::Function => lsp_types::SymbolKind::FUNCTION,
+                OutlineKind::Method => lsp_types::SymbolKind::METHOD,
+            },
+            tags: None,
+            deprecated: None,
+            range: Self::lsp_range(db, item.span),
+            selection_range: Self::lsp_range(db, item.name_span),
+            children: Some(
+                item.children
+                    .into_iter()
+                    .map(|child| Self::lsp_document_symbol(db, child))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn lsp_semantic_token_type(kind: SemanticTokenKind) -> lsp_types::SemanticTokenType {
+        match kind {
+            SemanticTokenKind::Keyword => lsp_types::SemanticTokenType::KEYWORD,
+            // 💡 There is no standard type for permissions, so editors
+            // need a theme that knows about this one to color them.
+            SemanticTokenKind::Permission => lsp_types::SemanticTokenType::new("permission"),
+            SemanticTokenKind::Type => lsp_types::SemanticTokenType::TYPE,
+            SemanticTokenKind::Function => lsp_types::SemanticTokenType::FUNCTION,
+            SemanticTokenKind::Variable => lsp_types::SemanticTokenType::VARIABLE,
+            SemanticTokenKind::Field => lsp_types::SemanticTokenType::PROPERTY,
+            SemanticTokenKind::Module => lsp_types::SemanticTokenType::NAMESPACE,
+            SemanticTokenKind::Literal(LiteralKind::Boolean) => {
+                lsp_types::SemanticTokenType::KEYWORD
+            }
+            SemanticTokenKind::Literal(LiteralKind::Integer) => {
+                lsp_types::SemanticTokenType::NUMBER
+            }
+            SemanticTokenKind::Literal(LiteralKind::String | LiteralKind::Char) => {
+                lsp_types::SemanticTokenType::STRING
+            }
+            SemanticTokenKind::Operator => lsp_types::SemanticTokenType::OPERATOR,
+        }
+    }
+
     fn lsp_uri(url: &Url) -> Uri {
         Uri::from_str(url.as_str()).unwrap()
     }
@@ -0,0 +1,173 @@
+use dada_ir_ast::{
+    ast::{AstAttribute, AstDocComment, SpannedIdentifier},
+    diagnostic::{Diagnostic, Level},
+    span::Span,
+};
+
+use crate::tokenizer::{Delimiter, operator};
+
+use super::{Expected, Parse, ParseFail, Parser};
+
+/// Parses an item that may be preceded by a doc comment and attributes. These are parsed first
+/// and held in the parser while `op` parses the item itself; the item's parser claims them
+/// with [`Parser::take_doc_comment`] and [`Parser::take_attributes`] once it knows
+/// it is parsing that kind of item.
+pub(crate) fn with_attributes<'db, T>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+    op: impl FnOnce(&mut Parser<'_, 'db>) -> Result<Option<T>, ParseFail<'db>>,
+) -> Result<Option<T>, ParseFail<'db>> {
+    // The doc comment may come before or after the attributes.
+    // Take the lines before eating the attributes, as consuming a token discards them.
+    let mut doc_lines = parser.take_doc_lines();
+    let attributes = eat_attributes(db, parser)?;
+    doc_lines.extend(parser.take_doc_lines());
+
+    let outer_attributes = std::mem::replace(&mut parser.attributes, attributes);
+    let outer_doc_comment = std::mem::replace(&mut parser.doc_comment, doc_comment(db, &doc_lines));
+    let result = op(parser);
+    let unclaimed = std::mem::replace(&mut parser.attributes, outer_attributes);
+    parser.doc_comment = outer_doc_comment;
+
+    match result {
+        Ok(Some(item)) => {
+            if let (Some(first), Some(last)) = (unclaimed.first(), unclaimed.last()) {
+                let span = first.span.to(db, last.span);
+                parser.push_diagnostic(
+                    Diagnostic::error(db, span, "attributes are not supported here").label(
+                        db,
+                        Level::Error,
+                        span,
+                        "only functions, classes, and structs can have attributes",
+                    ),
+                );
+            }
+            Ok(Some(item))
+        }
+        Ok(None) if unclaimed.is_empty() => Ok(None),
+        Ok(None) => Err(parser.illformed(Expected::Nonterminal("an item"))),
+        Err(err) => Err(err),
+    }
+}
+
+/// Combines consecutive `##` lines into a doc comment.
+fn doc_comment<'db>(
+    db: &'db dyn crate::Db,
+    doc_lines: &[(Span<'db>, &str)],
+) -> Option<AstDocComment<'db>> {
+    let (first, _) = doc_lines.first()?;
+    let (last, _) = doc_lines.last()?;
+    Some(AstDocComment {
+        span: first.to(db, *last),
+        text: doc_lines
+            .iter()
+            .map(|(_, text)| *text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    })
+}
+
+/// Parses zero or more `#[...]` attributes.
+/// Module attributes (`#![...]`) found here are reported as misplaced and discarded.
+fn eat_attributes<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+) -> Result<Vec<AstAttribute<'db>>, ParseFail<'db>> {
+    let mut attributes = vec![];
+    loop {
+        if let Some(attribute) = AstAttribute::opt_parse(db, parser)? {
+            attributes.push(attribute);
+        } else if let Some(attribute) = ModuleAttribute::opt_parse(db, parser)? {
+            parser.push_diagnostic(
+                Diagnostic::error(db, attribute.span, "misplaced module attribute").label(
+                    db,
+                    Level::Error,
+                    attribute.span,
+                    "attributes that apply to the module (`#![...]`) must come at the start of the file",
+                ),
+            );
+        } else {
+            return Ok(attributes);
+        }
+    }
+}
+
+/// `#[name(arg, ...)]`
+impl<'db> Parse<'db> for AstAttribute<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        let Ok(start) = parser.eat_op(operator::HASH) else {
+            return Ok(None);
+        };
+        Ok(Some(eat_attribute_after_hash(db, parser, start)?))
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("attribute")
+    }
+}
+
+/// `#![name(arg, ...)]`, an attribute that applies to the enclosing module.
+pub(crate) struct ModuleAttribute;
+
+impl<'db> Parse<'db> for ModuleAttribute {
+    type Output = AstAttribute<'db>;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<AstAttribute<'db>>, ParseFail<'db>> {
+        let Ok(start) = parser.eat_op(operator::HASHBANG) else {
+            return Ok(None);
+        };
+        Ok(Some(eat_attribute_after_hash(db, parser, start)?))
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("module attribute")
+    }
+}
+
+/// Parses the `[name(arg, ...)]` that follows `#` or `#!` (whose span is `start`).
+fn eat_attribute_after_hash<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+    start: Span<'db>,
+) -> Result<AstAttribute<'db>, ParseFail<'db>> {
+    let (name, args) =
+        AstAttribute::eat_delimited(db, parser, Delimiter::SquareBrackets, |db, parser| {
+            let name = parser.eat_id()?;
+            let args = SpannedIdentifier::opt_parse_delimited(
+                db,
+                parser,
+                Delimiter::Parentheses,
+                SpannedIdentifier::eat_comma,
+            )?;
+            Ok((name, args))
+        })?;
+
+    Ok(AstAttribute {
+        span: start.to(db, parser.last_span()),
+        name,
+        args,
+    })
+}
+
+impl<'db> Parse<'db> for SpannedIdentifier<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        _db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        Ok(parser.eat_id().ok())
+    }
+
+    fn expected() -> Expected {
+        Expected::Identifier
+    }
+}
@@ -0,0 +1,440 @@
+//! Incremental tokenization of source files.
+//!
+//! Editing a file changes its [`SourceFile::contents`][] as a whole, so a query that tokenizes
+//! the contents directly re-tokenizes the entire file on every keystroke. Instead we split the
+//! contents into *chunks*, one per top-level item, and intern the text of each chunk as a
+//! [`SourceChunk`][]. Chunks are tokenized by the tracked [`chunk_tokens`][] query, so after an
+//! edit only the chunks whose text changed are tokenized again; the tokens of the others are
+//! reused even if the edit moved them to a different offset in the file.
+//!
+//! Chunk tokens have spans relative to the start of their chunk. [`tokenize_source_file`][]
+//! shifts them into place and stitches them together, producing exactly the tokens that
+//! tokenizing the whole file at once would have produced. It also records where each chunk's
+//! tokens are, so that the parser can parse the items of each chunk in a query of its own.
+
+use std::ops::Range;
+
+use dada_ir_ast::{
+    diagnostic::Diagnostic,
+    inputs::SourceFile,
+    span::{AbsoluteSpan, Anchor, Offset, Span},
+};
+
+use crate::tokenizer::{Skipped, Token, TokenKind, tokenize, tokenize_with_trailing};
+
+/// The text of one chunk of a source file, see the [module docs](`self`).
+#[salsa::interned(debug)]
+pub(crate) struct SourceChunk<'db> {
+    #[return_ref]
+    pub text: String,
+}
+
+/// The tokens of a [`SourceChunk`][], with spans relative to the start of the chunk.
+#[derive(Clone, Debug)]
+pub(crate) struct ChunkTokens<'db> {
+    tokens: Vec<Token<'db, 'db>>,
+
+    /// What was skipped after the last token (e.g., the newline at the end of the chunk).
+    /// It belongs to the first token of the next chunk.
+    trailing: Option<Skipped>,
+}
+
+// Tokens do not implement `PartialEq`, so we conservatively report every new value as changed.
+unsafe impl salsa::Update for ChunkTokens<'_> {
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        unsafe { *old_pointer = new_value };
+        true
+    }
+}
+
+#[salsa::tracked(return_ref, no_eq)]
+fn chunk_tokens<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    chunk: SourceChunk<'db>,
+) -> ChunkTokens<'db> {
+    let (tokens, trailing) = tokenize_with_trailing(
+        db,
+        Anchor::SourceFile(source_file),
+        Offset::ZERO,
+        chunk.text(db),
+    );
+    ChunkTokens { tokens, trailing }
+}
+
+/// The tokens of a source file, along with the chunks they came from.
+pub(crate) struct TokenizedSourceFile<'db> {
+    pub tokens: Vec<Token<'db, 'db>>,
+    pub chunks: Vec<PlacedChunk<'db>>,
+}
+
+/// A chunk along with where it appears in its source file.
+#[derive(Clone, Debug)]
+pub(crate) struct PlacedChunk<'db> {
+    pub chunk: SourceChunk<'db>,
+
+    /// Offset of the chunk within the source file
+    pub start: usize,
+
+    /// Indices of the chunk's tokens in [`TokenizedSourceFile::tokens`][]
+    pub tokens: Range<usize>,
+}
+
+/// Tokenizes the contents of `source_file`, reusing the tokens of chunks that did not change.
+pub(crate) fn tokenize_source_file<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+) -> TokenizedSourceFile<'db> {
+    let text = source_file.contents_if_ok(db);
+    let starts = chunk_starts(text);
+    let ends = starts.iter().skip(1).copied().chain(Some(text.len()));
+
+    let mut tokens = vec![];
+    let mut chunks = vec![];
+    let mut pending_skipped = None;
+    for (start, end) in starts.iter().copied().zip(ends) {
+        let chunk = SourceChunk::new(db, text[start..end].to_string());
+        let (chunk_tokens, trailing) = placed_chunk_tokens(db, source_file, chunk, start);
+
+        let first_token = tokens.len();
+        for mut token in chunk_tokens {
+            token.skipped = std::cmp::max(token.skipped, pending_skipped.take());
+            tokens.push(token);
+        }
+        pending_skipped = std::cmp::max(pending_skipped, trailing);
+
+        chunks.push(PlacedChunk {
+            chunk,
+            start,
+            tokens: first_token..tokens.len(),
+        });
+    }
+
+    TokenizedSourceFile { tokens, chunks }
+}
+
+/// Checks the promise of the [module docs](`self`): that the stitched-together tokens of the
+/// chunks of `source_file` are exactly the tokens of the file tokenized as a whole.
+/// Returns a description of the first difference, if any.
+pub fn check_chunked_tokens(db: &dyn crate::Db, source_file: SourceFile) -> Result<(), String> {
+    let whole = tokenize(
+        db,
+        Anchor::SourceFile(source_file),
+        Offset::ZERO,
+        source_file.contents_if_ok(db),
+    );
+    let chunked = tokenize_source_file(db, source_file).tokens;
+
+    // Tokens do not implement `PartialEq`, and their `Debug` output omits the span.
+    let describe = |token: &Token<'_, '_>| {
+        format!(
+            "{token:?} at {}..{}",
+            token.span.start.as_usize(),
+            token.span.end.as_usize()
+        )
+    };
+    for (index, (whole, chunked)) in whole.iter().zip(&chunked).enumerate() {
+        let (whole, chunked) = (describe(whole), describe(chunked));
+        if whole != chunked {
+            return Err(format!(
+                "token {index} is `{whole}` when tokenizing the whole file, but `{chunked}` when tokenizing chunks"
+            ));
+        }
+    }
+    if whole.len() != chunked.len() {
+        return Err(format!(
+            "tokenizing the whole file gives {} tokens, but tokenizing chunks gives {}",
+            whole.len(),
+            chunked.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the tokens of `chunk` placed at offset `start` of `source_file`,
+/// along with what was skipped after the last token.
+/// The first token does not account for anything skipped at the end of the previous chunk.
+pub(crate) fn placed_chunk_tokens<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    chunk: SourceChunk<'db>,
+    start: usize,
+) -> (Vec<Token<'db, 'db>>, Option<Skipped>) {
+    let ChunkTokens { tokens, trailing } = chunk_tokens(db, source_file, chunk);
+    let offset = Offset::from(start);
+    let tokens = tokens
+        .iter()
+        .map(|token| Token {
+            span: shift_span(token.span, offset),
+            skipped: token.skipped,
+            kind: match &token.kind {
+                TokenKind::Error(diagnostic) => {
+                    TokenKind::Error(shift_diagnostic(diagnostic.clone(), offset))
+                }
+                kind => kind.clone(),
+            },
+        })
+        .collect();
+    (tokens, *trailing)
+}
+
+fn shift_span(span: Span<'_>, offset: Offset) -> Span<'_> {
+    Span {
+        anchor: span.anchor,
+        start: span.start + offset,
+        end: span.end + offset,
+    }
+}
+
+fn shift_absolute_span(span: AbsoluteSpan, offset: Offset) -> AbsoluteSpan {
+    AbsoluteSpan {
+        source_file: span.source_file,
+        start: span.start + offset,
+        end: span.end + offset,
+    }
+}
+
+fn shift_diagnostic(mut diagnostic: Diagnostic, offset: Offset) -> Diagnostic {
+    diagnostic.span = shift_absolute_span(diagnostic.span, offset);
+    for label in &mut diagnostic.labels {
+        label.span = shift_absolute_span(label.span, offset);
+    }
+    diagnostic.children = diagnostic
+        .children
+        .into_iter()
+        .map(|child| shift_diagnostic(child, offset))
+        .collect();
+    diagnostic
+}
+
+/// Keywords that can begin a top-level item.
+const ITEM_KEYWORDS: &[&str] = &[
+    "async", "class", "export", "extern", "fn", "pub", "struct", "unsafe", "use",
+];
+
+/// Returns the offsets at which the chunks of `text` start; the first is always `0`.
+///
+/// A chunk starts at each line that begins with one of [`ITEM_KEYWORDS`][]
+/// and which is not within a delimiter, string literal, or comment: that is, where a top-level
+/// item begins. If the item is directly preceded by lines of attributes or doc comments,
+/// the chunk starts with them instead, so that the items of each chunk can be parsed on their own.
+///
+/// Comments, string literals, and character literals are skipped following the same rules as the tokenizer,
+/// both at the top level and within delimiters, so that the tokenizer is always between tokens
+/// at the start of a chunk. Once a delimiter is left unclosed, the tokenizer's error recovery
+/// may look arbitrarily far ahead, so the rest of the text is a single chunk.
+fn chunk_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut close_stack = vec![];
+    let mut chars = text.char_indices().peekable();
+
+    // Start of the attributes and doc comments on the lines just before the current one
+    let mut attributes_start = None;
+
+    while let Some((index, ch)) = chars.next() {
+        if index > 0 && close_stack.is_empty() && text[..index].ends_with('\n') {
+            let line = &text[index..];
+            if line.starts_with("#[") || (line.starts_with("##") && !line.starts_with("###")) {
+                attributes_start.get_or_insert(index);
+            } else {
+                let starts_item = ITEM_KEYWORDS.iter().any(|kw| {
+                    line.strip_prefix(kw).is_some_and(|rest| {
+                        !rest.starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+                    })
+                });
+                if starts_item {
+                    starts.push(attributes_start.unwrap_or(index));
+                }
+                attributes_start = None;
+            }
+        }
+
+        match ch {
+            // Attributes (`#[` or `#![`) are ordinary tokens, other `#` begin a comment.
+            '#' => {
+                let rest = &text[index + 1..];
+                if !(rest.starts_with('[') || rest.starts_with("![")) {
+                    while chars.next_if(|&(_, ch)| ch != '\n').is_some() {}
+                }
+            }
+
+            // Within delimiters, the tokenizer only looks for the closing `"`.
+            '"' if !close_stack.is_empty() => {
+                while let Some((_, ch)) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => {
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            '"' => {
+                let quotes = if text[index..].starts_with("\"\"\"") {
+                    "\"\"\""
+                } else if text[index..].starts_with("\"\"") {
+                    // An empty string
+                    chars.next();
+                    continue;
+                } else {
+                    "\""
+                };
+                for _ in 1..quotes.len() {
+                    chars.next();
+                }
+                while let Some((index, ch)) = chars.next() {
+                    match ch {
+                        '"' if text[index..].starts_with(quotes) => {
+                            for _ in 1..quotes.len() {
+                                chars.next();
+                            }
+                            break;
+                        }
+                        '\\' => {
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            // Character literals (but not loop labels, which also begin with `'`)
+            // may contain delimiters, quotes, or `#`.
+            '\'' => {
+                if let Some(end) = char_literal_end(text, index) {
+                    while chars.next_if(|&(index, _)| index < end).is_some() {}
+                }
+            }
+
+            '{' => close_stack.push('}'),
+            '[' => close_stack.push(']'),
+            '(' => close_stack.push(')'),
+            '}' | ']' | ')' => {
+                // As in the tokenizer, a closing delimiter closes the nearest opener it matches.
+                if let Some(depth) = close_stack.iter().rposition(|&c| c == ch) {
+                    close_stack.truncate(depth);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    starts
+}
+
+/// If a character literal begins with the `'` at offset `start` of `text`,
+/// returns the offset just past it, recognizing it by the same rules as the tokenizer:
+/// an escape sequence, optionally followed by the closing `'`, or a single character
+/// that must be followed by the closing `'`.
+fn char_literal_end(text: &str, start: usize) -> Option<usize> {
+    let content_start = start + '\''.len_utf8();
+    let mut chars = text[content_start..].chars();
+    let content_len = match chars.next()? {
+        '\\' => '\\'.len_utf8() + chars.next().map_or(0, char::len_utf8),
+        '\'' | '\n' => return None,
+        ch if chars.next() == Some('\'') => ch.len_utf8(),
+        _ => return None,
+    };
+    let end = content_start + content_len;
+    if text[end..].starts_with('\'') {
+        Some(end + '\''.len_utf8())
+    } else {
+        Some(end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chunk_starts;
+
+    /// Splits `text` into chunks.
+    fn chunks(text: &str) -> Vec<&str> {
+        let starts = chunk_starts(text);
+        let ends = starts.iter().skip(1).copied().chain(Some(text.len()));
+        starts.iter().zip(ends).map(|(&s, e)| &text[s..e]).collect()
+    }
+
+    #[test]
+    fn one_chunk_per_item() {
+        assert_eq!(
+            chunks("fn a() {\n    x\n}\n\n# comment\nclass B(x: u32)\npub fn c() {}\n"),
+            vec![
+                "fn a() {\n    x\n}\n\n# comment\n",
+                "class B(x: u32)\n",
+                "pub fn c() {}\n"
+            ],
+        );
+    }
+
+    #[test]
+    fn attributes_and_doc_comments_start_the_chunk() {
+        assert_eq!(
+            chunks("fn a() {}\n\n## Docs\n#[test]\nfn b() {}\n"),
+            vec!["fn a() {}\n\n", "## Docs\n#[test]\nfn b() {}\n"],
+        );
+    }
+
+    #[test]
+    fn statements_do_not_start_a_chunk() {
+        assert_eq!(
+            chunks("fn a() {}\nlet x = a()\nprint(x)\nfnord()\n"),
+            vec!["fn a() {}\nlet x = a()\nprint(x)\nfnord()\n"],
+        );
+    }
+
+    #[test]
+    fn no_chunks_within_delimiters() {
+        assert_eq!(
+            chunks("fn a() {\nfn x() {}\n}\nfn b() {}\n"),
+            vec!["fn a() {\nfn x() {}\n}\n", "fn b() {}\n"],
+        );
+    }
+
+    #[test]
+    fn no_chunks_within_strings_or_comments() {
+        assert_eq!(
+            chunks("let x = \"\"\"\nfn a() {}\n\"\"\"\n# {\nfn b() { \"}\" }\nfn c() {}\n"),
+            vec![
+                "let x = \"\"\"\nfn a() {}\n\"\"\"\n# {\n",
+                "fn b() { \"}\" }\n",
+                "fn c() {}\n"
+            ],
+        );
+    }
+
+    #[test]
+    fn no_chunks_within_char_literals() {
+        assert_eq!(
+            chunks(
+                "let c = '{'\nfn a() { '}' }\nfn b() { '\"' }\nfn c() { '#' }\nfn d() { '\\'' }\nfn e() {}\n"
+            ),
+            vec![
+                "let c = '{'\n",
+                "fn a() { '}' }\n",
+                "fn b() { '\"' }\n",
+                "fn c() { '#' }\n",
+                "fn d() { '\\'' }\n",
+                "fn e() {}\n"
+            ],
+        );
+    }
+
+    #[test]
+    fn labels_are_not_char_literals() {
+        assert_eq!(
+            chunks("fn a() { 'outer: loop { break 'outer } }\nfn b() {}\n"),
+            vec!["fn a() { 'outer: loop { break 'outer } }\n", "fn b() {}\n"],
+        );
+    }
+
+    #[test]
+    fn unclosed_delimiter_ends_chunking() {
+        assert_eq!(
+            chunks("fn a() {}\nfn b() {\nfn c() {}\n"),
+            vec!["fn a() {}\n", "fn b() {\nfn c() {}\n"],
+        );
+    }
+}
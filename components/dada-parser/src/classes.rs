@@ -1,8 +1,10 @@
 use dada_ir_ast::{
     ast::{
-        AstAggregate, AstAggregateKind, AstFieldDecl, AstFunction, AstGenericDecl, AstMember,
-        AstTy, AstTyKind, AstVisibility, AstWhereClauses, SpanVec, VariableDecl, VisibilityKind,
+        AstAggregate, AstAggregateKind, AstExpr, AstFieldDecl, AstFunction, AstGenericDecl,
+        AstMember, AstTy, AstTyKind, AstVisibility, AstWhereClauses, SpanVec, VariableDecl,
+        VisibilityKind,
     },
+    diagnostic::{Diagnostic, Level},
     span::{Span, Spanned},
 };
 use salsa::Update;
@@ -11,6 +13,7 @@ use crate::{ParseFail, tokenizer::operator};
 
 use super::{
     Expected, Parse, Parser,
+    attributes::with_attributes,
     miscellaneous::OrOptParse,
     tokenizer::{Delimiter, Keyword},
 };
@@ -35,6 +38,9 @@ impl<'db> Parse<'db> for AstAggregate<'db> {
             aggregate_keyword: _,
         } = AstAggregatePrefix::eat(db, parser)?;
 
+        let doc_comment = parser.take_doc_comment();
+        let attributes = parser.take_attributes();
+
         let id = parser.eat_id()?;
 
         let generics = AstGenericDecl::opt_parse_delimited(
@@ -58,6 +64,8 @@ impl<'db> Parse<'db> for AstAggregate<'db> {
         Ok(Some(AstAggregate::new(
             db,
             start.to(db, parser.last_span()),
+            doc_comment,
+            attributes,
             visibility,
             aggregate_kind,
             id.id,
@@ -123,8 +131,12 @@ impl<'db> crate::prelude::ClassItemMembers<'db> for AstAggregate<'db> {
     #[salsa::tracked(return_ref)]
     fn members(self, db: &'db dyn crate::Db) -> SpanVec<'db, AstMember<'db>> {
         if let Some(contents) = self.contents(db) {
-            Parser::deferred(db, self, contents, |parser| {
-                parser.parse_many_and_report_diagnostics::<AstMember<'db>>(db)
+            Parser::deferred(db, self, contents, |mut parser| {
+                let members = eat_members(db, &mut parser);
+                for diagnostic in parser.into_diagnostics() {
+                    diagnostic.report(db);
+                }
+                members
             })
         } else {
             SpanVec {
@@ -135,6 +147,52 @@ impl<'db> crate::prelude::ClassItemMembers<'db> for AstAggregate<'db> {
     }
 }
 
+/// Keywords that can begin a class member. After an ill-formed member,
+/// we resume parsing at the next of these (or at the next line).
+const MEMBER_KEYWORDS: &[Keyword] = &[Keyword::Pub, Keyword::Export, Keyword::Async, Keyword::Fn];
+
+/// Parses the members of a class body. Unlike [`Parse::eat_many`], this does not stop
+/// at the first ill-formed member: it records the error, skips to the start of the next
+/// member, and keeps going, so that all the errors in the body are reported.
+fn eat_members<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+) -> SpanVec<'db, AstMember<'db>> {
+    let start_span = parser.peek_span();
+    let mut values = vec![];
+    while parser.peek().is_some() {
+        let next_token = parser.next_token;
+        match AstMember::opt_parse(db, parser) {
+            Ok(Some(member)) => {
+                values.push(member);
+                continue;
+            }
+
+            Ok(None) => {
+                let error = ParseFail::Expected(parser.peek_span(), AstMember::expected());
+                parser.push_diagnostic(error.into_diagnostic(db));
+                parser.eat_next_token().unwrap();
+            }
+
+            Err(error) => {
+                parser.push_diagnostic(error.into_diagnostic(db));
+
+                // Make progress even if the error was found before consuming anything.
+                if parser.next_token == next_token {
+                    parser.eat_next_token().unwrap();
+                }
+            }
+        }
+
+        parser.skip_to_line_start_or(MEMBER_KEYWORDS);
+    }
+
+    SpanVec {
+        span: start_span.to(db, parser.last_span()),
+        values,
+    }
+}
+
 impl<'db> Parse<'db> for AstMember<'db> {
     type Output = Self;
 
@@ -148,7 +206,9 @@ impl<'db> Parse<'db> for AstMember<'db> {
         // that keyword is not present, allowing easier detection of which
         // form is correct. In principle we could modify `AstFieldDecl`'s parser
         // to fail more gracefully, but it's easier to just reorder things here.
-        AstFunction::opt_parse(db, parser).or_opt_parse::<Self, AstFieldDecl<'db>>(db, parser)
+        with_attributes(db, parser, |parser| {
+            AstFunction::opt_parse(db, parser).or_opt_parse::<Self, AstFieldDecl<'db>>(db, parser)
+        })
     }
 
     fn expected() -> Expected {
@@ -177,6 +237,19 @@ impl<'db> Parse<'db> for AstFieldDecl<'db> {
             Err(e) => return Err(e),
         };
 
+        if let Some(default) = variable.default(db) {
+            tokens.push_diagnostic(
+                Diagnostic::error(db, default.span, "fields cannot have default values").label(
+                    db,
+                    Level::Error,
+                    default.span,
+                    "default values are only supported on function parameters",
+                ),
+            );
+        }
+
+        let doc_comment = tokens.take_doc_comment();
+
         Ok(Some(AstFieldDecl::new(
             db,
             visibility
@@ -184,6 +257,7 @@ impl<'db> Parse<'db> for AstFieldDecl<'db> {
                 .map(|v| v.span)
                 .unwrap_or_else(|| variable.span(db))
                 .to(db, variable.span(db)),
+            doc_comment,
             visibility,
             variable,
         )))
@@ -248,10 +322,14 @@ impl<'db> Parse<'db> for VariableDecl<'db> {
         let (perm, base_ty) = match ty.kind(db) {
             AstTyKind::Perm(ast_perm, ast_ty) => (Some(ast_perm), ast_ty),
             AstTyKind::Named(..) => (None, ty),
-            AstTyKind::GenericDecl(..) => (None, ty),
+            AstTyKind::GenericDecl(..) | AstTyKind::Never => (None, ty),
         };
 
-        Ok(Some(VariableDecl::new(db, mutable, name, perm, base_ty)))
+        let default = AstExpr::opt_parse_guarded(operator::EQ, db, tokens)?;
+
+        Ok(Some(VariableDecl::new(
+            db, mutable, name, perm, base_ty, default,
+        )))
     }
 
     fn expected() -> Expected {
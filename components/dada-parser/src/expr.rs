@@ -1,8 +1,13 @@
-use dada_ir_ast::ast::{
-    AstBinaryOp, AstBlock, AstConstructorField, AstExpr, AstExprKind, AstPath, AstPathKind,
-    DeferredParse, Identifier, IfArm, Literal, LiteralKind, PermissionOp, SpannedBinaryOp,
-    SpannedIdentifier, SpannedUnaryOp, SquareBracketArgs, UnaryOp,
+use dada_ir_ast::{
+    ast::{
+        AstBinaryOp, AstBlock, AstCallArg, AstConstructorField, AstExpr, AstExprKind, AstIfLet,
+        AstPath, AstPathKind, AstPattern, AstTy, DeferredParse, Identifier, IfArm, Literal,
+        LiteralKind, PermissionOp, SpanVec, SpannedBinaryOp, SpannedIdentifier, SpannedUnaryOp,
+        SquareBracketArgs, UnaryOp,
+    },
+    diagnostic::{Diagnostic, Level},
 };
+use dada_util::ensure_sufficient_stack;
 
 use crate::{
     Parse, Parser,
@@ -29,6 +34,7 @@ impl<'db> Parse<'db> for AstExpr<'db> {
 
 const SELECT_ALL: u32 = u32::MAX;
 const SELECT_STRUCT: u32 = 1;
+const SELECT_TRAILING_BLOCK: u32 = 2;
 
 fn eat_expr_with_precedence<'db>(
     db: &'db dyn crate::Db,
@@ -53,7 +59,7 @@ fn opt_parse_expr_with_precedence<'db>(
     ) -> Result<Option<AstExprKind<'db>>, crate::ParseFail<'db>>,
 ) -> Result<Option<AstExpr<'db>>, crate::ParseFail<'db>> {
     let start_span = parser.peek_span();
-    let Some(kind) = precedence(db, parser)? else {
+    let Some(kind) = ensure_sufficient_stack(|| precedence(db, parser))? else {
         return Ok(None);
     };
     Ok(Some(AstExpr::new(
@@ -62,77 +68,199 @@ fn opt_parse_expr_with_precedence<'db>(
     )))
 }
 
-const BINARY_OP_PRECEDENCE: &[&[(Op, AstBinaryOp)]] = &[
-    &[
-        (operator::PLUS, AstBinaryOp::Add),
-        (operator::MINUS, AstBinaryOp::Sub),
-    ],
-    &[
-        (operator::STAR, AstBinaryOp::Mul),
-        (operator::SLASH, AstBinaryOp::Div),
-    ],
-    &[
-        (operator::GREATERTHANEQ, AstBinaryOp::GreaterEqual),
-        (operator::LESSTHANEQ, AstBinaryOp::LessEqual),
-        (operator::GREATERTHAN, AstBinaryOp::GreaterThan),
-        (operator::LESSTHAN, AstBinaryOp::LessThan),
-        (operator::EQEQ, AstBinaryOp::EqualEqual),
-    ],
-    &[(operator::ANDAND, AstBinaryOp::AndAnd)],
-    &[(operator::PIPEPIPE, AstBinaryOp::OrOr)],
-    &[(operator::EQ, AstBinaryOp::Assign)],
+/// How a sequence of binary operators with the same precedence groups.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Associativity {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+
+    /// `a = b = c` is `a = (b = c)`.
+    Right,
+
+    /// `a < b < c` is an error.
+    None,
+}
+
+/// The binary operators, from lowest precedence to highest.
+/// The precedence of an operator is the index of its entry.
+const BINARY_OPS: &[(Associativity, &[(Op, AstBinaryOp)])] = &[
+    (
+        Associativity::Right,
+        &[
+            (operator::EQ, AstBinaryOp::Assign),
+            (operator::PLUSEQ, AstBinaryOp::AddAssign),
+            (operator::MINUSEQ, AstBinaryOp::SubAssign),
+            (operator::STAREQ, AstBinaryOp::MulAssign),
+            (operator::SLASHEQ, AstBinaryOp::DivAssign),
+        ],
+    ),
+    (
+        Associativity::Left,
+        &[(operator::PIPEPIPE, AstBinaryOp::OrOr)],
+    ),
+    (
+        Associativity::Left,
+        &[(operator::ANDAND, AstBinaryOp::AndAnd)],
+    ),
+    (
+        Associativity::None,
+        &[
+            (operator::GREATERTHANEQ, AstBinaryOp::GreaterEqual),
+            (operator::LESSTHANEQ, AstBinaryOp::LessEqual),
+            (operator::GREATERTHAN, AstBinaryOp::GreaterThan),
+            (operator::LESSTHAN, AstBinaryOp::LessThan),
+            (operator::EQEQ, AstBinaryOp::EqualEqual),
+        ],
+    ),
+    (
+        Associativity::Left,
+        &[
+            (operator::PLUS, AstBinaryOp::Add),
+            (operator::MINUS, AstBinaryOp::Sub),
+        ],
+    ),
+    (
+        Associativity::Left,
+        &[
+            (operator::STAR, AstBinaryOp::Mul),
+            (operator::SLASH, AstBinaryOp::Div),
+        ],
+    ),
 ];
 
+/// If the next token is a binary operator, returns its precedence and its entry in [`BINARY_OPS`].
+fn peek_binary_op(parser: &mut Parser<'_, '_>) -> Option<(usize, Op, AstBinaryOp)> {
+    BINARY_OPS
+        .iter()
+        .enumerate()
+        .find_map(|(precedence, (_, ops))| {
+            ops.iter()
+                .find(|(op_text, _)| parser.peek_op(*op_text))
+                .map(|&(op_text, op)| (precedence, op_text, op))
+        })
+}
+
 fn binary_expr_precedence<'db, const SELECT: u32>(
     db: &'db dyn crate::Db,
     parser: &mut Parser<'_, 'db>,
 ) -> Result<Option<AstExprKind<'db>>, crate::ParseFail<'db>> {
-    binary_expr_with_precedence_level::<SELECT>(db, parser, 0)
+    binary_expr_with_min_precedence::<SELECT>(db, parser, 0)
 }
 
-fn binary_expr_with_precedence_level<'db, const SELECT: u32>(
+/// Parses a sequence of operands separated by binary operators whose precedence
+/// is at least `min_precedence`, grouping them by precedence climbing.
+fn binary_expr_with_min_precedence<'db, const SELECT: u32>(
     db: &'db dyn crate::Db,
     parser: &mut Parser<'_, 'db>,
-    precedence: usize,
+    min_precedence: usize,
 ) -> Result<Option<AstExprKind<'db>>, crate::ParseFail<'db>> {
     let start_span = parser.peek_span();
 
-    if precedence >= BINARY_OP_PRECEDENCE.len() {
-        return postfix_expr_precedence::<SELECT>(db, parser);
-    }
-
-    // Parse the LHS at one higher level of precedence than
-    // the current one.
-    let Some(mut lhs_kind) =
-        binary_expr_with_precedence_level::<SELECT>(db, parser, precedence + 1)?
-    else {
+    let Some(mut lhs_kind) = cast_expr_precedence::<SELECT>(db, parser)? else {
         return Ok(None);
     };
 
-    // Parse as many RHS at the current level of precedence as we can find.
+    // The non-associative operator applied most recently (if any),
+    // and whether we have reported an operator of the same precedence chained onto it.
+    let mut non_associative: Option<(usize, SpannedBinaryOp<'db>)> = None;
+    let mut reported_chain = false;
+
     // Note that the binary operator must appear on the current line;
     // binary operators on the *next line* don't count, those are prefix unary operators (or errors,
     // as the case may be).
-    'outer: loop {
-        let mid_span = parser.peek_span();
-
-        if parser.next_token_on_same_line() {
-            for &(op_text, op) in BINARY_OP_PRECEDENCE[precedence] {
-                if let Ok(op_span) = parser.eat_op(op_text) {
-                    let lhs = AstExpr::new(start_span.to(db, mid_span), lhs_kind);
-                    let rhs = eat_expr_with_precedence(db, parser, |db, parser| {
-                        // Parse RHS at the current level of precedence:
-                        binary_expr_with_precedence_level::<SELECT>(db, parser, precedence)
-                    })?;
-                    lhs_kind =
-                        AstExprKind::BinaryOp(SpannedBinaryOp { span: op_span, op }, lhs, rhs);
-                    continue 'outer;
+    while parser.next_token_on_same_line() {
+        let Some((precedence, op_text, op)) = peek_binary_op(parser) else {
+            break;
+        };
+        if precedence < min_precedence {
+            break;
+        }
+
+        let mid_span = parser.last_span();
+        let op_span = parser.eat_op(op_text)?;
+        let op = SpannedBinaryOp { span: op_span, op };
+
+        // Operators of higher precedence bind more tightly, so they go into the RHS.
+        // Operators of the same precedence go into the RHS only if they are right-associative.
+        let (associativity, _) = BINARY_OPS[precedence];
+        let rhs_precedence = match associativity {
+            Associativity::Left | Associativity::None => precedence + 1,
+            Associativity::Right => precedence,
+        };
+        let rhs = eat_expr_with_precedence(db, parser, |db, parser| {
+            binary_expr_with_min_precedence::<SELECT>(db, parser, rhs_precedence)
+        })?;
+
+        if associativity == Associativity::None {
+            // `a < b < c` would compare the `bool` result of `a < b` with `c`,
+            // which is not what anyone means. Report it and recover by ignoring `< c`.
+            if let Some((first_precedence, first)) = non_associative
+                && first_precedence == precedence
+            {
+                if !reported_chain {
+                    reported_chain = true;
+                    let chain_span = start_span.to(db, parser.last_span());
+                    parser.push_diagnostic(
+                        Diagnostic::error(db, chain_span, "comparison operators cannot be chained")
+                            .label(
+                                db,
+                                Level::Error,
+                                op.span,
+                                format!("`{}` cannot follow another comparison", op.op),
+                            )
+                            .label(
+                                db,
+                                Level::Info,
+                                first.span,
+                                "this comparison produces a `bool`",
+                            )
+                            .label(
+                                db,
+                                Level::Help,
+                                chain_span,
+                                format!(
+                                    "to compare both pairs, write `a {} b && b {} c`",
+                                    first.op, op.op
+                                ),
+                            ),
+                    );
                 }
+                continue;
             }
+            non_associative = Some((precedence, op));
         }
 
-        return Ok(Some(lhs_kind));
+        let lhs = AstExpr::new(start_span.to(db, mid_span), lhs_kind);
+        lhs_kind = AstExprKind::BinaryOp(op, lhs, rhs);
     }
+
+    Ok(Some(lhs_kind))
+}
+
+/// Parses `E as T`, which binds more tightly than any binary operator
+/// (so `a + b as i64` casts only `b`) but less tightly than prefix operators.
+/// Like a binary operator, the `as` must appear on the same line as `E`.
+fn cast_expr_precedence<'db, const SELECT: u32>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+) -> Result<Option<AstExprKind<'db>>, crate::ParseFail<'db>> {
+    let start_span = parser.peek_span();
+
+    let Some(mut kind) = postfix_expr_precedence::<SELECT>(db, parser)? else {
+        return Ok(None);
+    };
+
+    while parser.next_token_on_same_line() {
+        let mid_span = parser.last_span();
+        if parser.eat_keyword(Keyword::As).is_err() {
+            break;
+        }
+        let value = AstExpr::new(start_span.to(db, mid_span), kind);
+        let ty = AstTy::eat(db, parser)?;
+        kind = AstExprKind::Cast(value, ty);
+    }
+
+    Ok(Some(kind))
 }
 
 fn postfix_expr_precedence<'db, const SELECT: u32>(
@@ -148,28 +276,50 @@ fn postfix_expr_precedence<'db, const SELECT: u32>(
     loop {
         let mid_span = parser.last_span();
 
-        // `.` can skip newlines
+        // `.` can skip newlines, but what follows it must be on the same line as the `.`
         if parser.eat_op(operator::DOT).is_ok() {
-            if let Ok(id) = parser.eat_id() {
-                let owner = AstExpr::new(start_span.to(db, mid_span), kind);
-                kind = AstExprKind::DotId(owner, id);
-                continue;
-            }
+            if parser.next_token_on_same_line() {
+                if let Ok(id) = parser.eat_id() {
+                    let owner = AstExpr::new(start_span.to(db, mid_span), kind);
+                    kind = AstExprKind::DotId(owner, id);
+                    continue;
+                }
 
-            if let Ok(await_keyword) = parser.eat_keyword(Keyword::Await) {
-                let future = AstExpr::new(start_span.to(db, mid_span), kind);
-                kind = AstExprKind::Await {
-                    future,
-                    await_keyword,
-                };
-                continue;
-            }
+                if let Ok(await_keyword) = parser.eat_keyword(Keyword::Await) {
+                    let future = AstExpr::new(start_span.to(db, mid_span), kind);
+                    kind = AstExprKind::Await {
+                        future,
+                        await_keyword,
+                    };
+                    continue;
+                }
 
-            if let Some(op) = PermissionOp::opt_parse(db, parser)? {
-                let value = AstExpr::new(start_span.to(db, mid_span), kind);
-                kind = AstExprKind::PermissionOp { value, op };
-                continue;
+                if let Some(op) = PermissionOp::opt_parse(db, parser)? {
+                    let value = AstExpr::new(start_span.to(db, mid_span), kind);
+                    kind = AstExprKind::PermissionOp { value, op };
+                    continue;
+                }
             }
+
+            // 💡 Recover from a dangling `x.` by keeping `x`. This is what the user
+            // has in front of them while typing a member name, so the owner must
+            // survive for IDE completions to know its type.
+            let error =
+                parser.illformed(crate::Expected::Nonterminal("a field, method, or `await`"));
+            parser.push_diagnostic(error.into_diagnostic(db));
+            return Ok(Some(kind));
+        }
+
+        // Postfix `?` is only valid on the same line, like the other postfix operators.
+        if parser.next_token_on_same_line()
+            && let Ok(question_mark) = parser.eat_op_prefix(operator::QUESTION)
+        {
+            let value = AstExpr::new(start_span.to(db, mid_span), kind);
+            kind = AstExprKind::Try {
+                value,
+                question_mark,
+            };
+            continue;
         }
 
         // Postfix `[]` is only valid on the same line, since `[..]` is also valid as the start of an expression
@@ -188,13 +338,25 @@ fn postfix_expr_precedence<'db, const SELECT: u32>(
 
         // Postfix `()` is only valid on the same line, since `[..]` is also valid as the start of an expression
         if parser.next_token_on_same_line()
-            && let Some(args) = AstExpr::opt_parse_delimited(
+            && let Some(mut args) = AstCallArg::opt_parse_delimited(
                 db,
                 parser,
                 crate::tokenizer::Delimiter::Parentheses,
-                AstExpr::eat_comma,
+                AstCallArg::eat_comma,
             )?
         {
+            // A block on the same line as the `)` is a closure passed as the final argument,
+            // as in `list.each() { ... }`.
+            if (SELECT & SELECT_TRAILING_BLOCK != 0)
+                && parser.next_token_on_same_line()
+                && let Some(block) = AstBlock::opt_parse(db, parser)?
+            {
+                args.values.push(AstCallArg {
+                    name: None,
+                    value: AstExpr::new(block.span(db), AstExprKind::Closure(block)),
+                });
+            }
+
             let owner = AstExpr::new(start_span.to(db, mid_span), kind);
             kind = AstExprKind::ParenthesisOp(owner, args);
             continue;
@@ -209,6 +371,8 @@ fn postfix_expr_precedence<'db, const SELECT: u32>(
 /// Base expressions are those that don't involve operators or complex precedence:
 /// - **Literals**: Numbers, strings, booleans (`42`, `"hello"`, `true`)
 /// - **Identifiers**: Variable names and `self`
+/// - **Parentheses and tuples**: `(a + b)`, `(a, b)`, `()`
+/// - **Blocks**: `{ ... }`
 /// - **Control flow**: `if` expressions, `return` statements
 /// - **Constructors**: `Type { field: value }` (when `SELECT_STRUCT` is enabled)
 /// - **Unary operators**: `!expr`, `-expr`
@@ -237,19 +401,45 @@ fn base_expr_precedence<'db, const SELECT: u32>(
         return Ok(Some(if_chain(db, parser, if_span)?));
     }
 
+    // `(e)` is just `e`, but `()` and `(e1, e2, ...)` are tuples
+    if let Some(mut exprs) = AstExpr::opt_parse_delimited(
+        db,
+        parser,
+        crate::tokenizer::Delimiter::Parentheses,
+        AstExpr::eat_comma,
+    )? {
+        if exprs.len() == 1 {
+            let expr = exprs.values.pop().unwrap();
+            return Ok(Some(*expr.kind));
+        }
+        exprs.span = parser.last_span();
+        return Ok(Some(AstExprKind::Tuple(exprs)));
+    }
+
+    // A block `{ ... }`, whose value is its trailing expression
+    if let Some(block) = AstBlock::opt_parse(db, parser)? {
+        return Ok(Some(AstExprKind::Block(block)));
+    }
+
+    // `assert` is a contextual keyword, so this must come before identifiers.
+    if parser.eat_contextual_keyword("assert").is_ok() {
+        let condition = AstExpr::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Assert(condition)));
+    }
+
     if let Ok(id) = parser.eat_id() {
         // Could be `X { field1: value1, .. }`
         if (SELECT & SELECT_STRUCT != 0)
             && parser.next_token_on_same_line()
-            && let Some(fields) = AstConstructorField::opt_parse_delimited(
+            && let Some((base, fields)) = AstConstructorField::opt_parse_delimited(
                 db,
                 parser,
                 crate::tokenizer::Delimiter::CurlyBraces,
-                AstConstructorField::eat_comma,
+                constructor_fields,
             )?
         {
             let path = AstPath::new(db, AstPathKind::Identifier(id));
-            return Ok(Some(AstExprKind::Constructor(path, fields)));
+            return Ok(Some(AstExprKind::Constructor(path, fields, base)));
         }
 
         return Ok(Some(AstExprKind::Id(id)));
@@ -273,6 +463,53 @@ fn base_expr_precedence<'db, const SELECT: u32>(
         return Ok(Some(AstExprKind::Return(None)));
     }
 
+    // Could be `'label: loop { ... }` or `'label: while ... { ... }`
+    if let Ok(label) = parser.eat_label() {
+        parser.eat_op(operator::COLON)?;
+        if let Some(kind) = opt_loop(db, parser, Some(label))? {
+            return Ok(Some(kind));
+        }
+        return Err(parser.illformed(crate::Expected::Nonterminal("`loop` or `while`")));
+    }
+
+    if let Some(kind) = opt_loop(db, parser, None)? {
+        return Ok(Some(kind));
+    }
+
+    if parser.eat_keyword(Keyword::Break).is_ok() {
+        // Like `return`, the label and value must be on the same line
+        let label = if parser.next_token_on_same_line() {
+            parser.eat_label().ok()
+        } else {
+            None
+        };
+        let value = if parser.next_token_on_same_line() {
+            AstExpr::opt_parse(db, parser)?
+        } else {
+            None
+        };
+        return Ok(Some(AstExprKind::Break { label, value }));
+    }
+
+    if parser.eat_keyword(Keyword::Continue).is_ok() {
+        let label = if parser.next_token_on_same_line() {
+            parser.eat_label().ok()
+        } else {
+            None
+        };
+        return Ok(Some(AstExprKind::Continue(label)));
+    }
+
+    if parser.eat_keyword(Keyword::Atomic).is_ok() {
+        let block = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Atomic(block)));
+    }
+
+    if parser.eat_keyword(Keyword::Spawn).is_ok() {
+        let block = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Spawn(block)));
+    }
+
     if let Ok(span) = parser.eat_op(operator::BANG) {
         let expr = eat_expr_with_precedence(db, parser, postfix_expr_precedence::<SELECT>)?;
         return Ok(Some(AstExprKind::UnaryOp(
@@ -298,40 +535,49 @@ fn base_expr_precedence<'db, const SELECT: u32>(
     Ok(None)
 }
 
+/// Parses `loop { ... }` or `while condition { ... }`, which has the label `label` (if any).
+fn opt_loop<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+    label: Option<SpannedIdentifier<'db>>,
+) -> Result<Option<AstExprKind<'db>>, crate::ParseFail<'db>> {
+    if parser.eat_keyword(Keyword::Loop).is_ok() {
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Loop { label, body }));
+    }
+
+    if parser.eat_keyword(Keyword::While).is_ok() {
+        let condition = eat_expr_with_precedence(
+            db,
+            parser,
+            binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT - SELECT_TRAILING_BLOCK }>,
+        )?;
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::While {
+            label,
+            condition,
+            body,
+        }));
+    }
+
+    Ok(None)
+}
+
 fn if_chain<'db>(
     db: &'db dyn crate::Db,
     parser: &mut Parser<'_, 'db>,
     _if_span: dada_ir_ast::span::Span<'db>,
 ) -> Result<AstExprKind<'db>, crate::ParseFail<'db>> {
-    let condition0 = eat_expr_with_precedence(
-        db,
-        parser,
-        binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
-    )?;
-
-    let block0 = AstBlock::eat(db, parser)?;
-
-    let mut arms = vec![IfArm {
-        condition: Some(condition0),
-        result: block0,
-    }];
+    let mut arms = vec![if_arm(db, parser)?];
 
     while parser.eat_keyword(Keyword::Else).is_ok() {
         if let Ok(_if_span) = parser.eat_keyword(Keyword::If) {
-            let else_if_condition = eat_expr_with_precedence(
-                db,
-                parser,
-                binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
-            )?;
-            let else_if_block = AstBlock::eat(db, parser)?;
-            arms.push(IfArm {
-                condition: Some(else_if_condition),
-                result: else_if_block,
-            });
+            arms.push(if_arm(db, parser)?);
         } else {
             let else_block = AstBlock::eat(db, parser)?;
             arms.push(IfArm {
                 condition: None,
+                binding: None,
                 result: else_block,
             });
             break;
@@ -341,6 +587,46 @@ fn if_chain<'db>(
     Ok(AstExprKind::If(arms))
 }
 
+/// Parses the condition and block following `if`:
+/// either `condition { ... }` or `let Some(pattern) = value { ... }`.
+fn if_arm<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+) -> Result<IfArm<'db>, crate::ParseFail<'db>> {
+    let binding = if parser.eat_keyword(Keyword::Let).is_ok() {
+        let some = parser.eat_id()?;
+        let Some(pattern) = AstPattern::opt_parse_delimited(
+            db,
+            parser,
+            crate::tokenizer::Delimiter::Parentheses,
+            AstPattern::eat,
+        )?
+        else {
+            return Err(parser.illformed(crate::Expected::Delimited(
+                crate::tokenizer::Delimiter::Parentheses,
+            )));
+        };
+        parser.eat_op(operator::EQ)?;
+        Some(AstIfLet { some, pattern })
+    } else {
+        None
+    };
+
+    let condition = eat_expr_with_precedence(
+        db,
+        parser,
+        binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT - SELECT_TRAILING_BLOCK }>,
+    )?;
+
+    let result = AstBlock::eat(db, parser)?;
+
+    Ok(IfArm {
+        condition: Some(condition),
+        binding,
+        result,
+    })
+}
+
 impl<'db> Parse<'db> for PermissionOp {
     type Output = Self;
 
@@ -378,7 +664,7 @@ impl<'db> Parse<'db> for Literal<'db> {
     ///
     /// # Supported Literals
     ///
-    /// * **Tokenizer literals**: Integers and strings from `TokenKind::Literal`
+    /// * **Tokenizer literals**: Integers, characters, and strings from `TokenKind::Literal`
     /// * **Boolean keywords**: `true` and `false` keywords are treated as boolean literals
     ///
     /// # String Literal Processing
@@ -431,6 +717,101 @@ impl<'db> Parse<'db> for Literal<'db> {
     }
 }
 
+/// `expr` or `name: expr`
+impl<'db> Parse<'db> for AstCallArg<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        // An identifier followed by `:` is the name of a named argument.
+        let name = if ArgName::can_eat(db, parser) {
+            Some(ArgName::eat(db, parser)?)
+        } else {
+            None
+        };
+
+        let value = match name {
+            Some(_) => AstExpr::eat(db, parser)?,
+            None => match AstExpr::opt_parse(db, parser)? {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(AstCallArg { name, value }))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("argument")
+    }
+}
+
+/// The `name:` prefix of a named argument.
+struct ArgName;
+
+impl<'db> Parse<'db> for ArgName {
+    type Output = SpannedIdentifier<'db>;
+
+    fn opt_parse(
+        _db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        let Ok(name) = parser.eat_id() else {
+            return Ok(None);
+        };
+
+        let _colon = parser.eat_op(operator::COLON)?;
+
+        Ok(Some(name))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("argument name")
+    }
+}
+
+/// Parses the contents of a constructor expression: `..base, field: value, ...`.
+/// The base expression (if any) must come first.
+fn constructor_fields<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+) -> Result<(Option<AstExpr<'db>>, SpanVec<'db, AstConstructorField<'db>>), crate::ParseFail<'db>> {
+    let base = if parser.eat_op(operator::DOTDOT).is_ok() {
+        let base = AstExpr::eat(db, parser)?;
+        if parser.eat_op(operator::COMMA).is_err() {
+            let fields = SpanVec {
+                span: parser.last_span().at_end(),
+                values: vec![],
+            };
+            return Ok((Some(base), fields));
+        }
+        Some(base)
+    } else {
+        None
+    };
+
+    let fields = AstConstructorField::eat_comma(db, parser)?;
+
+    if let Ok(dotdot_span) = parser.eat_op(operator::DOTDOT) {
+        let base_span = match AstExpr::opt_parse(db, parser)? {
+            Some(expr) => dotdot_span.to(db, expr.span),
+            None => dotdot_span,
+        };
+        parser.push_diagnostic(
+            Diagnostic::error(db, base_span, "base expression must come first").label(
+                db,
+                Level::Error,
+                base_span,
+                "move this `..base` before the fields",
+            ),
+        );
+    }
+
+    Ok((base, fields))
+}
+
 impl<'db> Parse<'db> for AstConstructorField<'db> {
     type Output = Self;
 
@@ -1,8 +1,9 @@
 use dada_ir_ast::{
     ast::{
-        AstBlock, AstExpr, AstFunction, AstFunctionEffects, AstFunctionInput, AstGenericDecl,
-        AstLetStatement, AstPerm, AstSelfArg, AstStatement, AstTy, AstVisibility, AstWhereClauses,
-        SpanVec, VariableDecl,
+        AstAggregate, AstBlock, AstExpr, AstExternFunction, AstFieldPattern, AstFunction,
+        AstFunctionEffects, AstFunctionInput, AstGenericDecl, AstLetStatement, AstPath,
+        AstPathKind, AstPattern, AstPerm, AstSelfArg, AstStatement, AstTy, AstVisibility,
+        AstWhereClauses, Literal, LiteralKind, PermissionOp, SpanVec, VariableDecl,
     },
     diagnostic::{Diagnostic, Level},
     span::Span,
@@ -12,7 +13,7 @@ use salsa::Update;
 use crate::{
     Expected, Parse, ParseFail, Parser,
     miscellaneous::OrOptParse,
-    tokenizer::{Delimiter, Keyword, operator},
+    tokenizer::{Delimiter, Keyword, Token, TokenKind, operator},
 };
 
 impl<'db> Parse<'db> for AstFunction<'db> {
@@ -34,53 +35,158 @@ impl<'db> Parse<'db> for AstFunction<'db> {
             fn_keyword: fn_span,
         } = AstFunctionPrefix::eat(db, parser)?;
 
-        let name = parser.eat_id()?;
+        Ok(Some(eat_function_after_prefix(
+            db, parser, start_span, visibility, effects, fn_span,
+        )?))
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("`fn`")
+    }
+}
+
+/// Parses the remainder of a function declaration after the `fn` keyword:
+/// name, generics, arguments, return type, where clauses, and (optional) body.
+fn eat_function_after_prefix<'db>(
+    db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
+    start_span: Span<'db>,
+    visibility: Option<AstVisibility<'db>>,
+    effects: AstFunctionEffects<'db>,
+    fn_span: Span<'db>,
+) -> Result<AstFunction<'db>, ParseFail<'db>> {
+    let doc_comment = parser.take_doc_comment();
+    let attributes = parser.take_attributes();
+
+    let name = parser.eat_id()?;
+
+    let generics = AstGenericDecl::opt_parse_delimited(
+        db,
+        parser,
+        Delimiter::SquareBrackets,
+        AstGenericDecl::eat_comma,
+    )?;
+
+    // Parse the arguments, accepting an empty list.
+    let arguments = AstFunctionInput::eat_delimited(
+        db,
+        parser,
+        Delimiter::Parentheses,
+        AstFunctionInput::opt_parse_comma,
+    )?;
+    let arguments = match arguments {
+        Some(arguments) => arguments,
+        None => SpanVec {
+            span: parser.last_span(),
+            values: vec![],
+        },
+    };
+
+    let return_ty = AstTy::opt_parse_guarded(operator::ARROW, db, parser)?;
+
+    let where_clauses = AstWhereClauses::opt_parse(db, parser)?;
+
+    let body = parser.defer_delimited(Delimiter::CurlyBraces).ok();
+
+    Ok(AstFunction::new(
+        db,
+        start_span.to(db, parser.last_span()),
+        doc_comment,
+        attributes,
+        effects,
+        fn_span,
+        visibility,
+        name,
+        generics,
+        arguments,
+        return_ty,
+        where_clauses,
+        body,
+    ))
+}
+
+impl<'db> Parse<'db> for AstExternFunction<'db> {
+    type Output = Self;
 
-        let generics = AstGenericDecl::opt_parse_delimited(
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, super::ParseFail<'db>> {
+        if !AstExternPrefix::can_eat(db, parser) {
+            return Ok(None);
+        }
+
+        let start_span = parser.peek_span();
+
+        let AstExternPrefix {
+            visibility,
+            extern_keyword: extern_span,
+            import_module,
+        } = AstExternPrefix::eat(db, parser)?;
+
+        let function_start_span = parser.peek_span();
+        let effects = AstFunctionEffects::eat(db, parser)?;
+        let fn_span = parser.eat_keyword(Keyword::Fn)?;
+        let function = eat_function_after_prefix(
             db,
             parser,
-            Delimiter::SquareBrackets,
-            AstGenericDecl::eat_comma,
+            function_start_span,
+            visibility,
+            effects,
+            fn_span,
         )?;
 
-        // Parse the arguments, accepting an empty list.
-        let arguments = AstFunctionInput::eat_delimited(
+        Ok(Some(AstExternFunction::new(
             db,
-            parser,
-            Delimiter::Parentheses,
-            AstFunctionInput::opt_parse_comma,
-        )?;
-        let arguments = match arguments {
-            Some(arguments) => arguments,
-            None => SpanVec {
-                span: parser.last_span(),
-                values: vec![],
-            },
-        };
+            start_span.to(db, parser.last_span()),
+            extern_span,
+            import_module,
+            function,
+        )))
+    }
 
-        let return_ty = AstTy::opt_parse_guarded(operator::ARROW, db, parser)?;
+    fn expected() -> Expected {
+        Expected::Nonterminal("`extern fn`")
+    }
+}
 
-        let where_clauses = AstWhereClauses::opt_parse(db, parser)?;
+/// The *prefix* of an extern function declaration:
+/// an optional visibility, the `extern` keyword, and an optional string literal
+/// naming the module to import from. Like [`AstFunctionPrefix`], intended to be probed with `can_eat`.
+#[derive(Update)]
+struct AstExternPrefix<'db> {
+    visibility: Option<AstVisibility<'db>>,
+    extern_keyword: Span<'db>,
+    import_module: Option<Literal<'db>>,
+}
 
-        let body = parser.defer_delimited(Delimiter::CurlyBraces).ok();
+impl<'db> Parse<'db> for AstExternPrefix<'db> {
+    type Output = Self;
 
-        Ok(Some(AstFunction::new(
-            db,
-            start_span.to(db, parser.last_span()),
-            effects,
-            fn_span,
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        let visibility = AstVisibility::opt_parse(db, parser)?;
+        let extern_keyword = parser.eat_keyword(Keyword::Extern)?;
+
+        let import_module = match parser.peek() {
+            Some(Token {
+                kind: TokenKind::Literal(LiteralKind::String, _),
+                ..
+            }) => Literal::opt_parse(db, parser)?,
+            _ => None,
+        };
+
+        Ok(Some(AstExternPrefix {
             visibility,
-            name,
-            generics,
-            arguments,
-            return_ty,
-            where_clauses,
-            body,
-        )))
+            extern_keyword,
+            import_module,
+        }))
     }
 
     fn expected() -> Expected {
-        Expected::Nonterminal("`fn`")
+        Expected::Nonterminal("extern")
     }
 }
 
@@ -128,7 +234,7 @@ impl<'db> Parse<'db> for AstFunctionEffects<'db> {
         loop {
             if let Ok(span) = parser.eat_keyword(Keyword::Async) {
                 if let Some(prev_span) = effects.async_effect {
-                    report_duplicate_keyword(db, "async", span, prev_span);
+                    report_duplicate_keyword(db, parser, "async", span, prev_span);
                 }
                 effects.async_effect = Some(span);
                 continue;
@@ -136,7 +242,7 @@ impl<'db> Parse<'db> for AstFunctionEffects<'db> {
 
             if let Ok(span) = parser.eat_keyword(Keyword::Unsafe) {
                 if let Some(prev_span) = effects.unsafe_effect {
-                    report_duplicate_keyword(db, "unsafe", span, prev_span);
+                    report_duplicate_keyword(db, parser, "unsafe", span, prev_span);
                 }
                 effects.unsafe_effect = Some(span);
                 continue;
@@ -155,11 +261,12 @@ impl<'db> Parse<'db> for AstFunctionEffects<'db> {
 
 fn report_duplicate_keyword<'db>(
     db: &'db dyn crate::Db,
+    parser: &mut Parser<'_, 'db>,
     kw: &str,
     span: Span<'db>,
     prev_span: Span<'db>,
 ) {
-    Diagnostic::error(db, span, format!("duplicate `{kw}` keyword"))
+    let diagnostic = Diagnostic::error(db, span, format!("duplicate `{kw}` keyword"))
         .label(
             db,
             Level::Error,
@@ -171,8 +278,8 @@ fn report_duplicate_keyword<'db>(
             Level::Error,
             prev_span,
             format!("previous `{kw}` keyword"),
-        )
-        .report(db);
+        );
+    parser.push_diagnostic(diagnostic);
 }
 
 impl<'db> Parse<'db> for AstFunctionInput<'db> {
@@ -228,7 +335,7 @@ impl<'db> crate::prelude::FunctionBlock<'db> for AstFunction<'db> {
         let body = self.body(db).as_ref()?;
         Some(Parser::deferred(db, self, body, |parser| {
             let statements = parser.parse_many_and_report_diagnostics::<AstStatement>(db);
-            AstBlock::new(db, statements)
+            AstBlock::new(db, body.span, statements)
         }))
     }
 }
@@ -250,7 +357,8 @@ impl<'db> Parse<'db> for AstBlock<'db> {
             return Ok(None);
         };
 
-        Ok(Some(AstBlock::new(db, statements)))
+        // The block's `{}` is the token that was just consumed.
+        Ok(Some(AstBlock::new(db, parser.last_span(), statements)))
     }
 
     fn expected() -> crate::Expected {
@@ -265,7 +373,10 @@ impl<'db> Parse<'db> for AstStatement<'db> {
         db: &'db dyn crate::Db,
         parser: &mut Parser<'_, 'db>,
     ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
-        AstLetStatement::opt_parse(db, parser).or_opt_parse::<Self, AstExpr>(db, parser)
+        AstLetStatement::opt_parse(db, parser)
+            .or_opt_parse::<Self, AstAggregate>(db, parser)
+            .or_opt_parse::<Self, AstFunction>(db, parser)
+            .or_opt_parse::<Self, AstExpr>(db, parser)
     }
 
     fn expected() -> crate::Expected {
@@ -284,14 +395,22 @@ impl<'db> Parse<'db> for AstLetStatement<'db> {
             return Ok(None);
         };
         let mutable = parser.eat_keyword(Keyword::Mut).ok();
-        let name = parser.eat_id()?;
+        let pattern = AstPattern::eat(db, parser)?;
         let ty = AstTy::opt_parse_guarded(operator::COLON, db, parser)?;
-        let initializer = AstExpr::opt_parse_guarded(operator::EQ, db, parser)?;
+        let initializer = match &pattern {
+            AstPattern::Id(_) => AstExpr::opt_parse_guarded(operator::EQ, db, parser)?,
+
+            // There is nothing to destructure without a value.
+            AstPattern::Tuple(_) | AstPattern::Aggregate(..) => {
+                parser.eat_op(operator::EQ)?;
+                Some(AstExpr::eat(db, parser)?)
+            }
+        };
         Ok(Some(AstLetStatement::new(
             db,
             let_span.to(db, parser.last_span()),
             mutable,
-            name,
+            pattern,
             ty,
             initializer,
         )))
@@ -301,3 +420,78 @@ impl<'db> Parse<'db> for AstLetStatement<'db> {
         crate::Expected::Nonterminal("let statement")
     }
 }
+
+impl<'db> Parse<'db> for AstPattern<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        if let Ok(id) = parser.eat_id() {
+            // Could be `Class { f0, f1, .. }`
+            if parser.next_token_on_same_line()
+                && let Some(fields) = AstFieldPattern::opt_parse_delimited(
+                    db,
+                    parser,
+                    Delimiter::CurlyBraces,
+                    AstFieldPattern::eat_comma,
+                )?
+            {
+                let path = AstPath::new(db, AstPathKind::Identifier(id));
+                return Ok(Some(AstPattern::Aggregate(path, fields)));
+            }
+
+            return Ok(Some(AstPattern::Id(id)));
+        }
+
+        let Some(mut patterns) = AstPattern::opt_parse_delimited(
+            db,
+            parser,
+            Delimiter::Parentheses,
+            AstPattern::eat_comma,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        // Like an expression, `(p)` is just `p`.
+        if patterns.len() == 1 {
+            return Ok(patterns.values.pop());
+        }
+
+        // The `()` is the token that was just consumed.
+        patterns.span = parser.last_span();
+        Ok(Some(AstPattern::Tuple(patterns)))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("pattern")
+    }
+}
+
+impl<'db> Parse<'db> for AstFieldPattern<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        let Ok(name) = parser.eat_id() else {
+            return Ok(None);
+        };
+
+        // Could be `f.ref`, `f.mut`, etc
+        let op = if parser.eat_op(operator::DOT).is_ok() {
+            Some(PermissionOp::eat(db, parser)?)
+        } else {
+            None
+        };
+
+        Ok(Some(AstFieldPattern { name, op }))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("field pattern")
+    }
+}
@@ -1,5 +1,6 @@
 #![doc = include_str!("../docs/overview.md")]
 
+use chunks::PlacedChunk;
 use salsa::Update;
 use tokenizer::{
     Delimiter, Keyword, Skipped, Token, TokenKind, is_op_char,
@@ -8,14 +9,20 @@ use tokenizer::{
 };
 
 use dada_ir_ast::{
-    ast::{AstModule, DeferredParse, SpanVec, SpannedIdentifier},
+    ast::{AstAttribute, AstDocComment, AstModule, DeferredParse, SpanVec, SpannedIdentifier},
     diagnostic::{Diagnostic, Level, Reported},
     inputs::SourceFile,
     span::{Anchor, Offset, Span, Spanned},
 };
 
 use dada_ir_ast::Db;
+use dada_util::ensure_sufficient_stack;
 
+pub use chunks::check_chunked_tokens;
+pub use tokenizer::{LexicalClass, is_identifier, lexical_tokens};
+
+mod attributes;
+mod chunks;
 mod classes;
 mod expr;
 mod functions;
@@ -26,6 +33,7 @@ pub mod prelude;
 mod square_bracket_args;
 mod tokenizer;
 mod types;
+pub mod visit;
 
 #[salsa::tracked]
 impl prelude::SourceFileParse for SourceFile {
@@ -35,9 +43,9 @@ impl prelude::SourceFileParse for SourceFile {
         if let Err(message) = self.contents(db) {
             Diagnostic::new(db, Level::Error, self.span(db), message).report(db);
         }
-        let text = self.contents_if_ok(db);
-        let tokens = tokenizer::tokenize(db, anchor, Offset::ZERO, text);
-        let mut parser = Parser::new(db, anchor, &tokens);
+        let tokenized = chunks::tokenize_source_file(db, self);
+        let mut parser = Parser::new(db, anchor, &tokenized.tokens);
+        parser.chunks = &tokenized.chunks;
         let module = AstModule::eat(db, &mut parser).expect("parsing a module is infallible");
         parser.into_diagnostics().into_iter().for_each(|d| {
             let Reported(_) = d.report(db);
@@ -63,6 +71,21 @@ struct Parser<'token, 'db> {
     /// These need to be reported to the user eventually.
     /// They are stored in the parser to support speculative parsing.
     diagnostics: Vec<Diagnostic>,
+
+    /// Attributes that preceded the item currently being parsed,
+    /// waiting to be claimed by it (see [`attributes::with_attributes`]).
+    attributes: Vec<AstAttribute<'db>>,
+
+    /// Lines of the doc comments between the last consumed token and the next one.
+    doc_lines: Vec<(Span<'db>, &'token str)>,
+
+    /// Doc comment of the item currently being parsed,
+    /// waiting to be claimed by it just like `attributes`.
+    doc_comment: Option<AstDocComment<'db>>,
+
+    /// The chunks that `tokens` came from, if this parser is parsing a whole source file.
+    /// The items of each chunk can be parsed on their own, see [`module_body`].
+    chunks: &'token [PlacedChunk<'db>],
 }
 
 impl<'token, 'db> Parser<'token, 'db> {
@@ -81,6 +104,10 @@ impl<'token, 'db> Parser<'token, 'db> {
                 end: Offset::ZERO,
             },
             diagnostics: Vec::new(),
+            attributes: Vec::new(),
+            doc_lines: Vec::new(),
+            doc_comment: None,
+            chunks: &[],
         };
 
         this.eat_errors();
@@ -190,6 +217,24 @@ impl<'token, 'db> Parser<'token, 'db> {
         result
     }
 
+    /// Claims the attributes preceding the item being parsed.
+    /// Item parsers call this once they are committed to parsing their kind of item.
+    pub fn take_attributes(&mut self) -> Vec<AstAttribute<'db>> {
+        std::mem::take(&mut self.attributes)
+    }
+
+    /// Takes the lines of doc comment that appear right before the next token.
+    /// Consuming a token discards them, so item parsers use [`Self::take_doc_comment`] instead.
+    fn take_doc_lines(&mut self) -> Vec<(Span<'db>, &'token str)> {
+        std::mem::take(&mut self.doc_lines)
+    }
+
+    /// Claims the doc comment preceding the item being parsed.
+    /// Like [`Self::take_attributes`], item parsers call this once they are committed.
+    pub fn take_doc_comment(&mut self) -> Option<AstDocComment<'db>> {
+        self.doc_comment.take()
+    }
+
     /// Record a diagnostic, indicating that parsing recovered from an error.
     pub fn push_diagnostic(&mut self, diagnostic: Diagnostic) {
         self.diagnostics.push(diagnostic);
@@ -226,6 +271,10 @@ impl<'token, 'db> Parser<'token, 'db> {
             next_token: self.next_token,
             last_span: self.last_span,
             diagnostics: Vec::new(),
+            attributes: self.attributes.clone(),
+            doc_lines: self.doc_lines.clone(),
+            doc_comment: self.doc_comment.clone(),
+            chunks: self.chunks,
         }
     }
 
@@ -238,13 +287,16 @@ impl<'token, 'db> Parser<'token, 'db> {
     ///
     /// This pattern allows parsing to continue after tokenizer errors, enabling
     /// better error recovery and multiple error reporting in a single pass.
+    ///
+    /// Doc comment tokens are consumed the same way and stashed in `doc_lines`
+    /// so that the item that follows can claim them (see [`Self::take_doc_lines`]).
     fn eat_errors(&mut self) {
-        while let Some(Token {
-            kind: TokenKind::Error(diagnostic),
-            ..
-        }) = self.tokens.get(self.next_token)
-        {
-            self.push_diagnostic(diagnostic.clone());
+        while let Some(token) = self.tokens.get(self.next_token) {
+            match &token.kind {
+                TokenKind::Error(diagnostic) => self.push_diagnostic(diagnostic.clone()),
+                TokenKind::DocComment(text) => self.doc_lines.push((token.span, *text)),
+                _ => break,
+            }
             self.next_token += 1;
         }
     }
@@ -258,6 +310,7 @@ impl<'token, 'db> Parser<'token, 'db> {
             assert_eq!(span.anchor, self.last_span.anchor);
             self.last_span = span;
             self.next_token += 1;
+            self.doc_lines.clear();
             self.eat_errors();
             Ok(())
         } else {
@@ -274,7 +327,7 @@ impl<'token, 'db> Parser<'token, 'db> {
         assert!(!matches!(
             token,
             Token {
-                kind: TokenKind::Error(_),
+                kind: TokenKind::Error(_) | TokenKind::DocComment(_),
                 ..
             },
         ));
@@ -308,7 +361,8 @@ impl<'token, 'db> Parser<'token, 'db> {
         let mut previous_token = self.next_token;
         while previous_token != 0 {
             previous_token -= 1;
-            if let TokenKind::Error(_) = self.tokens[previous_token].kind {
+            if let TokenKind::Error(_) | TokenKind::DocComment(_) = self.tokens[previous_token].kind
+            {
                 continue;
             }
 
@@ -337,6 +391,38 @@ impl<'token, 'db> Parser<'token, 'db> {
         Err(self.illformed(Expected::Keyword(kw)))
     }
 
+    /// Consumes the identifier `text` used as a contextual keyword, i.e., when it is
+    /// followed on the same line, after whitespace, by a token that can begin an expression
+    /// and cannot continue one that begins with an identifier: an identifier, keyword, literal,
+    /// delimited group, `!`, or `-`. So `assert x` uses `assert` as a keyword,
+    /// but `assert(x)`, `assert.x`, and `assert = x` use it as a name.
+    pub fn eat_contextual_keyword(
+        &mut self,
+        text: &'static str,
+    ) -> Result<Span<'db>, ParseFail<'db>> {
+        if let Some(&Token {
+            kind: TokenKind::Identifier(id),
+            span,
+            skipped: _,
+        }) = self.peek()
+            && id.text(self.db) == text
+            && let Some(Token {
+                kind:
+                    TokenKind::Identifier(_)
+                    | TokenKind::Keyword(_)
+                    | TokenKind::Literal(..)
+                    | TokenKind::Delimited { .. }
+                    | TokenKind::OpChar('!' | '-'),
+                skipped: Some(Skipped::Whitespace),
+                ..
+            }) = self.tokens.get(self.next_token + 1)
+        {
+            self.eat_next_token().unwrap();
+            return Ok(span);
+        }
+        Err(self.illformed(Expected::Nonterminal(text)))
+    }
+
     pub fn eat_id(&mut self) -> Result<SpannedIdentifier<'db>, ParseFail<'db>> {
         if let Some(&Token {
             kind: TokenKind::Identifier(id),
@@ -350,7 +436,41 @@ impl<'token, 'db> Parser<'token, 'db> {
         Err(self.illformed(Expected::Identifier))
     }
 
+    pub fn eat_label(&mut self) -> Result<SpannedIdentifier<'db>, ParseFail<'db>> {
+        if let Some(&Token {
+            kind: TokenKind::Label(id),
+            span,
+            skipped: _,
+        }) = self.peek()
+        {
+            self.eat_next_token().unwrap();
+            return Ok(SpannedIdentifier { span, id });
+        }
+        Err(self.illformed(Expected::Label))
+    }
+
+    /// True if the next token is the operator `op`: that is, if the run of adjacent
+    /// operator characters beginning with the next token is exactly `op`
+    /// (so `peek_op(LESSTHAN)` is false before `<=`).
+    pub fn peek_op(&mut self, op: Op) -> bool {
+        self.op_len(op).is_some()
+    }
+
     pub fn eat_op(&mut self, op: Op) -> Result<Span<'db>, ParseFail<'db>> {
+        let Some(len) = self.op_len(op) else {
+            return Err(self.illformed(Expected::Operator(op)));
+        };
+
+        let start_span = self.peek_span();
+        for _ in 0..len {
+            self.eat_next_token().unwrap();
+        }
+        Ok(start_span.to(self.db, self.last_span()))
+    }
+
+    /// If the next token is the operator `op` (see [`Self::peek_op`]),
+    /// returns the number of tokens it spans.
+    fn op_len(&mut self, op: Op) -> Option<usize> {
         const MAX_LEN: usize = 5;
         assert!(op.len() < MAX_LEN, "unexpectedly long operator");
 
@@ -366,11 +486,10 @@ impl<'token, 'db> Parser<'token, 'db> {
         // Check that next character is an operator character.
         let Some(&Token {
             kind: TokenKind::OpChar(ch0),
-            span: start_span,
-            skipped: _,
+            ..
         }) = self.peek()
         else {
-            return Err(self.illformed(Expected::Operator(op)));
+            return None;
         };
 
         // Now look for subsequent operator tokens.
@@ -388,17 +507,32 @@ impl<'token, 'db> Parser<'token, 'db> {
             buffer_len += 1;
         }
 
-        if op.len() != buffer_len {
-            return Err(self.illformed(Expected::Operator(op)));
+        if op[..] != buffer[..buffer_len] {
+            return None;
         }
 
-        for i in 0..buffer_len {
-            if op[i] != buffer[i] {
-                return Err(self.illformed(Expected::Operator(op)));
-            }
+        Some(buffer_len)
+    }
+
+    /// Like [`Self::eat_op`], but `op` may be followed by other operator characters,
+    /// as the postfix `?` is in `x?.y`.
+    pub fn eat_op_prefix(&mut self, op: Op) -> Result<Span<'db>, ParseFail<'db>> {
+        let matches = op.iter().enumerate().all(|(i, &ch)| {
+            matches!(
+                self.tokens.get(self.next_token + i),
+                Some(Token {
+                    kind: TokenKind::OpChar(ch1),
+                    skipped,
+                    ..
+                }) if *ch1 == ch && (i == 0 || skipped.is_none())
+            )
+        });
+        if !matches {
+            return Err(self.illformed(Expected::Operator(op)));
         }
 
-        for _ in 0..buffer_len {
+        let start_span = self.peek_span();
+        for _ in 0..op.len() {
             self.eat_next_token().unwrap();
         }
         Ok(start_span.to(self.db, self.last_span()))
@@ -419,6 +553,10 @@ impl<'token, 'db> Parser<'token, 'db> {
 
     /// Eats the next token if it is a delimited token with the given delimiter;
     /// returns a `&str` slice of the token's contents.
+    ///
+    /// This also accepts delimiters that are never closed: the tokenizer has already
+    /// reported an error at the opening delimiter and ended the token where it guessed
+    /// the closing delimiter belongs, so we carry on as though it were there.
     pub fn eat_delimited(&mut self, delimiter: Delimiter) -> Result<&'token str, ParseFail<'db>> {
         if let Some(&Token {
             kind:
@@ -438,6 +576,53 @@ impl<'token, 'db> Parser<'token, 'db> {
         Err(self.illformed(Expected::Delimited(delimiter)))
     }
 
+    /// Error recovery: skips tokens until reaching one that begins a new line
+    /// or is one of `keywords`, so that parsing can resume at what is likely
+    /// the start of the next construct.
+    fn skip_to_line_start_or(&mut self, keywords: &[Keyword]) {
+        while let Some(token) = self.peek() {
+            let line_start = matches!(token.skipped, Some(skipped) if skipped >= Skipped::Newline);
+            let keyword = matches!(token.kind, TokenKind::Keyword(kw) if keywords.contains(&kw));
+            if line_start || keyword {
+                break;
+            }
+            self.eat_next_token().unwrap();
+        }
+    }
+
+    /// Returns the chunk whose tokens come next, if none of them have been consumed yet
+    /// (other than doc comments and errors, which are eaten eagerly) and no attributes or
+    /// doc comment are waiting to be claimed.
+    fn at_chunk_start(&self) -> Option<PlacedChunk<'db>> {
+        if !self.attributes.is_empty() || self.doc_comment.is_some() {
+            return None;
+        }
+
+        let index = self
+            .chunks
+            .partition_point(|chunk| chunk.tokens.end <= self.next_token);
+        let chunk = self.chunks.get(index)?;
+        self.tokens[chunk.tokens.start..self.next_token]
+            .iter()
+            .all(|token| matches!(token.kind, TokenKind::Error(_) | TokenKind::DocComment(_)))
+            .then_some(chunk.clone())
+    }
+
+    /// Skips over the tokens of `chunk`, whose items were parsed separately,
+    /// just as though they had been consumed.
+    fn skip_chunk(&mut self, chunk: &PlacedChunk<'db>) {
+        if let Some(token) = self.tokens[chunk.tokens.clone()]
+            .iter()
+            .rev()
+            .find(|token| !matches!(token.kind, TokenKind::Error(_) | TokenKind::DocComment(_)))
+        {
+            self.last_span = token.span;
+        }
+        self.next_token = chunk.tokens.end;
+        self.doc_lines.clear();
+        self.eat_errors();
+    }
+
     /// Returns true if the next token is on the same line
     /// as the most recently consumed token.
     /// Some parts of our grammar are newline sensitive.
@@ -589,7 +774,8 @@ trait Parse<'db>: Sized {
         let input_offset = text_span.start + 1; // account for the opening delimiter
         let tokenized = tokenize(db, text_span.anchor, input_offset, text);
         let mut parser1 = Parser::new(db, text_span.anchor, &tokenized);
-        let opt_list_err = eat_method(db, &mut parser1);
+        // Each level of nested delimiters recurses here, so guard against deep nesting.
+        let opt_list_err = ensure_sufficient_stack(|| eat_method(db, &mut parser1));
         parser.take_diagnostics(parser1);
         Ok(Some(opt_list_err?))
     }
@@ -682,6 +868,7 @@ pub enum Expected {
     EOF,
     MoreTokens,
     Identifier,
+    Label,
     Operator(Op),
     Keyword(Keyword),
     Delimited(Delimiter),
@@ -733,6 +920,7 @@ impl ParseFail<'_> {
                 Expected::EOF => unreachable!(), // handled specially
                 Expected::MoreTokens => "more input".to_string(),
                 Expected::Identifier => "an identifier".to_string(),
+                Expected::Label => "a loop label".to_string(),
                 Expected::Operator(op) => format!("`{op}`"),
                 Expected::Keyword(k) => format!("`{k:?}`"),
                 Expected::Delimited(d) => format!("`{}`", d.open_char()),
@@ -1,15 +1,26 @@
 use dada_ir_ast::{
     ast::{
-        AstAggregate, AstFunction, AstItem, AstMainFunction, AstModule, AstPath, AstStatement,
-        AstUse, SpanVec,
+        AstAggregate, AstExternFunction, AstFunction, AstItem, AstMainFunction, AstModule, AstPath,
+        AstStatement, AstUse, AstVisibility, SpanVec,
     },
-    diagnostic::Diagnostic,
-    span::Spanned,
+    diagnostic::{Diagnostic, Level},
+    inputs::SourceFile,
+    span::{Anchor, Spanned},
 };
 
-use crate::tokenizer::operator;
+use salsa::Update;
 
-use super::{Expected, Parse, ParseFail, Parser, miscellaneous::OrOptParse, tokenizer::Keyword};
+use crate::{
+    chunks::{SourceChunk, placed_chunk_tokens},
+    tokenizer::operator,
+};
+
+use super::{
+    Expected, Parse, ParseFail, Parser,
+    attributes::{ModuleAttribute, with_attributes},
+    miscellaneous::OrOptParse,
+    tokenizer::Keyword,
+};
 
 impl<'db> Parse<'db> for AstModule<'db> {
     type Output = Self;
@@ -19,7 +30,17 @@ impl<'db> Parse<'db> for AstModule<'db> {
         parser: &mut Parser<'_, 'db>,
     ) -> Result<Option<Self>, ParseFail<'db>> {
         // Derive the name of the module from the source file in the span.
-        let name = parser.last_span().source_file(db).module_name(db);
+        let source_file = parser.last_span().source_file(db);
+        let name = source_file.module_name(db);
+
+        // Attributes for the module as a whole (`#![...]`) come first.
+        let attributes = match ModuleAttribute::eat_many(db, parser) {
+            Ok(attributes) => attributes.values,
+            Err(e) => {
+                parser.push_diagnostic(e.into_diagnostic(db));
+                vec![]
+            }
+        };
 
         // Parse (item* statement*), skipping unrecognized tokens.
         let mut items: Vec<AstItem<'db>> = vec![];
@@ -27,6 +48,17 @@ impl<'db> Parse<'db> for AstModule<'db> {
         let start_span = parser.peek_span();
         while parser.peek().is_some() {
             if statements.is_empty() {
+                // Where a chunk consists entirely of items, take them from `chunk_items`,
+                // so that editing one item does not re-parse the items in other chunks.
+                if let Some(chunk) = parser.at_chunk_start()
+                    && let Some(chunk_items) =
+                        chunk_items(db, source_file, chunk.chunk, chunk.start)
+                {
+                    items.extend(chunk_items.iter().copied());
+                    parser.skip_chunk(&chunk);
+                    continue;
+                }
+
                 match AstItem::opt_parse(db, parser) {
                     Ok(Some(v)) => {
                         items.push(v);
@@ -82,6 +114,7 @@ impl<'db> Parse<'db> for AstModule<'db> {
         Ok(Some(AstModule::new(
             db,
             name,
+            attributes,
             SpanVec {
                 span: start_span.to(db, parser.last_span()),
                 values: items,
@@ -94,6 +127,38 @@ impl<'db> Parse<'db> for AstModule<'db> {
     }
 }
 
+/// Parses the items in `chunk`, which appears at offset `start` of `source_file`.
+///
+/// Returns `None` unless the chunk is a sequence of well-formed items, as anything else
+/// (statements, parse errors) may depend on the chunks around it;
+/// such chunks are parsed as part of the whole file instead.
+/// Being a query of its own, this is only re-executed for chunks that were edited or moved.
+#[salsa::tracked(return_ref)]
+fn chunk_items<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    chunk: SourceChunk<'db>,
+    start: usize,
+) -> Option<Vec<AstItem<'db>>> {
+    let (tokens, _) = placed_chunk_tokens(db, source_file, chunk, start);
+    let mut parser = Parser::new(db, Anchor::SourceFile(source_file), &tokens);
+
+    let mut items = vec![];
+    while parser.peek().is_some() {
+        match AstItem::opt_parse(db, &mut parser) {
+            Ok(Some(item)) => items.push(item),
+            Ok(None) | Err(_) => return None,
+        }
+    }
+
+    // Doc comments after the last item would belong to an item in the next chunk.
+    if !parser.diagnostics.is_empty() || !parser.doc_lines.is_empty() {
+        return None;
+    }
+
+    Some(items)
+}
+
 impl<'db> Parse<'db> for AstItem<'db> {
     type Output = Self;
 
@@ -101,17 +166,48 @@ impl<'db> Parse<'db> for AstItem<'db> {
         db: &'db dyn crate::Db,
         parser: &mut Parser<'_, 'db>,
     ) -> Result<Option<Self>, ParseFail<'db>> {
-        AstAggregate::opt_parse(db, parser)
-            .or_opt_parse::<Self, AstUse<'db>>(db, parser)
-            .or_opt_parse::<Self, AstFunction<'db>>(db, parser)
+        with_attributes(db, parser, |parser| {
+            AstAggregate::opt_parse(db, parser)
+                .or_opt_parse::<Self, AstUse<'db>>(db, parser)
+                .or_opt_parse::<Self, AstExternFunction<'db>>(db, parser)
+                .or_opt_parse::<Self, AstFunction<'db>>(db, parser)
+        })
     }
 
     fn expected() -> Expected {
-        panic!("module-level item (class, function, use)")
+        panic!("module-level item (class, function, extern function, use)")
     }
 }
 
-/// use path [as name];
+/// The *prefix* parses a use declaration up until
+/// the `use` keyword. That is what we need to see
+/// to know that we should be parsing a use.
+/// Parsing always succeeds with `Ok(Some)` or errors;
+/// the intent is that you probe with `can_eat`.
+#[derive(Update)]
+struct AstUsePrefix<'db> {
+    visibility: Option<AstVisibility<'db>>,
+}
+
+impl<'db> Parse<'db> for AstUsePrefix<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        let visibility = AstVisibility::opt_parse(db, parser)?;
+        let _use_keyword = parser.eat_keyword(Keyword::Use)?;
+        Ok(Some(AstUsePrefix { visibility }))
+    }
+
+    fn expected() -> Expected {
+        Expected::Keyword(Keyword::Use)
+    }
+}
+
+/// [pub] use path [as name];
+/// [pub] use path.*;
 impl<'db> Parse<'db> for AstUse<'db> {
     type Output = Self;
 
@@ -119,13 +215,17 @@ impl<'db> Parse<'db> for AstUse<'db> {
         db: &'db dyn crate::Db,
         parser: &mut Parser<'_, 'db>,
     ) -> Result<Option<Self>, ParseFail<'db>> {
-        let Ok(start) = parser.eat_keyword(Keyword::Use) else {
+        if !AstUsePrefix::can_eat(db, parser) {
             return Ok(None);
-        };
+        }
+
+        let start = parser.peek_span();
+        let AstUsePrefix { visibility } = AstUsePrefix::eat(db, parser)?;
 
         let crate_name = parser.eat_id()?;
         let _dot = parser.eat_op(operator::DOT)?;
         let path = AstPath::eat(db, parser)?;
+        let glob = parser.eat_op(operator::DOTSTAR).ok();
 
         let as_id = if parser.eat_keyword(Keyword::As).is_ok() {
             Some(parser.eat_id()?)
@@ -133,11 +233,28 @@ impl<'db> Parse<'db> for AstUse<'db> {
             None
         };
 
+        if let Some(glob) = glob
+            && let Some(as_id) = as_id
+        {
+            parser.push_diagnostic(
+                Diagnostic::error(db, as_id.span, "glob imports cannot be renamed")
+                    .label(db, Level::Error, as_id.span, "I did not expect `as` here")
+                    .label(
+                        db,
+                        Level::Info,
+                        glob,
+                        "because this `use` imports all items from a module",
+                    ),
+            );
+        }
+
         Ok(Some(AstUse::new(
             db,
             start.to(db, parser.last_span()),
+            visibility,
             crate_name,
             path,
+            glob,
             as_id,
         )))
     }
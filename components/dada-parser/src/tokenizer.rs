@@ -1,9 +1,12 @@
-use dada_util::Map;
+use std::ops::Range;
+
+use dada_util::{Map, ensure_sufficient_stack};
 
 use dada_ir_ast::{
     ast::{Identifier, LiteralKind},
     diagnostic::{Diagnostic, Level},
-    span::{Anchor, Offset, Span},
+    inputs::SourceFile,
+    span::{AbsoluteSpan, Anchor, Offset, Span},
 };
 
 /// Interned text content for tokens, allowing processed string literals
@@ -52,6 +55,9 @@ pub enum TokenKind<'input, 'db> {
     /// A keyword
     Keyword(Keyword),
 
+    /// A loop label like `'outer`, containing the identifier after the `'`
+    Label(Identifier<'db>),
+
     /// A delimeted tree like `{}` or `[]` and the text that was in it.
     Delimited {
         delimiter: Delimiter,
@@ -64,6 +70,11 @@ pub enum TokenKind<'input, 'db> {
     /// An integer like `22`
     Literal(LiteralKind, TokenText<'db>),
 
+    /// A doc comment (`## text`), containing the text after the `##`
+    /// (and after one space, if present). The parser collects these
+    /// eagerly and attaches them to the item that follows.
+    DocComment(&'input str),
+
     /// Invalid characters
     Error(Diagnostic),
 }
@@ -98,21 +109,26 @@ keywords! {
     pub enum Keyword {
         As = "as",
         Async = "async",
+        Atomic = "atomic",
         Await = "await",
         Box = "box",
         Boxed = "boxed",
+        Break = "break",
         Class = "class",
+        Continue = "continue",
         Crate = "crate",
         Dyn = "dyn",
         Else = "else",
         Enum = "enum",
         Export = "export",
+        Extern = "extern",
         False = "false",
         Fn = "fn",
         If = "if",
         Is = "is",
         Lent = "lent",
         Let = "let",
+        Loop = "loop",
         Give = "give",
         Given = "given",
         Match = "match",
@@ -130,6 +146,7 @@ keywords! {
         Self_ = "self",
         Share = "share",
         Shared = "shared",
+        Spawn = "spawn",
         Struct = "struct",
         Tracked = "tracked",
         True = "true",
@@ -138,6 +155,7 @@ keywords! {
         Unsafe = "unsafe",
         Use = "use",
         Where = "where",
+        While = "while",
     }
 }
 
@@ -154,6 +172,105 @@ impl Keyword {
     }
 }
 
+/// True if `text` lexes as a single identifier (and so is not a keyword).
+pub fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+        && !Keyword::map().contains_key(text)
+}
+
+/// The lexical category of a token, see [`lexical_tokens`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LexicalClass {
+    Identifier,
+
+    /// A keyword other than those below
+    Keyword,
+
+    /// A keyword naming a permission (e.g., `ref`, `my`) or
+    /// a permission operation (e.g., `give`)
+    PermissionKeyword,
+
+    /// An integer or string literal, or `true`/`false`
+    Literal(LiteralKind),
+
+    /// An op-char like `+`
+    Operator,
+}
+
+impl Keyword {
+    fn lexical_class(self) -> LexicalClass {
+        match self {
+            Keyword::Give
+            | Keyword::Given
+            | Keyword::Lent
+            | Keyword::Mut
+            | Keyword::My
+            | Keyword::Our
+            | Keyword::Owned
+            | Keyword::Ref
+            | Keyword::Share
+            | Keyword::Shared
+            | Keyword::Tracked
+            | Keyword::Unique => LexicalClass::PermissionKeyword,
+            Keyword::True | Keyword::False => LexicalClass::Literal(LiteralKind::Boolean),
+            _ => LexicalClass::Keyword,
+        }
+    }
+}
+
+/// Tokenizes all of `source_file`, including the text within delimiters,
+/// and returns the span and lexical class of each token in source order.
+/// Comments, delimiters, and unrecognized characters are omitted.
+///
+/// 💡 The parser only tokenizes the text within delimiters when it parses it,
+/// which it does lazily; this is meant for tools (e.g., syntax highlighting)
+/// that want every token regardless of how the file parses.
+pub fn lexical_tokens(
+    db: &dyn crate::Db,
+    source_file: SourceFile,
+) -> Vec<(AbsoluteSpan, LexicalClass)> {
+    let mut output = vec![];
+    let text = source_file.contents_if_ok(db);
+    lexical_tokens_in(
+        db,
+        Anchor::SourceFile(source_file),
+        Offset::ZERO,
+        text,
+        &mut output,
+    );
+    output
+}
+
+fn lexical_tokens_in<'db>(
+    db: &'db dyn crate::Db,
+    anchor: Anchor<'db>,
+    input_offset: Offset,
+    input: &str,
+    output: &mut Vec<(AbsoluteSpan, LexicalClass)>,
+) {
+    for token in tokenize(db, anchor, input_offset, input) {
+        let class = match token.kind {
+            TokenKind::Identifier(_) | TokenKind::Label(_) => LexicalClass::Identifier,
+            TokenKind::Keyword(kw) => kw.lexical_class(),
+            TokenKind::Literal(kind, _) => LexicalClass::Literal(kind),
+            TokenKind::OpChar(_) => LexicalClass::Operator,
+            TokenKind::Delimited { delimiter: _, text } => {
+                // account for the opening delimiter
+                ensure_sufficient_stack(|| {
+                    lexical_tokens_in(db, anchor, token.span.start + 1, text, output)
+                });
+                continue;
+            }
+            TokenKind::DocComment(_) | TokenKind::Error(_) => continue,
+        };
+        output.push((token.span.absolute_span(db), class));
+    }
+}
+
 pub mod operator {
     /// A recognized operator, can be derefd to the characters
     #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -180,6 +297,10 @@ pub mod operator {
     pub const MINUS: Op = Op(&['-']);
     pub const STAR: Op = Op(&['*']);
     pub const SLASH: Op = Op(&['/']);
+    pub const PLUSEQ: Op = Op(&['+', '=']);
+    pub const MINUSEQ: Op = Op(&['-', '=']);
+    pub const STAREQ: Op = Op(&['*', '=']);
+    pub const SLASHEQ: Op = Op(&['/', '=']);
     #[expect(dead_code)]
     pub const AND: Op = Op(&['&']);
     pub const ANDAND: Op = Op(&['&', '&']);
@@ -194,9 +315,14 @@ pub mod operator {
     pub const EQEQ: Op = Op(&['=', '=']);
     pub const ARROW: Op = Op(&['-', '>']);
     pub const DOT: Op = Op(&['.']);
+    pub const DOTDOT: Op = Op(&['.', '.']);
+    pub const DOTSTAR: Op = Op(&['.', '*']);
     pub const COLON: Op = Op(&[':']);
     pub const BANG: Op = Op(&['!']);
     pub const COMMA: Op = Op(&[',']);
+    pub const HASH: Op = Op(&['#']);
+    pub const HASHBANG: Op = Op(&['#', '!']);
+    pub const QUESTION: Op = Op(&['?']);
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -238,6 +364,18 @@ pub fn tokenize<'input, 'db>(
     input_offset: Offset,
     input: &'input str,
 ) -> Vec<Token<'input, 'db>> {
+    tokenize_with_trailing(db, anchor, input_offset, input).0
+}
+
+/// Like [`tokenize`], but also returns what was skipped after the last token,
+/// which is needed to stitch together the tokens of consecutive chunks of input
+/// (see [`crate::chunks`]).
+pub(crate) fn tokenize_with_trailing<'input, 'db>(
+    db: &'db dyn crate::Db,
+    anchor: Anchor<'db>,
+    input_offset: Offset,
+    input: &'input str,
+) -> (Vec<Token<'input, 'db>>, Option<Skipped>) {
     Tokenizer {
         db,
         anchor,
@@ -265,15 +403,22 @@ struct Tokenizer<'input, 'db> {
 }
 
 impl<'input, 'db> Tokenizer<'input, 'db> {
-    fn tokenize(mut self) -> Vec<Token<'input, 'db>> {
+    fn tokenize(mut self) -> (Vec<Token<'input, 'db>>, Option<Skipped>) {
         while let Some((index, ch)) = self.chars.next() {
             match ch {
+                // Attributes (`#[` or `#![`)
+                '#' if self.at_attribute() => self.ops(index, ch),
+
                 // Comments
                 '#' => self.comment(index),
 
                 // Identifiers and keywords
                 _ if ch.is_alphabetic() || ch == '_' => self.identifier(index, ch),
 
+                // Character literals and loop labels
+                '\'' if self.at_char_literal() => self.char_literal(index),
+                '\'' if self.at_label() => self.label(index),
+
                 // Delimited
                 '{' => self.delimited(index, Delimiter::CurlyBraces, '}'),
                 '[' => self.delimited(index, Delimiter::SquareBrackets, ']'),
@@ -309,9 +454,9 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
             }
         }
 
-        let _skipped = self.clear_accumulated(self.input.len());
+        let skipped = self.clear_accumulated(self.input.len());
 
-        self.tokens
+        (self.tokens, skipped)
     }
 
     fn accumulate_skipped(&mut self, skipped: Skipped) {
@@ -352,15 +497,50 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
         }
     }
 
+    /// True if the `#` just consumed begins an attribute (`#[...]` or `#![...]`)
+    /// rather than a comment.
+    fn at_attribute(&self) -> bool {
+        let mut lookahead = self.chars.clone().map(|(_, ch)| ch);
+        match lookahead.next() {
+            Some('[') => true,
+            Some('!') => lookahead.next() == Some('['),
+            _ => false,
+        }
+    }
+
     fn comment(&mut self, index: usize) {
-        let _skipped = self.clear_accumulated(index);
-        self.accumulate_skipped(Skipped::Comment);
+        let skipped = self.clear_accumulated(index);
+        let doc_comment = self.at_doc_comment();
 
-        for (_index, ch) in &mut self.chars {
+        let mut end = self.input.len();
+        for (index, ch) in &mut self.chars {
             if ch == '\n' {
-                return;
+                end = index;
+                break;
             }
         }
+
+        // Doc comments that begin a line become tokens so that the parser
+        // can attach them to items; other comments are skipped entirely.
+        let line_start = self.tokens.is_empty() || skipped >= Some(Skipped::Newline);
+        if doc_comment && line_start {
+            let text = self.input[index + 2..end].trim_end();
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            self.tokens.push(Token {
+                span: self.span(index, end),
+                skipped,
+                kind: TokenKind::DocComment(text),
+            });
+        }
+
+        self.accumulate_skipped(Skipped::Comment);
+    }
+
+    /// True if the `#` just consumed begins a doc comment:
+    /// `##` not followed by a third `#`.
+    fn at_doc_comment(&self) -> bool {
+        let mut lookahead = self.chars.clone().map(|(_, ch)| ch);
+        lookahead.next() == Some('#') && lookahead.next() != Some('#')
     }
 
     fn identifier(&mut self, start: usize, ch: char) {
@@ -396,6 +576,89 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
         }
     }
 
+    /// True if the `'` just consumed begins a loop label (`'` followed by an identifier).
+    fn at_label(&self) -> bool {
+        let mut lookahead = self.chars.clone().map(|(_, ch)| ch);
+        lookahead
+            .next()
+            .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+    }
+
+    /// True if the `'` just consumed begins a character literal rather than a loop label:
+    /// either an escape sequence or a single character followed by the closing `'`.
+    fn at_char_literal(&self) -> bool {
+        let mut lookahead = self.chars.clone().map(|(_, ch)| ch);
+        match lookahead.next() {
+            Some('\\') => true,
+            Some('\'' | '\n') | None => false,
+            Some(_) => lookahead.next() == Some('\''),
+        }
+    }
+
+    fn char_literal(&mut self, start: usize) {
+        let skipped = self.clear_accumulated(start);
+
+        let mut content = String::new();
+        match self.chars.next() {
+            Some((index, '\\')) => self.escape_sequence(index, &mut content),
+            Some((_, ch)) => content.push(ch),
+            None => unreachable!("`at_char_literal` saw a character"),
+        }
+
+        // An escape sequence may be followed by something other than `'`, like `'\n`.
+        // Recover by treating what we have as the literal.
+        let (end, terminated) = match self.chars.peek() {
+            Some(&(index, '\'')) => {
+                self.chars.next();
+                (index + '\''.len_utf8(), true)
+            }
+            Some(&(index, _)) => (index, false),
+            None => (self.input.len(), false),
+        };
+
+        let span = self.span(start, end);
+        let token_text = TokenText::new(self.db, content);
+        self.tokens.push(Token {
+            span,
+            skipped,
+            kind: TokenKind::Literal(LiteralKind::Char, token_text),
+        });
+        if !terminated {
+            self.tokens.push(Token {
+                span,
+                skipped: None,
+                kind: TokenKind::Error(Diagnostic::error(
+                    self.db,
+                    span,
+                    "missing end quote for character literal",
+                )),
+            });
+        }
+    }
+
+    fn label(&mut self, start: usize) {
+        let skipped = self.clear_accumulated(start);
+
+        let mut end = start + '\''.len_utf8();
+
+        while let Some(&(index, ch)) = self.chars.peek() {
+            if ch.is_alphanumeric() || ch == '_' {
+                end = index + ch.len_utf8();
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+
+        // The span includes the `'` but the identifier does not.
+        let identifier = Identifier::new(self.db, self.input[start + 1..end].to_string());
+        self.tokens.push(Token {
+            span: self.span(start, end),
+            skipped,
+            kind: TokenKind::Label(identifier),
+        });
+    }
+
     fn integer(&mut self, start: usize, ch: char) {
         let skipped = self.clear_accumulated(start);
 
@@ -427,6 +690,7 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
         if let Some((index, escape)) = self.chars.next() {
             match escape {
                 '"' => content.push('"'),
+                '\'' => content.push('\''),
                 '\\' => content.push('\\'),
                 'n' => content.push('\n'),
                 'r' => content.push('\r'),
@@ -492,13 +756,15 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
         // Raw strings (`"\` prefix) skip dedenting — the raw_content starts
         // with `\<newline>`, so we skip the `\` marker and process escape
         // sequences on the rest without dedenting.
+        let mut inconsistent_lines = vec![];
         let final_content = if raw {
             // Skip the `\` marker; content from `\n` onward is preserved as-is
             let after_marker = &raw_content[1..]; // skip `\`
             process_escape_sequences(after_marker)
         } else if raw_content.starts_with('\n') {
             let dedented = dedent_multiline(raw_content);
-            process_escape_sequences(&dedented)
+            inconsistent_lines = dedented.inconsistent_lines;
+            process_escape_sequences(&dedented.text)
         } else {
             content
         };
@@ -509,6 +775,40 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
             skipped,
             kind: TokenKind::Literal(LiteralKind::String, token_text),
         });
+
+        for inconsistent in inconsistent_lines {
+            let indentation = self.span(
+                raw_start + inconsistent.indentation.start,
+                raw_start + inconsistent.indentation.end,
+            );
+            let reference = self.span(
+                raw_start + inconsistent.reference.start,
+                raw_start + inconsistent.reference.end,
+            );
+            self.tokens.push(Token {
+                span: indentation,
+                skipped: None,
+                kind: TokenKind::Error(
+                    Diagnostic::error(
+                        self.db,
+                        indentation,
+                        "inconsistent indentation in multiline string",
+                    )
+                    .label(
+                        self.db,
+                        Level::Error,
+                        indentation,
+                        "this indentation does not begin with the common indentation",
+                    )
+                    .label(
+                        self.db,
+                        Level::Info,
+                        reference,
+                        "common indentation is taken from this line",
+                    ),
+                ),
+            });
+        }
     }
 
     /// Emit tokens for an unterminated string literal: a literal token
@@ -657,48 +957,128 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
 
         while let Some((end, ch)) = self.chars.next() {
             match ch {
+                // Delimiters in comments, string literals, and character literals don't count.
+                '#' if !self.at_attribute() => {
+                    while self.chars.next_if(|&(_, ch)| ch != '\n').is_some() {}
+                }
+                '"' => {
+                    while let Some((_, ch)) = self.chars.next() {
+                        match ch {
+                            '"' => break,
+                            '\\' => {
+                                self.chars.next();
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                '\'' if self.at_char_literal() => {
+                    if let Some((_, '\\')) = self.chars.next() {
+                        self.chars.next();
+                    }
+                    self.chars.next_if(|&(_, ch)| ch == '\'');
+                }
                 '{' => close_stack.push('}'),
                 '[' => close_stack.push(']'),
                 '(' => close_stack.push(')'),
                 '}' | ']' | ')' => {
-                    if ch == *close_stack.last().unwrap() {
-                        close_stack.pop();
-                        if close_stack.is_empty() {
-                            assert!(ch.len_utf8() == 1);
-                            self.tokens.push(Token {
-                                span: self.span(start, end + 1),
-                                skipped,
-                                kind: TokenKind::Delimited {
-                                    delimiter: delim,
-                                    text: &self.input[start + 1..end],
-                                },
-                            });
-                            return;
-                        }
-                    } else {
-                        break;
+                    // A closing delimiter that doesn't match the innermost opener
+                    // closes the nearest enclosing opener that it does match.
+                    // The openers we skip are reported when the contents are tokenized.
+                    let Some(depth) = close_stack.iter().rposition(|&c| c == ch) else {
+                        continue;
+                    };
+                    close_stack.truncate(depth);
+                    if close_stack.is_empty() {
+                        assert!(ch.len_utf8() == 1);
+                        self.tokens.push(Token {
+                            span: self.span(start, end + 1),
+                            skipped,
+                            kind: TokenKind::Delimited {
+                                delimiter: delim,
+                                text: &self.input[start + 1..end],
+                            },
+                        });
+                        return;
                     }
                 }
                 _ => {}
             }
         }
 
-        // Hmm, ideally we'd push a Delimited token here
-        // with what we've seen so far, but the `narrow`
-        // function on `AbsoluteSpan` assumes there is an
-        // end-delimiter in the span, and I don't want to mess that up.
-
-        let end = self.input.len();
-        let span = self.span(start, end);
+        // The delimiter is never closed. Report an error pointing at the opener,
+        // guess where it was meant to be closed, and continue from there as if it were,
+        // so that the rest of the input still gets parsed.
+        let end = self.recovery_point(start);
+        let open_span = self.span(start, start + 1);
+        let mut diagnostic = Diagnostic::error(
+            self.db,
+            open_span,
+            format!("unclosed `{}`", delim.open_char()),
+        )
+        .label(
+            self.db,
+            Level::Error,
+            open_span,
+            format!("this `{}` is never closed", delim.open_char()),
+        );
+        if end < self.input.len() {
+            let line = self.input[end..].lines().next().unwrap_or_default();
+            let text = line.trim_start_matches([' ', '\t']);
+            let text_start = end + (line.len() - text.len());
+            diagnostic = diagnostic.label(
+                self.db,
+                Level::Help,
+                self.span(text_start, text_start + text.trim_end().len()),
+                format!("you may have meant to add a `{close}` before this"),
+            );
+        }
         self.tokens.push(Token {
-            span,
+            span: open_span,
             skipped: None,
-            kind: TokenKind::Error(Diagnostic::error(
-                self.db,
-                span,
-                format!("missing `{close}`"),
-            )),
+            kind: TokenKind::Error(diagnostic),
+        });
+        self.tokens.push(Token {
+            span: self.span(start, end),
+            skipped,
+            kind: TokenKind::Delimited {
+                delimiter: delim,
+                text: &self.input[start + 1..end],
+            },
         });
+
+        self.chars = self.input.char_indices().peekable();
+        while self.chars.next_if(|&(index, _)| index < end).is_some() {}
+        if end < self.input.len() {
+            // The recovery point is always at the start of a line.
+            self.accumulate_skipped(Skipped::Newline);
+        }
+    }
+
+    /// Given the offset of an opening delimiter that is never closed, guesses where its contents end:
+    /// at the start of the first later line that is indented no further than the line containing
+    /// the opener (skipping blank lines, comments, and lines that start with a closing delimiter).
+    /// If there is no such line, the contents extend to the end of the input.
+    fn recovery_point(&self, start: usize) -> usize {
+        let indentation = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+
+        let line_start = self.input[..start].rfind('\n').map_or(0, |i| i + 1);
+        let open_indentation = indentation(&self.input[line_start..]);
+
+        let mut offset = start;
+        while let Some(newline) = self.input[offset..].find('\n') {
+            offset += newline + 1;
+            let line = &self.input[offset..];
+            let text = line.trim_start_matches([' ', '\t']);
+            if text.is_empty() || text.starts_with(['\n', '\r', '#', '}', ']', ')']) {
+                continue;
+            }
+            if indentation(line) <= open_indentation {
+                return offset;
+            }
+        }
+
+        self.input.len()
     }
 
     fn ops(&mut self, start: usize, ch: char) {
@@ -714,7 +1094,8 @@ impl<'input, 'db> Tokenizer<'input, 'db> {
 pub fn is_op_char(ch: char) -> bool {
     matches!(
         ch,
-        '+' | '-'
+        '#' | '+'
+            | '-'
             | '*'
             | '/'
             | '%'
@@ -734,15 +1115,37 @@ pub fn is_op_char(ch: char) -> bool {
 
 type CharIndices<'input> = std::iter::Peekable<std::str::CharIndices<'input>>;
 
+/// The result of [`dedent_multiline`][].
+struct Dedented {
+    text: String,
+
+    /// Lines whose indentation does not begin with the common whitespace prefix,
+    /// e.g., because they indent with tabs where other lines use spaces.
+    inconsistent_lines: Vec<InconsistentLine>,
+}
+
+/// Byte ranges within the raw content given to [`dedent_multiline`][].
+struct InconsistentLine {
+    /// The indentation of the inconsistent line
+    indentation: Range<usize>,
+
+    /// The common indentation on the line it was taken from
+    reference: Range<usize>,
+}
+
 /// Apply multiline string dedenting to raw source content.
 ///
 /// Given raw content that starts with a newline (multiline string detected by caller),
 /// this function:
 /// 1. Strips the leading newline
 /// 2. Strips the trailing line (newline + any whitespace before closing quote)
-/// 3. Computes the common whitespace prefix across all non-empty lines
+/// 3. Computes the common whitespace prefix across all non-blank lines
 /// 4. Removes that prefix from the start of each line
 ///
+/// The width of the prefix is that of the least indented line. Lines whose indentation does
+/// not begin with the same whitespace as that line (e.g., a tab where it has spaces) are
+/// reported as inconsistent; the same width is removed from them anyway.
+///
 /// # Example
 /// ```text
 /// Input (raw):  "\n        hello\n          world\n        "
@@ -750,7 +1153,7 @@ type CharIndices<'input> = std::iter::Peekable<std::str::CharIndices<'input>>;
 /// Common prefix: "        " (8 spaces)
 /// Result:       "hello\n  world"
 /// ```
-fn dedent_multiline(raw: &str) -> String {
+fn dedent_multiline(raw: &str) -> Dedented {
     // Step 1: strip leading newline.
     let content = &raw[1..];
 
@@ -760,32 +1163,68 @@ fn dedent_multiline(raw: &str) -> String {
         None => {
             // No newlines left — single-line content after stripping.
             // No dedenting needed.
-            return content.to_string();
+            return Dedented {
+                text: content.to_string(),
+                inconsistent_lines: vec![],
+            };
         }
     };
 
     // Step 3: compute common whitespace prefix across non-empty lines.
-    let lines: Vec<&str> = content.split('\n').collect();
-    let common_prefix = lines
+    // Each line is paired with its offset in `raw`.
+    let mut lines: Vec<(usize, &str)> = vec![];
+    let mut offset = 1;
+    for line in content.split('\n') {
+        lines.push((offset, line));
+        offset += line.len() + 1;
+    }
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+    let is_blank = |line: &str| line.trim_start().is_empty();
+
+    // Lines of only whitespace do not count, unless there are no others.
+    let all_blank = lines.iter().all(|(_, line)| is_blank(line));
+    let Some(&(reference_offset, reference_line)) = lines
+        .iter()
+        .filter(|(_, line)| !line.is_empty() && (all_blank || !is_blank(line)))
+        .min_by_key(|(_, line)| indentation(line))
+    else {
+        return Dedented {
+            text: content.to_string(),
+            inconsistent_lines: vec![],
+        };
+    };
+    let common_prefix = indentation(reference_line);
+    let reference = &reference_line[..common_prefix];
+
+    // Lines of only whitespace have no text to misalign, so they are never inconsistent.
+    let inconsistent_lines = lines
         .iter()
-        .filter(|line| !line.is_empty())
-        .map(|line| line.len() - line.trim_start().len())
-        .min()
-        .unwrap_or(0);
+        .filter(|(_, line)| !is_blank(line))
+        .filter(|(_, line)| !line.starts_with(reference))
+        .map(|&(offset, line)| InconsistentLine {
+            indentation: offset..offset + indentation(line),
+            reference: reference_offset..reference_offset + common_prefix,
+        })
+        .collect();
 
     // Step 4: remove the common prefix from each line and rejoin.
-    lines
+    let text = lines
         .iter()
-        .map(|line| {
-            if line.len() >= common_prefix {
-                &line[common_prefix..]
-            } else {
-                // Empty or shorter than prefix (shouldn't happen for non-empty lines)
-                line
+        .map(|&(_, line)| {
+            // Blank lines may be shorter than the prefix, and the prefix may end
+            // within a multi-byte whitespace character on inconsistent lines.
+            match line.get(common_prefix..) {
+                Some(rest) => rest,
+                None => line.trim_start(),
             }
         })
         .collect::<Vec<_>>()
-        .join("\n")
+        .join("\n");
+
+    Dedented {
+        text,
+        inconsistent_lines,
+    }
 }
 
 /// Process escape sequences in raw source text, producing the final string content.
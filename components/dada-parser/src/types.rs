@@ -1,16 +1,17 @@
+use dada_util::ensure_sufficient_stack;
 use salsa::Update;
 
 use dada_ir_ast::{
     ast::{
         AstGenericDecl, AstGenericKind, AstGenericTerm, AstPath, AstPerm, AstPermKind, AstTy,
-        AstTyKind, SpanVec,
+        AstTyKind, Identifier, SpanVec,
     },
     span::{Span, Spanned},
 };
 
 use super::{
     Expected, Parse, ParseFail, Parser,
-    tokenizer::{Delimiter, Keyword},
+    tokenizer::{Delimiter, Keyword, operator},
 };
 
 // Parsing types and permissions is annoying.
@@ -28,6 +29,9 @@ enum TyOrPerm<'db> {
 
     /// P1 P2
     Apply(AstPerm<'db>, AstTy<'db>),
+
+    /// `!`
+    Never(Span<'db>),
 }
 
 impl<'db> Parse<'db> for TyOrPerm<'db> {
@@ -56,6 +60,10 @@ impl<'db> Parse<'db> for TyOrPerm<'db> {
             return TyOrPerm::PermKeyword(p).maybe_apply(db, parser);
         }
 
+        if let Ok(span) = parser.eat_op(operator::BANG) {
+            return Ok(Some(TyOrPerm::Never(span)));
+        }
+
         Ok(None)
     }
 
@@ -74,6 +82,7 @@ impl<'db> Spanned<'db> for TyOrPerm<'db> {
             TyOrPerm::Generic(decl) => decl.span(db),
             TyOrPerm::PermKeyword(p) => p.span(db),
             TyOrPerm::Apply(p, ty) => p.span(db).to(db, ty.span(db)),
+            TyOrPerm::Never(span) => *span,
         }
     }
 }
@@ -87,7 +96,7 @@ impl<'db> TyOrPerm<'db> {
     ) -> Result<Option<Self>, ParseFail<'db>> {
         if self.can_be_perm(db)
             && parser.next_token_on_same_line()
-            && let Some(ty) = AstTy::opt_parse(db, parser)?
+            && let Some(ty) = ensure_sufficient_stack(|| AstTy::opt_parse(db, parser))?
         {
             let perm = self.into_perm(db).unwrap();
             return Ok(Some(TyOrPerm::Apply(perm, ty)));
@@ -103,7 +112,7 @@ impl<'db> TyOrPerm<'db> {
             TyOrPerm::Path(_path, Some(_)) => false,
             TyOrPerm::Generic(decl) => matches!(decl.kind(db), AstGenericKind::Perm(_)),
             TyOrPerm::PermKeyword(_) => true,
-            TyOrPerm::Apply(_, _) => false,
+            TyOrPerm::Apply(_, _) | TyOrPerm::Never(_) => false,
         }
     }
 
@@ -123,7 +132,7 @@ impl<'db> TyOrPerm<'db> {
                 _ => None,
             },
             TyOrPerm::PermKeyword(p) => Some(p),
-            TyOrPerm::Apply(_, _) => None,
+            TyOrPerm::Apply(_, _) | TyOrPerm::Never(_) => None,
         }
     }
 
@@ -133,7 +142,7 @@ impl<'db> TyOrPerm<'db> {
             TyOrPerm::Path(..) => true,
             TyOrPerm::Generic(decl) => matches!(decl.kind(db), AstGenericKind::Type(_)),
             TyOrPerm::PermKeyword(_) => false,
-            TyOrPerm::Apply(_, _) => true,
+            TyOrPerm::Apply(_, _) | TyOrPerm::Never(_) => true,
         }
     }
 
@@ -147,6 +156,7 @@ impl<'db> TyOrPerm<'db> {
             },
             TyOrPerm::PermKeyword(_) => None,
             TyOrPerm::Apply(p, t) => Some(AstTy::new(db, span, AstTyKind::Perm(p, t))),
+            TyOrPerm::Never(_) => Some(AstTy::new(db, span, AstTyKind::Never)),
         }
     }
 }
@@ -264,6 +274,13 @@ impl<'db> Parse<'db> for AstGenericTerm<'db> {
         };
 
         match ty_or_perm {
+            // A lone `_` asks for the term to be inferred.
+            TyOrPerm::Path(path, None)
+                if path.len(db) == 1 && path.first_id(db).id == Identifier::underscore(db) =>
+            {
+                Ok(Some(AstGenericTerm::Infer(path.first_id(db).span)))
+            }
+
             // There is one case that could be either a type or a permission.
             TyOrPerm::Path(path, None) if path.len(db) == 1 => {
                 Ok(Some(AstGenericTerm::Id(path.first_id(db))))
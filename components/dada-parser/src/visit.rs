@@ -0,0 +1,353 @@
+//! Visiting and rewriting the syntax tree.
+//!
+//! [`AstVisitor`][] walks the items of a module, the statements of function bodies, and
+//! the expressions within them. Each `visit_*` method defaults to the `walk_*` function
+//! of the same name, which visits the node's children; override a method to act on one
+//! kind of node, calling the `walk_*` function from the override to keep descending.
+//! Function bodies and class members are parsed on demand as the visitor reaches them.
+//!
+//! [`AstFolder`][] rewrites expressions in the same way, with `super_fold_*` functions
+//! that rebuild a node from its folded children.
+
+use dada_ir_ast::ast::{
+    AstAggregate, AstBlock, AstCallArg, AstConstructorField, AstExpr, AstExprKind, AstFunction,
+    AstItem, AstLetStatement, AstMember, AstModule, AstStatement, IfArm, SpanVec,
+};
+use dada_util::ensure_sufficient_stack;
+use salsa::Update;
+
+use crate::prelude::{ClassItemMembers, FunctionBlock};
+
+/// Visits the syntax tree, see the [module docs](`self`).
+pub trait AstVisitor<'db> {
+    fn db(&self) -> &'db dyn crate::Db;
+
+    fn visit_module(&mut self, module: AstModule<'db>) {
+        walk_module(self, module)
+    }
+
+    fn visit_item(&mut self, item: &AstItem<'db>) {
+        walk_item(self, item)
+    }
+
+    fn visit_aggregate(&mut self, aggregate: AstAggregate<'db>) {
+        walk_aggregate(self, aggregate)
+    }
+
+    fn visit_function(&mut self, function: AstFunction<'db>) {
+        walk_function(self, function)
+    }
+
+    fn visit_block(&mut self, block: AstBlock<'db>) {
+        walk_block(self, block)
+    }
+
+    fn visit_statement(&mut self, statement: &AstStatement<'db>) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expr(&mut self, expr: &AstExpr<'db>) {
+        walk_expr(self, expr)
+    }
+}
+
+pub fn walk_module<'db, V: AstVisitor<'db> + ?Sized>(visitor: &mut V, module: AstModule<'db>) {
+    for item in module.items(visitor.db()).iter() {
+        visitor.visit_item(item);
+    }
+}
+
+pub fn walk_item<'db, V: AstVisitor<'db> + ?Sized>(visitor: &mut V, item: &AstItem<'db>) {
+    let db = visitor.db();
+    match item {
+        AstItem::Aggregate(aggregate) => visitor.visit_aggregate(*aggregate),
+        AstItem::Function(function) => visitor.visit_function(*function),
+        AstItem::MainFunction(main_function) => {
+            for statement in main_function.statements(db).iter() {
+                visitor.visit_statement(statement);
+            }
+        }
+        AstItem::SourceFile(_) | AstItem::Use(_) | AstItem::ExternFunction(_) => {}
+    }
+}
+
+pub fn walk_aggregate<'db, V: AstVisitor<'db> + ?Sized>(
+    visitor: &mut V,
+    aggregate: AstAggregate<'db>,
+) {
+    for member in aggregate.members(visitor.db()).iter() {
+        match member {
+            AstMember::Function(function) => visitor.visit_function(*function),
+            AstMember::Field(_) => {}
+        }
+    }
+}
+
+pub fn walk_function<'db, V: AstVisitor<'db> + ?Sized>(
+    visitor: &mut V,
+    function: AstFunction<'db>,
+) {
+    if let Some(block) = function.body_block(visitor.db()) {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_block<'db, V: AstVisitor<'db> + ?Sized>(visitor: &mut V, block: AstBlock<'db>) {
+    for statement in block.statements(visitor.db()).iter() {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<'db, V: AstVisitor<'db> + ?Sized>(
+    visitor: &mut V,
+    statement: &AstStatement<'db>,
+) {
+    let db = visitor.db();
+    match statement {
+        AstStatement::Let(s) => {
+            if let Some(initializer) = s.initializer(db) {
+                visitor.visit_expr(&initializer);
+            }
+        }
+        AstStatement::Expr(expr) => visitor.visit_expr(expr),
+        AstStatement::Aggregate(aggregate) => visitor.visit_aggregate(*aggregate),
+        AstStatement::Function(function) => visitor.visit_function(*function),
+    }
+}
+
+/// Visits the subexpressions of `expr`, in the order they appear in the source.
+pub fn walk_expr<'db, V: AstVisitor<'db> + ?Sized>(visitor: &mut V, expr: &AstExpr<'db>) {
+    ensure_sufficient_stack(|| match &*expr.kind {
+        AstExprKind::Block(block)
+        | AstExprKind::Atomic(block)
+        | AstExprKind::Spawn(block)
+        | AstExprKind::Closure(block) => visitor.visit_block(*block),
+        AstExprKind::Loop { label: _, body } => visitor.visit_block(*body),
+        AstExprKind::While {
+            label: _,
+            condition,
+            body,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(*body);
+        }
+        AstExprKind::DotId(owner, _)
+        | AstExprKind::SquareBracketOp(owner, _)
+        | AstExprKind::Assert(owner)
+        | AstExprKind::Await {
+            future: owner,
+            await_keyword: _,
+        }
+        | AstExprKind::Try {
+            value: owner,
+            question_mark: _,
+        }
+        | AstExprKind::PermissionOp {
+            value: owner,
+            op: _,
+        }
+        | AstExprKind::UnaryOp(_, owner)
+        | AstExprKind::Cast(owner, _) => visitor.visit_expr(owner),
+        AstExprKind::ParenthesisOp(callee, args) => {
+            visitor.visit_expr(callee);
+            for arg in args.iter() {
+                visitor.visit_expr(&arg.value);
+            }
+        }
+        AstExprKind::Tuple(exprs) => {
+            for expr in exprs.iter() {
+                visitor.visit_expr(expr);
+            }
+        }
+        AstExprKind::Constructor(_, fields, base) => {
+            if let Some(base) = base {
+                visitor.visit_expr(base);
+            }
+            for field in fields.iter() {
+                visitor.visit_expr(&field.value);
+            }
+        }
+        AstExprKind::Return(value) | AstExprKind::Break { label: _, value } => {
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        AstExprKind::BinaryOp(_, lhs, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        AstExprKind::If(arms) => {
+            for arm in arms {
+                if let Some(condition) = &arm.condition {
+                    visitor.visit_expr(condition);
+                }
+                visitor.visit_block(arm.result);
+            }
+        }
+        AstExprKind::Literal(_) | AstExprKind::Id(_) | AstExprKind::Continue(_) => {}
+    })
+}
+
+/// Rewrites expressions, see the [module docs](`self`).
+///
+/// Rewriting the contents of a block or `let` statement creates a new tracked struct,
+/// so folds that do so must run within a tracked function.
+/// Nodes whose children are unchanged are returned as they are.
+/// Items declared within blocks are left as they are, since their bodies are not yet parsed.
+pub trait AstFolder<'db> {
+    fn db(&self) -> &'db dyn crate::Db;
+
+    fn fold_block(&mut self, block: AstBlock<'db>) -> AstBlock<'db> {
+        super_fold_block(self, block)
+    }
+
+    fn fold_statement(&mut self, statement: AstStatement<'db>) -> AstStatement<'db> {
+        super_fold_statement(self, statement)
+    }
+
+    fn fold_expr(&mut self, expr: AstExpr<'db>) -> AstExpr<'db> {
+        super_fold_expr(self, expr)
+    }
+}
+
+pub fn super_fold_block<'db, F: AstFolder<'db> + ?Sized>(
+    folder: &mut F,
+    block: AstBlock<'db>,
+) -> AstBlock<'db> {
+    let db = folder.db();
+    let statements = block.statements(db);
+    let folded = fold_span_vec(statements.clone(), |s| folder.fold_statement(s));
+    if folded == *statements {
+        block
+    } else {
+        AstBlock::new(db, block.span(db), folded)
+    }
+}
+
+pub fn super_fold_statement<'db, F: AstFolder<'db> + ?Sized>(
+    folder: &mut F,
+    statement: AstStatement<'db>,
+) -> AstStatement<'db> {
+    let db = folder.db();
+    match statement {
+        AstStatement::Let(s) => {
+            let initializer = s.initializer(db);
+            let folded = initializer.clone().map(|e| folder.fold_expr(e));
+            if folded == initializer {
+                AstStatement::Let(s)
+            } else {
+                AstStatement::Let(AstLetStatement::new(
+                    db,
+                    s.span(db),
+                    s.mutable(db),
+                    s.pattern(db).clone(),
+                    s.ty(db),
+                    folded,
+                ))
+            }
+        }
+        AstStatement::Expr(expr) => AstStatement::Expr(folder.fold_expr(expr)),
+        AstStatement::Aggregate(_) | AstStatement::Function(_) => statement,
+    }
+}
+
+/// Rebuilds `expr` from its folded subexpressions, folding them in the order they appear in the source.
+pub fn super_fold_expr<'db, F: AstFolder<'db> + ?Sized>(
+    folder: &mut F,
+    expr: AstExpr<'db>,
+) -> AstExpr<'db> {
+    let AstExpr { span, kind } = expr;
+    let kind = ensure_sufficient_stack(|| match *kind {
+        AstExprKind::Block(block) => AstExprKind::Block(folder.fold_block(block)),
+        AstExprKind::Atomic(block) => AstExprKind::Atomic(folder.fold_block(block)),
+        AstExprKind::Spawn(block) => AstExprKind::Spawn(folder.fold_block(block)),
+        AstExprKind::Closure(block) => AstExprKind::Closure(folder.fold_block(block)),
+        AstExprKind::Loop { label, body } => AstExprKind::Loop {
+            label,
+            body: folder.fold_block(body),
+        },
+        AstExprKind::While {
+            label,
+            condition,
+            body,
+        } => AstExprKind::While {
+            label,
+            condition: folder.fold_expr(condition),
+            body: folder.fold_block(body),
+        },
+        AstExprKind::DotId(owner, id) => AstExprKind::DotId(folder.fold_expr(owner), id),
+        AstExprKind::SquareBracketOp(owner, args) => {
+            AstExprKind::SquareBracketOp(folder.fold_expr(owner), args)
+        }
+        AstExprKind::ParenthesisOp(callee, args) => AstExprKind::ParenthesisOp(
+            folder.fold_expr(callee),
+            fold_span_vec(args, |arg| AstCallArg {
+                name: arg.name,
+                value: folder.fold_expr(arg.value),
+            }),
+        ),
+        AstExprKind::Tuple(exprs) => {
+            AstExprKind::Tuple(fold_span_vec(exprs, |e| folder.fold_expr(e)))
+        }
+        AstExprKind::Constructor(path, fields, base) => {
+            let base = base.map(|b| folder.fold_expr(b));
+            let fields = fold_span_vec(fields, |field| AstConstructorField {
+                name: field.name,
+                value: folder.fold_expr(field.value),
+            });
+            AstExprKind::Constructor(path, fields, base)
+        }
+        AstExprKind::Return(value) => AstExprKind::Return(value.map(|v| folder.fold_expr(v))),
+        AstExprKind::Assert(value) => AstExprKind::Assert(folder.fold_expr(value)),
+        AstExprKind::Break { label, value } => AstExprKind::Break {
+            label,
+            value: value.map(|v| folder.fold_expr(v)),
+        },
+        AstExprKind::Await {
+            future,
+            await_keyword,
+        } => AstExprKind::Await {
+            future: folder.fold_expr(future),
+            await_keyword,
+        },
+        AstExprKind::Try {
+            value,
+            question_mark,
+        } => AstExprKind::Try {
+            value: folder.fold_expr(value),
+            question_mark,
+        },
+        AstExprKind::PermissionOp { value, op } => AstExprKind::PermissionOp {
+            value: folder.fold_expr(value),
+            op,
+        },
+        AstExprKind::BinaryOp(op, lhs, rhs) => {
+            let lhs = folder.fold_expr(lhs);
+            let rhs = folder.fold_expr(rhs);
+            AstExprKind::BinaryOp(op, lhs, rhs)
+        }
+        AstExprKind::UnaryOp(op, operand) => AstExprKind::UnaryOp(op, folder.fold_expr(operand)),
+        AstExprKind::Cast(value, ty) => AstExprKind::Cast(folder.fold_expr(value), ty),
+        AstExprKind::If(arms) => AstExprKind::If(
+            arms.into_iter()
+                .map(|arm| IfArm {
+                    condition: arm.condition.map(|c| folder.fold_expr(c)),
+                    binding: arm.binding,
+                    result: folder.fold_block(arm.result),
+                })
+                .collect(),
+        ),
+        kind @ (AstExprKind::Literal(_) | AstExprKind::Id(_) | AstExprKind::Continue(_)) => kind,
+    });
+    AstExpr::new(span, kind)
+}
+
+fn fold_span_vec<'db, T: Update>(
+    values: SpanVec<'db, T>,
+    fold: impl FnMut(T) -> T,
+) -> SpanVec<'db, T> {
+    SpanVec {
+        span: values.span,
+        values: values.values.into_iter().map(fold).collect(),
+    }
+}
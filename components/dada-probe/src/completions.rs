@@ -0,0 +1,241 @@
+use std::ops::ControlFlow;
+
+use dada_ir_ast::{
+    inputs::SourceFile,
+    span::{AbsoluteOffset, AbsoluteSpan, SourceSpanned},
+};
+use dada_ir_sym::{
+    check::NameResolutionSym,
+    ir::{
+        classes::SymClassMember,
+        exprs::{SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+        functions::SymFunctionSource,
+        types::{SymGenericKind, SymTy},
+    },
+    prelude::Symbol,
+};
+use dada_util::Set;
+
+use crate::{find_func, visit_exprs};
+
+/// A name that an editor can offer to complete at some position.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompletionKind {
+    /// A local variable or parameter
+    Variable,
+    Function,
+    Class,
+    Struct,
+    Module,
+    Primitive,
+    TypeParameter,
+    PermParameter,
+    Field,
+    Method,
+}
+
+/// Computes the completions for the identifier being typed at `offset`.
+///
+/// After a `.`, these are the fields and methods of the owner's type (as inferred
+/// by the type checker); otherwise they are the names in scope at `offset`.
+/// Only names that start with the partial identifier before `offset` are returned,
+/// sorted by name.
+pub fn completions<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+) -> Vec<Completion> {
+    let text = source_file.contents_if_ok(db);
+    let Some(before) = text.get(..offset.as_usize()) else {
+        return vec![];
+    };
+
+    let prefix_start = before.trim_end_matches(is_identifier_char).len();
+    let prefix = &before[prefix_start..];
+
+    let mut completions = match before[..prefix_start].strip_suffix('.') {
+        // `..` is not a member access
+        Some(owner) if !owner.ends_with('.') => {
+            let owner_end = AbsoluteOffset::from(owner.trim_end().len());
+            member_completions(db, point_span(source_file, owner_end))
+        }
+        _ => scope_completions(db, point_span(source_file, offset)),
+    };
+
+    // Earlier entries shadow later ones with the same name.
+    let mut seen = Set::default();
+    completions.retain(|c| c.label.starts_with(prefix) && seen.insert(c.label.clone()));
+    completions.sort();
+    completions
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+pub(crate) fn point_span(source_file: SourceFile, offset: AbsoluteOffset) -> AbsoluteSpan {
+    AbsoluteSpan {
+        source_file,
+        start: offset,
+        end: offset,
+    }
+}
+
+/// Fields and methods of the type of the expression that ends at `owner`.
+fn member_completions<'db>(db: &'db dyn crate::Db, owner: AbsoluteSpan) -> Vec<Completion> {
+    let Some(ty) = owner_ty(db, owner) else {
+        return vec![];
+    };
+
+    ty.members(db)
+        .iter()
+        .filter_map(|&member| match member {
+            SymClassMember::SymField(field) => Some(Completion {
+                label: field.name(db).to_string(),
+                kind: CompletionKind::Field,
+            }),
+
+            // Constructors are called through the class, not through a value.
+            SymClassMember::SymFunction(method) => match method.source(db) {
+                SymFunctionSource::Constructor(..) => None,
+                _ => Some(Completion {
+                    label: method.name(db).to_string(),
+                    kind: CompletionKind::Method,
+                }),
+            },
+        })
+        .collect()
+}
+
+/// Type of the smallest expression (or place) ending at `owner`.
+///
+/// 💡 The parser recovers from a dangling `x.` by keeping `x`,
+/// so this works while the member name is still being typed.
+pub(crate) fn owner_ty<'db>(db: &'db dyn crate::Db, owner: AbsoluteSpan) -> Option<SymTy<'db>> {
+    let mut best: Option<(usize, SymTy<'db>)> = None;
+    let mut consider = |span: AbsoluteSpan, ty: SymTy<'db>| {
+        if span.end != owner.end || span.start == span.end {
+            return;
+        }
+        let size = span.end.as_usize() - span.start.as_usize();
+        if best.is_none_or(|(best_size, _)| size < best_size) {
+            best = Some((size, ty));
+        }
+    };
+
+    visit_exprs(db, owner, &mut |expr| {
+        consider(expr.source_span(db).absolute_span(db), expr.ty(db));
+        match expr.kind(db) {
+            SymExprKind::PermissionOp(_, place) | SymExprKind::Assign { place, value: _ } => {
+                visit_places(db, *place, &mut consider)
+            }
+            _ => {}
+        }
+        ControlFlow::<()>::Continue(())
+    });
+
+    best.map(|(_, ty)| ty)
+}
+
+fn visit_places<'db>(
+    db: &'db dyn crate::Db,
+    place: SymPlaceExpr<'db>,
+    op: &mut impl FnMut(AbsoluteSpan, SymTy<'db>),
+) {
+    op(place.span(db).absolute_span(db), place.ty(db));
+    match place.kind(db) {
+        SymPlaceExprKind::Field(owner, _) => visit_places(db, *owner, op),
+        SymPlaceExprKind::Var(_) | SymPlaceExprKind::Error(_) => {}
+    }
+}
+
+/// Names in scope at `position`: the local variables declared before it,
+/// the parameters and generics of the enclosing function, and the names
+/// visible in the module (including the prelude and primitive types).
+pub(crate) fn scope_completions<'db>(
+    db: &'db dyn crate::Db,
+    position: AbsoluteSpan,
+) -> Vec<Completion> {
+    let (mut completions, scope) = match find_func(db, position) {
+        Some(function) => (local_completions(db, position), function.scope(db)),
+        None => (vec![], position.source_file.symbol(db).mod_scope(db)),
+    };
+
+    completions.extend(
+        scope
+            .visible_symbols(db)
+            .into_iter()
+            .map(|(id, sym)| Completion {
+                label: id.to_string(),
+                kind: match sym {
+                    NameResolutionSym::SymModule(_) => CompletionKind::Module,
+                    NameResolutionSym::SymAggregate(aggregate) => {
+                        if aggregate.is_struct(db) {
+                            CompletionKind::Struct
+                        } else {
+                            CompletionKind::Class
+                        }
+                    }
+                    NameResolutionSym::SymFunction(_) => CompletionKind::Function,
+                    NameResolutionSym::SymPrimitive(_) => CompletionKind::Primitive,
+                    NameResolutionSym::SymVariable(variable) => match variable.kind(db) {
+                        SymGenericKind::Type => CompletionKind::TypeParameter,
+                        SymGenericKind::Perm => CompletionKind::PermParameter,
+                        SymGenericKind::Place => CompletionKind::Variable,
+                    },
+                },
+            }),
+    );
+
+    completions
+}
+
+/// Local variables in scope at `position`, innermost first.
+/// A `let` is in scope once its initializer is complete.
+fn local_completions<'db>(db: &'db dyn crate::Db, position: AbsoluteSpan) -> Vec<Completion> {
+    let mut locals = vec![];
+    visit_exprs(db, position, &mut |expr| {
+        let (lvs, declared) = match expr.kind(db) {
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body: _,
+            } => (
+                vec![*lv],
+                match initializer {
+                    Some(initializer) => initializer.source_span(db),
+                    None => lv.span(db),
+                },
+            ),
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body: _,
+            } => (
+                lvs.iter().map(|&(lv, _)| lv).collect(),
+                initializer.source_span(db),
+            ),
+            _ => return ControlFlow::Continue(()),
+        };
+        if declared.absolute_span(db).end < position.start {
+            locals.extend(
+                lvs.iter()
+                    .filter_map(|lv| lv.name(db))
+                    .map(|name| Completion {
+                        label: name.to_string(),
+                        kind: CompletionKind::Variable,
+                    }),
+            );
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    locals.reverse();
+    locals
+}
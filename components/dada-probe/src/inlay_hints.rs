@@ -0,0 +1,116 @@
+use dada_ir_ast::{ast::AstFunctionInput, inputs::SourceFile, span::AbsoluteOffset};
+use dada_ir_sym::{
+    ir::{
+        exprs::SymExprKind,
+        functions::{SymFunction, SymFunctionSource},
+        module::SymItem,
+        types::SymTyKind,
+    },
+    prelude::{CheckedBody, CheckedSignature, Symbol},
+};
+
+use crate::walk_exprs;
+
+/// Text for an editor to display inline, as if it were written at `offset`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InlayHint {
+    pub offset: AbsoluteOffset,
+    pub label: String,
+    pub kind: InlayHintKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InlayHintKind {
+    /// The inferred type of a `let` without a type annotation,
+    /// shown after the name, e.g., `: my String`
+    Type,
+
+    /// The default permission of `self` or a parameter declared without one,
+    /// shown before `self` or the parameter's type, e.g., `perm `
+    Permission,
+}
+
+/// Computes the inlay hints for `source_file`, sorted by offset.
+///
+/// The types are those found by the type checker in the checked bodies,
+/// so a `let` whose type could not be inferred gets no hint.
+pub fn inlay_hints<'db>(db: &'db dyn crate::Db, source_file: SourceFile) -> Vec<InlayHint> {
+    let text = source_file.contents_if_ok(db);
+    let mut hints = vec![];
+
+    let mut functions = vec![];
+    for item in source_file.symbol(db).items(db) {
+        match item {
+            SymItem::SymFunction(function) => functions.push(function),
+            SymItem::SymClass(aggregate) => functions.extend(aggregate.methods(db)),
+            SymItem::SymPrimitive(_) => {}
+        }
+    }
+
+    for function in functions {
+        permission_hints(db, function, &mut hints);
+
+        let Some(body) = function.checked_body(db) else {
+            continue;
+        };
+        walk_exprs(db, body, &mut |expr| {
+            let lvs = match expr.kind(db) {
+                &SymExprKind::LetIn { lv, ty, .. } => vec![(lv, ty)],
+                SymExprKind::LetTuple { lvs, .. } => lvs.clone(),
+                _ => return,
+            };
+            for (lv, ty) in lvs {
+                if lv.name(db).is_none() || matches!(ty.kind(db), SymTyKind::Error(_)) {
+                    continue;
+                }
+
+                // Skip `let x: T`, whose type is already written out.
+                let end = lv.span(db).absolute_span(db).end;
+                if !text[end.as_usize()..].trim_start().starts_with(':') {
+                    hints.push(InlayHint {
+                        offset: end,
+                        label: format!(": {ty}"),
+                        kind: InlayHintKind::Type,
+                    });
+                }
+            }
+        });
+    }
+
+    hints.sort_by_key(|hint| hint.offset);
+    hints
+}
+
+/// Hints for the inputs of `function` declared without a permission
+/// whose type in the signature nonetheless has one (e.g., `self` in a class method).
+fn permission_hints<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+    hints: &mut Vec<InlayHint>,
+) {
+    let SymFunctionSource::Function(ast_function) = function.source(db) else {
+        return;
+    };
+    let Ok(signature) = function.checked_signature(db) else {
+        return;
+    };
+    let input_tys = &signature.input_output(db).bound_value.bound_value.input_tys;
+
+    for (input, &ty) in ast_function.inputs(db).values.iter().zip(input_tys) {
+        let (perm, written_at) = match input {
+            AstFunctionInput::SelfArg(arg) => (arg.perm(db), arg.self_span(db)),
+            AstFunctionInput::Variable(decl) => (decl.perm(db), decl.base_ty(db).span),
+        };
+        if perm.is_some() {
+            continue;
+        }
+
+        if let SymTyKind::Perm(perm, _) = ty.kind(db) {
+            hints.push(InlayHint {
+                offset: written_at.absolute_span(db).start,
+                label: format!("{perm} "),
+                kind: InlayHintKind::Permission,
+            });
+        }
+    }
+}
@@ -2,22 +2,41 @@ use std::ops::ControlFlow;
 
 use dada_ir_ast::{
     ast::{
-        AstExpr, AstExprKind, AstItem, AstMember, AstPathKind, AstStatement, LiteralKind,
-        PermissionOp, SpannedIdentifier, UnaryOp,
+        AstExpr, AstExprKind, AstItem, AstMember, AstPathKind, AstPattern, AstStatement, AstTyKind,
+        LiteralKind, PermissionOp, SpannedIdentifier, UnaryOp,
     },
+    inputs::SourceFile,
     span::{AbsoluteSpan, SourceSpanned, Spanned},
 };
 pub use dada_ir_sym::Db;
 use dada_ir_sym::{
     ir::{
-        exprs::{SymExpr, SymExprKind},
+        exprs::{SymExpr, SymExprKind, SymPlaceExprKind},
         functions::SymFunction,
         module::SymItem,
     },
-    prelude::{CheckedBody, Symbol},
+    prelude::{CheckedBody, ElaboratedBody, Symbol},
 };
 use dada_parser::prelude::{ClassItemMembers, FunctionBlock, SourceFileParse};
 
+pub use completions::{Completion, CompletionKind, completions};
+pub use inlay_hints::{InlayHint, InlayHintKind, inlay_hints};
+pub use outline::{OutlineItem, OutlineKind, outline};
+pub use ranges::{folding_ranges, selection_ranges};
+pub use references::{Referent, references, referent_at};
+pub use rename::{TextEdit, rename};
+pub use semantic_tokens::{SemanticToken, SemanticTokenKind, semantic_tokens};
+pub use signature_help::{SignatureHelp, signature_help};
+
+mod completions;
+mod inlay_hints;
+mod outline;
+mod ranges;
+mod references;
+mod rename;
+mod semantic_tokens;
+mod signature_help;
+
 /// Probe for the type of an expression found in a given file at a given span.
 /// Returns the type of the smallest expression that contains the given span.
 pub fn probe_expression_type<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<String> {
@@ -54,22 +73,45 @@ fn find_smallest_containing_expr<'db>(
 /// Probe for the type of a variable found in a given file at a given span.
 pub fn probe_variable_type<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<String> {
     // We expect `span` to be located in
-    visit_exprs(db, span, &mut |expr| {
-        if let SymExprKind::LetIn {
+    visit_exprs(db, span, &mut |expr| match expr.kind(db) {
+        SymExprKind::LetIn {
             lv,
             ty,
             initializer: _,
             body: _,
-        } = expr.kind(db)
-            && lv.span(db).absolute_span(db).contains(span)
+        } if lv.span(db).absolute_span(db).contains(span) => ControlFlow::Break(ty.to_string()),
+        SymExprKind::LetTuple {
+            lvs,
+            initializer: _,
+            body: _,
+        } => match lvs
+            .iter()
+            .find(|(lv, _)| lv.name(db).is_some() && lv.span(db).absolute_span(db).contains(span))
         {
-            ControlFlow::Break(ty.to_string())
-        } else {
-            ControlFlow::Continue(())
-        }
+            Some((_, ty)) => ControlFlow::Break(ty.to_string()),
+            None => ControlFlow::Continue(()),
+        },
+        _ => ControlFlow::Continue(()),
     })
 }
 
+/// Probe for the variables dropped by the function containing the given span,
+/// listed in the order the drops appear in its elaborated body (e.g. `b, a`).
+/// Temporaries (which have no name) are omitted.
+pub fn probe_drops<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<String> {
+    let body = find_func(db, span)?.elaborated_body(db)?;
+    let mut dropped = vec![];
+    walk_exprs(db, body, &mut |expr| {
+        if let SymExprKind::Drop(place) = expr.kind(db)
+            && let SymPlaceExprKind::Var(var) = place.kind(db)
+            && var.name(db).is_some()
+        {
+            dropped.push(var.to_string());
+        }
+    });
+    Some(dropped.join(", "))
+}
+
 /// Probe for the compact AST representation of the expression at a given span.
 ///
 /// # Example
@@ -155,22 +197,35 @@ fn walk_expr_and_visit<'db, B>(
         } => initializer
             .and_then(|initializer| walk_expr_and_visit(db, initializer, span, op))
             .or_else(|| walk_expr_and_visit(db, *body, span, op)),
+        SymExprKind::LetTuple {
+            lvs: _,
+            initializer,
+            body,
+        } => walk_expr_and_visit(db, *initializer, span, op)
+            .or_else(|| walk_expr_and_visit(db, *body, span, op)),
         SymExprKind::Await {
             future,
             await_keyword: _,
         } => walk_expr_and_visit(db, *future, span, op),
         SymExprKind::Assign { place: _, value } => walk_expr_and_visit(db, *value, span, op),
-        SymExprKind::PermissionOp(_, _) => None,
+        SymExprKind::PermissionOp(_, _) | SymExprKind::IsSome(_) | SymExprKind::Drop(_) => None,
         SymExprKind::Call {
             function: _,
             substitution: _,
             arg_temps: _,
         } => None,
         SymExprKind::Return(sym_expr) => walk_expr_and_visit(db, *sym_expr, span, op),
+        SymExprKind::Loop { sym_loop: _, body } => walk_expr_and_visit(db, *body, span, op),
+        SymExprKind::Break {
+            from_loop: _,
+            with_value,
+        } => walk_expr_and_visit(db, *with_value, span, op),
+        SymExprKind::Continue { to_loop: _ } => None,
         SymExprKind::Not {
             operand,
             op_span: _,
-        } => walk_expr_and_visit(db, *operand, span, op),
+        }
+        | SymExprKind::Cast(operand) => walk_expr_and_visit(db, *operand, span, op),
         SymExprKind::BinaryOp(_, lhs, rhs) => walk_expr_and_visit(db, *lhs, span, op)
             .or_else(|| walk_expr_and_visit(db, *rhs, span, op)),
         SymExprKind::Aggregate { ty: _, fields } => {
@@ -193,6 +248,81 @@ fn walk_expr_and_visit<'db, B>(
     }
 }
 
+/// Walk `expr` and all of its subexpressions, outermost first, and invoke `op`.
+fn walk_exprs<'db>(db: &'db dyn crate::Db, expr: SymExpr<'db>, op: &mut dyn FnMut(SymExpr<'db>)) {
+    op(expr);
+
+    match expr.kind(db) {
+        SymExprKind::Semi(e1, e2) | SymExprKind::BinaryOp(_, e1, e2) => {
+            walk_exprs(db, *e1, op);
+            walk_exprs(db, *e2, op);
+        }
+        SymExprKind::Tuple(exprs)
+        | SymExprKind::Aggregate {
+            ty: _,
+            fields: exprs,
+        } => {
+            for &expr in exprs {
+                walk_exprs(db, expr, op);
+            }
+        }
+        SymExprKind::LetIn {
+            lv: _,
+            ty: _,
+            initializer,
+            body,
+        } => {
+            if let Some(initializer) = initializer {
+                walk_exprs(db, *initializer, op);
+            }
+            walk_exprs(db, *body, op);
+        }
+        SymExprKind::LetTuple {
+            lvs: _,
+            initializer,
+            body,
+        } => {
+            walk_exprs(db, *initializer, op);
+            walk_exprs(db, *body, op);
+        }
+        SymExprKind::Await {
+            future: e,
+            await_keyword: _,
+        }
+        | SymExprKind::Assign { place: _, value: e }
+        | SymExprKind::Return(e)
+        | SymExprKind::Loop {
+            sym_loop: _,
+            body: e,
+        }
+        | SymExprKind::Break {
+            from_loop: _,
+            with_value: e,
+        }
+        | SymExprKind::Not {
+            operand: e,
+            op_span: _,
+        }
+        | SymExprKind::Cast(e) => walk_exprs(db, *e, op),
+        SymExprKind::Match { arms } => {
+            for arm in arms {
+                if let Some(condition) = arm.condition {
+                    walk_exprs(db, condition, op);
+                }
+                walk_exprs(db, arm.body, op);
+            }
+        }
+        SymExprKind::Primitive(_)
+        | SymExprKind::ByteLiteral(_)
+        | SymExprKind::PermissionOp(_, _)
+        | SymExprKind::IsSome(_)
+        | SymExprKind::Drop(_)
+        | SymExprKind::Call { .. }
+        | SymExprKind::Continue { .. }
+        | SymExprKind::Error(_) => {}
+    }
+}
+
 // ---- AST probe: expression finder ----
 
 /// Find the smallest AstExpr containing the target span by walking the parsed AST.
@@ -203,22 +333,57 @@ fn find_smallest_containing_ast_expr<'db>(
     db: &'db dyn crate::Db,
     target: AbsoluteSpan,
 ) -> Option<AstExpr<'db>> {
-    let module = target.source_file.parse(db);
     let mut best: Option<AstExpr<'db>> = None;
     let mut best_size = usize::MAX;
 
+    visit_ast_exprs(db, target, &mut |expr| {
+        let expr_abs = expr.span.absolute_span(db);
+        let size = expr_abs.end.as_usize() - expr_abs.start.as_usize();
+        if size < best_size {
+            best = Some(expr.clone());
+            best_size = size;
+        }
+    });
+
+    best
+}
+
+/// Walk all AST expressions containing `target`, outermost first, and invoke `op`.
+fn visit_ast_exprs<'db>(
+    db: &'db dyn crate::Db,
+    target: AbsoluteSpan,
+    op: &mut dyn FnMut(&AstExpr<'db>),
+) {
+    walk_ast_exprs(db, target.source_file, &mut |expr| {
+        if !expr.span.absolute_span(db).contains(target) {
+            return false;
+        }
+        op(expr);
+        true
+    });
+}
+
+/// Walk the AST expressions in the function bodies of `source_file`, outermost first.
+/// `op` returns whether to walk the subexpressions of the expression it is given.
+fn walk_ast_exprs<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    op: &mut dyn FnMut(&AstExpr<'db>) -> bool,
+) {
+    let module = source_file.parse(db);
+
     for item in &module.items(db).values {
         match item {
             AstItem::Function(func) => {
                 if let Some(block) = func.body_block(db) {
                     for stmt in &block.statements(db).values {
-                        walk_ast_statement(db, stmt, target, &mut best, &mut best_size);
+                        walk_ast_statement(db, stmt, op);
                     }
                 }
             }
             AstItem::MainFunction(main_fn) => {
                 for stmt in &main_fn.statements(db).values {
-                    walk_ast_statement(db, stmt, target, &mut best, &mut best_size);
+                    walk_ast_statement(db, stmt, op);
                 }
             }
             AstItem::Aggregate(aggr) => {
@@ -227,33 +392,47 @@ fn find_smallest_containing_ast_expr<'db>(
                         && let Some(block) = func.body_block(db)
                     {
                         for stmt in &block.statements(db).values {
-                            walk_ast_statement(db, stmt, target, &mut best, &mut best_size);
+                            walk_ast_statement(db, stmt, op);
                         }
                     }
                 }
             }
-            AstItem::SourceFile(_) | AstItem::Use(_) => {}
+            AstItem::SourceFile(_) | AstItem::Use(_) | AstItem::ExternFunction(_) => {}
         }
     }
-
-    best
 }
 
 fn walk_ast_statement<'db>(
     db: &'db dyn crate::Db,
     stmt: &AstStatement<'db>,
-    target: AbsoluteSpan,
-    best: &mut Option<AstExpr<'db>>,
-    best_size: &mut usize,
+    op: &mut dyn FnMut(&AstExpr<'db>) -> bool,
 ) {
     match stmt {
         AstStatement::Let(let_stmt) => {
             if let Some(init) = let_stmt.initializer(db) {
-                walk_ast_expr(db, &init, target, best, best_size);
+                walk_ast_expr(db, &init, op);
             }
         }
         AstStatement::Expr(expr) => {
-            walk_ast_expr(db, expr, target, best, best_size);
+            walk_ast_expr(db, expr, op);
+        }
+        AstStatement::Function(func) => {
+            if let Some(block) = func.body_block(db) {
+                for stmt in &block.statements(db).values {
+                    walk_ast_statement(db, stmt, op);
+                }
+            }
+        }
+        AstStatement::Aggregate(aggr) => {
+            for member in &aggr.members(db).values {
+                if let AstMember::Function(func) = member
+                    && let Some(block) = func.body_block(db)
+                {
+                    for stmt in &block.statements(db).values {
+                        walk_ast_statement(db, stmt, op);
+                    }
+                }
+            }
         }
     }
 }
@@ -261,76 +440,95 @@ fn walk_ast_statement<'db>(
 fn walk_ast_expr<'db>(
     db: &'db dyn crate::Db,
     expr: &AstExpr<'db>,
-    target: AbsoluteSpan,
-    best: &mut Option<AstExpr<'db>>,
-    best_size: &mut usize,
+    op: &mut dyn FnMut(&AstExpr<'db>) -> bool,
 ) {
-    let expr_abs = expr.span.absolute_span(db);
-    if !expr_abs.contains(target) {
+    if !op(expr) {
         return;
     }
 
-    let size = expr_abs.end.as_usize() - expr_abs.start.as_usize();
-    if size < *best_size {
-        *best = Some(expr.clone());
-        *best_size = size;
-    }
-
     // Recurse into children
     match &*expr.kind {
         AstExprKind::Literal(_) | AstExprKind::Id(_) => {}
-        AstExprKind::Block(block) => {
+        AstExprKind::Block(block)
+        | AstExprKind::Atomic(block)
+        | AstExprKind::Spawn(block)
+        | AstExprKind::Closure(block) => {
             for stmt in &block.statements(db).values {
-                walk_ast_statement(db, stmt, target, best, best_size);
+                walk_ast_statement(db, stmt, op);
             }
         }
         AstExprKind::DotId(sub_expr, _) => {
-            walk_ast_expr(db, sub_expr, target, best, best_size);
+            walk_ast_expr(db, sub_expr, op);
         }
         AstExprKind::SquareBracketOp(sub_expr, _) => {
-            walk_ast_expr(db, sub_expr, target, best, best_size);
+            walk_ast_expr(db, sub_expr, op);
         }
         AstExprKind::ParenthesisOp(callee, args) => {
-            walk_ast_expr(db, callee, target, best, best_size);
+            walk_ast_expr(db, callee, op);
             for arg in &args.values {
-                walk_ast_expr(db, arg, target, best, best_size);
+                walk_ast_expr(db, &arg.value, op);
             }
         }
         AstExprKind::Tuple(elems) => {
             for elem in &elems.values {
-                walk_ast_expr(db, elem, target, best, best_size);
+                walk_ast_expr(db, elem, op);
             }
         }
-        AstExprKind::Constructor(_, fields) => {
+        AstExprKind::Constructor(_, fields, base) => {
+            if let Some(base) = base {
+                walk_ast_expr(db, base, op);
+            }
             for field in &fields.values {
-                walk_ast_expr(db, &field.value, target, best, best_size);
+                walk_ast_expr(db, &field.value, op);
             }
         }
-        AstExprKind::Return(opt_expr) => {
+        AstExprKind::Return(opt_expr)
+        | AstExprKind::Break {
+            value: opt_expr, ..
+        } => {
             if let Some(sub_expr) = opt_expr {
-                walk_ast_expr(db, sub_expr, target, best, best_size);
+                walk_ast_expr(db, sub_expr, op);
             }
         }
+        AstExprKind::Loop { label: _, body } => {
+            for stmt in &body.statements(db).values {
+                walk_ast_statement(db, stmt, op);
+            }
+        }
+        AstExprKind::While {
+            label: _,
+            condition,
+            body,
+        } => {
+            walk_ast_expr(db, condition, op);
+            for stmt in &body.statements(db).values {
+                walk_ast_statement(db, stmt, op);
+            }
+        }
+        AstExprKind::Continue(_) => {}
+        AstExprKind::Assert(condition) => {
+            walk_ast_expr(db, condition, op);
+        }
         AstExprKind::Await { future, .. } => {
-            walk_ast_expr(db, future, target, best, best_size);
+            walk_ast_expr(db, future, op);
         }
-        AstExprKind::PermissionOp { value, .. } => {
-            walk_ast_expr(db, value, target, best, best_size);
+        AstExprKind::Try { value, .. } | AstExprKind::PermissionOp { value, .. } => {
+            walk_ast_expr(db, value, op);
         }
         AstExprKind::BinaryOp(_, lhs, rhs) => {
-            walk_ast_expr(db, lhs, target, best, best_size);
-            walk_ast_expr(db, rhs, target, best, best_size);
+            walk_ast_expr(db, lhs, op);
+            walk_ast_expr(db, rhs, op);
         }
-        AstExprKind::UnaryOp(_, sub_expr) => {
-            walk_ast_expr(db, sub_expr, target, best, best_size);
+        AstExprKind::UnaryOp(_, sub_expr) | AstExprKind::Cast(sub_expr, _) => {
+            walk_ast_expr(db, sub_expr, op);
         }
         AstExprKind::If(arms) => {
             for arm in arms {
                 if let Some(cond) = &arm.condition {
-                    walk_ast_expr(db, cond, target, best, best_size);
+                    walk_ast_expr(db, cond, op);
                 }
                 for stmt in &arm.result.statements(db).values {
-                    walk_ast_statement(db, stmt, target, best, best_size);
+                    walk_ast_statement(db, stmt, op);
                 }
             }
         }
@@ -356,6 +554,7 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
         AstExprKind::Literal(lit) => {
             let kind = match lit.kind(db) {
                 LiteralKind::Boolean => "Boolean",
+                LiteralKind::Char => "Char",
                 LiteralKind::Integer => "Integer",
                 LiteralKind::String => "String",
             };
@@ -400,7 +599,11 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
                 if i > 0 {
                     buf.push_str(", ");
                 }
-                format_ast_expr(db, arg, buf);
+                if let Some(name) = &arg.name {
+                    format_identifier(db, name, buf);
+                    buf.push_str(": ");
+                }
+                format_ast_expr(db, &arg.value, buf);
             }
             buf.push_str("])");
         }
@@ -414,12 +617,16 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
             }
             buf.push_str("])");
         }
-        AstExprKind::Constructor(path, fields) => {
+        AstExprKind::Constructor(path, fields, base) => {
             buf.push_str("Constructor(");
             format_ast_path(db, path, buf);
             buf.push_str(", [");
+            if let Some(base) = base {
+                buf.push_str("..");
+                format_ast_expr(db, base, buf);
+            }
             for (i, field) in fields.values.iter().enumerate() {
-                if i > 0 {
+                if i > 0 || base.is_some() {
                     buf.push_str(", ");
                 }
                 format_identifier(db, &field.name, buf);
@@ -436,11 +643,113 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
                 buf.push(')');
             }
         }
+        AstExprKind::Assert(condition) => {
+            buf.push_str("Assert(");
+            format_ast_expr(db, condition, buf);
+            buf.push(')');
+        }
+        AstExprKind::Atomic(block) => {
+            buf.push_str("Atomic([");
+            for (i, stmt) in block.statements(db).values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_statement(db, stmt, buf);
+            }
+            buf.push_str("])");
+        }
+        AstExprKind::Spawn(block) => {
+            buf.push_str("Spawn([");
+            for (i, stmt) in block.statements(db).values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_statement(db, stmt, buf);
+            }
+            buf.push_str("])");
+        }
+        AstExprKind::Closure(block) => {
+            buf.push_str("Closure([");
+            for (i, stmt) in block.statements(db).values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_statement(db, stmt, buf);
+            }
+            buf.push_str("])");
+        }
+        AstExprKind::Loop { label, body } => {
+            buf.push_str("Loop(");
+            if let Some(label) = label {
+                buf.push('\'');
+                format_identifier(db, label, buf);
+                buf.push_str(", ");
+            }
+            buf.push('[');
+            for (i, stmt) in body.statements(db).values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_statement(db, stmt, buf);
+            }
+            buf.push_str("])");
+        }
+        AstExprKind::While {
+            label,
+            condition,
+            body,
+        } => {
+            buf.push_str("While(");
+            if let Some(label) = label {
+                buf.push('\'');
+                format_identifier(db, label, buf);
+                buf.push_str(", ");
+            }
+            format_ast_expr(db, condition, buf);
+            buf.push_str(", [");
+            for (i, stmt) in body.statements(db).values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_statement(db, stmt, buf);
+            }
+            buf.push_str("])");
+        }
+        AstExprKind::Break { label, value } => {
+            buf.push_str("Break");
+            if label.is_some() || value.is_some() {
+                buf.push('(');
+                if let Some(label) = label {
+                    buf.push('\'');
+                    format_identifier(db, label, buf);
+                }
+                if let Some(value) = value {
+                    if label.is_some() {
+                        buf.push_str(", ");
+                    }
+                    format_ast_expr(db, value, buf);
+                }
+                buf.push(')');
+            }
+        }
+        AstExprKind::Continue(label) => {
+            buf.push_str("Continue");
+            if let Some(label) = label {
+                buf.push_str("('");
+                format_identifier(db, label, buf);
+                buf.push(')');
+            }
+        }
         AstExprKind::Await { future, .. } => {
             buf.push_str("Await(");
             format_ast_expr(db, future, buf);
             buf.push(')');
         }
+        AstExprKind::Try { value, .. } => {
+            buf.push_str("Try(");
+            format_ast_expr(db, value, buf);
+            buf.push(')');
+        }
         AstExprKind::PermissionOp { value, op } => {
             let op_str = match op {
                 PermissionOp::Mutate => "Mutate",
@@ -474,6 +783,16 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
             format_ast_expr(db, sub_expr, buf);
             buf.push(')');
         }
+        AstExprKind::Cast(value, ty) => {
+            buf.push_str("Cast(");
+            format_ast_expr(db, value, buf);
+            buf.push_str(", ");
+            match ty.kind(db) {
+                AstTyKind::Named(path, None) => format_ast_path(db, path, buf),
+                _ => buf.push_str("..."),
+            }
+            buf.push(')');
+        }
         AstExprKind::If(arms) => {
             buf.push_str("If([");
             for (i, arm) in arms.iter().enumerate() {
@@ -481,6 +800,13 @@ fn format_ast_expr<'db>(db: &'db dyn crate::Db, expr: &AstExpr<'db>, buf: &mut S
                     buf.push_str(", ");
                 }
                 if let Some(cond) = &arm.condition {
+                    if let Some(binding) = &arm.binding {
+                        buf.push_str("let ");
+                        format_identifier(db, &binding.some, buf);
+                        buf.push('(');
+                        format_ast_pattern(db, &binding.pattern, buf);
+                        buf.push_str(") = ");
+                    }
                     format_ast_expr(db, cond, buf);
                     buf.push_str(" => ");
                 } else {
@@ -504,7 +830,7 @@ fn format_ast_statement<'db>(db: &'db dyn crate::Db, stmt: &AstStatement<'db>, b
     match stmt {
         AstStatement::Let(let_stmt) => {
             buf.push_str("Let(");
-            format_identifier(db, &let_stmt.name(db), buf);
+            format_ast_pattern(db, let_stmt.pattern(db), buf);
             if let Some(init) = let_stmt.initializer(db) {
                 buf.push_str(", ");
                 format_ast_expr(db, &init, buf);
@@ -514,6 +840,51 @@ fn format_ast_statement<'db>(db: &'db dyn crate::Db, stmt: &AstStatement<'db>, b
         AstStatement::Expr(expr) => {
             format_ast_expr(db, expr, buf);
         }
+        AstStatement::Aggregate(aggr) => {
+            buf.push_str("Aggregate(");
+            buf.push_str(aggr.name(db).text(db));
+            buf.push(')');
+        }
+        AstStatement::Function(func) => {
+            buf.push_str("Function(");
+            format_identifier(db, &func.name(db), buf);
+            buf.push(')');
+        }
+    }
+}
+
+fn format_ast_pattern<'db>(db: &'db dyn crate::Db, pattern: &AstPattern<'db>, buf: &mut String) {
+    match pattern {
+        AstPattern::Id(id) => format_identifier(db, id, buf),
+        AstPattern::Tuple(patterns) => {
+            buf.push('(');
+            for (i, pattern) in patterns.values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_ast_pattern(db, pattern, buf);
+            }
+            buf.push(')');
+        }
+        AstPattern::Aggregate(path, fields) => {
+            format_ast_path(db, path, buf);
+            buf.push_str(" { ");
+            for (i, field) in fields.values.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_identifier(db, &field.name, buf);
+                if let Some(op) = field.op {
+                    buf.push_str(match op {
+                        PermissionOp::Mutate => ".mut",
+                        PermissionOp::Reference => ".ref",
+                        PermissionOp::Give => ".give",
+                        PermissionOp::Share => ".share",
+                    });
+                }
+            }
+            buf.push_str(" }");
+        }
     }
 }
 
@@ -0,0 +1,120 @@
+use dada_ir_ast::{
+    ast::{AstAggregateKind, AstFieldDecl, AstFunction, AstItem, AstMember},
+    inputs::SourceFile,
+    span::AbsoluteSpan,
+};
+use dada_parser::prelude::{ClassItemMembers, SourceFileParse};
+
+/// An item declared in a source file, for an editor to show in
+/// its outline view or breadcrumbs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OutlineItem {
+    pub name: String,
+    pub kind: OutlineKind,
+
+    /// The whole declaration, e.g., from `class` to the closing `}`
+    pub span: AbsoluteSpan,
+
+    /// The name in the declaration
+    pub name_span: AbsoluteSpan,
+
+    /// Items declared within this one (e.g., the fields and methods of a class),
+    /// in source order.
+    pub children: Vec<OutlineItem>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OutlineKind {
+    Class,
+    Struct,
+    Field,
+    Function,
+    Method,
+}
+
+/// Computes the outline of the module in `source_file`: its classes, structs,
+/// and functions (including extern functions) in source order, with the fields
+/// and methods of each class as its children.
+///
+/// 💡 This only needs the parse of `source_file`, so it stays available
+/// while the file does not type check.
+pub fn outline(db: &dyn crate::Db, source_file: SourceFile) -> Vec<OutlineItem> {
+    let module = source_file.parse(db);
+
+    module
+        .items(db)
+        .values
+        .iter()
+        .filter_map(|item| match *item {
+            AstItem::Aggregate(aggregate) => {
+                let kind = match aggregate.kind(db) {
+                    AstAggregateKind::Class => OutlineKind::Class,
+                    AstAggregateKind::Struct => OutlineKind::Struct,
+                };
+
+                // Fields declared in the `()` section come before the body.
+                let mut children: Vec<OutlineItem> = aggregate
+                    .inputs(db)
+                    .iter()
+                    .flat_map(|inputs| &inputs.values)
+                    .map(|&field| field_item(db, field))
+                    .collect();
+                children.extend(
+                    aggregate
+                        .members(db)
+                        .values
+                        .iter()
+                        .map(|member| match *member {
+                            AstMember::Field(field) => field_item(db, field),
+                            AstMember::Function(function) => {
+                                function_item(db, function, OutlineKind::Method)
+                            }
+                        }),
+                );
+
+                Some(OutlineItem {
+                    name: aggregate.name(db).to_string(),
+                    kind,
+                    span: aggregate.span(db).absolute_span(db),
+                    name_span: aggregate.name_span(db).absolute_span(db),
+                    children,
+                })
+            }
+            AstItem::Function(function) => Some(function_item(db, function, OutlineKind::Function)),
+            AstItem::ExternFunction(function) => {
+                let mut item = function_item(db, function.function(db), OutlineKind::Function);
+                item.span = function.span(db).absolute_span(db);
+                Some(item)
+            }
+
+            // The implicit `main` made from top-level statements has no declaration.
+            AstItem::SourceFile(_) | AstItem::Use(_) | AstItem::MainFunction(_) => None,
+        })
+        .collect()
+}
+
+fn function_item<'db>(
+    db: &'db dyn crate::Db,
+    function: AstFunction<'db>,
+    kind: OutlineKind,
+) -> OutlineItem {
+    let name = function.name(db);
+    OutlineItem {
+        name: name.id.to_string(),
+        kind,
+        span: function.span(db).absolute_span(db),
+        name_span: name.span.absolute_span(db),
+        children: vec![],
+    }
+}
+
+fn field_item<'db>(db: &'db dyn crate::Db, field: AstFieldDecl<'db>) -> OutlineItem {
+    let name = field.variable(db).name(db);
+    OutlineItem {
+        name: name.id.to_string(),
+        kind: OutlineKind::Field,
+        span: field.span(db).absolute_span(db),
+        name_span: name.span.absolute_span(db),
+        children: vec![],
+    }
+}
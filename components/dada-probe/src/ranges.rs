@@ -0,0 +1,172 @@
+use dada_ir_ast::{
+    ast::{AstBlock, AstExprKind, AstItem, AstMember},
+    inputs::SourceFile,
+    span::{AbsoluteOffset, AbsoluteSpan, Spanned},
+};
+use dada_parser::{
+    lexical_tokens,
+    prelude::{ClassItemMembers, FunctionBlock, SourceFileParse},
+};
+
+use crate::{completions::point_span, visit_ast_exprs, walk_ast_exprs};
+
+/// Spans that an editor can fold, in source order: the bodies of classes
+/// and functions, blocks, and the arms of `if`/`else` (each including its `{}`).
+pub fn folding_ranges(db: &dyn crate::Db, source_file: SourceFile) -> Vec<AbsoluteSpan> {
+    let mut spans = vec![];
+
+    for item in &source_file.parse(db).items(db).values {
+        match *item {
+            AstItem::Aggregate(aggregate) => {
+                if let Some(contents) = aggregate.contents(db) {
+                    spans.push(contents.span.absolute_span(db));
+                }
+                for member in &aggregate.members(db).values {
+                    if let AstMember::Function(function) = member
+                        && let Some(block) = function.body_block(db)
+                    {
+                        spans.push(block.span(db).absolute_span(db));
+                    }
+                }
+            }
+            AstItem::Function(function) => {
+                if let Some(block) = function.body_block(db) {
+                    spans.push(block.span(db).absolute_span(db));
+                }
+            }
+            AstItem::SourceFile(_)
+            | AstItem::Use(_)
+            | AstItem::ExternFunction(_)
+            | AstItem::MainFunction(_) => {}
+        }
+    }
+
+    walk_ast_exprs(db, source_file, &mut |expr| {
+        match &*expr.kind {
+            AstExprKind::Block(block)
+            | AstExprKind::Atomic(block)
+            | AstExprKind::Spawn(block)
+            | AstExprKind::Closure(block)
+            | AstExprKind::Loop {
+                label: _,
+                body: block,
+            }
+            | AstExprKind::While {
+                label: _,
+                condition: _,
+                body: block,
+            } => spans.push(block.span(db).absolute_span(db)),
+            AstExprKind::If(arms) => {
+                spans.extend(arms.iter().map(|arm| arm.result.span(db).absolute_span(db)))
+            }
+            _ => {}
+        }
+        true
+    });
+
+    spans.sort_by_key(|span| (span.start, span.end));
+    spans.dedup();
+    spans
+}
+
+/// Spans enclosing `offset`, from innermost to outermost, for an editor
+/// to grow the selection through: the token at `offset`, the expressions,
+/// statements, and blocks around it, the enclosing items, and the whole file.
+pub fn selection_ranges(
+    db: &dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+) -> Vec<AbsoluteSpan> {
+    let mut spans = vec![source_file.absolute_span(db)];
+
+    spans.extend(
+        lexical_tokens(db, source_file)
+            .into_iter()
+            .map(|(span, _)| span)
+            .find(|span| span.start <= offset && offset <= span.end),
+    );
+
+    for item in &source_file.parse(db).items(db).values {
+        match *item {
+            AstItem::Aggregate(aggregate) => {
+                spans.push(aggregate.span(db).absolute_span(db));
+                if let Some(contents) = aggregate.contents(db) {
+                    spans.push(contents.span.absolute_span(db));
+                }
+                for member in &aggregate.members(db).values {
+                    spans.push(member.span(db).absolute_span(db));
+                    if let AstMember::Function(function) = member
+                        && let Some(block) = function.body_block(db)
+                    {
+                        push_block(db, &mut spans, block);
+                    }
+                }
+            }
+            AstItem::Function(function) => {
+                spans.push(function.span(db).absolute_span(db));
+                if let Some(block) = function.body_block(db) {
+                    push_block(db, &mut spans, block);
+                }
+            }
+            AstItem::MainFunction(main_function) => spans.extend(
+                main_function
+                    .statements(db)
+                    .values
+                    .iter()
+                    .map(|statement| statement.span(db).absolute_span(db)),
+            ),
+            AstItem::ExternFunction(function) => spans.push(function.span(db).absolute_span(db)),
+            AstItem::Use(use_item) => spans.push(use_item.span(db).absolute_span(db)),
+            AstItem::SourceFile(_) => {}
+        }
+    }
+
+    visit_ast_exprs(db, point_span(source_file, offset), &mut |expr| {
+        spans.push(expr.span.absolute_span(db));
+        match &*expr.kind {
+            AstExprKind::Block(block)
+            | AstExprKind::Atomic(block)
+            | AstExprKind::Spawn(block)
+            | AstExprKind::Closure(block)
+            | AstExprKind::Loop {
+                label: _,
+                body: block,
+            }
+            | AstExprKind::While {
+                label: _,
+                condition: _,
+                body: block,
+            } => push_block(db, &mut spans, *block),
+            AstExprKind::If(arms) => {
+                for arm in arms {
+                    push_block(db, &mut spans, arm.result);
+                }
+            }
+            _ => {}
+        }
+    });
+
+    // Keep the spans around `offset`, each enclosing the one before.
+    spans.retain(|span| span.start <= offset && offset <= span.end);
+    spans.sort_by_key(|span| (span.end.as_usize() - span.start.as_usize(), span.start));
+    spans.dedup();
+    let mut ranges: Vec<AbsoluteSpan> = vec![];
+    for span in spans {
+        if ranges.last().is_none_or(|&inner| span.contains(inner)) {
+            ranges.push(span);
+        }
+    }
+    ranges
+}
+
+/// Pushes the span of `block` and of each of its statements.
+fn push_block<'db>(db: &'db dyn crate::Db, spans: &mut Vec<AbsoluteSpan>, block: AstBlock<'db>) {
+    spans.push(block.span(db).absolute_span(db));
+    spans.extend(
+        block
+            .statements(db)
+            .values
+            .iter()
+            .map(|statement| statement.span(db).absolute_span(db)),
+    );
+}
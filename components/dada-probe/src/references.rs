@@ -0,0 +1,424 @@
+use dada_ir_ast::{
+    ast::{AstExprKind, AstItem, Identifier, SpannedIdentifier},
+    inputs::SourceFile,
+    span::{AbsoluteOffset, AbsoluteSpan, Offset, SourceSpanned, Span},
+};
+use dada_ir_sym::{
+    check::NameResolutionSym,
+    ir::{
+        classes::{SymClassMember, SymField},
+        exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+        functions::{SymFunction, SymFunctionSource},
+        module::SymItem,
+        types::{SymGenericKind, SymTy},
+        variables::SymVariable,
+    },
+    prelude::{CheckedBody, Symbol},
+};
+use dada_parser::prelude::SourceFileParse;
+use dada_util::{FromImpls, Map, Set};
+use salsa::Update;
+
+use crate::walk_ast_exprs;
+
+/// Something that a name in the source can refer to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Update, FromImpls)]
+pub enum Referent<'db> {
+    /// A local variable or parameter
+    Variable(SymVariable<'db>),
+    Field(SymField<'db>),
+    Function(SymFunction<'db>),
+}
+
+impl<'db> Referent<'db> {
+    pub fn name(self, db: &'db dyn crate::Db) -> Identifier<'db> {
+        match self {
+            Referent::Variable(variable) => variable
+                .name(db)
+                .expect("only named variables are referents"),
+            Referent::Field(field) => field.name(db),
+            Referent::Function(function) => function.name(db),
+        }
+    }
+
+    /// Span of the name in the referent's declaration.
+    pub fn definition(self, db: &'db dyn crate::Db) -> AbsoluteSpan {
+        match self {
+            Referent::Variable(variable) => variable.span(db).absolute_span(db),
+            Referent::Field(field) => field.name_span(db).absolute_span(db),
+            Referent::Function(function) => function.name_span(db).absolute_span(db),
+        }
+    }
+}
+
+/// An occurrence of the name of a referent, including the one in its declaration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Update)]
+pub(crate) struct Reference<'db> {
+    pub referent: Referent<'db>,
+
+    /// Span of just the name, e.g., of `f` in `x.f`.
+    pub span: AbsoluteSpan,
+}
+
+/// The reference whose name contains `offset` (including its end), if any.
+pub(crate) fn reference_at<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+) -> Option<Reference<'db>> {
+    references_index(db, source_file)
+        .iter()
+        .find(|reference| reference.span.start <= offset && offset <= reference.span.end)
+        .copied()
+}
+
+/// The variable, field, or function named at `offset`, if any.
+pub fn referent_at<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+) -> Option<Referent<'db>> {
+    reference_at(db, source_file, offset).map(|reference| reference.referent)
+}
+
+/// Spans of every name in `source_files` that refers to `referent`,
+/// including the one in its declaration, sorted by position.
+///
+/// A variable can only be referenced in the file that declares it,
+/// but fields and functions can be referenced from any module of the crate
+/// (or of crates that depend on it), so callers choose which files to search.
+pub fn references<'db>(
+    db: &'db dyn crate::Db,
+    referent: impl Into<Referent<'db>>,
+    source_files: &[SourceFile],
+) -> Vec<AbsoluteSpan> {
+    let referent = referent.into();
+    let mut spans: Vec<AbsoluteSpan> = source_files
+        .iter()
+        .flat_map(|&source_file| references_index(db, source_file))
+        .filter(|reference| reference.referent == referent)
+        .map(|reference| reference.span)
+        .collect();
+    spans.sort_by_key(|span| (span.source_file.url(db).clone(), span.start));
+    spans.dedup();
+    spans
+}
+
+/// Every name in `source_file` that refers to a variable, field, or function,
+/// found by walking the declarations and checked bodies of its items.
+///
+/// 💡 The checked bodies do not record where the names of callees,
+/// named arguments, or constructor fields appear, so those are
+/// matched up with the AST by the span of the enclosing expression.
+#[salsa::tracked(return_ref)]
+pub(crate) fn references_index<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+) -> Vec<Reference<'db>> {
+    let mut indexer = Indexer {
+        db,
+        source_file,
+        ast_names: ast_names(db, source_file),
+        references: vec![],
+        seen: Set::default(),
+    };
+
+    for item in source_file.symbol(db).items(db) {
+        match item {
+            SymItem::SymFunction(function) => indexer.function(function),
+            SymItem::SymClass(aggregate) => {
+                for &member in aggregate.members(db) {
+                    match member {
+                        SymClassMember::SymField(field) => {
+                            indexer.push(Referent::Field(field), field.name_span(db))
+                        }
+                        SymClassMember::SymFunction(method) => indexer.function(method),
+                    }
+                }
+            }
+            SymItem::SymPrimitive(_) => {}
+        }
+    }
+
+    // `use crate.module.f` names `f` too.
+    let module = source_file.symbol(db);
+    for item in &source_file.parse(db).items(db).values {
+        if let AstItem::Use(use_item) = item
+            && use_item.glob(db).is_none()
+        {
+            let id = use_item.path(db).last_id(db);
+            let binding = use_item.as_id(db).unwrap_or(id).id;
+            let function = module
+                .mod_scope(db)
+                .visible_symbols(db)
+                .into_iter()
+                .find_map(|(name, sym)| match sym {
+                    NameResolutionSym::SymFunction(function) if name == binding => Some(function),
+                    _ => None,
+                });
+            if let Some(function) = function {
+                indexer.push(Referent::Function(function), id.span);
+            }
+        }
+    }
+
+    indexer.references
+}
+
+/// Names written in a call or constructor expression.
+struct AstNames<'db> {
+    /// Name of the function or method being called, if it is written as `f(..)` or `x.f(..)`.
+    callee: Option<SpannedIdentifier<'db>>,
+
+    /// Names of the named arguments or of the fields given to a constructor.
+    fields: Vec<SpannedIdentifier<'db>>,
+}
+
+fn ast_names<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+) -> Map<AbsoluteSpan, AstNames<'db>> {
+    let mut names = Map::default();
+    walk_ast_exprs(db, source_file, &mut |expr| {
+        match &*expr.kind {
+            AstExprKind::ParenthesisOp(callee, args) => {
+                let callee = match &*callee.kind {
+                    AstExprKind::Id(id) | AstExprKind::DotId(_, id) => Some(*id),
+                    _ => None,
+                };
+                let fields = args.values.iter().filter_map(|arg| arg.name).collect();
+                names.insert(expr.span.absolute_span(db), AstNames { callee, fields });
+            }
+            AstExprKind::Constructor(_, fields, _) => {
+                let fields = fields.values.iter().map(|field| field.name).collect();
+                names.insert(
+                    expr.span.absolute_span(db),
+                    AstNames {
+                        callee: None,
+                        fields,
+                    },
+                );
+            }
+            _ => {}
+        }
+        true
+    });
+    names
+}
+
+struct Indexer<'db> {
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    ast_names: Map<AbsoluteSpan, AstNames<'db>>,
+    references: Vec<Reference<'db>>,
+    seen: Set<Reference<'db>>,
+}
+
+impl<'db> Indexer<'db> {
+    fn push(&mut self, referent: Referent<'db>, span: Span<'db>) {
+        self.push_absolute(referent, span.absolute_span(self.db));
+    }
+
+    /// Records a reference, unless the text at `span` is not the referent's name
+    /// (e.g., because the span belongs to some desugared code).
+    fn push_absolute(&mut self, referent: Referent<'db>, span: AbsoluteSpan) {
+        let name = referent.name(self.db).text(self.db);
+        let text = self.source_file.contents_if_ok(self.db);
+        if span.source_file != self.source_file
+            || text.get(span.start.as_usize()..span.end.as_usize()) != Some(name)
+        {
+            return;
+        }
+
+        let reference = Reference { referent, span };
+        if self.seen.insert(reference) {
+            self.references.push(reference);
+        }
+    }
+
+    fn variable(&mut self, variable: SymVariable<'db>, span: Span<'db>) {
+        if variable.kind(self.db) == SymGenericKind::Place && variable.name(self.db).is_some() {
+            self.push(Referent::Variable(variable), span);
+        }
+    }
+
+    fn function(&mut self, function: SymFunction<'db>) {
+        let db = self.db;
+        match function.source(db) {
+            SymFunctionSource::Function(_) | SymFunctionSource::Extern(_) => {
+                self.push(Referent::Function(function), function.name_span(db));
+            }
+
+            // The generated `main` function has no name.
+            SymFunctionSource::MainFunction(_) => {}
+
+            // Constructors are generated from the class declaration,
+            // whose parameters are indexed as its fields.
+            SymFunctionSource::Constructor(..) => return,
+        }
+
+        for &input in &function.symbols(db).input_variables {
+            self.variable(input, input.span(db));
+        }
+
+        if let Some(body) = function.checked_body(db) {
+            self.expr(body);
+        }
+    }
+
+    fn expr(&mut self, expr: SymExpr<'db>) {
+        let db = self.db;
+        match expr.kind(db) {
+            SymExprKind::Semi(lhs, rhs) | SymExprKind::BinaryOp(_, lhs, rhs) => {
+                self.expr(*lhs);
+                self.expr(*rhs);
+            }
+            SymExprKind::Tuple(exprs) => {
+                for &expr in exprs {
+                    self.expr(expr);
+                }
+            }
+            SymExprKind::Primitive(_) | SymExprKind::ByteLiteral(_) | SymExprKind::Error(_) => {}
+            SymExprKind::LetIn {
+                lv,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                self.variable(*lv, lv.span(db));
+                if let Some(initializer) = initializer {
+                    self.expr(*initializer);
+                }
+                self.expr(*body);
+            }
+            SymExprKind::LetTuple {
+                lvs,
+                initializer,
+                body,
+            } => {
+                for &(lv, _) in lvs {
+                    self.variable(lv, lv.span(db));
+                }
+                self.expr(*initializer);
+                self.expr(*body);
+            }
+            SymExprKind::Await {
+                future,
+                await_keyword: _,
+            } => self.expr(*future),
+            SymExprKind::Assign { place, value } => {
+                self.place(*place);
+                self.expr(*value);
+            }
+            SymExprKind::PermissionOp(_, place)
+            | SymExprKind::IsSome(place)
+            | SymExprKind::Drop(place) => self.place(*place),
+            SymExprKind::Call {
+                function,
+                substitution: _,
+                arg_temps: _,
+            } => self.call(expr, *function),
+            SymExprKind::Return(value)
+            | SymExprKind::Break {
+                from_loop: _,
+                with_value: value,
+            } => self.expr(*value),
+            SymExprKind::Loop { sym_loop: _, body } => self.expr(*body),
+            SymExprKind::Continue { to_loop: _ } => {}
+            SymExprKind::Not {
+                operand,
+                op_span: _,
+            }
+            | SymExprKind::Cast(operand) => self.expr(*operand),
+            SymExprKind::Aggregate { ty, fields } => {
+                self.constructor_fields(expr, *ty);
+                for &field in fields {
+                    self.expr(field);
+                }
+            }
+            SymExprKind::Match { arms } => {
+                for arm in arms {
+                    if let Some(condition) = arm.condition {
+                        self.expr(condition);
+                    }
+                    self.expr(arm.body);
+                }
+            }
+        }
+    }
+
+    fn place(&mut self, place: SymPlaceExpr<'db>) {
+        let db = self.db;
+        match place.kind(db) {
+            SymPlaceExprKind::Var(variable) => self.variable(*variable, place.span(db)),
+            SymPlaceExprKind::Field(owner, field) => {
+                self.place(*owner);
+
+                // The field name is at the end of `owner.field`.
+                let span = place.span(db).absolute_span(db);
+                let len = field.name(db).text(db).len();
+                if len <= span.end.as_usize() - span.start.as_usize() {
+                    let start = span.end - Offset::from(len);
+                    self.push_absolute(Referent::Field(*field), AbsoluteSpan { start, ..span });
+                }
+            }
+            SymPlaceExprKind::Error(_) => {}
+        }
+    }
+
+    fn call(&mut self, expr: SymExpr<'db>, function: SymFunction<'db>) {
+        let db = self.db;
+        let Some(names) = self.ast_names.get(&expr.source_span(db).absolute_span(db)) else {
+            return;
+        };
+        let callee = names.callee;
+        let fields = names.fields.clone();
+
+        // A named argument to a constructor names a field.
+        let constructed = match function.source(db) {
+            SymFunctionSource::Constructor(aggregate, _) => Some(aggregate),
+            _ => {
+                if let Some(callee) = callee {
+                    self.push(Referent::Function(function), callee.span);
+                }
+                None
+            }
+        };
+
+        for name in fields {
+            let referent = match constructed {
+                Some(aggregate) => aggregate
+                    .fields(db)
+                    .find(|field| field.name(db) == name.id)
+                    .map(Referent::Field),
+                None => function
+                    .symbols(db)
+                    .input_variables
+                    .iter()
+                    .find(|input| input.name(db) == Some(name.id))
+                    .map(|&input| Referent::Variable(input)),
+            };
+            if let Some(referent) = referent {
+                self.push(referent, name.span);
+            }
+        }
+    }
+
+    fn constructor_fields(&mut self, expr: SymExpr<'db>, ty: SymTy<'db>) {
+        let db = self.db;
+        let Some(names) = self.ast_names.get(&expr.source_span(db).absolute_span(db)) else {
+            return;
+        };
+        let fields = names.fields.clone();
+
+        for name in fields {
+            let field = ty.members(db).iter().find_map(|&member| match member {
+                SymClassMember::SymField(field) if field.name(db) == name.id => Some(field),
+                _ => None,
+            });
+            if let Some(field) = field {
+                self.push(Referent::Field(field), name.span);
+            }
+        }
+    }
+}
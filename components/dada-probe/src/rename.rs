@@ -0,0 +1,171 @@
+use dada_ir_ast::{
+    ast::Identifier,
+    diagnostic::{Diagnostic, Level},
+    inputs::SourceFile,
+    span::{AbsoluteOffset, AbsoluteSpan},
+};
+use dada_ir_sym::{
+    ir::{
+        classes::{SymAggregate, SymClassMember},
+        module::SymItem,
+    },
+    prelude::Symbol,
+};
+use dada_parser::is_identifier;
+
+use crate::{
+    completions::{point_span, scope_completions},
+    references::{Referent, reference_at, references},
+};
+
+/// A replacement of the text at `span` with `new_text`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    pub span: AbsoluteSpan,
+    pub new_text: String,
+}
+
+/// Computes the edits that rename the variable, field, or function named at `offset`
+/// to `new_name`: one for its declaration and one for each reference to it in
+/// `crate_files`, the source files of the crate being edited.
+///
+/// Fails with a diagnostic if there is nothing to rename at `offset`, if `new_name` is
+/// not an identifier, if the declaration is not in `crate_files`, or if the rename
+/// would conflict with an existing name (see `check_conflicts`).
+pub fn rename<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+    new_name: &str,
+    crate_files: &[SourceFile],
+) -> Result<Vec<TextEdit>, Diagnostic> {
+    let Some(reference) = reference_at(db, source_file, offset) else {
+        return Err(Diagnostic::error(
+            db,
+            point_span(source_file, offset).into_span(db),
+            "there is no variable, field, or function to rename here",
+        ));
+    };
+    let referent = reference.referent;
+    let name = referent.name(db);
+    let span = reference.span.into_span(db);
+
+    if name == Identifier::self_ident(db) {
+        return Err(Diagnostic::error(db, span, "`self` cannot be renamed"));
+    }
+
+    if !is_identifier(new_name) {
+        return Err(Diagnostic::error(
+            db,
+            span,
+            format!("`{new_name}` is not a valid name"),
+        ));
+    }
+
+    if !crate_files.contains(&referent.definition(db).source_file) {
+        return Err(Diagnostic::error(
+            db,
+            span,
+            format!("cannot rename `{name}` because it is defined outside of this crate"),
+        )
+        .label(
+            db,
+            Level::Error,
+            span,
+            format!("`{name}` is not defined in this crate"),
+        ));
+    }
+
+    let spans = references(db, referent, crate_files);
+    if name.text(db) != new_name {
+        check_conflicts(db, referent, &spans, new_name)?;
+    }
+
+    Ok(spans
+        .into_iter()
+        .map(|span| TextEdit {
+            span,
+            new_text: new_name.to_string(),
+        })
+        .collect())
+}
+
+/// Renaming must not change what any name refers to. So `new_name` must not
+/// already be a member of the class (for a field or method), or be in scope
+/// at any of the `spans` where `referent` is named (for a variable or function),
+/// since it would then be shadowed by (or shadow) the renamed referent.
+fn check_conflicts<'db>(
+    db: &'db dyn crate::Db,
+    referent: Referent<'db>,
+    spans: &[AbsoluteSpan],
+    new_name: &str,
+) -> Result<(), Diagnostic> {
+    let name = referent.name(db);
+    let definition = referent.definition(db);
+
+    if let Some(aggregate) = owner_class(db, referent) {
+        if aggregate.inherent_member_str(db, new_name).is_none() {
+            return Ok(());
+        }
+        return Err(Diagnostic::error(
+            db,
+            definition.into_span(db),
+            format!("cannot rename `{name}` to `{new_name}` because `{aggregate}` already has a member named `{new_name}`"),
+        )
+        .label(
+            db,
+            Level::Error,
+            definition.into_span(db),
+            format!("`{new_name}` is already declared in `{aggregate}`"),
+        ));
+    }
+
+    for &span in spans {
+        // The scope at a function's declaration includes its own parameters,
+        // which do not conflict with its name.
+        let in_scope = if let Referent::Function(_) = referent
+            && span == definition
+        {
+            let module = span.source_file.symbol(db);
+            module
+                .mod_scope(db)
+                .visible_names(db)
+                .any(|id| id.text(db) == new_name)
+        } else {
+            scope_completions(db, point_span(span.source_file, span.start))
+                .iter()
+                .any(|completion| completion.label == new_name)
+        };
+
+        if in_scope {
+            return Err(Diagnostic::error(
+                db,
+                span.into_span(db),
+                format!("cannot rename `{name}` to `{new_name}` because `{new_name}` is already in scope"),
+            )
+            .label(
+                db,
+                Level::Error,
+                span.into_span(db),
+                format!("`{new_name}` already refers to something else here"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The class declaring `referent`, if it is a field or method.
+fn owner_class<'db>(db: &'db dyn crate::Db, referent: Referent<'db>) -> Option<SymAggregate<'db>> {
+    let member = match referent {
+        Referent::Field(field) => SymClassMember::SymField(field),
+        Referent::Function(function) => SymClassMember::SymFunction(function),
+        Referent::Variable(_) => return None,
+    };
+
+    let module = referent.definition(db).source_file.symbol(db);
+    module.items(db).find_map(|item| match item {
+        SymItem::SymClass(aggregate) if aggregate.members(db).contains(&member) => Some(aggregate),
+        _ => None,
+    })
+}
@@ -0,0 +1,130 @@
+use dada_ir_ast::{
+    ast::{Identifier, LiteralKind},
+    inputs::SourceFile,
+    span::AbsoluteSpan,
+};
+use dada_ir_sym::{
+    check::NameResolutionSym,
+    ir::{functions::SymFunction, types::SymGenericKind},
+    prelude::Symbol,
+};
+use dada_parser::{LexicalClass, lexical_tokens};
+use dada_util::Map;
+
+use crate::{
+    find_func,
+    references::{Referent, references_index},
+};
+
+/// A token classified for syntax highlighting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SemanticToken {
+    pub span: AbsoluteSpan,
+    pub kind: SemanticTokenKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SemanticTokenKind {
+    Keyword,
+
+    /// A permission keyword like `ref` or `my` (or a permission operation like `give`),
+    /// or a permission parameter
+    Permission,
+
+    /// A class, struct, primitive type, or type parameter
+    Type,
+
+    /// A function or method
+    Function,
+
+    /// A local variable or parameter
+    Variable,
+
+    Field,
+    Module,
+    Literal(LiteralKind),
+    Operator,
+}
+
+/// Classifies the tokens of `source_file` for semantic highlighting, in source order.
+///
+/// Identifiers are classified by what they refer to: the references found in
+/// the checked bodies first, otherwise the name in scope at that point.
+/// Identifiers that resolve to nothing (e.g., a misspelled name, or a member
+/// of a value whose type is unknown) are omitted, as are comments.
+pub fn semantic_tokens<'db>(db: &'db dyn crate::Db, source_file: SourceFile) -> Vec<SemanticToken> {
+    let text = source_file.contents_if_ok(db);
+    let referents: Map<AbsoluteSpan, Referent<'db>> = references_index(db, source_file)
+        .iter()
+        .map(|reference| (reference.span, reference.referent))
+        .collect();
+    let mut scopes: Map<Option<SymFunction<'db>>, Map<Identifier<'db>, SemanticTokenKind>> =
+        Map::default();
+
+    lexical_tokens(db, source_file)
+        .into_iter()
+        .filter_map(|(span, class)| {
+            let kind = match class {
+                LexicalClass::Keyword => SemanticTokenKind::Keyword,
+                LexicalClass::PermissionKeyword => SemanticTokenKind::Permission,
+                LexicalClass::Literal(kind) => SemanticTokenKind::Literal(kind),
+                LexicalClass::Operator => SemanticTokenKind::Operator,
+                LexicalClass::Identifier => match referents.get(&span) {
+                    Some(Referent::Variable(_)) => SemanticTokenKind::Variable,
+                    Some(Referent::Field(_)) => SemanticTokenKind::Field,
+                    Some(Referent::Function(_)) => SemanticTokenKind::Function,
+
+                    // A member name that was not resolved cannot be looked up in scope.
+                    None if text[..span.start.as_usize()].trim_end().ends_with('.') => {
+                        return None;
+                    }
+
+                    None => {
+                        let function = find_func(db, span);
+                        let scope = scopes
+                            .entry(function)
+                            .or_insert_with(|| scope_kinds(db, source_file, function));
+                        let name = Identifier::new(
+                            db,
+                            text[span.start.as_usize()..span.end.as_usize()].to_string(),
+                        );
+                        *scope.get(&name)?
+                    }
+                },
+            };
+            Some(SemanticToken { span, kind })
+        })
+        .collect()
+}
+
+/// The kind of each name visible in `function`, or in the module if `None`.
+fn scope_kinds<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    function: Option<SymFunction<'db>>,
+) -> Map<Identifier<'db>, SemanticTokenKind> {
+    let scope = match function {
+        Some(function) => function.scope(db),
+        None => source_file.symbol(db).mod_scope(db),
+    };
+
+    let mut kinds = Map::default();
+    for (name, sym) in scope.visible_symbols(db) {
+        let kind = match sym {
+            NameResolutionSym::SymModule(_) => SemanticTokenKind::Module,
+            NameResolutionSym::SymAggregate(_) | NameResolutionSym::SymPrimitive(_) => {
+                SemanticTokenKind::Type
+            }
+            NameResolutionSym::SymFunction(_) => SemanticTokenKind::Function,
+            NameResolutionSym::SymVariable(variable) => match variable.kind(db) {
+                SymGenericKind::Type => SemanticTokenKind::Type,
+                SymGenericKind::Perm => SemanticTokenKind::Permission,
+                SymGenericKind::Place => SemanticTokenKind::Variable,
+            },
+        };
+
+        // Earlier entries shadow later ones with the same name.
+        kinds.entry(name).or_insert(kind);
+    }
+    kinds
+}
@@ -0,0 +1,201 @@
+use std::ops::ControlFlow;
+
+use dada_ir_ast::{
+    ast::{AstCallArg, AstExpr, AstExprKind, Identifier, SpanVec},
+    inputs::SourceFile,
+    span::{AbsoluteOffset, SourceSpanned},
+};
+use dada_ir_sym::{
+    check::NameResolutionSym,
+    ir::{
+        classes::{SymAggregate, SymClassMember},
+        exprs::SymExprKind,
+        functions::{SymFunction, SymFunctionSource},
+        types::{SymTy, SymTyKind, SymTyName},
+    },
+    prelude::CheckedSignature,
+};
+
+use crate::{
+    completions::{owner_ty, point_span},
+    find_func, visit_ast_exprs, visit_exprs,
+};
+
+/// The signature of the function being called at some position,
+/// for an editor to show while the arguments are typed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureHelp {
+    /// The whole signature, e.g., `fn add(a: u32, b: u32) -> u32`
+    pub label: String,
+
+    /// The parameters as they appear in `label`, e.g., `a: u32`.
+    /// The `self` parameter of a method is not included.
+    pub parameters: Vec<String>,
+
+    /// Index into `parameters` of the argument under the cursor, if any.
+    pub active_parameter: Option<usize>,
+}
+
+/// Computes the signature help for the innermost call whose parentheses enclose `offset`.
+///
+/// The target function is taken from the type-checked call if there is one;
+/// otherwise (e.g., while some arguments are still missing) it is resolved
+/// from the name of the callee.
+pub fn signature_help<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+    offset: AbsoluteOffset,
+) -> Option<SignatureHelp> {
+    let text = source_file.contents_if_ok(db);
+    let position = point_span(source_file, offset);
+
+    // Find the innermost call with the cursor between its parentheses.
+    let mut innermost = None;
+    visit_ast_exprs(db, position, &mut |expr| {
+        if let AstExprKind::ParenthesisOp(callee, args) = &*expr.kind {
+            let callee_end = callee.span.absolute_span(db).end.as_usize();
+            let call_end = expr.span.absolute_span(db).end.as_usize();
+            let Some(open) = text[callee_end..].find('(') else {
+                return;
+            };
+            if callee_end + open < offset.as_usize() && offset.as_usize() < call_end {
+                innermost = Some((expr.clone(), callee.clone(), args.clone()));
+            }
+        }
+    });
+    let (call, callee, args) = innermost?;
+
+    let function = called_function(db, &call).or_else(|| resolve_callee(db, &callee))?;
+    let signature = function.checked_signature(db).ok()?;
+    let symbols = signature.symbols(db);
+    let input_output = &signature.input_output(db).bound_value.bound_value;
+
+    // Methods are called as `owner.method(...)`, so `self` is not among the arguments.
+    let self_ident = Identifier::self_ident(db);
+    let skip_self = symbols
+        .input_variables
+        .first()
+        .is_some_and(|input| input.name(db) == Some(self_ident)) as usize;
+
+    let parameters = symbols
+        .input_variables
+        .iter()
+        .zip(&input_output.input_tys)
+        .skip(skip_self)
+        .map(|(input, ty)| format!("{input}: {ty}"))
+        .collect::<Vec<_>>();
+
+    // Constructors are shown as they are called, e.g., `Point(x: u32, y: u32)`.
+    let label = match function.source(db) {
+        SymFunctionSource::Constructor(aggregate, _) => {
+            format!("{}({})", aggregate.name(db), parameters.join(", "))
+        }
+        _ if is_unit(db, input_output.output_ty) => {
+            format!("fn {}({})", function.name(db), parameters.join(", "))
+        }
+        _ => format!(
+            "fn {}({}) -> {}",
+            function.name(db),
+            parameters.join(", "),
+            input_output.output_ty
+        ),
+    };
+
+    // A named argument selects the parameter with that name.
+    let index = argument_index(db, text, &args, offset);
+    let active_parameter = match args.values.get(index).and_then(|arg| arg.name) {
+        Some(name) => symbols
+            .input_variables
+            .iter()
+            .skip(skip_self)
+            .position(|input| input.name(db) == Some(name.id)),
+        None => Some(index),
+    }
+    .filter(|&parameter| parameter < parameters.len());
+
+    Some(SignatureHelp {
+        label,
+        parameters,
+        active_parameter,
+    })
+}
+
+/// Index of the argument at `offset`: the number of arguments that precede it,
+/// each followed by a `,` before `offset`.
+fn argument_index<'db>(
+    db: &'db dyn crate::Db,
+    text: &str,
+    args: &SpanVec<'db, AstCallArg<'db>>,
+    offset: AbsoluteOffset,
+) -> usize {
+    let offset = offset.as_usize();
+    args.values
+        .iter()
+        .take_while(|arg| {
+            let arg_end = arg.value.span.absolute_span(db).end.as_usize();
+            arg_end < offset && text[arg_end..offset].trim_start().starts_with(',')
+        })
+        .count()
+}
+
+/// The function invoked by `call`, as determined by the type checker.
+fn called_function<'db>(db: &'db dyn crate::Db, call: &AstExpr<'db>) -> Option<SymFunction<'db>> {
+    let call_span = call.span.absolute_span(db);
+    visit_exprs(db, call_span, &mut |expr| match expr.kind(db) {
+        SymExprKind::Call { function, .. }
+            if expr.source_span(db).absolute_span(db) == call_span =>
+        {
+            ControlFlow::Break(*function)
+        }
+        _ => ControlFlow::Continue(()),
+    })
+}
+
+/// Resolves the function named by `callee` without relying on the call
+/// having been type checked: a function or class in scope, or a method
+/// of the owner's type.
+fn resolve_callee<'db>(db: &'db dyn crate::Db, callee: &AstExpr<'db>) -> Option<SymFunction<'db>> {
+    let callee_span = callee.span.absolute_span(db);
+    match &*callee.kind {
+        AstExprKind::Id(id) => {
+            let function = find_func(db, callee_span)?;
+            let (_, sym) = function
+                .scope(db)
+                .visible_symbols(db)
+                .into_iter()
+                .find(|&(name, _)| name == id.id)?;
+            match sym {
+                NameResolutionSym::SymFunction(function) => Some(function),
+                NameResolutionSym::SymAggregate(aggregate) => constructor(db, aggregate),
+                NameResolutionSym::SymModule(_)
+                | NameResolutionSym::SymPrimitive(_)
+                | NameResolutionSym::SymVariable(_) => None,
+            }
+        }
+        AstExprKind::DotId(owner, id) => {
+            let owner = owner.span.absolute_span(db);
+            let ty = owner_ty(db, point_span(owner.source_file, owner.end))?;
+            ty.members(db).iter().find_map(|&member| match member {
+                SymClassMember::SymFunction(method) if method.name(db) == id.id => Some(method),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn constructor<'db>(
+    db: &'db dyn crate::Db,
+    aggregate: SymAggregate<'db>,
+) -> Option<SymFunction<'db>> {
+    aggregate
+        .methods(db)
+        .find(|method| matches!(method.source(db), SymFunctionSource::Constructor(..)))
+}
+
+fn is_unit<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> bool {
+    matches!(
+        ty.kind(db),
+        SymTyKind::Named(SymTyName::Tuple { arity: 0 }, _)
+    )
+}
@@ -4,7 +4,9 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
 
-/// Transforms an async fn to return a `Box<dyn Future<Output = T>>`.
+/// Transforms an async fn to box its future, so that it can call itself recursively.
+/// The boxed future is polled with `dada_util::ensure_sufficient_stack`,
+/// so deep recursion grows the stack rather than overflowing it.
 ///
 /// Originally based on the [`async_recursion`](https://crates.io/crates/async-recursion) crate
 /// authored by Robert Usher and licensed under MIT/APACHE-2.0.
@@ -13,7 +15,9 @@ pub fn boxed_async_fn(args: TokenStream, input: TokenStream) -> TokenStream {
     let _args = parse_macro_input!(args as syn::parse::Nothing);
 
     let block = item.block;
-    item.block = syn::parse2(quote!({Box::pin(async move #block).await})).unwrap();
+    item.block =
+        syn::parse2(quote!({dada_util::stack::StackGuarded::new(async move #block).await}))
+            .unwrap();
 
     TokenStream::from(quote!(#item))
 }
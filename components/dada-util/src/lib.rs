@@ -32,9 +32,11 @@ pub mod arena;
 
 pub mod log;
 
+pub mod stack;
+pub use stack::ensure_sufficient_stack;
+
 pub async fn indirect<T>(op: impl AsyncFnOnce() -> T) -> T {
-    let boxed_future = futures::future::FutureExt::boxed_local(op());
-    boxed_future.await
+    stack::StackGuarded::new(op()).await
 }
 
 pub mod vecext;
@@ -0,0 +1,46 @@
+//! Guards against stack overflow in deeply recursive code.
+//!
+//! The parser and the type checker recurse once per level of nesting in the source,
+//! so a program with thousands of nested parentheses would otherwise overflow the stack.
+//! Rather than impose a nesting limit, recursive code calls [`ensure_sufficient_stack`],
+//! which switches to a freshly allocated stack segment when the current one runs low.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// If less than this much stack remains, a new stack segment is allocated.
+const RED_ZONE: usize = 100 * 1024;
+
+/// Size of each newly allocated stack segment.
+const STACK_PER_RECURSION: usize = 1024 * 1024;
+
+/// Runs `f`, first growing the stack if it is close to running out.
+/// Call this at the point where deeply nested input leads to recursion.
+#[inline]
+pub fn ensure_sufficient_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_PER_RECURSION, f)
+}
+
+/// A boxed future that is polled with [`ensure_sufficient_stack`].
+///
+/// Recursive async fns poll the future for a nested call from within their own `poll`,
+/// so the stack grows with the nesting depth just as it does for ordinary recursion.
+/// [`boxed_async_fn`](`crate::boxed_async_fn`) wraps each call in one of these.
+pub struct StackGuarded<F: Future>(Pin<Box<F>>);
+
+impl<F: Future> StackGuarded<F> {
+    pub fn new(future: F) -> Self {
+        Self(Box::pin(future))
+    }
+}
+
+impl<F: Future> Future for StackGuarded<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = &mut self.get_mut().0;
+        ensure_sufficient_stack(|| future.as_mut().poll(cx))
+    }
+}